@@ -133,9 +133,9 @@ def generate_wood():
     print("wood.png")
 
 def generate_leaves():
-    """Genera textura de hojas"""
+    """Genera textura de hojas (con huecos transparentes entre las hojas)"""
     size = 16
-    img = Image.new('RGB', (size, size))
+    img = Image.new('RGBA', (size, size))
     pixels = img.load()
     
     base_color = (40, 120, 40)
@@ -150,7 +150,9 @@ def generate_leaves():
             elif random.random() < 0.1:
                 color = tuple(min(255, c + 30) for c in color)
             
-            pixels[x, y] = color
+            # Huecos: el ray tracer deja pasar los rayos donde el alfa es 0
+            alpha = 0 if (x * 7 + y * 13 + x * y) % 10 < 3 else 255
+            pixels[x, y] = color + (alpha,)
     
     img.save("assets/textures/leaves.png")
     print("leaves.png")
@@ -433,6 +435,34 @@ def generate_obsidian():
     img.save("assets/textures/obsidian.png")
     print("obsidian.png")
 
+def generate_obsidian_roughness():
+    """Genera el mapa de rugosidad de la obsidiana: vetas y reflejos lisos como vidrio"""
+    source = Image.open("assets/textures/obsidian.png").convert('RGB')
+    img = Image.new('L', source.size)
+    pixels = img.load()
+
+    for y in range(source.height):
+        for x in range(source.width):
+            brightness = sum(source.getpixel((x, y))) // 3
+            pixels[x, y] = max(20, min(200, 170 - 4 * brightness))
+
+    img.save("assets/textures/obsidian_roughness.png")
+    print("obsidian_roughness.png")
+
+def generate_diamond_metal():
+    """Genera el mapa de metal del diamante: las facetas brillantes reflejan como espejos"""
+    source = Image.open("assets/textures/diamond.png").convert('RGB')
+    img = Image.new('L', source.size)
+    pixels = img.load()
+
+    for y in range(source.height):
+        for x in range(source.width):
+            brightness = sum(source.getpixel((x, y))) // 3
+            pixels[x, y] = 255 if brightness >= 235 else 0
+
+    img.save("assets/textures/diamond_metal.png")
+    print("diamond_metal.png")
+
 def generate_ice():
     """Genera textura de hielo"""
     size = 16
@@ -478,6 +508,24 @@ def generate_brick():
     img.save("assets/textures/brick.png")
     print("brick.png (bonus)")
 
+def generate_glass():
+    """Genera textura de vidrio: marco y reflejos opacos, el centro transparente"""
+    size = 16
+    img = Image.new('RGBA', (size, size))
+    pixels = img.load()
+
+    for y in range(size):
+        for x in range(size):
+            if x in (0, size - 1) or y in (0, size - 1):
+                pixels[x, y] = (200, 215, 225, 255)
+            elif x + y in (6, 9) and 2 <= x <= 5:
+                pixels[x, y] = (235, 245, 250, 255)
+            else:
+                pixels[x, y] = (220, 235, 245, 0)
+
+    img.save("assets/textures/glass.png")
+    print("glass.png")
+
 def generate_sand():
     """Genera textura de arena"""
     size = 16
@@ -536,6 +584,12 @@ def main():
     generate_emerald()
     generate_obsidian()
     generate_ice()
+    generate_glass()
+
+    # Mapas de rugosidad y metal (se derivan de las texturas de arriba)
+    print("\nMapas de rugosidad y metal:")
+    generate_obsidian_roughness()
+    generate_diamond_metal()
     
     # Texturas bonus
     print("\nTexturas bonus:")
@@ -549,6 +603,7 @@ def main():
     print("\nTips:")
     print("   • Edita los PNG con cualquier editor de imágenes")
     print("   • Las texturas animadas tienen sufijos _0, _1, etc.")
+    print("   • _normal, _roughness y _metal son mapas de la textura del mismo nombre")
     print("   • El programa Rust las cargará automáticamente")
     print("=" * 60)
 
@@ -0,0 +1,185 @@
+use raylib::prelude::{Color, Vector3};
+
+use crate::material::{limit_radiance, vector3_to_color};
+
+/// Opciones para eliminar "fireflies" (pixels blancos aislados) al acumular muestras
+#[derive(Clone, Copy)]
+pub struct FireflyFilter {
+    /// Radiancia máxima por canal de cada muestra
+    pub sample_clamp: f32,
+    /// Recorta las muestras cuya luminancia se aleja demasiado de la media del pixel
+    pub reject_outliers: bool,
+    /// Desviaciones estándar permitidas antes de considerar una muestra atípica
+    pub outlier_sigma: f32,
+    /// Muestras necesarias antes de empezar a rechazar atípicas
+    pub min_samples: u32,
+}
+
+impl Default for FireflyFilter {
+    fn default() -> Self {
+        FireflyFilter {
+            sample_clamp: 4.0,
+            reject_outliers: true,
+            outlier_sigma: 3.0,
+            min_samples: 4,
+        }
+    }
+}
+
+/// Muestreo adaptativo: deja de trazar los pixels cuya media ya convergió
+/// para que las pasadas siguientes solo gasten rayos en las zonas ruidosas
+/// (bordes de sombras suaves, reflejos)
+#[derive(Clone, Copy)]
+pub struct AdaptiveSampling {
+    pub enabled: bool,
+    /// Muestras mínimas de cada pixel antes de decidir si convergió
+    pub min_samples: u32,
+    /// Error estándar de la luminancia media, relativo a la media, para dar
+    /// el pixel por convergido
+    pub max_relative_error: f32,
+    /// Luminancia mínima con que se compara el error (evita exigir de más en zonas oscuras)
+    pub dark_luminance: f32,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling {
+            enabled: true,
+            min_samples: 8,
+            max_relative_error: 0.02,
+            dark_luminance: 0.05,
+        }
+    }
+}
+
+#[inline]
+fn luminance(color: Vector3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+/// Suma de muestras por pixel para el render progresivo.
+/// Guarda la media y varianza de la luminancia de cada pixel (Welford)
+/// para detectar muestras atípicas y pixels ya convergidos.
+pub struct AccumulationBuffer {
+    width: usize,
+    height: usize,
+    sum: Vec<Vector3>,
+    luminance_mean: Vec<f32>,
+    luminance_m2: Vec<f32>,
+    /// Muestras de cada pixel (con muestreo adaptativo no todos reciben las mismas)
+    pixel_samples: Vec<u32>,
+    /// Pasadas acumuladas
+    sample_count: u32,
+    pub filter: FireflyFilter,
+    pub adaptive: AdaptiveSampling,
+}
+
+impl AccumulationBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        AccumulationBuffer {
+            width,
+            height,
+            sum: vec![Vector3::zero(); width * height],
+            luminance_mean: vec![0.0; width * height],
+            luminance_m2: vec![0.0; width * height],
+            pixel_samples: vec![0; width * height],
+            sample_count: 0,
+            filter: FireflyFilter::default(),
+            adaptive: AdaptiveSampling::default(),
+        }
+    }
+
+    /// Cambia el tamaño (descartando lo acumulado) sin perder las opciones
+    pub fn resize(&mut self, width: usize, height: usize) {
+        *self = AccumulationBuffer {
+            filter: self.filter,
+            adaptive: self.adaptive,
+            ..AccumulationBuffer::new(width, height)
+        };
+    }
+
+    /// Descarta lo acumulado (la cámara, la escena o la hora cambiaron)
+    pub fn reset(&mut self) {
+        self.sum.fill(Vector3::zero());
+        self.luminance_mean.fill(0.0);
+        self.luminance_m2.fill(0.0);
+        self.pixel_samples.fill(0);
+        self.sample_count = 0;
+    }
+
+    /// Pasadas acumuladas (las que recibió el pixel más muestreado)
+    pub fn samples(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Si el error estándar de la luminancia media del pixel ya es lo
+    /// bastante chico para dejar de muestrearlo
+    fn converged(&self, i: usize) -> bool {
+        let n = self.pixel_samples[i];
+        if n < self.adaptive.min_samples.max(2) {
+            return false;
+        }
+        let variance = self.luminance_m2[i] / (n - 1) as f32;
+        let error = (variance / n as f32).sqrt();
+        error <= self.adaptive.max_relative_error * self.luminance_mean[i].max(self.adaptive.dark_luminance)
+    }
+
+    /// Índices (en el orden del framebuffer) de los pixels que todavía
+    /// necesitan muestras; sin muestreo adaptativo son todos
+    pub fn pending_pixels(&self) -> Vec<usize> {
+        let pixels = 0..self.width * self.height;
+        if !self.adaptive.enabled {
+            return pixels.collect();
+        }
+        pixels.filter(|&i| !self.converged(i)).collect()
+    }
+
+    /// Agrega una muestra por pixel (en el mismo orden que el framebuffer)
+    pub fn add_frame(&mut self, frame: &[Vector3]) {
+        debug_assert_eq!(frame.len(), self.width * self.height);
+        for (i, sample) in frame.iter().enumerate() {
+            self.add_sample(i, *sample);
+        }
+        self.sample_count += 1;
+    }
+
+    /// Agrega una pasada que solo trazó los pixels `pixels` (`samples` en el mismo orden)
+    pub fn add_samples(&mut self, pixels: &[usize], samples: &[Vector3]) {
+        debug_assert_eq!(pixels.len(), samples.len());
+        for (&i, sample) in pixels.iter().zip(samples) {
+            self.add_sample(i, *sample);
+        }
+        self.sample_count += 1;
+    }
+
+    fn add_sample(&mut self, i: usize, sample: Vector3) {
+        let n = self.pixel_samples[i];
+        let mut sample = limit_radiance(sample, self.filter.sample_clamp);
+        let mut lum = luminance(sample);
+
+        if self.filter.reject_outliers && n >= self.filter.min_samples {
+            let variance = self.luminance_m2[i] / (n - 1) as f32;
+            let max_lum = self.luminance_mean[i] + self.filter.outlier_sigma * variance.sqrt() + 0.05;
+            if lum > max_lum {
+                sample *= max_lum / lum;
+                lum = max_lum;
+            }
+        }
+
+        self.sum[i] += sample;
+
+        let delta = lum - self.luminance_mean[i];
+        self.luminance_mean[i] += delta / (n + 1) as f32;
+        self.luminance_m2[i] += delta * (lum - self.luminance_mean[i]);
+        self.pixel_samples[i] += 1;
+    }
+
+    /// Promedio de las muestras acumuladas, listo para mostrar
+    pub fn resolve(&self) -> Vec<Color> {
+        self.sum
+            .iter()
+            .zip(&self.pixel_samples)
+            .map(|(c, &n)| vector3_to_color(*c / n.max(1) as f32))
+            .collect()
+    }
+}
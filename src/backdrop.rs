@@ -0,0 +1,127 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+
+use crate::camera::Camera;
+use crate::material::srgb_byte_to_linear;
+
+/// Cómo se proyecta la imagen de fondo sobre las direcciones de los rayos
+#[derive(Clone, Copy, PartialEq)]
+pub enum BackdropProjection {
+    /// La imagen llena la pantalla, proyectada desde la cámara (para fotografías)
+    Camera,
+    /// Panorama equirectangular alrededor de la escena
+    Spherical,
+}
+
+impl BackdropProjection {
+    pub fn next(self) -> Self {
+        match self {
+            BackdropProjection::Camera => BackdropProjection::Spherical,
+            BackdropProjection::Spherical => BackdropProjection::Camera,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackdropProjection::Camera => "Cámara",
+            BackdropProjection::Spherical => "Esférica",
+        }
+    }
+}
+
+/// Imagen que se muestra donde los rayos no golpean geometría
+pub struct Backdrop {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vector3>,
+    pub projection: BackdropProjection,
+}
+
+impl Backdrop {
+    pub fn load(path: &str, projection: BackdropProjection) -> Result<Self, String> {
+        let img = image::open(path)
+            .map_err(|e| format!("No se pudo abrir fondo {}: {}", path, e))?
+            .to_rgb8();
+
+        if img.width() == 0 || img.height() == 0 {
+            return Err(format!("El fondo {} está vacío", path));
+        }
+
+        // La foto está en sRGB; se guarda lineal, como la radiancia de la escena
+        let pixels = img
+            .pixels()
+            .map(|p| Vector3::new(srgb_byte_to_linear(p[0]), srgb_byte_to_linear(p[1]), srgb_byte_to_linear(p[2])))
+            .collect();
+
+        Ok(Backdrop {
+            width: img.width(),
+            height: img.height(),
+            pixels,
+            projection,
+        })
+    }
+
+    #[inline]
+    fn texel(&self, x: u32, y: u32) -> Vector3 {
+        self.pixels[(y.min(self.height - 1) * self.width + x.min(self.width - 1)) as usize]
+    }
+
+    /// Muestreo bilineal con coordenadas (u, v) en [0, 1]; los bordes se extienden
+    fn sample_uv(&self, u: f32, v: f32) -> Vector3 {
+        let x = (u.clamp(0.0, 1.0) * self.width as f32 - 0.5).max(0.0);
+        let y = (v.clamp(0.0, 1.0) * self.height as f32 - 0.5).max(0.0);
+        let (x0, y0) = (x as u32, y as u32);
+        let (fx, fy) = (x.fract(), y.fract());
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Fondo listo para un frame: guarda la orientación de la cámara para la
+/// proyección desde cámara (también se usa en reflejos y refracciones)
+pub struct BackdropView<'a> {
+    backdrop: &'a Backdrop,
+    right: Vector3,
+    up: Vector3,
+    forward: Vector3,
+    scale_x: f32,
+    scale_y: f32,
+}
+
+impl<'a> BackdropView<'a> {
+    /// `tan_half_fov` y `aspect_ratio` deben coincidir con los rayos primarios
+    pub fn new(backdrop: &'a Backdrop, camera: &Camera, aspect_ratio: f32, tan_half_fov: f32) -> Self {
+        BackdropView {
+            backdrop,
+            right: camera.right,
+            up: camera.up,
+            forward: camera.forward,
+            scale_x: aspect_ratio * tan_half_fov,
+            scale_y: tan_half_fov,
+        }
+    }
+
+    /// Color del fondo en la dirección `dir`, o `None` si la proyección no la
+    /// cubre (detrás de la cámara), en cuyo caso se usa el cielo
+    pub fn sample(&self, dir: Vector3) -> Option<Vector3> {
+        let d = dir.normalized();
+        match self.backdrop.projection {
+            BackdropProjection::Camera => {
+                let z = d.dot(self.forward);
+                if z <= 1e-4 {
+                    return None;
+                }
+                let screen_x = d.dot(self.right) / z / self.scale_x;
+                let screen_y = d.dot(self.up) / z / self.scale_y;
+                Some(self.backdrop.sample_uv((screen_x + 1.0) * 0.5, (1.0 - screen_y) * 0.5))
+            }
+            BackdropProjection::Spherical => {
+                let u = 0.5 + d.z.atan2(d.x) / (2.0 * PI);
+                let v = 0.5 - d.y.clamp(-1.0, 1.0).asin() / PI;
+                Some(self.backdrop.sample_uv(u, v))
+            }
+        }
+    }
+}
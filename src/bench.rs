@@ -0,0 +1,245 @@
+use rayon::prelude::*;
+use std::time::Instant;
+
+use crate::ray_intersect::Intersect;
+use crate::renderer::{Ray, RenderSettings, Renderer, Scene};
+use crate::scenes::{SceneInfo, load_scene};
+use crate::screenshot::escape_json;
+use crate::skybox::Environment;
+
+/// Escenas del menú, en el orden de las teclas
+const BENCH_SCENES: [i32; 12] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+/// Mediodía: todas las corridas ven la misma luz
+const BENCH_TIME_OF_DAY: f32 = 0.5;
+
+/// Opciones de `--bench`
+pub struct BenchOptions {
+    /// Veces que se renderiza cada escena
+    pub runs: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Archivo JSON con los resultados
+    pub output: String,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            runs: 5,
+            width: 400,
+            height: 300,
+            output: "renders/bench.json".to_string(),
+        }
+    }
+}
+
+/// Valor de `--nombre valor` o `--nombre=valor`
+pub fn arg_value(args: &[String], name: &str) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == name {
+            return iter.next().cloned();
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+/// Lee `--bench` con sus opciones `--bench-runs N`, `--bench-size ANCHOxALTO`
+/// y `--bench-out archivo`. Devuelve `None` si no se pidió el benchmark.
+pub fn bench_args(args: &[String]) -> Result<Option<BenchOptions>, String> {
+    if !args.iter().any(|arg| arg == "--bench") {
+        return Ok(None);
+    }
+
+    let mut options = BenchOptions::default();
+    if let Some(runs) = arg_value(args, "--bench-runs") {
+        options.runs = runs
+            .parse()
+            .ok()
+            .filter(|&runs| runs > 0)
+            .ok_or_else(|| format!("Cantidad de corridas inválida: {}", runs))?;
+    }
+    if let Some(size) = arg_value(args, "--bench-size") {
+        let parsed = size
+            .split_once('x')
+            .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+            .filter(|&(width, height)| width > 0 && height > 0);
+        (options.width, options.height) = parsed.ok_or_else(|| format!("Tamaño inválido: {} (se espera ANCHOxALTO)", size))?;
+    }
+    if let Some(output) = arg_value(args, "--bench-out") {
+        options.output = output;
+    }
+    Ok(Some(options))
+}
+
+/// Tiempos (en segundos) de una etapa en cada corrida
+struct StageTimes {
+    times: Vec<f32>,
+    /// Rayos (o pixels) que procesa la etapa en cada corrida
+    work: usize,
+}
+
+impl StageTimes {
+    fn new() -> Self {
+        StageTimes { times: Vec::new(), work: 0 }
+    }
+
+    fn mean(&self) -> f32 {
+        self.times.iter().sum::<f32>() / self.times.len().max(1) as f32
+    }
+
+    fn to_json(&self, work_name: &str) -> String {
+        let min = self.times.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = self.times.iter().copied().fold(0.0, f32::max);
+        let mean = self.mean();
+        let per_second = if mean > 0.0 { self.work as f32 / mean } else { 0.0 };
+        format!(
+            "{{ \"mean\": {:.6}, \"min\": {:.6}, \"max\": {:.6}, \"{}\": {}, \"{}_per_second\": {:.1} }}",
+            mean, min, max, work_name, self.work, work_name, per_second,
+        )
+    }
+}
+
+struct SceneBench {
+    scene_num: i32,
+    scene_name: String,
+    object_count: usize,
+    light_count: usize,
+    load_time: f32,
+    bvh_build_time: f32,
+    /// Intersección de los rayos primarios
+    primary: StageTimes,
+    /// Rayos de sombra desde cada impacto primario hacia las luces que lo alcanzan
+    shadow: StageTimes,
+    /// Frame completo (intersección, sombras, reflejos y refracciones)
+    render: StageTimes,
+}
+
+impl SceneBench {
+    fn to_json(&self) -> String {
+        format!(
+            "    {{\n      \"scene\": {},\n      \"scene_name\": \"{}\",\n      \"objects\": {},\n      \"lights\": {},\n      \"load_time\": {:.6},\n      \"bvh_build_time\": {:.6},\n      \"stages\": {{\n        \"primary\": {},\n        \"shadow\": {},\n        \"render\": {}\n      }}\n    }}",
+            self.scene_num,
+            escape_json(&self.scene_name),
+            self.object_count,
+            self.light_count,
+            self.load_time,
+            self.bvh_build_time,
+            self.primary.to_json("rays"),
+            self.shadow.to_json("rays"),
+            self.render.to_json("pixels"),
+        )
+    }
+}
+
+fn bench_scene(scene_num: i32, options: &BenchOptions) -> SceneBench {
+    let start = Instant::now();
+    let scene = load_scene(scene_num);
+    // La carga incluye el BVH, que se construye junto con la escena
+    let bvh_build_time = scene.bvh_build_time();
+    let load_time = start.elapsed().as_secs_f32() - bvh_build_time;
+    let (objects, lights, bvh) = (&scene.objects, &scene.lights, &scene.bvh);
+
+    let info = SceneInfo::get(scene_num);
+    let camera = info.camera();
+    let environment = Environment::new(BENCH_TIME_OF_DAY, 0.0);
+    let renderer = Renderer::new(Scene { bvh, objects, grid: None, chunks: None, lights, environment: &environment });
+    let settings = RenderSettings::new(options.width, options.height);
+
+    let (width, height) = (options.width as usize, options.height as usize);
+    let rays: Vec<Ray> = (0..width * height)
+        .map(|i| Ray::primary(&camera, (i % width) as f32, (i / width) as f32, width, height))
+        .collect();
+
+    let mut primary = StageTimes::new();
+    let mut shadow = StageTimes::new();
+    let mut render = StageTimes::new();
+    for _ in 0..options.runs {
+        let start = Instant::now();
+        let hits: Vec<Intersect> = rays.par_iter().map(|ray| renderer.intersect(ray)).collect();
+        primary.times.push(start.elapsed().as_secs_f32());
+        primary.work = rays.len();
+
+        // Mismos descartes que el sombreado: solo las luces que iluminan la cara
+        let start = Instant::now();
+        shadow.work = hits
+            .par_iter()
+            .filter(|hit| hit.is_intersecting)
+            .map(|hit| {
+                lights
+                    .iter()
+                    .filter(|light| {
+                        let light_dir = (light.position - hit.point).normalized();
+                        hit.normal.dot(light_dir) > 0.0 && light.cone_factor(&hit.point) > 0.0
+                    })
+                    .map(|light| std::hint::black_box(renderer.shadow_transmission(hit, light.position)))
+                    .fold(0, |count, _| count + 1)
+            })
+            .sum();
+        shadow.times.push(start.elapsed().as_secs_f32());
+
+        let start = Instant::now();
+        renderer.render_radiance(&camera, &settings);
+        render.times.push(start.elapsed().as_secs_f32());
+        render.work = width * height;
+    }
+
+    SceneBench {
+        scene_num,
+        scene_name: info.name.to_string(),
+        object_count: objects.len(),
+        light_count: lights.len(),
+        load_time,
+        bvh_build_time,
+        primary,
+        shadow,
+        render,
+    }
+}
+
+/// Renderiza cada escena `runs` veces con la cámara inicial y a hora fija,
+/// y escribe los tiempos de cada etapa en `output` como JSON
+pub fn run(options: &BenchOptions) -> Result<(), String> {
+    println!(
+        "Benchmark: {} corridas por escena a {}x{} con {} hilos",
+        options.runs,
+        options.width,
+        options.height,
+        rayon::current_num_threads()
+    );
+
+    let mut scenes = Vec::new();
+    for scene_num in BENCH_SCENES {
+        let result = bench_scene(scene_num, options);
+        println!(
+            "Escena {:2} ({}): BVH {:.3}s | primarios {:.3}s | sombras {:.3}s | frame {:.3}s",
+            scene_num,
+            result.scene_name,
+            result.bvh_build_time,
+            result.primary.mean(),
+            result.shadow.mean(),
+            result.render.mean()
+        );
+        scenes.push(result);
+    }
+
+    let scenes: Vec<String> = scenes.iter().map(|scene| scene.to_json()).collect();
+    let text = format!(
+        "{{\n  \"version\": \"{}\",\n  \"threads\": {},\n  \"width\": {},\n  \"height\": {},\n  \"runs\": {},\n  \"time_of_day\": {},\n  \"scenes\": [\n{}\n  ]\n}}\n",
+        env!("CARGO_PKG_VERSION"),
+        rayon::current_num_threads(),
+        options.width,
+        options.height,
+        options.runs,
+        BENCH_TIME_OF_DAY,
+        scenes.join(",\n"),
+    );
+
+    if let Some(dir) = std::path::Path::new(&options.output).parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Error creando {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&options.output, text).map_err(|e| format!("Error guardando {}: {}", options.output, e))
+}
@@ -0,0 +1,124 @@
+use crate::colormap::PLAINS_CLIMATE;
+use crate::noise::Perlin;
+use crate::trees::TreeSpecies;
+
+/// Tamaño aproximado de una región de un mismo bioma, en bloques
+const BIOME_SIZE: f32 = 48.0;
+/// Cuánto cambian la temperatura y la humedad de los colormaps por unidad de ruido
+const CLIMATE_SPREAD: f32 = 1.2;
+
+/// Región del terreno generado con sus propios materiales y vegetación
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Snowy,
+    Mushroom,
+    NetherWastes,
+}
+
+/// Qué crece en la superficie de un bioma
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Vegetation {
+    /// Árbol de tronco y hojas cúbicas (`add_tree_species`)
+    Tree(TreeSpecies),
+    /// Pino de conos (`add_pine`)
+    Pine,
+    Cactus,
+    GiantMushroom,
+    /// Racimo de glowstone que brota del suelo
+    Glowstone,
+}
+
+impl Biome {
+    /// Material del bloque de la superficie
+    pub fn surface(&self) -> &'static str {
+        match self {
+            Biome::Plains => "grass_top",
+            Biome::Desert => "sand",
+            Biome::Snowy => "snow",
+            Biome::Mushroom => "mycelium",
+            Biome::NetherWastes => "netherrack",
+        }
+    }
+
+    /// Material de las capas justo debajo de la superficie
+    pub fn subsurface(&self) -> &'static str {
+        match self {
+            Biome::Plains | Biome::Snowy | Biome::Mushroom => "dirt",
+            Biome::Desert => "sand",
+            Biome::NetherWastes => "soul_sand",
+        }
+    }
+
+    /// Qué crece en una columna; `roll` (de 0 a 1, al azar por columna) elige
+    /// entre las especies del bioma
+    pub fn vegetation(&self, roll: f32) -> Vegetation {
+        match self {
+            Biome::Plains if roll < 0.3 => Vegetation::Tree(TreeSpecies::Birch),
+            Biome::Plains => Vegetation::Tree(TreeSpecies::Oak),
+            Biome::Desert if roll < 0.15 => Vegetation::Tree(TreeSpecies::Dead),
+            Biome::Desert => Vegetation::Cactus,
+            Biome::Snowy if roll < 0.5 => Vegetation::Tree(TreeSpecies::Spruce),
+            Biome::Snowy => Vegetation::Pine,
+            Biome::Mushroom => Vegetation::GiantMushroom,
+            Biome::NetherWastes => Vegetation::Glowstone,
+        }
+    }
+
+    /// Multiplica la densidad de vegetación pedida a la escena
+    pub fn vegetation_density(&self) -> f32 {
+        match self {
+            Biome::Plains => 1.0,
+            Biome::Desert => 0.3,
+            Biome::Snowy => 0.8,
+            Biome::Mushroom => 0.5,
+            Biome::NetherWastes => 0.2,
+        }
+    }
+}
+
+/// Reparte los biomas con dos capas de ruido independientes, temperatura y
+/// humedad: el frío da nieve, el calor seco desierto y el extremo el Nether
+pub struct BiomeMap {
+    temperature: Perlin,
+    humidity: Perlin,
+}
+
+impl BiomeMap {
+    pub fn new(seed: u32) -> Self {
+        BiomeMap {
+            temperature: Perlin::new(seed),
+            humidity: Perlin::new(seed.wrapping_add(0x5bd1_e995)),
+        }
+    }
+
+    /// Temperatura y humedad (de 0 a 1) de la columna para los colormaps; el
+    /// centro de la llanura cae en `PLAINS_CLIMATE` y el desierto en el borde cálido
+    pub fn climate_at(&self, x: i32, z: i32) -> (f32, f32) {
+        let (fx, fz) = (x as f32 / BIOME_SIZE, z as f32 / BIOME_SIZE);
+        let (temperature, humidity) = PLAINS_CLIMATE;
+        (
+            (temperature + self.temperature.fbm2(fx, fz, 2) * CLIMATE_SPREAD).clamp(0.0, 1.0),
+            (humidity + self.humidity.fbm2(fx, fz, 2) * CLIMATE_SPREAD).clamp(0.0, 1.0),
+        )
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let (fx, fz) = (x as f32 / BIOME_SIZE, z as f32 / BIOME_SIZE);
+        let temperature = self.temperature.fbm2(fx, fz, 2);
+        let humidity = self.humidity.fbm2(fx, fz, 2);
+
+        if temperature > 0.35 {
+            Biome::NetherWastes
+        } else if temperature > 0.12 && humidity < 0.0 {
+            Biome::Desert
+        } else if temperature < -0.18 {
+            Biome::Snowy
+        } else if humidity > 0.25 {
+            Biome::Mushroom
+        } else {
+            Biome::Plains
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use raylib::prelude::{Color, Vector2, Vector3};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::built_scene::{self, BlockEntry, BuiltScene, HiddenBlocks, Hit, SceneData, removed_at};
+use crate::camera::Camera;
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::ray_intersect::RayIntersect;
+use crate::ray_tree::project_segment;
+use crate::renderer::Ray;
+use crate::scene_builder::{self, FACE_NEIGHBORS};
+
+type SceneObject = Arc<dyn RayIntersect + Send + Sync>;
+/// Celda del índice de bloques de la escena, con el bloque de antes y el de después
+type CellChange = ((i32, i32, i32), Option<BlockEntry>, Option<BlockEntry>);
+
+/// Holgura al decidir si un objeto cabe en una celda
+const CELL_MARGIN: f32 = 0.01;
+/// Ediciones que se pueden deshacer; las más viejas se olvidan
+const HISTORY_LIMIT: usize = 200;
+
+/// Edición ya aplicada, con lo que había y lo que quedó en cada lugar de la
+/// escena: deshacerla y rehacerla solo cambia objetos en esos lugares
+struct Edit {
+    description: String,
+    /// Índice en la escena, objeto anterior y objeto nuevo
+    slots: Vec<(usize, SceneObject, SceneObject)>,
+    cells: Vec<CellChange>,
+    /// Bloques interiores que la edición descubrió, con su celda, su índice y su material
+    uncovered: Vec<((i32, i32, i32), usize, String)>,
+}
+
+impl Edit {
+    fn indices(&self) -> Vec<usize> {
+        self.slots.iter().map(|(index, ..)| *index).collect()
+    }
+
+    /// Pone en la escena los objetos y los bloques de después de la edición
+    fn apply(&self, scene: &mut BuiltScene) {
+        for (index, _, after) in &self.slots {
+            scene.objects[*index] = after.clone();
+        }
+        for (cell, _, after) in &self.cells {
+            scene.set_block(*cell, after.clone());
+        }
+    }
+}
+
+/// Bloque bajo el mouse y la cara por la que lo toca el rayo
+#[derive(Clone, Copy)]
+pub struct BlockPick {
+    pub block: (i32, i32, i32),
+    /// Normal de la cara apuntada, en pasos de la grilla
+    pub face: (i32, i32, i32),
+}
+
+impl BlockPick {
+    fn new(hit: &Hit) -> Self {
+        let block = hit.nearest_cell();
+        let normal = hit.intersect.normal;
+        let face = if normal.x.abs() >= normal.y.abs() && normal.x.abs() >= normal.z.abs() {
+            (normal.x.signum() as i32, 0, 0)
+        } else if normal.y.abs() >= normal.z.abs() {
+            (0, normal.y.signum() as i32, 0)
+        } else {
+            (0, 0, normal.z.signum() as i32)
+        };
+        BlockPick { block, face }
+    }
+
+    /// Celda vecina por la cara apuntada, donde se coloca un bloque nuevo
+    pub fn adjacent(&self) -> (i32, i32, i32) {
+        let ((x, y, z), (dx, dy, dz)) = (self.block, self.face);
+        (x + dx, y + dy, z + dz)
+    }
+
+    /// Contorno de la cara apuntada en pixeles de una imagen de `width`×`height`
+    pub fn outline(&self, camera: &Camera, width: u32, height: u32) -> Vec<(Vector2, Vector2, Color)> {
+        let ((x, y, z), (dx, dy, dz)) = (self.block, self.face);
+        let normal = Vector3::new(dx as f32, dy as f32, dz as f32);
+        let center = Vector3::new(x as f32, y as f32, z as f32) + normal * 0.5;
+        // Dos ejes sobre la cara, perpendiculares a la normal
+        let (u, v) = match self.face {
+            (_, 0, 0) => (Vector3::new(0.0, 0.5, 0.0), Vector3::new(0.0, 0.0, 0.5)),
+            (0, _, 0) => (Vector3::new(0.5, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.5)),
+            _ => (Vector3::new(0.5, 0.0, 0.0), Vector3::new(0.0, 0.5, 0.0)),
+        };
+        let corners = [center - u - v, center + u - v, center + u + v, center - u + v];
+
+        (0..4)
+            .filter_map(|i| project_segment(camera, corners[i], corners[(i + 1) % 4], width, height))
+            .map(|(start, end)| (start, end, Color::WHITE))
+            .collect()
+    }
+}
+
+/// Modo de edición de bloques (tecla Z): quita y coloca bloques con el mouse
+/// sobre la escena ya construida
+pub struct BlockEditor {
+    pub open: bool,
+    /// Materiales que se pueden colocar, por nombre
+    palette: Vec<(String, Arc<Material>)>,
+    selected: usize,
+    /// Bloques interiores que la escena descartó al construirse; vuelven a
+    /// aparecer cuando se quita un bloque junto a ellos
+    hidden: HiddenBlocks,
+    /// Objetos y bloques interiores de la escena tal como se construyó,
+    /// antes de editarla, para reconocerlos al armarla otra vez
+    base: Vec<SceneObject>,
+    base_hidden: HiddenBlocks,
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl BlockEditor {
+    pub fn new() -> Self {
+        let palette = scene_builder::block_materials();
+        let selected = palette.iter().position(|(name, _)| name == "stone").unwrap_or(0);
+        BlockEditor {
+            open: false,
+            palette,
+            selected,
+            hidden: HiddenBlocks::new(),
+            base: Vec::new(),
+            base_hidden: HiddenBlocks::new(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Toma los bloques interiores de la escena recién construida y olvida
+    /// las ediciones de la anterior
+    pub fn reset(&mut self, scene: &mut BuiltScene) {
+        self.hidden = scene.take_hidden_blocks();
+        self.base = scene.objects.clone();
+        self.base_hidden = self.hidden.clone();
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Pasa la escena y el historial a `data`, la misma escena armada otra
+    /// vez con los materiales cambiados: los objetos originales, los bloques
+    /// colocados y los interiores toman los materiales nuevos en el mismo
+    /// lugar, sin perder las ediciones ni tocar la forma del BVH. Devuelve
+    /// los índices de los objetos cuyas cajas cambiaron, o `None` si `data`
+    /// no tiene los mismos objetos que la escena original y se reconstruyó.
+    pub fn rebase(&mut self, scene: &mut BuiltScene, mut data: SceneData) -> Option<Vec<usize>> {
+        if data.objects.len() != self.base.len() {
+            println!(
+                "La escena armada tiene {} objetos en lugar de {}: se reconstruye y se descartan las ediciones de bloques",
+                data.objects.len(),
+                self.base.len()
+            );
+            data.lights = std::mem::take(&mut scene.lights);
+            *scene = BuiltScene::new(data);
+            self.reset(scene);
+            return None;
+        }
+
+        let (objects, hidden) = scene.replace_materials(data);
+        let selected = self.selected_name().to_string();
+        self.palette = scene_builder::block_materials();
+        self.selected = self.palette.iter().position(|(name, _)| *name == selected).unwrap_or(0);
+
+        // Objeto nuevo de cada objeto actual, por su dirección
+        let key = |object: &SceneObject| Arc::as_ptr(object) as *const ();
+        let mut fresh: HashMap<*const (), SceneObject> = self
+            .base
+            .iter()
+            .zip(&objects)
+            .map(|(old, new)| (key(old), new.clone()))
+            .collect();
+        for (cell, (old, _)) in &self.base_hidden {
+            if let Some((new, _)) = hidden.get(cell) {
+                fresh.insert(key(old), new.clone());
+            }
+        }
+        // Los bloques colocados se arman otra vez con la paleta, tanto los que
+        // están en la escena como los que solo quedan en el historial
+        let mut placed: Vec<(SceneObject, (i32, i32, i32), String)> = scene
+            .blocks()
+            .map(|block| (scene.objects[block.index].clone(), block.cell, block.material.to_string()))
+            .collect();
+        for edit in self.undo.iter().chain(&self.redo) {
+            for (cell, before, after) in &edit.cells {
+                for (entry, is_after) in [(before, false), (after, true)] {
+                    let Some((index, name)) = entry else { continue };
+                    if let Some((_, old, new)) = edit.slots.iter().find(|(slot, ..)| slot == index) {
+                        let object = if is_after { new } else { old };
+                        placed.push((object.clone(), *cell, name.clone()));
+                    }
+                }
+            }
+        }
+        for (object, (x, y, z), name) in placed {
+            if built_scene::is_removed(&object) || fresh.contains_key(&key(&object)) {
+                continue;
+            }
+            if let Some(cube) = self.block_cube(scene, &name, Vector3::new(x as f32, y as f32, z as f32)) {
+                fresh.insert(key(&object), cube);
+            }
+        }
+
+        let rebind = |object: &SceneObject| fresh.get(&key(object)).cloned().unwrap_or_else(|| object.clone());
+        let mut changed = Vec::new();
+        for (index, object) in scene.objects.iter_mut().enumerate() {
+            let new = rebind(object);
+            let (old_bounds, new_bounds) = (object.get_bounds(), new.get_bounds());
+            if old_bounds.min != new_bounds.min || old_bounds.max != new_bounds.max {
+                changed.push(index);
+            }
+            *object = new;
+        }
+        for edit in self.undo.iter_mut().chain(self.redo.iter_mut()) {
+            for (_, before, after) in &mut edit.slots {
+                *before = rebind(before);
+                *after = rebind(after);
+            }
+        }
+        for (object, _) in self.hidden.values_mut() {
+            *object = rebind(object);
+        }
+        self.base = objects;
+        self.base_hidden = hidden;
+
+        scene.refit(&changed);
+        Some(changed)
+    }
+
+    pub fn selected_name(&self) -> &str {
+        &self.palette[self.selected].0
+    }
+
+    /// Avanza `step` materiales en la paleta (negativo retrocede)
+    pub fn cycle(&mut self, step: i32) {
+        let count = self.palette.len() as i32;
+        self.selected = (self.selected as i32 + step).rem_euclid(count) as usize;
+    }
+
+    /// Selecciona el material de la biblioteca con el nombre de `material`
+    pub fn select(&mut self, material: &Material) -> bool {
+        match self.palette.iter().position(|(name, _)| *name == material.name) {
+            Some(index) => {
+                self.selected = index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Bloque bajo el pixel (x, y) de una imagen de `width`×`height`
+    pub fn pick(&self, scene: &BuiltScene, camera: &Camera, (x, y): (u32, u32), width: u32, height: u32) -> Option<BlockPick> {
+        let ray = Ray::primary(camera, x as f32, y as f32, width as usize, height as usize);
+        scene.pick(&ray).map(|hit| BlockPick::new(&hit))
+    }
+
+    /// Quita todo lo que hay en la celda apuntada y agrega al final de los
+    /// objetos de la escena los bloques interiores que quedan al descubierto.
+    /// Devuelve los índices de los objetos cuyas cajas cambiaron.
+    pub fn remove(&mut self, scene: &mut BuiltScene, pick: &BlockPick) -> Result<Vec<usize>, String> {
+        let (x, y, z) = pick.block;
+        let removed = objects_in_cell(&scene.objects, pick.block);
+        if removed.is_empty() {
+            return Err(format!("Solo se pueden quitar bloques: el objeto en ({}, {}, {}) ocupa más de una celda", x, y, z));
+        }
+
+        let before = scene.block_at(x, y, z).map(|block| (block.index, block.material.to_string()));
+        let mut edit = Edit {
+            description: format!("quitar el bloque en ({}, {}, {})", x, y, z),
+            slots: Vec::new(),
+            cells: vec![(pick.block, before, None)],
+            uncovered: Vec::new(),
+        };
+        for index in removed {
+            let object = scene.objects[index].clone();
+            edit.slots.push((index, object.clone(), removed_at(object.get_bounds().center())));
+        }
+        for (dx, dy, dz) in FACE_NEIGHBORS {
+            let cell = (x + dx, y + dy, z + dz);
+            if let Some((block, material)) = self.hidden.remove(&cell) {
+                let index = scene.objects.len();
+                scene.objects.push(removed_at(block.get_bounds().center()));
+                edit.slots.push((index, scene.objects[index].clone(), block));
+                edit.cells.push((cell, None, Some((index, material.clone()))));
+                edit.uncovered.push((cell, index, material));
+            }
+        }
+        Ok(self.record(scene, edit))
+    }
+
+    /// Agrega un bloque del material elegido junto a la cara apuntada.
+    /// Devuelve los índices de los objetos cuyas cajas cambiaron.
+    pub fn place(&mut self, scene: &mut BuiltScene, pick: &BlockPick) -> Result<Vec<usize>, String> {
+        let (x, y, z) = pick.adjacent();
+        if self.hidden.contains_key(&(x, y, z)) || !objects_in_cell(&scene.objects, (x, y, z)).is_empty() {
+            return Err(format!("La celda ({}, {}, {}) ya está ocupada", x, y, z));
+        }
+
+        let name = self.selected_name().to_string();
+        let center = Vector3::new(x as f32, y as f32, z as f32);
+        let cube = self.block_cube(scene, &name, center).ok_or_else(|| format!("No hay un bloque de {}", name))?;
+        let index = scene.objects.len();
+        scene.objects.push(removed_at(center));
+        let edit = Edit {
+            description: format!("colocar {} en ({}, {}, {})", name, x, y, z),
+            slots: vec![(index, scene.objects[index].clone(), cube)],
+            cells: vec![((x, y, z), None, Some((index, name.clone())))],
+            uncovered: Vec::new(),
+        };
+        Ok(self.record(scene, edit))
+    }
+
+    /// Cubo del material `name` de la paleta en `center`; los bloques de la
+    /// paleta de la escena (como el pasto) llevan sus caras
+    fn block_cube(&self, scene: &BuiltScene, name: &str, center: Vector3) -> Option<SceneObject> {
+        if let Some(faces) = scene.block_faces(name) {
+            return Some(Arc::new(Cube::with_faces(center, 1.0, faces)));
+        }
+        let (_, material) = self.palette.iter().find(|(entry, _)| entry == name)?;
+        Some(Arc::new(Cube::new(center, 1.0, material.clone())))
+    }
+
+    /// Aplica una edición nueva y la guarda en el historial; lo que se podía
+    /// rehacer se descarta
+    fn record(&mut self, scene: &mut BuiltScene, edit: Edit) -> Vec<usize> {
+        edit.apply(scene);
+        let indices = edit.indices();
+        println!("Edición: {}", edit.description);
+        self.redo.clear();
+        self.undo.push_back(edit);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+        indices
+    }
+
+    /// Deshace la última edición. Devuelve los índices de los objetos cuyas
+    /// cajas cambiaron, o `None` si no hay nada que deshacer.
+    pub fn undo(&mut self, scene: &mut BuiltScene) -> Option<Vec<usize>> {
+        let edit = self.undo.pop_back()?;
+        for (index, before, _) in &edit.slots {
+            scene.objects[*index] = before.clone();
+        }
+        for (cell, before, _) in &edit.cells {
+            scene.set_block(*cell, before.clone());
+        }
+        for (cell, index, material) in &edit.uncovered {
+            if let Some((_, _, block)) = edit.slots.iter().find(|(slot, ..)| slot == index) {
+                self.hidden.insert(*cell, (block.clone(), material.clone()));
+            }
+        }
+        println!("Deshecho: {}", edit.description);
+        let indices = edit.indices();
+        self.redo.push(edit);
+        Some(indices)
+    }
+
+    /// Vuelve a aplicar la última edición deshecha
+    pub fn redo(&mut self, scene: &mut BuiltScene) -> Option<Vec<usize>> {
+        let edit = self.redo.pop()?;
+        edit.apply(scene);
+        for (cell, ..) in &edit.uncovered {
+            self.hidden.remove(cell);
+        }
+        println!("Rehecho: {}", edit.description);
+        let indices = edit.indices();
+        self.undo.push_back(edit);
+        Some(indices)
+    }
+
+    /// Texto del modo de edición
+    pub fn overlay_lines(&self) -> Vec<String> {
+        vec![
+            "EDICIÓN DE BLOQUES (Z para salir)".to_string(),
+            format!("Material: {} (rueda para cambiar)", self.selected_name()),
+            "Clic izq.: quitar   Clic der.: colocar".to_string(),
+            "Clic medio: tomar material".to_string(),
+            format!("Ctrl+Z / Ctrl+Y: deshacer ({}) / rehacer ({})", self.undo.len(), self.redo.len()),
+        ]
+    }
+}
+
+/// Índices de los objetos que caben enteros en la celda
+fn objects_in_cell(objects: &[SceneObject], (x, y, z): (i32, i32, i32)) -> Vec<usize> {
+    let cell = Vector3::new(x as f32, y as f32, z as f32);
+    let (low, high) = (cell - Vector3::one() * (0.5 + CELL_MARGIN), cell + Vector3::one() * (0.5 + CELL_MARGIN));
+    objects
+        .iter()
+        .enumerate()
+        .filter(|(_, object)| {
+            let bounds = object.get_bounds();
+            !built_scene::is_removed(object)
+                && bounds.min.x >= low.x && bounds.min.y >= low.y && bounds.min.z >= low.z
+                && bounds.max.x <= high.x && bounds.max.y <= high.y && bounds.max.z <= high.z
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
@@ -0,0 +1,48 @@
+use raylib::prelude::Vector3;
+
+use crate::ray_intersect::Intersect;
+
+/// Nivel de luz máximo, como en Minecraft
+const MAX_LIGHT_LEVEL: f32 = 15.0;
+
+/// Bloque bajo la mira en el modo de edición, calculado con un solo rayo por frame
+pub struct BlockTarget {
+    /// Coordenadas enteras del bloque
+    pub block: (i32, i32, i32),
+    pub material: String,
+    /// Estimación 0-15 de la luz que recibe la cara apuntada
+    pub light_level: u8,
+    /// Distancia desde la cámara (en bloques)
+    pub distance: f32,
+}
+
+impl BlockTarget {
+    /// `irradiance` es la luz total (ambiental + luces, con sombras) que llega a la cara
+    pub fn new(intersect: &Intersect, irradiance: Vector3) -> Self {
+        // El punto golpeado está sobre la cara; medio bloque hacia adentro queda el centro
+        let inside = intersect.point - intersect.normal * 0.5;
+        let luminance = 0.2126 * irradiance.x + 0.7152 * irradiance.y + 0.0722 * irradiance.z;
+
+        BlockTarget {
+            block: (inside.x.round() as i32, inside.y.round() as i32, inside.z.round() as i32),
+            material: if intersect.material.name.is_empty() {
+                "(sin nombre)".to_string()
+            } else {
+                intersect.material.name.clone()
+            },
+            light_level: (luminance * MAX_LIGHT_LEVEL).round().clamp(0.0, MAX_LIGHT_LEVEL) as u8,
+            distance: intersect.distance,
+        }
+    }
+
+    /// Texto a mostrar junto a la mira
+    pub fn overlay_lines(&self) -> Vec<String> {
+        let (x, y, z) = self.block;
+        vec![
+            format!("Bloque {} {} {}", x, y, z),
+            format!("Material {}", self.material),
+            format!("Luz {}/15", self.light_level),
+            format!("Distancia {:.1}", self.distance),
+        ]
+    }
+}
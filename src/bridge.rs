@@ -0,0 +1,139 @@
+use raylib::prelude::Vector3;
+use serde::Deserialize;
+
+use crate::scene_builder::curve_cells;
+
+/// Cada cuántos bloques del tablero baja un pilar en los puentes de arco
+const SUPPORT_SPACING: usize = 6;
+/// Cada cuántos bloques del tablero cuelga un tirante en los colgantes
+const HANGER_SPACING: usize = 2;
+
+/// Cómo se sostiene el puente
+#[derive(Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BridgeKind {
+    /// Tablero en arco con pilares que bajan hasta el suelo, si lo hay
+    Arch,
+    /// Tablero recto colgado de dos cables entre torres en los extremos
+    Suspension,
+}
+
+/// Cómo es el puente que construye `SceneBuilder::add_bridge`
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BridgeStyle {
+    pub kind: BridgeKind,
+    /// Material del tablero, los pilares y las torres
+    pub material: String,
+    /// Material de las barandas (cercas); sin él el puente no tiene barandas ni luces
+    pub railing: Option<String>,
+    /// Ancho del tablero, en bloques
+    pub width: i32,
+    /// En los de arco, cuánto sube el medio del tablero (negativo para que
+    /// cuelgue); en los colgantes, la altura de las torres sobre el tablero
+    pub height: f32,
+    /// Una antorcha sobre la baranda cada tantos bloques; 0 las desactiva
+    pub light_spacing: usize,
+}
+
+impl BridgeStyle {
+    /// Puente de piedra de tres bloques de ancho con barandas de madera,
+    /// que sube `height` bloques en el medio
+    pub fn arch(material: &str, height: f32) -> Self {
+        BridgeStyle {
+            kind: BridgeKind::Arch,
+            material: material.to_string(),
+            railing: Some("wood".to_string()),
+            width: 3,
+            height,
+            light_spacing: 8,
+        }
+    }
+
+    /// Puente colgante con torres de `height` bloques sobre el tablero
+    pub fn suspension(material: &str, height: f32) -> Self {
+        BridgeStyle {
+            kind: BridgeKind::Suspension,
+            ..Self::arch(material, height)
+        }
+    }
+}
+
+impl Default for BridgeStyle {
+    fn default() -> Self {
+        Self::arch("stone", 3.0)
+    }
+}
+
+/// Dónde va cada bloque de un puente, en celdas del mundo
+pub struct BridgePlan {
+    pub deck: Vec<(i32, i32, i32)>,
+    /// Cercas de las barandas, de los cables y de los tirantes
+    pub fences: Vec<(i32, i32, i32)>,
+    /// Bloques de las torres de los colgantes
+    pub towers: Vec<(i32, i32, i32)>,
+    /// Celdas justo debajo del tablero desde las que bajan los pilares
+    pub supports: Vec<(i32, i32, i32)>,
+    /// Posiciones de las antorchas
+    pub lights: Vec<Vector3>,
+}
+
+impl BridgePlan {
+    pub fn new(from: Vector3, to: Vector3, style: &BridgeStyle) -> Self {
+        let half = (style.width.max(1) - 1) / 2;
+        let span = to - from;
+        // El tablero se ensancha a lo largo del eje horizontal que cruza el recorrido
+        let side = if span.x.abs() >= span.z.abs() { [0, 0, 1] } else { [1, 0, 0] };
+        let offset = |[x, y, z]: [i32; 3], k: i32, dy: i32| (x + side[0] * k, y + dy, z + side[2] * k);
+
+        let rise = if style.kind == BridgeKind::Arch { style.height } else { 0.0 };
+        let length = span.length() + 2.0 * rise.abs();
+        let path = curve_cells(length, |t| from + span * t + Vector3::new(0.0, rise * 4.0 * t * (1.0 - t), 0.0));
+        let last = path.len().saturating_sub(1);
+
+        let mut plan = BridgePlan { deck: Vec::new(), fences: Vec::new(), towers: Vec::new(), supports: Vec::new(), lights: Vec::new() };
+        for (i, &cell) in path.iter().enumerate() {
+            plan.deck.extend((-half..=half).map(|k| offset(cell, k, 0)));
+            if style.kind == BridgeKind::Arch && i > 0 && i < last && i.is_multiple_of(SUPPORT_SPACING) {
+                plan.supports.extend([-half, half].map(|k| offset(cell, k, -1)));
+            }
+            if style.railing.is_none() {
+                continue;
+            }
+            for rail in [-(half + 1), half + 1] {
+                plan.fences.push(offset(cell, rail, 1));
+            }
+            if style.light_spacing > 0 && i % style.light_spacing == style.light_spacing / 2 {
+                // Alternando de lado, sobre el poste de la baranda
+                let rail = if (i / style.light_spacing).is_multiple_of(2) { half + 1 } else { -(half + 1) };
+                let (x, y, z) = offset(cell, rail, 1);
+                plan.lights.push(Vector3::new(x as f32, y as f32 + 0.7, z as f32));
+            }
+        }
+
+        if style.kind == BridgeKind::Suspension && !path.is_empty() {
+            // Torres en los extremos, de las que cuelga un cable por lado que
+            // baja hasta dos bloques sobre el medio del tablero
+            let tower_height = style.height.max(3.0).round() as i32;
+            for end in [path[0], path[last]] {
+                for rail in [-(half + 1), half + 1] {
+                    plan.towers.extend((1..=tower_height).map(|dy| offset(end, rail, dy)));
+                }
+            }
+            for rail in [-(half + 1), half + 1] {
+                let sag = |t: f32| 2.0 + (tower_height - 2) as f32 * (2.0 * t - 1.0).powi(2);
+                let cable = curve_cells(length, |t| {
+                    let deck = from + span * t;
+                    Vector3::new(deck.x + (side[0] * rail) as f32, deck.y + sag(t), deck.z + (side[2] * rail) as f32)
+                });
+                plan.fences.extend(cable.into_iter().map(|[x, y, z]| (x, y, z)));
+                for (i, &cell) in path.iter().enumerate().step_by(HANGER_SPACING) {
+                    let top = cell[1] + sag(i as f32 / last.max(1) as f32).round() as i32;
+                    plan.fences.extend((2..top - cell[1]).map(|dy| offset(cell, rail, dy)));
+                }
+            }
+        }
+
+        plan
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use raylib::prelude::Vector3;
+
+use crate::cube::BlockFaces;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray_intersect::{AABB, Intersect, RayIntersect, BVH};
+use crate::renderer::Ray;
+
+type SceneObject = Arc<dyn RayIntersect + Send + Sync>;
+
+/// Cuánto se entra desde el punto golpeado para saber en qué celda cae
+/// (alcanza también para losas y otras piezas que no llenan la celda)
+const PICK_DEPTH: f32 = 0.01;
+/// Dirección del rayo con que se reconoce cada objeto al comparar escenas;
+/// inclinada para no pasar justo por aristas ni centros de caras
+const PROBE_DIRECTION: Vector3 = Vector3 { x: -0.48, y: -0.79, z: -0.38 };
+/// Cambios que se aplican sobre el BVH existente; con más, o con muchos
+/// lugares vacíos acumulados, se reconstruye. Fracción de los objetos
+const MAX_DIFF_FRACTION: f32 = 0.1;
+/// Mínimo de cambios que se aplican sobre el BVH existente en escenas chicas
+const MIN_DIFF_OBJECTS: usize = 64;
+
+/// Índice en `BuiltScene::objects` y material de un bloque
+pub type BlockEntry = (usize, String);
+
+/// Bloques interiores que se descartaron al construir la escena, por celda,
+/// con su objeto y su material
+pub type HiddenBlocks = HashMap<(i32, i32, i32), (SceneObject, String)>;
+
+/// Lugar que deja un objeto quitado: conserva su índice en la escena, así el
+/// BVH y los chunks solo se reajustan, pero ningún rayo lo toca
+struct RemovedObject {
+    bounds: AABB,
+}
+
+impl RayIntersect for RemovedObject {
+    fn ray_intersect(&self, _ray_origin: &Vector3, _ray_direction: &Vector3) -> Intersect {
+        Intersect::empty()
+    }
+
+    fn get_bounds(&self) -> AABB {
+        self.bounds
+    }
+}
+
+/// Objeto vacío que ocupa el lugar de uno quitado en `center`
+pub fn removed_at(center: Vector3) -> SceneObject {
+    Arc::new(RemovedObject { bounds: AABB::new(center, center) })
+}
+
+/// Los lugares de objetos ya quitados no tienen volumen
+pub fn is_removed(object: &SceneObject) -> bool {
+    let bounds = object.get_bounds();
+    bounds.max == bounds.min
+}
+
+/// Materiales y tipos de bloque con que se armó una escena
+#[derive(Default, PartialEq)]
+pub struct SceneMaterials {
+    pub materials: HashMap<String, Arc<Material>>,
+    pub block_types: HashMap<String, Arc<BlockFaces>>,
+}
+
+/// Lo que arma `SceneBuilder::build_data`, antes de construir el BVH
+pub struct SceneData {
+    pub objects: Vec<SceneObject>,
+    pub lights: Vec<Light>,
+    blocks: HashMap<(i32, i32, i32), BlockEntry>,
+    hidden: HiddenBlocks,
+    materials: SceneMaterials,
+}
+
+impl SceneData {
+    pub fn new(
+        objects: Vec<SceneObject>,
+        lights: Vec<Light>,
+        blocks: HashMap<(i32, i32, i32), BlockEntry>,
+        hidden: HiddenBlocks,
+        materials: SceneMaterials,
+    ) -> Self {
+        SceneData { objects, lights, blocks, hidden, materials }
+    }
+}
+
+/// Lo que cambió al actualizar una escena con `BuiltScene::update`
+pub struct SceneDiff {
+    pub added: usize,
+    pub removed: usize,
+    /// Índices de los objetos quitados, que quedaron como lugares vacíos; los
+    /// agregados van al final de `objects`
+    pub changed: Vec<usize>,
+    /// Se reconstruyó todo (con un BVH nuevo) en lugar de ajustar el anterior
+    pub rebuilt: bool,
+}
+
+/// Con qué se reconoce un objeto al comparar dos versiones de la escena: su
+/// caja, dónde lo toca un rayo de prueba y su material (el del bloque, o el
+/// del punto que toca el rayo)
+type ObjectKey = ([u32; 6], u32, String);
+
+fn object_key(object: &SceneObject, block_material: Option<&str>) -> ObjectKey {
+    let bounds = object.get_bounds();
+    let center = (bounds.min + bounds.max) * 0.5;
+    let origin = center - PROBE_DIRECTION * ((bounds.max - bounds.min).length() + 1.0);
+    let hit = object.ray_intersect(&origin, &PROBE_DIRECTION);
+    let (distance, material) = if hit.is_intersecting {
+        (hit.distance.to_bits(), hit.material.name.clone())
+    } else {
+        (u32::MAX, String::new())
+    };
+    let (min, max) = (bounds.min, bounds.max);
+    (
+        [min.x, min.y, min.z, max.x, max.y, max.z].map(f32::to_bits),
+        distance,
+        block_material.map(str::to_string).unwrap_or(material),
+    )
+}
+
+/// Bloque de la escena construida
+#[derive(Clone, Copy, Debug)]
+pub struct BlockRef<'a> {
+    pub cell: (i32, i32, i32),
+    /// Índice del objeto en `BuiltScene::objects`
+    pub index: usize,
+    pub material: &'a str,
+}
+
+/// Lo que golpea un rayo con `BuiltScene::pick`
+pub struct Hit {
+    pub intersect: Intersect,
+    /// Celda del bloque golpeado; `None` si el objeto no es un bloque
+    pub cell: Option<(i32, i32, i32)>,
+}
+
+impl Hit {
+    /// Celda del bloque golpeado o, si no es un bloque, la celda en la que
+    /// cae el punto golpeado
+    pub fn nearest_cell(&self) -> (i32, i32, i32) {
+        self.cell.unwrap_or_else(|| {
+            let inside = self.intersect.point - self.intersect.normal * PICK_DEPTH;
+            (inside.x.round() as i32, inside.y.round() as i32, inside.z.round() as i32)
+        })
+    }
+}
+
+/// Escena que devuelve `SceneBuilder::build`: los objetos y las luces para
+/// el render, el BVH y un índice de los bloques por celda para consultarla
+/// (el editor, colisiones de la cámara y herramientas) sin recorrer objetos
+pub struct BuiltScene {
+    pub objects: Vec<SceneObject>,
+    pub lights: Vec<Light>,
+    pub bvh: BVH,
+    blocks: HashMap<(i32, i32, i32), BlockEntry>,
+    hidden: HiddenBlocks,
+    materials: SceneMaterials,
+    bvh_build_time: f32,
+}
+
+impl BuiltScene {
+    pub fn new(data: SceneData) -> Self {
+        let SceneData { objects, lights, blocks, hidden, materials } = data;
+        let start = Instant::now();
+        let bvh = BVH::build(&objects);
+        BuiltScene { objects, lights, bvh, blocks, hidden, materials, bvh_build_time: start.elapsed().as_secs_f32() }
+    }
+
+    /// Como `new` pero con un BVH ya armado para estos objetos (el que se
+    /// guardó en la caché de la escena)
+    pub fn with_bvh(data: SceneData, bvh: BVH) -> Self {
+        let SceneData { objects, lights, blocks, hidden, materials } = data;
+        BuiltScene { objects, lights, bvh, blocks, hidden, materials, bvh_build_time: 0.0 }
+    }
+
+    /// Lleva la escena a `data`, otra versión de la misma (por ejemplo el
+    /// archivo de escena guardado de nuevo), sin reconstruir el BVH: los
+    /// objetos que siguen iguales conservan su índice, los que ya no están
+    /// quedan como lugares vacíos y los nuevos se agregan al final. Si
+    /// cambiaron los materiales o son demasiados cambios se reconstruye todo.
+    pub fn update(&mut self, data: SceneData) -> SceneDiff {
+        let rebuild = |scene: &mut BuiltScene, data: SceneData| {
+            let (added, removed) = (data.objects.len(), scene.objects.len());
+            *scene = BuiltScene::new(data);
+            SceneDiff { added, removed, changed: Vec::new(), rebuilt: true }
+        };
+        if data.materials != self.materials {
+            return rebuild(self, data);
+        }
+
+        // Objetos de la escena actual por su clave; los lugares vacíos no cuentan
+        let block_materials = |blocks: &HashMap<(i32, i32, i32), BlockEntry>| -> HashMap<usize, String> {
+            blocks.values().map(|(index, material)| (*index, material.clone())).collect()
+        };
+        let old_materials = block_materials(&self.blocks);
+        let mut empty = 0;
+        let mut old: HashMap<ObjectKey, Vec<usize>> = HashMap::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            if is_removed(object) {
+                empty += 1;
+            } else {
+                old.entry(object_key(object, old_materials.get(&index).map(String::as_str))).or_default().push(index);
+            }
+        }
+
+        let new_materials = block_materials(&data.blocks);
+        let mut slots = Vec::with_capacity(data.objects.len());
+        let mut added = Vec::new();
+        for (index, object) in data.objects.iter().enumerate() {
+            let key = object_key(object, new_materials.get(&index).map(String::as_str));
+            let slot = old.get_mut(&key).and_then(Vec::pop);
+            if slot.is_none() {
+                added.push(index);
+            }
+            slots.push(slot);
+        }
+        let removed: Vec<usize> = old.into_values().flatten().collect();
+
+        let limit = MIN_DIFF_OBJECTS.max((self.objects.len() as f32 * MAX_DIFF_FRACTION) as usize);
+        if added.len() + removed.len() > limit || empty + removed.len() > limit {
+            return rebuild(self, data);
+        }
+
+        for &index in &removed {
+            self.objects[index] = removed_at(self.objects[index].get_bounds().center());
+            self.bvh.mark_dirty(index);
+        }
+        for &index in &added {
+            slots[index] = Some(self.objects.len());
+            self.objects.push(data.objects[index].clone());
+        }
+        self.bvh.refit(&self.objects);
+        self.bvh.insert_new(&self.objects);
+
+        let SceneData { lights, blocks, hidden, .. } = data;
+        self.blocks = blocks
+            .into_iter()
+            .filter_map(|(cell, (index, material))| slots[index].map(|slot| (cell, (slot, material))))
+            .collect();
+        self.hidden = hidden;
+        self.lights = lights;
+        SceneDiff { added: added.len(), removed: removed.len(), changed: removed, rebuilt: false }
+    }
+
+    /// Toma los materiales de `data`, la misma escena armada otra vez (por
+    /// ejemplo después de cambiar un material), y devuelve sus objetos y sus
+    /// bloques interiores para que el editor de bloques los ponga en lugar de
+    /// los actuales sin perder sus ediciones. Las luces no cambian.
+    pub fn replace_materials(&mut self, data: SceneData) -> (Vec<SceneObject>, HiddenBlocks) {
+        self.materials = data.materials;
+        (data.objects, data.hidden)
+    }
+
+    /// Reajusta en el BVH las cajas de los objetos `indices`, reemplazados
+    /// por otros en el mismo lugar de `objects`
+    pub fn refit(&mut self, indices: &[usize]) {
+        for &index in indices {
+            self.bvh.mark_dirty(index);
+        }
+        self.bvh.refit(&self.objects);
+    }
+
+    /// Segundos que tardó en construirse el BVH
+    pub fn bvh_build_time(&self) -> f32 {
+        self.bvh_build_time
+    }
+
+    /// Primer objeto que golpea el rayo y, si es un bloque, su celda
+    pub fn pick(&self, ray: &Ray) -> Option<Hit> {
+        let intersect = self.bvh.intersect(&ray.origin, &ray.direction, &self.objects);
+        if !intersect.is_intersecting {
+            return None;
+        }
+
+        let inside = intersect.point - intersect.normal * PICK_DEPTH;
+        // Sobre una arista el punto queda justo entre dos celdas: se prueban
+        // las dos y se elige la del bloque que golpeó el rayo
+        let candidates = |c: f32| {
+            if (c - c.floor() - 0.5).abs() < PICK_DEPTH * 0.5 {
+                vec![(c - 0.5).round() as i32, (c + 0.5).round() as i32]
+            } else {
+                vec![c.round() as i32]
+            }
+        };
+        let (xs, ys, zs) = (candidates(inside.x), candidates(inside.y), candidates(inside.z));
+        let mut cells = Vec::new();
+        for &x in &xs {
+            for &y in &ys {
+                cells.extend(zs.iter().map(|&z| (x, y, z)));
+            }
+        }
+        let cell = cells.into_iter().find(|&(x, y, z)| {
+            self.block_at(x, y, z).is_some_and(|block| {
+                let own = self.objects[block.index].ray_intersect(&ray.origin, &ray.direction);
+                own.is_intersecting && (own.distance - intersect.distance).abs() < PICK_DEPTH
+            })
+        });
+        Some(Hit { intersect, cell })
+    }
+
+    /// Caras del tipo de bloque con ese nombre (como `grass_top`), si la
+    /// escena lo registró en su paleta
+    pub fn block_faces(&self, name: &str) -> Option<Arc<BlockFaces>> {
+        self.materials.block_types.get(name).cloned()
+    }
+
+    /// Bloque en la celda, si hay uno
+    pub fn block_at(&self, x: i32, y: i32, z: i32) -> Option<BlockRef<'_>> {
+        self.blocks.get(&(x, y, z)).map(|(index, material)| BlockRef { cell: (x, y, z), index: *index, material })
+    }
+
+    /// Todos los bloques de la escena, en cualquier orden
+    pub fn blocks(&self) -> impl Iterator<Item = BlockRef<'_>> {
+        self.blocks.iter().map(|(&cell, (index, material))| BlockRef { cell, index: *index, material })
+    }
+
+    /// Bloques cuyas celdas tocan la caja
+    pub fn blocks_in_aabb(&self, aabb: &AABB) -> Vec<BlockRef<'_>> {
+        // Celdas c con c - 0.5 < max y c + 0.5 > min en cada eje
+        let low = |min: f32| (min - 0.5).floor() as i32 + 1;
+        let high = |max: f32| (max + 0.5).ceil() as i32 - 1;
+        let (min, max) = (
+            (low(aabb.min.x), low(aabb.min.y), low(aabb.min.z)),
+            (high(aabb.max.x), high(aabb.max.y), high(aabb.max.z)),
+        );
+        if min.0 > max.0 || min.1 > max.1 || min.2 > max.2 {
+            return Vec::new();
+        }
+
+        let inside = |&(x, y, z): &(i32, i32, i32)| {
+            (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y) && (min.2..=max.2).contains(&z)
+        };
+        let volume = (max.0 - min.0 + 1) as i64 * (max.1 - min.1 + 1) as i64 * (max.2 - min.2 + 1) as i64;
+        // Una caja chica se recorre celda por celda; una grande, bloque por bloque
+        if volume <= self.blocks.len() as i64 {
+            let mut found = Vec::new();
+            for x in min.0..=max.0 {
+                for y in min.1..=max.1 {
+                    found.extend((min.2..=max.2).filter_map(|z| self.block_at(x, y, z)));
+                }
+            }
+            found
+        } else {
+            self.blocks
+                .iter()
+                .filter(|(cell, _)| inside(cell))
+                .map(|(&cell, (index, material))| BlockRef { cell, index: *index, material })
+                .collect()
+        }
+    }
+
+    /// Pone o saca un bloque del índice después de editar `objects`
+    pub fn set_block(&mut self, cell: (i32, i32, i32), block: Option<BlockEntry>) {
+        match block {
+            Some(block) => self.blocks.insert(cell, block),
+            None => self.blocks.remove(&cell),
+        };
+    }
+
+    /// Saca los bloques interiores descartados, para que el editor de
+    /// bloques los vuelva a poner al excavar hasta ellos
+    pub fn take_hidden_blocks(&mut self) -> HiddenBlocks {
+        std::mem::take(&mut self.hidden)
+    }
+}
@@ -0,0 +1,284 @@
+use raylib::prelude::Vector3;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::TEXTURE_MANAGER;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::material_editor::MATERIAL_FILE;
+use crate::ray_intersect::{AABB, BVH, BvhMetrics, FlatNode, RayIntersect};
+use crate::screenshot::{THUMBNAIL_WIDTH, escape_json};
+
+/// Directorio donde se guardan los datos derivados de cada escena
+pub const CACHE_DIR: &str = ".cache";
+/// Archivo de la entrada con el BVH aplanado
+const BVH_FILE: &str = "bvh.bin";
+/// Archivo de la entrada con el atlas de texturas
+const ATLAS_FILE: &str = "atlas.bin";
+/// Encabezado del BVH guardado; cambia si cambia el formato
+const BVH_MAGIC: &[u8; 4] = b"BVH1";
+
+/// Lee de a un valor (little-endian) un archivo binario de la caché; cada
+/// lectura devuelve `None` si el archivo se termina antes
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes }
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(..len)?;
+        self.bytes = &self.bytes[len..];
+        Some(bytes)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_bits(self.u32()?))
+    }
+
+    pub fn vec3(&mut self) -> Option<Vector3> {
+        Some(Vector3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    /// Si ya se leyó todo el archivo
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Hash FNV-1a de 64 bits (estable entre ejecuciones y versiones de Rust,
+/// a diferencia de `DefaultHasher`)
+struct ContentHash(u64);
+
+impl ContentHash {
+    fn new() -> Self {
+        ContentHash(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_bits().to_le_bytes());
+    }
+
+    fn vec3(&mut self, v: Vector3) {
+        self.f32(v.x);
+        self.f32(v.y);
+        self.f32(v.z);
+    }
+}
+
+/// Hash del contenido de una escena: geometría, luces, materiales personalizados,
+/// tamaño y semilla de las texturas procedurales y paquete de texturas.
+/// Cambia cuando cambia la definición de la escena, invalidando su caché.
+fn scene_hash(
+    scene_num: i32,
+    objects: &[Arc<dyn RayIntersect + Send + Sync>],
+    lights: &[Light],
+) -> u64 {
+    let mut hash = ContentHash::new();
+    hash.bytes(&scene_num.to_le_bytes());
+
+    for object in objects {
+        let bounds = object.get_bounds();
+        hash.vec3(bounds.min);
+        hash.vec3(bounds.max);
+    }
+
+    for light in lights {
+        hash.vec3(light.position);
+        hash.bytes(&[light.color.r, light.color.g, light.color.b]);
+        hash.f32(light.intensity);
+        if let Some(area) = &light.area {
+            hash.vec3(area.edge_u);
+            hash.vec3(area.edge_v);
+            hash.bytes(&area.samples.to_le_bytes());
+        }
+    }
+
+    if let Ok(materials) = std::fs::read(MATERIAL_FILE) {
+        hash.bytes(&materials);
+    }
+
+    let textures = TEXTURE_MANAGER.lock().unwrap();
+    let (texture_size, texture_seed) = textures.procedural_settings();
+    hash.bytes(&texture_size.to_le_bytes());
+    hash.bytes(&texture_seed.to_le_bytes());
+    if let Some(pack) = textures.resource_pack() {
+        hash.bytes(pack.as_bytes());
+    }
+
+    hash.0
+}
+
+/// Entrada de caché de una escena: `.cache/scene_NN_<hash>/`.
+/// Guarda la miniatura, los metadatos, el BVH aplanado y el atlas de
+/// texturas; `artifact_path` da la ruta de cada uno, así todos quedan
+/// invalidados juntos al cambiar el hash.
+pub struct SceneCache {
+    scene_num: i32,
+    key: String,
+    dir: PathBuf,
+}
+
+impl SceneCache {
+    pub fn new(
+        scene_num: i32,
+        objects: &[Arc<dyn RayIntersect + Send + Sync>],
+        lights: &[Light],
+    ) -> Self {
+        let key = format!("{:016x}", scene_hash(scene_num, objects, lights));
+        let dir = Path::new(CACHE_DIR).join(format!("scene_{:02}_{}", scene_num, key));
+        SceneCache { scene_num, key, dir }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Ruta de un archivo dentro de la entrada de esta escena
+    pub fn artifact_path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    pub fn has_thumbnail(&self) -> bool {
+        self.artifact_path("thumbnail.png").exists()
+    }
+
+    /// Crea el directorio de la entrada y borra las entradas viejas de la misma escena
+    fn prepare(&self) -> Result<(), String> {
+        let prefix = format!("scene_{:02}_", self.scene_num);
+        if let Ok(entries) = std::fs::read_dir(CACHE_DIR) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with(&prefix) && entry.path() != self.dir {
+                    std::fs::remove_dir_all(entry.path())
+                        .map_err(|e| format!("Error borrando {}: {}", entry.path().display(), e))?;
+                }
+            }
+        }
+
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("No se pudo crear directorio {}: {}", self.dir.display(), e))
+    }
+
+    /// Guarda el BVH de la escena aplanado (ver `BVH::flatten`)
+    pub fn store_bvh(&self, bvh: &BVH) -> Result<(), String> {
+        self.prepare()?;
+
+        let nodes = bvh.flatten();
+        let mut bytes = Vec::with_capacity(8 + nodes.len() * 29);
+        bytes.extend_from_slice(BVH_MAGIC);
+        bytes.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+        for node in &nodes {
+            let (min, max) = (node.bounds.min, node.bounds.max);
+            for value in [min.x, min.y, min.z, max.x, max.y, max.z] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.extend_from_slice(&(node.index as u32).to_le_bytes());
+            bytes.push(node.is_leaf as u8);
+        }
+        let path = self.artifact_path(BVH_FILE);
+        std::fs::write(&path, bytes).map_err(|e| format!("Error guardando {}: {}", path.display(), e))
+    }
+
+    /// BVH guardado para los `object_count` objetos de la escena; `None` si
+    /// no hay o el archivo no es válido
+    pub fn load_bvh(&self, object_count: usize) -> Option<BVH> {
+        let bytes = std::fs::read(self.artifact_path(BVH_FILE)).ok()?;
+        let mut reader = ByteReader::new(&bytes);
+        if reader.bytes(4)? != BVH_MAGIC {
+            return None;
+        }
+        let count = reader.u32()? as usize;
+        let mut nodes = Vec::with_capacity(count.min(bytes.len() / 29));
+        for _ in 0..count {
+            let bounds = AABB::new(reader.vec3()?, reader.vec3()?);
+            let index = reader.u32()? as usize;
+            let is_leaf = reader.u8()? != 0;
+            nodes.push(FlatNode { bounds, index, is_leaf });
+        }
+        if !reader.is_empty() {
+            return None;
+        }
+        BVH::from_flat(&nodes, object_count)
+    }
+
+    /// Guarda el atlas de texturas armado para la escena
+    pub fn store_atlas(&self) -> Result<(), String> {
+        self.prepare()?;
+
+        let bytes = TEXTURE_MANAGER.lock().unwrap().atlas_bytes();
+        let path = self.artifact_path(ATLAS_FILE);
+        std::fs::write(&path, bytes).map_err(|e| format!("Error guardando {}: {}", path.display(), e))
+    }
+
+    /// Reemplaza el atlas de texturas por el guardado, si hay uno que sirve
+    /// para las texturas cargadas; devuelve si lo usó
+    pub fn load_atlas(&self) -> bool {
+        match std::fs::read(self.artifact_path(ATLAS_FILE)) {
+            Ok(bytes) => TEXTURE_MANAGER.lock().unwrap().restore_atlas(&bytes),
+            Err(_) => false,
+        }
+    }
+
+    /// Guarda la miniatura del render y los metadatos de la escena
+    pub fn store(
+        &self,
+        framebuffer: &Framebuffer,
+        scene_name: &str,
+        object_count: usize,
+        light_count: usize,
+        bvh: &BvhMetrics,
+    ) -> Result<(), String> {
+        self.prepare()?;
+
+        let image = image::RgbaImage::from_raw(framebuffer.width, framebuffer.height, framebuffer.to_rgba())
+            .ok_or_else(|| "Error creando miniatura".to_string())?;
+        let thumb_height = (framebuffer.height * THUMBNAIL_WIDTH / framebuffer.width.max(1)).max(1);
+        let thumbnail = image::imageops::thumbnail(&image, THUMBNAIL_WIDTH, thumb_height);
+        let thumb_path = self.artifact_path("thumbnail.png");
+        thumbnail
+            .save(&thumb_path)
+            .map_err(|e| format!("Error guardando {}: {}", thumb_path.display(), e))?;
+
+        let json = format!(
+            "{{\n  \"scene\": {},\n  \"scene_name\": \"{}\",\n  \"hash\": \"{}\",\n  \"objects\": {},\n  \"lights\": {},\n  \"bvh_nodes\": {},\n  \"bvh_max_depth\": {},\n  \"version\": \"{}\"\n}}\n",
+            self.scene_num,
+            escape_json(scene_name),
+            self.key,
+            object_count,
+            light_count,
+            bvh.node_count,
+            bvh.max_depth,
+            env!("CARGO_PKG_VERSION"),
+        );
+        let json_path = self.artifact_path("metadata.json");
+        std::fs::write(&json_path, json).map_err(|e| format!("Error guardando {}: {}", json_path.display(), e))
+    }
+}
+
+/// Borra todo el directorio de caché (opción `--clear-cache`)
+pub fn clear_cache() -> Result<(), String> {
+    match std::fs::remove_dir_all(CACHE_DIR) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Error borrando {}: {}", CACHE_DIR, e)),
+    }
+}
@@ -1,6 +1,36 @@
 use raylib::prelude::*;
+use std::f32::consts::PI;
+
+use crate::renderer::FOV;
+
+/// Cómo la cámara convierte un pixel en un rayo
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Projection {
+    /// Rayos que salen del ojo abriéndose según el FOV
+    #[default]
+    Perspective,
+    /// Rayos paralelos a `forward`; `extent` es la mitad de la altura de la
+    /// imagen en bloques (el ancho sale de la relación de aspecto)
+    Orthographic { extent: f32 },
+}
+
+/// Extensión mínima de la proyección ortográfica al hacer zoom
+const MIN_ORTHOGRAPHIC_EXTENT: f32 = 0.5;
+/// Velocidad de giro con una tecla sostenida (radianes por segundo)
+pub const ORBIT_SPEED: f32 = std::f32::consts::FRAC_PI_2;
+/// Qué tan rápido la velocidad alcanza la pedida (1/s): con 8 llega al 63% en
+/// 1/8 s, así el movimiento arranca y frena suave sin sentirse lento
+const MOTION_RESPONSE: f32 = 8.0;
+/// Por debajo de esta fracción de la velocidad máxima la cámara se detiene
+/// del todo, para que el render progresivo pueda empezar a acumular
+const MOTION_REST: f32 = 0.01;
+/// Paso de tiempo máximo: un frame lento no hace saltar la cámara
+const MAX_MOTION_STEP: f32 = 0.1;
+/// Velocidad de roll con una tecla sostenida (radianes por segundo)
+pub const ROLL_SPEED: f32 = std::f32::consts::FRAC_PI_4;
 
 /// Cámara orbital 3D que mantiene su posición y orientación en el espacio
+#[derive(Clone)]
 pub struct Camera {
     /// Posición de la cámara en coordenadas del mundo
     pub eye: Vector3,
@@ -8,14 +38,35 @@ pub struct Camera {
     /// Punto hacia el que mira la cámara
     pub center: Vector3,
     
-    /// Vector "arriba" de la cámara (se ortonormaliza automáticamente)
+    /// Vector "arriba" de la cámara, ya inclinado por el roll (se
+    /// ortonormaliza automáticamente)
     pub up: Vector3,
+
+    /// Arriba del mundo con el que se nivela el horizonte antes del roll
+    pub world_up: Vector3,
+
+    /// Giro alrededor de `forward` en radianes; positivo gira la cámara en
+    /// sentido antihorario, con lo que el horizonte se inclina en sentido horario
+    pub roll: f32,
     
     /// Dirección hacia donde mira la cámara (calculado de eye->center)
     pub forward: Vector3,
     
     /// Dirección hacia la derecha (perpendicular a forward y up)
     pub right: Vector3,
+
+    /// Derecha nivelada con `world_up`, antes del roll; se conserva al mirar
+    /// hacia los polos, donde no se puede nivelar
+    level_right: Vector3,
+
+    /// Perspectiva u ortográfica
+    pub projection: Projection,
+
+    /// Velocidad de giro (radianes por segundo en yaw y pitch) y de zoom
+    /// (bloques por segundo), que `steer` acerca a la pedida y `glide` aplica
+    pub orbit_velocity: (f32, f32),
+    pub roll_velocity: f32,
+    pub zoom_velocity: f32,
 }
 
 impl Camera {
@@ -25,25 +76,125 @@ impl Camera {
             eye,
             center,
             up,
+            world_up: up,
+            roll: 0.0,
             forward: Vector3::zero(),
             right: Vector3::zero(),
+            level_right: Vector3::zero(),
+            projection: Projection::Perspective,
+            orbit_velocity: (0.0, 0.0),
+            roll_velocity: 0.0,
+            zoom_velocity: 0.0,
         };
         camera.update_basis_vectors();
         camera
     }
 
+    /// Cámara isométrica mirando a `center` desde la diagonal (1, 1, 1): las
+    /// tres caras visibles de un bloque quedan del mismo tamaño, como en un
+    /// diorama. `extent` es la mitad de la altura de la imagen en bloques.
+    pub fn isometric(center: Vector3, distance: f32, extent: f32) -> Self {
+        let eye = center + Vector3::one().normalized() * distance;
+        Camera::new(eye, center, Vector3::new(0.0, 1.0, 0.0))
+            .with_projection(Projection::Orthographic { extent })
+    }
+
+    /// Extensión ortográfica con la que lo que está en `center` se ve del
+    /// mismo tamaño que en perspectiva
+    pub fn matching_extent(&self) -> f32 {
+        (self.center - self.eye).length() * (FOV * 0.5).tan()
+    }
+
+    /// La misma cámara girada `roll` radianes alrededor de su dirección de vista
+    pub fn with_roll(mut self, roll: f32) -> Self {
+        self.roll = 0.0;
+        self.roll_by(roll);
+        self
+    }
+
+    /// Suma `amount` radianes al roll (ángulo holandés)
+    pub fn roll_by(&mut self, amount: f32) {
+        self.roll = (self.roll + amount + PI).rem_euclid(2.0 * PI) - PI;
+        self.update_basis_vectors();
+    }
+
+    /// La misma cámara con otra proyección
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Origen y dirección (en el mundo) del rayo que pasa por el punto
+    /// `(screen_x, screen_y)` de la pantalla, ambos de -1 a 1 con y hacia arriba
+    pub fn screen_ray(&self, screen_x: f32, screen_y: f32, aspect_ratio: f32, tan_half_fov: f32) -> (Vector3, Vector3) {
+        match self.projection {
+            Projection::Perspective => {
+                let direction = Vector3::new(
+                    screen_x * aspect_ratio * tan_half_fov,
+                    screen_y * tan_half_fov,
+                    -1.0,
+                ).normalized();
+                (self.eye, self.basis_change(&direction))
+            }
+            Projection::Orthographic { extent } => {
+                let origin = self.eye
+                    + self.right * (screen_x * aspect_ratio * extent)
+                    + self.up * (screen_y * extent);
+                (origin, self.forward)
+            }
+        }
+    }
+
+    /// Inversa de `screen_ray`: punto de la pantalla (de -1 a 1) donde se ve
+    /// `point` y su profundidad a lo largo de `forward`, o `None` si queda
+    /// detrás de la cámara (a menos de `near`)
+    pub fn to_screen(&self, point: Vector3, aspect_ratio: f32, tan_half_fov: f32, near: f32) -> Option<(f32, f32, f32)> {
+        let offset = point - self.eye;
+        let depth = offset.dot(self.forward);
+        if depth <= near {
+            return None;
+        }
+        let (scale_x, scale_y) = match self.projection {
+            Projection::Perspective => (depth * aspect_ratio * tan_half_fov, depth * tan_half_fov),
+            Projection::Orthographic { extent } => (aspect_ratio * extent, extent),
+        };
+        Some((offset.dot(self.right) / scale_x, offset.dot(self.up) / scale_y, depth))
+    }
+
     /// Recalcula los vectores de base ortonormal de la cámara
     fn update_basis_vectors(&mut self) {
         // 1. Calcular dirección forward (de eye hacia center)
         self.forward = (self.center - self.eye).normalized();
-        
-        // 2. Calcular dirección right usando producto cruz
-        // forward × up da un vector perpendicular a ambos (apuntando a la derecha)
-        self.right = self.forward.cross(self.up).normalized();
-        
+
+        // 2. Calcular la derecha nivelada con el mundo usando producto cruz
+        // forward × world_up da un vector perpendicular a ambos (apuntando a la derecha).
+        // Mirando casi derecho hacia arriba o abajo el producto se anula: se
+        // conserva la derecha anterior (sin el roll) para que la imagen no gire de golpe
+        let level = self.forward.cross(self.world_up);
+        let previous = self.level_right - self.forward * self.forward.dot(self.level_right);
+        let level_right = if level.length() > 1e-3 {
+            level.normalized()
+        } else if previous.length() > 1e-3 {
+            previous.normalized()
+        } else {
+            self.forward.cross(Vector3::new(0.0, 0.0, -1.0)).normalized()
+        };
+        self.level_right = level_right;
+
         // 3. Recalcular up para asegurar ortogonalidad perfecta
         // right × forward da un vector perpendicular a ambos
-        self.up = self.right.cross(self.forward);
+        let level_up = level_right.cross(self.forward);
+
+        // 4. Aplicar el roll: girar right y up alrededor de forward
+        let (sin, cos) = self.roll.sin_cos();
+        self.right = level_right * cos + level_up * sin;
+        self.up = level_up * cos - level_right * sin;
+    }
+
+    /// Ángulo de elevación de la cámara sobre el punto central (radianes)
+    pub fn elevation(&self) -> f32 {
+        let relative_pos = self.eye - self.center;
+        (relative_pos.y / relative_pos.length()).asin()
     }
 
     /// Rota la cámara alrededor del punto central (movimiento orbital)
@@ -79,11 +230,52 @@ impl Camera {
         self.update_basis_vectors();
     }
 
+    /// Acerca las velocidades a las que piden las teclas: `yaw`, `pitch`,
+    /// `roll` y `zoom` van de -1 a 1 (0 sin tecla) y `zoom_speed` es el zoom máximo en
+    /// bloques por segundo. Al soltar, la velocidad decae igual hasta parar.
+    pub fn steer(&mut self, yaw: f32, pitch: f32, roll: f32, zoom: f32, zoom_speed: f32, delta_time: f32) {
+        let blend = 1.0 - (-MOTION_RESPONSE * delta_time.min(MAX_MOTION_STEP)).exp();
+        let ease = |velocity: f32, target: f32, max: f32| {
+            let velocity = velocity + (target * max - velocity) * blend;
+            if target == 0.0 && velocity.abs() < max * MOTION_REST { 0.0 } else { velocity }
+        };
+        self.orbit_velocity = (
+            ease(self.orbit_velocity.0, yaw, ORBIT_SPEED),
+            ease(self.orbit_velocity.1, pitch, ORBIT_SPEED),
+        );
+        self.roll_velocity = ease(self.roll_velocity, roll, ROLL_SPEED);
+        self.zoom_velocity = ease(self.zoom_velocity, zoom, zoom_speed);
+    }
+
+    /// Gira la cámara según su velocidad durante `delta_time` segundos y
+    /// devuelve cuánto debería hacer zoom, para que quien la mueve revise
+    /// antes si choca con algo
+    pub fn glide(&mut self, delta_time: f32) -> f32 {
+        let delta_time = delta_time.min(MAX_MOTION_STEP);
+        let (yaw, pitch) = self.orbit_velocity;
+        if yaw != 0.0 || pitch != 0.0 {
+            self.orbit(yaw * delta_time, pitch * delta_time);
+        }
+        if self.roll_velocity != 0.0 {
+            self.roll_by(self.roll_velocity * delta_time);
+        }
+        self.zoom_velocity * delta_time
+    }
+
+    /// Si la cámara todavía se está moviendo por inercia
+    pub fn is_moving(&self) -> bool {
+        self.orbit_velocity != (0.0, 0.0) || self.roll_velocity != 0.0 || self.zoom_velocity != 0.0
+    }
+
     /// Acerca o aleja la cámara del punto central
     /// 
     /// # Argumentos
     /// * `amount` - Cantidad de zoom (positivo = acercar, negativo = alejar)
     pub fn zoom(&mut self, amount: f32) {
+        // En ortográfica acercarse no agranda nada: se achica la extensión
+        if let Projection::Orthographic { extent } = &mut self.projection {
+            *extent = (*extent - amount * 0.5).max(MIN_ORTHOGRAPHIC_EXTENT);
+        }
         let forward = (self.center - self.eye).normalized();
         self.eye = self.eye + forward * amount;
         self.update_basis_vectors();
@@ -0,0 +1,173 @@
+use raylib::prelude::Vector3;
+use std::path::Path;
+
+use crate::camera::{Camera, Projection};
+use crate::material_editor::parse_vec3;
+
+/// Directorio con las cámaras guardadas, un archivo por escena
+pub const BOOKMARK_DIR: &str = "assets/cameras";
+/// Casilleros por escena (teclas 1 a 9)
+pub const BOOKMARK_SLOTS: usize = 9;
+/// Casillero con el que se abre la escena si está guardado
+pub const START_SLOT: usize = 1;
+
+/// Campos leídos de un casillero; sin posición u objetivo se descarta
+#[derive(Clone, Copy)]
+struct BookmarkEntry {
+    position: Option<Vector3>,
+    target: Option<Vector3>,
+    up: Vector3,
+    roll: f32,
+    projection: Projection,
+}
+
+impl Default for BookmarkEntry {
+    fn default() -> Self {
+        BookmarkEntry {
+            position: None,
+            target: None,
+            up: Vector3::new(0.0, 1.0, 0.0),
+            roll: 0.0,
+            projection: Projection::Perspective,
+        }
+    }
+}
+
+/// Cámaras guardadas de una escena en casilleros numerados del 1 al 9
+pub struct CameraBookmarks {
+    path: String,
+    slots: [Option<Camera>; BOOKMARK_SLOTS],
+}
+
+impl CameraBookmarks {
+    /// Cámaras de la escena `scene_num`, o del archivo de escena si se abrió uno
+    pub fn for_scene(scene_num: i32, scene_file: Option<&str>) -> Self {
+        let name = match scene_file {
+            Some(file) => {
+                let stem = Path::new(file).file_stem().and_then(|stem| stem.to_str()).unwrap_or("escena");
+                format!("archivo_{}", stem)
+            }
+            None => format!("escena_{:02}", scene_num),
+        };
+        CameraBookmarks::load(&format!("{}/{}.txt", BOOKMARK_DIR, name))
+    }
+
+    /// Lee el archivo de cámaras; si no existe no hay ninguna guardada. Formato:
+    ///
+    /// ```text
+    /// [1]
+    /// position = 25 0 25
+    /// target = 0 0 0
+    /// up = 0 1 0
+    /// roll = 15
+    /// projection = orthographic 12.5
+    /// ```
+    fn load(path: &str) -> Self {
+        let mut bookmarks = CameraBookmarks { path: path.to_string(), slots: Default::default() };
+        let Ok(text) = std::fs::read_to_string(path) else {
+            return bookmarks;
+        };
+
+        let mut entries = [BookmarkEntry::default(); BOOKMARK_SLOTS];
+        let mut current: Option<usize> = None;
+        for (line_num, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(slot) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                current = slot.trim().parse::<usize>().ok().filter(|slot| (1..=BOOKMARK_SLOTS).contains(slot));
+                if current.is_none() {
+                    println!("  {}:{}: casillero inválido '{}'", path, line_num + 1, line);
+                }
+                continue;
+            }
+
+            let (Some(slot), Some((key, value))) = (current, line.split_once('=')) else {
+                println!("  {}:{}: línea ignorada", path, line_num + 1);
+                continue;
+            };
+
+            let entry = &mut entries[slot - 1];
+            let value = value.trim();
+            let parsed = match key.trim() {
+                "position" => parse_vec3(value).map(|v| entry.position = Some(v)),
+                "target" => parse_vec3(value).map(|v| entry.target = Some(v)),
+                "up" => parse_vec3(value).map(|v| entry.up = v),
+                "roll" => value.parse::<f32>().ok().map(|degrees| entry.roll = degrees.to_radians()),
+                "projection" => parse_projection(value).map(|p| entry.projection = p),
+                _ => None,
+            };
+
+            if parsed.is_none() {
+                println!("  {}:{}: valor inválido '{}'", path, line_num + 1, line);
+            }
+        }
+
+        for (slot, entry) in bookmarks.slots.iter_mut().zip(entries) {
+            if let (Some(position), Some(target)) = (entry.position, entry.target) {
+                *slot = Some(
+                    Camera::new(position, target, entry.up)
+                        .with_projection(entry.projection)
+                        .with_roll(entry.roll),
+                );
+            }
+        }
+        bookmarks
+    }
+
+    /// Cámara del casillero `slot` (de 1 a 9), si hay una guardada
+    pub fn get(&self, slot: usize) -> Option<&Camera> {
+        self.slots.get(slot.checked_sub(1)?)?.as_ref()
+    }
+
+    /// Guarda `camera` en el casillero `slot` y reescribe el archivo de la escena
+    pub fn store(&mut self, slot: usize, camera: &Camera) -> Result<(), String> {
+        let index = slot.checked_sub(1).filter(|&index| index < BOOKMARK_SLOTS)
+            .ok_or_else(|| format!("Casillero de cámara inválido: {}", slot))?;
+        self.slots[index] = Some(camera.clone());
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let mut text = String::from("# Cámaras guardadas (Ctrl+1-9 guarda, Shift+1-9 vuelve a ellas)\n");
+        for (index, camera) in self.slots.iter().enumerate() {
+            let Some(camera) = camera else {
+                continue;
+            };
+            let (p, t, u) = (camera.eye, camera.center, camera.world_up);
+            text.push_str(&format!("\n[{}]\n", index + 1));
+            text.push_str(&format!("position = {} {} {}\n", p.x, p.y, p.z));
+            text.push_str(&format!("target = {} {} {}\n", t.x, t.y, t.z));
+            text.push_str(&format!("up = {} {} {}\n", u.x, u.y, u.z));
+            text.push_str(&format!("roll = {}\n", camera.roll.to_degrees()));
+            match camera.projection {
+                Projection::Perspective => text.push_str("projection = perspective\n"),
+                Projection::Orthographic { extent } => text.push_str(&format!("projection = orthographic {}\n", extent)),
+            }
+        }
+
+        if let Some(dir) = Path::new(&self.path).parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("No se pudo crear directorio {}: {}", dir.display(), e))?;
+        }
+        std::fs::write(&self.path, text).map_err(|e| format!("Error guardando {}: {}", self.path, e))
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+fn parse_projection(value: &str) -> Option<Projection> {
+    let mut parts = value.split_whitespace();
+    match (parts.next()?, parts.next(), parts.next()) {
+        ("perspective", None, None) => Some(Projection::Perspective),
+        ("orthographic", Some(extent), None) => {
+            let extent: f32 = extent.parse().ok()?;
+            (extent > 0.0).then_some(Projection::Orthographic { extent })
+        }
+        _ => None,
+    }
+}
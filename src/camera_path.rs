@@ -0,0 +1,216 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::camera::{Camera, Projection};
+use crate::light::Light;
+use crate::ray_intersect::{RayIntersect, BVH};
+use crate::renderer::{RenderSettings, Renderer, Scene};
+use crate::skybox::Environment;
+
+/// Tamaño (en pixels) de cada frame de la secuencia
+const FRAME_WIDTH: u32 = 640;
+const FRAME_HEIGHT: u32 = 480;
+/// Frames entre dos keyframes seguidos
+const FRAMES_PER_SEGMENT: u32 = 30;
+/// Frames de una vuelta completa del turntable
+const TURNTABLE_FRAMES: u32 = 120;
+/// Puntos de control del círculo del turntable; con estos la spline se
+/// aparta del círculo menos de una milésima del radio
+const TURNTABLE_KEYFRAMES: usize = 16;
+/// Cuadros por segundo del video: el tiempo de la escena (agua, portal,
+/// nubes) avanza esto por frame
+const FRAMES_PER_SECOND: f32 = 30.0;
+
+/// Recorrido de la cámara por keyframes, interpolado con splines de
+/// Catmull-Rom (la posición, el punto mirado y el vector arriba pasan por
+/// cada keyframe sin saltos de velocidad)
+pub struct CameraPath {
+    keyframes: Vec<Camera>,
+    /// Si el último keyframe vuelve al primero (el turntable)
+    closed: bool,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        CameraPath { keyframes: Vec::new(), closed: false }
+    }
+
+    /// Vuelta completa alrededor del punto que mira `camera`, a su misma
+    /// distancia horizontal y altura
+    pub fn turntable(camera: &Camera) -> Self {
+        let offset = camera.eye - camera.center;
+        let radius = (offset.x * offset.x + offset.z * offset.z).sqrt().max(1.0);
+        let start_angle = offset.z.atan2(offset.x);
+
+        let keyframes = (0..TURNTABLE_KEYFRAMES)
+            .map(|i| {
+                let angle = start_angle + i as f32 / TURNTABLE_KEYFRAMES as f32 * 2.0 * PI;
+                let eye = camera.center + Vector3::new(angle.cos() * radius, offset.y, angle.sin() * radius);
+                Camera::new(eye, camera.center, Vector3::new(0.0, 1.0, 0.0)).with_projection(camera.projection)
+            })
+            .collect();
+        CameraPath { keyframes, closed: true }
+    }
+
+    pub fn add_keyframe(&mut self, camera: &Camera) {
+        self.keyframes.push(camera.clone());
+    }
+
+    pub fn clear(&mut self) {
+        self.keyframes.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.keyframes.len()
+    }
+
+    /// Tramos entre keyframes; el recorrido cerrado suma el que vuelve al inicio
+    fn segments(&self) -> usize {
+        if self.closed {
+            self.keyframes.len()
+        } else {
+            self.keyframes.len().saturating_sub(1)
+        }
+    }
+
+    /// Frames de la secuencia completa; el recorrido cerrado no repite el primero
+    pub fn frame_count(&self) -> u32 {
+        if self.closed {
+            TURNTABLE_FRAMES
+        } else {
+            self.segments() as u32 * FRAMES_PER_SEGMENT + 1
+        }
+    }
+
+    /// Keyframe `index`, dando la vuelta en el recorrido cerrado y repitiendo
+    /// los extremos en el abierto
+    fn keyframe(&self, index: isize) -> &Camera {
+        let count = self.keyframes.len() as isize;
+        let index = if self.closed {
+            index.rem_euclid(count)
+        } else {
+            index.clamp(0, count - 1)
+        };
+        &self.keyframes[index as usize]
+    }
+
+    /// Cámara en la fracción `t` (de 0 a 1) del recorrido
+    fn camera_at(&self, t: f32) -> Camera {
+        let segments = self.segments();
+        if segments == 0 {
+            return self.keyframes[0].clone();
+        }
+        let position = t.clamp(0.0, 1.0) * segments as f32;
+        let segment = (position.floor() as usize).min(segments - 1);
+        let local = position - segment as f32;
+
+        let i = segment as isize;
+        let [p0, p1, p2, p3] = [i - 1, i, i + 1, i + 2].map(|index| self.keyframe(index));
+        let spline = |value: fn(&Camera) -> Vector3| {
+            catmull_rom(value(p0), value(p1), value(p2), value(p3), local)
+        };
+        let projection = match (p1.projection, p2.projection) {
+            (Projection::Orthographic { extent: from }, Projection::Orthographic { extent: to }) => {
+                Projection::Orthographic { extent: from + (to - from) * local }
+            }
+            (projection, _) => projection,
+        };
+        Camera::new(spline(|c| c.eye), spline(|c| c.center), spline(|c| c.up)).with_projection(projection)
+    }
+}
+
+/// Velocidad de giro de la demostración (radianes por segundo): una vuelta en ~40 s
+const SHOWCASE_ORBIT_SPEED: f32 = 2.0 * PI / 40.0;
+/// Cuánto sube y baja la cámara alrededor de la elevación inicial (radianes)
+const SHOWCASE_ELEVATION_SWING: f32 = 0.2;
+/// Período de la subida y bajada (segundos)
+const SHOWCASE_ELEVATION_PERIOD: f32 = 25.0;
+/// Segundos que tarda la demostración en llegar a su velocidad
+const SHOWCASE_RAMP: f32 = 2.0;
+/// Elevación máxima (en valor absoluto), lejos de los polos donde `orbit` se traba
+const SHOWCASE_MAX_ELEVATION: f32 = 1.3;
+
+/// Modo demostración: la cámara gira sola alrededor de lo que mira, subiendo y
+/// bajando despacio, para dejar el programa corriendo o grabarlo
+pub struct Showcase {
+    elapsed: f32,
+    /// Elevación alrededor de la cual oscila (la de la cámara al empezar)
+    base_elevation: f32,
+    /// Si además corre el ciclo de día y noche
+    pub day_night: bool,
+}
+
+impl Showcase {
+    pub fn new(camera: &Camera, day_night: bool) -> Self {
+        Showcase { elapsed: 0.0, base_elevation: camera.elevation(), day_night }
+    }
+
+    /// Avanza la demostración `delta_time` segundos; arranca de a poco para no
+    /// dar un tirón al activarla
+    pub fn update(&mut self, camera: &mut Camera, delta_time: f32) {
+        self.elapsed += delta_time;
+        let ramp = (self.elapsed / SHOWCASE_RAMP).min(1.0);
+        let ramp = ramp * ramp * (3.0 - 2.0 * ramp);
+
+        let swing = (self.elapsed / SHOWCASE_ELEVATION_PERIOD * 2.0 * PI).sin();
+        let target = (self.base_elevation + SHOWCASE_ELEVATION_SWING * swing)
+            .clamp(-SHOWCASE_MAX_ELEVATION, SHOWCASE_MAX_ELEVATION);
+        camera.orbit(SHOWCASE_ORBIT_SPEED * ramp * delta_time, (target - camera.elevation()) * ramp);
+    }
+}
+
+/// Punto de la spline de Catmull-Rom uniforme que va de `p1` (t = 0) a `p2`
+/// (t = 1), con `p0` y `p3` como vecinos que fijan las tangentes
+fn catmull_rom(p0: Vector3, p1: Vector3, p2: Vector3, p3: Vector3, t: f32) -> Vector3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+/// Renderiza el recorrido como PNGs numerados (`frame_0000.png`...) en una
+/// carpeta nueva, listos para armar un video. Devuelve la carpeta.
+pub fn render_path(
+    path: &CameraPath,
+    scene_num: i32,
+    bvh: &BVH,
+    objects: &[Arc<dyn RayIntersect + Send + Sync>],
+    lights: &[Light],
+    time_of_day: f32,
+    absolute_time: f32,
+) -> Result<String, String> {
+    if path.segments() == 0 {
+        return Err("El recorrido necesita al menos dos keyframes (F6 agrega la vista actual)".to_string());
+    }
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let dir_path = format!("renders/paths/escena_{}_{}", scene_num, timestamp);
+    std::fs::create_dir_all(&dir_path)
+        .map_err(|e| format!("No se pudo crear directorio {}: {}", dir_path, e))?;
+
+    let settings = RenderSettings::new(FRAME_WIDTH, FRAME_HEIGHT);
+    let frames = path.frame_count();
+    // El recorrido abierto termina justo en el último keyframe; el cerrado
+    // se detiene un frame antes de volver al primero, para que el video haga loop
+    let last = if path.closed { frames } else { frames - 1 };
+    for frame in 0..frames {
+        let camera = path.camera_at(frame as f32 / last as f32);
+        let environment = Environment::new(time_of_day, absolute_time + frame as f32 / FRAMES_PER_SECOND);
+        let renderer = Renderer::new(Scene { bvh, objects, grid: None, chunks: None, lights, environment: &environment });
+
+        let frame_path = format!("{}/frame_{:04}.png", dir_path, frame);
+        renderer
+            .render_frame(&camera, &settings)
+            .save(&frame_path)
+            .map_err(|e| format!("Error guardando {}: {}", frame_path, e))?;
+        println!("  Frame {}/{}", frame + 1, frames);
+    }
+    Ok(dir_path)
+}
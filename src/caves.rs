@@ -0,0 +1,93 @@
+use serde::Deserialize;
+
+use crate::noise::Perlin;
+
+/// Cuánto gira un túnel por paso como máximo, en radianes
+const TUNNEL_TURN: f32 = 0.6;
+/// Inclinación máxima de los túneles: bajan y suben, pero nunca en vertical
+const TUNNEL_MAX_PITCH: f32 = 0.6;
+
+/// Forma de las cuevas que excava `SceneBuilder::add_caves`
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaveParams {
+    pub seed: u32,
+    /// Tamaño aproximado de las cavernas, en bloques
+    pub cave_size: f32,
+    /// Umbral del ruido 3D (0-1) a partir del que se abre una caverna: más
+    /// alto deja menos cavernas; 1 las desactiva y deja solo los túneles
+    pub threshold: f32,
+    /// Cantidad de túneles serpenteantes
+    pub tunnels: u32,
+    /// Largo de cada túnel, en bloques
+    pub tunnel_length: u32,
+    pub tunnel_radius: f32,
+    /// Bloques que se dejan intactos bajo la superficie de cada columna
+    pub surface_margin: i32,
+    /// Probabilidad (0-1) de que un bloque de las paredes sea una veta de glowstone
+    pub glowstone: Option<f32>,
+}
+
+impl CaveParams {
+    /// Algunas cavernas medianas unidas por cuatro túneles, sin glowstone
+    pub fn new(seed: u32) -> Self {
+        CaveParams {
+            seed,
+            cave_size: 8.0,
+            threshold: 0.4,
+            tunnels: 4,
+            tunnel_length: 40,
+            tunnel_radius: 1.5,
+            surface_margin: 2,
+            glowstone: None,
+        }
+    }
+}
+
+impl Default for CaveParams {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Cavernas de ruido 3D y recorridos de túneles para unos `CaveParams`
+pub struct Caves {
+    params: CaveParams,
+    perlin: Perlin,
+}
+
+impl Caves {
+    pub fn new(params: CaveParams) -> Self {
+        Caves {
+            params,
+            perlin: Perlin::new(params.seed),
+        }
+    }
+
+    /// Si la celda (x, y, z) cae dentro de una caverna
+    pub fn is_cavern(&self, x: i32, y: i32, z: i32) -> bool {
+        let size = self.params.cave_size;
+        // Las cavernas se aplastan en Y para que sean más anchas que altas
+        self.perlin.noise3(x as f32 / size, y as f32 / (size * 0.6), z as f32 / size) > self.params.threshold
+    }
+
+    /// Centros de los pasos del túnel número `index` que sale de `start`: un
+    /// gusano que avanza un bloque por paso y gira según el ruido
+    pub fn tunnel_path(&self, index: u32, start: (i32, i32, i32)) -> Vec<(f32, f32, f32)> {
+        let lane = index as f32 * 7.31;
+        let mut yaw = (self.perlin.noise2(lane, 0.5) + 1.0) * std::f32::consts::PI;
+        let mut position = (start.0 as f32, start.1 as f32, start.2 as f32);
+
+        let mut path = Vec::with_capacity(self.params.tunnel_length as usize);
+        for step in 0..self.params.tunnel_length {
+            let t = step as f32 * 0.08;
+            yaw += self.perlin.noise2(t, lane) * TUNNEL_TURN;
+            let pitch = self.perlin.noise2(lane, t + 13.7) * TUNNEL_MAX_PITCH;
+            position.0 += yaw.cos() * pitch.cos();
+            position.1 += pitch.sin();
+            position.2 += yaw.sin() * pitch.cos();
+            path.push(position);
+        }
+        path
+    }
+}
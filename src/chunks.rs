@@ -0,0 +1,178 @@
+use raylib::prelude::Vector3;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::ray_intersect::{AABB, Intersect, RayIntersect, BVH};
+
+/// Lado de un chunk, en bloques
+pub const CHUNK_SIZE: f32 = 16.0;
+
+type SceneObject = Arc<dyn RayIntersect + Send + Sync>;
+
+/// Chunk al que pertenece un objeto: el que contiene el centro de su caja
+fn chunk_key(object: &SceneObject) -> [i32; 3] {
+    let center = object.get_bounds().center();
+    [center.x, center.y, center.z].map(|c| (c / CHUNK_SIZE).floor() as i32)
+}
+
+/// Porción de 16×16×16 bloques del mundo con su propio BVH
+struct Chunk {
+    key: [i32; 3],
+    /// Índices (en la escena) de los objetos del chunk
+    members: Vec<usize>,
+    objects: Vec<SceneObject>,
+    bvh: BVH,
+    bounds: AABB,
+}
+
+impl Chunk {
+    fn new(key: [i32; 3], members: Vec<usize>, objects: &[SceneObject]) -> Self {
+        let mut chunk = Chunk {
+            key,
+            members,
+            objects: Vec::new(),
+            bvh: BVH::build::<SceneObject>(&[]),
+            bounds: AABB::new(Vector3::zero(), Vector3::zero()),
+        };
+        chunk.rebuild(objects);
+        chunk
+    }
+
+    /// Vuelve a tomar los objetos de la escena y reconstruye el BVH del chunk
+    fn rebuild(&mut self, objects: &[SceneObject]) {
+        self.refresh(objects);
+        self.bvh = BVH::build(&self.objects);
+        if let Some(bounds) = self.bvh.bounds() {
+            self.bounds = bounds;
+        }
+    }
+
+    /// Vuelve a tomar los objetos de la escena sin tocar el BVH (sirve mientras
+    /// sus cajas no cambien, por ejemplo si solo cambió el material)
+    fn refresh(&mut self, objects: &[SceneObject]) {
+        self.objects = self.members.iter().map(|&index| objects[index].clone()).collect();
+    }
+}
+
+/// El BVH de nivel superior trata a cada chunk como un objeto más
+impl RayIntersect for Chunk {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        self.bvh.intersect(ray_origin, ray_direction, &self.objects)
+    }
+
+    fn get_bounds(&self) -> AABB {
+        self.bounds
+    }
+}
+
+/// Mundo dividido en chunks de `CHUNK_SIZE`³ bloques, cada uno con su BVH,
+/// bajo un BVH grueso sobre las cajas de los chunks. Al editar objetos solo
+/// se reconstruyen los chunks afectados y el nivel superior, que es chico.
+pub struct ChunkedWorld {
+    chunks: Vec<Chunk>,
+    /// Chunk de cada objeto de la escena, en el mismo orden
+    object_chunks: Vec<[i32; 3]>,
+    top: BVH,
+}
+
+impl ChunkedWorld {
+    pub fn build(objects: &[SceneObject]) -> Self {
+        let object_chunks: Vec<[i32; 3]> = objects.iter().map(chunk_key).collect();
+        let mut members: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+        for (index, key) in object_chunks.iter().enumerate() {
+            members.entry(*key).or_default().push(index);
+        }
+
+        let mut chunks: Vec<Chunk> = members
+            .into_iter()
+            .map(|(key, members)| Chunk::new(key, members, objects))
+            .collect();
+        chunks.sort_by_key(|chunk| chunk.key);
+        let top = BVH::build(&chunks);
+
+        ChunkedWorld { chunks, object_chunks, top }
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Actualiza el mundo después de reemplazar los objetos de la escena por
+    /// otros en el mismo orden, quizás con objetos nuevos al final. `changed`
+    /// son los índices cuyas cajas cambiaron: solo se reconstruyen sus chunks
+    /// (el de antes y el de ahora) y los de los objetos nuevos; los demás
+    /// toman los objetos nuevos sin tocar su BVH. Devuelve los chunks reconstruidos.
+    pub fn update(&mut self, objects: &[SceneObject], changed: &[usize]) -> Result<usize, String> {
+        if objects.len() < self.object_chunks.len() {
+            return Err(format!(
+                "La escena tiene {} objetos y el mundo en chunks {}",
+                objects.len(),
+                self.object_chunks.len()
+            ));
+        }
+
+        let mut dirty = HashSet::new();
+        for (index, object) in objects.iter().enumerate().skip(self.object_chunks.len()) {
+            let key = chunk_key(object);
+            dirty.insert(key);
+            match self.chunks.iter_mut().find(|chunk| chunk.key == key) {
+                Some(chunk) => chunk.members.push(index),
+                None => self.chunks.push(Chunk::new(key, vec![index], objects)),
+            }
+            self.object_chunks.push(key);
+        }
+
+        for &index in changed {
+            let old_key = self.object_chunks[index];
+            let new_key = chunk_key(&objects[index]);
+            dirty.insert(old_key);
+            dirty.insert(new_key);
+            if old_key == new_key {
+                continue;
+            }
+
+            if let Some(chunk) = self.chunks.iter_mut().find(|chunk| chunk.key == old_key) {
+                chunk.members.retain(|&member| member != index);
+            }
+            match self.chunks.iter_mut().find(|chunk| chunk.key == new_key) {
+                Some(chunk) => chunk.members.push(index),
+                None => self.chunks.push(Chunk::new(new_key, vec![index], objects)),
+            }
+            self.object_chunks[index] = new_key;
+        }
+        self.chunks.retain(|chunk| !chunk.members.is_empty());
+
+        let mut rebuilt = 0;
+        for chunk in &mut self.chunks {
+            if dirty.contains(&chunk.key) {
+                chunk.rebuild(objects);
+                rebuilt += 1;
+            } else {
+                chunk.refresh(objects);
+            }
+        }
+        if !dirty.is_empty() {
+            self.chunks.sort_by_key(|chunk| chunk.key);
+            self.top = BVH::build(&self.chunks);
+        }
+        Ok(rebuilt)
+    }
+
+    pub fn intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        self.top.intersect(ray_origin, ray_direction, &self.chunks)
+    }
+
+    pub fn occluded(&self, ray_origin: &Vector3, ray_direction: &Vector3, max_distance: f32) -> bool {
+        self.top.occluded(ray_origin, ray_direction, max_distance, &self.chunks)
+    }
+
+    /// Paquete de rayos coherentes, como `BVH::intersect_packet`
+    pub fn intersect_packet(&self, rays: &[(Vector3, Vector3)]) -> Vec<Intersect> {
+        self.top.intersect_packet(rays, &self.chunks)
+    }
+
+    /// Paquete de rayos de sombra, como `BVH::occluded_packet`
+    pub fn occluded_packet(&self, rays: &[(Vector3, Vector3, f32)]) -> Vec<bool> {
+        self.top.occluded_packet(rays, &self.chunks)
+    }
+}
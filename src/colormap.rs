@@ -0,0 +1,100 @@
+use raylib::prelude::Vector3;
+
+use crate::material::srgb_byte_to_linear;
+use crate::texture::TextureData;
+
+/// Clima (temperatura, humedad; de 0 a 1) de la llanura: el que se usa sin
+/// biomas y con el que vienen pintadas las texturas que ya traen su verde
+pub const PLAINS_CLIMATE: (f32, f32) = (0.8, 0.4);
+
+/// Lado de los colormaps procedurales, como los del juego
+const COLORMAP_SIZE: u32 = 256;
+
+/// Qué colormap tiñe la textura de un material según el bioma
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BiomeTint {
+    Grass,
+    Foliage,
+}
+
+impl BiomeTint {
+    pub const ALL: [BiomeTint; 2] = [BiomeTint::Grass, BiomeTint::Foliage];
+
+    /// Nombre del archivo del colormap (`grass.png`, `foliage.png`)
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            BiomeTint::Grass => "grass",
+            BiomeTint::Foliage => "foliage",
+        }
+    }
+
+    /// Esquinas del colormap procedural: templado y húmedo, templado y seco, y frío
+    fn corners(&self) -> [[u8; 3]; 3] {
+        match self {
+            BiomeTint::Grass => [[0x47, 0xcd, 0x33], [0xbf, 0xb7, 0x55], [0x80, 0xb4, 0x97]],
+            BiomeTint::Foliage => [[0x1a, 0xbf, 0x00], [0xae, 0xa4, 0x2a], [0x60, 0xa1, 0x7b]],
+        }
+    }
+}
+
+/// Tabla de colores por temperatura y humedad, como `colormap/grass.png` de
+/// Minecraft: la temperatura baja de izquierda a derecha y la humedad de
+/// abajo hacia arriba, dentro del triángulo donde la humedad no supera a la
+/// temperatura. Los colores se guardan en espacio lineal.
+pub struct Colormap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vector3>,
+}
+
+impl Colormap {
+    /// Colormap de una imagen sRGB (la de disco o la de un paquete de recursos)
+    pub fn from_texture(texture: &TextureData) -> Result<Self, String> {
+        if texture.width == 0 || texture.height == 0 {
+            return Err("El colormap está vacío".to_string());
+        }
+        let pixels = texture
+            .data
+            .chunks_exact(4)
+            .map(|p| Vector3::new(srgb_byte_to_linear(p[0]), srgb_byte_to_linear(p[1]), srgb_byte_to_linear(p[2])))
+            .collect();
+        Ok(Colormap { width: texture.width, height: texture.height, pixels })
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let img = image::open(path)
+            .map_err(|e| format!("No se pudo abrir colormap {}: {}", path, e))?
+            .to_rgba8();
+        let texture = TextureData { width: img.width(), height: img.height(), data: img.into_raw(), hdr: None, mips: Vec::new() };
+        Self::from_texture(&texture)
+    }
+
+    /// Colormap generado con las tres esquinas del juego, para cuando no hay imagen
+    pub fn procedural(tint: BiomeTint) -> Self {
+        let [lush, dry, cold] = tint.corners().map(|[r, g, b]| {
+            Vector3::new(srgb_byte_to_linear(r), srgb_byte_to_linear(g), srgb_byte_to_linear(b))
+        });
+        let last = (COLORMAP_SIZE - 1) as f32;
+        let pixels = (0..COLORMAP_SIZE * COLORMAP_SIZE)
+            .map(|i| {
+                let temperature = 1.0 - (i % COLORMAP_SIZE) as f32 / last;
+                let humidity = 1.0 - (i / COLORMAP_SIZE) as f32 / last;
+                // Fuera del triángulo se repite el borde, como al muestrear
+                let wetness = if temperature > 0.0 { (humidity / temperature).min(1.0) } else { 0.0 };
+                let warm = dry * (1.0 - wetness) + lush * wetness;
+                cold * (1.0 - temperature) + warm * temperature
+            })
+            .collect();
+        Colormap { width: COLORMAP_SIZE, height: COLORMAP_SIZE, pixels }
+    }
+
+    /// Color (lineal) para una temperatura y humedad de 0 a 1; la humedad se
+    /// escala por la temperatura, como en el juego
+    pub fn sample(&self, temperature: f32, humidity: f32) -> Vector3 {
+        let temperature = temperature.clamp(0.0, 1.0);
+        let humidity = humidity.clamp(0.0, 1.0) * temperature;
+        let x = ((1.0 - temperature) * (self.width - 1) as f32).round() as u32;
+        let y = ((1.0 - humidity) * (self.height - 1) as f32).round() as u32;
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
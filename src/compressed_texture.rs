@@ -0,0 +1,439 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::texture::{MipLevel, TextureData};
+
+/// Extensiones de los contenedores de texturas comprimidas
+pub const COMPRESSED_EXTENSIONS: [&str; 2] = ["dds", "ktx2"];
+
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+/// Tamaño de la cabecera DDS (sin la firma) y de la extensión DX10
+const DDS_HEADER_SIZE: usize = 124;
+const DDS_DX10_HEADER_SIZE: usize = 20;
+/// El formato del píxel viene como FourCC y no como máscaras
+const DDPF_FOURCC: u32 = 0x4;
+/// El canal alfa de las máscaras es válido
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+/// La cabecera trae la cantidad de niveles de mipmap
+const DDSD_MIPMAPCOUNT: u32 = 0x2_0000;
+
+const KTX2_MAGIC: [u8; 12] = [0xab, 0x4b, 0x54, 0x58, 0x20, 0x32, 0x30, 0xbb, 0x0d, 0x0a, 0x1a, 0x0a];
+/// Cabecera e índice de un KTX2 hasta el primer nivel (el más grande)
+const KTX2_HEADER_SIZE: usize = 104;
+/// Comienzo y tamaño de cada entrada del índice de niveles de un KTX2
+const KTX2_LEVEL_INDEX: usize = 80;
+const KTX2_LEVEL_ENTRY_SIZE: usize = 24;
+/// Lado máximo aceptado; un archivo dañado no puede pedir más memoria que esto
+const MAX_TEXTURE_SIDE: u32 = 16384;
+
+/// Cómo vienen los texels de los niveles
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PixelFormat {
+    Rgba8,
+    Bgra8,
+    Rgb8,
+    Bgr8,
+    /// BC1 (DXT1): bloques de 4x4 en 8 bytes, alfa de un bit
+    Bc1,
+    /// BC2 (DXT3): alfa explícito de 4 bits más un bloque BC1
+    Bc2,
+    /// BC3 (DXT5): alfa interpolado más un bloque BC1
+    Bc3,
+}
+
+impl PixelFormat {
+    /// Bytes que ocupa una imagen de ese tamaño; error si el tamaño no es
+    /// válido o el cálculo se desborda
+    fn image_size(&self, width: u32, height: u32) -> Result<usize, String> {
+        check_size(width, height)?;
+        let (width, height) = (width as usize, height as usize);
+        let (units, unit_size) = match self {
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => (width.checked_mul(height), 4),
+            PixelFormat::Rgb8 | PixelFormat::Bgr8 => (width.checked_mul(height), 3),
+            PixelFormat::Bc1 => (width.div_ceil(4).checked_mul(height.div_ceil(4)), 8),
+            PixelFormat::Bc2 | PixelFormat::Bc3 => (width.div_ceil(4).checked_mul(height.div_ceil(4)), 16),
+        };
+        units
+            .and_then(|units| units.checked_mul(unit_size))
+            .ok_or_else(|| format!("imagen de {}x{} demasiado grande", width, height))
+    }
+
+    /// Formato DXGI de la extensión DX10 (las variantes sRGB se leen igual)
+    fn from_dxgi(format: u32) -> Result<Self, String> {
+        match format {
+            28 | 29 => Ok(PixelFormat::Rgba8),
+            87 | 91 => Ok(PixelFormat::Bgra8),
+            71 | 72 => Ok(PixelFormat::Bc1),
+            74 | 75 => Ok(PixelFormat::Bc2),
+            77 | 78 => Ok(PixelFormat::Bc3),
+            _ => Err(format!("formato DXGI {} no soportado", format)),
+        }
+    }
+
+    /// Formato de Vulkan de un KTX2
+    fn from_vulkan(format: u32) -> Result<Self, String> {
+        match format {
+            37 | 43 => Ok(PixelFormat::Rgba8),
+            44 | 50 => Ok(PixelFormat::Bgra8),
+            23 | 29 => Ok(PixelFormat::Rgb8),
+            30 | 36 => Ok(PixelFormat::Bgr8),
+            131..=134 => Ok(PixelFormat::Bc1),
+            135 | 136 => Ok(PixelFormat::Bc2),
+            137 | 138 => Ok(PixelFormat::Bc3),
+            0 => Err("formato sin definir (Basis Universal) no soportado".to_string()),
+            _ => Err(format!("formato Vulkan {} no soportado", format)),
+        }
+    }
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Rechaza lados nulos o mayores que `MAX_TEXTURE_SIDE`
+fn check_size(width: u32, height: u32) -> Result<(), String> {
+    if width == 0 || height == 0 || width > MAX_TEXTURE_SIDE || height > MAX_TEXTURE_SIDE {
+        return Err(format!("tamaño {}x{} inválido (máximo {} por lado)", width, height, MAX_TEXTURE_SIDE));
+    }
+    Ok(())
+}
+
+/// Cantidad de niveles que dice el archivo, entre 1 y los que hay hasta
+/// llegar a 1×1 (un archivo dañado no puede pedir más)
+fn level_count(declared: u32, width: u32, height: u32) -> u32 {
+    let max_levels = 32 - width.max(height).leading_zeros();
+    declared.clamp(1, max_levels)
+}
+
+/// Tamaño del nivel `level` de una imagen de `width`×`height`
+fn level_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Lee `len` bytes; antes de reservar memoria comprueba que el archivo los tenga
+fn read_exact(file: &mut File, len: usize) -> Result<Vec<u8>, String> {
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    let position = file.stream_position().map_err(|e| e.to_string())?;
+    let remaining = size.saturating_sub(position);
+    if len as u64 > remaining {
+        return Err(format!("archivo incompleto: faltan {} bytes", len as u64 - remaining));
+    }
+    let mut bytes = vec![0; len];
+    file.read_exact(&mut bytes).map_err(|e| format!("archivo incompleto: {}", e))?;
+    Ok(bytes)
+}
+
+/// Lee una textura `.dds` o `.ktx2` con toda la cadena de mipmaps que trae
+/// el archivo, sin volver a generarla: el nivel más grande queda como la
+/// imagen de la textura y los demás en `mips`
+pub fn load(path: &str) -> Result<TextureData, String> {
+    let mut file = File::open(path).map_err(|e| format!("No se pudo abrir {}: {}", path, e))?;
+    let is_ktx2 = Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ktx2"));
+    let levels = if is_ktx2 { read_ktx2(&mut file) } else { read_dds(&mut file) };
+    let (format, levels) = levels.map_err(|e| format!("{}: {}", path, e))?;
+    let mut levels = levels
+        .into_iter()
+        .map(|(width, height, bytes)| MipLevel { width, height, data: decode(format, width, height, &bytes) });
+    let top = levels.next().ok_or_else(|| format!("{}: no tiene imágenes", path))?;
+    Ok(TextureData { width: top.width, height: top.height, data: top.data, hdr: None, mips: levels.collect() })
+}
+
+/// Tamaño y bytes de cada nivel de una textura, del más grande al más chico
+type Levels = Vec<(u32, u32, Vec<u8>)>;
+
+/// Formato y niveles de un DDS, que vienen uno detrás del otro
+fn read_dds(file: &mut File) -> Result<(PixelFormat, Levels), String> {
+    let header = read_exact(file, 4 + DDS_HEADER_SIZE)?;
+    if &header[..4] != DDS_MAGIC {
+        return Err("no es un archivo DDS".to_string());
+    }
+    let header = &header[4..];
+    if u32_at(header, 0) as usize != DDS_HEADER_SIZE {
+        return Err("cabecera DDS inválida".to_string());
+    }
+    let (height, width) = (u32_at(header, 8), u32_at(header, 12));
+    let declared_levels = if u32_at(header, 4) & DDSD_MIPMAPCOUNT != 0 { u32_at(header, 24) } else { 1 };
+    let (flags, fourcc) = (u32_at(header, 76), &header[80..84]);
+
+    let format = if flags & DDPF_FOURCC == 0 {
+        let bits = u32_at(header, 84);
+        let masks = [u32_at(header, 88), u32_at(header, 92), u32_at(header, 96)];
+        let alpha = flags & DDPF_ALPHAPIXELS != 0;
+        match (bits, masks, alpha) {
+            (32, [0xff, 0xff00, 0xff_0000], true) => PixelFormat::Rgba8,
+            (32, [0xff_0000, 0xff00, 0xff], true) => PixelFormat::Bgra8,
+            (24, [0xff, 0xff00, 0xff_0000], false) => PixelFormat::Rgb8,
+            (24, [0xff_0000, 0xff00, 0xff], false) => PixelFormat::Bgr8,
+            _ => return Err(format!("formato DDS sin comprimir de {} bits no soportado", bits)),
+        }
+    } else {
+        match fourcc {
+            b"DXT1" => PixelFormat::Bc1,
+            b"DXT2" | b"DXT3" => PixelFormat::Bc2,
+            b"DXT4" | b"DXT5" => PixelFormat::Bc3,
+            b"DX10" => PixelFormat::from_dxgi(u32_at(&read_exact(file, DDS_DX10_HEADER_SIZE)?, 0))?,
+            _ => return Err(format!("FourCC {} no soportado", String::from_utf8_lossy(fourcc))),
+        }
+    };
+    check_size(width, height)?;
+    let mut levels = Vec::new();
+    for level in 0..level_count(declared_levels, width, height) {
+        let (width, height) = level_size(width, height, level);
+        levels.push((width, height, read_exact(file, format.image_size(width, height)?)?));
+    }
+    Ok((format, levels))
+}
+
+/// Formato y niveles de un KTX2, que se ubican con el índice de niveles
+fn read_ktx2(file: &mut File) -> Result<(PixelFormat, Levels), String> {
+    let header = read_exact(file, KTX2_HEADER_SIZE)?;
+    if header[..12] != KTX2_MAGIC {
+        return Err("no es un archivo KTX2".to_string());
+    }
+    let format = PixelFormat::from_vulkan(u32_at(&header, 12))?;
+    // Las texturas 1D guardan alto 0
+    let (width, height) = (u32_at(&header, 20), u32_at(&header, 24).max(1));
+    if u32_at(&header, 44) != 0 {
+        return Err("la supercompresión (Basis, Zstandard) no está soportada".to_string());
+    }
+    check_size(width, height)?;
+    // El índice de niveles va del más grande al más chico (sus bytes, al
+    // revés); la cabecera ya trae la entrada del primero
+    let count = level_count(u32_at(&header, 40), width, height);
+    let mut index = header[KTX2_LEVEL_INDEX..].to_vec();
+    index.extend(read_exact(file, (count as usize - 1) * KTX2_LEVEL_ENTRY_SIZE)?);
+
+    let mut levels = Vec::new();
+    for (level, entry) in index.chunks_exact(KTX2_LEVEL_ENTRY_SIZE).enumerate() {
+        let (width, height) = level_size(width, height, level as u32);
+        let (offset, length) = (u64_at(entry, 0), u64_at(entry, 8));
+        let image_size = format.image_size(width, height)?;
+        if length < image_size as u64 {
+            return Err(format!("el nivel {} es más chico que la imagen", level));
+        }
+        file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+        levels.push((width, height, read_exact(file, image_size)?));
+    }
+    Ok((format, levels))
+}
+
+/// Texels RGBA de una imagen en ese formato
+fn decode(format: PixelFormat, width: u32, height: u32, bytes: &[u8]) -> Vec<u8> {
+    match format {
+        PixelFormat::Rgba8 => bytes.to_vec(),
+        PixelFormat::Bgra8 => bytes.chunks_exact(4).flat_map(|p| [p[2], p[1], p[0], p[3]]).collect(),
+        PixelFormat::Rgb8 => bytes.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        PixelFormat::Bgr8 => bytes.chunks_exact(3).flat_map(|p| [p[2], p[1], p[0], 255]).collect(),
+        PixelFormat::Bc1 | PixelFormat::Bc2 | PixelFormat::Bc3 => decode_blocks(format, width, height, bytes),
+    }
+}
+
+/// Descomprime los bloques de 4x4 de BC1, BC2 o BC3; los de los bordes se
+/// recortan si el lado no es múltiplo de 4
+fn decode_blocks(format: PixelFormat, width: u32, height: u32, bytes: &[u8]) -> Vec<u8> {
+    let block_size = if format == PixelFormat::Bc1 { 8 } else { 16 };
+    let blocks_wide = width.div_ceil(4) as usize;
+    let mut data = vec![0; width as usize * height as usize * 4];
+    for (index, block) in bytes.chunks_exact(block_size).enumerate() {
+        let texels = match format {
+            PixelFormat::Bc2 => with_alpha(color_block(&block[8..], false), explicit_alpha(&block[..8])),
+            PixelFormat::Bc3 => with_alpha(color_block(&block[8..], false), interpolated_alpha(&block[..8])),
+            _ => color_block(block, true),
+        };
+        let (bx, by) = ((index % blocks_wide) * 4, (index / blocks_wide) * 4);
+        for (i, texel) in texels.iter().enumerate() {
+            let (x, y) = (bx + i % 4, by + i / 4);
+            if x < width as usize && y < height as usize {
+                let start = (y * width as usize + x) * 4;
+                data[start..start + 4].copy_from_slice(texel);
+            }
+        }
+    }
+    data
+}
+
+fn rgb565(color: u16) -> [u8; 3] {
+    let expand = |value: u16, bits: u32| ((value as u32 * 255 + ((1 << bits) - 1) / 2) / ((1 << bits) - 1)) as u8;
+    [expand(color >> 11, 5), expand((color >> 5) & 0x3f, 6), expand(color & 0x1f, 5)]
+}
+
+/// Los 16 texels de un bloque de color BC1. Con `punch_through` (solo BC1)
+/// el orden de los dos extremos elige el modo de tres colores más transparente.
+fn color_block(block: &[u8], punch_through: bool) -> [[u8; 4]; 16] {
+    let (c0, c1) = (u16::from_le_bytes([block[0], block[1]]), u16::from_le_bytes([block[2], block[3]]));
+    let (a, b) = (rgb565(c0), rgb565(c1));
+    let mix = |wa: u32, wb: u32| {
+        let channel = |i: usize| ((a[i] as u32 * wa + b[i] as u32 * wb) / (wa + wb)) as u8;
+        [channel(0), channel(1), channel(2), 255]
+    };
+    let palette = if c0 > c1 || !punch_through {
+        [[a[0], a[1], a[2], 255], [b[0], b[1], b[2], 255], mix(2, 1), mix(1, 2)]
+    } else {
+        [[a[0], a[1], a[2], 255], [b[0], b[1], b[2], 255], mix(1, 1), [0, 0, 0, 0]]
+    };
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|i| palette[((indices >> (2 * i)) & 0b11) as usize])
+}
+
+/// Alfa de 4 bits por texel de BC2
+fn explicit_alpha(block: &[u8]) -> [u8; 16] {
+    let bits = u64::from_le_bytes(block[..8].try_into().unwrap());
+    std::array::from_fn(|i| ((bits >> (4 * i)) & 0xf) as u8 * 17)
+}
+
+/// Alfa de BC3: dos extremos y un índice de 3 bits por texel
+fn interpolated_alpha(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let palette: [u8; 8] = std::array::from_fn(|i| {
+        let i = i as u32;
+        match i {
+            0 => a0 as u8,
+            1 => a1 as u8,
+            _ if a0 > a1 => (((8 - i) * a0 + (i - 1) * a1) / 7) as u8,
+            6 => 0,
+            7 => 255,
+            _ => (((6 - i) * a0 + (i - 1) * a1) / 5) as u8,
+        }
+    });
+    let mut bits = [0; 8];
+    bits[..6].copy_from_slice(&block[2..8]);
+    let indices = u64::from_le_bytes(bits);
+    std::array::from_fn(|i| palette[((indices >> (3 * i)) & 0b111) as usize])
+}
+
+fn with_alpha(mut texels: [[u8; 4]; 16], alpha: [u8; 16]) -> [[u8; 4]; 16] {
+    for (texel, alpha) in texels.iter_mut().zip(alpha) {
+        texel[3] = alpha;
+    }
+    texels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DDS sin comprimir en RGBA de `width`x`height` con `pixels` detrás de la cabecera
+    fn dds(width: u32, height: u32, pixels: &[u8]) -> Vec<u8> {
+        dds_with_mips(width, height, 1, pixels)
+    }
+
+    /// Igual que `dds`, declarando `levels` niveles de mipmap
+    fn dds_with_mips(width: u32, height: u32, levels: u32, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = DDS_MAGIC.to_vec();
+        let mut header = [0u8; DDS_HEADER_SIZE];
+        let mut put = |offset: usize, value: u32| header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        put(0, DDS_HEADER_SIZE as u32);
+        put(4, DDSD_MIPMAPCOUNT);
+        put(8, height);
+        put(12, width);
+        put(24, levels);
+        put(76, DDPF_ALPHAPIXELS);
+        put(84, 32);
+        put(88, 0xff);
+        put(92, 0xff00);
+        put(96, 0xff_0000);
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(pixels);
+        bytes
+    }
+
+    /// Escribe `bytes` en un archivo temporal y lo carga
+    fn load_bytes(name: &str, bytes: &[u8]) -> Result<TextureData, String> {
+        let path = std::env::temp_dir().join(format!("proyecto2_{}_{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        let result = load(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn reads_uncompressed_dds() {
+        let pixels: Vec<u8> = (0..16).collect();
+        let texture = load_bytes("ok.dds", &dds(2, 2, &pixels)).unwrap();
+        assert_eq!((texture.width, texture.height), (2, 2));
+        assert_eq!(texture.data, pixels);
+    }
+
+    #[test]
+    fn reads_dds_mip_chain() {
+        // 4x2, 2x1 y 1x1: 8 + 2 + 1 texels
+        let pixels: Vec<u8> = (0..44).collect();
+        let texture = load_bytes("mips.dds", &dds_with_mips(4, 2, 3, &pixels)).unwrap();
+        assert_eq!((texture.width, texture.height), (4, 2));
+        assert_eq!(texture.data, pixels[..32]);
+        let sizes: Vec<_> = texture.mips.iter().map(|mip| (mip.width, mip.height)).collect();
+        assert_eq!(sizes, [(2, 1), (1, 1)]);
+        assert_eq!(texture.mips[1].data, pixels[40..]);
+        // Más niveles de los que caben hasta 1x1 se recortan
+        let pixels = [0; 44];
+        assert_eq!(load_bytes("extra_mips.dds", &dds_with_mips(4, 2, 12, &pixels)).unwrap().mips.len(), 2);
+    }
+
+    #[test]
+    fn reads_ktx2_mip_chain() {
+        let mut bytes = KTX2_MAGIC.to_vec();
+        bytes.resize(KTX2_HEADER_SIZE + KTX2_LEVEL_ENTRY_SIZE, 0);
+        let mut put = |offset: usize, value: u64, size: usize| bytes[offset..offset + size].copy_from_slice(&value.to_le_bytes()[..size]);
+        put(12, 37, 4);
+        put(20, 2, 4);
+        put(24, 2, 4);
+        put(40, 2, 4);
+        // Los bytes del nivel chico van primero
+        let data_start = (KTX2_HEADER_SIZE + KTX2_LEVEL_ENTRY_SIZE) as u64;
+        put(KTX2_LEVEL_INDEX, data_start + 4, 8);
+        put(KTX2_LEVEL_INDEX + 8, 16, 8);
+        put(KTX2_LEVEL_INDEX + KTX2_LEVEL_ENTRY_SIZE, data_start, 8);
+        put(KTX2_LEVEL_INDEX + KTX2_LEVEL_ENTRY_SIZE + 8, 4, 8);
+        bytes.extend([9, 9, 9, 9]);
+        bytes.extend(0..16);
+        let texture = load_bytes("mips.ktx2", &bytes).unwrap();
+        assert_eq!(texture.data, (0..16).collect::<Vec<u8>>());
+        assert_eq!(texture.mips.len(), 1);
+        assert_eq!(texture.mips[0].data, [9, 9, 9, 9]);
+    }
+
+    #[test]
+    fn decodes_bc1_block() {
+        // Extremos rojo y azul puros; índices 0 y 1 alternados
+        let block = [0x00, 0xf8, 0x1f, 0x00, 0x44, 0x44, 0x44, 0x44];
+        let data = decode(PixelFormat::Bc1, 4, 4, &block);
+        assert_eq!(&data[..8], &[255, 0, 0, 255, 0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let error = load_bytes("truncated.dds", &dds(4, 4, &[0; 10])).err().expect("archivo truncado aceptado");
+        assert!(error.contains("incompleto"), "{}", error);
+        let header = dds(4, 4, &[]);
+        assert!(load_bytes("short_header.dds", &header[..64]).is_err());
+    }
+
+    #[test]
+    fn rejects_zero_size() {
+        assert!(load_bytes("zero_width.dds", &dds(0, 4, &[])).is_err());
+        assert!(load_bytes("zero_height.dds", &dds(4, 0, &[])).is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_header() {
+        let error = load_bytes("huge.dds", &dds(u32::MAX, u32::MAX, &[0; 64])).err().expect("cabecera enorme aceptada");
+        assert!(error.contains("inválido"), "{}", error);
+        assert!(PixelFormat::Bc3.image_size(MAX_TEXTURE_SIDE + 1, 4).is_err());
+        assert_eq!(PixelFormat::Bc1.image_size(5, 5), Ok(4 * 8));
+    }
+
+    #[test]
+    fn rejects_oversized_ktx2() {
+        let mut bytes = KTX2_MAGIC.to_vec();
+        bytes.resize(KTX2_HEADER_SIZE, 0);
+        bytes[12..16].copy_from_slice(&37u32.to_le_bytes());
+        bytes[20..24].copy_from_slice(&100_000u32.to_le_bytes());
+        bytes[24..28].copy_from_slice(&100_000u32.to_le_bytes());
+        assert!(load_bytes("huge.ktx2", &bytes).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+use crate::material::Material;
+
+/// Cono vertical con la base tapada y la punta hacia arriba, apoyado sobre
+/// el centro de su base
+pub struct Cone {
+    pub base: Vector3,
+    pub radius: f32,
+    pub height: f32,
+    pub material: Arc<Material>,
+}
+
+impl Cone {
+    pub fn new(base: Vector3, radius: f32, height: f32, material: Arc<Material>) -> Self {
+        Cone {
+            base,
+            radius,
+            height,
+            material,
+        }
+    }
+
+    /// Impacto con el costado: u da la vuelta, v baja de la punta a la base
+    #[inline]
+    fn side_hit(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, Vector3, f32, f32)> {
+        // Radio que crece `slope` por cada unidad que se baja desde la punta
+        let slope = self.radius / self.height;
+        let slope2 = slope * slope;
+        let (ox, oz) = (ray_origin.x - self.base.x, ray_origin.z - self.base.z);
+        let below_apex = self.base.y + self.height - ray_origin.y;
+
+        let a = ray_direction.x * ray_direction.x + ray_direction.z * ray_direction.z - slope2 * ray_direction.y * ray_direction.y;
+        let half_b = ox * ray_direction.x + oz * ray_direction.z + slope2 * below_apex * ray_direction.y;
+        let c = ox * ox + oz * oz - slope2 * below_apex * below_apex;
+
+        // Rayo paralelo al costado: la ecuación queda lineal
+        let roots = if a.abs() < 1e-8 {
+            if half_b.abs() < 1e-8 {
+                return None;
+            }
+            [-c / (2.0 * half_b), f32::NAN]
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let sqrt_d = discriminant.sqrt();
+            let (t0, t1) = ((-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a);
+            [t0.min(t1), t0.max(t1)]
+        };
+
+        roots.into_iter().filter(|&t| t > 1e-4).find_map(|t| {
+            // La ecuación también incluye el cono reflejado sobre la punta
+            let y = ray_origin.y + ray_direction.y * t - self.base.y;
+            if !(0.0..=self.height).contains(&y) {
+                return None;
+            }
+            let (x, z) = (ox + ray_direction.x * t, oz + ray_direction.z * t);
+            let distance = (x * x + z * z).sqrt();
+            let normal = if distance > 1e-6 {
+                Vector3::new(x / distance, slope, z / distance).normalized()
+            } else {
+                Vector3::new(0.0, 1.0, 0.0)
+            };
+            let u = 0.5 + z.atan2(x) / (2.0 * PI);
+            Some((t, normal, u, y / self.height))
+        })
+    }
+
+    /// Impacto con la base, con mapeo plano
+    #[inline]
+    fn base_hit(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, Vector3, f32, f32)> {
+        if ray_direction.y.abs() < 1e-8 {
+            return None;
+        }
+        let t = (self.base.y - ray_origin.y) / ray_direction.y;
+        if t <= 1e-4 {
+            return None;
+        }
+        let (x, z) = (ray_origin.x + ray_direction.x * t - self.base.x, ray_origin.z + ray_direction.z * t - self.base.z);
+        if x * x + z * z > self.radius * self.radius {
+            return None;
+        }
+        Some((t, Vector3::new(0.0, -1.0, 0.0), 0.5 + x / (2.0 * self.radius), 0.5 + z / (2.0 * self.radius)))
+    }
+}
+
+impl RayIntersect for Cone {
+    #[inline]
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let hit = [self.side_hit(ray_origin, ray_direction), self.base_hit(ray_origin, ray_direction)]
+            .into_iter()
+            .flatten()
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        match hit {
+            Some((t, normal, u, v)) => {
+                let point = *ray_origin + *ray_direction * t;
+                Intersect::new(point, normal, t, Arc::clone(&self.material), u, v)
+            }
+            None => Intersect::empty(),
+        }
+    }
+
+    fn get_bounds(&self) -> AABB {
+        let r = Vector3::new(self.radius, 0.0, self.radius);
+        AABB::new(self.base - r, self.base + r + Vector3::new(0.0, self.height, 0.0))
+    }
+}
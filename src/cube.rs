@@ -1,12 +1,44 @@
 use raylib::prelude::Vector3;
+use std::sync::Arc;
+
 use crate::ray_intersect::{Intersect, RayIntersect, AABB};
-use crate::material::Material;
+use crate::material::{Material, UvTransform};
+
+/// Materiales de las caras de un bloque que no es igual por todos lados,
+/// como el pasto: verde arriba, tierra abajo y con el borde verde a los costados
+#[derive(PartialEq)]
+pub struct BlockFaces {
+    pub top: Arc<Material>,
+    pub side: Arc<Material>,
+    pub bottom: Arc<Material>,
+}
+
+impl BlockFaces {
+    pub fn new(top: Arc<Material>, side: Arc<Material>, bottom: Arc<Material>) -> Self {
+        BlockFaces { top, side, bottom }
+    }
+
+    /// Material de la cara con esa normal
+    #[inline]
+    pub fn for_normal(&self, normal: &Vector3) -> &Arc<Material> {
+        if normal.y > 0.9 {
+            &self.top
+        } else if normal.y < -0.9 {
+            &self.bottom
+        } else {
+            &self.side
+        }
+    }
+}
 
 /// Cubo alineado a los ejes con cache optimizado
 pub struct Cube {
     pub center: Vector3,
     pub size: f32,
-    pub material: Material,
+    /// Material del cubo; en los bloques con caras distintas es el de arriba
+    /// (el que usa el backend de GPU, que no distingue caras)
+    pub material: Arc<Material>,
+    pub faces: Option<Arc<BlockFaces>>,
     min: Vector3,
     max: Vector3,
     half_size: f32,
@@ -14,7 +46,7 @@ pub struct Cube {
 }
 
 impl Cube {
-    pub fn new(center: Vector3, size: f32, material: Material) -> Self {
+    pub fn new(center: Vector3, size: f32, material: Arc<Material>) -> Self {
         let half_size = size / 2.0;
         let min = center - Vector3::new(half_size, half_size, half_size);
         let max = center + Vector3::new(half_size, half_size, half_size);
@@ -23,6 +55,7 @@ impl Cube {
             center,
             size,
             material,
+            faces: None,
             min,
             max,
             half_size,
@@ -30,19 +63,168 @@ impl Cube {
         }
     }
 
+    /// Cubo con un material por cara (arriba, costados y abajo)
+    pub fn with_faces(center: Vector3, size: f32, faces: Arc<BlockFaces>) -> Self {
+        Cube {
+            faces: Some(faces.clone()),
+            ..Cube::new(center, size, faces.top.clone())
+        }
+    }
+
+    /// Material de la cara con esa normal
     #[inline]
-    fn get_uv(&self, point: &Vector3, normal: &Vector3) -> (f32, f32) {
-        let local = (*point - self.center) * self.inv_size;
+    fn face_material(&self, normal: &Vector3) -> &Arc<Material> {
+        match &self.faces {
+            Some(faces) => faces.for_normal(normal),
+            None => &self.material,
+        }
+    }
+}
 
-        let (u, v) = if normal.x.abs() > 0.9 {
-            ((local.z + 1.0) * 0.5, (local.y + 1.0) * 0.5)
-        } else if normal.y.abs() > 0.9 {
-            ((local.x + 1.0) * 0.5, (local.z + 1.0) * 0.5)
-        } else {
-            ((local.x + 1.0) * 0.5, (local.y + 1.0) * 0.5)
-        };
+/// UV de un punto sobre una cara de la celda de `center` (de lado
+/// `1 / inv_size`), con el giro/espejo fijo por bloque si el material lo pide.
+/// Lo comparten los cubos y las piezas de bloque para que las texturas coincidan.
+/// Devuelve también el giro/espejo usado (el neutro si no hubo).
+#[inline]
+pub fn block_face_uv(center: &Vector3, inv_size: f32, point: &Vector3, normal: &Vector3, material: &Material) -> (f32, f32, UvTransform) {
+    // De -0.5 a 0.5 sobre la cara: la textura entera la cubre, bordes incluidos
+    let local = (*point - *center) * inv_size;
+
+    // En los costados la fila 0 de la textura va arriba, para que
+    // texturas como grass_side queden con el pasto en el borde de arriba
+    let (u, v) = if normal.x.abs() > 0.9 {
+        (local.z + 0.5, 0.5 - local.y)
+    } else if normal.y.abs() > 0.9 {
+        (local.x + 0.5, local.z + 0.5)
+    } else {
+        (local.x + 0.5, 0.5 - local.y)
+    };
+
+    let (u, v) = (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+
+    if material.uv_variation {
+        let transform = face_transform(center, normal);
+        let (u, v) = transform.apply(u, v);
+        (u, v, transform)
+    } else {
+        (u, v, UvTransform::default())
+    }
+}
 
-        (u.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+/// Direcciones en que crecen u y v sobre una cara con esa normal, según las
+/// mismas reglas que `block_face_uv` (sin el giro/espejo), ajustadas para
+/// quedar perpendiculares a la normal cuando la cara no es de un cubo
+pub fn block_face_axes(normal: &Vector3) -> (Vector3, Vector3) {
+    let (u_axis, v_axis) = if normal.x.abs() > 0.9 {
+        (Vector3::new(0.0, 0.0, 1.0), Vector3::new(0.0, -1.0, 0.0))
+    } else if normal.y.abs() > 0.9 {
+        (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0))
+    } else {
+        (Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, -1.0, 0.0))
+    };
+    let tangent = (u_axis - *normal * normal.dot(u_axis)).normalized();
+    let bitangent = v_axis - *normal * normal.dot(v_axis);
+    let bitangent = (bitangent - tangent * tangent.dot(bitangent)).normalized();
+    (tangent, bitangent)
+}
+
+/// Qué tanto se afila la mezcla tri-planar hacia el eje dominante
+const TRIPLANAR_SHARPNESS: i32 = 4;
+
+/// Proyección tri-planar de un punto: la UV que le toca desde cada eje (con
+/// las mismas orientaciones que las caras de un cubo) y el peso de cada una
+/// según cuánto mira la normal hacia ese eje
+pub struct TriplanarProjection {
+    uvs: [(f32, f32); 3],
+    weights: [f32; 3],
+    /// Normal de la cara de cubo que mira hacia cada eje, del lado del punto
+    axes: [Vector3; 3],
+}
+
+impl TriplanarProjection {
+    pub fn new(point: Vector3, normal: Vector3, scale: f32) -> Self {
+        let p = point / scale.max(1e-3);
+        let wrap = |value: f32| (value + 0.5).rem_euclid(1.0);
+        let weights = [normal.x, normal.y, normal.z].map(|n| n.abs().powi(TRIPLANAR_SHARPNESS));
+        let total: f32 = weights.iter().sum();
+        TriplanarProjection {
+            uvs: [(wrap(p.z), wrap(-p.y)), (wrap(p.x), wrap(p.z)), (wrap(p.x), wrap(-p.y))],
+            weights: weights.map(|weight| weight / total.max(1e-6)),
+            axes: [
+                Vector3::new(normal.x.signum(), 0.0, 0.0),
+                Vector3::new(0.0, normal.y.signum(), 0.0),
+                Vector3::new(0.0, 0.0, normal.z.signum()),
+            ],
+        }
+    }
+
+    /// Muestras de los ejes que pesan, mezcladas; `None` si `sample` no da nada
+    pub fn blend<T>(&self, sample: impl Fn(f32, f32) -> Option<T>) -> Option<T>
+    where
+        T: std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+    {
+        let mut blended: Option<T> = None;
+        for (&(u, v), &weight) in self.uvs.iter().zip(&self.weights) {
+            if weight < 1e-3 {
+                continue;
+            }
+            let value = sample(u, v)? * weight;
+            blended = Some(match blended {
+                Some(sum) => sum + value,
+                None => value,
+            });
+        }
+        blended
+    }
+
+    /// `normal` inclinada por el mapa de normales leído desde cada eje: la
+    /// inclinación de cada proyección sobre su cara se mezcla y se suma
+    pub fn relief_normal(&self, normal: Vector3, sample: impl Fn(f32, f32) -> Option<Vector3>) -> Option<Vector3> {
+        let mut tilt = Vector3::zero();
+        for ((&(u, v), &weight), axis) in self.uvs.iter().zip(&self.weights).zip(&self.axes) {
+            if weight < 1e-3 {
+                continue;
+            }
+            let relief = sample(u, v)?;
+            let (tangent, bitangent) = block_face_axes(axis);
+            tilt += (tangent * relief.x + bitangent * relief.y) * (weight / relief.z.max(0.1));
+        }
+        Some((normal + tilt).normalized())
+    }
+}
+
+/// Giro/espejo de la textura para una cara, fijo según la posición del bloque.
+/// Las caras laterales solo se reflejan para que la textura quede derecha.
+#[inline]
+fn face_transform(center: &Vector3, normal: &Vector3) -> UvTransform {
+    let face = if normal.x.abs() > 0.9 {
+        if normal.x > 0.0 { 0 } else { 1 }
+    } else if normal.y.abs() > 0.9 {
+        if normal.y > 0.0 { 2 } else { 3 }
+    } else if normal.z > 0.0 {
+        4
+    } else {
+        5
+    };
+    let is_top_or_bottom = face == 2 || face == 3;
+
+    UvTransform::from_seed(
+        center.x.round() as i32,
+        center.y.round() as i32,
+        center.z.round() as i32,
+        face,
+        is_top_or_bottom,
+    )
+}
+
+impl Cube {
+    /// Impacto a distancia `t` en el punto de la cara con esa normal
+    #[inline]
+    fn face_hit(&self, point: Vector3, normal: Vector3, t: f32) -> Intersect {
+        let material = self.face_material(&normal);
+        let (u, v, uv_transform) = block_face_uv(&self.center, self.inv_size, &point, &normal, material);
+
+        Intersect::new(point, normal, t, Arc::clone(material), u, v).with_uv_transform(uv_transform)
     }
 }
 
@@ -92,12 +274,38 @@ impl RayIntersect for Cube {
             Vector3::new(0.0, 0.0, local.z.signum())
         };
 
-        let (u, v) = self.get_uv(&point, &normal);
+        let hit = self.face_hit(point, normal, t);
+        if !hit.material.is_cut_out(hit.u, hit.v) {
+            return hit;
+        }
 
-        Intersect::new(point, normal, t, self.material.clone(), u, v)
+        // Hueco en la textura: el rayo sigue hasta la cara del fondo (la del
+        // eje que cierra el intervalo), que se ve desde adentro como una
+        // lámina, con la normal hacia el rayo
+        if tmin > 0.0 {
+            let exits = [t1.max(t2), t3.max(t4), t5.max(t6)];
+            let back_normal = if exits[0] <= exits[1] && exits[0] <= exits[2] {
+                Vector3::new(ray_direction.x.signum(), 0.0, 0.0)
+            } else if exits[1] <= exits[2] {
+                Vector3::new(0.0, ray_direction.y.signum(), 0.0)
+            } else {
+                Vector3::new(0.0, 0.0, ray_direction.z.signum())
+            };
+            let mut back = self.face_hit(*ray_origin + *ray_direction * tmax, back_normal, tmax);
+            if !back.material.is_cut_out(back.u, back.v) {
+                back.normal = -back.normal;
+                return back;
+            }
+        }
+        Intersect::empty()
     }
 
     fn get_bounds(&self) -> AABB {
         AABB::new(self.min, self.max)
     }
+
+    #[cfg(feature = "gpu")]
+    fn as_cube(&self) -> Option<&Cube> {
+        Some(self)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,144 @@
+use raylib::prelude::Vector3;
+use std::path::Path;
+
+use crate::material::srgb_byte_to_linear;
+
+/// Nombres de las seis caras en `assets/skybox/`, en el orden +X, -X, +Y, -Y, +Z, -Z
+const FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+/// Imagen con las seis caras en forma de cruz horizontal (4x3 caras)
+const CROSS_NAME: &str = "cross.png";
+
+/// Una cara cuadrada del cubemap
+struct CubeFace {
+    size: u32,
+    pixels: Vec<Vector3>,
+}
+
+impl CubeFace {
+    fn from_image(img: &image::RgbImage, x0: u32, y0: u32, size: u32) -> Self {
+        let mut pixels = Vec::with_capacity((size * size) as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let p = img.get_pixel(x0 + x, y0 + y);
+                pixels.push(Vector3::new(srgb_byte_to_linear(p[0]), srgb_byte_to_linear(p[1]), srgb_byte_to_linear(p[2])));
+            }
+        }
+        CubeFace { size, pixels }
+    }
+
+    #[inline]
+    fn texel(&self, x: u32, y: u32) -> Vector3 {
+        let max = self.size - 1;
+        self.pixels[(y.min(max) * self.size + x.min(max)) as usize]
+    }
+
+    /// Muestreo bilineal con (u, v) en [0, 1]; los bordes se extienden
+    fn sample(&self, u: f32, v: f32) -> Vector3 {
+        let x = (u.clamp(0.0, 1.0) * self.size as f32 - 0.5).max(0.0);
+        let y = (v.clamp(0.0, 1.0) * self.size as f32 - 0.5).max(0.0);
+        let (x0, y0) = (x as u32, y as u32);
+        let (fx, fy) = (x.fract(), y.fract());
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+/// Cielo de seis caras (convención de OpenGL), alternativa al cielo procedural
+pub struct Cubemap {
+    faces: [CubeFace; 6],
+}
+
+impl Cubemap {
+    /// Carga `px.png` ... `nz.png` del directorio, o `cross.png` si no están las seis caras
+    pub fn load_dir(dir_path: &str) -> Result<Self, String> {
+        let face_paths = FACE_NAMES.map(|name| format!("{}/{}.png", dir_path, name));
+        if face_paths.iter().all(|p| Path::new(p).exists()) {
+            return Self::load_faces(&face_paths);
+        }
+
+        let cross_path = format!("{}/{}", dir_path, CROSS_NAME);
+        if Path::new(&cross_path).exists() {
+            return Self::load_cross(&cross_path);
+        }
+
+        Err(format!(
+            "No hay cubemap en {} (se esperan {}.png o {})",
+            dir_path,
+            FACE_NAMES.join(".png, "),
+            CROSS_NAME
+        ))
+    }
+
+    fn load_faces(paths: &[String; 6]) -> Result<Self, String> {
+        let mut faces = Vec::with_capacity(6);
+        for path in paths {
+            let img = open_rgb(path)?;
+            if img.width() != img.height() || img.width() == 0 {
+                return Err(format!("La cara {} no es cuadrada ({}x{})", path, img.width(), img.height()));
+            }
+            faces.push(CubeFace::from_image(&img, 0, 0, img.width()));
+        }
+
+        let size = faces[0].size;
+        if faces.iter().any(|f| f.size != size) {
+            return Err("Las caras del cubemap no tienen el mismo tamaño".to_string());
+        }
+
+        let faces: [CubeFace; 6] = faces.try_into().map_err(|_| "Faltan caras del cubemap".to_string())?;
+        Ok(Cubemap { faces })
+    }
+
+    /// Cruz horizontal:
+    ///
+    /// ```text
+    ///      +Y
+    /// -X   +Z   +X   -Z
+    ///      -Y
+    /// ```
+    fn load_cross(path: &str) -> Result<Self, String> {
+        let img = open_rgb(path)?;
+        let size = img.width() / 4;
+        if size == 0 || img.width() != size * 4 || img.height() != size * 3 {
+            return Err(format!(
+                "{} debe ser una cruz de 4x3 caras cuadradas ({}x{})",
+                path,
+                img.width(),
+                img.height()
+            ));
+        }
+
+        let face = |col: u32, row: u32| CubeFace::from_image(&img, col * size, row * size, size);
+        Ok(Cubemap {
+            faces: [face(2, 1), face(0, 1), face(1, 0), face(1, 2), face(1, 1), face(3, 1)],
+        })
+    }
+
+    /// Color del cielo en la dirección `dir`
+    pub fn sample(&self, dir: Vector3) -> Vector3 {
+        let (ax, ay, az) = (dir.x.abs(), dir.y.abs(), dir.z.abs());
+
+        // Cara según el eje dominante, con las coordenadas (s, t) de OpenGL
+        let (face, s, t, major) = if ax >= ay && ax >= az {
+            if dir.x > 0.0 { (0, -dir.z, -dir.y, ax) } else { (1, dir.z, -dir.y, ax) }
+        } else if ay >= az {
+            if dir.y > 0.0 { (2, dir.x, dir.z, ay) } else { (3, dir.x, -dir.z, ay) }
+        } else if dir.z > 0.0 {
+            (4, dir.x, -dir.y, az)
+        } else {
+            (5, -dir.x, -dir.y, az)
+        };
+
+        if major <= 0.0 {
+            return Vector3::zero();
+        }
+        self.faces[face].sample((s / major + 1.0) * 0.5, (t / major + 1.0) * 0.5)
+    }
+}
+
+fn open_rgb(path: &str) -> Result<image::RgbImage, String> {
+    image::open(path)
+        .map(|img| img.to_rgb8())
+        .map_err(|e| format!("No se pudo abrir {}: {}", path, e))
+}
@@ -0,0 +1,102 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+use crate::material::Material;
+
+/// Cilindro vertical con tapas, apoyado sobre el centro de su base
+pub struct Cylinder {
+    pub base: Vector3,
+    pub radius: f32,
+    pub height: f32,
+    pub material: Arc<Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Vector3, radius: f32, height: f32, material: Arc<Material>) -> Self {
+        Cylinder {
+            base,
+            radius,
+            height,
+            material,
+        }
+    }
+
+    /// Impacto con el costado: u da la vuelta, v sube de la base a la tapa
+    #[inline]
+    fn side_hit(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Option<(f32, Vector3, f32, f32)> {
+        let (ox, oz) = (ray_origin.x - self.base.x, ray_origin.z - self.base.z);
+        let a = ray_direction.x * ray_direction.x + ray_direction.z * ray_direction.z;
+        if a < 1e-12 {
+            return None;
+        }
+        let half_b = ox * ray_direction.x + oz * ray_direction.z;
+        let c = ox * ox + oz * oz - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrt_d = discriminant.sqrt();
+        [(-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a]
+            .into_iter()
+            .filter(|&t| t > 1e-4)
+            .find_map(|t| {
+                let y = ray_origin.y + ray_direction.y * t - self.base.y;
+                if !(0.0..=self.height).contains(&y) {
+                    return None;
+                }
+                let (x, z) = (ox + ray_direction.x * t, oz + ray_direction.z * t);
+                let normal = Vector3::new(x / self.radius, 0.0, z / self.radius);
+                let u = 0.5 + z.atan2(x) / (2.0 * PI);
+                Some((t, normal, u, 1.0 - y / self.height))
+            })
+    }
+
+    /// Impacto con la tapa de arriba o la de abajo, con mapeo plano
+    #[inline]
+    fn cap_hit(&self, ray_origin: &Vector3, ray_direction: &Vector3, top: bool) -> Option<(f32, Vector3, f32, f32)> {
+        if ray_direction.y.abs() < 1e-8 {
+            return None;
+        }
+        let cap_y = if top { self.base.y + self.height } else { self.base.y };
+        let t = (cap_y - ray_origin.y) / ray_direction.y;
+        if t <= 1e-4 {
+            return None;
+        }
+        let (x, z) = (ray_origin.x + ray_direction.x * t - self.base.x, ray_origin.z + ray_direction.z * t - self.base.z);
+        if x * x + z * z > self.radius * self.radius {
+            return None;
+        }
+        let normal = Vector3::new(0.0, if top { 1.0 } else { -1.0 }, 0.0);
+        Some((t, normal, 0.5 + x / (2.0 * self.radius), 0.5 + z / (2.0 * self.radius)))
+    }
+}
+
+impl RayIntersect for Cylinder {
+    #[inline]
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let hit = [
+            self.side_hit(ray_origin, ray_direction),
+            self.cap_hit(ray_origin, ray_direction, true),
+            self.cap_hit(ray_origin, ray_direction, false),
+        ]
+        .into_iter()
+        .flatten()
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        match hit {
+            Some((t, normal, u, v)) => {
+                let point = *ray_origin + *ray_direction * t;
+                Intersect::new(point, normal, t, Arc::clone(&self.material), u, v)
+            }
+            None => Intersect::empty(),
+        }
+    }
+
+    fn get_bounds(&self) -> AABB {
+        let r = Vector3::new(self.radius, 0.0, self.radius);
+        AABB::new(self.base - r, self.base + r + Vector3::new(0.0, self.height, 0.0))
+    }
+}
@@ -1,5 +1,35 @@
 use raylib::prelude::*;
 
+/// Filtro con que se estira el render al tamaño de la ventana (tecla N)
+#[derive(Clone, Copy, PartialEq)]
+pub enum Upscale {
+    Nearest,
+    Bilinear,
+}
+
+impl Upscale {
+    pub fn next(self) -> Self {
+        match self {
+            Upscale::Nearest => Upscale::Bilinear,
+            Upscale::Bilinear => Upscale::Nearest,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Upscale::Nearest => "vecino más cercano",
+            Upscale::Bilinear => "bilineal",
+        }
+    }
+
+    fn texture_filter(self) -> TextureFilter {
+        match self {
+            Upscale::Nearest => TextureFilter::TEXTURE_FILTER_POINT,
+            Upscale::Bilinear => TextureFilter::TEXTURE_FILTER_BILINEAR,
+        }
+    }
+}
+
 /// Buffer de imagen que almacena el resultado del renderizado
 pub struct Framebuffer {
     pub width: u32,
@@ -55,12 +85,61 @@ impl Framebuffer {
         self.color_buffer.export_image(file_path);
     }
 
-    /// Muestra el framebuffer en la ventana de raylib
-    /// Convierte la imagen en una textura y la dibuja
-    pub fn swap_buffers(&self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+    /// Copia los pixels del framebuffer como bytes RGBA (fila por fila)
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.color_buffer
+            .get_image_data()
+            .iter()
+            .flat_map(|c| [c.r, c.g, c.b, c.a])
+            .collect()
+    }
+
+    /// Muestra el render con un panel de texto (`overlay`) y, si se indica,
+    /// una mira en el centro con sus líneas de información al lado.
+    /// `lines` son segmentos en pixels de la ventana dibujados sobre la imagen (recorrido de rayos).
+    /// Si el framebuffer no mide lo mismo que la ventana se estira con el filtro `upscale`.
+    pub fn swap_buffers(
+        &self,
+        window: &mut RaylibHandle,
+        raylib_thread: &RaylibThread,
+        upscale: Upscale,
+        overlay: &[String],
+        crosshair: Option<&[String]>,
+        lines: &[(Vector2, Vector2, Color)],
+    ) {
+        let (screen_width, screen_height) = (window.get_screen_width(), window.get_screen_height());
         if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
+            texture.set_texture_filter(raylib_thread, upscale.texture_filter());
             let mut renderer = window.begin_drawing(raylib_thread);
-            renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+            renderer.draw_texture_pro(
+                &texture,
+                Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32),
+                Rectangle::new(0.0, 0.0, screen_width as f32, screen_height as f32),
+                Vector2::zero(),
+                0.0,
+                Color::WHITE,
+            );
+
+            for (start, end, color) in lines {
+                renderer.draw_line(start.x as i32, start.y as i32, end.x as i32, end.y as i32, *color);
+            }
+
+            if let Some(info) = crosshair {
+                let (cx, cy) = (screen_width / 2, screen_height / 2);
+                renderer.draw_line(cx - 8, cy, cx + 8, cy, Color::WHITE);
+                renderer.draw_line(cx, cy - 8, cx, cy + 8, Color::WHITE);
+                for (i, line) in info.iter().enumerate() {
+                    renderer.draw_text(line, cx + 14, cy + 10 + i as i32 * 14, 12, Color::WHITE);
+                }
+            }
+
+            if !overlay.is_empty() {
+                let line_height = 18;
+                renderer.draw_rectangle(5, 5, 330, overlay.len() as i32 * line_height + 10, Color::new(0, 0, 0, 180));
+                for (i, line) in overlay.iter().enumerate() {
+                    renderer.draw_text(line, 12, 10 + i as i32 * line_height, 16, Color::WHITE);
+                }
+            }
         }
     }
 }
\ No newline at end of file
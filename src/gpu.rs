@@ -0,0 +1,67 @@
+//! Backend opcional de trazado en GPU. Con la característica `gpu` los
+//! bloques de la escena, su BVH aplanado y las texturas se suben a un compute
+//! shader de wgpu; sin ella (o si no hay adaptador) todo se traza en la CPU.
+//!
+//! El shader solo conoce cubos: esferas, mallas y caídas no se dibujan, y las
+//! sombras de nubes, el plano de sombras, la imagen de fondo, las luces de
+//! área y los materiales con recortes por alfa, mapas o mapeo tri-planar
+//! quedan para la CPU. Sirve como vista previa rápida para recorrer escenas grandes.
+
+#[cfg(feature = "gpu")]
+mod wgpu_backend;
+
+#[cfg(feature = "gpu")]
+pub use wgpu_backend::{GpuRenderer, GpuScene};
+
+#[cfg(not(feature = "gpu"))]
+mod unavailable {
+    use raylib::prelude::Vector3;
+    use std::sync::Arc;
+
+    use crate::camera::Camera;
+    use crate::ray_intersect::RayIntersect;
+    use crate::renderer::{RenderSettings, Scene};
+
+    /// Sin la característica `gpu` no hay backend: `new` siempre falla
+    #[allow(dead_code)]
+    pub struct GpuRenderer(());
+
+    pub struct GpuScene(());
+
+    impl GpuScene {
+        pub fn counts(&self) -> (usize, usize) {
+            (0, 0)
+        }
+    }
+
+    impl GpuRenderer {
+        pub fn new() -> Result<Self, String> {
+            Err("compilado sin la característica `gpu` (cargo run --release --features gpu)".to_string())
+        }
+
+        pub fn adapter_name(&self) -> &str {
+            ""
+        }
+
+        pub fn upload_scene(&self, _objects: &[Arc<dyn RayIntersect + Send + Sync>]) -> GpuScene {
+            GpuScene(())
+        }
+
+        pub fn supports(_scene: &Scene, _gpu_scene: &GpuScene) -> bool {
+            false
+        }
+
+        pub fn render(
+            &mut self,
+            _gpu_scene: &GpuScene,
+            _scene: &Scene,
+            _camera: &Camera,
+            _settings: &RenderSettings,
+        ) -> Result<Vec<Vector3>, String> {
+            Err("compilado sin la característica `gpu`".to_string())
+        }
+    }
+}
+
+#[cfg(not(feature = "gpu"))]
+pub use unavailable::{GpuRenderer, GpuScene};
@@ -0,0 +1,556 @@
+// Trazado de rayos de la escena de bloques en un compute shader.
+// Replica Renderer::shade para cubos: luz ambiental, emisión, luces con
+// sombras (atravesando bloques transparentes), refracción y reflejo.
+
+const ORIGIN_BIAS: f32 = 1e-4;
+const MAX_DEPTH: u32 = 2u;
+const SHADOW_TRANSMISSION: f32 = 0.4;
+const MAX_SHADOW_LAYERS: u32 = 8u;
+const STACK_SIZE: u32 = 64u;
+const PENDING_SIZE: u32 = 8u;
+const PI: f32 = 3.14159265;
+
+const FALLOFF_NONE: u32 = 0u;
+const FALLOFF_LINEAR: u32 = 1u;
+const FALLOFF_INVERSE_SQUARE: u32 = 2u;
+
+const FLAG_UV_VARIATION: u32 = 1u;
+const FLAG_EMISSIVE_PULSE: u32 = 2u;
+
+struct Params {
+    eye: vec3<f32>,
+    width: u32,
+    right: vec3<f32>,
+    height: u32,
+    up: vec3<f32>,
+    light_count: u32,
+    forward: vec3<f32>,
+    portal_pulse: f32,
+    jitter: vec2<f32>,
+    perspective_scale: f32,
+    aspect_ratio: f32,
+    sky_width: u32,
+    sky_height: u32,
+    node_count: u32,
+    // Mitad de la altura de la imagen con proyección ortográfica; 0 en perspectiva
+    orthographic_extent: f32,
+    // Luz ambiental por eje (+X, -X, +Y, -Y, +Z, -Z): mundo normal y Nether
+    ambient: array<vec4<f32>, 12>,
+}
+
+struct Node {
+    min: vec3<f32>,
+    // Hoja: índice del cubo; nodo interno: posición del hijo derecho
+    index: u32,
+    max: vec3<f32>,
+    is_leaf: u32,
+}
+
+struct Cube {
+    center: vec3<f32>,
+    half_size: f32,
+    // Material de arriba (o de todo el cubo), de los costados y de abajo
+    material: u32,
+    side_material: u32,
+    bottom_material: u32,
+    _padding: u32,
+}
+
+struct Material {
+    diffuse: vec3<f32>,
+    specular: f32,
+    emissive: vec3<f32>,
+    transparency: f32,
+    albedo: vec2<f32>,
+    reflectivity: f32,
+    refraction_index: f32,
+    texture: i32,
+    flags: u32,
+    _padding: vec2<u32>,
+    tint: vec3<f32>,
+}
+
+struct Light {
+    position: vec3<f32>,
+    falloff: u32,
+    // Color por intensidad
+    color: vec3<f32>,
+    falloff_param: f32,
+    spot_direction: vec3<f32>,
+    cos_inner: f32,
+    cos_outer: f32,
+    meters_per_block: f32,
+    is_spot: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> nodes: array<Node>;
+@group(0) @binding(2) var<storage, read> cubes: array<Cube>;
+@group(0) @binding(3) var<storage, read> materials: array<Material>;
+@group(0) @binding(4) var<storage, read> lights: array<Light>;
+// Por índice del atlas: (primer texel del cuadro actual, ancho, alto, ancho
+// del atlas); ancho 0 = textura faltante
+@group(0) @binding(5) var<storage, read> texture_info: array<vec4<u32>>;
+@group(0) @binding(6) var<storage, read> texels: array<u32>;
+// Cielo en proyección equirectangular: mundo normal y luego Nether
+@group(0) @binding(7) var<storage, read> sky: array<vec4<f32>>;
+@group(0) @binding(8) var<storage, read_write> output: array<vec4<f32>>;
+
+struct Hit {
+    found: bool,
+    distance: f32,
+    point: vec3<f32>,
+    normal: vec3<f32>,
+    material: u32,
+    uv: vec2<f32>,
+}
+
+fn box_entry(bmin: vec3<f32>, bmax: vec3<f32>, origin: vec3<f32>, inv_dir: vec3<f32>, max_distance: f32) -> f32 {
+    let t1 = (bmin - origin) * inv_dir;
+    let t2 = (bmax - origin) * inv_dir;
+    let lo = min(t1, t2);
+    let hi = max(t1, t2);
+    let tmin = max(max(lo.x, lo.y), lo.z);
+    let tmax = min(min(hi.x, hi.y), hi.z);
+    if tmax >= 0.0 && tmin <= tmax && tmin <= max_distance {
+        return max(tmin, 0.0);
+    }
+    return -1.0;
+}
+
+// Igual que UvTransform::from_seed y UvTransform::apply
+fn face_uv(center: vec3<f32>, face: u32, uv: vec2<f32>) -> vec2<f32> {
+    let cell = vec3<i32>(round(center));
+    var h = (bitcast<u32>(cell.x) * 73856093u)
+        ^ (bitcast<u32>(cell.y) * 19349663u)
+        ^ (bitcast<u32>(cell.z) * 83492791u)
+        ^ (face * 2654435761u);
+    h = h ^ (h >> 13u);
+    h = h * 0x5bd1e995u;
+    h = h ^ (h >> 15u);
+
+    let allow_rotation = face == 2u || face == 3u;
+    let rotation = select(0u, h & 3u, allow_rotation);
+    var u = uv.x;
+    if (h & 4u) != 0u {
+        u = 1.0 - u;
+    }
+    switch rotation {
+        case 1u: { return vec2<f32>(uv.y, 1.0 - u); }
+        case 2u: { return vec2<f32>(1.0 - u, 1.0 - uv.y); }
+        case 3u: { return vec2<f32>(1.0 - uv.y, u); }
+        default: { return vec2<f32>(u, uv.y); }
+    }
+}
+
+fn cube_hit(cube: Cube, origin: vec3<f32>, direction: vec3<f32>, inv_dir: vec3<f32>) -> Hit {
+    var hit: Hit;
+    hit.found = false;
+
+    let bmin = cube.center - vec3<f32>(cube.half_size);
+    let bmax = cube.center + vec3<f32>(cube.half_size);
+    let t1 = (bmin - origin) * inv_dir;
+    let t2 = (bmax - origin) * inv_dir;
+    let lo = min(t1, t2);
+    let hi = max(t1, t2);
+    let tmin = max(max(lo.x, lo.y), lo.z);
+    let tmax = min(min(hi.x, hi.y), hi.z);
+    if tmax < 0.0 || tmin > tmax {
+        return hit;
+    }
+    let t = select(tmax, tmin, tmin > 0.0);
+    if t < 0.0 {
+        return hit;
+    }
+
+    let point = origin + direction * t;
+    let local = point - cube.center;
+    let a = abs(local);
+    var normal: vec3<f32>;
+    if a.x > a.y && a.x > a.z {
+        normal = vec3<f32>(sign(local.x), 0.0, 0.0);
+    } else if a.y > a.z {
+        normal = vec3<f32>(0.0, sign(local.y), 0.0);
+    } else {
+        normal = vec3<f32>(0.0, 0.0, sign(local.z));
+    }
+
+    let scaled = local / (cube.half_size * 2.0);
+    var uv: vec2<f32>;
+    var face: u32;
+    if abs(normal.x) > 0.9 {
+        uv = vec2<f32>(scaled.z, -scaled.y);
+        face = select(1u, 0u, normal.x > 0.0);
+    } else if abs(normal.y) > 0.9 {
+        uv = vec2<f32>(scaled.x, scaled.z);
+        face = select(3u, 2u, normal.y > 0.0);
+    } else {
+        uv = vec2<f32>(scaled.x, -scaled.y);
+        face = select(5u, 4u, normal.z > 0.0);
+    }
+    uv = clamp(uv + vec2<f32>(0.5), vec2<f32>(0.0), vec2<f32>(1.0));
+    // Igual que BlockFaces::for_normal
+    var material = cube.side_material;
+    if normal.y > 0.9 {
+        material = cube.material;
+    } else if normal.y < -0.9 {
+        material = cube.bottom_material;
+    }
+    if (materials[material].flags & FLAG_UV_VARIATION) != 0u {
+        uv = face_uv(cube.center, face, uv);
+    }
+
+    hit.found = true;
+    hit.distance = t;
+    hit.point = point;
+    hit.normal = normal;
+    hit.material = material;
+    hit.uv = uv;
+    return hit;
+}
+
+// Impacto más cercano, recorriendo primero el hijo más cercano
+fn intersect(origin: vec3<f32>, direction: vec3<f32>) -> Hit {
+    var best: Hit;
+    best.found = false;
+    best.distance = 1e30;
+    if params.node_count == 0u {
+        return best;
+    }
+
+    let inv_dir = 1.0 / direction;
+    var stack: array<u32, STACK_SIZE>;
+    var stack_len = 1u;
+    stack[0] = 0u;
+
+    while stack_len > 0u {
+        stack_len -= 1u;
+        let index = stack[stack_len];
+        let node = nodes[index];
+        if box_entry(node.min, node.max, origin, inv_dir, best.distance) < 0.0 {
+            continue;
+        }
+
+        if node.is_leaf != 0u {
+            let hit = cube_hit(cubes[node.index], origin, direction, inv_dir);
+            if hit.found && hit.distance < best.distance {
+                best = hit;
+            }
+            continue;
+        }
+
+        let left = index + 1u;
+        let right = node.index;
+        let left_node = nodes[left];
+        let right_node = nodes[right];
+        let left_t = box_entry(left_node.min, left_node.max, origin, inv_dir, best.distance);
+        let right_t = box_entry(right_node.min, right_node.max, origin, inv_dir, best.distance);
+        if stack_len + 2u > STACK_SIZE {
+            continue;
+        }
+        // El más cercano se apila al final para visitarlo primero
+        if left_t >= 0.0 && right_t >= 0.0 {
+            if right_t < left_t {
+                stack[stack_len] = left;
+                stack[stack_len + 1u] = right;
+            } else {
+                stack[stack_len] = right;
+                stack[stack_len + 1u] = left;
+            }
+            stack_len += 2u;
+        } else if left_t >= 0.0 {
+            stack[stack_len] = left;
+            stack_len += 1u;
+        } else if right_t >= 0.0 {
+            stack[stack_len] = right;
+            stack_len += 1u;
+        }
+    }
+    return best;
+}
+
+// Igual que BVH::occluded: cualquier cubo a menos de `max_distance`
+fn occluded(origin: vec3<f32>, direction: vec3<f32>, max_distance: f32) -> bool {
+    if params.node_count == 0u {
+        return false;
+    }
+
+    let inv_dir = 1.0 / direction;
+    var stack: array<u32, STACK_SIZE>;
+    var stack_len = 1u;
+    stack[0] = 0u;
+
+    while stack_len > 0u {
+        stack_len -= 1u;
+        let index = stack[stack_len];
+        let node = nodes[index];
+        if box_entry(node.min, node.max, origin, inv_dir, max_distance) < 0.0 {
+            continue;
+        }
+
+        if node.is_leaf != 0u {
+            let hit = cube_hit(cubes[node.index], origin, direction, inv_dir);
+            if hit.found && hit.distance < max_distance {
+                return true;
+            }
+        } else if stack_len + 2u <= STACK_SIZE {
+            stack[stack_len] = node.index;
+            stack[stack_len + 1u] = index + 1u;
+            stack_len += 2u;
+        }
+    }
+    return false;
+}
+
+fn leaving(hit: Hit, direction: vec3<f32>) -> vec3<f32> {
+    let offset = hit.normal * ORIGIN_BIAS;
+    return select(hit.point + offset, hit.point - offset, dot(direction, hit.normal) < 0.0);
+}
+
+// Los texels se guardan en sRGB; se ilumina en espacio lineal
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    return select(pow((c + vec3<f32>(0.055)) / 1.055, vec3<f32>(2.4)), c / 12.92, c <= vec3<f32>(0.04045));
+}
+
+fn sample_texture(texture: i32, uv: vec2<f32>) -> vec3<f32> {
+    let info = texture_info[texture];
+    if info.y == 0u || info.z == 0u {
+        // Textura faltante: el mismo damero que TextureManager::sample
+        let checker = (i32(uv.x * 8.0) + i32(uv.y * 8.0)) % 2;
+        return select(vec3<f32>(0.0), vec3<f32>(1.0, 0.0, 1.0), checker == 0);
+    }
+    let x = u32(uv.x * f32(info.y)) % info.y;
+    let y = u32(uv.y * f32(info.z)) % info.z;
+    let texel = unpack4x8unorm(texels[info.x + y * info.w + x]);
+    return srgb_to_linear(texel.rgb);
+}
+
+// Misma proyección que EnvironmentMap::sample, con interpolación bilineal
+fn background(direction: vec3<f32>, is_nether: bool) -> vec3<f32> {
+    let d = normalize(direction);
+    let u = 0.5 + atan2(d.z, d.x) / (2.0 * PI);
+    let v = 0.5 - asin(clamp(d.y, -1.0, 1.0)) / PI;
+
+    let w = params.sky_width;
+    let h = params.sky_height;
+    let base = select(0u, w * h, is_nether);
+    let x = u * f32(w) - 0.5;
+    let y = clamp(v * f32(h) - 0.5, 0.0, f32(h - 1u));
+    let fx = fract(x);
+    let fy = fract(y);
+    let x0 = u32(floor(x) + f32(w)) % w;
+    let x1 = (x0 + 1u) % w;
+    let y0 = u32(y);
+    let y1 = min(y0 + 1u, h - 1u);
+
+    let top = mix(sky[base + y0 * w + x0].rgb, sky[base + y0 * w + x1].rgb, fx);
+    let bottom = mix(sky[base + y1 * w + x0].rgb, sky[base + y1 * w + x1].rgb, fx);
+    return mix(top, bottom, fy);
+}
+
+fn ambient(normal: vec3<f32>, is_nether: bool) -> vec3<f32> {
+    var axis: u32;
+    if abs(normal.x) > 0.5 {
+        axis = select(1u, 0u, normal.x >= 0.0);
+    } else if abs(normal.y) > 0.5 {
+        axis = select(3u, 2u, normal.y >= 0.0);
+    } else {
+        axis = select(5u, 4u, normal.z >= 0.0);
+    }
+    return params.ambient[axis + select(0u, 6u, is_nether)].rgb;
+}
+
+fn attenuation(light: Light, distance: f32) -> f32 {
+    let meters = distance * light.meters_per_block;
+    switch light.falloff {
+        case FALLOFF_NONE: { return 1.0; }
+        case FALLOFF_LINEAR: { return max(1.0 - meters / max(light.falloff_param, 1e-3), 0.0); }
+        case FALLOFF_INVERSE_SQUARE: {
+            let ratio = meters / max(light.falloff_param, 1e-3);
+            let window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+            return window * window / (meters * meters + 1.0);
+        }
+        default: { return 1.0 / (1.0 + 0.05 * meters + 0.01 * meters * meters); }
+    }
+}
+
+fn cone_factor(light: Light, point: vec3<f32>) -> f32 {
+    if light.is_spot == 0u {
+        return 1.0;
+    }
+    let cos_angle = dot(normalize(point - light.position), light.spot_direction);
+    if cos_angle <= light.cos_outer {
+        return 0.0;
+    }
+    if cos_angle >= light.cos_inner {
+        return 1.0;
+    }
+    let t = (cos_angle - light.cos_outer) / (light.cos_inner - light.cos_outer);
+    return t * t * (3.0 - 2.0 * t);
+}
+
+// Igual que Renderer::shadow_transmission
+fn shadow_transmission(hit: Hit, light: Light) -> vec3<f32> {
+    let light_dir = normalize(light.position - hit.point);
+    let light_distance = length(light.position - hit.point);
+    var origin = leaving(hit, light_dir);
+
+    if !occluded(origin, light_dir, light_distance) {
+        return vec3<f32>(1.0);
+    }
+
+    var transmission = vec3<f32>(1.0);
+    for (var layer = 0u; layer < MAX_SHADOW_LAYERS; layer++) {
+        let blocker = intersect(origin, light_dir);
+        if !blocker.found || length(blocker.point - hit.point) >= light_distance {
+            return transmission;
+        }
+
+        let material = materials[blocker.material];
+        if material.transparency <= 0.05 {
+            return vec3<f32>(SHADOW_TRANSMISSION);
+        }
+        if dot(light_dir, blocker.normal) < 0.0 {
+            let tint = material.diffuse * material.transparency;
+            transmission = max(transmission * tint, vec3<f32>(SHADOW_TRANSMISSION));
+        }
+        origin = leaving(blocker, light_dir);
+    }
+    return transmission;
+}
+
+// Luz propia de la superficie, sin reflejos ni refracciones
+fn shade_local(hit: Hit, view_origin: vec3<f32>) -> vec3<f32> {
+    let material = materials[hit.material];
+    var diffuse_color = material.diffuse;
+    if material.texture >= 0 {
+        diffuse_color = sample_texture(material.texture, hit.uv);
+    }
+    diffuse_color *= material.tint;
+
+    let view_dir = normalize(view_origin - hit.point);
+    var color = diffuse_color * ambient(hit.normal, hit.point.y < 0.0);
+
+    if length(material.emissive) > 0.0 {
+        let pulse = select(1.0, params.portal_pulse, (material.flags & FLAG_EMISSIVE_PULSE) != 0u);
+        color += material.emissive * 0.5 * pulse;
+    }
+
+    for (var i = 0u; i < params.light_count; i++) {
+        let light = lights[i];
+        let light_dir = normalize(light.position - hit.point);
+        let diffuse_dot = dot(hit.normal, light_dir);
+        if diffuse_dot <= 0.0 {
+            continue;
+        }
+        let cone = cone_factor(light, hit.point);
+        if cone <= 0.0 {
+            continue;
+        }
+
+        let distance = length(light.position - hit.point);
+        let light_color = light.color * attenuation(light, distance) * cone * shadow_transmission(hit, light);
+
+        let reflect_dir = reflect(-light_dir, hit.normal);
+        let highlight = max(dot(view_dir, reflect_dir), 0.0);
+        let specular = select(0.0, pow(highlight, material.specular), highlight > 0.0);
+
+        color += diffuse_color * light_color * diffuse_dot * material.albedo.x
+            + light_color * specular * material.albedo.y;
+    }
+    return color;
+}
+
+fn refract_dir(incident: vec3<f32>, normal: vec3<f32>, eta: f32) -> vec4<f32> {
+    let cos_i = -clamp(dot(incident, normal), -1.0, 1.0);
+    let sin_t2 = eta * eta * (1.0 - cos_i * cos_i);
+    if sin_t2 > 1.0 {
+        return vec4<f32>(0.0);
+    }
+    let cos_t = sqrt(1.0 - sin_t2);
+    return vec4<f32>(incident * eta + normal * (eta * cos_i - cos_t), 1.0);
+}
+
+struct PendingRay {
+    origin: vec3<f32>,
+    direction: vec3<f32>,
+    weight: vec3<f32>,
+    depth: u32,
+}
+
+// Sin recursión: cada rayo pendiente lleva el peso con que su radiancia
+// entra en el pixel, y los reflejos y refracciones se apilan
+fn trace(origin: vec3<f32>, direction: vec3<f32>) -> vec3<f32> {
+    var pending: array<PendingRay, PENDING_SIZE>;
+    var pending_len = 1u;
+    pending[0] = PendingRay(origin, direction, vec3<f32>(1.0), 0u);
+    var color = vec3<f32>(0.0);
+
+    while pending_len > 0u {
+        pending_len -= 1u;
+        let ray = pending[pending_len];
+        let hit = intersect(ray.origin, ray.direction);
+        if !hit.found {
+            color += ray.weight * background(ray.direction, ray.origin.y < 0.0);
+            continue;
+        }
+
+        let material = materials[hit.material];
+        let can_recurse = ray.depth < MAX_DEPTH && pending_len + 2u <= PENDING_SIZE;
+        let reflectivity = select(0.0, material.reflectivity, can_recurse && material.reflectivity > 0.05);
+        var refractivity = 0.0;
+
+        if can_recurse && material.transparency > 0.05 {
+            let entering = dot(ray.direction, hit.normal) < 0.0;
+            let eta = select(material.refraction_index, 1.0 / material.refraction_index, entering);
+            let normal = select(-hit.normal, hit.normal, entering);
+            let refracted = refract_dir(ray.direction, normal, eta);
+            if refracted.w > 0.0 {
+                refractivity = material.transparency;
+                let weight = ray.weight * refractivity * (1.0 - reflectivity);
+                pending[pending_len] = PendingRay(leaving(hit, refracted.xyz), refracted.xyz, weight, ray.depth + 1u);
+                pending_len += 1u;
+            }
+        }
+
+        if reflectivity > 0.0 {
+            let reflected = normalize(reflect(ray.direction, hit.normal));
+            pending[pending_len] = PendingRay(leaving(hit, reflected), reflected, ray.weight * reflectivity, ray.depth + 1u);
+            pending_len += 1u;
+        }
+
+        let local_weight = (1.0 - refractivity) * (1.0 - reflectivity);
+        color += ray.weight * local_weight * shade_local(hit, ray.origin);
+    }
+    return color;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.width || id.y >= params.height {
+        return;
+    }
+
+    // Igual que Ray::primary
+    let x = f32(id.x) + params.jitter.x;
+    let y = f32(id.y) + params.jitter.y;
+    let ndc_x = (2.0 * x) / f32(params.width) - 1.0;
+    let ndc_y = -(2.0 * y) / f32(params.height) + 1.0;
+
+    var origin = params.eye;
+    var direction = params.forward;
+    if params.orthographic_extent > 0.0 {
+        // Rayos paralelos que salen de un plano de 2·extent de alto
+        let extent = params.orthographic_extent;
+        origin = params.eye + params.right * (ndc_x * params.aspect_ratio * extent) + params.up * (ndc_y * extent);
+    } else {
+        let screen_x = ndc_x * params.aspect_ratio * params.perspective_scale;
+        let screen_y = ndc_y * params.perspective_scale;
+        let camera_dir = normalize(vec3<f32>(screen_x, screen_y, -1.0));
+        direction = params.right * camera_dir.x + params.up * camera_dir.y - params.forward * camera_dir.z;
+    }
+
+    let color = trace(origin, direction);
+    output[id.y * params.width + id.x] = vec4<f32>(color, 1.0);
+}
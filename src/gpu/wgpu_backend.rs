@@ -0,0 +1,510 @@
+use bytemuck::{Pod, Zeroable};
+use raylib::prelude::Vector3;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+use crate::TEXTURE_MANAGER;
+use crate::camera::{Camera, Projection};
+use crate::light::{Falloff, Light};
+use crate::material::Material;
+use crate::ray_intersect::{RayIntersect, BVH};
+use crate::renderer::{FOV, RenderSettings, Scene};
+use crate::skybox;
+
+/// Resolución del cielo que se sube en cada frame (proyección equirectangular)
+const SKY_WIDTH: u32 = 256;
+const SKY_HEIGHT: u32 = 128;
+/// Debe coincidir con `@workgroup_size` del shader
+const WORKGROUP_SIZE: u32 = 8;
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuParams {
+    eye: [f32; 3],
+    width: u32,
+    right: [f32; 3],
+    height: u32,
+    up: [f32; 3],
+    light_count: u32,
+    forward: [f32; 3],
+    portal_pulse: f32,
+    jitter: [f32; 2],
+    perspective_scale: f32,
+    aspect_ratio: f32,
+    sky_width: u32,
+    sky_height: u32,
+    node_count: u32,
+    /// Mitad de la altura de la imagen en bloques con proyección ortográfica; 0 en perspectiva
+    orthographic_extent: f32,
+    ambient: [[f32; 4]; 12],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuNode {
+    min: [f32; 3],
+    index: u32,
+    max: [f32; 3],
+    is_leaf: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuCube {
+    center: [f32; 3],
+    half_size: f32,
+    /// Material de arriba (o de todo el cubo), de los costados y de abajo
+    material: u32,
+    side_material: u32,
+    bottom_material: u32,
+    _padding: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuMaterial {
+    diffuse: [f32; 3],
+    specular: f32,
+    emissive: [f32; 3],
+    transparency: f32,
+    albedo: [f32; 2],
+    reflectivity: f32,
+    refraction_index: f32,
+    texture: i32,
+    flags: u32,
+    _padding: [u32; 2],
+    tint: [f32; 3],
+    _tint_padding: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuLight {
+    position: [f32; 3],
+    falloff: u32,
+    color: [f32; 3],
+    falloff_param: f32,
+    spot_direction: [f32; 3],
+    cos_inner: f32,
+    cos_outer: f32,
+    meters_per_block: f32,
+    is_spot: u32,
+    _padding: u32,
+}
+
+fn array3(v: Vector3) -> [f32; 3] {
+    [v.x, v.y, v.z]
+}
+
+impl GpuMaterial {
+    fn new(material: &Material, texture: Option<usize>) -> Self {
+        let flags = material.uv_variation as u32 | (material.emissive_pulse as u32) << 1;
+        GpuMaterial {
+            diffuse: array3(material.linear_diffuse()),
+            specular: material.specular,
+            emissive: array3(material.linear_emissive()),
+            transparency: material.transparency,
+            albedo: material.albedo,
+            reflectivity: material.reflectivity,
+            refraction_index: material.refraction_index,
+            texture: texture.map_or(-1, |index| index as i32),
+            flags,
+            _padding: [0; 2],
+            tint: array3(material.tint),
+            _tint_padding: 0.0,
+        }
+    }
+}
+
+impl GpuLight {
+    fn new(light: &Light) -> Self {
+        let (falloff, falloff_param) = match light.falloff {
+            Falloff::None => (0, 0.0),
+            Falloff::Linear { range } => (1, range),
+            Falloff::InverseSquare { radius } => (2, radius),
+            Falloff::Smooth => (3, 0.0),
+        };
+        let color = light.linear_color() * light.intensity;
+        let spot = light.spot.as_ref();
+        GpuLight {
+            position: array3(light.position),
+            falloff,
+            color: array3(color),
+            falloff_param,
+            spot_direction: spot.map_or([0.0; 3], |spot| array3(spot.direction)),
+            cos_inner: spot.map_or(0.0, |spot| spot.cos_inner),
+            cos_outer: spot.map_or(0.0, |spot| spot.cos_outer),
+            meters_per_block: light.world_scale.meters_per_block,
+            is_spot: spot.is_some() as u32,
+            _padding: 0,
+        }
+    }
+}
+
+/// Bloques de la escena ya subidos a la GPU, con su BVH aplanado.
+/// Se construye una vez por escena, igual que el BVH de la CPU.
+pub struct GpuScene {
+    nodes: wgpu::Buffer,
+    cubes: wgpu::Buffer,
+    materials: wgpu::Buffer,
+    node_count: u32,
+    /// Índices en el atlas de las texturas que usan los materiales
+    textures: Vec<usize>,
+    cube_count: usize,
+    skipped: usize,
+    /// Algún material usa recortes por alfa, mapas (normales, rugosidad,
+    /// metal) o mapeo tri-planar, que el shader no sabe aplicar
+    cpu_materials: bool,
+}
+
+impl GpuScene {
+    /// Cubos subidos y objetos que el shader no sabe trazar (esferas, mallas, caídas)
+    pub fn counts(&self) -> (usize, usize) {
+        (self.cube_count, self.skipped)
+    }
+}
+
+struct OutputBuffers {
+    width: u32,
+    height: u32,
+    output: wgpu::Buffer,
+    readback: wgpu::Buffer,
+}
+
+pub struct GpuRenderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    adapter_name: String,
+    buffers: Option<OutputBuffers>,
+}
+
+/// Buffer de almacenamiento de solo lectura; wgpu no acepta buffers vacíos
+fn storage_buffer<T: Pod + Zeroable>(device: &wgpu::Device, label: &str, data: &[T]) -> wgpu::Buffer {
+    let padding = [T::zeroed()];
+    let contents = if data.is_empty() { &padding[..] } else { data };
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: bytemuck::cast_slice(contents),
+        usage: wgpu::BufferUsages::STORAGE,
+    })
+}
+
+impl GpuRenderer {
+    /// Busca un adaptador y compila el shader; falla si no hay GPU utilizable
+    pub fn new() -> Result<Self, String> {
+        pollster::block_on(Self::request())
+    }
+
+    async fn request() -> Result<Self, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("No se encontró un adaptador de GPU: {}", e))?;
+        let adapter_name = adapter.get_info().name;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("trazado"),
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("No se pudo abrir la GPU {}: {}", adapter_name, e))?;
+
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = device.create_shader_module(wgpu::include_wgsl!("trace.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("trazado"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        if let Some(e) = device.pop_error_scope().await {
+            return Err(format!("Error compilando el shader de trazado: {}", e));
+        }
+
+        Ok(GpuRenderer {
+            device,
+            queue,
+            pipeline,
+            adapter_name,
+            buffers: None,
+        })
+    }
+
+    pub fn adapter_name(&self) -> &str {
+        &self.adapter_name
+    }
+
+    /// Sube los cubos de la escena con sus materiales y un BVH propio.
+    /// El resto de los objetos no se dibuja en la GPU.
+    pub fn upload_scene(&self, objects: &[Arc<dyn RayIntersect + Send + Sync>]) -> GpuScene {
+        let blocks: Vec<Arc<dyn RayIntersect + Send + Sync>> =
+            objects.iter().filter(|object| object.as_cube().is_some()).cloned().collect();
+        let bvh = BVH::build(&blocks);
+
+        let mut material_indices: HashMap<*const Material, u32> = HashMap::new();
+        let mut materials = Vec::new();
+        let mut textures = Vec::new();
+        let mut cubes = Vec::with_capacity(blocks.len());
+        let mut cpu_materials = false;
+        let mut material_index = |material: &Arc<Material>| {
+            *material_indices.entry(Arc::as_ptr(material)).or_insert_with(|| {
+                let slots = material.slots;
+                cpu_materials |= material.alpha_mask.is_some()
+                    || material.triplanar.is_some()
+                    || slots.normal.is_some()
+                    || slots.roughness.is_some()
+                    || slots.metalness.is_some();
+                let texture = material.slots.texture;
+                if let Some(slot) = texture.filter(|slot| !textures.contains(slot)) {
+                    textures.push(slot);
+                }
+                materials.push(GpuMaterial::new(material, texture));
+                materials.len() as u32 - 1
+            })
+        };
+        for cube in blocks.iter().filter_map(|object| object.as_cube()) {
+            let (top, side, bottom) = match &cube.faces {
+                Some(faces) => (&faces.top, &faces.side, &faces.bottom),
+                None => (&cube.material, &cube.material, &cube.material),
+            };
+            cubes.push(GpuCube {
+                center: array3(cube.center),
+                half_size: cube.size * 0.5,
+                material: material_index(top),
+                side_material: material_index(side),
+                bottom_material: material_index(bottom),
+                _padding: 0,
+            });
+        }
+
+        let nodes: Vec<GpuNode> = bvh
+            .flatten()
+            .iter()
+            .map(|node| GpuNode {
+                min: array3(node.bounds.min),
+                index: node.index as u32,
+                max: array3(node.bounds.max),
+                is_leaf: node.is_leaf as u32,
+            })
+            .collect();
+
+        GpuScene {
+            nodes: storage_buffer(&self.device, "nodos", &nodes),
+            cubes: storage_buffer(&self.device, "cubos", &cubes),
+            materials: storage_buffer(&self.device, "materiales", &materials),
+            node_count: nodes.len() as u32,
+            textures,
+            cube_count: cubes.len(),
+            skipped: objects.len() - cubes.len(),
+            cpu_materials,
+        }
+    }
+
+    /// Si el shader puede dibujar el frame; el plano de sombras, la imagen
+    /// de fondo, las luces de área (sombras suaves) y los materiales con
+    /// recortes o mapas solo existen en la CPU
+    pub fn supports(scene: &Scene, gpu_scene: &GpuScene) -> bool {
+        !gpu_scene.cpu_materials
+            && scene.environment.shadow_catcher.is_none()
+            && scene.environment.backdrop.is_none()
+            && scene.lights.iter().all(|light| light.area.is_none())
+    }
+
+    /// Cielo de fondo (mundo normal y Nether) muestreado en una grilla equirectangular
+    fn sky(scene: &Scene) -> Vec<[f32; 4]> {
+        let texels = (SKY_WIDTH * SKY_HEIGHT) as usize;
+        (0..texels * 2)
+            .into_par_iter()
+            .map(|i| {
+                let is_nether = i >= texels;
+                let x = (i % texels) as u32 % SKY_WIDTH;
+                let y = (i % texels) as u32 / SKY_WIDTH;
+                let phi = ((x as f32 + 0.5) / SKY_WIDTH as f32 - 0.5) * 2.0 * PI;
+                let latitude = (0.5 - (y as f32 + 0.5) / SKY_HEIGHT as f32) * PI;
+                let dir = Vector3::new(latitude.cos() * phi.cos(), latitude.sin(), latitude.cos() * phi.sin());
+                let color = scene.environment.background(dir, is_nether, skybox::PRIMARY_DISK_LIMIT);
+                [color.x, color.y, color.z, 1.0]
+            })
+            .collect()
+    }
+
+    /// Atlas de texturas empaquetado como RGBA8 en `u32`, y por cada índice
+    /// del atlas el cuadro actual (solo de las texturas que usa la escena)
+    fn textures(gpu_scene: &GpuScene) -> (Vec<[u32; 4]>, Vec<u32>) {
+        let texture_manager = TEXTURE_MANAGER.lock().unwrap();
+        let atlas = texture_manager.atlas();
+        let mut info = vec![[0; 4]; atlas.slot_count()];
+        for &slot in &gpu_scene.textures {
+            if let (Some(rect), Some(info)) = (texture_manager.frame_rect(slot), info.get_mut(slot)) {
+                *info = [rect.y * atlas.width + rect.x, rect.width, rect.height, atlas.width];
+            }
+        }
+        let texels = atlas.data
+            .chunks_exact(4)
+            .map(|rgba| u32::from_le_bytes([rgba[0], rgba[1], rgba[2], rgba[3]]))
+            .collect();
+        (info, texels)
+    }
+
+    /// Crea los buffers de la imagen si cambió la resolución
+    fn prepare_output(&mut self, width: u32, height: u32) {
+        let stale = self.buffers.as_ref().is_none_or(|buffers| buffers.width != width || buffers.height != height);
+        if stale {
+            let size = width as u64 * height as u64 * 16;
+            self.buffers = Some(OutputBuffers {
+                width,
+                height,
+                output: self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("radiancia"),
+                    size,
+                    usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                readback: self.device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("lectura"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+            });
+        }
+    }
+
+    /// Traza un rayo por pixel en la GPU y devuelve la radiancia fila por
+    /// fila, igual que `Renderer::render_radiance`
+    pub fn render(
+        &mut self,
+        gpu_scene: &GpuScene,
+        scene: &Scene,
+        camera: &Camera,
+        settings: &RenderSettings,
+    ) -> Result<Vec<Vector3>, String> {
+        let (width, height) = (settings.width, settings.height);
+        let axes = [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+        ];
+        let mut ambient = [[0.0; 4]; 12];
+        for (i, axis) in axes.iter().enumerate() {
+            for (offset, is_nether) in [(0, false), (6, true)] {
+                let irradiance = scene.environment.ambient.irradiance(axis, is_nether);
+                ambient[i + offset] = [irradiance.x, irradiance.y, irradiance.z, 0.0];
+            }
+        }
+
+        let params = GpuParams {
+            eye: array3(camera.eye),
+            width,
+            right: array3(camera.right),
+            height,
+            up: array3(camera.up),
+            light_count: scene.lights.len() as u32,
+            forward: array3(camera.forward),
+            portal_pulse: scene.environment.portal_pulse,
+            // El shader desplaza todos los pixels igual: se usa el desplazamiento del primero
+            jitter: {
+                let (x, y) = settings.pixel_jitter(0, 0);
+                [x, y]
+            },
+            perspective_scale: (FOV * 0.5).tan(),
+            aspect_ratio: width as f32 / height as f32,
+            sky_width: SKY_WIDTH,
+            sky_height: SKY_HEIGHT,
+            node_count: gpu_scene.node_count,
+            orthographic_extent: match camera.projection {
+                Projection::Perspective => 0.0,
+                Projection::Orthographic { extent } => extent,
+            },
+            ambient,
+        };
+        let lights: Vec<GpuLight> = scene.lights.iter().map(GpuLight::new).collect();
+        let (texture_info, texels) = Self::textures(gpu_scene);
+        let sky = Self::sky(scene);
+
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("parámetros"),
+            contents: bytemuck::bytes_of(&params),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let lights = storage_buffer(&self.device, "luces", &lights);
+        let texture_info = storage_buffer(&self.device, "texturas", &texture_info);
+        let texels = storage_buffer(&self.device, "texels", &texels);
+        let sky = storage_buffer(&self.device, "cielo", &sky);
+
+        self.prepare_output(width, height);
+        let Some(buffers) = &self.buffers else {
+            return Err("No se pudo crear la imagen en la GPU".to_string());
+        };
+        let device = &self.device;
+        let layout = self.pipeline.get_bind_group_layout(0);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("trazado"),
+            layout: &layout,
+            entries: &[
+                params.as_entire_binding(),
+                gpu_scene.nodes.as_entire_binding(),
+                gpu_scene.cubes.as_entire_binding(),
+                gpu_scene.materials.as_entire_binding(),
+                lights.as_entire_binding(),
+                texture_info.as_entire_binding(),
+                texels.as_entire_binding(),
+                sky.as_entire_binding(),
+                buffers.output.as_entire_binding(),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(binding, resource)| wgpu::BindGroupEntry { binding: binding as u32, resource })
+            .collect::<Vec<_>>(),
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("trazado") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("trazado"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(width.div_ceil(WORKGROUP_SIZE), height.div_ceil(WORKGROUP_SIZE), 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.output, 0, &buffers.readback, 0, buffers.output.size());
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffers.readback.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| format!("Error esperando a la GPU: {}", e))?;
+        receiver
+            .recv()
+            .map_err(|e| format!("Error leyendo la imagen de la GPU: {}", e))?
+            .map_err(|e| format!("Error leyendo la imagen de la GPU: {}", e))?;
+
+        let frame = {
+            let data = slice.get_mapped_range();
+            let pixels: &[[f32; 4]] = bytemuck::cast_slice(&data);
+            pixels.iter().map(|p| Vector3::new(p[0], p[1], p[2])).collect()
+        };
+        buffers.readback.unmap();
+        Ok(frame)
+    }
+}
@@ -0,0 +1,52 @@
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+
+/// Copia de un modelo colocada en la escena con una traslación, una escala
+/// uniforme y un material propios. El BVH de la escena actúa como nivel
+/// superior sobre las instancias; cada una recorre el BVH interno de la malla
+/// compartida, cargada una sola vez en su espacio local.
+pub struct Instance {
+    mesh: Arc<Mesh>,
+    position: Vector3,
+    scale: f32,
+    material: Arc<Material>,
+    bounds: AABB,
+}
+
+impl Instance {
+    pub fn new(mesh: Arc<Mesh>, position: Vector3, scale: f32, material: Arc<Material>) -> Self {
+        let mesh_bounds = mesh.get_bounds();
+        let bounds = AABB::new(mesh_bounds.min * scale + position, mesh_bounds.max * scale + position);
+        Instance {
+            mesh,
+            position,
+            scale,
+            material,
+            bounds,
+        }
+    }
+}
+
+impl RayIntersect for Instance {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        // Con la dirección sin escalar, la distancia local es la del mundo dividida por la escala
+        let local_origin = (*ray_origin - self.position) / self.scale;
+        let mut intersect = self.mesh.ray_intersect(&local_origin, ray_direction);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+
+        intersect.distance *= self.scale;
+        intersect.point = *ray_origin + *ray_direction * intersect.distance;
+        intersect.material = Arc::clone(&self.material);
+        intersect
+    }
+
+    fn get_bounds(&self) -> AABB {
+        self.bounds
+    }
+}
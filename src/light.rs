@@ -1,6 +1,120 @@
 use raylib::prelude::*;
+use crate::material::srgb_byte_to_linear;
+use crate::world_scale::WorldScale;
 
-/// Representa una fuente de luz puntual en la escena
+/// Cono de un foco: la luz solo ilumina dentro del ángulo exterior,
+/// con transición suave entre el ángulo interior y el exterior
+#[derive(Clone, Copy)]
+pub struct SpotLight {
+    /// Dirección (normalizada) hacia donde apunta el foco
+    pub direction: Vector3,
+
+    /// Coseno del ángulo interior (intensidad completa)
+    pub cos_inner: f32,
+
+    /// Coseno del ángulo exterior (sin luz fuera de él)
+    pub cos_outer: f32,
+}
+
+/// Rectángulo emisor de una luz de área: va de `position - (edge_u + edge_v) / 2`
+/// a `position + (edge_u + edge_v) / 2` y emite por sus dos caras
+#[derive(Clone, Copy)]
+pub struct AreaLight {
+    /// Lado del rectángulo (en bloques), con su largo y dirección
+    pub edge_u: Vector3,
+
+    /// Otro lado del rectángulo, no paralelo a `edge_u`
+    pub edge_v: Vector3,
+
+    /// Normal (normalizada) del rectángulo
+    pub normal: Vector3,
+
+    /// Lado de la grilla de muestras (de 1 a `MAX_AREA_LIGHT_SAMPLES`): se
+    /// lanzan `samples`² rayos de sombra
+    pub samples: u32,
+}
+
+/// Lado de la grilla de muestras por omisión (4×4 = 16 rayos de sombra)
+pub const AREA_LIGHT_SAMPLES: u32 = 4;
+/// Lado máximo de la grilla (8×8 = 64 rayos de sombra por luz y por impacto)
+pub const MAX_AREA_LIGHT_SAMPLES: u32 = 8;
+
+/// Modelo de caída de la intensidad con la distancia (en metros)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Falloff {
+    /// Sin atenuación: la luz llega igual a toda la escena (soles)
+    None,
+    /// Decae linealmente hasta cero en `range`
+    Linear { range: f32 },
+    /// Inverso del cuadrado, recortado suavemente a cero en `radius`
+    InverseSquare { radius: f32 },
+    /// 1 / (1 + 0.05·d + 0.01·d²), el modelo original
+    Smooth,
+}
+
+impl Falloff {
+    /// Factor (0-1) de la luz que llega a `meters` de distancia
+    pub fn attenuation(&self, meters: f32) -> f32 {
+        match *self {
+            Falloff::None => 1.0,
+            Falloff::Linear { range } => (1.0 - meters / range.max(1e-3)).max(0.0),
+            Falloff::InverseSquare { radius } => {
+                let ratio = meters / radius.max(1e-3);
+                let window = (1.0 - ratio.powi(4)).clamp(0.0, 1.0);
+                window * window / (meters * meters + 1.0)
+            }
+            Falloff::Smooth => 1.0 / (1.0 + 0.05 * meters + 0.01 * meters * meters),
+        }
+    }
+}
+
+/// Parpadeo de una luz de fuego (antorchas, lava): varía la intensidad y
+/// mueve ligeramente la posición con el tiempo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Flame {
+    /// Variación relativa de la intensidad (0.2 = ±20%)
+    pub intensity_variation: f32,
+
+    /// Desplazamiento máximo de la posición, en bloques
+    pub position_jitter: f32,
+
+    /// Velocidad del parpadeo
+    pub speed: f32,
+
+    /// Desfase para que luces cercanas no parpadeen al unísono
+    pub seed: f32,
+}
+
+impl Flame {
+    /// Parpadeo rápido y nervioso de una antorcha
+    pub fn torch(seed: f32) -> Self {
+        Flame {
+            intensity_variation: 0.25,
+            position_jitter: 0.06,
+            speed: 9.0,
+            seed,
+        }
+    }
+
+    /// Pulso lento del resplandor de la lava
+    pub fn lava(seed: f32) -> Self {
+        Flame {
+            intensity_variation: 0.2,
+            position_jitter: 0.0,
+            speed: 1.5,
+            seed,
+        }
+    }
+
+    /// Ruido suave en [-1, 1] (suma de senos de frecuencias no múltiplos)
+    fn noise(&self, time: f32, channel: f32) -> f32 {
+        let t = time * self.speed + self.seed + channel * 17.3;
+        0.5 * t.sin() + 0.3 * (t * 2.31 + 1.7).sin() + 0.2 * (t * 5.87 + 4.2).sin()
+    }
+}
+
+/// Representa una fuente de luz puntual (o un foco) en la escena
+#[derive(Clone)]
 pub struct Light {
     /// Posición de la luz en el espacio 3D
     pub position: Vector3,
@@ -10,9 +124,35 @@ pub struct Light {
     
     /// Intensidad de la luz (multiplicador de brillo)
     pub intensity: f32,
+
+    /// Cono del foco; `None` para luces puntuales que iluminan en todas direcciones
+    pub spot: Option<SpotLight>,
+
+    /// Rectángulo emisor; `None` para luces que salen de un solo punto
+    pub area: Option<AreaLight>,
+
+    /// Escala de la escena, para calcular la caída de la luz en metros
+    pub world_scale: WorldScale,
+
+    /// Cómo se atenúa la luz con la distancia
+    pub falloff: Falloff,
+
+    /// Parpadeo de fuego; `None` para luces estables
+    pub flame: Option<Flame>,
+
+    /// La intensidad sigue el pulso del portal (sincronizado con su material)
+    pub portal_pulse: bool,
+
+    /// Luz del cielo (sol o luna): las nubes le proyectan sombras
+    pub cloud_shadows: bool,
 }
 
 impl Light {
+    /// Color de la luz (elegido en sRGB) en el espacio lineal en que se ilumina
+    pub fn linear_color(&self) -> Vector3 {
+        Vector3::new(srgb_byte_to_linear(self.color.r), srgb_byte_to_linear(self.color.g), srgb_byte_to_linear(self.color.b))
+    }
+
     /// Crea una nueva fuente de luz
     /// 
     /// # Argumentos
@@ -24,6 +164,149 @@ impl Light {
             position,
             color,
             intensity,
+            spot: None,
+            area: None,
+            world_scale: WorldScale::default(),
+            falloff: Falloff::Smooth,
+            flame: None,
+            portal_pulse: false,
+            cloud_shadows: false,
+        }
+    }
+
+    /// Crea un foco con caída suave en el borde del cono
+    ///
+    /// # Argumentos
+    /// * `direction` - Hacia dónde apunta el foco (no necesita estar normalizada)
+    /// * `inner_angle` - Semiángulo (radianes) con intensidad completa
+    /// * `outer_angle` - Semiángulo (radianes) donde la luz llega a cero
+    pub fn spot(
+        position: Vector3,
+        direction: Vector3,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: Color,
+        intensity: f32,
+    ) -> Self {
+        let outer_angle = outer_angle.max(inner_angle);
+        Light {
+            position,
+            color,
+            intensity,
+            spot: Some(SpotLight {
+                direction: direction.normalized(),
+                cos_inner: inner_angle.cos(),
+                cos_outer: outer_angle.cos(),
+            }),
+            area: None,
+            world_scale: WorldScale::default(),
+            falloff: Falloff::Smooth,
+            flame: None,
+            portal_pulse: false,
+            cloud_shadows: false,
+        }
+    }
+
+    /// Crea una luz de área rectangular (una ventana, la superficie de un
+    /// portal) centrada en `center`, con lados `edge_u` y `edge_v`. Ilumina
+    /// con varios rayos de sombra repartidos por el rectángulo, así sus
+    /// sombras tienen penumbra en vez del borde duro de una luz puntual.
+    /// Si los lados son nulos o paralelos no hay rectángulo y queda una luz
+    /// puntual en `center`.
+    pub fn area(center: Vector3, edge_u: Vector3, edge_v: Vector3, color: Color, intensity: f32) -> Self {
+        let mut light = Light::new(center, color, intensity);
+        let normal = edge_u.cross(edge_v);
+        if normal.length() > f32::EPSILON {
+            light.area = Some(AreaLight {
+                edge_u,
+                edge_v,
+                normal: normal.normalized(),
+                samples: AREA_LIGHT_SAMPLES,
+            });
+        }
+        light
+    }
+
+    /// Cambia el modelo de atenuación de la luz
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Marca la luz como fuego para que parpadee
+    pub fn with_flame(mut self, flame: Flame) -> Self {
+        self.flame = Some(flame);
+        self
+    }
+
+    /// Copia de la luz en el instante `time`, con el parpadeo aplicado.
+    /// `portal_pulse` es el brillo actual del portal (ver `material::portal_pulse`).
+    pub fn animated(&self, time: f32, portal_pulse: f32) -> Light {
+        let mut light = self.clone();
+        if self.portal_pulse {
+            light.intensity *= portal_pulse;
+        }
+        if let Some(flame) = &self.flame {
+            light.intensity *= (1.0 + flame.intensity_variation * flame.noise(time, 0.0)).max(0.0);
+            light.position += Vector3::new(
+                flame.noise(time, 1.0),
+                flame.noise(time, 2.0) * 0.5,
+                flame.noise(time, 3.0),
+            ) * flame.position_jitter;
+        }
+        light
+    }
+
+    /// Rayos de sombra que lanza la luz: uno para las puntuales
+    pub fn sample_count(&self) -> u32 {
+        self.area.map_or(1, |area| area.samples * area.samples)
+    }
+
+    /// Punto de la luz para la muestra `index` (de `sample_count`): cada
+    /// muestra cae en su propia celda de la grilla del rectángulo, movida por
+    /// `jitter` (en [0, 1)²) dentro de ella para no dejar bandas en la penumbra
+    pub fn sample_point(&self, index: u32, jitter: (f32, f32)) -> Vector3 {
+        let Some(area) = &self.area else {
+            return self.position;
+        };
+        let side = area.samples as f32;
+        let u = ((index % area.samples) as f32 + jitter.0) / side - 0.5;
+        let v = ((index / area.samples) as f32 + jitter.1) / side - 0.5;
+        self.position + area.edge_u * u + area.edge_v * v
+    }
+
+    /// Factor (0-1) de lo que emite el punto `sample` de la luz hacia `point`:
+    /// un rectángulo visto de canto ilumina menos que de frente. Siempre 1 en
+    /// luces puntuales.
+    pub fn emission_factor(&self, sample: &Vector3, point: &Vector3) -> f32 {
+        let Some(area) = &self.area else {
+            return 1.0;
+        };
+        (*point - *sample).normalized().dot(area.normal).abs()
+    }
+
+    /// Atenuación por distancia (en bloques); el modelo trabaja en metros
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        self.falloff.attenuation(self.world_scale.to_meters(distance))
+    }
+
+    /// Factor (0-1) del cono del foco para un punto; siempre 1 en luces puntuales
+    pub fn cone_factor(&self, point: &Vector3) -> f32 {
+        let Some(spot) = &self.spot else {
+            return 1.0;
+        };
+
+        let to_point = (*point - self.position).normalized();
+        let cos_angle = to_point.dot(spot.direction);
+        if cos_angle <= spot.cos_outer {
+            return 0.0;
         }
+        if cos_angle >= spot.cos_inner {
+            return 1.0;
+        }
+
+        // smoothstep entre el borde exterior y el interior
+        let t = (cos_angle - spot.cos_outer) / (spot.cos_inner - spot.cos_outer);
+        t * t * (3.0 - 2.0 * t)
     }
 }
\ No newline at end of file
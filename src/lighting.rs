@@ -0,0 +1,97 @@
+use raylib::prelude::{Color, Vector3};
+use serde::Deserialize;
+
+use crate::skybox;
+
+/// Ambientes de iluminación listos para cualquier escena (tecla A): cada uno
+/// fija la hora del cielo, el color y la fuerza del sol, y tiñe el cielo y la
+/// luz ambiental, sin tocar el código de la escena
+#[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum LightingPreset {
+    /// Sol blanco en lo alto
+    Noon,
+    /// Sol bajo y anaranjado, sombras largas
+    GoldenHour,
+    /// Luna azulada y cielo estrellado
+    Night,
+    /// Cielo gris y luz débil y fría, como de tormenta
+    Storm,
+    /// Resplandor rojizo como el del Nether, también sobre el mundo normal
+    Nether,
+}
+
+impl LightingPreset {
+    /// Siguiente ambiente; después del último se vuelve al ciclo día/noche (`None`)
+    pub fn next(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(LightingPreset::Noon),
+            Some(LightingPreset::Noon) => Some(LightingPreset::GoldenHour),
+            Some(LightingPreset::GoldenHour) => Some(LightingPreset::Night),
+            Some(LightingPreset::Night) => Some(LightingPreset::Storm),
+            Some(LightingPreset::Storm) => Some(LightingPreset::Nether),
+            Some(LightingPreset::Nether) => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LightingPreset::Noon => "Mediodía",
+            LightingPreset::GoldenHour => "Hora dorada",
+            LightingPreset::Night => "Noche",
+            LightingPreset::Storm => "Tormenta",
+            LightingPreset::Nether => "Nether",
+        }
+    }
+
+    /// Hora del cielo (0.5 = mediodía, ver `skybox::sun_direction`)
+    pub fn time_of_day(self) -> f32 {
+        match self {
+            LightingPreset::Noon => 0.5,
+            LightingPreset::GoldenHour => 0.29,
+            LightingPreset::Night => 0.0,
+            LightingPreset::Storm => 0.42,
+            LightingPreset::Nether => 0.9,
+        }
+    }
+
+    /// Luz direccional del cielo: posición, color e intensidad, como
+    /// `DayNightCycle::get_sky_light`. De noche (y en el Nether) la luz viene
+    /// del lado opuesto al sol
+    pub fn sky_light(self, center: Vector3, radius: f32) -> (Vector3, Color, f32) {
+        let sun_dir = skybox::sun_direction(self.time_of_day());
+        let (color, intensity) = match self {
+            LightingPreset::Noon => (Color::new(255, 250, 240, 255), 1.0),
+            LightingPreset::GoldenHour => (Color::new(255, 170, 90, 255), 0.85),
+            LightingPreset::Night => (Color::new(150, 180, 220, 255), 0.35),
+            LightingPreset::Storm => (Color::new(190, 200, 215, 255), 0.45),
+            LightingPreset::Nether => (Color::new(255, 110, 50, 255), 0.5),
+        };
+        let position = if sun_dir.y >= 0.0 { center + sun_dir * radius } else { center - sun_dir * radius };
+        (position, color, intensity)
+    }
+
+    /// Multiplicador (por canal) del cielo procedural del mundo normal
+    pub fn sky_tint(self) -> Vector3 {
+        match self {
+            LightingPreset::Storm => Vector3::new(0.55, 0.57, 0.6),
+            LightingPreset::Nether => Vector3::new(1.4, 0.45, 0.3),
+            _ => Vector3::one(),
+        }
+    }
+
+    /// Multiplicador (por canal) de la luz ambiental del mundo normal
+    pub fn ambient_tint(self) -> Vector3 {
+        match self {
+            LightingPreset::GoldenHour => Vector3::new(1.1, 0.95, 0.8),
+            LightingPreset::Storm => Vector3::new(0.7, 0.75, 0.8),
+            LightingPreset::Nether => Vector3::new(1.6, 0.5, 0.35),
+            _ => Vector3::one(),
+        }
+    }
+
+    /// Las nubes proyectan sombras; con cielo cubierto la luz ya es difusa
+    pub fn cloud_shadows(self) -> bool {
+        !matches!(self, LightingPreset::Storm | LightingPreset::Nether)
+    }
+}
@@ -0,0 +1,183 @@
+use raylib::prelude::Vector3;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::particles::hash01;
+use crate::scene_builder::curve_cells;
+
+/// Largo máximo de la cadena al reescribirla; las generaciones que lo
+/// pasarían no se aplican
+const MAX_SYMBOLS: usize = 20_000;
+
+/// Sistema de Lindenmayer leído de un archivo TOML de `assets/lsystems`: la
+/// cadena inicial se reescribe `iterations` veces con las reglas y el
+/// resultado lo recorre una tortuga en 3D que va dejando bloques.
+///
+/// Símbolos de la tortuga: `F` avanza `step` bloques dejando madera, `G`
+/// avanza dejando hojas, `f` avanza sin dejar nada, `L` deja una copa de
+/// hojas, `+`/`-` giran a izquierda y derecha, `&`/`^` inclinan hacia abajo
+/// y hacia arriba, `\`/`/` rotan sobre el eje de avance, `|` da media vuelta
+/// y `[`/`]` guardan y recuperan la posición. Los demás símbolos solo sirven
+/// para las reglas.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LSystem {
+    pub axiom: String,
+    /// Cada símbolo con sus reemplazos posibles; si hay varios se elige uno
+    /// al azar por símbolo según la semilla
+    #[serde(default)]
+    pub rules: HashMap<String, Vec<String>>,
+    pub iterations: u32,
+    /// Ángulo de cada giro, en grados
+    pub angle: f32,
+    /// Bloques que avanza cada paso; por defecto 1
+    pub step: Option<i32>,
+    /// Material de `F`; por defecto `wood`
+    pub wood: Option<String>,
+    /// Material de `G` y de `L`; por defecto `leaves`
+    pub leaves: Option<String>,
+    /// Radio horizontal de las copas de `L`; por defecto 1.5
+    pub leaf_radius: Option<f32>,
+    /// Radio vertical de las copas de `L`; por defecto el mismo que el horizontal
+    pub leaf_height: Option<f32>,
+    /// La tortuga empieza mirando hacia abajo, para enredaderas que cuelgan
+    #[serde(default)]
+    pub hanging: bool,
+}
+
+/// Bloques que deja la tortuga, en celdas del mundo
+pub struct LSystemPlan {
+    pub wood: Vec<(i32, i32, i32)>,
+    pub leaves: Vec<(i32, i32, i32)>,
+}
+
+/// Posición y orientación de la tortuga: hacia dónde avanza, su izquierda y su arriba
+#[derive(Clone, Copy)]
+struct Turtle {
+    position: Vector3,
+    heading: Vector3,
+    left: Vector3,
+    up: Vector3,
+}
+
+impl LSystem {
+    /// Material de la madera de `F`
+    pub fn wood_material(&self) -> &str {
+        self.wood.as_deref().unwrap_or("wood")
+    }
+
+    /// Material de las hojas de `G` y de `L`
+    pub fn leaf_material(&self) -> &str {
+        self.leaves.as_deref().unwrap_or("leaves")
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Error abriendo {}: {}", path, e))?;
+        let system: LSystem = toml::from_str(&text).map_err(|e| format!("Error leyendo {}: {}", path, e))?;
+        if let Some(symbol) = system.rules.keys().find(|symbol| symbol.chars().count() != 1) {
+            return Err(format!("Error leyendo {}: la regla `{}` debe ser de un solo símbolo", path, symbol));
+        }
+        if let Some((symbol, _)) = system.rules.iter().find(|(_, options)| options.is_empty()) {
+            return Err(format!("Error leyendo {}: la regla `{}` no tiene reemplazos", path, symbol));
+        }
+        Ok(system)
+    }
+
+    /// Cadena después de aplicar las reglas; cada símbolo con varios
+    /// reemplazos elige el suyo según la semilla, la generación y su lugar
+    fn expand(&self, seed: u32) -> String {
+        let rules: HashMap<char, &Vec<String>> = self.rules.iter().filter_map(|(symbol, options)| Some((symbol.chars().next()?, options))).collect();
+        let mut current = self.axiom.clone();
+        for generation in 0..self.iterations {
+            let mut next = String::with_capacity(current.len() * 2);
+            for (i, symbol) in current.chars().enumerate() {
+                match rules.get(&symbol) {
+                    Some(options) => {
+                        let pick = hash01(i as i32, generation as i32, seed as i32, 31) * options.len() as f32;
+                        next.push_str(&options[(pick as usize).min(options.len() - 1)]);
+                    }
+                    None => next.push(symbol),
+                }
+            }
+            if next.len() > MAX_SYMBOLS {
+                println!("  L-system: la generación {} pasa de {} símbolos, se corta ahí", generation + 1, MAX_SYMBOLS);
+                break;
+            }
+            current = next;
+        }
+        current
+    }
+
+    /// Hace crecer el sistema desde la celda `base`; la semilla y la celda
+    /// eligen entre los reemplazos de las reglas y el giro inicial alrededor
+    /// de la vertical, así cada planta sale distinta
+    pub fn grow(&self, base: (i32, i32, i32), seed: u32) -> LSystemPlan {
+        let seed = seed
+            ^ (base.0 as u32).wrapping_mul(0x9e37_79b9)
+            ^ (base.1 as u32).wrapping_mul(0x85eb_ca6b)
+            ^ (base.2 as u32).wrapping_mul(0xc2b2_ae35);
+        let angle = self.angle.to_radians();
+        let spin = hash01(base.0, base.2, seed as i32, 32) * std::f32::consts::TAU;
+        let vertical = if self.hanging { -1.0 } else { 1.0 };
+        let mut turtle = Turtle {
+            position: Vector3::new(base.0 as f32, base.1 as f32, base.2 as f32),
+            heading: Vector3::new(0.0, vertical, 0.0),
+            left: Vector3::new(spin.cos(), 0.0, spin.sin()),
+            up: Vector3::new(-spin.sin(), 0.0, spin.cos()) * vertical,
+        };
+        let mut stack: Vec<Turtle> = Vec::new();
+        let mut plan = LSystemPlan { wood: Vec::new(), leaves: Vec::new() };
+        let step = self.step.unwrap_or(1).max(1) as f32;
+
+        for symbol in self.expand(seed).chars() {
+            match symbol {
+                'F' | 'G' | 'f' => {
+                    let (start, end) = (turtle.position, turtle.position + turtle.heading * step);
+                    if symbol != 'f' {
+                        let cells = curve_cells(step, |t| start + (end - start) * t).into_iter().map(|[x, y, z]| (x, y, z));
+                        if symbol == 'F' { plan.wood.extend(cells) } else { plan.leaves.extend(cells) }
+                    }
+                    turtle.position = end;
+                }
+                'L' => plan.leaves.extend(self.canopy(turtle.position)),
+                '+' => (turtle.heading, turtle.left) = rotate(turtle.heading, turtle.left, angle),
+                '-' => (turtle.heading, turtle.left) = rotate(turtle.heading, turtle.left, -angle),
+                '&' => (turtle.heading, turtle.up) = rotate(turtle.heading, turtle.up, -angle),
+                '^' => (turtle.heading, turtle.up) = rotate(turtle.heading, turtle.up, angle),
+                '\\' => (turtle.left, turtle.up) = rotate(turtle.left, turtle.up, angle),
+                '/' => (turtle.left, turtle.up) = rotate(turtle.left, turtle.up, -angle),
+                '|' => (turtle.heading, turtle.left) = (turtle.heading * -1.0, turtle.left * -1.0),
+                '[' => stack.push(turtle),
+                ']' => turtle = stack.pop().unwrap_or(turtle),
+                _ => {}
+            }
+        }
+        plan
+    }
+
+    /// Celdas de una copa elipsoidal alrededor de `center`
+    fn canopy(&self, center: Vector3) -> Vec<(i32, i32, i32)> {
+        let radius = self.leaf_radius.unwrap_or(1.5).max(0.5);
+        let height = self.leaf_height.unwrap_or(radius).max(0.5);
+        let (reach, rise) = (radius.ceil() as i32, height.ceil() as i32);
+        let (cx, cy, cz) = (center.x.round() as i32, center.y.round() as i32, center.z.round() as i32);
+        let mut cells = Vec::new();
+        for x in cx - reach..=cx + reach {
+            for y in cy - rise..=cy + rise {
+                for z in cz - reach..=cz + reach {
+                    let (dx, dy, dz) = ((x as f32 - center.x) / radius, (y as f32 - center.y) / height, (z as f32 - center.z) / radius);
+                    if dx * dx + dy * dy + dz * dz <= 1.0 {
+                        cells.push((x, y, z));
+                    }
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// Gira el par de ejes `a` y `b` (perpendiculares) `angle` radianes, de `a` hacia `b`
+fn rotate(a: Vector3, b: Vector3, angle: f32) -> (Vector3, Vector3) {
+    let (sin, cos) = angle.sin_cos();
+    (a * cos + b * sin, b * cos - a * sin)
+}
@@ -1,6 +1,5 @@
 use raylib::prelude::*;
 use rayon::prelude::*;
-use std::f32::consts::PI;
 use std::sync::{Arc, Mutex};
 
 mod framebuffer;
@@ -10,21 +9,117 @@ mod light;
 mod material;
 mod cube;
 mod texture;
+mod texture_animation;
+mod procedural_textures;
+mod colormap;
+mod compressed_texture;
+mod resource_pack;
 mod mesh;
 mod scene_builder;
 mod scenes;
 mod skybox;
-
-use framebuffer::Framebuffer;
-use ray_intersect::{Intersect, RayIntersect, BVH};
-use camera::Camera;
-use light::Light;
-use material::{Material, vector3_to_color};
-use texture::TextureManager;
-use scenes::{SceneInfo, load_scene};
-
-const ORIGIN_BIAS: f32 = 1e-4;
-const MAX_DEPTH: u32 = 2;
+mod screenshot;
+mod sphere;
+mod cylinder;
+mod cone;
+mod partial_block;
+mod transform;
+mod material_preview;
+mod material_editor;
+mod accumulation;
+mod world_scale;
+mod backdrop;
+mod cubemap;
+mod block_target;
+mod block_editor;
+mod stats;
+mod scene_sheet;
+mod camera_path;
+mod camera_bookmarks;
+mod split_view;
+mod cache;
+mod particles;
+mod volume;
+mod instance;
+mod renderer;
+mod ray_tree;
+mod voxel_grid;
+mod chunks;
+mod sampler;
+mod bench;
+mod render_job;
+mod scene_file;
+mod scene_script;
+mod nbt;
+mod schematic;
+mod noise;
+mod terrain;
+mod biome;
+mod caves;
+mod village;
+mod bridge;
+mod trees;
+mod lsystem;
+mod lighting;
+mod built_scene;
+mod reprojection;
+mod gpu;
+
+use framebuffer::{Framebuffer, Upscale};
+use ray_intersect::{RayIntersect, TraversalStats, BVH};
+use camera::{Camera, Projection};
+use camera_path::{CameraPath, Showcase};
+use camera_bookmarks::{CameraBookmarks, START_SLOT};
+use light::{Falloff, Light};
+use material::{portal_pulse, vector3_to_color};
+use texture::{EnvironmentMap, TextureManager};
+use scenes::{SceneInfo, load_scene_data};
+use screenshot::RenderMetadata;
+use skybox::Environment;
+use built_scene::{BuiltScene, SceneData};
+use lighting::LightingPreset;
+use backdrop::{Backdrop, BackdropProjection, BackdropView};
+use cubemap::Cubemap;
+use block_target::BlockTarget;
+use block_editor::BlockEditor;
+use stats::{SceneStats, StatsRecorder};
+use cache::SceneCache;
+use material_editor::MaterialEditor;
+use accumulation::AccumulationBuffer;
+use renderer::{FOV, Ray, RenderSettings, Renderer, Scene};
+use ray_tree::RayTree;
+use voxel_grid::VoxelGrid;
+use chunks::{CHUNK_SIZE, ChunkedWorld};
+use sampler::SamplerKind;
+use render_job::RenderJob;
+use scene_file::{SceneFile, SCENE_FILE_NUM};
+use reprojection::FrameHistory;
+use gpu::{GpuRenderer, GpuScene};
+
+/// Escalas del render respecto del tamaño de la ventana (teclas F1-F4)
+const RENDER_SCALES: [f32; 4] = [0.5, 0.75, 1.0, 2.0];
+/// Muestras por pixel que acumula el render progresivo con la imagen quieta
+const MAX_ACCUMULATED_SAMPLES: u32 = 64;
+/// Pasadas máximas con muestreo adaptativo: como los pixels convergidos dejan
+/// de trazarse, las zonas ruidosas pueden seguir recibiendo muestras
+const MAX_ADAPTIVE_SAMPLES: u32 = 256;
+/// Tiempo de trazado por frame de la ventana; una pasada más larga sigue en
+/// los frames siguientes para que la interfaz no se congele
+const FRAME_BUDGET: std::time::Duration = std::time::Duration::from_millis(16);
+/// Intensidad máxima del sol del ciclo día/noche (sin atenuación)
+const SUN_LIGHT_INTENSITY: f32 = 1.0;
+/// Intensidad de la luz de luna con la luna en lo alto
+const MOON_LIGHT_INTENSITY: f32 = 0.35;
+/// Imagen de fondo que se carga con la tecla B
+const BACKDROP_PATH: &str = "assets/backdrop.png";
+/// Directorio del cielo de seis caras (tecla C)
+const SKYBOX_DIR: &str = "assets/skybox";
+/// Altura del plano que recibe sombras sobre el fondo (tecla G)
+const SHADOW_CATCHER_HEIGHT: f32 = 0.0;
+/// Distancia (en metros) que avanza la cámara por segundo con W/S
+const CAMERA_ZOOM_METERS: f32 = 15.0;
+/// Medio lado (en bloques) de la caja de la cámara al chocar con bloques
+const CAMERA_COLLISION_RADIUS: f32 = 0.2;
 
 lazy_static::lazy_static! {
     static ref TEXTURE_MANAGER: Arc<Mutex<TextureManager>> = 
@@ -53,26 +148,19 @@ impl DayNightCycle {
     }
 
     fn get_sun_position(&self, center: Vector3, radius: f32) -> Vector3 {
-        let angle = (self.time / self.cycle_duration) * std::f32::consts::PI * 2.0;
-        Vector3::new(
-            center.x + angle.cos() * radius,
-            center.y + angle.sin() * radius,
-            center.z,
-        )
+        center + skybox::sun_direction(self.get_time_of_day()) * radius
     }
 
+    /// Se apaga al ponerse el sol, para dar paso a la luna
     fn get_sun_intensity(&self) -> f32 {
-        let angle = (self.time / self.cycle_duration) * std::f32::consts::PI * 2.0;
-        (angle.sin() * 0.5 + 0.5).max(0.2)
+        let elevation = skybox::sun_direction(self.get_time_of_day()).y;
+        (elevation * 0.5 + 0.5) * (elevation * 5.0).clamp(0.0, 1.0)
     }
 
     fn get_sun_color(&self) -> Color {
         let time_of_day = self.get_time_of_day();
         
-        if time_of_day < 0.2 || time_of_day > 0.85 {
-            // Noche - luz de luna azulada
-            Color::new(150, 180, 220, 255)
-        } else if time_of_day < 0.3 || (time_of_day > 0.7 && time_of_day < 0.85) {
+        if time_of_day < 0.3 || (time_of_day > 0.7 && time_of_day < 0.85) {
             // Amanecer/Atardecer - naranja/rosa
             Color::new(255, 180, 120, 255)
         } else if time_of_day < 0.35 || (time_of_day > 0.65 && time_of_day < 0.7) {
@@ -83,206 +171,339 @@ impl DayNightCycle {
             Color::new(255, 250, 240, 255)
         }
     }
-}
 
+    /// Luz direccional del cielo: el sol de día y una luna tenue y azulada de noche.
+    /// Devuelve posición, color e intensidad.
+    fn get_sky_light(&self, center: Vector3, radius: f32) -> (Vector3, Color, f32) {
+        let sun_dir = skybox::sun_direction(self.get_time_of_day());
+        if sun_dir.y >= 0.0 {
+            return (
+                self.get_sun_position(center, radius),
+                self.get_sun_color(),
+                self.get_sun_intensity() * SUN_LIGHT_INTENSITY,
+            );
+        }
 
-#[inline]
-fn offset_origin(intersect: &Intersect, direction: &Vector3) -> Vector3 {
-    let offset = intersect.normal * ORIGIN_BIAS;
-    if direction.dot(intersect.normal) < 0.0 {
-        intersect.point - offset
-    } else {
-        intersect.point + offset
+        let moon_intensity = skybox::night_factor(self.get_time_of_day()) * MOON_LIGHT_INTENSITY;
+        (center - sun_dir * radius, Color::new(150, 180, 220, 255), moon_intensity)
     }
 }
 
-#[inline]
-fn reflect(incident: &Vector3, normal: &Vector3) -> Vector3 {
-    *incident - *normal * 2.0 * incident.dot(*normal)
+
+fn write_pixels(framebuffer: &mut Framebuffer, pixels: &[Color]) {
+    write_pixels_from(framebuffer, 0, pixels);
 }
 
-#[inline]
-fn refract(incident: &Vector3, normal: &Vector3, eta: f32) -> Option<Vector3> {
-    let cos_i = -incident.dot(*normal).max(-1.0).min(1.0);
-    let sin_t2 = eta * eta * (1.0 - cos_i * cos_i);
-    
-    if sin_t2 > 1.0 {
-        return None;
+/// Escribe `pixels` a partir del pixel `first` (contando fila por fila)
+fn write_pixels_from(framebuffer: &mut Framebuffer, first: usize, pixels: &[Color]) {
+    let width = framebuffer.width as usize;
+    for (i, color) in (first..).zip(pixels) {
+        let x = (i % width) as u32;
+        let y = (i / width) as u32;
+        framebuffer.set_current_color(*color);
+        framebuffer.set_pixel(x, y);
     }
-    
-    let cos_t = (1.0 - sin_t2).sqrt();
-    Some(*incident * eta + *normal * (eta * cos_i - cos_t))
 }
 
-fn cast_shadow(
-    intersect: &Intersect,
-    light: &Light,
-    bvh: &BVH,
-    objects: &[Arc<dyn RayIntersect + Send + Sync>],
-) -> f32 {
-    let light_dir = (light.position - intersect.point).normalized();
-    let light_distance = (light.position - intersect.point).length();
-    let shadow_ray_origin = offset_origin(intersect, &light_dir);
+/// Tamaño del framebuffer para renderizar a `scale` del tamaño de la ventana
+fn scaled_size(window_width: i32, window_height: i32, scale: f32) -> (u32, u32) {
+    (
+        ((window_width as f32 * scale).round() as u32).max(1),
+        ((window_height as f32 * scale).round() as u32).max(1),
+    )
+}
 
-    let shadow_intersect = bvh.intersect(&shadow_ray_origin, &light_dir, objects);
-    
-    if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-        return 0.6;
+/// Pixel del framebuffer bajo el mouse; con otra escala de render el
+/// framebuffer no mide lo mismo que la ventana
+fn mouse_pixel(window: &RaylibHandle, framebuffer: &Framebuffer) -> Option<(u32, u32)> {
+    let mouse = window.get_mouse_position();
+    let x = mouse.x * framebuffer.width as f32 / window.get_screen_width() as f32;
+    let y = mouse.y * framebuffer.height as f32 / window.get_screen_height() as f32;
+    (x >= 0.0 && y >= 0.0 && (x as u32) < framebuffer.width && (y as u32) < framebuffer.height)
+        .then_some((x as u32, y as u32))
+}
+
+/// Luces con el parpadeo de fuego aplicado en el instante `time`.
+/// Con el ciclo pausado el tiempo no avanza, así que el render progresivo
+/// sigue acumulando sobre la misma imagen.
+fn animate_lights(lights: &[Light], time: f32, portal_pulse: f32) -> Vec<Light> {
+    lights.iter().map(|light| light.animated(time, portal_pulse)).collect()
+}
+
+/// Fija la hora del ciclo y la luz del cielo (`lights[0]`) según el ambiente
+/// de iluminación; el ciclo queda pausado mientras el ambiente esté activo
+fn apply_lighting(preset: LightingPreset, day_night: &mut DayNightCycle, lights: &mut [Light]) {
+    day_night.time = preset.time_of_day() * day_night.cycle_duration;
+    if let Some(sky) = lights.first_mut() {
+        let (position, color, intensity) = preset.sky_light(Vector3::new(0.0, 0.0, 0.0), 50.0);
+        sky.position = position;
+        sky.color = color;
+        sky.intensity = intensity;
+        sky.falloff = Falloff::None;
     }
-    0.0
 }
 
-pub fn cast_ray(
-    ray_origin: &Vector3,
-    ray_direction: &Vector3,
-    bvh: &BVH,
-    objects: &[Arc<dyn RayIntersect + Send + Sync>],
-    lights: &[Light],
-    depth: u32,
+/// Entorno (cielo, luz ambiental y fondo opcional) para el frame actual
+fn frame_environment<'a>(
     time_of_day: f32,
     absolute_time: f32,
-) -> Vector3 {
-    if depth > MAX_DEPTH {
-        let is_nether = ray_origin.y < 0.0;
-        return skybox::sky_color(*ray_direction, is_nether, time_of_day, absolute_time);
+    backdrop: Option<&'a Backdrop>,
+    environment_map: Option<&'a EnvironmentMap>,
+    cubemap: Option<&'a Cubemap>,
+    shadow_catcher: bool,
+    lighting: Option<LightingPreset>,
+    camera: &Camera,
+    framebuffer: &Framebuffer,
+) -> Environment<'a> {
+    let mut environment = Environment::new(time_of_day, absolute_time);
+    let portal_phase = TEXTURE_MANAGER.lock().unwrap().animation_phase("portal").unwrap_or(0.0);
+    environment.portal_pulse = portal_pulse(absolute_time, portal_phase);
+    if let Some(map) = environment_map {
+        environment = environment.with_environment_map(map);
     }
-
-    let intersect = bvh.intersect(ray_origin, ray_direction, objects);
-
-    if !intersect.is_intersecting {
-        let is_nether = ray_origin.y < 0.0;
-        return skybox::sky_color(*ray_direction, is_nether, time_of_day, absolute_time);
+    if let Some(cubemap) = cubemap {
+        environment = environment.with_cubemap(cubemap);
     }
-
-    let mut diffuse_color = intersect.material.diffuse;
-    if let Some(ref texture_name) = intersect.material.texture_path {
-        let texture_color = TEXTURE_MANAGER.lock().unwrap().sample(texture_name, intersect.u, intersect.v);
-        diffuse_color = texture_color;
+    if let Some(preset) = lighting {
+        environment = environment.with_lighting(preset);
     }
+    match backdrop {
+        Some(backdrop) => {
+            let aspect_ratio = framebuffer.width as f32 / framebuffer.height as f32;
+            let view = BackdropView::new(backdrop, camera, aspect_ratio, (FOV * 0.5).tan());
+            environment.with_backdrop(view, shadow_catcher.then_some(SHADOW_CATCHER_HEIGHT))
+        }
+        None => environment,
+    }
+}
 
-    let view_dir = (*ray_origin - intersect.point).normalized();
-    let mut final_color = Vector3::zero();
-
-    let ambient = if intersect.point.y < 0.0 {
-        Vector3::new(0.05, 0.01, 0.01)
-    } else {
-        Vector3::new(0.12, 0.12, 0.15)
-    };
-    final_color = final_color + diffuse_color * ambient;
-
-    if intersect.material.emissive.length() > 0.0 {
-        final_color = final_color + intersect.material.emissive * 0.5;
+/// Bloque en el centro de la pantalla con una estimación de la luz que recibe.
+/// Usa un solo rayo (más uno de sombra por luz), así que se puede calcular
+/// cada frame sin esperar al render completo.
+fn target_block(renderer: &Renderer, camera: &Camera, framebuffer: &Framebuffer) -> Option<BlockTarget> {
+    let (width, height) = (framebuffer.width as usize, framebuffer.height as usize);
+    let ray = Ray::primary(camera, width as f32 * 0.5, height as f32 * 0.5, width, height);
+    let hit = renderer.intersect(&ray);
+    if !hit.is_intersecting {
+        return None;
     }
 
-    for light in lights {
-        let light_dir = (light.position - intersect.point).normalized();
-        let diffuse_dot = intersect.normal.dot(light_dir);
-        
-        if diffuse_dot <= 0.0 {
+    let scene = renderer.scene();
+    let mut irradiance = scene.environment.ambient.irradiance(&hit.normal, hit.point.y < 0.0) + hit.material.linear_emissive() * 0.5;
+    for light in scene.lights {
+        let light_dir = (light.position - hit.point).normalized();
+        let diffuse_dot = hit.normal.dot(light_dir);
+        let cone = light.cone_factor(&hit.point);
+        if diffuse_dot <= 0.0 || cone <= 0.0 {
             continue;
         }
 
-        let distance = (light.position - intersect.point).length();
-        let attenuation = 1.0 / (1.0 + 0.05 * distance + 0.01 * distance * distance);
+        let attenuation = light.attenuation((light.position - hit.point).length());
+        irradiance += light.linear_color() * renderer.shadow_transmission(&hit, light.position)
+            * (light.intensity * attenuation * cone * diffuse_dot);
+    }
 
-        let shadow_intensity = cast_shadow(&intersect, light, bvh, objects);
-        let light_intensity = light.intensity * (1.0 - shadow_intensity) * attenuation;
+    Some(BlockTarget::new(&hit, irradiance))
+}
 
-        let light_color_v3 = Vector3::new(
-            light.color.r as f32 / 255.0,
-            light.color.g as f32 / 255.0,
-            light.color.b as f32 / 255.0,
-        );
+/// Abre el backend de GPU, o avisa por qué se sigue trazando en la CPU
+fn start_gpu() -> Option<GpuRenderer> {
+    match GpuRenderer::new() {
+        Ok(gpu) => {
+            println!("Trazado en GPU: {}", gpu.adapter_name());
+            Some(gpu)
+        }
+        Err(e) => {
+            println!("GPU no disponible ({}); se traza en la CPU", e);
+            None
+        }
+    }
+}
 
-        let diffuse = diffuse_color * light_color_v3 * diffuse_dot * light_intensity;
+/// Radiancia del frame en la GPU si está activa y puede dibujar la escena;
+/// `None` (también si falla) para trazarlo en la CPU
+fn gpu_radiance(
+    renderer: &Renderer,
+    gpu: Option<(&mut GpuRenderer, &GpuScene)>,
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> Option<Vec<Vector3>> {
+    let (gpu, gpu_scene) = gpu?;
+    if !GpuRenderer::supports(renderer.scene(), gpu_scene) {
+        return None;
+    }
+    match gpu.render(gpu_scene, renderer.scene(), camera, settings) {
+        Ok(frame) => Some(frame),
+        Err(e) => {
+            println!("{}; se traza en la CPU", e);
+            None
+        }
+    }
+}
 
-        let reflect_dir = reflect(&-light_dir, &intersect.normal);
-        let specular_intensity = view_dir
-            .dot(reflect_dir)
-            .max(0.0)
-            .powf(intersect.material.specular)
-            * light_intensity;
-        let specular = light_color_v3 * specular_intensity;
+/// Mapa de entorno HDR configurado para la escena, o `None` para el cielo procedural
+fn scene_environment_map(scene_info: &SceneInfo) -> Option<Arc<EnvironmentMap>> {
+    let path = scene_info.environment_map?;
+    match TEXTURE_MANAGER.lock().unwrap().load_environment_map(path) {
+        Ok(map) => Some(map),
+        Err(e) => {
+            println!("{} - se usa el cielo procedural", e);
+            None
+        }
+    }
+}
 
-        final_color = final_color + diffuse * intersect.material.albedo[0] 
-                                  + specular * intersect.material.albedo[1];
+/// Vistas de depuración disponibles (tecla H)
+#[derive(Clone, Copy, PartialEq)]
+enum DebugView {
+    Off,
+    BvhNodes,
+    BvhPrimitives,
+}
+
+impl DebugView {
+    fn next(self) -> Self {
+        match self {
+            DebugView::Off => DebugView::BvhNodes,
+            DebugView::BvhNodes => DebugView::BvhPrimitives,
+            DebugView::BvhPrimitives => DebugView::Off,
+        }
     }
 
-    let refractivity = intersect.material.transparency;
-    if refractivity > 0.05 && depth < MAX_DEPTH {
-        let refraction_index = intersect.material.refraction_index;
-        let (eta, adjusted_normal) = if ray_direction.dot(intersect.normal) < 0.0 {
-            (1.0 / refraction_index, intersect.normal)
-        } else {
-            (refraction_index, -intersect.normal)
-        };
+    fn name(self) -> &'static str {
+        match self {
+            DebugView::Off => "Desactivada",
+            DebugView::BvhNodes => "Heatmap BVH (nodos)",
+            DebugView::BvhPrimitives => "Heatmap BVH (primitivas)",
+        }
+    }
+}
+
+/// Estructuras de aceleración para trazar la escena (tecla U)
+#[derive(Clone, Copy, PartialEq)]
+enum Acceleration {
+    Bvh,
+    VoxelGrid,
+    Chunks,
+}
 
-        if let Some(refract_dir) = refract(ray_direction, &adjusted_normal, eta) {
-            let refract_origin = offset_origin(&intersect, &refract_dir);
-            let refract_color = cast_ray(&refract_origin, &refract_dir, bvh, objects, lights, depth + 1, time_of_day, absolute_time);
-            final_color = final_color * (1.0 - refractivity) + refract_color * refractivity;
+impl Acceleration {
+    fn next(self) -> Self {
+        match self {
+            Acceleration::Bvh => Acceleration::VoxelGrid,
+            Acceleration::VoxelGrid => Acceleration::Chunks,
+            Acceleration::Chunks => Acceleration::Bvh,
         }
     }
 
-    let reflectivity = intersect.material.reflectivity;
-    if reflectivity > 0.05 && depth < MAX_DEPTH {
-        let reflect_dir = reflect(ray_direction, &intersect.normal).normalized();
-        let reflect_origin = offset_origin(&intersect, &reflect_dir);
-        let reflect_color = cast_ray(&reflect_origin, &reflect_dir, bvh, objects, lights, depth + 1, time_of_day, absolute_time);
-        final_color = final_color * (1.0 - reflectivity) + reflect_color * reflectivity;
+    fn name(self) -> &'static str {
+        match self {
+            Acceleration::Bvh => "BVH",
+            Acceleration::VoxelGrid => "grilla de vóxeles (DDA)",
+            Acceleration::Chunks => "chunks de 16³ con un BVH por chunk",
+        }
     }
+}
 
-    final_color
+/// Mapa de color azul → cian → verde → amarillo → rojo para `t` en [0, 1]
+fn heatmap_color(t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let (r, g, b) = if t < 0.25 {
+        (0.0, t / 0.25, 1.0)
+    } else if t < 0.5 {
+        (0.0, 1.0, 1.0 - (t - 0.25) / 0.25)
+    } else if t < 0.75 {
+        ((t - 0.5) / 0.25, 1.0, 0.0)
+    } else {
+        (1.0, 1.0 - (t - 0.75) / 0.25, 0.0)
+    };
+    Color::new((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 255)
 }
 
-pub fn render(
+/// Colorea cada pixel según el trabajo que hizo su rayo primario en el BVH
+fn render_heatmap(
     framebuffer: &mut Framebuffer,
     bvh: &BVH,
     objects: &[Arc<dyn RayIntersect + Send + Sync>],
     camera: &Camera,
-    lights: &[Light],
-    time_of_day: f32,
-    absolute_time: f32,
+    view: DebugView,
 ) {
     let width = framebuffer.width as usize;
     let height = framebuffer.height as usize;
-    let aspect_ratio = width as f32 / height as f32;
-    let fov = PI / 2.0;
-    let perspective_scale = (fov * 0.5).tan();
 
-    let pixels: Vec<Color> = (0..height)
+    let counts: Vec<u32> = (0..height)
         .into_par_iter()
         .flat_map(|y| {
-            let mut row_colors = Vec::with_capacity(width);
+            let mut row_counts = Vec::with_capacity(width);
             for x in 0..width {
-                let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
-                let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
+                let ray = Ray::primary(camera, x as f32, y as f32, width, height);
+                let mut stats = TraversalStats::default();
+                bvh.intersect_with_stats(&ray.origin, &ray.direction, objects, &mut stats);
+
+                row_counts.push(match view {
+                    DebugView::BvhPrimitives => stats.primitive_tests,
+                    _ => stats.node_tests,
+                });
+            }
+            row_counts
+        })
+        .collect();
 
-                let screen_x = screen_x * aspect_ratio * perspective_scale;
-                let screen_y = screen_y * perspective_scale;
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1);
+    let avg_count = counts.iter().map(|&c| c as f64).sum::<f64>() / counts.len().max(1) as f64;
+    println!("{}: máximo {} | promedio {:.1} por rayo", view.name(), max_count, avg_count);
 
-                let ray_direction = Vector3::new(screen_x, screen_y, -1.0).normalized();
-                let rotated_direction = camera.basis_change(&ray_direction);
+    let pixels: Vec<Color> = counts
+        .iter()
+        .map(|&c| heatmap_color(c as f32 / max_count as f32))
+        .collect();
 
-                let pixel_color_v3 = cast_ray(&camera.eye, &rotated_direction, bvh, objects, lights, 0, time_of_day, absolute_time);
-                let pixel_color = vector3_to_color(pixel_color_v3);
+    write_pixels(framebuffer, &pixels);
+}
 
-                row_colors.push(pixel_color);
+/// Objetos y luces de la escena elegida, con su entrada de caché; la escena
+/// `SCENE_FILE_NUM` sale del archivo. El BVH y el atlas de texturas se leen
+/// de la caché si están guardados; si no, se arman y se guardan.
+fn load_current_scene(scene_num: i32, scene_file: Option<&SceneFile>) -> (BuiltScene, SceneCache) {
+    let data = load_current_scene_data(scene_num, scene_file);
+    let cache = SceneCache::new(scene_num, &data.objects, &data.lights);
+    let scene = match cache.load_bvh(data.objects.len()) {
+        Some(bvh) => {
+            println!("BVH leído de la caché ({})", cache.key());
+            BuiltScene::with_bvh(data, bvh)
+        }
+        None => {
+            let scene = BuiltScene::new(data);
+            if let Err(e) = cache.store_bvh(&scene.bvh) {
+                println!("Error guardando caché de escena: {}", e);
             }
-            row_colors
-        })
-        .collect();
+            scene
+        }
+    };
+    if !cache.load_atlas() {
+        if let Err(e) = cache.store_atlas() {
+            println!("Error guardando caché de escena: {}", e);
+        }
+    }
+    (scene, cache)
+}
 
-    for (i, color) in pixels.iter().enumerate() {
-        let x = (i % width) as u32;
-        let y = (i / width) as u32;
-        framebuffer.set_current_color(*color);
-        framebuffer.set_pixel(x, y);
+/// Objetos y luces de la escena elegida, sin construir el BVH
+fn load_current_scene_data(scene_num: i32, scene_file: Option<&SceneFile>) -> SceneData {
+    match scene_file {
+        Some(file) if scene_num == SCENE_FILE_NUM => file.data(),
+        _ => load_scene_data(scene_num),
     }
 }
 
-fn print_scene_info(scene_num: i32, obj_count: usize, light_count: usize) {
-    let info = SceneInfo::get(scene_num);
+fn current_scene_info(scene_num: i32, scene_file: Option<&SceneFile>) -> SceneInfo {
+    match scene_file {
+        Some(file) if scene_num == SCENE_FILE_NUM => file.info(),
+        _ => SceneInfo::get(scene_num),
+    }
+}
+
+fn print_scene_info(scene_num: i32, info: &SceneInfo, obj_count: usize, light_count: usize) {
     println!("╔════════════════════════════════════════╗");
     println!("║  ESCENA CARGADA: {:2}                 ║", scene_num);
     println!("╠════════════════════════════════════════╣");
@@ -293,6 +514,116 @@ fn print_scene_info(scene_num: i32, obj_count: usize, light_count: usize) {
     println!("╚════════════════════════════════════════╝");
 }
 
+fn scene_stats(
+    scene_num: i32,
+    scene_name: &str,
+    objects: &[Arc<dyn RayIntersect + Send + Sync>],
+    lights: &[Light],
+    bvh: &BVH,
+    load_time: f32,
+    bvh_build_time: f32,
+) -> SceneStats {
+    SceneStats {
+        scene_num,
+        scene_name: scene_name.to_string(),
+        object_count: objects.len(),
+        light_count: lights.len(),
+        bvh: bvh.metrics(),
+        load_time,
+        bvh_build_time,
+        frame_times: Vec::new(),
+    }
+}
+
+/// Guarda la miniatura y los metadatos de la escena la primera vez que se
+/// renderiza con su contenido actual
+fn update_scene_cache(
+    scene_cache: &mut Option<SceneCache>,
+    framebuffer: &Framebuffer,
+    scene_name: &str,
+    objects: &[Arc<dyn RayIntersect + Send + Sync>],
+    lights: &[Light],
+    bvh: &BVH,
+) {
+    let Some(cache) = scene_cache.take() else {
+        return;
+    };
+    if cache.has_thumbnail() {
+        return;
+    }
+
+    match cache.store(framebuffer, scene_name, objects.len(), lights.len(), &bvh.metrics()) {
+        Ok(()) => println!("Caché de escena actualizada ({})", cache.key()),
+        Err(e) => println!("Error guardando caché de escena: {}", e),
+    }
+}
+
+/// Lleva las estructuras de aceleración al estado de `objects` después de
+/// editar bloques: el BVH reajusta las cajas de `changed` e inserta los
+/// objetos agregados al final, y en chunks solo se reconstruyen los afectados
+fn apply_block_edit(
+    bvh: &mut BVH,
+    chunked_world: &mut Option<ChunkedWorld>,
+    objects: &[Arc<dyn RayIntersect + Send + Sync>],
+    changed: &[usize],
+) {
+    for &index in changed {
+        bvh.mark_dirty(index);
+    }
+    bvh.refit(objects);
+    bvh.insert_new(objects);
+    update_chunks(chunked_world, objects, changed);
+}
+
+/// Reconstruye solo los chunks de los objetos cambiados y de los agregados al final
+fn update_chunks(chunked_world: &mut Option<ChunkedWorld>, objects: &[Arc<dyn RayIntersect + Send + Sync>], changed: &[usize]) {
+    if let Some(world) = chunked_world
+        && let Err(e) = world.update(objects, changed)
+    {
+        println!("Error actualizando chunks: {}", e);
+        *chunked_world = None;
+    }
+}
+
+/// Cámara con la que se abre la escena: la del casillero 1 si hay una guardada
+fn start_camera(info: &SceneInfo, bookmarks: &CameraBookmarks) -> Camera {
+    bookmarks.get(START_SLOT).cloned().unwrap_or_else(|| info.camera())
+}
+
+/// Casillero de cámara (1 a 9) cuyo número se presionó en este frame
+fn pressed_slot(window: &RaylibHandle) -> Option<usize> {
+    const DIGIT_KEYS: [KeyboardKey; camera_bookmarks::BOOKMARK_SLOTS] = [
+        KeyboardKey::KEY_ONE,
+        KeyboardKey::KEY_TWO,
+        KeyboardKey::KEY_THREE,
+        KeyboardKey::KEY_FOUR,
+        KeyboardKey::KEY_FIVE,
+        KeyboardKey::KEY_SIX,
+        KeyboardKey::KEY_SEVEN,
+        KeyboardKey::KEY_EIGHT,
+        KeyboardKey::KEY_NINE,
+    ];
+    DIGIT_KEYS.iter().position(|&key| window.is_key_pressed(key)).map(|index| index + 1)
+}
+
+/// Acerca o aleja la cámara salvo que su caja entre en un bloque en el que
+/// no estaba, para no atravesar paredes con W/S. Devuelve si se movió.
+fn zoom_camera(camera: &mut Camera, scene: &BuiltScene, amount: f32) -> bool {
+    let around = |eye: Vector3| {
+        let half = Vector3::one() * CAMERA_COLLISION_RADIUS;
+        ray_intersect::AABB::new(eye - half, eye + half)
+    };
+    let mut moved = camera.clone();
+    moved.zoom(amount);
+    // Si la cámara ya está dentro de un bloque se la deja salir de él
+    let current: Vec<_> = scene.blocks_in_aabb(&around(camera.eye)).iter().map(|block| block.cell).collect();
+    if scene.blocks_in_aabb(&around(moved.eye)).iter().any(|block| !current.contains(&block.cell)) {
+        return false;
+    }
+    *camera = moved;
+    true
+}
+
 fn get_time_description(time_of_day: f32) -> &'static str {
     if time_of_day < 0.2 {
         "Noche"
@@ -319,6 +650,43 @@ fn get_time_description(time_of_day: f32) -> &'static str {
 
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // Tamaño y semilla de las texturas procedurales (las que no están en disco)
+    match procedural_textures::texture_args(&args) {
+        Ok(Some((size, seed))) => TEXTURE_MANAGER.lock().unwrap().regenerate_procedural(size, seed),
+        Ok(None) => {}
+        Err(e) => println!("{}", e),
+    }
+    // Paquete de recursos de Minecraft: reemplaza las texturas de los bloques
+    if let Some(pack) = bench::arg_value(&args, "--resource-pack") {
+        match resource_pack::load(&pack) {
+            Ok(count) => println!("Paquete de texturas {}: {} texturas reemplazadas", pack, count),
+            Err(e) => println!("{}", e),
+        }
+    }
+    // Benchmark sin ventana: renderiza cada escena y sale
+    match bench::bench_args(&args) {
+        Ok(Some(options)) => {
+            match bench::run(&options) {
+                Ok(()) => println!("Resultados del benchmark guardados en {}", options.output),
+                Err(e) => println!("{}", e),
+            }
+            return;
+        }
+        Ok(None) => {}
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    }
+    let mut stats = stats::stats_out_arg(&args).map(|path| StatsRecorder::new(&path));
+    if args.iter().any(|arg| arg == "--clear-cache") {
+        match cache::clear_cache() {
+            Ok(()) => println!("Caché borrada: {}", cache::CACHE_DIR),
+            Err(e) => println!("{}", e),
+        }
+    }
+
     let window_width = 800;
     let window_height = 600;
 
@@ -354,30 +722,65 @@ fn main() {
     println!("│ [=] Portal Dimensional                 │");
     println!("└────────────────────────────────────────┘\n");
 
-    let mut scene_choice = 1;
+    // Escena descrita en un archivo (`--scene-file`), que se recarga al guardarlo
+    let mut scene_file = bench::arg_value(&args, "--scene-file").and_then(|path| match SceneFile::load(&path) {
+        Ok(file) => {
+            println!("Escena cargada desde {} (se recarga al guardar el archivo)", path);
+            Some(file)
+        }
+        Err(e) => {
+            println!("{}", e);
+            None
+        }
+    });
+    let mut scene_choice = if scene_file.is_some() { SCENE_FILE_NUM } else { 1 };
     
-    let (mut objects, mut lights) = load_scene(scene_choice);
-    print_scene_info(scene_choice, objects.len(), lights.len());
+    let load_start = std::time::Instant::now();
+    let (mut scene, cache) = load_current_scene(scene_choice, scene_file.as_ref());
+    // El BVH se construye (o se lee de la caché) junto con la escena
+    let bvh_build_time = scene.bvh_build_time();
+    let load_time = load_start.elapsed().as_secs_f32() - bvh_build_time;
+    let scene_info = current_scene_info(scene_choice, scene_file.as_ref());
+    print_scene_info(scene_choice, &scene_info, scene.objects.len(), scene.lights.len());
     
-    let scene_info = SceneInfo::get(scene_choice);
-    let mut camera = Camera::new(
-        scene_info.camera_pos,
-        scene_info.camera_target,
-        Vector3::new(0.0, 1.0, 0.0),
-    );
+    // Cámaras guardadas de la escena (Ctrl+1-9 / Shift+1-9); la del casillero 1 reemplaza a la inicial
+    let mut bookmarks = CameraBookmarks::for_scene(scene_choice, scene_file.as_ref().map(|file| file.path()));
+    let mut camera = start_camera(&scene_info, &bookmarks);
     
-    println!("Construyendo BVH...");
-    let bvh_start = std::time::Instant::now();
-    let mut bvh = BVH::build(&objects);
-    println!("BVH construido en {:.3}s\n", bvh_start.elapsed().as_secs_f32());
-
-    let rotation_speed = PI / 60.0;
-    let zoom_speed = 0.5;
+    println!("BVH construido en {:.3}s\n", bvh_build_time);
+    if let Some(stats) = &mut stats {
+        stats.begin_scene(scene_stats(scene_choice, scene_info.name, &scene.objects, &scene.lights, &scene.bvh, load_time, bvh_build_time));
+    }
+    let mut scene_cache = Some(cache);
+
+    let mut zoom_speed = scene_info.world_scale.to_blocks(CAMERA_ZOOM_METERS);
+    let mut environment_map = scene_environment_map(&scene_info);
+    // Ambiente de iluminación fijo (tecla A); `None` sigue el ciclo día/noche
+    let mut lighting = scene_info.lighting;
+    if let Some(preset) = lighting {
+        apply_lighting(preset, &mut day_night, &mut scene.lights);
+        println!("Iluminación: {}", preset.name());
+    }
 
     println!("Renderizando primera imagen...");
     let render_start = std::time::Instant::now();
-    render(&mut framebuffer, &bvh, &objects, &camera, &lights, day_night.get_time_of_day(), absolute_time);
-    println!("Renderizado inicial: {:.3}s\n", render_start.elapsed().as_secs_f32());
+    {
+        let environment = Environment::new(day_night.get_time_of_day(), absolute_time);
+        let environment = match lighting {
+            Some(preset) => environment.with_lighting(preset),
+            None => environment,
+        };
+        let renderer = Renderer::new(Scene { bvh: &scene.bvh, objects: &scene.objects, grid: None, chunks: None, lights: &scene.lights, environment: &environment });
+        let frame = renderer.render_radiance(&camera, &RenderSettings::new(framebuffer.width, framebuffer.height));
+        let pixels: Vec<Color> = frame.into_iter().map(vector3_to_color).collect();
+        write_pixels(&mut framebuffer, &pixels);
+    }
+    let mut last_render_time = render_start.elapsed().as_secs_f32();
+    println!("Renderizado inicial: {:.3}s\n", last_render_time);
+    if let Some(stats) = &mut stats {
+        stats.record_frame(last_render_time);
+    }
+    update_scene_cache(&mut scene_cache, &framebuffer, current_scene_info(scene_choice, scene_file.as_ref()).name, &scene.objects, &scene.lights, &scene.bvh);
 
     window.set_target_fps(30);
 
@@ -388,17 +791,75 @@ fn main() {
     println!("║  ← →       : Rotar horizontalmente    ║");
     println!("║  ↑ ↓       : Rotar verticalmente      ║");
     println!("║  W S       : Zoom in/out              ║");
+    println!("║  , .       : Inclinar (roll)          ║");
     println!("║  R         : Reset cámara             ║");
+    println!("║  Ctrl/Shift+1-9 : Guardar/ir a cámara ║");
+    println!("║  D         : Perspectiva / orto / iso ║");
     println!("║  P         : Pausar ciclo día/noche   ║");
     println!("║  [         : Adelantar tiempo         ║");
     println!("║  ]         : Retroceder tiempo        ║");
+    println!("║  A         : Ambiente de iluminación  ║");
     println!("║  E         : Exportar texturas        ║");
+    println!("║  M         : Hoja de materiales       ║");
+    println!("║  V         : Hoja de vistas de escena ║");
+    println!("║  F6 / F7   : Keyframe / render camino ║");
+    println!("║  F8        : Demostración (órbita)    ║");
+    println!("║  F9        : Pantalla dividida/Nether ║");
+    println!("║  Tab/Click : Editor de materiales     ║");
+    println!("║  Z         : Editar bloques (mouse)   ║");
+    println!("║  H         : Heatmap del BVH          ║");
+    println!("║  F         : Filtro de fireflies      ║");
+    println!("║  B / G     : Fondo / plano de sombras ║");
+    println!("║  C         : Cielo cubemap            ║");
+    println!("║  Clic der. : Árbol de rayos (X borra) ║");
+    println!("║  U         : Grilla de vóxeles / BVH  ║");
+    println!("║  T         : Trazado en GPU / CPU     ║");
+    println!("║  Y         : Reproyección temporal    ║");
+    println!("║  F12       : Guardar captura          ║");
     println!("║  ESC       : Salir                    ║");
     println!("╚════════════════════════════════════════╝\n");
 
     let mut frame_count = 0;
     let mut total_render_time = 0.0;
-    let mut paused = false;
+    let mut paused = lighting.is_some();
+    let mut debug_view = DebugView::Off;
+    let mut material_editor = MaterialEditor::new();
+    let mut block_editor = BlockEditor::new();
+    block_editor.reset(&mut scene);
+    let mut accumulation = AccumulationBuffer::new(framebuffer.width as usize, framebuffer.height as usize);
+    let mut backdrop: Option<Backdrop> = None;
+    let mut backdrop_projection = BackdropProjection::Camera;
+    let mut shadow_catcher = false;
+    let mut cubemap: Option<Cubemap> = None;
+    let mut ray_tree: Option<RayTree> = None;
+    // Keyframes del recorrido de cámara (teclas F6/F7)
+    let mut camera_path = CameraPath::new();
+    // Modo demostración (tecla F8): la cámara gira sola
+    let mut showcase: Option<Showcase> = None;
+    // Pantalla dividida (tecla F9): mundo normal a la izquierda y Nether a la derecha
+    let mut split_screen = false;
+    // Grilla de vóxeles o mundo en chunks como alternativa al BVH (tecla U);
+    // se construyen al activarlos
+    let mut acceleration = Acceleration::Bvh;
+    let mut voxel_grid: Option<VoxelGrid> = None;
+    let mut chunked_world: Option<ChunkedWorld> = None;
+    // Trazado en GPU (tecla T o `--gpu`); los bloques se suben al activarlo
+    let mut gpu = if args.iter().any(|arg| arg == "--gpu") { start_gpu() } else { None };
+    let mut gpu_scene: Option<GpuScene> = None;
+    // Resolución interna del render (teclas F1-F4) y filtro para llevarla a la ventana (tecla N)
+    let mut render_scale = 1.0;
+    let mut upscale = Upscale::Nearest;
+    // Desplazamientos dentro del pixel de las muestras del render progresivo (tecla Q)
+    let mut sampler = SamplerKind::BlueNoise;
+    // Pasada de render en curso, repartida entre frames
+    let mut render_job: Option<RenderJob> = None;
+    // Imagen en pantalla con la distancia de cada pixel: al mover la cámara se
+    // reproyecta y solo se trazan los huecos (tecla Y)
+    let mut reprojection = true;
+    let mut history: Option<FrameHistory> = None;
+    let mut holes: Vec<usize> = Vec::new();
+    // Si lo trazado desde que empezó la imagen actual leyó alguna textura animada
+    let mut shows_animated = false;
 
     while !window.window_should_close() {
         let current_time = std::time::Instant::now();
@@ -408,14 +869,47 @@ fn main() {
         if !paused {
             day_night.update(delta_time);
             absolute_time += delta_time;
-            TEXTURE_MANAGER.lock().unwrap().update(delta_time);
         }
         
-        let mut needs_render = false;
+        // El agua, la lava y los portales cambian de cuadro con el tiempo, también
+        // con el ciclo en pausa: si la imagen los muestra se vuelve a trazar (la
+        // pasada sigue desde su fila)
+        let (frame_advanced, animated_sampled) = {
+            let mut texture_manager = TEXTURE_MANAGER.lock().unwrap();
+            (texture_manager.update(delta_time), texture_manager.take_animated_sampled())
+        };
+        shows_animated |= animated_sampled;
+        let mut needs_render = frame_advanced && shows_animated;
+        // Si lo único que cambió es la cámara, el frame anterior se puede reproyectar
+        let mut camera_moved = false;
+        let control = window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL)
+            || window.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+        let shift = window.is_key_down(KeyboardKey::KEY_LEFT_SHIFT)
+            || window.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT);
 
         if window.is_key_pressed(KeyboardKey::KEY_P) {
             paused = !paused;
             println!("Day/Night Cycle: {}", if paused { "Pausado" } else { "Activo" });
+            // Al reanudar el ciclo se deja el ambiente fijo
+            if !paused && lighting.take().is_some() {
+                println!("Iluminación: ciclo día/noche");
+            }
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_A) {
+            lighting = LightingPreset::next(lighting);
+            match lighting {
+                Some(preset) => {
+                    apply_lighting(preset, &mut day_night, &mut scene.lights);
+                    paused = true;
+                    println!("Iluminación: {}", preset.name());
+                }
+                None => {
+                    paused = false;
+                    println!("Iluminación: ciclo día/noche");
+                }
+            }
+            needs_render = true;
         }
 
         if window.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET) {
@@ -430,7 +924,44 @@ fn main() {
             needs_render = true;
         }
 
-        let new_scene = if window.is_key_pressed(KeyboardKey::KEY_ONE) { Some(1) }
+        // Al guardar el archivo de escena se vuelve a importar (manteniendo la cámara)
+        let scene_file_changed = match &mut scene_file {
+            Some(file) => match file.reload_if_changed() {
+                Ok(changed) => {
+                    if changed {
+                        println!("Archivo de escena modificado, recargando {}", file.path());
+                    }
+                    changed
+                }
+                Err(e) => {
+                    println!("{}", e);
+                    false
+                }
+            },
+            None => false,
+        };
+
+        // Con Ctrl o Shift los números son casilleros de cámara, no escenas
+        let bookmark_keys = control || shift;
+        if bookmark_keys && let Some(slot) = pressed_slot(&window) {
+            if control {
+                match bookmarks.store(slot, &camera) {
+                    Ok(()) => println!("Cámara guardada en el casillero {} ({})", slot, bookmarks.path()),
+                    Err(e) => println!("{}", e),
+                }
+            } else if let Some(saved) = bookmarks.get(slot) {
+                camera = saved.clone();
+                println!("Cámara del casillero {}", slot);
+                needs_render = true;
+                camera_moved = true;
+            } else {
+                println!("El casillero {} no tiene cámara guardada (Ctrl+{} guarda la actual)", slot, slot);
+            }
+        }
+
+        let new_scene = if scene_file_changed { Some(SCENE_FILE_NUM) }
+            else if bookmark_keys { None }
+            else if window.is_key_pressed(KeyboardKey::KEY_ONE) { Some(1) }
             else if window.is_key_pressed(KeyboardKey::KEY_TWO) { Some(2) }
             else if window.is_key_pressed(KeyboardKey::KEY_THREE) { Some(3) }
             else if window.is_key_pressed(KeyboardKey::KEY_FOUR) { Some(4) }
@@ -445,73 +976,168 @@ fn main() {
             else { None };
 
         if let Some(new_scene_num) = new_scene {
-            if new_scene_num != scene_choice {
+            if new_scene_num != scene_choice || scene_file_changed {
+                let keep_camera = scene_file_changed && scene_choice == SCENE_FILE_NUM;
                 scene_choice = new_scene_num;
-                
-                println!("\n╔════════════════════════════════════════╗");
-                println!("║       CAMBIANDO DE ESCENA...           ║");
-                println!("╚════════════════════════════════════════╝\n");
-                
                 let start = std::time::Instant::now();
-                (objects, lights) = load_scene(scene_choice);
-                print_scene_info(scene_choice, objects.len(), lights.len());
+                // Al volver a guardar el archivo solo se aplica lo que cambió
+                let update = scene_file.as_ref().filter(|_| keep_camera).map(|file| file.update(&mut scene));
+                if let Some(diff) = update.as_ref().filter(|diff| !diff.rebuilt) {
+                    println!(
+                        "Escena actualizada en {:.3}s: {} objetos nuevos y {} quitados, sin reconstruir el BVH",
+                        start.elapsed().as_secs_f32(),
+                        diff.added,
+                        diff.removed
+                    );
+                    update_chunks(&mut chunked_world, &scene.objects, &diff.changed);
+                    voxel_grid = None;
+                    gpu_scene = None;
+                    scene_cache = Some(SceneCache::new(scene_choice, &scene.objects, &scene.lights));
+                    ray_tree = None;
+                    block_editor.reset(&mut scene);
+                    let scene_info = current_scene_info(scene_choice, scene_file.as_ref());
+                    zoom_speed = scene_info.world_scale.to_blocks(CAMERA_ZOOM_METERS);
+                    lighting = scene_info.lighting.or(lighting);
+                    if let Some(preset) = lighting {
+                        apply_lighting(preset, &mut day_night, &mut scene.lights);
+                    }
+                    needs_render = true;
+                } else {
+                    println!("\n╔════════════════════════════════════════╗");
+                    println!("║       CAMBIANDO DE ESCENA...           ║");
+                    println!("╚════════════════════════════════════════╝\n");
                 
-                println!("Reconstruyendo BVH...");
-                let bvh_start = std::time::Instant::now();
-                bvh = BVH::build(&objects);
-                println!("BVH reconstruido en {:.3}s", bvh_start.elapsed().as_secs_f32());
+                    let cache = match update {
+                        Some(_) => SceneCache::new(scene_choice, &scene.objects, &scene.lights),
+                        None => {
+                            let (loaded, cache) = load_current_scene(scene_choice, scene_file.as_ref());
+                            scene = loaded;
+                            cache
+                        }
+                    };
+                    let bvh_build_time = scene.bvh_build_time();
+                    let load_time = start.elapsed().as_secs_f32() - bvh_build_time;
+                    let scene_info = current_scene_info(scene_choice, scene_file.as_ref());
+                    print_scene_info(scene_choice, &scene_info, scene.objects.len(), scene.lights.len());
                 
-                let scene_info = SceneInfo::get(scene_choice);
-                camera = Camera::new(
-                    scene_info.camera_pos,
-                    scene_info.camera_target,
-                    Vector3::new(0.0, 1.0, 0.0),
-                );
+                    voxel_grid = None;
+                    chunked_world = None;
+                    gpu_scene = None;
+                    println!("BVH reconstruido en {:.3}s", bvh_build_time);
+                    if let Some(stats) = &mut stats {
+                        stats.begin_scene(scene_stats(scene_choice, scene_info.name, &scene.objects, &scene.lights, &scene.bvh, load_time, bvh_build_time));
+                    }
+                    scene_cache = Some(cache);
+                    ray_tree = None;
+                    block_editor.reset(&mut scene);
                 
-                println!("Tiempo total: {:.3}s\n", start.elapsed().as_secs_f32());
+                    if !keep_camera {
+                        bookmarks = CameraBookmarks::for_scene(scene_choice, scene_file.as_ref().map(|file| file.path()));
+                        camera = start_camera(&scene_info, &bookmarks);
+                        // La demostración sigue en la escena nueva, oscilando alrededor de su cámara
+                        if let Some(current) = &mut showcase {
+                            *current = Showcase::new(&camera, current.day_night);
+                        }
+                    }
+                    zoom_speed = scene_info.world_scale.to_blocks(CAMERA_ZOOM_METERS);
+                    environment_map = scene_environment_map(&scene_info);
+                    // El ambiente elegido se conserva entre escenas salvo que la nueva traiga el suyo
+                    lighting = scene_info.lighting.or(lighting);
+                    if let Some(preset) = lighting {
+                        apply_lighting(preset, &mut day_night, &mut scene.lights);
+                        paused = true;
+                        println!("Iluminación: {}", preset.name());
+                    }
                 
-                needs_render = true;
-                frame_count = 0;
-                total_render_time = 0.0;
+                    println!("Tiempo total: {:.3}s\n", start.elapsed().as_secs_f32());
+                
+                    needs_render = true;
+                    frame_count = 0;
+                    total_render_time = 0.0;
+                }
             }
         }
 
-        if window.is_key_down(KeyboardKey::KEY_LEFT) {
-            camera.orbit(rotation_speed, 0.0);
-            needs_render = true;
-        }
-        if window.is_key_down(KeyboardKey::KEY_RIGHT) {
-            camera.orbit(-rotation_speed, 0.0);
-            needs_render = true;
+        // Las teclas piden una velocidad y la cámara la alcanza (y la pierde al
+        // soltarlas) de a poco, con el tiempo real entre frames
+        let axis = |positive: KeyboardKey, negative: KeyboardKey| {
+            window.is_key_down(positive) as i32 as f32 - window.is_key_down(negative) as i32 as f32
+        };
+        let (yaw, pitch, roll, zoom) = (
+            axis(KeyboardKey::KEY_LEFT, KeyboardKey::KEY_RIGHT),
+            axis(KeyboardKey::KEY_DOWN, KeyboardKey::KEY_UP),
+            axis(KeyboardKey::KEY_PERIOD, KeyboardKey::KEY_COMMA),
+            axis(KeyboardKey::KEY_W, KeyboardKey::KEY_S),
+        );
+        camera.steer(yaw, pitch, roll, zoom, zoom_speed, delta_time);
+
+        // Demostración: F8 la activa, con el ciclo de día y noche la segunda vez;
+        // mover la cámara a mano la detiene
+        if window.is_key_pressed(KeyboardKey::KEY_F8) {
+            showcase = match showcase {
+                None => Some(Showcase::new(&camera, false)),
+                Some(current) if !current.day_night => Some(Showcase::new(&camera, true)),
+                Some(_) => None,
+            };
+            match &showcase {
+                Some(current) if current.day_night => {
+                    // El ciclo corre aunque hubiera un ambiente fijo o estuviera pausado
+                    paused = false;
+                    lighting = None;
+                    println!("Demostración: órbita con ciclo día/noche");
+                }
+                Some(_) => println!("Demostración: órbita"),
+                None => println!("Demostración detenida"),
+            }
         }
-        if window.is_key_down(KeyboardKey::KEY_UP) {
-            camera.orbit(0.0, -rotation_speed);
-            needs_render = true;
+        if showcase.is_some() && (yaw != 0.0 || pitch != 0.0 || roll != 0.0 || zoom != 0.0) {
+            showcase = None;
+            println!("Demostración detenida");
         }
-        if window.is_key_down(KeyboardKey::KEY_DOWN) {
-            camera.orbit(0.0, rotation_speed);
+        if let Some(current) = &mut showcase {
+            current.update(&mut camera, delta_time);
             needs_render = true;
+            camera_moved = true;
         }
-        if window.is_key_down(KeyboardKey::KEY_W) {
-            camera.zoom(zoom_speed);
-            needs_render = true;
-        }
-        if window.is_key_down(KeyboardKey::KEY_S) {
-            camera.zoom(-zoom_speed);
+
+        if camera.is_moving() {
+            let zoom = camera.glide(delta_time);
+            if zoom != 0.0 && !zoom_camera(&mut camera, &scene, zoom) {
+                camera.zoom_velocity = 0.0;
+            }
             needs_render = true;
+            camera_moved = true;
         }
         
         if window.is_key_pressed(KeyboardKey::KEY_R) {
-            let scene_info = SceneInfo::get(scene_choice);
-            camera = Camera::new(
-                scene_info.camera_pos,
-                scene_info.camera_target,
-                Vector3::new(0.0, 1.0, 0.0),
-            );
+            let scene_info = current_scene_info(scene_choice, scene_file.as_ref());
+            camera = start_camera(&scene_info, &bookmarks);
             println!("Cámara reseteada");
             needs_render = true;
         }
 
+        // Proyección: perspectiva → ortográfica → isométrica (sin deformar lo que hay en el centro)
+        if window.is_key_pressed(KeyboardKey::KEY_D) {
+            let name = match camera.projection {
+                Projection::Perspective => {
+                    camera.projection = Projection::Orthographic { extent: camera.matching_extent() };
+                    "Ortográfica"
+                }
+                Projection::Orthographic { extent } => {
+                    let isometric = Camera::isometric(camera.center, (camera.center - camera.eye).length(), extent);
+                    if camera.forward.dot(isometric.forward) < 0.999 {
+                        camera = isometric.with_roll(camera.roll);
+                        "Isométrica"
+                    } else {
+                        camera.projection = Projection::Perspective;
+                        "Perspectiva"
+                    }
+                }
+            };
+            println!("Proyección: {}", name);
+            needs_render = true;
+        }
+
         if window.is_key_pressed(KeyboardKey::KEY_E) {
             println!("Exportando texturas...");
             TEXTURE_MANAGER.lock().unwrap()
@@ -519,44 +1145,607 @@ fn main() {
             println!("Texturas exportadas a assets/textures_exported/");
         }
 
+        if window.is_key_pressed(KeyboardKey::KEY_TAB) {
+            material_editor.open = !material_editor.open;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_Z) && !control {
+            block_editor.open = !block_editor.open;
+            println!("Edición de bloques: {}", if block_editor.open { "Activa" } else { "Desactivada" });
+        }
+
+        // Edición de bloques: los clics quitan, colocan o toman el bloque bajo el mouse
+        if block_editor.open {
+            let wheel = window.get_mouse_wheel_move();
+            if wheel != 0.0 {
+                block_editor.cycle(-wheel.signum() as i32);
+            }
+
+            let pick = mouse_pixel(&window, &framebuffer)
+                .and_then(|pixel| block_editor.pick(&scene, &camera, pixel, framebuffer.width, framebuffer.height));
+            let edit = if control && window.is_key_pressed(KeyboardKey::KEY_Z) && !shift {
+                block_editor.undo(&mut scene).ok_or("No hay ediciones para deshacer".to_string())
+            } else if control && (window.is_key_pressed(KeyboardKey::KEY_Y) || window.is_key_pressed(KeyboardKey::KEY_Z)) {
+                block_editor.redo(&mut scene).ok_or("No hay ediciones para rehacer".to_string())
+            } else {
+                match pick {
+                    Some(pick) if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT) => block_editor.remove(&mut scene, &pick),
+                    Some(pick) if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) => block_editor.place(&mut scene, &pick),
+                    _ => Ok(Vec::new()),
+                }
+            };
+
+            match edit {
+                Ok(changed) if !changed.is_empty() => {
+                    let edit_start = std::time::Instant::now();
+                    apply_block_edit(&mut scene.bvh, &mut chunked_world, &scene.objects, &changed);
+                    println!("Estructuras de aceleración actualizadas en {:.3}s", edit_start.elapsed().as_secs_f32());
+                    voxel_grid = None;
+                    gpu_scene = None;
+                    needs_render = true;
+                }
+                Ok(_) => {}
+                Err(e) => println!("{}", e),
+            }
+
+            if window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_MIDDLE)
+                && let Some((x, y)) = mouse_pixel(&window, &framebuffer)
+            {
+                let ray = Ray::primary(&camera, x as f32, y as f32, framebuffer.width as usize, framebuffer.height as usize);
+                let intersect = scene.bvh.intersect(&ray.origin, &ray.direction, &scene.objects);
+                if intersect.is_intersecting && block_editor.select(&intersect.material) {
+                    println!("Material para colocar: {}", block_editor.selected_name());
+                }
+            }
+        }
+
+        if !block_editor.open
+            && window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+            && let Some((x, y)) = mouse_pixel(&window, &framebuffer)
+        {
+            let ray = Ray::primary(
+                &camera, x as f32, y as f32, framebuffer.width as usize, framebuffer.height as usize,
+            );
+            let intersect = scene.bvh.intersect(&ray.origin, &ray.direction, &scene.objects);
+            if intersect.is_intersecting {
+                material_editor.select(&intersect.material);
+            }
+        }
+
+        // Clic derecho: registra el árbol de rayos del pixel y lo exporta
+        if !block_editor.open
+            && window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT)
+            && let Some((x, y)) = mouse_pixel(&window, &framebuffer)
+        {
+            let environment = frame_environment(
+                day_night.get_time_of_day(),
+                absolute_time,
+                backdrop.as_ref(),
+                environment_map.as_deref(),
+                cubemap.as_ref(),
+                shadow_catcher,
+                lighting,
+                &camera,
+                &framebuffer,
+            );
+            let frame_lights = animate_lights(&scene.lights, absolute_time, environment.portal_pulse);
+            let renderer = Renderer::new(Scene { bvh: &scene.bvh, objects: &scene.objects, grid: voxel_grid.as_ref(), chunks: chunked_world.as_ref(), lights: &frame_lights, environment: &environment });
+            let ray = Ray::primary(&camera, x as f32, y as f32, framebuffer.width as usize, framebuffer.height as usize);
+            let tree = RayTree::new((x, y), renderer.trace_tree(&ray));
+            match tree.export() {
+                Ok(path) => println!("Árbol de rayos del pixel ({}, {}): {} rayos -> {}", x, y, tree.root.count(), path),
+                Err(e) => println!("{}", e),
+            }
+            ray_tree = Some(tree);
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_X) {
+            ray_tree = None;
+        }
+
+        if material_editor.open {
+            let step = if shift { 5.0 } else { 1.0 };
+
+            if window.is_key_pressed(KeyboardKey::KEY_I) {
+                material_editor.prev_field();
+            }
+            if window.is_key_pressed(KeyboardKey::KEY_K) {
+                material_editor.next_field();
+            }
+
+            let delta = if window.is_key_pressed(KeyboardKey::KEY_J) { -step }
+                else if window.is_key_pressed(KeyboardKey::KEY_L) { step }
+                else { 0.0 };
+
+            if delta != 0.0 {
+                let texture_names = TEXTURE_MANAGER.lock().unwrap().texture_names();
+                if material_editor.adjust(delta, &texture_names) {
+                    // Los materiales quedan fijos en los objetos: se arma la escena otra
+                    // vez y el editor de bloques pone los objetos nuevos en el lugar de
+                    // los actuales, conservando sus ediciones, el BVH (solo se reajustan
+                    // las cajas que cambiaron) y las luces del ciclo día/noche
+                    let data = load_current_scene_data(scene_choice, scene_file.as_ref());
+                    match block_editor.rebase(&mut scene, data) {
+                        Some(changed) => update_chunks(&mut chunked_world, &scene.objects, &changed),
+                        None => chunked_world = None,
+                    }
+                    voxel_grid = None;
+                    gpu_scene = None;
+                    needs_render = true;
+                }
+            }
+
+            if window.is_key_pressed(KeyboardKey::KEY_F5) {
+                match material_editor.save() {
+                    Ok(count) => println!("{} materiales guardados en {}", count, material_editor::MATERIAL_FILE),
+                    Err(e) => println!("Error guardando materiales: {}", e),
+                }
+            }
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_M) {
+            println!("Renderizando vistas previas de materiales...");
+            match material_preview::render_material_sheet("renders/materials") {
+                Ok(count) => println!("{} materiales guardados en renders/materials/", count),
+                Err(e) => println!("Error renderizando materiales: {}", e),
+            }
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_V) {
+            println!("Renderizando vistas de la escena...");
+            match scene_sheet::render_scene_sheet(
+                scene_choice,
+                &current_scene_info(scene_choice, scene_file.as_ref()),
+                &scene.bvh,
+                &scene.objects,
+                &scene.lights,
+                day_night.get_time_of_day(),
+                absolute_time,
+            ) {
+                Ok(path) => println!("Hoja de vistas guardada: {}", path),
+                Err(e) => println!("Error renderizando vistas: {}", e),
+            }
+        }
+
+        // Recorrido de cámara: F6 agrega la vista como keyframe (Shift+F6 los
+        // borra); F7 lo renderiza a PNGs, Shift+F7 una vuelta alrededor de la vista
+        if window.is_key_pressed(KeyboardKey::KEY_F6) {
+            if shift {
+                camera_path.clear();
+                println!("Keyframes borrados");
+            } else {
+                camera_path.add_keyframe(&camera);
+                println!("Keyframe {} agregado", camera_path.len());
+            }
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_F7) {
+            let turntable = CameraPath::turntable(&camera);
+            let path = if shift { &turntable } else { &camera_path };
+            println!("Renderizando recorrido de cámara ({} frames)...", path.frame_count());
+            match camera_path::render_path(
+                path,
+                scene_choice,
+                &scene.bvh,
+                &scene.objects,
+                &scene.lights,
+                day_night.get_time_of_day(),
+                absolute_time,
+            ) {
+                Ok(dir) => println!("Frames guardados en {}", dir),
+                Err(e) => println!("Error renderizando recorrido: {}", e),
+            }
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_F9) {
+            split_screen = !split_screen;
+            println!("Pantalla dividida (mundo normal / Nether): {}", if split_screen { "Activa" } else { "Desactivada" });
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_H) {
+            debug_view = debug_view.next();
+            println!("Vista de depuración: {}", debug_view.name());
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_F12) {
+            let write_sidecar = shift;
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let path = format!("renders/escena_{}_{}.png", scene_choice, timestamp);
+            let metadata = RenderMetadata {
+                scene_num: scene_choice,
+                scene_name: current_scene_info(scene_choice, scene_file.as_ref()).name.to_string(),
+                camera_eye: camera.eye,
+                camera_center: camera.center,
+                time_of_day: day_night.get_time_of_day(),
+                absolute_time,
+                samples: accumulation.samples().max(1),
+                render_time: last_render_time,
+            };
+
+            match screenshot::save_render(&framebuffer, &path, &metadata, write_sidecar) {
+                Ok(()) => println!("Captura guardada: {}", path),
+                Err(e) => println!("Error guardando captura: {}", e),
+            }
+        }
+
         if !paused {
-            if !lights.is_empty() {
-                let sun_pos = day_night.get_sun_position(Vector3::new(0.0, 0.0, 0.0), 50.0);
-                let sun_intensity = day_night.get_sun_intensity();
-                let sun_color = day_night.get_sun_color();
+            if !scene.lights.is_empty() {
+                let (sky_pos, sky_color, sky_intensity) = day_night.get_sky_light(Vector3::new(0.0, 0.0, 0.0), 50.0);
                 
-                lights[0] = Light::new(sun_pos, sun_color, sun_intensity * 4.0);
+                // El sol (o la luna) no se atenúa con la distancia; se conserva la escala de la escena
+                scene.lights[0].position = sky_pos;
+                scene.lights[0].color = sky_color;
+                scene.lights[0].intensity = sky_intensity;
+                scene.lights[0].falloff = Falloff::None;
             }
             
             needs_render = true;
         }
 
+        if window.is_key_pressed(KeyboardKey::KEY_B) {
+            if shift {
+                backdrop_projection = backdrop_projection.next();
+                if let Some(backdrop) = &mut backdrop {
+                    backdrop.projection = backdrop_projection;
+                }
+                println!("Proyección del fondo: {}", backdrop_projection.name());
+            } else if backdrop.is_some() {
+                backdrop = None;
+                println!("Fondo desactivado");
+            } else {
+                match Backdrop::load(BACKDROP_PATH, backdrop_projection) {
+                    Ok(loaded) => {
+                        backdrop = Some(loaded);
+                        println!("Fondo cargado: {} ({})", BACKDROP_PATH, backdrop_projection.name());
+                    }
+                    Err(e) => println!("Error cargando fondo: {}", e),
+                }
+            }
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_C) {
+            if cubemap.is_some() {
+                cubemap = None;
+                println!("Cielo procedural");
+            } else {
+                match Cubemap::load_dir(SKYBOX_DIR) {
+                    Ok(loaded) => {
+                        cubemap = Some(loaded);
+                        println!("Cubemap cargado desde {}", SKYBOX_DIR);
+                    }
+                    Err(e) => println!("Error cargando cubemap: {}", e),
+                }
+            }
+            needs_render = true;
+        }
+
+        let new_scale = if window.is_key_pressed(KeyboardKey::KEY_F1) { Some(RENDER_SCALES[0]) }
+            else if window.is_key_pressed(KeyboardKey::KEY_F2) { Some(RENDER_SCALES[1]) }
+            else if window.is_key_pressed(KeyboardKey::KEY_F3) { Some(RENDER_SCALES[2]) }
+            else if window.is_key_pressed(KeyboardKey::KEY_F4) { Some(RENDER_SCALES[3]) }
+            else { None };
+
+        if let Some(scale) = new_scale
+            && scale != render_scale
+        {
+            render_scale = scale;
+            let (width, height) = scaled_size(window_width, window_height, render_scale);
+            framebuffer = Framebuffer::new(width, height);
+            accumulation.resize(width as usize, height as usize);
+            println!("Escala de render: {:.0}% ({}x{})", render_scale * 100.0, width, height);
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_N) {
+            upscale = upscale.next();
+            println!("Escalado a la ventana: {}", upscale.name());
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_U) {
+            acceleration = acceleration.next();
+            println!("Aceleración: {}", acceleration.name());
+            if acceleration != Acceleration::VoxelGrid {
+                voxel_grid = None;
+            }
+            if acceleration != Acceleration::Chunks {
+                chunked_world = None;
+            }
+            needs_render = true;
+        }
+
+        if acceleration == Acceleration::VoxelGrid && voxel_grid.is_none() {
+            let grid_start = std::time::Instant::now();
+            voxel_grid = VoxelGrid::build(&scene.objects);
+            match &voxel_grid {
+                Some(grid) => {
+                    let (voxels, others) = grid.counts();
+                    println!("Grilla construida en {:.3}s: {} bloques en celdas, {} objetos en BVH auxiliar",
+                             grid_start.elapsed().as_secs_f32(), voxels, others);
+                }
+                None => {
+                    println!("La escena no cabe en una grilla de vóxeles; se usa el BVH");
+                    acceleration = Acceleration::Bvh;
+                }
+            }
+            needs_render = true;
+        }
+
+        if acceleration == Acceleration::Chunks && chunked_world.is_none() {
+            let chunks_start = std::time::Instant::now();
+            let world = ChunkedWorld::build(&scene.objects);
+            println!("Mundo en chunks de {}³ construido en {:.3}s: {} chunks",
+                     CHUNK_SIZE, chunks_start.elapsed().as_secs_f32(), world.chunk_count());
+            chunked_world = Some(world);
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_T) {
+            if gpu.is_some() {
+                gpu = None;
+                gpu_scene = None;
+                println!("Trazado en CPU");
+            } else {
+                gpu = start_gpu();
+            }
+            needs_render = true;
+        }
+
+        if let Some(gpu) = &gpu
+            && gpu_scene.is_none()
+        {
+            let upload_start = std::time::Instant::now();
+            let uploaded = gpu.upload_scene(&scene.objects);
+            let (cubes, skipped) = uploaded.counts();
+            println!("Escena subida a la GPU en {:.3}s: {} cubos ({} objetos solo se ven en la CPU)",
+                     upload_start.elapsed().as_secs_f32(), cubes, skipped);
+            gpu_scene = Some(uploaded);
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_G) {
+            shadow_catcher = !shadow_catcher;
+            println!("Plano de sombras sobre el fondo: {}", if shadow_catcher { "Activo" } else { "Desactivado" });
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_F) {
+            accumulation.filter.reject_outliers = !accumulation.filter.reject_outliers;
+            println!("Filtro de fireflies: {}", if accumulation.filter.reject_outliers { "Activo" } else { "Desactivado" });
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_Q) {
+            sampler = sampler.next();
+            println!("Muestreo del anti-aliasing: {}", sampler.name());
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_O) {
+            accumulation.adaptive.enabled = !accumulation.adaptive.enabled;
+            println!("Muestreo adaptativo: {}", if accumulation.adaptive.enabled { "Activo" } else { "Desactivado" });
+            needs_render = true;
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_Y) && !control {
+            reprojection = !reprojection;
+            println!("Reproyección temporal: {}", if reprojection { "Activa" } else { "Desactivada" });
+        }
+
         if needs_render {
-            let frame_start = std::time::Instant::now();
-            render(
-                &mut framebuffer, 
-                &bvh, 
-                &objects, 
-                &camera, 
-                &lights, 
-                day_night.get_time_of_day(),
-                absolute_time
-            );
-            let elapsed = frame_start.elapsed().as_secs_f32();
-            
-            frame_count += 1;
-            total_render_time += elapsed;
-            
-            if frame_count % 30 == 0 {
-                let avg_time = total_render_time / frame_count as f32;
-                let fps = 1.0 / avg_time;
-                println!("Frame {}: {:.3}s ({:.1} FPS) | {}", 
-                         frame_count, elapsed, fps,
-                         get_time_description(day_night.get_time_of_day()));
+            // Una pasada a medio trazar queda vieja: se descarta, pero la nueva
+            // sigue desde la fila donde quedó
+            accumulation.reset();
+            shows_animated = false;
+            let resume_row = render_job.take().map_or(0, |job| job.next_row());
+            if debug_view == DebugView::Off && !split_screen {
+                let settings = RenderSettings::new(framebuffer.width, framebuffer.height).with_sample(0, sampler);
+                render_job = Some(RenderJob::new(settings, None).starting_at(resume_row));
+
+                // Con solo la cámara movida, el frame anterior se reproyecta a
+                // la vista nueva y se muestra enseguida; sus huecos se trazan en
+                // este mismo frame y la pasada nueva lo va reemplazando
+                let (width, height) = (framebuffer.width as usize, framebuffer.height as usize);
+                let reprojected = history
+                    .as_ref()
+                    .filter(|history| reprojection && camera_moved && history.matches(width, height))
+                    .and_then(|history| history.reproject(&camera));
+                holes = match &reprojected {
+                    Some(reprojected) => reprojected.holes(),
+                    None => Vec::new(),
+                };
+                if let Some(reprojected) = &reprojected {
+                    let mut is_hole = vec![false; width * height];
+                    holes.iter().for_each(|&pixel| is_hole[pixel] = true);
+                    for (pixel, radiance) in reprojected.radiance().iter().enumerate() {
+                        if !is_hole[pixel] {
+                            write_pixels_from(&mut framebuffer, pixel, &[vector3_to_color(*radiance)]);
+                        }
+                    }
+                }
+                history = Some(reprojected.unwrap_or_else(|| FrameHistory::new(&camera, width, height)));
+            } else {
+                history = None;
+                holes.clear();
+                let frame_start = std::time::Instant::now();
+                if debug_view != DebugView::Off {
+                    render_heatmap(&mut framebuffer, &scene.bvh, &scene.objects, &camera, debug_view);
+                } else {
+                    // Las dos vistas se trazan enteras en cada frame, sin pasadas en tramos ni acumulación
+                    let environment = frame_environment(
+                        day_night.get_time_of_day(),
+                        absolute_time,
+                        backdrop.as_ref(),
+                        environment_map.as_deref(),
+                        cubemap.as_ref(),
+                        shadow_catcher,
+                        lighting,
+                        &camera,
+                        &framebuffer,
+                    );
+                    let frame_lights = animate_lights(&scene.lights, absolute_time, environment.portal_pulse);
+                    let renderer = Renderer::new(Scene { bvh: &scene.bvh, objects: &scene.objects, grid: voxel_grid.as_ref(), chunks: chunked_world.as_ref(), lights: &frame_lights, environment: &environment });
+                    let nether = split_view::nether_camera(&camera);
+                    let radiance = split_view::render_split(&renderer, [&camera, &nether], framebuffer.width, framebuffer.height);
+                    let pixels: Vec<Color> = radiance.into_iter().map(vector3_to_color).collect();
+                    write_pixels(&mut framebuffer, &pixels);
+                }
+                last_render_time = frame_start.elapsed().as_secs_f32();
+            }
+        }
+
+        // Con la imagen quieta se siguen acumulando muestras desplazadas dentro del pixel
+        // (con muestreo adaptativo, solo en los pixels que todavía no convergieron)
+        let max_samples = if accumulation.adaptive.enabled { MAX_ADAPTIVE_SAMPLES } else { MAX_ACCUMULATED_SAMPLES };
+        if render_job.is_none()
+            && debug_view == DebugView::Off
+            && !split_screen
+            && accumulation.samples() > 0
+            && accumulation.samples() < max_samples
+        {
+            let pending = accumulation.pending_pixels();
+            if !pending.is_empty() {
+                let settings = RenderSettings::new(framebuffer.width, framebuffer.height).with_sample(accumulation.samples(), sampler);
+                let all_pixels = pending.len() == (framebuffer.width * framebuffer.height) as usize;
+                render_job = Some(RenderJob::new(settings, (!all_pixels).then_some(pending)));
+            }
+        }
+
+        let job_finished = match &mut render_job {
+            Some(job) => {
+                let environment = frame_environment(
+                    day_night.get_time_of_day(),
+                    absolute_time,
+                    backdrop.as_ref(),
+                    environment_map.as_deref(),
+                    cubemap.as_ref(),
+                    shadow_catcher,
+                    lighting,
+                    &camera,
+                    &framebuffer,
+                );
+                let frame_lights = animate_lights(&scene.lights, absolute_time, environment.portal_pulse);
+                let renderer = Renderer::new(Scene { bvh: &scene.bvh, objects: &scene.objects, grid: voxel_grid.as_ref(), chunks: chunked_world.as_ref(), lights: &frame_lights, environment: &environment });
+
+                // La GPU traza el frame entero de una vez
+                let gpu_start = std::time::Instant::now();
+                let gpu_frame = if job.pixels().is_none() && job.is_fresh() {
+                    gpu_radiance(&renderer, gpu.as_mut().zip(gpu_scene.as_ref()), &camera, job.settings())
+                } else {
+                    None
+                };
+
+                // Los huecos de la reproyección van primero y descuentan su tiempo del presupuesto
+                let holes_start = std::time::Instant::now();
+                if !holes.is_empty() && gpu_frame.is_none() {
+                    let traced = renderer.render_pixels_with_depth(&camera, job.settings(), &holes);
+                    for (&pixel, (radiance, _)) in holes.iter().zip(&traced) {
+                        write_pixels_from(&mut framebuffer, pixel, &[vector3_to_color(*radiance)]);
+                    }
+                    if let Some(history) = &mut history {
+                        history.store_pixels(&holes, &traced);
+                    }
+                }
+                holes.clear();
+                let budget = FRAME_BUDGET.saturating_sub(holes_start.elapsed());
+
+                let finished = match gpu_frame {
+                    Some(frame) => {
+                        job.finish_with(frame, gpu_start.elapsed());
+                        true
+                    }
+                    None => job.advance(&renderer, &camera, budget),
+                };
+
+                // La primera pasada se va mostrando fila por fila sobre la imagen
+                // anterior (o la reproyectada) y se guarda para reproyectarla después
+                if job.pixels().is_none() && accumulation.samples() == 0 {
+                    for range in job.take_new() {
+                        if !finished {
+                            let colors: Vec<Color> = job.radiance()[range.clone()].iter().map(|r| vector3_to_color(*r)).collect();
+                            write_pixels_from(&mut framebuffer, range.start, &colors);
+                        }
+                        if let Some(history) = &mut history {
+                            history.store_range(range.clone(), &job.radiance()[range.clone()], &job.depth()[range]);
+                        }
+                    }
+                }
+                finished
+            }
+            None => false,
+        };
+
+        if job_finished && let Some(job) = render_job.take() {
+            let first_pass = accumulation.samples() == 0;
+            match job.pixels() {
+                Some(pixels) => accumulation.add_samples(pixels, job.radiance()),
+                None => accumulation.add_frame(job.radiance()),
+            }
+            write_pixels(&mut framebuffer, &accumulation.resolve());
+
+            if first_pass {
+                update_scene_cache(&mut scene_cache, &framebuffer, current_scene_info(scene_choice, scene_file.as_ref()).name, &scene.objects, &scene.lights, &scene.bvh);
+                let elapsed = job.busy_time().as_secs_f32();
+                last_render_time = elapsed;
+                if let Some(stats) = &mut stats {
+                    stats.record_frame(elapsed);
+                }
+
+                frame_count += 1;
+                total_render_time += elapsed;
+
+                if frame_count % 30 == 0 {
+                    let avg_time = total_render_time / frame_count as f32;
+                    let fps = 1.0 / avg_time;
+                    println!("Frame {}: {:.3}s ({:.1} FPS) | {}",
+                             frame_count, elapsed, fps,
+                             get_time_description(day_night.get_time_of_day()));
+                }
+            } else if accumulation.adaptive.enabled && accumulation.pending_pixels().is_empty() {
+                println!("Render convergido tras {} pasadas", accumulation.samples());
             }
         }
 
-        framebuffer.swap_buffers(&mut window, &thread);
+        // En modo edición (bloques o materiales) se muestra qué bloque está bajo la mira
+        let crosshair_lines = (block_editor.open || material_editor.open).then(|| {
+            let environment = Environment::new(day_night.get_time_of_day(), absolute_time);
+            let environment = match lighting {
+                Some(preset) => environment.with_lighting(preset),
+                None => environment,
+            };
+            let renderer = Renderer::new(Scene { bvh: &scene.bvh, objects: &scene.objects, grid: voxel_grid.as_ref(), chunks: chunked_world.as_ref(), lights: &scene.lights, environment: &environment });
+            match target_block(&renderer, &camera, &framebuffer) {
+                Some(target) => target.overlay_lines(),
+                None => vec!["Sin bloque".to_string()],
+            }
+        });
+
+        let mut ray_lines = ray_tree
+            .as_ref()
+            .map(|tree| tree.screen_segments(&camera, window_width as u32, window_height as u32))
+            .unwrap_or_default();
+        let mut overlay = material_editor.overlay_lines();
+        if block_editor.open {
+            // Contorno de la cara bajo el mouse
+            if let Some(pick) = mouse_pixel(&window, &framebuffer)
+                .and_then(|pixel| block_editor.pick(&scene, &camera, pixel, framebuffer.width, framebuffer.height))
+            {
+                ray_lines.extend(pick.outline(&camera, window_width as u32, window_height as u32));
+            }
+            overlay.extend(block_editor.overlay_lines());
+        }
+        framebuffer.swap_buffers(&mut window, &thread, upscale, &overlay, crosshair_lines.as_deref(), &ray_lines);
+    }
+
+    if let Some(stats) = &stats {
+        match stats.write() {
+            Ok(()) => println!("Estadísticas guardadas en {}", stats.path()),
+            Err(e) => println!("{}", e),
+        }
     }
 
     println!("\n╔════════════════════════════════════════╗");
@@ -1,6 +1,17 @@
 use raylib::prelude::{Color, Vector3};
+use std::sync::Arc;
 
-#[derive(Clone)]
+use crate::TEXTURE_MANAGER;
+use crate::colormap::{BiomeTint, PLAINS_CLIMATE};
+use crate::texture::{AlphaMask, METALNESS_MAP_SUFFIX, NORMAL_MAP_SUFFIX, ROUGHNESS_MAP_SUFFIX, TextureSlots};
+
+thread_local! {
+    /// Material negro compartido por los impactos vacíos; uno por hilo para que
+    /// contar referencias no compita entre los hilos del render
+    static SHARED_BLACK: Arc<Material> = Arc::new(Material::black());
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Material {
     pub diffuse: Vector3,
     pub albedo: [f32; 2],
@@ -10,6 +21,32 @@ pub struct Material {
     pub refraction_index: f32,
     pub emissive: Vector3,
     pub texture_path: Option<String>,
+    /// Mapa de normales (una textura como `stone_normal`) que le da relieve a la superficie
+    pub normal_map: Option<String>,
+    /// Rugosidad por texel (0 lisa, 1 áspera), en escala de grises
+    pub roughness_map: Option<String>,
+    /// Metal por texel (0 no metálico, 1 metálico), en escala de grises
+    pub metalness_map: Option<String>,
+    /// Huecos de la textura (por su canal alfa), por los que los rayos pasan de largo
+    pub alpha_mask: Option<Arc<AlphaMask>>,
+    /// Índices en el atlas de la textura y los mapas de arriba
+    pub slots: TextureSlots,
+    /// Nombre en la biblioteca de materiales (vacío si no está registrado)
+    pub name: String,
+    /// Material "natural": cada cara gira/refleja la textura al azar para
+    /// que no se note la repetición en terrenos grandes
+    pub uv_variation: bool,
+    /// El brillo emisivo late con el pulso del portal (ver `portal_pulse`)
+    pub emissive_pulse: bool,
+    /// Colormap que tiñe la textura según el bioma (pasto, follaje)
+    pub biome_tint: Option<BiomeTint>,
+    /// Tinte lineal por el que se multiplica el color difuso; el del bioma
+    /// lo fija `SceneBuilder` en cada bloque
+    pub tint: Vector3,
+    /// Mapeo tri-planar: la textura y sus mapas se proyectan desde los tres
+    /// ejes del mundo, en repeticiones de este tamaño, en lugar de usar las
+    /// coordenadas (u, v) de la figura
+    pub triplanar: Option<f32>,
 }
 
 impl Material {
@@ -23,6 +60,10 @@ impl Material {
         emissive: Vector3,
         texture_path: Option<String>,
     ) -> Self {
+        let slots = TextureSlots {
+            texture: texture_path.as_deref().map(|name| TEXTURE_MANAGER.lock().unwrap().slot(name)),
+            ..TextureSlots::default()
+        };
         Material {
             diffuse,
             albedo,
@@ -32,9 +73,129 @@ impl Material {
             refraction_index,
             emissive,
             texture_path,
+            normal_map: None,
+            roughness_map: None,
+            metalness_map: None,
+            alpha_mask: None,
+            slots,
+            name: String::new(),
+            uv_variation: false,
+            emissive_pulse: false,
+            biome_tint: None,
+            tint: Vector3::one(),
+            triplanar: None,
         }
     }
 
+    /// Activa la variación aleatoria de la textura por cara
+    pub fn with_uv_variation(mut self) -> Self {
+        self.uv_variation = true;
+        self
+    }
+
+    /// Proyecta la textura desde los tres ejes, una repetición cada `scale`
+    /// unidades: sin estirarse en mallas, esferas, conos y cajas giradas
+    pub fn with_triplanar(mut self, scale: f32) -> Self {
+        self.triplanar = Some(scale);
+        self
+    }
+
+    /// Tiñe la textura con el colormap del bioma, empezando por el de la llanura
+    pub fn with_biome_tint(mut self, tint: BiomeTint) -> Self {
+        self.tint = TEXTURE_MANAGER.lock().unwrap().biome_tint(tint, self.texture_path.as_deref(), PLAINS_CLIMATE);
+        self.biome_tint = Some(tint);
+        self
+    }
+
+    /// Usa los mapas que haya junto a la textura (`<textura>_normal`,
+    /// `<textura>_roughness` y `<textura>_metal`) y quita los que no estén;
+    /// también toma los huecos del canal alfa de la textura, si los tiene, y
+    /// los índices de todo en el atlas
+    pub fn with_texture_maps(mut self) -> Self {
+        let mut texture_manager = TEXTURE_MANAGER.lock().unwrap();
+        let find = |suffix: &str| {
+            let texture = self.texture_path.as_ref()?;
+            let map = format!("{}{}", texture, suffix);
+            texture_manager.has_texture(&map).then_some(map)
+        };
+        self.normal_map = find(NORMAL_MAP_SUFFIX);
+        self.roughness_map = find(ROUGHNESS_MAP_SUFFIX);
+        self.metalness_map = find(METALNESS_MAP_SUFFIX);
+        self.alpha_mask = self
+            .texture_path
+            .as_ref()
+            .and_then(|texture| texture_manager.alpha_mask(texture))
+            .map(Arc::new);
+        let mut slot = |name: &Option<String>| name.as_deref().map(|name| texture_manager.slot(name));
+        self.slots = TextureSlots {
+            texture: slot(&self.texture_path),
+            normal: slot(&self.normal_map),
+            roughness: slot(&self.roughness_map),
+            metalness: slot(&self.metalness_map),
+        };
+        self
+    }
+
+    /// Si (u, v) cae en un hueco de la textura
+    #[inline]
+    pub fn is_cut_out(&self, u: f32, v: f32) -> bool {
+        self.alpha_mask.as_ref().is_some_and(|mask| !mask.is_opaque(u, v))
+    }
+
+    /// Si el sombreado tiene que leer alguna textura del material
+    #[inline]
+    pub fn is_textured(&self) -> bool {
+        self.texture_path.is_some()
+            || self.normal_map.is_some()
+            || self.roughness_map.is_some()
+            || self.metalness_map.is_some()
+    }
+
+    /// Color difuso en espacio lineal (`diffuse` se define en sRGB)
+    #[inline]
+    pub fn linear_diffuse(&self) -> Vector3 {
+        srgb_to_linear(self.diffuse)
+    }
+
+    /// Brillo propio en espacio lineal (`emissive` se define en sRGB)
+    #[inline]
+    pub fn linear_emissive(&self) -> Vector3 {
+        srgb_to_linear(self.emissive)
+    }
+
+    /// Brillo del material tal como está definido
+    pub fn gloss(&self) -> Gloss {
+        Gloss { specular: self.specular, albedo: self.albedo, reflectivity: self.reflectivity }
+    }
+
+    /// Rugosidad equivalente al exponente especular (la inversa de `Gloss::from_pbr`)
+    pub fn roughness(&self) -> f32 {
+        1.0 - ((self.specular - 2.0).max(0.0) / 250.0).sqrt().min(1.0)
+    }
+
+    /// Metal equivalente a cuánto se apaga el difuso (la inversa de `Gloss::from_pbr`)
+    pub fn metalness(&self) -> f32 {
+        ((1.0 - self.albedo[0]) / 0.7).clamp(0.0, 1.0)
+    }
+
+    /// Brillo en un punto con la rugosidad y el metal leídos de los mapas;
+    /// el que no tenga mapa sale de los parámetros del material
+    pub fn gloss_at(&self, roughness: Option<f32>, metalness: Option<f32>) -> Gloss {
+        if roughness.is_none() && metalness.is_none() {
+            return self.gloss();
+        }
+        Gloss::from_pbr(
+            roughness.unwrap_or_else(|| self.roughness()),
+            metalness.unwrap_or_else(|| self.metalness()),
+        )
+    }
+
+    /// Hace que el brillo emisivo siga el pulso del portal
+    pub fn with_emissive_pulse(mut self) -> Self {
+        self.emissive_pulse = true;
+        self
+    }
+
     pub fn black() -> Self {
         Material {
             diffuse: Vector3::zero(),
@@ -45,15 +206,165 @@ impl Material {
             refraction_index: 1.0,
             emissive: Vector3::zero(),
             texture_path: None,
+            normal_map: None,
+            roughness_map: None,
+            metalness_map: None,
+            alpha_mask: None,
+            slots: TextureSlots::default(),
+            name: String::new(),
+            uv_variation: false,
+            emissive_pulse: false,
+            biome_tint: None,
+            tint: Vector3::one(),
+            triplanar: None,
         }
     }
+
+    /// `Material::black()` compartido, sin reservar memoria en cada llamada
+    pub fn shared_black() -> Arc<Material> {
+        SHARED_BLACK.with(Arc::clone)
+    }
+}
+
+/// Parámetros de brillo con que se sombrea un punto (Phong más reflejo)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Gloss {
+    pub specular: f32,
+    pub albedo: [f32; 2],
+    pub reflectivity: f32,
 }
 
+impl Gloss {
+    /// Equivalente de una superficie metal-rugosidad como las de glTF
+    /// (ambos valores de 0 a 1)
+    pub fn from_pbr(roughness: f32, metalness: f32) -> Self {
+        let smoothness = 1.0 - roughness.clamp(0.0, 1.0);
+        let metalness = metalness.clamp(0.0, 1.0);
+        Gloss {
+            // Brillo especular más concentrado cuanto más lisa la superficie
+            specular: 2.0 + smoothness.powi(2) * 250.0,
+            albedo: [1.0 - metalness * 0.7, 0.1 + smoothness * 0.6],
+            reflectivity: metalness * smoothness * 0.8,
+        }
+    }
+}
+
+/// Brillo relativo del portal en el instante `time`: un ruido suave y lento
+/// más un latido en cada vuelta de la animación de su textura
+/// (`texture_phase`, de 0 a 1). Lo comparten el material y las luces del portal.
+pub fn portal_pulse(time: f32, texture_phase: f32) -> f32 {
+    let noise = 0.6 * (time * 0.9).sin()
+        + 0.3 * (time * 2.3 + 1.3).sin()
+        + 0.1 * (time * 5.1 + 0.4).sin();
+    let hum = (texture_phase * std::f32::consts::PI * 2.0).cos();
+    (1.0 + 0.3 * noise + 0.1 * hum).max(0.0)
+}
+
+/// Transformación de las coordenadas UV de una cara: giros de 90° y espejo horizontal
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct UvTransform {
+    /// Cuartos de vuelta (0-3)
+    pub rotation: u8,
+    pub flip_u: bool,
+}
+
+impl UvTransform {
+    /// Transformación pseudoaleatoria, siempre la misma para un bloque y una cara.
+    /// Si `allow_rotation` es falso solo se refleja (caras laterales que deben quedar derechas).
+    pub fn from_seed(x: i32, y: i32, z: i32, face: u32, allow_rotation: bool) -> Self {
+        let mut h = (x as u32).wrapping_mul(73_856_093)
+            ^ (y as u32).wrapping_mul(19_349_663)
+            ^ (z as u32).wrapping_mul(83_492_791)
+            ^ face.wrapping_mul(2_654_435_761);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0x5bd1_e995);
+        h ^= h >> 15;
+
+        UvTransform {
+            rotation: if allow_rotation { (h & 3) as u8 } else { 0 },
+            flip_u: h & 4 != 0,
+        }
+    }
+
+    /// Aplica la transformación a (u, v) en [0, 1], girando alrededor del centro
+    #[inline]
+    pub fn apply(&self, u: f32, v: f32) -> (f32, f32) {
+        let u = if self.flip_u { 1.0 - u } else { u };
+        match self.rotation {
+            1 => (v, 1.0 - u),
+            2 => (1.0 - u, 1.0 - v),
+            3 => (1.0 - v, u),
+            _ => (u, v),
+        }
+    }
+
+    /// Lleva una dirección (x, y) escrita en los ejes de la UV transformada a
+    /// los ejes de la UV original (la inversa de la parte lineal de `apply`)
+    #[inline]
+    pub fn unapply_direction(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x, y) = match self.rotation {
+            1 => (-y, x),
+            2 => (-x, -y),
+            3 => (y, -x),
+            _ => (x, y),
+        };
+        if self.flip_u { (-x, y) } else { (x, y) }
+    }
+}
+
+/// Pasos de la tabla de `vector3_to_color` entre 0 y 1 (lineal)
+const SRGB_TABLE_SIZE: usize = 4096;
+
+lazy_static::lazy_static! {
+    /// Byte sRGB de cada valor lineal de 0 a 1, en `SRGB_TABLE_SIZE` pasos
+    static ref LINEAR_TO_SRGB: Vec<u8> = (0..SRGB_TABLE_SIZE)
+        .map(|i| (linear_channel_to_srgb(i as f32 / (SRGB_TABLE_SIZE - 1) as f32) * 255.0).round() as u8)
+        .collect();
+
+    /// Valor lineal de cada byte sRGB, para leer texturas sin calcular potencias
+    static ref SRGB_BYTE_TO_LINEAR: [f32; 256] = std::array::from_fn(|i| srgb_channel_to_linear(i as f32 / 255.0));
+}
+
+/// Canal sRGB (0-1, como se guardan las imágenes y se eligen los colores) a lineal
+#[inline]
+pub fn srgb_channel_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Byte sRGB (de una imagen o un `Color`) a valor lineal de 0 a 1
+#[inline]
+pub fn srgb_byte_to_linear(byte: u8) -> f32 {
+    SRGB_BYTE_TO_LINEAR[byte as usize]
+}
+
+fn linear_channel_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Color elegido a ojo (sRGB) llevado al espacio lineal en que se ilumina
+#[inline]
+pub fn srgb_to_linear(color: Vector3) -> Vector3 {
+    Vector3::new(srgb_channel_to_linear(color.x), srgb_channel_to_linear(color.y), srgb_channel_to_linear(color.z))
+}
+
+/// Color lineal llevado a sRGB, la inversa de `srgb_to_linear`
+pub fn linear_to_srgb(color: Vector3) -> Vector3 {
+    Vector3::new(linear_channel_to_srgb(color.x), linear_channel_to_srgb(color.y), linear_channel_to_srgb(color.z))
+}
+
+/// Radiancia lineal a un pixel sRGB para mostrar o guardar (recortando en 1)
 pub fn vector3_to_color(v: Vector3) -> Color {
-    Color::new(
-        (v.x * 255.0).clamp(0.0, 255.0) as u8,
-        (v.y * 255.0).clamp(0.0, 255.0) as u8,
-        (v.z * 255.0).clamp(0.0, 255.0) as u8,
-        255,
-    )
-}
\ No newline at end of file
+    let encode = |c: f32| LINEAR_TO_SRGB[(c.clamp(0.0, 1.0) * (SRGB_TABLE_SIZE - 1) as f32).round() as usize];
+    Color::new(encode(v.x), encode(v.y), encode(v.z), 255)
+}
+
+/// Escala el color para que ningún canal supere `limit`, conservando el tono
+#[inline]
+pub fn limit_radiance(color: Vector3, limit: f32) -> Vector3 {
+    let peak = color.x.max(color.y).max(color.z);
+    if peak > limit {
+        color * (limit / peak)
+    } else {
+        color
+    }
+}
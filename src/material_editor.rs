@@ -0,0 +1,338 @@
+use raylib::prelude::Vector3;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::material::Material;
+
+/// Archivo donde se guardan los cambios hechos con el editor de materiales
+pub const MATERIAL_FILE: &str = "assets/materials.txt";
+
+lazy_static::lazy_static! {
+    static ref MATERIAL_OVERRIDES: Mutex<HashMap<String, MaterialOverride>> =
+        Mutex::new(load_overrides(MATERIAL_FILE));
+}
+
+/// Valores que reemplazan a los de un material de la biblioteca.
+/// Los campos en `None` conservan el valor original.
+#[derive(Clone, Default)]
+struct MaterialOverride {
+    diffuse: Option<Vector3>,
+    specular: Option<f32>,
+    reflectivity: Option<f32>,
+    emissive: Option<Vector3>,
+    texture: Option<Option<String>>,
+}
+
+impl MaterialOverride {
+    fn from_material(material: &Material) -> Self {
+        MaterialOverride {
+            diffuse: Some(material.diffuse),
+            specular: Some(material.specular),
+            reflectivity: Some(material.reflectivity),
+            emissive: Some(material.emissive),
+            texture: Some(material.texture_path.clone()),
+        }
+    }
+
+    fn apply(&self, material: &mut Material) {
+        if let Some(diffuse) = self.diffuse {
+            material.diffuse = diffuse;
+        }
+        if let Some(specular) = self.specular {
+            material.specular = specular;
+        }
+        if let Some(reflectivity) = self.reflectivity {
+            material.reflectivity = reflectivity;
+        }
+        if let Some(emissive) = self.emissive {
+            material.emissive = emissive;
+        }
+        if let Some(texture) = &self.texture {
+            material.texture_path = texture.clone();
+        }
+    }
+}
+
+/// Aplica los cambios guardados (o hechos en esta sesión) a la biblioteca de materiales
+pub fn apply_overrides(materials: &mut HashMap<String, Material>) {
+    let overrides = MATERIAL_OVERRIDES.lock().unwrap();
+    for (name, material_override) in overrides.iter() {
+        if let Some(material) = materials.get_mut(name) {
+            material_override.apply(material);
+        }
+    }
+}
+
+/// Lee el archivo de materiales. Formato:
+///
+/// ```text
+/// [water]
+/// diffuse = 0.1 0.3 0.7
+/// specular = 100
+/// reflectivity = 0.3
+/// emissive = 0 0 0
+/// texture = water
+/// ```
+fn load_overrides(path: &str) -> HashMap<String, MaterialOverride> {
+    let mut overrides = HashMap::new();
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return overrides;
+    };
+
+    let mut current: Option<String> = None;
+    for (line_num, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = Some(name.trim().to_string());
+            continue;
+        }
+
+        let (Some(name), Some((key, value))) = (&current, line.split_once('=')) else {
+            println!("  {}:{}: línea ignorada", path, line_num + 1);
+            continue;
+        };
+
+        let entry: &mut MaterialOverride = overrides.entry(name.clone()).or_default();
+        let value = value.trim();
+        let parsed = match key.trim() {
+            "diffuse" => parse_vec3(value).map(|v| entry.diffuse = Some(v)),
+            "specular" => value.parse().ok().map(|v| entry.specular = Some(v)),
+            "reflectivity" => value.parse().ok().map(|v| entry.reflectivity = Some(v)),
+            "emissive" => parse_vec3(value).map(|v| entry.emissive = Some(v)),
+            "texture" => {
+                entry.texture = Some(if value == "none" { None } else { Some(value.to_string()) });
+                Some(())
+            }
+            _ => None,
+        };
+
+        if parsed.is_none() {
+            println!("  {}:{}: valor inválido '{}'", path, line_num + 1, line);
+        }
+    }
+
+    if !overrides.is_empty() {
+        println!("{} materiales personalizados cargados de {}", overrides.len(), path);
+    }
+    overrides
+}
+
+pub fn parse_vec3(value: &str) -> Option<Vector3> {
+    let parts: Vec<f32> = value
+        .split_whitespace()
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    match parts[..] {
+        [x, y, z] => Some(Vector3::new(x, y, z)),
+        _ => None,
+    }
+}
+
+/// Escribe todos los materiales modificados en `path`
+fn save_overrides(path: &str) -> Result<usize, String> {
+    let overrides = MATERIAL_OVERRIDES.lock().unwrap();
+    let mut names: Vec<&String> = overrides.keys().collect();
+    names.sort();
+
+    let mut text = String::from("# Materiales modificados con el editor (tecla Tab)\n");
+    for name in &names {
+        let entry = &overrides[*name];
+        text.push_str(&format!("\n[{}]\n", name));
+        if let Some(d) = entry.diffuse {
+            text.push_str(&format!("diffuse = {} {} {}\n", d.x, d.y, d.z));
+        }
+        if let Some(s) = entry.specular {
+            text.push_str(&format!("specular = {}\n", s));
+        }
+        if let Some(r) = entry.reflectivity {
+            text.push_str(&format!("reflectivity = {}\n", r));
+        }
+        if let Some(e) = entry.emissive {
+            text.push_str(&format!("emissive = {} {} {}\n", e.x, e.y, e.z));
+        }
+        if let Some(texture) = &entry.texture {
+            text.push_str(&format!("texture = {}\n", texture.as_deref().unwrap_or("none")));
+        }
+    }
+
+    std::fs::write(path, text).map_err(|e| format!("Error guardando {}: {}", path, e))?;
+    Ok(names.len())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum EditorField {
+    DiffuseR,
+    DiffuseG,
+    DiffuseB,
+    Specular,
+    Reflectivity,
+    EmissiveR,
+    EmissiveG,
+    EmissiveB,
+    Texture,
+}
+
+impl EditorField {
+    const ALL: [EditorField; 9] = [
+        EditorField::DiffuseR,
+        EditorField::DiffuseG,
+        EditorField::DiffuseB,
+        EditorField::Specular,
+        EditorField::Reflectivity,
+        EditorField::EmissiveR,
+        EditorField::EmissiveG,
+        EditorField::EmissiveB,
+        EditorField::Texture,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            EditorField::DiffuseR => "Difuso R",
+            EditorField::DiffuseG => "Difuso G",
+            EditorField::DiffuseB => "Difuso B",
+            EditorField::Specular => "Especular",
+            EditorField::Reflectivity => "Reflectividad",
+            EditorField::EmissiveR => "Emisivo R",
+            EditorField::EmissiveG => "Emisivo G",
+            EditorField::EmissiveB => "Emisivo B",
+            EditorField::Texture => "Textura",
+        }
+    }
+
+    fn value(&self, material: &Material) -> String {
+        match self {
+            EditorField::DiffuseR => format!("{:.2}", material.diffuse.x),
+            EditorField::DiffuseG => format!("{:.2}", material.diffuse.y),
+            EditorField::DiffuseB => format!("{:.2}", material.diffuse.z),
+            EditorField::Specular => format!("{:.0}", material.specular),
+            EditorField::Reflectivity => format!("{:.2}", material.reflectivity),
+            EditorField::EmissiveR => format!("{:.2}", material.emissive.x),
+            EditorField::EmissiveG => format!("{:.2}", material.emissive.y),
+            EditorField::EmissiveB => format!("{:.2}", material.emissive.z),
+            EditorField::Texture => material.texture_path.clone().unwrap_or_else(|| "ninguna".to_string()),
+        }
+    }
+}
+
+/// Panel para ajustar en vivo el material del bloque seleccionado con el mouse
+pub struct MaterialEditor {
+    pub open: bool,
+    material: Option<Material>,
+    field: usize,
+    dirty: bool,
+}
+
+impl MaterialEditor {
+    pub fn new() -> Self {
+        MaterialEditor {
+            open: false,
+            material: None,
+            field: 0,
+            dirty: false,
+        }
+    }
+
+    /// Selecciona el material del bloque apuntado y abre el panel
+    pub fn select(&mut self, material: &Material) {
+        if material.name.is_empty() {
+            println!("El objeto seleccionado no usa un material de la biblioteca");
+            return;
+        }
+        println!("Material seleccionado: {}", material.name);
+        self.material = Some(material.clone());
+        self.open = true;
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = (self.field + 1) % EditorField::ALL.len();
+    }
+
+    pub fn prev_field(&mut self) {
+        self.field = (self.field + EditorField::ALL.len() - 1) % EditorField::ALL.len();
+    }
+
+    /// Modifica el campo activo `steps` pasos. Devuelve `true` si el material
+    /// cambió y la escena debe reconstruirse con el nuevo valor.
+    pub fn adjust(&mut self, steps: f32, texture_names: &[String]) -> bool {
+        let Some(material) = &mut self.material else {
+            return false;
+        };
+
+        match EditorField::ALL[self.field] {
+            EditorField::DiffuseR => material.diffuse.x = (material.diffuse.x + steps * 0.05).clamp(0.0, 1.0),
+            EditorField::DiffuseG => material.diffuse.y = (material.diffuse.y + steps * 0.05).clamp(0.0, 1.0),
+            EditorField::DiffuseB => material.diffuse.z = (material.diffuse.z + steps * 0.05).clamp(0.0, 1.0),
+            EditorField::Specular => material.specular = (material.specular + steps * 5.0).clamp(1.0, 500.0),
+            EditorField::Reflectivity => {
+                material.reflectivity = (material.reflectivity + steps * 0.05).clamp(0.0, 1.0)
+            }
+            EditorField::EmissiveR => material.emissive.x = (material.emissive.x + steps * 0.1).clamp(0.0, 3.0),
+            EditorField::EmissiveG => material.emissive.y = (material.emissive.y + steps * 0.1).clamp(0.0, 3.0),
+            EditorField::EmissiveB => material.emissive.z = (material.emissive.z + steps * 0.1).clamp(0.0, 3.0),
+            EditorField::Texture => {
+                // Posición 0 = sin textura, el resto recorre las texturas cargadas
+                let count = texture_names.len() as i32 + 1;
+                let current = material
+                    .texture_path
+                    .as_ref()
+                    .and_then(|t| texture_names.iter().position(|n| n == t))
+                    .map_or(0, |i| i as i32 + 1);
+                let next = (current + steps.signum() as i32).rem_euclid(count);
+                material.texture_path = if next == 0 {
+                    None
+                } else {
+                    Some(texture_names[next as usize - 1].clone())
+                };
+            }
+        }
+
+        MATERIAL_OVERRIDES
+            .lock()
+            .unwrap()
+            .insert(material.name.clone(), MaterialOverride::from_material(material));
+        self.dirty = true;
+        true
+    }
+
+    /// Guarda los cambios en el archivo de materiales
+    pub fn save(&mut self) -> Result<usize, String> {
+        let count = save_overrides(MATERIAL_FILE)?;
+        self.dirty = false;
+        Ok(count)
+    }
+
+    /// Líneas de texto a dibujar sobre el render
+    pub fn overlay_lines(&self) -> Vec<String> {
+        if !self.open {
+            return Vec::new();
+        }
+
+        let Some(material) = &self.material else {
+            return vec![
+                "EDITOR DE MATERIALES".to_string(),
+                "Click en un bloque para seleccionarlo".to_string(),
+            ];
+        };
+
+        let mut lines = vec![format!("EDITOR DE MATERIALES: {}", material.name)];
+        for (i, field) in EditorField::ALL.iter().enumerate() {
+            let cursor = if i == self.field { ">" } else { " " };
+            lines.push(format!("{} {:14} {}", cursor, field.label(), field.value(material)));
+        }
+        lines.push("I/K campo  J/L valor  Shift x5".to_string());
+        if self.dirty {
+            lines.push(format!("Cambios sin guardar - F5 guarda en {}", MATERIAL_FILE));
+        }
+        lines
+    }
+}
+
+impl Default for MaterialEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,80 @@
+use raylib::prelude::*;
+
+use crate::camera::Camera;
+use crate::renderer::{RenderSettings, Renderer, Scene};
+use crate::scene_builder::SceneBuilder;
+use crate::skybox::Environment;
+
+/// Tamaño (en pixels) de cada vista previa
+const PREVIEW_SIZE: u32 = 128;
+/// Columnas de la hoja de contactos
+const SHEET_COLUMNS: u32 = 6;
+/// Hora del día usada para el cielo de fondo (mediodía)
+const PREVIEW_TIME_OF_DAY: f32 = 0.5;
+
+/// Escena de estudio: esfera y cubo con el material sobre un pedestal,
+/// iluminados con luz principal, de relleno y de contorno
+fn studio_scene(material: &str) -> SceneBuilder {
+    SceneBuilder::new()
+        .add_sphere(-0.7, 1.6, 0.0, 0.6, material)
+        .add_cube(0.8, 1.5, 0.0, 1.0, material)
+        .add_checkered_floor(2, "stone", "obsidian")
+        .add_light(4.0, 6.0, 5.0, Color::new(255, 245, 230, 255), 4.0)
+        .add_light(-5.0, 3.0, 3.0, Color::new(180, 200, 255, 255), 1.5)
+        .add_light(0.0, 4.0, -5.0, Color::new(255, 255, 255, 255), 2.0)
+}
+
+fn studio_camera() -> Camera {
+    Camera::new(
+        Vector3::new(2.5, 3.2, 4.0),
+        Vector3::new(0.0, 1.3, 0.0),
+        Vector3::new(0.0, 1.0, 0.0),
+    )
+}
+
+/// Renderiza un material con el rig de estudio
+fn render_preview(material: &str, camera: &Camera) -> image::RgbaImage {
+    let scene = studio_scene(material).build();
+    let environment = Environment::new(PREVIEW_TIME_OF_DAY, 0.0);
+    let renderer = Renderer::new(Scene { bvh: &scene.bvh, objects: &scene.objects, grid: None, chunks: None, lights: &scene.lights, environment: &environment });
+    renderer.render_frame(camera, &RenderSettings::new(PREVIEW_SIZE, PREVIEW_SIZE))
+}
+
+/// Renderiza todos los materiales registrados y los compone en una hoja de
+/// contactos (`contact_sheet.png`), guardando además cada vista por separado
+pub fn render_material_sheet(dir_path: &str) -> Result<usize, String> {
+    std::fs::create_dir_all(dir_path)
+        .map_err(|e| format!("No se pudo crear directorio {}: {}", dir_path, e))?;
+
+    let names = SceneBuilder::new().material_names();
+    let camera = studio_camera();
+
+    let rows = (names.len() as u32).div_ceil(SHEET_COLUMNS);
+    let mut sheet = image::RgbaImage::new(SHEET_COLUMNS * PREVIEW_SIZE, rows.max(1) * PREVIEW_SIZE);
+
+    for (i, name) in names.iter().enumerate() {
+        let preview = render_preview(name, &camera);
+
+        let col = i as u32 % SHEET_COLUMNS;
+        let row = i as u32 / SHEET_COLUMNS;
+        image::imageops::replace(
+            &mut sheet,
+            &preview,
+            (col * PREVIEW_SIZE) as i64,
+            (row * PREVIEW_SIZE) as i64,
+        );
+
+        let file_path = format!("{}/{}.png", dir_path, name);
+        preview
+            .save(&file_path)
+            .map_err(|e| format!("Error guardando {}: {}", file_path, e))?;
+        println!("  [{:2}] {}", i, name);
+    }
+
+    let sheet_path = format!("{}/contact_sheet.png", dir_path);
+    sheet
+        .save(&sheet_path)
+        .map_err(|e| format!("Error guardando {}: {}", sheet_path, e))?;
+
+    Ok(names.len())
+}
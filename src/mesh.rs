@@ -1,8 +1,15 @@
 use raylib::prelude::Vector3;
-use crate::ray_intersect::{Intersect, RayIntersect, AABB};
-use crate::material::Material;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::ray_intersect::{Intersect, RayIntersect, AABB, BVH};
+use crate::light::{Falloff, Light};
+use crate::material::{Gloss, Material, linear_to_srgb, vector3_to_color};
+use crate::TEXTURE_MANAGER;
+
+/// Alcance (en metros) de las luces glTF que no indican `range`
+const GLTF_LIGHT_RANGE: f32 = 20.0;
+
 /// Triángulo optimizado con precálculos
 pub struct Triangle {
     pub v0: Vector3,
@@ -14,7 +21,7 @@ pub struct Triangle {
     pub uv0: (f32, f32),
     pub uv1: (f32, f32),
     pub uv2: (f32, f32),
-    pub material: Material,
+    pub material: Arc<Material>,
     // Precálculos para optimización
     edge1: Vector3,
     edge2: Vector3,
@@ -32,7 +39,7 @@ impl Triangle {
         uv0: (f32, f32),
         uv1: (f32, f32),
         uv2: (f32, f32),
-        material: Material,
+        material: Arc<Material>,
     ) -> Self {
         let edge1 = v1 - v0;
         let edge2 = v2 - v0;
@@ -123,7 +130,7 @@ impl RayIntersect for Triangle {
         let uv_u = self.uv0.0 * u_bary + self.uv1.0 * v_bary + self.uv2.0 * w_bary;
         let uv_v = self.uv0.1 * u_bary + self.uv1.1 * v_bary + self.uv2.1 * w_bary;
 
-        Intersect::new(point, normal, t, self.material.clone(), uv_u, uv_v)
+        Intersect::new(point, normal, t, Arc::clone(&self.material), uv_u, uv_v)
     }
 
     fn get_bounds(&self) -> AABB {
@@ -143,7 +150,7 @@ impl Clone for Triangle {
             uv0: self.uv0,
             uv1: self.uv1,
             uv2: self.uv2,
-            material: self.material.clone(),
+            material: Arc::clone(&self.material),
             edge1: self.edge1,
             edge2: self.edge2,
             bounds: self.bounds,
@@ -151,15 +158,17 @@ impl Clone for Triangle {
     }
 }
 
-/// Malla optimizada con BVH interno
+/// Malla optimizada con BVH interno: se agrega a la escena como un solo objeto
 pub struct Mesh {
     pub triangles: Vec<Triangle>,
+    bvh: BVH,
+    bounds: AABB,
 }
 
 impl Mesh {
     pub fn from_obj(
         path: &str,
-        material: &Material,
+        material: &Arc<Material>,
         position: Vector3,
         scale: f32,
     ) -> Result<Self, String> {
@@ -241,19 +250,278 @@ impl Mesh {
                     v0, v1, v2,
                     n0, n1, n2,
                     uv0, uv1, uv2,
-                    material.clone(),
+                    Arc::clone(material),
                 ));
             }
         }
 
+        Self::from_triangles(triangles, path)
+    }
+
+    fn from_triangles(triangles: Vec<Triangle>, path: &str) -> Result<Self, String> {
+        let bvh = BVH::build(&triangles);
+        let bounds = bvh
+            .bounds()
+            .ok_or_else(|| format!("El modelo {} no tiene triángulos", path))?;
+
         println!("Malla cargada: {} triángulos desde {}", triangles.len(), path);
-        Ok(Mesh { triangles })
+        Ok(Mesh { triangles, bvh, bounds })
+    }
+
+    /// Carga un archivo glTF 2.0 (`.gltf` o `.glb`) con sus transformaciones
+    /// de nodo, materiales (color base, textura, metal, rugosidad y emisión)
+    /// y luces puntuales y focos (`KHR_lights_punctual`). Las primitivas sin
+    /// material usan `material`. Todo se lleva al mundo con `position` y `scale`.
+    pub fn from_gltf(
+        path: &str,
+        material: &Arc<Material>,
+        position: Vector3,
+        scale: f32,
+    ) -> Result<(Self, Vec<Light>), String> {
+        let (document, buffers, images) = gltf::import(path).map_err(|e| format!("Error cargando glTF: {}", e))?;
+        let scene = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .ok_or_else(|| format!("El archivo {} no tiene escenas", path))?;
+
+        let placement = NodeTransform::placement(position, scale);
+        let mut loader = GltfLoader {
+            path,
+            buffers: &buffers,
+            images: &images,
+            fallback: material,
+            materials: HashMap::new(),
+            triangles: Vec::new(),
+            lights: Vec::new(),
+        };
+        for node in scene.nodes() {
+            loader.visit(&node, &placement);
+        }
+
+        let lights = loader.lights;
+        let mesh = Self::from_triangles(loader.triangles, path)?;
+        if !lights.is_empty() {
+            println!("  {} luces importadas desde {}", lights.len(), path);
+        }
+        Ok((mesh, lights))
+    }
+}
+
+/// Transformación afín de un nodo glTF (matriz 3x3 por columnas más traslación)
+#[derive(Clone, Copy)]
+struct NodeTransform {
+    columns: [Vector3; 3],
+    translation: Vector3,
+}
+
+impl NodeTransform {
+    fn placement(position: Vector3, scale: f32) -> Self {
+        NodeTransform {
+            columns: [Vector3::new(scale, 0.0, 0.0), Vector3::new(0.0, scale, 0.0), Vector3::new(0.0, 0.0, scale)],
+            translation: position,
+        }
+    }
+
+    /// Matriz 4x4 por columnas, como la guarda glTF
+    fn from_matrix(m: [[f32; 4]; 4]) -> Self {
+        let column = |c: [f32; 4]| Vector3::new(c[0], c[1], c[2]);
+        NodeTransform {
+            columns: [column(m[0]), column(m[1]), column(m[2])],
+            translation: column(m[3]),
+        }
+    }
+
+    fn vector(&self, v: Vector3) -> Vector3 {
+        self.columns[0] * v.x + self.columns[1] * v.y + self.columns[2] * v.z
+    }
+
+    fn point(&self, p: Vector3) -> Vector3 {
+        self.translation + self.vector(p)
+    }
+
+    /// Normal transformada con la matriz de cofactores (la inversa traspuesta
+    /// multiplicada por el determinante, con su signo corregido)
+    fn normal(&self, n: Vector3) -> Vector3 {
+        let [c0, c1, c2] = self.columns;
+        let cofactor = c1.cross(c2) * n.x + c2.cross(c0) * n.y + c0.cross(c1) * n.z;
+        let determinant = c0.dot(c1.cross(c2));
+        (cofactor * determinant.signum()).normalized()
     }
 
-    pub fn to_objects(&self) -> Vec<Arc<dyn RayIntersect + Send + Sync>> {
-        self.triangles
-            .iter()
-            .map(|tri| Arc::new(tri.clone()) as Arc<dyn RayIntersect + Send + Sync>)
-            .collect()
+    /// `self` aplicada después de `local`
+    fn then(&self, local: &NodeTransform) -> Self {
+        NodeTransform {
+            columns: local.columns.map(|column| self.vector(column)),
+            translation: self.point(local.translation),
+        }
+    }
+}
+
+/// Estado de la carga de un glTF mientras se recorren sus nodos
+struct GltfLoader<'a> {
+    path: &'a str,
+    buffers: &'a [gltf::buffer::Data],
+    images: &'a [gltf::image::Data],
+    fallback: &'a Arc<Material>,
+    /// Materiales ya convertidos, por índice glTF
+    materials: HashMap<usize, Arc<Material>>,
+    triangles: Vec<Triangle>,
+    lights: Vec<Light>,
+}
+
+impl GltfLoader<'_> {
+    fn visit(&mut self, node: &gltf::Node, parent: &NodeTransform) {
+        let transform = parent.then(&NodeTransform::from_matrix(node.transform().matrix()));
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    continue;
+                }
+                self.add_primitive(&primitive, &transform);
+            }
+        }
+        if let Some(light) = node.light() {
+            self.add_light(&light, &transform);
+        }
+        for child in node.children() {
+            self.visit(&child, &transform);
+        }
+    }
+
+    fn add_primitive(&mut self, primitive: &gltf::Primitive, transform: &NodeTransform) {
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+        let Some(positions) = reader.read_positions() else {
+            return;
+        };
+        let positions: Vec<Vector3> = positions.map(|p| transform.point(Vector3::new(p[0], p[1], p[2]))).collect();
+        let normals: Option<Vec<Vector3>> = reader
+            .read_normals()
+            .map(|normals| normals.map(|n| transform.normal(Vector3::new(n[0], n[1], n[2]))).collect());
+        let uvs: Option<Vec<(f32, f32)>> = reader
+            .read_tex_coords(0)
+            .map(|uvs| uvs.into_f32().map(|uv| (uv[0], uv[1])).collect());
+        let indices: Vec<usize> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+            None => (0..positions.len()).collect(),
+        };
+        let material = self.material(&primitive.material());
+
+        for face in indices.chunks_exact(3) {
+            let [i0, i1, i2] = [face[0], face[1], face[2]];
+            if [i0, i1, i2].iter().any(|&i| i >= positions.len()) {
+                continue;
+            }
+            let (v0, v1, v2) = (positions[i0], positions[i1], positions[i2]);
+            // Las normales y UV pueden traer menos elementos que las
+            // posiciones; lo que falta sale de la cara, como si no vinieran
+            let flat = (v1 - v0).cross(v2 - v0).normalized();
+            let normal = |i: usize| normals.as_ref().and_then(|normals| normals.get(i).copied()).unwrap_or(flat);
+            let (n0, n1, n2) = (normal(i0), normal(i1), normal(i2));
+            // glTF pone el origen de las UV arriba a la izquierda, como las texturas
+            let uv = |i: usize, default: (f32, f32)| uvs.as_ref().and_then(|uvs| uvs.get(i).copied()).unwrap_or(default);
+            let (uv0, uv1, uv2) = (uv(i0, (0.0, 0.0)), uv(i1, (1.0, 0.0)), uv(i2, (0.5, 1.0)));
+            self.triangles.push(Triangle::new(v0, v1, v2, n0, n1, n2, uv0, uv1, uv2, Arc::clone(&material)));
+        }
+    }
+
+    /// Material de la escena equivalente a uno glTF (metal-rugosidad)
+    fn material(&mut self, material: &gltf::Material) -> Arc<Material> {
+        let Some(index) = material.index() else {
+            return Arc::clone(self.fallback);
+        };
+        if let Some(converted) = self.materials.get(&index) {
+            return Arc::clone(converted);
+        }
+
+        let pbr = material.pbr_metallic_roughness();
+        // glTF da los colores en espacio lineal; los materiales se definen en sRGB
+        let [r, g, b, alpha] = pbr.base_color_factor();
+        let base_color = linear_to_srgb(Vector3::new(r, g, b));
+        let (metallic, roughness) = (pbr.metallic_factor(), pbr.roughness_factor());
+        let texture = pbr
+            .base_color_texture()
+            .and_then(|info| self.register_texture(info.texture().source().index(), [base_color.x, base_color.y, base_color.z]));
+        let transparency = match material.alpha_mode() {
+            gltf::material::AlphaMode::Blend => 1.0 - alpha,
+            _ => 0.0,
+        };
+        let [er, eg, eb] = material.emissive_factor();
+
+        let gloss = Gloss::from_pbr(roughness, metallic);
+        let mut converted = Material::new(
+            base_color,
+            gloss.specular,
+            gloss.albedo,
+            transparency,
+            gloss.reflectivity,
+            1.5,
+            linear_to_srgb(Vector3::new(er, eg, eb)),
+            texture,
+        );
+        converted.name = material.name().unwrap_or("gltf").to_string();
+
+        let converted = Arc::new(converted);
+        self.materials.insert(index, Arc::clone(&converted));
+        converted
+    }
+
+    /// Registra la imagen (teñida con el color base) en el administrador de
+    /// texturas y devuelve su nombre
+    fn register_texture(&self, image_index: usize, tint: [f32; 3]) -> Option<String> {
+        let image = self.images.get(image_index)?;
+        let channels = match image.format {
+            gltf::image::Format::R8G8B8 => 3,
+            gltf::image::Format::R8G8B8A8 => 4,
+            format => {
+                println!("  Textura {} de {} en formato {:?} no soportado", image_index, self.path, format);
+                return None;
+            }
+        };
+
+        let mut data = Vec::with_capacity((image.width * image.height * 4) as usize);
+        for pixel in image.pixels.chunks_exact(channels) {
+            for channel in 0..3 {
+                data.push((pixel[channel] as f32 * tint[channel]).round() as u8);
+            }
+            data.push(if channels == 4 { pixel[3] } else { 255 });
+        }
+
+        let name = format!("{}#{}", self.path, image_index);
+        TEXTURE_MANAGER.lock().unwrap().register_procedural(&name, image.width, image.height, data);
+        Some(name)
+    }
+
+    fn add_light(&mut self, light: &gltf::khr_lights_punctual::Light, transform: &NodeTransform) {
+        let [r, g, b] = light.color();
+        let color = vector3_to_color(Vector3::new(r, g, b));
+        let position = transform.point(Vector3::zero());
+        // En candelas: con caída por el inverso del cuadrado equivale a la intensidad de la escena
+        let intensity = light.intensity();
+        let falloff = Falloff::InverseSquare { radius: light.range().unwrap_or(GLTF_LIGHT_RANGE) };
+
+        let converted = match light.kind() {
+            gltf::khr_lights_punctual::Kind::Point => Light::new(position, color, intensity),
+            gltf::khr_lights_punctual::Kind::Spot { inner_cone_angle, outer_cone_angle } => {
+                // Los focos glTF apuntan hacia -Z local
+                let direction = transform.vector(Vector3::new(0.0, 0.0, -1.0));
+                Light::spot(position, direction, inner_cone_angle, outer_cone_angle, color, intensity)
+            }
+            gltf::khr_lights_punctual::Kind::Directional => {
+                println!("  Luz direccional de {} ignorada (usar el sol de la escena)", self.path);
+                return;
+            }
+        };
+        self.lights.push(converted.with_falloff(falloff));
+    }
+}
+
+impl RayIntersect for Mesh {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        self.bvh.intersect(ray_origin, ray_direction, &self.triangles)
+    }
+
+    fn get_bounds(&self) -> AABB {
+        self.bounds
     }
 }
\ No newline at end of file
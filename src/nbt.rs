@@ -0,0 +1,200 @@
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::io::Read;
+
+/// Valor de un archivo NBT (el formato binario de Minecraft). Los decimales
+/// se leen completos aunque los esquemas de bloques no los usen.
+#[derive(Debug)]
+#[allow(dead_code)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.get(name),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&HashMap<String, Tag>> {
+        match self {
+            Tag::Compound(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Tag]> {
+        match self {
+            Tag::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Cualquier entero como `i64` (los tamaños vienen como short o int según la versión)
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Enteros de un arreglo o de una lista de enteros
+    pub fn as_ints(&self) -> Option<Vec<i64>> {
+        match self {
+            Tag::IntArray(values) => Some(values.iter().map(|&v| v as i64).collect()),
+            Tag::LongArray(values) => Some(values.clone()),
+            Tag::List(items) => items.iter().map(Tag::as_int).collect(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Option<&[i8]> {
+        match self {
+            Tag::ByteArray(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+/// Lee el compuesto raíz de un archivo NBT, comprimido con gzip o sin comprimir
+pub fn read_file(path: &str) -> Result<Tag, String> {
+    let raw = std::fs::read(path).map_err(|e| format!("No se pudo abrir {}: {}", path, e))?;
+    let data = if raw.starts_with(&[0x1f, 0x8b]) {
+        let mut data = Vec::new();
+        GzDecoder::new(raw.as_slice())
+            .read_to_end(&mut data)
+            .map_err(|e| format!("No se pudo descomprimir {}: {}", path, e))?;
+        data
+    } else {
+        raw
+    };
+
+    let mut reader = Reader { data: &data, pos: 0 };
+    let kind = reader.u8()?;
+    if kind != 10 {
+        return Err(format!("{} no empieza con un compuesto NBT (tipo {})", path, kind));
+    }
+    reader.string()?;
+    reader.payload(kind, 0).map_err(|e| format!("NBT inválido en {}: {}", path, e))
+}
+
+/// Anidamiento máximo de listas y compuestos, para no desbordar la pila con un archivo roto
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], String> {
+        let end = self.pos.checked_add(count).filter(|&end| end <= self.data.len());
+        let Some(end) = end else {
+            return Err(format!("fin de datos inesperado en el byte {}", self.pos));
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, String> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+
+    /// Largo de un arreglo o lista; los negativos cuentan como vacíos
+    fn length(&mut self) -> Result<usize, String> {
+        Ok(self.i32()?.max(0) as usize)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        // UTF-8 modificado de Java: igual a UTF-8 salvo en caracteres raros
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn payload(&mut self, kind: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err("anidamiento demasiado profundo".to_string());
+        }
+        Ok(match kind {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(self.i32()?),
+            4 => Tag::Long(self.i64()?),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let len = self.length()?;
+                Tag::ByteArray(self.take(len)?.iter().map(|&b| b as i8).collect())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_kind = self.u8()?;
+                let len = self.length()?;
+                let mut items = Vec::with_capacity(len.min(self.data.len() - self.pos));
+                for _ in 0..len {
+                    items.push(self.payload(item_kind, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = HashMap::new();
+                loop {
+                    let entry_kind = self.u8()?;
+                    if entry_kind == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.insert(name, self.payload(entry_kind, depth + 1)?);
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.length()?;
+                let bytes = self.take(len.saturating_mul(4))?;
+                Tag::IntArray(bytes.chunks_exact(4).map(|b| i32::from_be_bytes(b.try_into().unwrap())).collect())
+            }
+            12 => {
+                let len = self.length()?;
+                let bytes = self.take(len.saturating_mul(8))?;
+                Tag::LongArray(bytes.chunks_exact(8).map(|b| i64::from_be_bytes(b.try_into().unwrap())).collect())
+            }
+            _ => return Err(format!("tipo de etiqueta desconocido {} en el byte {}", kind, self.pos)),
+        })
+    }
+}
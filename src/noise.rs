@@ -0,0 +1,207 @@
+/// Ruido de Perlin 2D (la versión mejorada, con interpolación quíntica) a
+/// partir de una semilla: la misma semilla da siempre el mismo terreno
+pub struct Perlin {
+    /// Permutación de 0..256 repetida dos veces para no tener que envolver índices
+    permutation: [u8; 512],
+}
+
+/// Direcciones de los gradientes en cada esquina de la grilla
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (std::f32::consts::FRAC_1_SQRT_2, std::f32::consts::FRAC_1_SQRT_2),
+    (-std::f32::consts::FRAC_1_SQRT_2, std::f32::consts::FRAC_1_SQRT_2),
+    (std::f32::consts::FRAC_1_SQRT_2, -std::f32::consts::FRAC_1_SQRT_2),
+    (-std::f32::consts::FRAC_1_SQRT_2, -std::f32::consts::FRAC_1_SQRT_2),
+];
+
+/// Factor que lleva el rango del ruido 2D (±√2/2) a ±1
+const NOISE_SCALE: f32 = std::f32::consts::SQRT_2;
+
+/// Gradientes del ruido 3D: los puntos medios de las aristas de un cubo
+const GRADIENTS_3D: [(f32, f32, f32); 12] = [
+    (1.0, 1.0, 0.0),
+    (-1.0, 1.0, 0.0),
+    (1.0, -1.0, 0.0),
+    (-1.0, -1.0, 0.0),
+    (1.0, 0.0, 1.0),
+    (-1.0, 0.0, 1.0),
+    (1.0, 0.0, -1.0),
+    (-1.0, 0.0, -1.0),
+    (0.0, 1.0, 1.0),
+    (0.0, -1.0, 1.0),
+    (0.0, 1.0, -1.0),
+    (0.0, -1.0, -1.0),
+];
+
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+impl Perlin {
+    pub fn new(seed: u32) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        // Fisher-Yates con un xorshift sembrado (nunca en cero)
+        let mut state = seed.wrapping_mul(0x9e37_79b9) | 1;
+        for i in (1..table.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            table.swap(i, state as usize % (i + 1));
+        }
+
+        Perlin {
+            permutation: std::array::from_fn(|i| table[i % 256]),
+        }
+    }
+
+    #[inline]
+    fn gradient(&self, x: usize, y: usize, dx: f32, dy: f32) -> f32 {
+        let hash = self.permutation[self.permutation[x] as usize + y] as usize;
+        let (gx, gy) = GRADIENTS[hash % GRADIENTS.len()];
+        gx * dx + gy * dy
+    }
+
+    #[inline]
+    fn gradient3(&self, x: usize, y: usize, z: usize, dx: f32, dy: f32, dz: f32) -> f32 {
+        let hash = self.permutation[self.permutation[self.permutation[x] as usize + y] as usize + z] as usize;
+        let (gx, gy, gz) = GRADIENTS_3D[hash % GRADIENTS_3D.len()];
+        gx * dx + gy * dy + gz * dz
+    }
+
+    /// Ruido en (x, y), aproximadamente en [-1, 1] y 0 en los puntos enteros
+    pub fn noise2(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (xi, yi) = ((x0 as i32 & 255) as usize, (y0 as i32 & 255) as usize);
+        self.cell2(xi, yi, xi + 1, yi + 1, x - x0, y - y0)
+    }
+
+    /// Como `noise2`, pero se repite cada `period` unidades en x y en y
+    /// (hasta 256), para texturas que se repiten sin costura
+    pub fn periodic_noise2(&self, x: f32, y: f32, period: u32) -> f32 {
+        let period = period.clamp(1, 256) as i32;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (xi, yi) = ((x0 as i32).rem_euclid(period), (y0 as i32).rem_euclid(period));
+        let (xn, yn) = ((xi + 1) % period, (yi + 1) % period);
+        self.cell2(xi as usize, yi as usize, xn as usize, yn as usize, x - x0, y - y0)
+    }
+
+    /// Interpola los gradientes de las esquinas (x0, y0) y (x1, y1) de una
+    /// celda en el punto (dx, dy) de adentro
+    #[inline]
+    fn cell2(&self, x0: usize, y0: usize, x1: usize, y1: usize, dx: f32, dy: f32) -> f32 {
+        let (u, v) = (fade(dx), fade(dy));
+        let bottom = lerp(self.gradient(x0, y0, dx, dy), self.gradient(x1, y0, dx - 1.0, dy), u);
+        let top = lerp(self.gradient(x0, y1, dx, dy - 1.0), self.gradient(x1, y1, dx - 1.0, dy - 1.0), u);
+        (lerp(bottom, top, v) * NOISE_SCALE).clamp(-1.0, 1.0)
+    }
+
+    /// Ruido en (x, y, z), aproximadamente en [-1, 1] y 0 en los puntos enteros
+    pub fn noise3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+        let (dx, dy, dz) = (x - x0, y - y0, z - z0);
+        let (xi, yi, zi) = ((x0 as i32 & 255) as usize, (y0 as i32 & 255) as usize, (z0 as i32 & 255) as usize);
+
+        let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+        let layer = |zi: usize, dz: f32| {
+            let bottom = lerp(self.gradient3(xi, yi, zi, dx, dy, dz), self.gradient3(xi + 1, yi, zi, dx - 1.0, dy, dz), u);
+            let top = lerp(
+                self.gradient3(xi, yi + 1, zi, dx, dy - 1.0, dz),
+                self.gradient3(xi + 1, yi + 1, zi, dx - 1.0, dy - 1.0, dz),
+                u,
+            );
+            lerp(bottom, top, v)
+        };
+        lerp(layer(zi, dz), layer(zi + 1, dz - 1.0), w).clamp(-1.0, 1.0)
+    }
+
+    /// Suma de `octaves` capas de ruido, cada una al doble de frecuencia y la
+    /// mitad de amplitud que la anterior (movimiento browniano fraccionario).
+    /// Normalizada a [-1, 1].
+    pub fn fbm2(&self, x: f32, y: f32, octaves: u32) -> f32 {
+        let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 1.0, 1.0, 0.0);
+        for octave in 0..octaves.max(1) {
+            // Cada octava se desplaza para que sus puntos enteros no coincidan con los de las otras
+            let offset = octave as f32 * 17.31;
+            sum += self.noise2(x * frequency + offset, y * frequency - offset) * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+
+    /// `fbm2` que se repite cada `period` unidades (la primera octava; cada
+    /// una repite al doble), para cubrir una textura de 0 a `period` sin costura
+    pub fn periodic_fbm2(&self, x: f32, y: f32, period: u32, octaves: u32) -> f32 {
+        let (mut sum, mut amplitude, mut frequency, mut total) = (0.0, 1.0, 1.0, 0.0);
+        for octave in 0..octaves.max(1) {
+            let offset = octave as f32 * 17.31;
+            let octave_period = period << octave;
+            sum += self.periodic_noise2(x * frequency + offset, y * frequency - offset, octave_period) * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+}
+
+/// Ruido celular (Worley) 2D: cada celda de la grilla tiene un punto al azar
+/// según la semilla, y el ruido es la distancia a los puntos más cercanos.
+/// Las celdas se repiten cada `period`, así cubre una textura sin costura.
+pub struct Worley {
+    seed: u32,
+}
+
+impl Worley {
+    pub fn new(seed: u32) -> Self {
+        Worley { seed }
+    }
+
+    /// Punto de la celda (cx, cy), dentro de ella (de 0 a 1 en cada eje)
+    fn feature_point(&self, cx: i32, cy: i32) -> (f32, f32) {
+        let mut hash = (cx as u32).wrapping_mul(0x8da6_b343)
+            ^ (cy as u32).wrapping_mul(0xd816_3841)
+            ^ self.seed.wrapping_mul(0xcb1a_b31f);
+        let mut next = || {
+            hash ^= hash >> 16;
+            hash = hash.wrapping_mul(0x7feb_352d);
+            hash ^= hash >> 15;
+            hash = hash.wrapping_mul(0x846c_a68b);
+            hash ^= hash >> 16;
+            (hash & 0xffff) as f32 / 65535.0
+        };
+        (next(), next())
+    }
+
+    /// Distancias (en celdas) al punto más cercano y al segundo más cercano
+    /// desde (x, y). La resta de las dos es 0 en los bordes entre celdas.
+    pub fn distances(&self, x: f32, y: f32, period: u32) -> (f32, f32) {
+        let period = period.max(1) as i32;
+        let (cx, cy) = (x.floor() as i32, y.floor() as i32);
+        let (mut nearest, mut second) = (f32::MAX, f32::MAX);
+        for oy in -1..=1 {
+            for ox in -1..=1 {
+                let (px, py) = self.feature_point((cx + ox).rem_euclid(period), (cy + oy).rem_euclid(period));
+                let (dx, dy) = ((cx + ox) as f32 + px - x, (cy + oy) as f32 + py - y);
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance < nearest {
+                    second = nearest;
+                    nearest = distance;
+                } else if distance < second {
+                    second = distance;
+                }
+            }
+        }
+        (nearest, second)
+    }
+}
@@ -0,0 +1,232 @@
+use raylib::prelude::Vector3;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::cube::block_face_uv;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+
+/// Medio ancho del poste de una cerca
+const FENCE_POST: f32 = 0.125;
+/// Medio ancho de los travesaños de una cerca
+const FENCE_RAIL: f32 = 0.0625;
+/// Alturas (relativas al centro de la celda) de los dos travesaños
+const FENCE_RAIL_HEIGHTS: [(f32, f32); 2] = [(-0.125, 0.0625), (0.25, 0.4375)];
+/// Medio espesor de un panel
+const PANE_HALF_THICKNESS: f32 = 0.0625;
+
+/// Punto cardinal: el norte es -Z y el este +X
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Facing {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 4] = [Facing::North, Facing::South, Facing::East, Facing::West];
+
+    /// Celda vecina en esa dirección (dx, dz)
+    pub fn offset(self) -> (i32, i32) {
+        match self {
+            Facing::North => (0, -1),
+            Facing::South => (0, 1),
+            Facing::East => (1, 0),
+            Facing::West => (-1, 0),
+        }
+    }
+}
+
+/// Forma de un bloque que no llena su celda
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlockShape {
+    /// Media losa en la mitad de abajo o la de arriba de la celda
+    Slab { top: bool },
+    /// Escalón: losa de abajo más medio bloque del lado hacia el que sube
+    Stairs(Facing),
+    /// Poste con dos travesaños hacia cada vecino conectado (norte, sur, este, oeste)
+    Fence { connections: [bool; 4] },
+    /// Panel fino que atraviesa la celda a lo largo del eje X (o del Z)
+    Pane { along_x: bool },
+}
+
+impl BlockShape {
+    /// Cajas de la forma en coordenadas de la celda (de -0.5 a 0.5 en cada eje)
+    fn boxes(self) -> Vec<([f32; 3], [f32; 3])> {
+        match self {
+            BlockShape::Slab { top: false } => vec![([-0.5, -0.5, -0.5], [0.5, 0.0, 0.5])],
+            BlockShape::Slab { top: true } => vec![([-0.5, 0.0, -0.5], [0.5, 0.5, 0.5])],
+            BlockShape::Stairs(facing) => {
+                let step = match facing {
+                    Facing::North => ([-0.5, 0.0, -0.5], [0.5, 0.5, 0.0]),
+                    Facing::South => ([-0.5, 0.0, 0.0], [0.5, 0.5, 0.5]),
+                    Facing::East => ([0.0, 0.0, -0.5], [0.5, 0.5, 0.5]),
+                    Facing::West => ([-0.5, 0.0, -0.5], [0.0, 0.5, 0.5]),
+                };
+                vec![([-0.5, -0.5, -0.5], [0.5, 0.0, 0.5]), step]
+            }
+            BlockShape::Fence { connections } => {
+                let mut boxes = vec![([-FENCE_POST, -0.5, -FENCE_POST], [FENCE_POST, 0.5, FENCE_POST])];
+                for (facing, _) in Facing::ALL.into_iter().zip(connections).filter(|(_, connected)| *connected) {
+                    let (dx, dz) = facing.offset();
+                    // Del borde del poste al borde de la celda
+                    let x = match dx {
+                        1 => (FENCE_POST, 0.5),
+                        -1 => (-0.5, -FENCE_POST),
+                        _ => (-FENCE_RAIL, FENCE_RAIL),
+                    };
+                    let z = match dz {
+                        1 => (FENCE_POST, 0.5),
+                        -1 => (-0.5, -FENCE_POST),
+                        _ => (-FENCE_RAIL, FENCE_RAIL),
+                    };
+                    for (bottom, top) in FENCE_RAIL_HEIGHTS {
+                        boxes.push(([x.0, bottom, z.0], [x.1, top, z.1]));
+                    }
+                }
+                boxes
+            }
+            BlockShape::Pane { along_x: true } => vec![([-0.5, -0.5, -PANE_HALF_THICKNESS], [0.5, 0.5, PANE_HALF_THICKNESS])],
+            BlockShape::Pane { along_x: false } => vec![([-PANE_HALF_THICKNESS, -0.5, -0.5], [PANE_HALF_THICKNESS, 0.5, 0.5])],
+        }
+    }
+}
+
+/// Bloque que ocupa solo parte de su celda (losas, escaleras, cercas y
+/// paneles), armado con unas pocas cajas alineadas a los ejes. Las texturas
+/// se mapean como en un cubo entero, así una losa muestra media textura.
+pub struct PartialBlock {
+    /// Centro de la celda
+    pub center: Vector3,
+    pub material: Arc<Material>,
+    /// Cajas de la forma en coordenadas del mundo
+    boxes: Vec<AABB>,
+    bounds: AABB,
+}
+
+impl PartialBlock {
+    pub fn new(center: Vector3, shape: BlockShape, material: Arc<Material>) -> Self {
+        let boxes: Vec<AABB> = shape
+            .boxes()
+            .into_iter()
+            .map(|(min, max)| {
+                AABB::new(
+                    center + Vector3::new(min[0], min[1], min[2]),
+                    center + Vector3::new(max[0], max[1], max[2]),
+                )
+            })
+            .collect();
+        let bounds = boxes.iter().skip(1).fold(boxes[0], |bounds, b| bounds.union(b));
+
+        PartialBlock {
+            center,
+            material,
+            boxes,
+            bounds,
+        }
+    }
+
+    /// Impacto con una caja: distancia y normal de la cara (la de entrada, o
+    /// la de salida si el rayo empieza adentro)
+    #[inline]
+    fn box_hit(b: &AABB, ray_origin: &Vector3, inv_dir: &Vector3) -> Option<(f32, Vector3)> {
+        let (enter, exit) = Self::box_faces(b, ray_origin, inv_dir)?;
+        let (t, normal) = if enter.0 > 1e-4 { enter } else { exit };
+        (t > 1e-4).then_some((t, normal))
+    }
+
+    /// Caras de entrada y de salida del rayo en una caja (distancia y normal
+    /// hacia afuera), si la toca
+    #[inline]
+    fn box_faces(b: &AABB, ray_origin: &Vector3, inv_dir: &Vector3) -> Option<((f32, Vector3), (f32, Vector3))> {
+        let origin = [ray_origin.x, ray_origin.y, ray_origin.z];
+        let inv = [inv_dir.x, inv_dir.y, inv_dir.z];
+        let (min, max) = ([b.min.x, b.min.y, b.min.z], [b.max.x, b.max.y, b.max.z]);
+
+        let mut t_enter = (f32::NEG_INFINITY, 0, 0.0);
+        let mut t_exit = (f32::INFINITY, 0, 0.0);
+        for axis in 0..3 {
+            let t1 = (min[axis] - origin[axis]) * inv[axis];
+            let t2 = (max[axis] - origin[axis]) * inv[axis];
+            // Cara de entrada: la de menor coordenada si el rayo avanza en el eje
+            let (near, far, sign) = if t1 < t2 { (t1, t2, -1.0) } else { (t2, t1, 1.0) };
+            if near > t_enter.0 {
+                t_enter = (near, axis, sign);
+            }
+            if far < t_exit.0 {
+                t_exit = (far, axis, -sign);
+            }
+        }
+
+        if t_exit.0 < 0.0 || t_enter.0 > t_exit.0 {
+            return None;
+        }
+        let face = |(t, axis, sign): (f32, usize, f32)| {
+            let mut normal = [0.0; 3];
+            normal[axis] = sign;
+            (t, Vector3::new(normal[0], normal[1], normal[2]))
+        };
+        Some((face(t_enter), face(t_exit)))
+    }
+
+    /// Impacto en la cara a distancia `t` con esa normal
+    #[inline]
+    fn face_hit(&self, ray_origin: &Vector3, ray_direction: &Vector3, t: f32, normal: Vector3) -> Intersect {
+        let point = *ray_origin + *ray_direction * t;
+        let (u, v, uv_transform) = block_face_uv(&self.center, 1.0, &point, &normal, &self.material);
+        Intersect::new(point, normal, t, Arc::clone(&self.material), u, v).with_uv_transform(uv_transform)
+    }
+
+    /// Primera cara sin hueco en la textura cuando la más cercana cayó en
+    /// uno: las caras de salida se ven desde adentro, con la normal hacia el rayo
+    fn hit_behind_cutout(&self, ray_origin: &Vector3, ray_direction: &Vector3, inv_dir: &Vector3) -> Intersect {
+        let mut faces: Vec<(f32, Vector3, bool)> = self
+            .boxes
+            .iter()
+            .filter_map(|b| Self::box_faces(b, ray_origin, inv_dir))
+            .flat_map(|(enter, exit)| [(enter.0, enter.1, false), (exit.0, exit.1, true)])
+            .filter(|&(t, _, _)| t > 1e-4)
+            .collect();
+        faces.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        for (t, normal, is_exit) in faces {
+            let mut hit = self.face_hit(ray_origin, ray_direction, t, normal);
+            if !self.material.is_cut_out(hit.u, hit.v) {
+                if is_exit {
+                    hit.normal = -hit.normal;
+                }
+                return hit;
+            }
+        }
+        Intersect::empty()
+    }
+}
+
+impl RayIntersect for PartialBlock {
+    #[inline]
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let inv_dir = Vector3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
+        let hit = self
+            .boxes
+            .iter()
+            .filter_map(|b| Self::box_hit(b, ray_origin, &inv_dir))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        match hit {
+            Some((t, normal)) => {
+                let hit = self.face_hit(ray_origin, ray_direction, t, normal);
+                if self.material.is_cut_out(hit.u, hit.v) {
+                    return self.hit_behind_cutout(ray_origin, ray_direction, &inv_dir);
+                }
+                hit
+            }
+            None => Intersect::empty(),
+        }
+    }
+
+    fn get_bounds(&self) -> AABB {
+        self.bounds
+    }
+}
@@ -0,0 +1,88 @@
+/// Partículas ambientales que flotan sobre el terreno. El tipo depende de la
+/// dimensión (debajo de y = 0 está el Nether) y del bloque de la superficie.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParticleKind {
+    /// Semillas de diente de león sobre el pasto
+    Seed,
+    /// Ceniza en el Nether
+    Ash,
+    /// Copos de nieve sobre hielo
+    Snowflake,
+    /// Columnas de esporas sobre campos de hongos
+    Spore,
+}
+
+impl ParticleKind {
+    /// Partícula para una superficie `surface` cuya cara superior está a la altura `y`
+    pub fn for_surface(surface: &str, y: i32) -> Option<Self> {
+        if y < 0 {
+            return Some(ParticleKind::Ash);
+        }
+        match surface {
+            "grass_top" => Some(ParticleKind::Seed),
+            "ice" => Some(ParticleKind::Snowflake),
+            "mycelium" | "mushroom" => Some(ParticleKind::Spore),
+            _ => None,
+        }
+    }
+
+    /// Probabilidad de que aparezca una partícula sobre cada bloque de superficie
+    pub fn density(self) -> f32 {
+        match self {
+            ParticleKind::Seed => 0.04,
+            ParticleKind::Ash => 0.08,
+            ParticleKind::Snowflake => 0.15,
+            ParticleKind::Spore => 0.1,
+        }
+    }
+
+    /// Nombre del material en la biblioteca
+    pub fn material(self) -> &'static str {
+        match self {
+            ParticleKind::Seed => "particle_seed",
+            ParticleKind::Ash => "particle_ash",
+            ParticleKind::Snowflake => "particle_snow",
+            ParticleKind::Spore => "particle_spore",
+        }
+    }
+
+    pub fn radius(self) -> f32 {
+        match self {
+            ParticleKind::Seed => 0.06,
+            ParticleKind::Ash => 0.05,
+            ParticleKind::Snowflake => 0.07,
+            ParticleKind::Spore => 0.04,
+        }
+    }
+
+    /// Partículas apiladas por aparición (las esporas suben en columna)
+    pub fn column_length(self) -> u32 {
+        match self {
+            ParticleKind::Spore => 5,
+            _ => 1,
+        }
+    }
+
+    /// Rango de alturas (en bloques) sobre la superficie
+    pub fn height_range(self) -> (f32, f32) {
+        match self {
+            ParticleKind::Seed => (0.8, 4.0),
+            ParticleKind::Ash => (0.5, 8.0),
+            ParticleKind::Snowflake => (1.0, 10.0),
+            ParticleKind::Spore => (0.3, 1.0),
+        }
+    }
+}
+
+/// Número pseudoaleatorio en [0, 1) fijo para una posición y un canal,
+/// para que las partículas no cambien entre cargas de la escena
+pub fn hash01(x: i32, y: i32, z: i32, channel: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f)
+        ^ channel.wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
@@ -0,0 +1,355 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::TAU;
+
+use crate::bench::arg_value;
+use crate::noise::{Perlin, Worley};
+use crate::particles::hash01;
+
+/// Lado (en texels) de las texturas procedurales si no se elige otro con `--texture-size`
+pub const DEFAULT_TEXTURE_SIZE: u32 = 16;
+/// Semilla de las texturas procedurales si no se elige otra con `--texture-seed`
+pub const DEFAULT_TEXTURE_SEED: u32 = 7;
+/// Lado máximo de las texturas procedurales
+const MAX_TEXTURE_SIZE: u32 = 1024;
+
+/// Lee `--texture-size N` (de 1 a 1024 texels por lado) y `--texture-seed N`.
+/// Devuelve `None` si no se pidió ninguna de las dos.
+pub fn texture_args(args: &[String]) -> Result<Option<(u32, u32)>, String> {
+    let (size, seed) = (arg_value(args, "--texture-size"), arg_value(args, "--texture-seed"));
+    if size.is_none() && seed.is_none() {
+        return Ok(None);
+    }
+    let size = match size {
+        Some(size) => size
+            .parse()
+            .ok()
+            .filter(|size| (1..=MAX_TEXTURE_SIZE).contains(size))
+            .ok_or_else(|| format!("Tamaño de textura inválido: {} (de 1 a {})", size, MAX_TEXTURE_SIZE))?,
+        None => DEFAULT_TEXTURE_SIZE,
+    };
+    let seed = match seed {
+        Some(seed) => seed.parse().map_err(|_| format!("Semilla de texturas inválida: {}", seed))?,
+        None => DEFAULT_TEXTURE_SEED,
+    };
+    Ok(Some((size, seed)))
+}
+
+/// Patrón procedural propio que se registra con
+/// `TextureManager::register_procedural_texture`: se pinta en cuadros del lado
+/// de las texturas procedurales y entra al atlas como cualquier otra textura,
+/// así que se usa por nombre en los materiales y se anima sola.
+pub trait ProceduralTexture: Send + Sync {
+    /// Color sRGB (de 0 a 1) en (u, v), de 0 a 1, a los `t` segundos
+    fn sample(&self, u: f32, v: f32, t: f32) -> Vector3;
+
+    /// Cuántos cuadros tiene la animación y cuántos segundos dura cada uno;
+    /// un solo cuadro si el patrón no cambia con `t`
+    fn frames(&self) -> (u32, f32) {
+        (1, 0.0)
+    }
+}
+
+/// Textura RGBA de `size` texels por lado pintando cada uno con `texel(u, v)`,
+/// con u y v de 0 a 1 (el centro de cada texel)
+fn paint(size: u32, texel: impl Fn(f32, f32) -> [u8; 4]) -> Vec<u8> {
+    let mut data = Vec::with_capacity((size * size * 4) as usize);
+    for y in 0..size {
+        for x in 0..size {
+            let (u, v) = ((x as f32 + 0.5) / size as f32, (y as f32 + 0.5) / size as f32);
+            data.extend_from_slice(&texel(u, v));
+        }
+    }
+    data
+}
+
+/// Cuadros RGBA de un patrón propio, de `size` texels por lado
+pub fn bake(texture: &dyn ProceduralTexture, size: u32) -> Vec<Vec<u8>> {
+    let (frames, frame_duration) = texture.frames();
+    (0..frames.max(1))
+        .map(|frame| {
+            let t = frame as f32 * frame_duration;
+            paint(size, |u, v| {
+                let color = texture.sample(u, v, t);
+                let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+                [channel(color.x), channel(color.y), channel(color.z), 255]
+            })
+        })
+        .collect()
+}
+
+/// Placa de circuito: pistas de cobre en una grilla sobre el verde de la
+/// placa, con pulsos de luz que las recorren en una vuelta por segundo
+pub struct CircuitBoard {
+    /// Celdas por lado de la grilla de pistas
+    pub cells: u32,
+    pub seed: u32,
+}
+
+impl ProceduralTexture for CircuitBoard {
+    fn sample(&self, u: f32, v: f32, t: f32) -> Vector3 {
+        let cells = self.cells.max(1);
+        let (x, y) = (u * cells as f32, v * cells as f32);
+        let (cell_x, cell_y) = (x.floor() as i32 % cells as i32, y.floor() as i32 % cells as i32);
+        let (fx, fy) = (x.fract() - 0.5, y.fract() - 0.5);
+        let roll = |channel: u32| hash01(cell_x, cell_y, self.seed as i32, channel) < 0.5;
+
+        let horizontal = roll(0) && fy.abs() < 0.14;
+        let vertical = roll(1) && fx.abs() < 0.14;
+        let pad = roll(2) && fx * fx + fy * fy < 0.07;
+        if !(horizontal || vertical || pad) {
+            let weave = 0.9 + 0.1 * ((x * 8.0).floor() + (y * 8.0).floor()).rem_euclid(2.0);
+            return Vector3::new(0.06, 0.3, 0.12) * weave;
+        }
+        // Cada pulso recorre su pista una vez por vuelta, con un desfase por fila o columna
+        let along = |position: f32, lane: i32| {
+            let phase = (position - t + hash01(lane, 0, self.seed as i32, 3)).rem_euclid(1.0);
+            1.0 - smoothstep(0.0, 0.15, phase)
+        };
+        let mut glow: f32 = 0.0;
+        if horizontal {
+            glow = glow.max(along(u, cell_y));
+        }
+        if vertical {
+            glow = glow.max(along(v, cell_x + cells as i32));
+        }
+        let copper = Vector3::new(0.78, 0.55, 0.22);
+        copper + (Vector3::new(0.7, 1.0, 0.85) - copper) * glow
+    }
+
+    fn frames(&self) -> (u32, f32) {
+        (6, 1.0 / 6.0)
+    }
+}
+
+/// Generador de las texturas de respaldo (las que no están en `assets/textures`):
+/// ruido de Perlin fractal y celular (Worley) con una semilla, a la resolución
+/// que se pida. Todas se repiten sin costura y las animadas vuelven al primer
+/// cuadro, porque el ruido se repite a lo ancho y alto de la textura.
+pub struct ProceduralTextures {
+    size: u32,
+    perlin: Perlin,
+    worley: Worley,
+}
+
+#[inline]
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Texel RGBA que mezcla `dark` y `light` según `t` (0-1) y lo multiplica por `shade`
+fn mix(dark: [u8; 3], light: [u8; 3], t: f32, shade: f32, alpha: u8) -> [u8; 4] {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |i: usize| {
+        let (dark, light) = (dark[i] as f32, light[i] as f32);
+        ((dark + (light - dark) * t) * shade).round().clamp(0.0, 255.0) as u8
+    };
+    [channel(0), channel(1), channel(2), alpha]
+}
+
+impl ProceduralTextures {
+    pub fn new(size: u32, seed: u32) -> Self {
+        ProceduralTextures { size: size.max(1), perlin: Perlin::new(seed), worley: Worley::new(seed) }
+    }
+
+    /// Textura RGBA del lado de este generador pintando cada texel con `texel(u, v)`
+    fn paint(&self, texel: impl Fn(f32, f32) -> [u8; 4]) -> Vec<u8> {
+        paint(self.size, texel)
+    }
+
+    /// Ruido fractal de 0 a 1 con `cells` celdas por lado en la primera octava.
+    /// Correr u o v en un entero da el mismo dibujo (así se animan en bucle).
+    fn fbm(&self, u: f32, v: f32, cells: u32, octaves: u32) -> f32 {
+        let (x, y) = (u * cells as f32, v * cells as f32);
+        self.perlin.periodic_fbm2(x, y, cells, octaves) * 0.5 + 0.5
+    }
+
+    /// Distancias (F1, F2) a los puntos de `cells` celdas por lado
+    fn cells(&self, u: f32, v: f32, cells: u32) -> (f32, f32) {
+        self.worley.distances(u * cells as f32, v * cells as f32, cells)
+    }
+
+    fn grass_texel(&self, u: f32, v: f32) -> [u8; 4] {
+        let blades = self.fbm(u, v, 16, 1);
+        mix([56, 128, 38], [104, 182, 62], self.fbm(u, v, 4, 4), 0.88 + 0.24 * blades, 255)
+    }
+
+    fn dirt_texel(&self, u: f32, v: f32) -> [u8; 4] {
+        let (pebble, _) = self.cells(u + 0.37, v + 0.61, 6);
+        let shade = if pebble < 0.18 { 0.78 } else { 0.92 + 0.16 * self.fbm(u, v, 16, 1) };
+        mix([104, 70, 42], [150, 106, 66], self.fbm(u, v, 4, 4), shade, 255)
+    }
+
+    pub fn grass_top(&self) -> Vec<u8> {
+        self.paint(|u, v| self.grass_texel(u, v))
+    }
+
+    /// Tierra con una franja de pasto arriba de borde irregular
+    pub fn grass_side(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let edge = 0.18 + 0.14 * self.fbm(u, 0.5, 8, 2);
+            if v < edge { self.grass_texel(u, v) } else { self.dirt_texel(u, v) }
+        })
+    }
+
+    pub fn dirt(&self) -> Vec<u8> {
+        self.paint(|u, v| self.dirt_texel(u, v))
+    }
+
+    /// Piedra en lajas (celdas de Worley) con grietas en los bordes y manchas
+    pub fn stone(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let (near, second) = self.cells(u, v, 5);
+            let crack = smoothstep(0.0, 0.1, second - near);
+            let shade = (0.72 + 0.28 * crack) * (0.9 + 0.2 * self.fbm(u, v, 16, 1));
+            mix([96, 96, 98], [146, 146, 146], self.fbm(u, v, 4, 4), shade, 255)
+        })
+    }
+
+    /// Corteza: fibras verticales onduladas por el ruido
+    pub fn wood(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let warp = self.fbm(u, v, 4, 3);
+            let fiber = 0.5 + 0.5 * (TAU * (u * 6.0 + warp * 1.5)).sin();
+            let shade = 0.9 + 0.2 * self.fbm(u, v, 16, 1);
+            mix([62, 42, 22], [118, 84, 48], fiber * fiber, shade, 255)
+        })
+    }
+
+    /// Racimos de hojas (celdas de Worley) con huecos entre ellos
+    pub fn leaves(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let (near, second) = self.cells(u + 0.5, v + 0.5, 6);
+            let hole = second - near < 0.12;
+            let light = (1.0 - near) * self.fbm(u, v, 8, 2);
+            mix([26, 88, 24], [84, 168, 56], light * 1.4, 1.0, if hole { 0 } else { 255 })
+        })
+    }
+
+    /// Vidrio transparente con marco y dos reflejos opacos; el resto son huecos
+    pub fn glass(&self) -> Vec<u8> {
+        let texel = 1.0 / self.size as f32;
+        let border = (self.size / 16).max(1) as f32 * texel;
+        self.paint(|u, v| {
+            let is_frame = u < border || v < border || u > 1.0 - border || v > 1.0 - border;
+            let diagonal = u + v;
+            let is_streak = (0.125..0.375).contains(&u)
+                && ((diagonal - 0.4375).abs() < texel || (diagonal - 0.625).abs() < texel);
+            if is_frame {
+                [200, 215, 225, 255]
+            } else if is_streak {
+                [235, 245, 250, 255]
+            } else {
+                [220, 235, 245, 0]
+            }
+        })
+    }
+
+    /// Cuadro `frame` de `frames` del agua: dos capas de ondas que se cruzan
+    pub fn water_frame(&self, frame: u32, frames: u32) -> Vec<u8> {
+        let t = frame as f32 / frames.max(1) as f32;
+        self.paint(|u, v| {
+            let waves = (self.fbm(u + t, v, 4, 3) + self.fbm(u, v - t, 4, 3)) * 0.5;
+            let crest = smoothstep(0.6, 0.7, waves) * 0.3;
+            mix([26, 60, 160], [70, 124, 226], (waves - 0.35) / 0.3 + crest, 1.0, 200)
+        })
+    }
+
+    /// Cuadro `frame` de `frames` de la lava: costra oscura entre venas brillantes que fluyen
+    pub fn lava_frame(&self, frame: u32, frames: u32) -> Vec<u8> {
+        let t = frame as f32 / frames.max(1) as f32;
+        self.paint(|u, v| {
+            let (near, second) = self.cells(u, v + t, 4);
+            let vein = 1.0 - smoothstep(0.0, 0.3, second - near);
+            let heat = (vein * 0.7 + self.fbm(u + t, v, 4, 3) * 0.5).min(1.0);
+            mix([190, 54, 0], [255, 214, 80], heat * heat, 1.0, 255)
+        })
+    }
+
+    /// Cuadro `frame` de `frames` del portal: bandas moradas retorcidas por el ruido
+    pub fn portal_frame(&self, frame: u32, frames: u32) -> Vec<u8> {
+        let t = frame as f32 / frames.max(1) as f32;
+        self.paint(|u, v| {
+            let warp = self.fbm(u + t, v, 3, 3);
+            let band = 0.5 + 0.5 * (TAU * ((u + v) * 3.0 + warp * 2.0 - t)).sin();
+            mix([84, 16, 160], [206, 110, 255], band, 1.0, 180)
+        })
+    }
+
+    pub fn netherrack(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let (near, second) = self.cells(u, v, 6);
+            let crack = smoothstep(0.0, 0.08, second - near);
+            let shade = (0.68 + 0.32 * crack) * (0.88 + 0.24 * self.fbm(u, v, 16, 1));
+            mix([104, 32, 32], [164, 62, 56], self.fbm(u, v, 4, 4), shade, 255)
+        })
+    }
+
+    /// Ladrillos intercalados (la fila de abajo corrida medio ladrillo) con mortero
+    pub fn nether_brick(&self) -> Vec<u8> {
+        let mortar = (self.size / 16).max(1) as f32 / self.size as f32;
+        self.paint(|u, v| {
+            let row = (v * 2.0).floor();
+            let x = (u * 2.0 + row * 0.5).fract();
+            let y = (v * 2.0).fract();
+            if x < mortar * 2.0 || y < mortar * 2.0 {
+                return mix([20, 9, 11], [34, 14, 16], self.fbm(u, v, 8, 1), 1.0, 255);
+            }
+            let shade = 0.85 + 0.3 * self.fbm(u, v, 16, 1);
+            mix([52, 16, 20], [82, 28, 32], self.fbm(u, v, 4, 3), shade, 255)
+        })
+    }
+
+    /// Arena de almas con caras hundidas (las manchas oscuras)
+    pub fn soul_sand(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let (near, _) = self.cells(u, v, 4);
+            let shade = if near < 0.22 { 0.62 + near } else { 0.92 + 0.16 * self.fbm(u, v, 16, 1) };
+            mix([78, 58, 44], [114, 88, 68], self.fbm(u, v, 4, 4), shade, 255)
+        })
+    }
+
+    /// Cristales brillantes en el centro de cada celda, más oscuros en los bordes
+    pub fn glowstone(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let (near, second) = self.cells(u, v, 4);
+            let core = smoothstep(0.0, 0.35, second - near) * (1.0 - near * 0.5);
+            mix([176, 120, 58], [255, 236, 150], core, 1.0, 255)
+        })
+    }
+
+    /// Facetas (celdas) de un color con destellos donde el ruido fino es alto
+    fn gem(&self, dark: [u8; 3], light: [u8; 3], u: f32, v: f32) -> [u8; 4] {
+        let (near, second) = self.cells(u, v, 3);
+        let facet = near / second.max(0.001);
+        let sparkle = smoothstep(0.78, 0.9, self.fbm(u, v, 16, 1)) * 0.25;
+        let edge = smoothstep(0.0, 0.06, second - near);
+        mix(dark, light, 1.0 - facet + sparkle, 0.8 + 0.2 * edge, 255)
+    }
+
+    pub fn diamond(&self) -> Vec<u8> {
+        self.paint(|u, v| self.gem([96, 200, 210], [200, 250, 255], u, v))
+    }
+
+    pub fn emerald(&self) -> Vec<u8> {
+        self.paint(|u, v| self.gem([24, 150, 64], [110, 240, 140], u, v))
+    }
+
+    /// Obsidiana casi negra con vetas moradas donde el ruido cruza el medio
+    pub fn obsidian(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let n = self.fbm(u, v, 4, 4);
+            let vein = 1.0 - smoothstep(0.0, 0.04, (n - 0.5).abs());
+            mix([12, 8, 22], [62, 34, 96], n * 0.5 + vein * 0.6, 1.0, 255)
+        })
+    }
+
+    /// Hielo celeste con grietas claras en los bordes de las celdas
+    pub fn ice(&self) -> Vec<u8> {
+        self.paint(|u, v| {
+            let (near, second) = self.cells(u, v, 3);
+            let crack = 1.0 - smoothstep(0.0, 0.05, second - near);
+            mix([150, 192, 245], [235, 248, 255], self.fbm(u, v, 4, 3) * 0.6 + crack, 1.0, 200)
+        })
+    }
+}
@@ -1,5 +1,7 @@
 use raylib::prelude::Vector3;
-use crate::material::Material;
+use std::sync::Arc;
+
+use crate::material::{Material, UvTransform};
 
 #[derive(Clone)]
 pub struct Intersect {
@@ -7,9 +9,12 @@ pub struct Intersect {
     pub normal: Vector3,
     pub distance: f32,
     pub is_intersecting: bool,
-    pub material: Material,
+    /// Compartido con el objeto golpeado: copiar un impacto no copia el material
+    pub material: Arc<Material>,
     pub u: f32,
     pub v: f32,
+    /// Giro/espejo aplicado a (u, v) en esta cara, para orientar el mapa de normales
+    pub uv_transform: UvTransform,
 }
 
 impl Intersect {
@@ -17,7 +22,7 @@ impl Intersect {
         point: Vector3,
         normal: Vector3,
         distance: f32,
-        material: Material,
+        material: Arc<Material>,
         u: f32,
         v: f32,
     ) -> Self {
@@ -29,18 +34,26 @@ impl Intersect {
             material,
             u,
             v,
+            uv_transform: UvTransform::default(),
         }
     }
 
+    /// Anota el giro/espejo con que se calculó la UV del impacto
+    pub fn with_uv_transform(mut self, uv_transform: UvTransform) -> Self {
+        self.uv_transform = uv_transform;
+        self
+    }
+
     pub fn empty() -> Self {
         Intersect {
             point: Vector3::zero(),
             normal: Vector3::zero(),
             distance: 0.0,
             is_intersecting: false,
-            material: Material::black(),
+            material: Material::shared_black(),
             u: 0.0,
             v: 0.0,
+            uv_transform: UvTransform::default(),
         }
     }
 }
@@ -48,6 +61,30 @@ impl Intersect {
 pub trait RayIntersect {
     fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect;
     fn get_bounds(&self) -> AABB;
+
+    /// Cubo detrás del objeto, para el backend de GPU que solo traza bloques
+    #[cfg(feature = "gpu")]
+    fn as_cube(&self) -> Option<&crate::cube::Cube> {
+        None
+    }
+}
+
+/// Permite guardar objetos de la escena como `Arc<dyn RayIntersect>` y usar
+/// el mismo BVH para primitivas concretas (por ejemplo los triángulos de una malla)
+impl<T: RayIntersect + ?Sized> RayIntersect for std::sync::Arc<T> {
+    #[inline]
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        (**self).ray_intersect(ray_origin, ray_direction)
+    }
+
+    fn get_bounds(&self) -> AABB {
+        (**self).get_bounds()
+    }
+
+    #[cfg(feature = "gpu")]
+    fn as_cube(&self) -> Option<&crate::cube::Cube> {
+        (**self).as_cube()
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -97,12 +134,24 @@ impl AABB {
     }
 
     pub fn intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> bool {
+        self.intersect_within(ray_origin, ray_direction, f32::INFINITY)
+    }
+
+    /// Igual que `intersect`, pero descarta la caja si empieza más allá de `max_distance`
+    pub fn intersect_within(&self, ray_origin: &Vector3, ray_direction: &Vector3, max_distance: f32) -> bool {
         let inv_dir = Vector3::new(
             1.0 / ray_direction.x,
             1.0 / ray_direction.y,
             1.0 / ray_direction.z,
         );
+        self.entry_distance(ray_origin, &inv_dir, max_distance).is_some()
+    }
 
+    /// Distancia a la que el rayo entra en la caja (0 si empieza dentro), o `None`
+    /// si no la toca antes de `max_distance`. Recibe la inversa de la dirección,
+    /// calculada una sola vez por rayo.
+    #[inline]
+    pub fn entry_distance(&self, ray_origin: &Vector3, inv_dir: &Vector3, max_distance: f32) -> Option<f32> {
         let t1 = (self.min.x - ray_origin.x) * inv_dir.x;
         let t2 = (self.max.x - ray_origin.x) * inv_dir.x;
         let t3 = (self.min.y - ray_origin.y) * inv_dir.y;
@@ -113,14 +162,51 @@ impl AABB {
         let tmin = t1.min(t2).max(t3.min(t4)).max(t5.min(t6));
         let tmax = t1.max(t2).min(t3.max(t4)).min(t5.max(t6));
 
-        tmax >= 0.0 && tmin <= tmax
+        (tmax >= 0.0 && tmin <= tmax && tmin <= max_distance).then_some(tmin.max(0.0))
     }
 }
 
 pub struct BVH {
     root: Option<Box<BVHNode>>,
+    /// El mismo árbol con hasta 4 hijos por nodo, para probar sus cajas de a
+    /// cuatro con SIMD; es el que usan `intersect` y `occluded`
+    wide: Vec<WideNode>,
+    /// Objetos cuya caja cambió desde el último `refit` (por índice)
+    dirty: Vec<bool>,
 }
 
+/// Contadores de trabajo realizados por un rayo al recorrer el BVH
+#[derive(Clone, Copy, Default, Debug)]
+pub struct TraversalStats {
+    /// Pruebas rayo/AABB contra nodos del árbol
+    pub node_tests: u32,
+    /// Pruebas rayo/primitiva en las hojas
+    pub primitive_tests: u32,
+}
+
+/// Forma del árbol construido, para comparar la calidad del BVH entre versiones
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BvhMetrics {
+    pub node_count: usize,
+    pub leaf_count: usize,
+    pub max_depth: u32,
+    /// Profundidad promedio de las hojas
+    pub mean_leaf_depth: f32,
+}
+
+/// Nodo del BVH guardado en un arreglo plano en orden de profundidad: el hijo
+/// izquierdo de un nodo interno es el que le sigue en el arreglo
+pub struct FlatNode {
+    pub bounds: AABB,
+    /// Hoja: índice del objeto; nodo interno: posición del hijo derecho
+    pub index: usize,
+    pub is_leaf: bool,
+}
+
+/// Profundidad máxima del árbol (ver `build_recursive`) más margen; tamaño de
+/// la pila de recorrido
+const TRAVERSAL_STACK_SIZE: usize = 64;
+
 enum BVHNode {
     Leaf {
         bounds: AABB,
@@ -133,10 +219,119 @@ enum BVHNode {
     },
 }
 
+impl BVHNode {
+    #[inline]
+    fn bounds(&self) -> &AABB {
+        match self {
+            BVHNode::Leaf { bounds, .. } | BVHNode::Internal { bounds, .. } => bounds,
+        }
+    }
+
+    fn surface_area(&self) -> f32 {
+        let extent = self.bounds().max - self.bounds().min;
+        extent.x * extent.y + extent.y * extent.z + extent.z * extent.x
+    }
+}
+
+/// Marca en `WideNode::children` de los hijos que son objetos (hojas)
+const WIDE_LEAF: u32 = 1 << 31;
+/// Cada nodo ancho apila a lo sumo 3 hijos más de los que saca
+const WIDE_STACK_SIZE: usize = 3 * TRAVERSAL_STACK_SIZE;
+
+/// Nodo de 4 hijos con las cajas guardadas por eje (estructura de arreglos),
+/// así una sola instrucción SIMD compara el rayo con las cuatro
+#[derive(Clone, Copy)]
+#[repr(C, align(16))]
+struct WideNode {
+    min_x: [f32; 4],
+    min_y: [f32; 4],
+    min_z: [f32; 4],
+    max_x: [f32; 4],
+    max_y: [f32; 4],
+    max_z: [f32; 4],
+    /// Índice del nodo hijo, o del objeto con `WIDE_LEAF`
+    children: [u32; 4],
+    /// Hijos usados (1 a 4); el resto de las columnas se ignora
+    count: u32,
+}
+
+/// Rayos por paquete en `intersect_packet` y `occluded_packet` (un bit de máscara por rayo)
+pub const MAX_PACKET_SIZE: usize = 32;
+
+/// Rayo preparado para probar cajas de a cuatro
+#[derive(Clone, Copy)]
+struct WideRay {
+    origin: [f32; 3],
+    inv_dir: [f32; 3],
+}
+
+impl WideRay {
+    fn new(origin: &Vector3, direction: &Vector3) -> Self {
+        WideRay {
+            origin: [origin.x, origin.y, origin.z],
+            inv_dir: [1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z],
+        }
+    }
+}
+
+impl WideNode {
+    /// Prueba las cuatro cajas a la vez. Devuelve una máscara con un bit por
+    /// hijo tocado antes de `max_distance` y la distancia de entrada de cada
+    /// uno (0 si el rayo empieza dentro), como `AABB::entry_distance`.
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    fn hit(&self, ray: &WideRay, max_distance: f32) -> (u32, [f32; 4]) {
+        use std::arch::x86_64::*;
+
+        // SSE2 es parte de la base de x86_64, así que siempre está disponible
+        unsafe {
+            let slab = |min: &[f32; 4], max: &[f32; 4], origin: f32, inv_dir: f32| {
+                let origin = _mm_set1_ps(origin);
+                let inv_dir = _mm_set1_ps(inv_dir);
+                let t1 = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(min.as_ptr()), origin), inv_dir);
+                let t2 = _mm_mul_ps(_mm_sub_ps(_mm_loadu_ps(max.as_ptr()), origin), inv_dir);
+                (_mm_min_ps(t1, t2), _mm_max_ps(t1, t2))
+            };
+            let (near_x, far_x) = slab(&self.min_x, &self.max_x, ray.origin[0], ray.inv_dir[0]);
+            let (near_y, far_y) = slab(&self.min_y, &self.max_y, ray.origin[1], ray.inv_dir[1]);
+            let (near_z, far_z) = slab(&self.min_z, &self.max_z, ray.origin[2], ray.inv_dir[2]);
+
+            let tmin = _mm_max_ps(_mm_max_ps(near_x, near_y), _mm_max_ps(near_z, _mm_setzero_ps()));
+            let tmax = _mm_min_ps(_mm_min_ps(far_x, far_y), _mm_min_ps(far_z, _mm_set1_ps(max_distance)));
+            let mask = _mm_movemask_ps(_mm_cmple_ps(tmin, tmax)) as u32 & ((1 << self.count) - 1);
+
+            let mut entry = [0.0; 4];
+            _mm_storeu_ps(entry.as_mut_ptr(), tmin);
+            (mask, entry)
+        }
+    }
+
+    /// Versión escalar para otras arquitecturas, con la misma lógica por columna
+    #[cfg(not(target_arch = "x86_64"))]
+    #[inline]
+    fn hit(&self, ray: &WideRay, max_distance: f32) -> (u32, [f32; 4]) {
+        let mut mask = 0;
+        let mut entry = [0.0; 4];
+        for i in 0..self.count as usize {
+            let bounds = AABB::new(
+                Vector3::new(self.min_x[i], self.min_y[i], self.min_z[i]),
+                Vector3::new(self.max_x[i], self.max_y[i], self.max_z[i]),
+            );
+            let origin = Vector3::new(ray.origin[0], ray.origin[1], ray.origin[2]);
+            let inv_dir = Vector3::new(ray.inv_dir[0], ray.inv_dir[1], ray.inv_dir[2]);
+            if let Some(distance) = bounds.entry_distance(&origin, &inv_dir, max_distance) {
+                mask |= 1 << i;
+                entry[i] = distance;
+            }
+        }
+        (mask, entry)
+    }
+}
+
 impl BVH {
-    pub fn build(objects: &[std::sync::Arc<dyn RayIntersect + Send + Sync>]) -> Self {
+    pub fn build<T: RayIntersect>(objects: &[T]) -> Self {
         if objects.is_empty() {
-            return BVH { root: None };
+            return BVH { root: None, wide: Vec::new(), dirty: Vec::new() };
         }
 
         let mut primitives: Vec<(AABB, usize)> = objects
@@ -146,8 +341,180 @@ impl BVH {
             .collect();
 
         let root = Self::build_recursive(&mut primitives, 0);
-        
-        BVH { root: Some(Box::new(root)) }
+        let wide = Self::collapse(&root);
+
+        BVH {
+            root: Some(Box::new(root)),
+            wide,
+            dirty: vec![false; objects.len()],
+        }
+    }
+
+    /// Arma el árbol de 4 hijos juntando niveles del árbol binario: en cada
+    /// nodo se abre el hijo interno más grande hasta tener cuatro
+    fn collapse(root: &BVHNode) -> Vec<WideNode> {
+        let mut wide = Vec::new();
+        match root {
+            BVHNode::Leaf { .. } => Self::collapse_node(&[root], &mut wide),
+            BVHNode::Internal { left, right, .. } => Self::collapse_node(&[&**left, &**right], &mut wide),
+        };
+        wide
+    }
+
+    /// Agrega el nodo ancho con estos hijos (y sus descendientes); devuelve su índice
+    fn collapse_node(children: &[&BVHNode], wide: &mut Vec<WideNode>) -> u32 {
+        let mut children = children.to_vec();
+        while children.len() < 4 {
+            let largest = children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| matches!(child, BVHNode::Internal { .. }))
+                .max_by(|(_, a), (_, b)| a.surface_area().total_cmp(&b.surface_area()))
+                .map(|(i, _)| i);
+            let Some(i) = largest else { break };
+            if let BVHNode::Internal { left, right, .. } = children[i] {
+                children[i] = left;
+                children.push(right);
+            }
+        }
+
+        let index = wide.len();
+        wide.push(WideNode {
+            min_x: [0.0; 4],
+            min_y: [0.0; 4],
+            min_z: [0.0; 4],
+            max_x: [0.0; 4],
+            max_y: [0.0; 4],
+            max_z: [0.0; 4],
+            children: [0; 4],
+            count: children.len() as u32,
+        });
+
+        for (slot, child) in children.iter().enumerate() {
+            let bounds = *child.bounds();
+            let reference = match child {
+                BVHNode::Leaf { object_index, .. } => *object_index as u32 | WIDE_LEAF,
+                BVHNode::Internal { left, right, .. } => Self::collapse_node(&[&**left, &**right], wide),
+            };
+            let node = &mut wide[index];
+            node.min_x[slot] = bounds.min.x;
+            node.min_y[slot] = bounds.min.y;
+            node.min_z[slot] = bounds.min.z;
+            node.max_x[slot] = bounds.max.x;
+            node.max_y[slot] = bounds.max.y;
+            node.max_z[slot] = bounds.max.z;
+            node.children[slot] = reference;
+        }
+        index as u32
+    }
+
+    /// Marca el objeto `object_index` como movido o reemplazado; su caja se
+    /// actualiza en el próximo `refit`
+    pub fn mark_dirty(&mut self, object_index: usize) {
+        if let Some(dirty) = self.dirty.get_mut(object_index) {
+            *dirty = true;
+        }
+    }
+
+    /// Actualiza las cajas de los objetos marcados y de sus nodos ancestros,
+    /// de abajo hacia arriba, sin cambiar la forma del árbol. Sirve para
+    /// ediciones pequeñas en que `objects` mantiene la cantidad y el orden
+    /// usados en `build`; con muchos cambios conviene reconstruir, porque los
+    /// nodos pueden terminar muy superpuestos. Devuelve cuántos objetos se
+    /// actualizaron.
+    pub fn refit<T: RayIntersect>(&mut self, objects: &[T]) -> usize {
+        let refitted = self.dirty.iter().filter(|&&dirty| dirty).count();
+        if refitted == 0 {
+            return 0;
+        }
+        if let Some(root) = &mut self.root {
+            Self::refit_node(root, objects, &self.dirty);
+            self.wide = Self::collapse(root);
+        }
+        self.dirty.fill(false);
+        refitted
+    }
+
+    /// Agrega al árbol los objetos que se sumaron al final de `objects` desde
+    /// el `build`, bajando cada hoja nueva por el hijo cuya caja crece menos.
+    /// Para pocos objetos es mucho más barato que reconstruir; si el árbol
+    /// queda demasiado profundo para la pila de recorrido se reconstruye.
+    /// Devuelve cuántos objetos se agregaron.
+    pub fn insert_new<T: RayIntersect>(&mut self, objects: &[T]) -> usize {
+        let first = self.dirty.len();
+        if objects.len() <= first {
+            return 0;
+        }
+
+        for (index, object) in objects.iter().enumerate().skip(first) {
+            let leaf = BVHNode::Leaf { bounds: object.get_bounds(), object_index: index };
+            let root = match self.root.take() {
+                Some(root) => Self::insert_node(*root, leaf),
+                None => leaf,
+            };
+            self.root = Some(Box::new(root));
+        }
+        self.dirty.resize(objects.len(), false);
+
+        if let Some(root) = &self.root {
+            if Self::depth(root) > TRAVERSAL_STACK_SIZE as u32 - 8 {
+                *self = Self::build(objects);
+            } else {
+                self.wide = Self::collapse(root);
+            }
+        }
+        objects.len() - first
+    }
+
+    fn insert_node(node: BVHNode, leaf: BVHNode) -> BVHNode {
+        let bounds = node.bounds().union(leaf.bounds());
+        match node {
+            BVHNode::Internal { left, right, .. } => {
+                let growth = |child: &BVHNode| {
+                    let extent = child.bounds().union(leaf.bounds()).max - child.bounds().union(leaf.bounds()).min;
+                    extent.x * extent.y + extent.y * extent.z + extent.z * extent.x - child.surface_area()
+                };
+                if growth(&left) <= growth(&right) {
+                    BVHNode::Internal { bounds, left: Box::new(Self::insert_node(*left, leaf)), right }
+                } else {
+                    BVHNode::Internal { bounds, left, right: Box::new(Self::insert_node(*right, leaf)) }
+                }
+            }
+            BVHNode::Leaf { .. } => BVHNode::Internal { bounds, left: Box::new(node), right: Box::new(leaf) },
+        }
+    }
+
+    fn depth(node: &BVHNode) -> u32 {
+        match node {
+            BVHNode::Leaf { .. } => 0,
+            BVHNode::Internal { left, right, .. } => 1 + Self::depth(left).max(Self::depth(right)),
+        }
+    }
+
+    /// Devuelve `true` si la caja del nodo cambió
+    fn refit_node<T: RayIntersect>(node: &mut BVHNode, objects: &[T], dirty: &[bool]) -> bool {
+        match node {
+            BVHNode::Leaf { bounds, object_index } => {
+                if !dirty[*object_index] {
+                    return false;
+                }
+                match objects.get(*object_index) {
+                    Some(object) => {
+                        *bounds = object.get_bounds();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            BVHNode::Internal { bounds, left, right } => {
+                let left_changed = Self::refit_node(left, objects, dirty);
+                let right_changed = Self::refit_node(right, objects, dirty);
+                if left_changed || right_changed {
+                    *bounds = left.bounds().union(right.bounds());
+                }
+                left_changed || right_changed
+            }
+        }
     }
 
     fn build_recursive(primitives: &mut [(AABB, usize)], depth: u32) -> BVHNode {
@@ -209,61 +576,438 @@ impl BVH {
         }
     }
 
-    pub fn intersect(
+    /// Caja que envuelve todo el árbol (`None` si está vacío)
+    pub fn bounds(&self) -> Option<AABB> {
+        self.root.as_ref().map(|root| *root.bounds())
+    }
+
+    /// Árbol aplanado para recorrerlo sin punteros (por ejemplo en un shader)
+    /// o guardarlo en la caché
+    pub fn flatten(&self) -> Vec<FlatNode> {
+        let mut nodes = Vec::new();
+        if let Some(root) = &self.root {
+            Self::flatten_node(root, &mut nodes);
+        }
+        nodes
+    }
+
+    fn flatten_node(node: &BVHNode, nodes: &mut Vec<FlatNode>) {
+        match node {
+            BVHNode::Leaf { bounds, object_index } => nodes.push(FlatNode {
+                bounds: *bounds,
+                index: *object_index,
+                is_leaf: true,
+            }),
+            BVHNode::Internal { bounds, left, right } => {
+                let position = nodes.len();
+                nodes.push(FlatNode {
+                    bounds: *bounds,
+                    index: 0,
+                    is_leaf: false,
+                });
+                Self::flatten_node(left, nodes);
+                nodes[position].index = nodes.len();
+                Self::flatten_node(right, nodes);
+            }
+        }
+    }
+
+    /// Arma el árbol desde `flatten` (por ejemplo leído de la caché) para
+    /// `object_count` objetos. `None` si los nodos no forman un árbol válido
+    /// o alguna hoja apunta fuera de los objetos.
+    pub fn from_flat(nodes: &[FlatNode], object_count: usize) -> Option<Self> {
+        if nodes.is_empty() {
+            return (object_count == 0).then(|| BVH { root: None, wide: Vec::new(), dirty: Vec::new() });
+        }
+        let (root, end) = Self::unflatten_node(nodes, 0, object_count, 0)?;
+        if end != nodes.len() {
+            return None;
+        }
+        let wide = Self::collapse(&root);
+        Some(BVH { root: Some(Box::new(root)), wide, dirty: vec![false; object_count] })
+    }
+
+    /// Nodo en `position` y la posición que sigue a su subárbol
+    fn unflatten_node(nodes: &[FlatNode], position: usize, object_count: usize, depth: u32) -> Option<(BVHNode, usize)> {
+        let node = nodes.get(position)?;
+        if node.is_leaf {
+            return (node.index < object_count)
+                .then_some((BVHNode::Leaf { bounds: node.bounds, object_index: node.index }, position + 1));
+        }
+        // El mismo margen que `insert_new`: nunca más profundo que un árbol armado acá
+        if depth >= TRAVERSAL_STACK_SIZE as u32 - 8 {
+            return None;
+        }
+        let (left, left_end) = Self::unflatten_node(nodes, position + 1, object_count, depth + 1)?;
+        if node.index != left_end {
+            return None;
+        }
+        let (right, end) = Self::unflatten_node(nodes, left_end, object_count, depth + 1)?;
+        Some((BVHNode::Internal { bounds: node.bounds, left: Box::new(left), right: Box::new(right) }, end))
+    }
+
+    /// Cuenta nodos y hojas y mide la profundidad del árbol
+    pub fn metrics(&self) -> BvhMetrics {
+        let mut metrics = BvhMetrics::default();
+        let mut leaf_depth_sum = 0u64;
+        if let Some(root) = &self.root {
+            Self::collect_metrics(root, 0, &mut metrics, &mut leaf_depth_sum);
+        }
+        if metrics.leaf_count > 0 {
+            metrics.mean_leaf_depth = leaf_depth_sum as f32 / metrics.leaf_count as f32;
+        }
+        metrics
+    }
+
+    fn collect_metrics(node: &BVHNode, depth: u32, metrics: &mut BvhMetrics, leaf_depth_sum: &mut u64) {
+        metrics.node_count += 1;
+        metrics.max_depth = metrics.max_depth.max(depth);
+        match node {
+            BVHNode::Leaf { .. } => {
+                metrics.leaf_count += 1;
+                *leaf_depth_sum += depth as u64;
+            }
+            BVHNode::Internal { left, right, .. } => {
+                Self::collect_metrics(left, depth + 1, metrics, leaf_depth_sum);
+                Self::collect_metrics(right, depth + 1, metrics, leaf_depth_sum);
+            }
+        }
+    }
+
+    pub fn intersect<T: RayIntersect>(
         &self,
         ray_origin: &Vector3,
         ray_direction: &Vector3,
-        objects: &[std::sync::Arc<dyn RayIntersect + Send + Sync>],
+        objects: &[T],
     ) -> Intersect {
-        if let Some(root) = &self.root {
-            let mut best_intersect = Intersect::empty();
-            let mut best_distance = f32::INFINITY;
-            
-            Self::intersect_node(
-                root,
-                ray_origin,
-                ray_direction,
-                objects,
-                &mut best_intersect,
-                &mut best_distance,
-            );
-            
-            best_intersect
-        } else {
-            Intersect::empty()
+        if self.wide.is_empty() {
+            return Intersect::empty();
+        }
+
+        let ray = WideRay::new(ray_origin, ray_direction);
+        let mut best_intersect = Intersect::empty();
+        let mut best_distance = f32::INFINITY;
+
+        let mut stack = [(0u32, 0.0f32); WIDE_STACK_SIZE];
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let (node_index, entry) = stack[stack_len];
+            if entry > best_distance {
+                continue;
+            }
+
+            let node = &self.wide[node_index as usize];
+            let (mask, entries) = node.hit(&ray, best_distance);
+            if mask == 0 {
+                continue;
+            }
+
+            // Hijos tocados, del más cercano al más lejano
+            let mut hits = [(0u32, 0.0f32); 4];
+            let mut hit_count = 0;
+            for (slot, &child_entry) in entries.iter().enumerate() {
+                if mask & (1 << slot) != 0 {
+                    let mut i = hit_count;
+                    while i > 0 && hits[i - 1].1 > child_entry {
+                        hits[i] = hits[i - 1];
+                        i -= 1;
+                    }
+                    hits[i] = (node.children[slot], child_entry);
+                    hit_count += 1;
+                }
+            }
+
+            // Las hojas se prueban en orden; los nodos se apilan con el más cercano arriba
+            for &(child, child_entry) in hits[..hit_count].iter().rev() {
+                if child & WIDE_LEAF == 0 {
+                    stack[stack_len] = (child, child_entry);
+                    stack_len += 1;
+                }
+            }
+            for &(child, child_entry) in &hits[..hit_count] {
+                if child & WIDE_LEAF == 0 || child_entry > best_distance {
+                    continue;
+                }
+                if let Some(object) = objects.get((child & !WIDE_LEAF) as usize) {
+                    let intersect = object.ray_intersect(ray_origin, ray_direction);
+                    if intersect.is_intersecting && intersect.distance < best_distance {
+                        best_distance = intersect.distance;
+                        best_intersect = intersect;
+                    }
+                }
+            }
         }
+
+        best_intersect
     }
 
-    fn intersect_node(
-        node: &BVHNode,
+    /// Mismo resultado que `intersect`, pero recorre el árbol binario y
+    /// acumula en `stats` cuántos nodos y primitivas se probaron (usado por
+    /// la vista de depuración).
+    ///
+    /// Recorre el árbol con una pila en lugar de recursión, visitando primero
+    /// el hijo más cercano y descartando los nodos que empiezan más lejos que
+    /// el mejor impacto encontrado.
+    pub fn intersect_with_stats<T: RayIntersect>(
+        &self,
         ray_origin: &Vector3,
         ray_direction: &Vector3,
-        objects: &[std::sync::Arc<dyn RayIntersect + Send + Sync>],
-        best_intersect: &mut Intersect,
-        best_distance: &mut f32,
-    ) {
-        match node {
-            BVHNode::Leaf { bounds, object_index } => {
-                if !bounds.intersect(ray_origin, ray_direction) {
-                    return;
+        objects: &[T],
+        stats: &mut TraversalStats,
+    ) -> Intersect {
+        let Some(root) = &self.root else {
+            return Intersect::empty();
+        };
+
+        let inv_dir = Vector3::new(
+            1.0 / ray_direction.x,
+            1.0 / ray_direction.y,
+            1.0 / ray_direction.z,
+        );
+        let mut best_intersect = Intersect::empty();
+        let mut best_distance = f32::INFINITY;
+
+        stats.node_tests += 1;
+        let Some(root_distance) = root.bounds().entry_distance(ray_origin, &inv_dir, best_distance) else {
+            return best_intersect;
+        };
+
+        let mut stack: [(&BVHNode, f32); TRAVERSAL_STACK_SIZE] = [(root, 0.0); TRAVERSAL_STACK_SIZE];
+        let mut stack_len = 1;
+        stack[0] = (root, root_distance);
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let (node, entry) = stack[stack_len];
+            if entry > best_distance {
+                continue;
+            }
+
+            match node {
+                BVHNode::Leaf { object_index, .. } => {
+                    if let Some(object) = objects.get(*object_index) {
+                        stats.primitive_tests += 1;
+                        let intersect = object.ray_intersect(ray_origin, ray_direction);
+                        if intersect.is_intersecting && intersect.distance < best_distance {
+                            best_distance = intersect.distance;
+                            best_intersect = intersect;
+                        }
+                    }
                 }
-                
-                if *object_index < objects.len() {
-                    let intersect = objects[*object_index].ray_intersect(ray_origin, ray_direction);
-                    if intersect.is_intersecting && intersect.distance < *best_distance {
-                        *best_distance = intersect.distance;
-                        *best_intersect = intersect;
+                BVHNode::Internal { left, right, .. } => {
+                    stats.node_tests += 2;
+                    let left_hit = left.bounds().entry_distance(ray_origin, &inv_dir, best_distance);
+                    let right_hit = right.bounds().entry_distance(ray_origin, &inv_dir, best_distance);
+
+                    // El más cercano se apila al final para visitarlo primero
+                    let (near, far) = match (left_hit, right_hit) {
+                        (Some(l), Some(r)) if r < l => (Some((&**right, r)), Some((&**left, l))),
+                        (Some(l), r) => (Some((&**left, l)), r.map(|r| (&**right, r))),
+                        (None, r) => (r.map(|r| (&**right, r)), None),
+                    };
+                    for child in [far, near].into_iter().flatten() {
+                        stack[stack_len] = child;
+                        stack_len += 1;
                     }
                 }
             }
-            BVHNode::Internal { bounds, left, right } => {
-                if !bounds.intersect(ray_origin, ray_direction) {
-                    return;
+        }
+
+        best_intersect
+    }
+
+    /// Impacto más cercano de cada rayo (origen, dirección) de un paquete de
+    /// rayos coherentes, como los de un bloque de pixels vecinos. Los rayos
+    /// recorren juntos el árbol: cada nodo se lee una vez para todo el
+    /// paquete y solo se prueba con los rayos que tocaron a su padre.
+    pub fn intersect_packet<T: RayIntersect>(&self, rays: &[(Vector3, Vector3)], objects: &[T]) -> Vec<Intersect> {
+        debug_assert!(rays.len() <= MAX_PACKET_SIZE);
+        let mut results: Vec<Intersect> = rays.iter().map(|_| Intersect::empty()).collect();
+        if self.wide.is_empty() || rays.is_empty() {
+            return results;
+        }
+
+        let mut wide_rays = [WideRay { origin: [0.0; 3], inv_dir: [0.0; 3] }; MAX_PACKET_SIZE];
+        for (wide_ray, (origin, direction)) in wide_rays.iter_mut().zip(rays) {
+            *wide_ray = WideRay::new(origin, direction);
+        }
+        let mut best_distance = [f32::INFINITY; MAX_PACKET_SIZE];
+
+        let all_rays = (u64::MAX >> (64 - rays.len())) as u32;
+        let mut stack = [(0u32, 0u32); WIDE_STACK_SIZE];
+        stack[0] = (0, all_rays);
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let (node_index, mut active) = stack[stack_len];
+            let node = &self.wide[node_index as usize];
+
+            // Rayos que tocan cada hijo y la entrada más cercana entre ellos
+            let mut child_rays = [0u32; 4];
+            let mut child_entry = [f32::INFINITY; 4];
+            while active != 0 {
+                let ray = active.trailing_zeros() as usize;
+                active &= active - 1;
+                let (mut mask, entries) = node.hit(&wide_rays[ray], best_distance[ray]);
+                while mask != 0 {
+                    let slot = mask.trailing_zeros() as usize;
+                    mask &= mask - 1;
+                    child_rays[slot] |= 1 << ray;
+                    child_entry[slot] = child_entry[slot].min(entries[slot]);
+                }
+            }
+
+            let mut order = [0usize; 4];
+            let mut hit_count = 0;
+            for slot in 0..node.count as usize {
+                if child_rays[slot] != 0 {
+                    let mut i = hit_count;
+                    while i > 0 && child_entry[order[i - 1]] > child_entry[slot] {
+                        order[i] = order[i - 1];
+                        i -= 1;
+                    }
+                    order[i] = slot;
+                    hit_count += 1;
+                }
+            }
+
+            for &slot in order[..hit_count].iter().rev() {
+                if node.children[slot] & WIDE_LEAF == 0 {
+                    stack[stack_len] = (node.children[slot], child_rays[slot]);
+                    stack_len += 1;
+                }
+            }
+            for &slot in &order[..hit_count] {
+                let child = node.children[slot];
+                if child & WIDE_LEAF == 0 {
+                    continue;
+                }
+                let Some(object) = objects.get((child & !WIDE_LEAF) as usize) else {
+                    continue;
+                };
+                let mut leaf_rays = child_rays[slot];
+                while leaf_rays != 0 {
+                    let ray = leaf_rays.trailing_zeros() as usize;
+                    leaf_rays &= leaf_rays - 1;
+                    let (origin, direction) = &rays[ray];
+                    let intersect = object.ray_intersect(origin, direction);
+                    if intersect.is_intersecting && intersect.distance < best_distance[ray] {
+                        best_distance[ray] = intersect.distance;
+                        results[ray] = intersect;
+                    }
+                }
+            }
+        }
+
+        results
+    }
+
+    /// `occluded` para un paquete de rayos de sombra (origen, dirección,
+    /// distancia máxima); los rayos ya bloqueados dejan de recorrer el árbol
+    pub fn occluded_packet<T: RayIntersect>(&self, rays: &[(Vector3, Vector3, f32)], objects: &[T]) -> Vec<bool> {
+        debug_assert!(rays.len() <= MAX_PACKET_SIZE);
+        if self.wide.is_empty() || rays.is_empty() {
+            return vec![false; rays.len()];
+        }
+
+        let mut wide_rays = [WideRay { origin: [0.0; 3], inv_dir: [0.0; 3] }; MAX_PACKET_SIZE];
+        for (wide_ray, (origin, direction, _)) in wide_rays.iter_mut().zip(rays) {
+            *wide_ray = WideRay::new(origin, direction);
+        }
+
+        let all_rays = (u64::MAX >> (64 - rays.len())) as u32;
+        let mut blocked = 0u32;
+        let mut stack = [(0u32, 0u32); WIDE_STACK_SIZE];
+        stack[0] = (0, all_rays);
+        let mut stack_len = 1;
+
+        while stack_len > 0 && blocked != all_rays {
+            stack_len -= 1;
+            let (node_index, node_rays) = stack[stack_len];
+            let node = &self.wide[node_index as usize];
+
+            let mut child_rays = [0u32; 4];
+            let mut active = node_rays & !blocked;
+            while active != 0 {
+                let ray = active.trailing_zeros() as usize;
+                active &= active - 1;
+                let (mut mask, _) = node.hit(&wide_rays[ray], rays[ray].2);
+                while mask != 0 {
+                    let slot = mask.trailing_zeros() as usize;
+                    mask &= mask - 1;
+                    child_rays[slot] |= 1 << ray;
+                }
+            }
+
+            for (slot, &slot_rays) in child_rays.iter().enumerate() {
+                let child = node.children[slot];
+                if slot_rays == 0 {
+                    continue;
+                }
+                if child & WIDE_LEAF == 0 {
+                    stack[stack_len] = (child, slot_rays);
+                    stack_len += 1;
+                    continue;
+                }
+                let Some(object) = objects.get((child & !WIDE_LEAF) as usize) else {
+                    continue;
+                };
+                let mut leaf_rays = slot_rays & !blocked;
+                while leaf_rays != 0 {
+                    let ray = leaf_rays.trailing_zeros() as usize;
+                    leaf_rays &= leaf_rays - 1;
+                    let (origin, direction, max_distance) = &rays[ray];
+                    let intersect = object.ray_intersect(origin, direction);
+                    if intersect.is_intersecting && intersect.distance < *max_distance {
+                        blocked |= 1 << ray;
+                    }
                 }
-                
-                Self::intersect_node(left, ray_origin, ray_direction, objects, best_intersect, best_distance);
-                Self::intersect_node(right, ray_origin, ray_direction, objects, best_intersect, best_distance);
             }
         }
+
+        (0..rays.len()).map(|ray| blocked & (1 << ray) != 0).collect()
     }
-}
\ No newline at end of file
+
+    /// Consulta de oclusión para rayos de sombra: devuelve `true` en cuanto
+    /// encuentra cualquier objeto a menos de `max_distance`, sin buscar el más cercano
+    pub fn occluded<T: RayIntersect>(
+        &self,
+        ray_origin: &Vector3,
+        ray_direction: &Vector3,
+        max_distance: f32,
+        objects: &[T],
+    ) -> bool {
+        if self.wide.is_empty() {
+            return false;
+        }
+
+        let ray = WideRay::new(ray_origin, ray_direction);
+        let mut stack = [0u32; WIDE_STACK_SIZE];
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let node = &self.wide[stack[stack_len] as usize];
+            let (mut mask, _) = node.hit(&ray, max_distance);
+            while mask != 0 {
+                let slot = mask.trailing_zeros() as usize;
+                mask &= mask - 1;
+                let child = node.children[slot];
+                if child & WIDE_LEAF == 0 {
+                    stack[stack_len] = child;
+                    stack_len += 1;
+                } else if let Some(object) = objects.get((child & !WIDE_LEAF) as usize) {
+                    let intersect = object.ray_intersect(ray_origin, ray_direction);
+                    if intersect.is_intersecting && intersect.distance < max_distance {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+}
+
@@ -0,0 +1,212 @@
+use raylib::prelude::{Color, Vector2, Vector3};
+
+use crate::camera::Camera;
+use crate::renderer::{FOV, Ray};
+use crate::screenshot::escape_json;
+
+/// Largo (en bloques) con que se dibuja un rayo que no golpea nada
+const MISS_LENGTH: f32 = 30.0;
+/// Distancia mínima delante de la cámara para proyectar un punto
+const NEAR_PLANE: f32 = 0.05;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RayKind {
+    Primary,
+    Reflection,
+    Refraction,
+}
+
+impl RayKind {
+    fn name(self) -> &'static str {
+        match self {
+            RayKind::Primary => "primary",
+            RayKind::Reflection => "reflection",
+            RayKind::Refraction => "refraction",
+        }
+    }
+
+    /// Color con que se dibuja en el visor
+    pub fn color(self) -> Color {
+        match self {
+            RayKind::Primary => Color::YELLOW,
+            RayKind::Reflection => Color::SKYBLUE,
+            RayKind::Refraction => Color::MAGENTA,
+        }
+    }
+}
+
+/// Impacto de un rayo del árbol
+pub struct RayHit {
+    pub point: Vector3,
+    pub normal: Vector3,
+    pub distance: f32,
+    pub material: String,
+}
+
+/// Un rayo trazado, con lo que golpeó, la radiancia que devolvió y los rayos
+/// secundarios que generó. `weight` es la fracción con que su radiancia entra
+/// en la del rayo padre (reflectividad o transparencia del material).
+pub struct RayNode {
+    pub kind: RayKind,
+    pub ray: Ray,
+    pub depth: u32,
+    pub weight: f32,
+    pub hit: Option<RayHit>,
+    pub radiance: Vector3,
+    pub children: Vec<RayNode>,
+}
+
+fn vec3_json(v: Vector3) -> String {
+    format!("[{:.5}, {:.5}, {:.5}]", v.x, v.y, v.z)
+}
+
+impl RayNode {
+    pub fn new(kind: RayKind, ray: Ray, depth: u32, weight: f32) -> Self {
+        RayNode {
+            kind,
+            ray,
+            depth,
+            weight,
+            hit: None,
+            radiance: Vector3::zero(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Cantidad de rayos del árbol (incluyendo este)
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(RayNode::count).sum::<usize>()
+    }
+
+    /// Punto final del rayo: el impacto, o un tramo fijo si no golpeó nada
+    fn end_point(&self) -> Vector3 {
+        match &self.hit {
+            Some(hit) => hit.point,
+            None => self.ray.at(MISS_LENGTH),
+        }
+    }
+
+    fn to_json(&self, indent: usize) -> String {
+        let pad = " ".repeat(indent);
+        let hit = match &self.hit {
+            Some(hit) => format!(
+                "{{ \"point\": {}, \"normal\": {}, \"distance\": {:.5}, \"material\": \"{}\" }}",
+                vec3_json(hit.point),
+                vec3_json(hit.normal),
+                hit.distance,
+                escape_json(&hit.material),
+            ),
+            None => "null".to_string(),
+        };
+        let children: Vec<String> = self.children.iter().map(|child| child.to_json(indent + 4)).collect();
+        let children = if children.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n{}  ]", children.join(",\n"), pad)
+        };
+
+        format!(
+            "{pad}{{\n{pad}  \"kind\": \"{}\",\n{pad}  \"depth\": {},\n{pad}  \"origin\": {},\n{pad}  \"direction\": {},\n{pad}  \"hit\": {},\n{pad}  \"radiance\": {},\n{pad}  \"weight\": {:.5},\n{pad}  \"contribution\": {},\n{pad}  \"children\": {}\n{pad}}}",
+            self.kind.name(),
+            self.depth,
+            vec3_json(self.ray.origin),
+            vec3_json(self.ray.direction),
+            hit,
+            vec3_json(self.radiance),
+            self.weight,
+            vec3_json(self.radiance * self.weight),
+            children,
+            pad = pad,
+        )
+    }
+}
+
+/// Árbol de rayos de un pixel, para depurar la recursión de reflejos y
+/// refracciones (clic derecho sobre la imagen)
+pub struct RayTree {
+    pub pixel: (u32, u32),
+    pub root: RayNode,
+}
+
+impl RayTree {
+    pub fn new(pixel: (u32, u32), root: RayNode) -> Self {
+        RayTree { pixel, root }
+    }
+
+    /// Guarda el árbol como JSON en `renders/rays/` y devuelve la ruta
+    pub fn export(&self) -> Result<String, String> {
+        let dir_path = "renders/rays";
+        std::fs::create_dir_all(dir_path)
+            .map_err(|e| format!("No se pudo crear directorio {}: {}", dir_path, e))?;
+
+        let json = format!(
+            "{{\n  \"pixel\": [{}, {}],\n  \"rays\": {},\n  \"root\":\n{}\n}}\n",
+            self.pixel.0,
+            self.pixel.1,
+            self.root.count(),
+            self.root.to_json(2),
+        );
+        let path = format!("{}/ray_{:04}_{:04}.json", dir_path, self.pixel.0, self.pixel.1);
+        std::fs::write(&path, json).map_err(|e| format!("Error guardando {}: {}", path, e))?;
+        Ok(path)
+    }
+
+    /// Segmentos del árbol proyectados a la pantalla, con el color de cada tipo de rayo
+    pub fn screen_segments(&self, camera: &Camera, width: u32, height: u32) -> Vec<(Vector2, Vector2, Color)> {
+        let mut segments = Vec::new();
+        collect_segments(&self.root, camera, width, height, &mut segments);
+        segments
+    }
+}
+
+fn collect_segments(
+    node: &RayNode,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+    segments: &mut Vec<(Vector2, Vector2, Color)>,
+) {
+    if let Some((start, end)) = project_segment(camera, node.ray.origin, node.end_point(), width, height) {
+        segments.push((start, end, node.kind.color()));
+    }
+    for child in &node.children {
+        collect_segments(child, camera, width, height, segments);
+    }
+}
+
+/// Proyecta un segmento del mundo a pixels, recortándolo contra el plano cercano
+pub fn project_segment(camera: &Camera, a: Vector3, b: Vector3, width: u32, height: u32) -> Option<(Vector2, Vector2)> {
+    let depth_a = (a - camera.eye).dot(camera.forward);
+    let depth_b = (b - camera.eye).dot(camera.forward);
+    if depth_a < NEAR_PLANE && depth_b < NEAR_PLANE {
+        return None;
+    }
+
+    let clip = |from: Vector3, to: Vector3, depth_from: f32, depth_to: f32| {
+        if depth_from >= NEAR_PLANE {
+            from
+        } else {
+            from + (to - from) * ((NEAR_PLANE - depth_from) / (depth_to - depth_from))
+        }
+    };
+    let a_clipped = clip(a, b, depth_a, depth_b);
+    let b_clipped = clip(b, a, depth_b, depth_a);
+
+    Some((project(camera, a_clipped, width, height), project(camera, b_clipped, width, height)))
+}
+
+/// Inversa de `Ray::primary`: pixel por el que pasa un punto delante de la cámara
+fn project(camera: &Camera, point: Vector3, width: u32, height: u32) -> Vector2 {
+    // Los extremos ya vienen recortados al plano cercano
+    let depth = (point - camera.eye).dot(camera.forward);
+    let point = point + camera.forward * (NEAR_PLANE - depth).max(0.0);
+    let aspect_ratio = width as f32 / height as f32;
+    let (screen_x, screen_y, _) = camera
+        .to_screen(point, aspect_ratio, (FOV * 0.5).tan(), 0.0)
+        .unwrap_or_default();
+
+    Vector2::new(
+        (screen_x + 1.0) * 0.5 * width as f32,
+        (1.0 - screen_y) * 0.5 * height as f32,
+    )
+}
@@ -0,0 +1,189 @@
+use raylib::prelude::Vector3;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::renderer::{RenderSettings, Renderer};
+
+/// Filas mínimas por tramo: un bloque de paquetes (4 filas)
+const MIN_SLICE_ROWS: usize = 4;
+/// Pixels mínimos por tramo cuando solo se trazan algunos pixels
+const MIN_SLICE_PIXELS: usize = 1024;
+
+/// Pasada de render repartida en tramos que se trazan a lo largo de varios
+/// frames de la ventana, para que la interfaz siga respondiendo mientras
+/// tanto. La escena se pasa en cada tramo, así que la pasada no la retiene.
+pub struct RenderJob {
+    settings: RenderSettings,
+    /// Pixels a trazar (índices fila por fila); `None` traza el frame entero
+    pixels: Option<Vec<usize>>,
+    /// Frame entero: radiancia fila por fila. Lista de pixels: en el orden de `pixels`
+    radiance: Vec<Vector3>,
+    /// Distancia al primer impacto de cada pixel, en el mismo orden que `radiance`
+    depth: Vec<f32>,
+    /// Fila por la que empieza un frame entero (después sigue desde la 0)
+    first_row: usize,
+    /// Filas (o pixels de la lista) ya trazados
+    done: usize,
+    /// Filas (o pixels) ya entregados con `take_new`
+    shown: usize,
+    /// Tiempo dedicado a trazar, sin contar los frames intermedios
+    busy: Duration,
+    /// Segundos por fila (o pixel) del último tramo, para dimensionar el siguiente
+    seconds_per_unit: Option<f32>,
+}
+
+impl RenderJob {
+    pub fn new(settings: RenderSettings, pixels: Option<Vec<usize>>) -> Self {
+        let (radiance, depth) = match &pixels {
+            Some(pixels) => (Vec::with_capacity(pixels.len()), Vec::with_capacity(pixels.len())),
+            None => {
+                let size = settings.width as usize * settings.height as usize;
+                (vec![Vector3::zero(); size], vec![f32::NAN; size])
+            }
+        };
+        RenderJob {
+            settings,
+            pixels,
+            radiance,
+            depth,
+            first_row: 0,
+            done: 0,
+            shown: 0,
+            busy: Duration::ZERO,
+            seconds_per_unit: None,
+        }
+    }
+
+    /// Empieza el frame por la fila `row`. Al reemplazar una pasada que no
+    /// terminó (la cámara sigue moviéndose) se sigue desde donde quedó, así la
+    /// imagen se refresca entera de a poco en vez de solo su parte de arriba.
+    pub fn starting_at(mut self, row: usize) -> Self {
+        self.first_row = row % self.settings.height.max(1) as usize;
+        self
+    }
+
+    /// Fila por la que seguiría el frame (en una lista de pixels, la inicial)
+    pub fn next_row(&self) -> usize {
+        if self.pixels.is_some() {
+            return self.first_row;
+        }
+        (self.first_row + self.done) % self.settings.height.max(1) as usize
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// Pixels que traza la pasada, o `None` si es el frame entero
+    pub fn pixels(&self) -> Option<&[usize]> {
+        self.pixels.as_deref()
+    }
+
+    /// Radiancia de la pasada: fila por fila en un frame entero o en el orden
+    /// de `pixels`. Solo está completa al terminar.
+    pub fn radiance(&self) -> &[Vector3] {
+        &self.radiance
+    }
+
+    /// Distancia al primer impacto de cada pixel, en el orden de `radiance`.
+    /// Es NaN donde no se conoce (un frame trazado en la GPU).
+    pub fn depth(&self) -> &[f32] {
+        &self.depth
+    }
+
+    /// Si todavía no se trazó nada
+    pub fn is_fresh(&self) -> bool {
+        self.done == 0
+    }
+
+    pub fn busy_time(&self) -> Duration {
+        self.busy
+    }
+
+    fn total_units(&self) -> usize {
+        match &self.pixels {
+            Some(pixels) => pixels.len(),
+            None => self.settings.height as usize,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done >= self.total_units()
+    }
+
+    /// Termina un frame entero con la radiancia trazada por otro medio (la GPU)
+    pub fn finish_with(&mut self, frame: Vec<Vector3>, elapsed: Duration) {
+        self.radiance = frame;
+        self.depth = vec![f32::NAN; self.radiance.len()];
+        self.done = self.total_units();
+        self.busy += elapsed;
+    }
+
+    /// Traza tramos hasta terminar o hasta gastar `budget`; cada tramo se
+    /// dimensiona con lo que tardó el anterior. Devuelve `true` al terminar.
+    pub fn advance(&mut self, renderer: &Renderer, camera: &Camera, budget: Duration) -> bool {
+        let start = Instant::now();
+        let minimum = if self.pixels.is_some() { MIN_SLICE_PIXELS } else { MIN_SLICE_ROWS };
+
+        while !self.is_done() {
+            let remaining = budget.saturating_sub(start.elapsed()).as_secs_f32();
+            let slice = match self.seconds_per_unit {
+                Some(seconds) if seconds > 0.0 => ((remaining / seconds) as usize).max(minimum),
+                _ => minimum,
+            };
+            let slice = slice.next_multiple_of(minimum).min(self.total_units() - self.done);
+
+            let slice_start = Instant::now();
+            let traced = match &self.pixels {
+                Some(pixels) => {
+                    let traced = renderer.render_pixels_with_depth(camera, &self.settings, &pixels[self.done..self.done + slice]);
+                    for (radiance, depth) in traced {
+                        self.radiance.push(radiance);
+                        self.depth.push(depth);
+                    }
+                    slice
+                }
+                None => {
+                    // Sin pasar de la última fila: el resto sigue desde la 0 en el tramo siguiente
+                    let height = self.settings.height as usize;
+                    let rows = self.next_row()..(self.next_row() + slice).min(height);
+                    let width = self.settings.width as usize;
+                    let traced = renderer.render_rows_with_depth(camera, &self.settings, rows.clone());
+                    let first = rows.start * width;
+                    for (i, (radiance, depth)) in traced.into_iter().enumerate() {
+                        self.radiance[first + i] = radiance;
+                        self.depth[first + i] = depth;
+                    }
+                    rows.len()
+                }
+            };
+            self.done += traced;
+            self.seconds_per_unit = Some(slice_start.elapsed().as_secs_f32() / traced as f32);
+
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+
+        self.busy += start.elapsed();
+        self.is_done()
+    }
+
+    /// Tramos de un frame entero trazados desde la última llamada, como
+    /// rangos de pixels (fila por fila) dentro de `radiance`
+    pub fn take_new(&mut self) -> Vec<Range<usize>> {
+        let (height, width) = (self.settings.height.max(1) as usize, self.settings.width as usize);
+        let mut ranges = Vec::new();
+        let mut row = (self.first_row + self.shown) % height;
+        let mut left = self.done - self.shown;
+        while left > 0 {
+            let rows = left.min(height - row);
+            ranges.push(row * width..(row + rows) * width);
+            left -= rows;
+            row = 0;
+        }
+        self.shown = self.done;
+        ranges
+    }
+}
@@ -0,0 +1,708 @@
+use raylib::prelude::{Color, Vector3};
+use rayon::prelude::*;
+use std::f32::consts::PI;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::TEXTURE_MANAGER;
+use crate::camera::{Camera, Projection};
+use crate::chunks::ChunkedWorld;
+use crate::cube::{TriplanarProjection, block_face_axes};
+use crate::light::{Light, MAX_AREA_LIGHT_SAMPLES};
+use crate::material::{Material, limit_radiance, vector3_to_color};
+use crate::ray_intersect::{Intersect, RayIntersect, BVH, MAX_PACKET_SIZE};
+use crate::ray_tree::{RayHit, RayKind, RayNode};
+use crate::sampler::{AREA_LIGHT_DIMENSION, PIXEL_DIMENSION, SamplerKind};
+use crate::skybox::{self, Environment};
+use crate::texture::TextureView;
+use crate::volume;
+use crate::voxel_grid::VoxelGrid;
+
+const ORIGIN_BIAS: f32 = 1e-4;
+const MAX_DEPTH: u32 = 2;
+pub const FOV: f32 = PI / 2.0;
+/// Radiancia máxima por canal de los rayos reflejados/refractados (evita fireflies)
+const SECONDARY_SAMPLE_CLAMP: f32 = 8.0;
+/// Luz que deja pasar una sombra opaca
+const SHADOW_TRANSMISSION: f32 = 0.4;
+/// Máximo de superficies transparentes que atraviesa un rayo de sombra
+const MAX_SHADOW_LAYERS: u32 = 8;
+/// Lado (en pixels) de los bloques de rayos primarios que se trazan como paquete
+const PACKET_TILE: usize = 4;
+/// Coseno mínimo entre el rayo y la cara al estirar el ancho del pixel sobre
+/// una cara inclinada (para elegir el mipmap de las texturas)
+const MIN_FOOTPRINT_COS: f32 = 0.1;
+
+/// Distancia de un impacto primario; infinita si el rayo no tocó nada
+#[inline]
+fn hit_depth(hit: &Intersect) -> f32 {
+    if hit.is_intersecting { hit.distance } else { f32::INFINITY }
+}
+
+/// Desplazamiento (en [0, 1)²) de la muestra `sample` de la luz de área
+/// `light_index` dentro de su celda, tomado del muestreador del render con la
+/// muestra actual del pixel del rayo: cambia de un pixel a otro (grano fino
+/// en vez de escalones) y de una muestra a otra, así la penumbra converge al
+/// acumular. Los rayos sueltos, sin pixel, usan el centro de la celda.
+fn area_jitter(ray: &Ray, light_index: usize, sample: u32, depth: u32) -> (f32, f32) {
+    let Some(pixel) = ray.sample else {
+        return (0.5, 0.5);
+    };
+    let cells = MAX_AREA_LIGHT_SAMPLES * MAX_AREA_LIGHT_SAMPLES;
+    let dimension = AREA_LIGHT_DIMENSION + (light_index as u32 * (MAX_DEPTH + 1) + depth) * cells + sample;
+    pixel.sampler.sampler().sample_2d(pixel.index, pixel.pixel, dimension)
+}
+
+/// Normal geométrica del impacto inclinada según `relief`, la normal leída
+/// del mapa (en los ejes u/v de la cara, deshaciendo el giro/espejo de la UV)
+fn shading_normal(intersect: &Intersect, relief: Vector3) -> Vector3 {
+    let (tangent, bitangent) = block_face_axes(&intersect.normal);
+    let (x, y) = intersect.uv_transform.unapply_direction(relief.x, relief.y);
+    (tangent * x + bitangent * y + intersect.normal * relief.z).normalized()
+}
+
+/// Ancho (en el mundo) que cubre un pixel a lo largo de un rayo: `width` en
+/// el origen más `spread` por unidad de distancia. Con él se elige el nivel
+/// de mipmap de las texturas; sin ancho se lee siempre el más grande.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Footprint {
+    pub width: f32,
+    pub spread: f32,
+}
+
+impl Footprint {
+    /// Ancho a distancia `t` del origen
+    pub fn at(&self, t: f32) -> f32 {
+        self.width + self.spread * t
+    }
+}
+
+/// Pixel y muestra del render progresivo de los que sale un rayo, con el
+/// muestreador que reparte sus puntos
+#[derive(Clone, Copy, Debug)]
+pub struct PixelSample {
+    pub pixel: (usize, usize),
+    pub index: u32,
+    pub sampler: SamplerKind,
+}
+
+/// Rayo con origen y dirección normalizada
+#[derive(Clone, Copy, Debug)]
+pub struct Ray {
+    pub origin: Vector3,
+    pub direction: Vector3,
+    /// Ancho del pixel del que sale; los rayos sueltos no tienen
+    pub footprint: Footprint,
+    /// Muestra del pixel del que sale, si lo trazó un render
+    pub sample: Option<PixelSample>,
+}
+
+impl Ray {
+    pub fn new(origin: Vector3, direction: Vector3) -> Self {
+        Ray { origin, direction, footprint: Footprint::default(), sample: None }
+    }
+
+    /// Punto a distancia `t` del origen
+    pub fn at(&self, t: f32) -> Vector3 {
+        self.origin + self.direction * t
+    }
+
+    /// Rayo de la cámara que pasa por el pixel (x, y) de una imagen de `width` x `height`
+    pub fn primary(camera: &Camera, x: f32, y: f32, width: usize, height: usize) -> Self {
+        let aspect_ratio = width as f32 / height as f32;
+
+        let screen_x = (2.0 * x) / width as f32 - 1.0;
+        let screen_y = -(2.0 * y) / height as f32 + 1.0;
+
+        let tan_half_fov = (FOV * 0.5).tan();
+        let (origin, direction) = camera.screen_ray(screen_x, screen_y, aspect_ratio, tan_half_fov);
+        let footprint = match camera.projection {
+            Projection::Perspective => Footprint { width: 0.0, spread: 2.0 * tan_half_fov / height as f32 },
+            Projection::Orthographic { extent } => Footprint { width: 2.0 * extent / height as f32, spread: 0.0 },
+        };
+        Ray { origin, direction, footprint, sample: None }
+    }
+
+    /// Rayo que sale de un impacto hacia `direction`, separado un poco de la
+    /// superficie (del lado al que apunta) para no volver a chocar con ella
+    #[inline]
+    fn leaving(intersect: &Intersect, direction: Vector3) -> Self {
+        let offset = intersect.normal * ORIGIN_BIAS;
+        let origin = if direction.dot(intersect.normal) < 0.0 {
+            intersect.point - offset
+        } else {
+            intersect.point + offset
+        };
+        Ray::new(origin, direction)
+    }
+
+    /// Rayo reflejado o refractado de `self` en `intersect`: sale de la
+    /// superficie como `leaving` y sigue cubriendo el ancho del pixel, con
+    /// la misma muestra
+    #[inline]
+    fn bounce(&self, intersect: &Intersect, direction: Vector3) -> Self {
+        let mut ray = Ray::leaving(intersect, direction);
+        ray.footprint = Footprint { width: self.footprint.at(intersect.distance), spread: self.footprint.spread };
+        ray.sample = self.sample;
+        ray
+    }
+}
+
+#[inline]
+fn reflect(incident: &Vector3, normal: &Vector3) -> Vector3 {
+    *incident - *normal * 2.0 * incident.dot(*normal)
+}
+
+#[inline]
+fn refract(incident: &Vector3, normal: &Vector3, eta: f32) -> Option<Vector3> {
+    let cos_i = -incident.dot(*normal).max(-1.0).min(1.0);
+    let sin_t2 = eta * eta * (1.0 - cos_i * cos_i);
+
+    if sin_t2 > 1.0 {
+        return None;
+    }
+
+    let cos_t = (1.0 - sin_t2).sqrt();
+    Some(*incident * eta + *normal * (eta * cos_i - cos_t))
+}
+
+/// Todo lo que se renderiza en un frame: geometría con su BVH, luces y entorno
+#[derive(Clone, Copy)]
+pub struct Scene<'a> {
+    pub bvh: &'a BVH,
+    pub objects: &'a [Arc<dyn RayIntersect + Send + Sync>],
+    /// Grilla de vóxeles construida sobre los mismos objetos; si está, se usa en lugar del BVH
+    pub grid: Option<&'a VoxelGrid>,
+    /// Mundo en chunks con un BVH por chunk; si está (y no hay grilla), se usa en lugar del BVH
+    pub chunks: Option<&'a ChunkedWorld>,
+    pub lights: &'a [Light],
+    pub environment: &'a Environment<'a>,
+}
+
+/// Opciones de un frame
+#[derive(Clone, Copy)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    /// Número de muestra del render progresivo; la 0 pasa por el centro de cada pixel
+    pub sample_index: u32,
+    /// De dónde salen los desplazamientos dentro del pixel de las muestras siguientes
+    pub sampler: SamplerKind,
+}
+
+impl RenderSettings {
+    pub fn new(width: u32, height: u32) -> Self {
+        RenderSettings {
+            width,
+            height,
+            sample_index: 0,
+            sampler: SamplerKind::BlueNoise,
+        }
+    }
+
+    pub fn with_sample(mut self, sample_index: u32, sampler: SamplerKind) -> Self {
+        self.sample_index = sample_index;
+        self.sampler = sampler;
+        self
+    }
+
+    /// Desplazamiento del rayo dentro del pixel (x, y), en pixels
+    pub fn pixel_jitter(&self, x: usize, y: usize) -> (f32, f32) {
+        if self.sample_index == 0 {
+            return (0.0, 0.0);
+        }
+        let (u, v) = self.sampler.sampler().sample_2d(self.sample_index, (x, y), PIXEL_DIMENSION);
+        (u - 0.5, v - 0.5)
+    }
+
+    /// Rayo primario que pasa por el pixel (x, y) con el desplazamiento de esta muestra
+    fn primary_ray(&self, camera: &Camera, x: usize, y: usize) -> Ray {
+        let (jitter_x, jitter_y) = self.pixel_jitter(x, y);
+        let mut ray = Ray::primary(camera, x as f32 + jitter_x, y as f32 + jitter_y, self.width as usize, self.height as usize);
+        ray.sample = Some(PixelSample { pixel: (x, y), index: self.sample_index, sampler: self.sampler });
+        ray
+    }
+}
+
+/// Punto de entrada del motor: traza rayos sueltos o frames completos sobre una escena
+pub struct Renderer<'a> {
+    scene: Scene<'a>,
+    /// Texturas y reloj de las animaciones al crear el renderer; el lock
+    /// del administrador se toma una vez acá y no en cada impacto
+    textures: TextureView,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(scene: Scene<'a>) -> Self {
+        volume::set_flow_time(scene.environment.absolute_time);
+        let textures = TEXTURE_MANAGER.lock().unwrap().view();
+        Renderer { scene, textures }
+    }
+
+    pub fn scene(&self) -> &Scene<'a> {
+        &self.scene
+    }
+
+    /// Objeto más cercano que toca el rayo
+    pub fn intersect(&self, ray: &Ray) -> Intersect {
+        match (self.scene.grid, self.scene.chunks) {
+            (Some(grid), _) => grid.intersect(&ray.origin, &ray.direction),
+            (None, Some(chunks)) => chunks.intersect(&ray.origin, &ray.direction),
+            (None, None) => self.scene.bvh.intersect(&ray.origin, &ray.direction, self.scene.objects),
+        }
+    }
+
+    fn occluded(&self, ray: &Ray, max_distance: f32) -> bool {
+        match (self.scene.grid, self.scene.chunks) {
+            (Some(grid), _) => grid.occluded(&ray.origin, &ray.direction, max_distance),
+            (None, Some(chunks)) => chunks.occluded(&ray.origin, &ray.direction, max_distance),
+            (None, None) => self.scene.bvh.occluded(&ray.origin, &ray.direction, max_distance, self.scene.objects),
+        }
+    }
+
+    /// Radiancia (sin recortar) que llega por un rayo primario y la distancia
+    /// al primer impacto (infinita si el rayo sale al cielo), para poder
+    /// reproyectar el pixel en otro frame
+    pub fn trace_with_depth(&self, ray: &Ray) -> (Vector3, f32) {
+        let hit = self.intersect(ray);
+        let radiance = match self.scene.environment.shadow_catcher {
+            Some(ground_y) => self.trace_with_shadow_catcher(ray, ground_y),
+            None => self.shade_hit(ray, &hit, 0, skybox::PRIMARY_DISK_LIMIT, None, None),
+        };
+        (radiance, hit_depth(&hit))
+    }
+
+    /// Igual que `trace_with_depth`, pero registra cada rayo generado (reflejos y
+    /// refracciones) con su impacto y su aporte, para inspeccionar un pixel
+    pub fn trace_tree(&self, ray: &Ray) -> RayNode {
+        let mut root = RayNode::new(RayKind::Primary, *ray, 0, 1.0);
+        self.trace_depth(ray, 0, skybox::PRIMARY_DISK_LIMIT, Some(&mut root));
+        root
+    }
+
+    /// Devuelve la fracción (por canal) de la luz que llega al punto desde
+    /// `light_position` (la luz o, en las de área, uno de sus puntos).
+    /// Los materiales transparentes dejan pasar luz teñida por su color difuso.
+    pub fn shadow_transmission(&self, intersect: &Intersect, light_position: Vector3) -> Vector3 {
+        let light_dir = (light_position - intersect.point).normalized();
+        let light_distance = (light_position - intersect.point).length();
+        let ray = Ray::leaving(intersect, light_dir);
+
+        if !self.occluded(&ray, light_distance) {
+            return Vector3::one();
+        }
+        self.blocked_transmission(intersect, light_position)
+    }
+
+    /// Transmisión hacia una luz que ya se sabe tapada: recorre las capas que
+    /// hay en el camino y tiñe la luz con las transparentes
+    fn blocked_transmission(&self, intersect: &Intersect, light_position: Vector3) -> Vector3 {
+        let light_dir = (light_position - intersect.point).normalized();
+        let light_distance = (light_position - intersect.point).length();
+        let mut ray = Ray::leaving(intersect, light_dir);
+
+        let opaque = Vector3::new(SHADOW_TRANSMISSION, SHADOW_TRANSMISSION, SHADOW_TRANSMISSION);
+        let mut transmission = Vector3::one();
+        for _ in 0..MAX_SHADOW_LAYERS {
+            let blocker = self.intersect(&ray);
+            if !blocker.is_intersecting || (blocker.point - intersect.point).length() >= light_distance {
+                return transmission;
+            }
+
+            let transparency = blocker.material.transparency;
+            if transparency <= 0.05 {
+                return opaque;
+            }
+
+            // Solo se atenúa al entrar al objeto, para no contar dos veces cada bloque
+            if light_dir.dot(blocker.normal) < 0.0 {
+                let tint = blocker.material.linear_diffuse() * transparency;
+                transmission = Vector3::new(
+                    (transmission.x * tint.x).max(SHADOW_TRANSMISSION),
+                    (transmission.y * tint.y).max(SHADOW_TRANSMISSION),
+                    (transmission.z * tint.z).max(SHADOW_TRANSMISSION),
+                );
+            }
+            ray = Ray::leaving(&blocker, light_dir);
+        }
+
+        transmission
+    }
+
+    /// Radiancia de un rayo a profundidad `depth`; si `tree` es `Some`, guarda
+    /// en ese nodo el impacto, la radiancia y los rayos secundarios
+    fn trace_depth(&self, ray: &Ray, depth: u32, disk_limit: f32, mut tree: Option<&mut RayNode>) -> Vector3 {
+        let radiance = self.shade(ray, depth, disk_limit, tree.as_deref_mut());
+        if let Some(node) = tree {
+            node.radiance = radiance;
+        }
+        radiance
+    }
+
+    fn shade(&self, ray: &Ray, depth: u32, disk_limit: f32, tree: Option<&mut RayNode>) -> Vector3 {
+        let environment = self.scene.environment;
+        let ray_direction = &ray.direction;
+
+        if depth > MAX_DEPTH {
+            let is_nether = ray.origin.y < 0.0;
+            return environment.background(*ray_direction, is_nether, disk_limit);
+        }
+
+        let intersect = self.intersect(ray);
+        self.shade_hit(ray, &intersect, depth, disk_limit, tree, None)
+    }
+
+    /// Sombreado de un impacto ya calculado. `blocked`, si se conoce, dice por
+    /// cada luz de la escena si su rayo de sombra está tapado
+    fn shade_hit(
+        &self,
+        ray: &Ray,
+        intersect: &Intersect,
+        depth: u32,
+        disk_limit: f32,
+        mut tree: Option<&mut RayNode>,
+        blocked: Option<&[bool]>,
+    ) -> Vector3 {
+        let environment = self.scene.environment;
+        let ray_direction = &ray.direction;
+
+        if !intersect.is_intersecting {
+            let is_nether = ray.origin.y < 0.0;
+            return environment.background(*ray_direction, is_nether, disk_limit);
+        }
+
+        if let Some(node) = tree.as_deref_mut() {
+            node.hit = Some(RayHit {
+                point: intersect.point,
+                normal: intersect.normal,
+                distance: intersect.distance,
+                material: intersect.material.name.clone(),
+            });
+        }
+
+        let mut diffuse_color = intersect.material.linear_diffuse();
+        // Normal con que se ilumina: la de la geometría, con el relieve del
+        // mapa de normales si el material tiene uno (los rayos secundarios
+        // siguen saliendo con la normal geométrica)
+        let mut normal = intersect.normal;
+        // Brillo del punto: el del material, o el de sus mapas de rugosidad y metal
+        let mut gloss = intersect.material.gloss();
+        if intersect.material.is_textured() {
+            let textures = &self.textures;
+            let slots = intersect.material.slots;
+            let projection = intersect.material.triplanar.map(|scale| TriplanarProjection::new(intersect.point, intersect.normal, scale));
+            // Lo que cubre el pixel de la textura (una cara de bloque es una
+            // unidad de UV), estirado si la cara se ve de costado
+            let slant = ray_direction.dot(intersect.normal).abs().max(MIN_FOOTPRINT_COS);
+            let footprint = ray.footprint.at(intersect.distance) / slant
+                / intersect.material.triplanar.map_or(1.0, |scale| scale.max(1e-3));
+            if let Some(texture) = slots.texture {
+                diffuse_color = match &projection {
+                    Some(projection) => projection.blend(|u, v| Some(textures.sample(texture, u, v, footprint))).unwrap_or(diffuse_color),
+                    None => textures.sample(texture, intersect.u, intersect.v, footprint),
+                };
+            }
+            if let Some(normal_map) = slots.normal {
+                let relief_normal = match &projection {
+                    Some(projection) => projection.relief_normal(intersect.normal, |u, v| textures.sample_normal(normal_map, u, v, footprint)),
+                    None => textures
+                        .sample_normal(normal_map, intersect.u, intersect.v, footprint)
+                        .map(|relief| shading_normal(intersect, relief)),
+                };
+                normal = relief_normal.unwrap_or(normal);
+            }
+            let sample_map = |map: Option<usize>| {
+                let map = map?;
+                match &projection {
+                    Some(projection) => projection.blend(|u, v| textures.sample_value(map, u, v, footprint)),
+                    None => textures.sample_value(map, intersect.u, intersect.v, footprint),
+                }
+            };
+            gloss = intersect.material.gloss_at(sample_map(slots.roughness), sample_map(slots.metalness));
+        }
+        diffuse_color *= intersect.material.tint;
+
+        let view_dir = (ray.origin - intersect.point).normalized();
+        let mut final_color = Vector3::zero();
+
+        let ambient = environment.ambient.irradiance(&normal, intersect.point.y < 0.0);
+        final_color = final_color + diffuse_color * ambient;
+
+        if intersect.material.emissive.length() > 0.0 {
+            let pulse = if intersect.material.emissive_pulse { environment.portal_pulse } else { 1.0 };
+            final_color = final_color + intersect.material.linear_emissive() * 0.5 * pulse;
+        }
+
+        for (light_index, light) in self.scene.lights.iter().enumerate() {
+            let cone = light.cone_factor(&intersect.point);
+            if cone <= 0.0 {
+                continue;
+            }
+
+            // Las luces de área se reparten en varias muestras, cada una con su
+            // propio rayo de sombra; `blocked` solo sabe del centro de la luz
+            let sample_count = light.sample_count();
+            for sample in 0..sample_count {
+                let light_position = match light.area {
+                    Some(_) => light.sample_point(sample, area_jitter(ray, light_index, sample, depth)),
+                    None => light.position,
+                };
+                let light_dir = (light_position - intersect.point).normalized();
+                // Una luz detrás de la cara no la ilumina aunque el relieve mire hacia ella
+                if intersect.normal.dot(light_dir) <= 0.0 {
+                    continue;
+                }
+                let diffuse_dot = normal.dot(light_dir).max(0.0);
+
+                let emission = light.emission_factor(&light_position, &intersect.point) / sample_count as f32;
+                if emission <= 0.0 {
+                    continue;
+                }
+
+                let distance = (light_position - intersect.point).length();
+                let attenuation = light.attenuation(distance);
+
+                let transmission = match blocked.filter(|_| light.area.is_none()).map(|blocked| blocked[light_index]) {
+                    Some(false) => Vector3::one(),
+                    Some(true) => self.blocked_transmission(intersect, light_position),
+                    None => self.shadow_transmission(intersect, light_position),
+                };
+                let mut light_intensity = light.intensity * attenuation * cone * emission;
+                if light.cloud_shadows {
+                    light_intensity *= environment.cloud_shadow(intersect.point, light_dir);
+                }
+
+                let light_color_v3 = light.linear_color() * transmission;
+
+                let diffuse = diffuse_color * light_color_v3 * diffuse_dot * light_intensity;
+
+                let reflect_dir = reflect(&-light_dir, &normal);
+                let specular_intensity = view_dir
+                    .dot(reflect_dir)
+                    .max(0.0)
+                    .powf(gloss.specular)
+                    * light_intensity;
+                let specular = light_color_v3 * specular_intensity;
+
+                final_color = final_color + diffuse * gloss.albedo[0]
+                                          + specular * gloss.albedo[1];
+            }
+        }
+
+        let refractivity = intersect.material.transparency;
+        if refractivity > 0.05 && depth < MAX_DEPTH {
+            let refraction_index = intersect.material.refraction_index;
+            let (eta, adjusted_normal) = if ray_direction.dot(intersect.normal) < 0.0 {
+                (1.0 / refraction_index, intersect.normal)
+            } else {
+                (refraction_index, -intersect.normal)
+            };
+
+            if let Some(refract_dir) = refract(ray_direction, &adjusted_normal, eta) {
+                let refract_ray = ray.bounce(intersect, refract_dir);
+                let mut child = tree.is_some().then(|| RayNode::new(RayKind::Refraction, refract_ray, depth + 1, refractivity));
+                let refract_color = self.trace_depth(&refract_ray, depth + 1, disk_limit, child.as_mut());
+                if let (Some(node), Some(child)) = (tree.as_deref_mut(), child) {
+                    node.children.push(child);
+                }
+                let refract_color = limit_radiance(refract_color, SECONDARY_SAMPLE_CLAMP);
+                final_color = final_color * (1.0 - refractivity) + refract_color * refractivity;
+            }
+        }
+
+        let reflectivity = gloss.reflectivity;
+        if reflectivity > 0.05 && depth < MAX_DEPTH {
+            let reflect_dir = reflect(ray_direction, &intersect.normal).normalized();
+            let reflect_ray = ray.bounce(intersect, reflect_dir);
+            // Los reflejos pueden mostrar el sol más brillante que la pantalla, según lo pulido del material
+            let reflect_limit = skybox::reflection_disk_limit(gloss.specular);
+            let mut child = tree.is_some().then(|| RayNode::new(RayKind::Reflection, reflect_ray, depth + 1, reflectivity));
+            let reflect_color = self.trace_depth(&reflect_ray, depth + 1, reflect_limit, child.as_mut());
+            if let (Some(node), Some(child)) = (tree, child) {
+                node.children.push(child);
+            }
+            let reflect_color = limit_radiance(reflect_color, SECONDARY_SAMPLE_CLAMP);
+            final_color = final_color * (1.0 - reflectivity) + reflect_color * reflectivity;
+        }
+
+        final_color
+    }
+
+    /// Rayo primario con "shadow catcher": un plano invisible a la altura `ground_y`
+    /// que muestra el fondo, oscurecido donde la geometría le hace sombra
+    fn trace_with_shadow_catcher(&self, ray: &Ray, ground_y: f32) -> Vector3 {
+        let plane_t = if ray.direction.y.abs() > 1e-6 { (ground_y - ray.origin.y) / ray.direction.y } else { -1.0 };
+        let hits_geometry_first = plane_t <= 0.0 || {
+            let hit = self.intersect(ray);
+            hit.is_intersecting && hit.distance < plane_t
+        };
+        if hits_geometry_first {
+            return self.trace_depth(ray, 0, skybox::PRIMARY_DISK_LIMIT, None);
+        }
+
+        let point = ray.at(plane_t);
+        let ground = Intersect::new(point, Vector3::new(0.0, 1.0, 0.0), plane_t, Material::shared_black(), 0.0, 0.0);
+
+        // Promedio de la luz que llega al plano, pesado por lo que aportaría cada luz
+        let mut lit = Vector3::zero();
+        let mut total_weight = 0.0;
+        for light in self.scene.lights {
+            let to_light = light.position - point;
+            let weight = (to_light.y / to_light.length()).max(0.0)
+                * light.intensity
+                * light.attenuation(to_light.length())
+                * light.cone_factor(&point);
+            if weight <= 0.0 {
+                continue;
+            }
+            lit += self.shadow_transmission(&ground, light.position) * weight;
+            total_weight += weight;
+        }
+
+        let shadow = if total_weight > 0.0 { lit / total_weight } else { Vector3::one() };
+        self.scene.environment.background(ray.direction, false, skybox::PRIMARY_DISK_LIMIT) * shadow
+    }
+
+    /// Radiancia de un paquete de rayos primarios coherentes (a lo sumo
+    /// `MAX_PACKET_SIZE`): los impactos y los rayos de sombra hacia cada luz
+    /// se resuelven en paquete, y el resto del sombreado rayo por rayo.
+    /// Junto a cada radiancia va la distancia al impacto, como en `trace_with_depth`.
+    fn trace_packet(&self, rays: &[Ray]) -> Vec<(Vector3, f32)> {
+        let packet: Vec<_> = rays.iter().map(|ray| (ray.origin, ray.direction)).collect();
+        let hits = match (self.scene.grid, self.scene.chunks) {
+            (Some(_), _) => rays.iter().map(|ray| self.intersect(ray)).collect(),
+            (None, Some(chunks)) => chunks.intersect_packet(&packet),
+            (None, None) => self.scene.bvh.intersect_packet(&packet, self.scene.objects),
+        };
+        let blocked = self.packet_shadows(&hits);
+        let light_count = self.scene.lights.len();
+
+        rays.iter()
+            .zip(&hits)
+            .enumerate()
+            .map(|(i, (ray, hit))| {
+                let blocked = &blocked[i * light_count..(i + 1) * light_count];
+                let radiance = self.shade_hit(ray, hit, 0, skybox::PRIMARY_DISK_LIMIT, None, Some(blocked));
+                (radiance, hit_depth(hit))
+            })
+            .collect()
+    }
+
+    /// Rayos de sombra de un paquete de impactos: para cada luz dice, por
+    /// impacto, si algo tapa el camino (pixel por pixel, luz por luz)
+    fn packet_shadows(&self, hits: &[Intersect]) -> Vec<bool> {
+        let lights = self.scene.lights;
+        let mut blocked = vec![false; hits.len() * lights.len()];
+
+        for (light_index, light) in lights.iter().enumerate() {
+            let mut rays = Vec::with_capacity(hits.len());
+            let mut pixels = Vec::with_capacity(hits.len());
+            for (i, hit) in hits.iter().enumerate() {
+                if !hit.is_intersecting {
+                    continue;
+                }
+                // Mismos descartes que `shade_hit`: esas luces no se consultan
+                // (las de área lanzan sus propios rayos de sombra al sombrear)
+                let light_dir = (light.position - hit.point).normalized();
+                if light.area.is_some() || hit.normal.dot(light_dir) <= 0.0 || light.cone_factor(&hit.point) <= 0.0 {
+                    continue;
+                }
+                let ray = Ray::leaving(hit, light_dir);
+                rays.push((ray.origin, ray.direction, (light.position - hit.point).length()));
+                pixels.push(i);
+            }
+            if rays.is_empty() {
+                continue;
+            }
+
+            let occluded: Vec<bool> = match (self.scene.grid, self.scene.chunks) {
+                (Some(grid), _) => rays.iter().map(|(origin, direction, max)| grid.occluded(origin, direction, *max)).collect(),
+                (None, Some(chunks)) => chunks.occluded_packet(&rays),
+                (None, None) => self.scene.bvh.occluded_packet(&rays, self.scene.objects),
+            };
+            for (pixel, occluded) in pixels.into_iter().zip(occluded) {
+                blocked[pixel * lights.len() + light_index] = occluded;
+            }
+        }
+
+        blocked
+    }
+
+    /// Traza un rayo por pixel y devuelve la radiancia sin recortar, fila por fila
+    pub fn render_radiance(&self, camera: &Camera, settings: &RenderSettings) -> Vec<Vector3> {
+        self.render_rows(camera, settings, 0..settings.height as usize)
+    }
+
+    /// Como `render_radiance`, pero solo las filas `rows` del frame
+    pub fn render_rows(&self, camera: &Camera, settings: &RenderSettings, rows: Range<usize>) -> Vec<Vector3> {
+        self.render_rows_with_depth(camera, settings, rows).into_iter().map(|(radiance, _)| radiance).collect()
+    }
+
+    /// Radiancia y distancia al primer impacto de cada pixel de las filas `rows`.
+    /// Los pixels se agrupan en bloques de `PACKET_TILE`×`PACKET_TILE` que se
+    /// trazan como un paquete, salvo con shadow catcher.
+    pub fn render_rows_with_depth(&self, camera: &Camera, settings: &RenderSettings, rows: Range<usize>) -> Vec<(Vector3, f32)> {
+        let width = settings.width as usize;
+
+        if self.scene.environment.shadow_catcher.is_some() {
+            return rows
+                .into_par_iter()
+                .flat_map(|y| {
+                    let mut row_pixels = Vec::with_capacity(width);
+                    for x in 0..width {
+                        row_pixels.push(self.trace_with_depth(&settings.primary_ray(camera, x, y)));
+                    }
+                    row_pixels
+                })
+                .collect();
+        }
+
+        const _: () = assert!(PACKET_TILE * PACKET_TILE <= MAX_PACKET_SIZE);
+        let first_row = rows.start;
+        (0..rows.len().div_ceil(PACKET_TILE))
+            .into_par_iter()
+            .flat_map(|tile_row| {
+                let y0 = first_row + tile_row * PACKET_TILE;
+                let tile_rows = PACKET_TILE.min(rows.end - y0);
+                let mut tile = vec![(Vector3::zero(), f32::INFINITY); tile_rows * width];
+                let mut rays = Vec::with_capacity(PACKET_TILE * PACKET_TILE);
+                for x0 in (0..width).step_by(PACKET_TILE) {
+                    let columns = PACKET_TILE.min(width - x0);
+                    rays.clear();
+                    for dy in 0..tile_rows {
+                        for dx in 0..columns {
+                            rays.push(settings.primary_ray(camera, x0 + dx, y0 + dy));
+                        }
+                    }
+                    for (i, pixel) in self.trace_packet(&rays).into_iter().enumerate() {
+                        tile[(i / columns) * width + x0 + i % columns] = pixel;
+                    }
+                }
+                tile
+            })
+            .collect()
+    }
+
+    /// Traza solo los pixels indicados (índices fila por fila, como en
+    /// `render_radiance`) y devuelve su radiancia y distancia al primer
+    /// impacto en el mismo orden
+    pub fn render_pixels_with_depth(&self, camera: &Camera, settings: &RenderSettings, pixels: &[usize]) -> Vec<(Vector3, f32)> {
+        let width = settings.width as usize;
+
+        pixels
+            .par_iter()
+            .map(|&pixel| self.trace_with_depth(&settings.primary_ray(camera, pixel % width, pixel / width)))
+            .collect()
+    }
+
+    /// Renderiza un frame completo como imagen RGBA
+    pub fn render_frame(&self, camera: &Camera, settings: &RenderSettings) -> image::RgbaImage {
+        let mut image = image::RgbaImage::new(settings.width, settings.height);
+        let radiance = self.render_radiance(camera, settings);
+        for (pixel, radiance) in image.pixels_mut().zip(radiance) {
+            let color: Color = vector3_to_color(radiance);
+            *pixel = image::Rgba([color.r, color.g, color.b, color.a]);
+        }
+        image
+    }
+}
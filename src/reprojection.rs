@@ -0,0 +1,158 @@
+use raylib::prelude::Vector3;
+use std::ops::Range;
+
+use crate::camera::{Camera, Projection};
+use crate::renderer::{FOV, Ray};
+
+/// Si después de reproyectar quedan más huecos que esta fracción de la
+/// imagen (un salto grande de cámara), conviene trazar el frame de cero
+pub const MAX_HOLE_FRACTION: f32 = 0.25;
+/// Diferencia relativa de distancia hasta la que dos pixels vecinos se
+/// consideran de la misma superficie al rellenar grietas
+const CRACK_DEPTH_TOLERANCE: f32 = 0.05;
+
+/// Lo que muestra la pantalla junto con la distancia al primer impacto de
+/// cada pixel y la cámara que lo vio. Al mover la cámara se reproyecta a la
+/// vista nueva y solo los huecos necesitan rayos nuevos.
+pub struct FrameHistory {
+    camera: Camera,
+    width: usize,
+    height: usize,
+    radiance: Vec<Vector3>,
+    /// Distancia a lo largo del rayo primario; infinita para el cielo y NaN
+    /// donde todavía no hay dato (un hueco)
+    depth: Vec<f32>,
+}
+
+impl FrameHistory {
+    /// Historial vacío: todos los pixels son huecos hasta que se guarden
+    pub fn new(camera: &Camera, width: usize, height: usize) -> Self {
+        FrameHistory {
+            camera: camera.clone(),
+            width,
+            height,
+            radiance: vec![Vector3::zero(); width * height],
+            depth: vec![f32::NAN; width * height],
+        }
+    }
+
+    pub fn matches(&self, width: usize, height: usize) -> bool {
+        self.width == width && self.height == height
+    }
+
+    pub fn radiance(&self) -> &[Vector3] {
+        &self.radiance
+    }
+
+    /// Guarda pixels consecutivos (fila por fila) trazados con la cámara del historial
+    pub fn store_range(&mut self, range: Range<usize>, radiance: &[Vector3], depth: &[f32]) {
+        self.radiance[range.clone()].copy_from_slice(radiance);
+        self.depth[range].copy_from_slice(depth);
+    }
+
+    /// Guarda pixels sueltos trazados con la cámara del historial
+    pub fn store_pixels(&mut self, pixels: &[usize], traced: &[(Vector3, f32)]) {
+        for (&pixel, &(radiance, depth)) in pixels.iter().zip(traced) {
+            self.radiance[pixel] = radiance;
+            self.depth[pixel] = depth;
+        }
+    }
+
+    /// Pixels sin dato, fila por fila
+    pub fn holes(&self) -> Vec<usize> {
+        (0..self.depth.len()).filter(|&i| self.depth[i].is_nan()).collect()
+    }
+
+    /// Pixel (x, y) de la imagen donde `camera` ve el punto `point`, o la
+    /// dirección `point` si `at_infinity` (el cielo no cambia al trasladarse),
+    /// junto con la distancia a lo largo del rayo primario de ese pixel
+    fn project(&self, camera: &Camera, point: Vector3, at_infinity: bool) -> Option<(usize, usize, f32)> {
+        // Con rayos paralelos todo el cielo se ve en la misma dirección: no hay
+        // dónde ubicarlo, se vuelve a trazar
+        let orthographic = matches!(camera.projection, Projection::Orthographic { .. });
+        if at_infinity && orthographic {
+            return None;
+        }
+        let target = if at_infinity { camera.eye + point } else { point };
+
+        // Inversa de `Ray::primary`: pixel entero en el centro de la muestra 0
+        let aspect_ratio = self.width as f32 / self.height as f32;
+        let (screen_x, screen_y, depth) = camera.to_screen(target, aspect_ratio, (FOV * 0.5).tan(), 1e-4)?;
+        let x = ((screen_x + 1.0) * 0.5 * self.width as f32).round();
+        let y = ((1.0 - screen_y) * 0.5 * self.height as f32).round();
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+        let distance = if at_infinity {
+            f32::INFINITY
+        } else if orthographic {
+            depth
+        } else {
+            (point - camera.eye).length()
+        };
+        Some((x as usize, y as usize, distance))
+    }
+
+    /// Rellena las grietas de un pixel que deja la reproyección cuando la
+    /// imagen se estira: huecos entre dos vecinos opuestos a la misma
+    /// distancia, que por lo tanto son de la misma superficie (o del cielo)
+    fn fill_cracks(&mut self) {
+        let (width, height) = (self.width, self.height);
+        let same_surface = |a: f32, b: f32| {
+            (a.is_infinite() && b.is_infinite()) || (a - b).abs() <= CRACK_DEPTH_TOLERANCE * a.min(b)
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if !self.depth[i].is_nan() {
+                    continue;
+                }
+                let pairs = [
+                    (x > 0 && x + 1 < width).then(|| (i - 1, i + 1)),
+                    (y > 0 && y + 1 < height).then(|| (i - width, i + width)),
+                ];
+                for (a, b) in pairs.into_iter().flatten() {
+                    let (depth_a, depth_b) = (self.depth[a], self.depth[b]);
+                    if depth_a.is_nan() || depth_b.is_nan() || !same_surface(depth_a, depth_b) {
+                        continue;
+                    }
+                    self.radiance[i] = (self.radiance[a] + self.radiance[b]) * 0.5;
+                    self.depth[i] = (depth_a + depth_b) * 0.5;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Lleva cada pixel a donde lo ve `camera`, quedándose con el más cercano
+    /// cuando varios caen en el mismo lugar. Devuelve `None` si quedan más
+    /// huecos (zonas recién descubiertas o que salen del borde) que
+    /// `MAX_HOLE_FRACTION` de la imagen.
+    pub fn reproject(&self, camera: &Camera) -> Option<FrameHistory> {
+        let mut reprojected = FrameHistory::new(camera, self.width, self.height);
+
+        for (i, &depth) in self.depth.iter().enumerate() {
+            if depth.is_nan() {
+                continue;
+            }
+            let ray = Ray::primary(&self.camera, (i % self.width) as f32, (i / self.width) as f32, self.width, self.height);
+            let at_infinity = depth.is_infinite();
+            let point = if at_infinity { ray.direction } else { ray.at(depth) };
+            let Some((x, y, new_depth)) = self.project(camera, point, at_infinity) else {
+                continue;
+            };
+
+            let target = y * self.width + x;
+            let current = reprojected.depth[target];
+            if current.is_nan() || new_depth < current {
+                reprojected.depth[target] = new_depth;
+                reprojected.radiance[target] = self.radiance[i];
+            }
+        }
+
+        reprojected.fill_cracks();
+        let holes = reprojected.depth.iter().filter(|depth| depth.is_nan()).count();
+        (holes as f32 <= self.depth.len() as f32 * MAX_HOLE_FRACTION).then_some(reprojected)
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::Read;
+
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+use crate::TEXTURE_MANAGER;
+use crate::colormap::{BiomeTint, Colormap};
+use crate::texture::TextureData;
+use crate::texture_animation::AnimationTimeline;
+
+/// Carpeta de las texturas de bloques dentro del zip (Minecraft 1.13 en adelante)
+const BLOCK_TEXTURE_DIR: &str = "assets/minecraft/textures/block";
+
+/// Carpeta de los colormaps del pasto y el follaje dentro del zip
+const COLORMAP_DIR: &str = "assets/minecraft/textures/colormap";
+
+/// Tintes del bioma llanura con que el juego colorea las texturas grises que
+/// no siguen a los colormaps (el pasto de arriba y las hojas quedan grises y
+/// se tiñen por bioma al armar la escena)
+const GRASS_TINT: [u8; 3] = [0x91, 0xbd, 0x59];
+const WATER_TINT: [u8; 3] = [0x3f, 0x76, 0xe4];
+
+/// Archivo del paquete, textura que reemplaza y tinte que se le aplica
+const PACK_TEXTURES: [(&str, &str, Option<[u8; 3]>); 20] = [
+    ("stone", "stone", None),
+    ("dirt", "dirt", None),
+    ("grass_block_top", "grass_top", None),
+    ("grass_block_side", "grass_side", None),
+    ("oak_log", "wood", None),
+    ("oak_leaves", "leaves", None),
+    ("sand", "sand", None),
+    ("glass", "glass", None),
+    ("ice", "ice", None),
+    ("obsidian", "obsidian", None),
+    ("glowstone", "glowstone", None),
+    ("netherrack", "netherrack", None),
+    ("soul_sand", "soul_sand", None),
+    ("nether_bricks", "nether_brick", None),
+    ("bricks", "brick", None),
+    ("diamond_block", "diamond", None),
+    ("emerald_block", "emerald", None),
+    ("water_still", "water", Some(WATER_TINT)),
+    ("lava_still", "lava", None),
+    ("nether_portal", "portal", None),
+];
+
+/// Imagen `<name>.png` de la carpeta de bloques, o `None` si el paquete no la trae
+fn read_block_texture(archive: &mut ZipArchive<File>, name: &str) -> Result<Option<TextureData>, String> {
+    read_texture(archive, &format!("{}/{}.png", BLOCK_TEXTURE_DIR, name))
+}
+
+/// Imagen en `entry_path` dentro del zip, o `None` si el paquete no la trae
+fn read_texture(archive: &mut ZipArchive<File>, entry_path: &str) -> Result<Option<TextureData>, String> {
+    let mut entry = match archive.by_name(entry_path) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", entry_path, e)),
+    };
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| format!("{}: {}", entry_path, e))?;
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| format!("{}: no es una imagen válida: {}", entry_path, e))?
+        .to_rgba8();
+    Ok(Some(TextureData { width: image.width(), height: image.height(), data: image.into_raw(), hdr: None, mips: Vec::new() }))
+}
+
+/// Texto del `<name>.png.mcmeta` de la carpeta de bloques (cómo se anima la
+/// textura), o `None` si el paquete no lo trae
+fn read_block_meta(archive: &mut ZipArchive<File>, name: &str) -> Result<Option<String>, String> {
+    let entry_path = format!("{}/{}.png.mcmeta", BLOCK_TEXTURE_DIR, name);
+    let mut entry = match archive.by_name(&entry_path) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", entry_path, e)),
+    };
+    let mut json = String::new();
+    entry.read_to_string(&mut json).map_err(|e| format!("{}: {}", entry_path, e))?;
+    Ok(Some(json))
+}
+
+fn tint(texture: &mut TextureData, color: [u8; 3]) {
+    for rgba in texture.data.chunks_exact_mut(4) {
+        for (channel, tint) in rgba.iter_mut().zip(color) {
+            *channel = (*channel as u32 * tint as u32 / 255) as u8;
+        }
+    }
+}
+
+/// Cuadros de una tira vertical de animación (cuadrados, uno debajo del otro);
+/// una imagen que no es tira queda como un solo cuadro
+fn split_frames(texture: TextureData) -> Vec<TextureData> {
+    let (width, height) = (texture.width, texture.height);
+    if width == 0 || height <= width || height % width != 0 {
+        return vec![texture];
+    }
+    let frame_len = (width * width * 4) as usize;
+    texture.data
+        .chunks_exact(frame_len)
+        .map(|data| TextureData { width, height: width, data: data.to_vec(), hdr: None, mips: Vec::new() })
+        .collect()
+}
+
+/// Pinta encima del costado del pasto la franja verde (ya teñida) de
+/// `grass_block_side_overlay`, donde el juego la dibuja por separado
+fn blend_overlay(side: &mut TextureData, overlay: &TextureData) {
+    if (side.width, side.height) != (overlay.width, overlay.height) {
+        return;
+    }
+    for (base, top) in side.data.chunks_exact_mut(4).zip(overlay.data.chunks_exact(4)) {
+        let alpha = top[3] as u32;
+        for channel in 0..3 {
+            base[channel] = ((top[channel] as u32 * alpha + base[channel] as u32 * (255 - alpha)) / 255) as u8;
+        }
+    }
+}
+
+/// Abre un paquete de recursos de Minecraft (.zip) y reemplaza las texturas
+/// de bloques conocidas por las suyas. Devuelve cuántas reemplazó.
+pub fn load(path: &str) -> Result<usize, String> {
+    let file = File::open(path).map_err(|e| format!("No se pudo abrir el paquete {}: {}", path, e))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| format!("{} no es un zip válido: {}", path, e))?;
+
+    let mut textures = Vec::new();
+    for (file_name, texture_name, tint_color) in PACK_TEXTURES {
+        let Some(mut texture) = read_block_texture(&mut archive, file_name)? else {
+            continue;
+        };
+        if let Some(color) = tint_color {
+            tint(&mut texture, color);
+        }
+        let frames = split_frames(texture);
+        // Un mcmeta roto no descarta la textura: se anima con la duración por defecto
+        let timeline = match read_block_meta(&mut archive, file_name)? {
+            Some(json) if frames.len() > 1 => AnimationTimeline::from_mcmeta(&json, frames.len())
+                .map_err(|e| println!("{}/{}.png.mcmeta: {}", BLOCK_TEXTURE_DIR, file_name, e))
+                .ok(),
+            _ => None,
+        };
+        textures.push((texture_name, frames, timeline));
+    }
+    if textures.is_empty() {
+        return Err(format!(
+            "{} no trae texturas de bloques conocidas en {} (¿paquete anterior a 1.13?)",
+            path, BLOCK_TEXTURE_DIR
+        ));
+    }
+
+    if let Some(mut overlay) = read_block_texture(&mut archive, "grass_block_side_overlay")? {
+        tint(&mut overlay, GRASS_TINT);
+        let side = textures.iter_mut().find(|(name, _, _)| *name == "grass_side");
+        for frame in side.into_iter().flat_map(|(_, frames, _)| frames.iter_mut()) {
+            blend_overlay(frame, &overlay);
+        }
+    }
+
+    let mut colormaps = Vec::new();
+    for tint in BiomeTint::ALL {
+        let entry_path = format!("{}/{}.png", COLORMAP_DIR, tint.file_name());
+        if let Some(texture) = read_texture(&mut archive, &entry_path)? {
+            colormaps.push((tint, Colormap::from_texture(&texture).map_err(|e| format!("{}: {}", entry_path, e))?));
+        }
+    }
+
+    let count = textures.len();
+    let mut texture_manager = TEXTURE_MANAGER.lock().unwrap();
+    texture_manager.apply_resource_pack(path, textures);
+    for (tint, colormap) in colormaps {
+        texture_manager.set_colormap(tint, colormap);
+    }
+    Ok(count)
+}
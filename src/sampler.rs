@@ -0,0 +1,148 @@
+/// Dimensión de las muestras de anti-aliasing (desplazamiento dentro del pixel)
+pub const PIXEL_DIMENSION: u32 = 0;
+/// Primera dimensión de los puntos de las luces de área; cada celda de la
+/// grilla de cada luz usa la suya a partir de esta
+pub const AREA_LIGHT_DIMENSION: u32 = 1;
+
+/// Lado de la grilla de estratos del muestreo estratificado (8×8 = 64 muestras,
+/// las que acumula el render progresivo sin muestreo adaptativo)
+const STRATA_SIDE: u32 = 8;
+
+/// Fuente de puntos en [0, 1)² para la muestra `index` de un pixel.
+/// `dimension` separa los distintos usos de un mismo pixel para que sus
+/// puntos no queden correlacionados entre sí.
+pub trait Sampler: Send + Sync {
+    fn sample_2d(&self, index: u32, pixel: (usize, usize), dimension: u32) -> (f32, f32);
+}
+
+/// Mezcla de bits (lowbias32) para obtener valores pseudoaleatorios repetibles
+#[inline]
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
+
+#[inline]
+fn pixel_seed(pixel: (usize, usize), dimension: u32) -> u32 {
+    hash(pixel.0 as u32 ^ hash(pixel.1 as u32 ^ hash(dimension.wrapping_add(0x9e37_79b9))))
+}
+
+/// Convierte 24 bits del hash en un flotante en [0, 1)
+#[inline]
+fn unit_float(bits: u32) -> f32 {
+    (bits >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// Ruido blanco: cada muestra es independiente. Converge lento y deja grumos;
+/// queda como referencia para comparar con los otros
+pub struct RandomSampler;
+
+impl Sampler for RandomSampler {
+    fn sample_2d(&self, index: u32, pixel: (usize, usize), dimension: u32) -> (f32, f32) {
+        let seed = hash(pixel_seed(pixel, dimension) ^ hash(index));
+        (unit_float(seed), unit_float(hash(seed)))
+    }
+}
+
+/// Grilla de `side`×`side` estratos con un punto al azar dentro de cada uno.
+/// Cada pixel recorre los estratos en otro orden para que las primeras
+/// muestras no formen el mismo patrón en toda la imagen.
+pub struct StratifiedSampler {
+    side: u32,
+}
+
+impl StratifiedSampler {
+    pub const fn new(side: u32) -> Self {
+        StratifiedSampler { side }
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn sample_2d(&self, index: u32, pixel: (usize, usize), dimension: u32) -> (f32, f32) {
+        let count = self.side * self.side;
+        let seed = pixel_seed(pixel, dimension);
+        // Paso impar: con una cantidad de estratos potencia de dos recorre todos antes de repetir
+        let stride = (hash(seed) % count) | 1;
+        let stratum = (index % count).wrapping_mul(stride).wrapping_add(seed) % count;
+        let offset = hash(seed ^ hash(index));
+        (
+            ((stratum % self.side) as f32 + unit_float(offset)) / self.side as f32,
+            ((stratum / self.side) as f32 + unit_float(hash(offset))) / self.side as f32,
+        )
+    }
+}
+
+/// Secuencia R2 (baja discrepancia): los puntos cubren el cuadrado de forma
+/// pareja sin importar cuántos se tomen. Es la misma en todos los pixels.
+pub struct LowDiscrepancySampler;
+
+impl Sampler for LowDiscrepancySampler {
+    fn sample_2d(&self, index: u32, _pixel: (usize, usize), _dimension: u32) -> (f32, f32) {
+        (
+            (0.5 + index as f32 * 0.754_877_7).fract(),
+            (0.5 + index as f32 * 0.569_840_3).fract(),
+        )
+    }
+}
+
+/// Secuencia R2 desplazada en cada pixel por ruido de gradiente intercalado,
+/// cuyo espectro es de "ruido azul": el error entre pixels vecinos se reparte
+/// en alta frecuencia y se ve como grano fino en vez de grumos
+pub struct BlueNoiseSampler;
+
+impl Sampler for BlueNoiseSampler {
+    fn sample_2d(&self, index: u32, pixel: (usize, usize), dimension: u32) -> (f32, f32) {
+        let (x, y) = (pixel.0 as f32, pixel.1 as f32);
+        let noise = (52.982_918 * (0.067_110_56 * x + 0.005_837_15 * y).fract()).fract();
+        let shift = unit_float(hash(dimension.wrapping_add(1)));
+        let (u, v) = LowDiscrepancySampler.sample_2d(index, pixel, dimension);
+        ((u + noise + shift).fract(), (v + (noise * 1.618_034).fract() + shift).fract())
+    }
+}
+
+static RANDOM: RandomSampler = RandomSampler;
+static STRATIFIED: StratifiedSampler = StratifiedSampler::new(STRATA_SIDE);
+static LOW_DISCREPANCY: LowDiscrepancySampler = LowDiscrepancySampler;
+static BLUE_NOISE: BlueNoiseSampler = BlueNoiseSampler;
+
+/// Muestreadores disponibles (tecla Q)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SamplerKind {
+    Random,
+    Stratified,
+    LowDiscrepancy,
+    BlueNoise,
+}
+
+impl SamplerKind {
+    pub fn next(self) -> Self {
+        match self {
+            SamplerKind::Random => SamplerKind::Stratified,
+            SamplerKind::Stratified => SamplerKind::LowDiscrepancy,
+            SamplerKind::LowDiscrepancy => SamplerKind::BlueNoise,
+            SamplerKind::BlueNoise => SamplerKind::Random,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SamplerKind::Random => "aleatorio",
+            SamplerKind::Stratified => "estratificado",
+            SamplerKind::LowDiscrepancy => "baja discrepancia (R2)",
+            SamplerKind::BlueNoise => "ruido azul",
+        }
+    }
+
+    pub fn sampler(self) -> &'static dyn Sampler {
+        match self {
+            SamplerKind::Random => &RANDOM,
+            SamplerKind::Stratified => &STRATIFIED,
+            SamplerKind::LowDiscrepancy => &LOW_DISCREPANCY,
+            SamplerKind::BlueNoise => &BLUE_NOISE,
+        }
+    }
+}
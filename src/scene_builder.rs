@@ -1,11 +1,83 @@
 use raylib::prelude::*;
+use serde::Deserialize;
+use std::cell::RefCell;
 use std::sync::Arc;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use crate::TEXTURE_MANAGER;
+use crate::colormap::BiomeTint;
 use crate::ray_intersect::RayIntersect;
-use crate::light::Light;
+use crate::light::{Falloff, Flame, Light, MAX_AREA_LIGHT_SAMPLES};
 use crate::material::Material;
-use crate::cube::Cube;
+use crate::material_editor;
+use crate::cube::{BlockFaces, Cube};
+use crate::instance::Instance;
 use crate::mesh::Mesh;
+use crate::sphere::Sphere;
+use crate::cylinder::Cylinder;
+use crate::cone::Cone;
+use crate::partial_block::{BlockShape, Facing, PartialBlock};
+use crate::schematic::{BlockPlacement, Schematic};
+use crate::terrain::{Terrain, TerrainParams};
+use crate::biome::{BiomeMap, Vegetation};
+use crate::caves::{CaveParams, Caves};
+use crate::village::{VillagePlan, VillageStyle, HOUSE_SIZE};
+use crate::bridge::{BridgePlan, BridgeStyle};
+use crate::trees::{TreePlan, TreeSpecies};
+use crate::lsystem::LSystem;
+use crate::transform::{Transform, Transformed};
+use crate::volume::FallVolume;
+use crate::particles::{ParticleKind, hash01};
+use crate::built_scene::{BuiltScene, HiddenBlocks, SceneData, SceneMaterials};
+
+/// Alcance (en metros) de la luz de las antorchas
+const TORCH_LIGHT_RADIUS: f32 = 12.0;
+
+/// Vecinos de un bloque que comparten una cara con él
+pub const FACE_NEIGHBORS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// Cuántos bloques puede cortar un río para salir de un pozo antes de terminar ahí
+const RIVER_MAX_CUT: i32 = 1;
+/// Radio en columnas del promedio de alturas con que un río elige hacia dónde
+/// baja, para seguir la pendiente general y no cada escalón
+const RIVER_SLOPE_RADIUS: i32 = 4;
+
+/// Hasta cuántos bloques bajan los pilares de un puente buscando el suelo
+const MAX_PILLAR_DEPTH: i32 = 24;
+
+/// Hasta cuántas columnas desde su nacimiento busca una cascada el borde
+const WATERFALL_EDGE_SEARCH: i32 = 12;
+/// Bloques libres bajo la superficie que hacen de una columna el borde de
+/// una cascada, para no confundirlo con los escalones de una ladera
+const WATERFALL_MIN_DROP: i32 = 3;
+/// Radio en columnas de la poza que forma una cascada al pie
+const WATERFALL_POOL_RADIUS: i32 = 2;
+
+/// Materiales del suelo natural, los únicos que excavan las cuevas
+const CARVABLE_MATERIALS: [&str; 8] = ["stone", "dirt", "grass_top", "sand", "snow", "mycelium", "netherrack", "soul_sand"];
+
+/// Materiales de la biblioteca que se pueden colocar como bloques, por nombre
+pub fn block_materials() -> Vec<(String, Arc<Material>)> {
+    let mut materials: Vec<(String, Arc<Material>)> = MaterialLibrary::new()
+        .materials
+        .into_iter()
+        .filter(|(name, _)| !name.starts_with("particle_"))
+        .collect();
+    materials.sort_by(|a, b| a.0.cmp(&b.0));
+    materials
+}
+
+/// Pasos en que se redondean la temperatura y la humedad al teñir con los colormaps
+const CLIMATE_STEPS: u8 = 32;
+
+/// Material (por su dirección) y clima redondeado de una copia teñida
+type TintKey = (usize, (u8, u8));
 
 pub struct SceneBuilder {
     objects: Vec<Arc<dyn RayIntersect + Send + Sync>>,
@@ -13,11 +85,43 @@ pub struct SceneBuilder {
     materials: MaterialLibrary,
     use_obj_cubes: bool,
     grass_positions: Vec<(i32, i32, i32)>,
-    occupied_positions: HashSet<(i32, i32, i32)>,
+    /// Bloques colocados y el nombre de su material
+    occupied_positions: HashMap<(i32, i32, i32), String>,
+    /// Índice en `objects` del cubo de cada bloque, y si llena la celda completa
+    blocks: HashMap<(i32, i32, i32), (usize, bool)>,
+    /// Objetos de bloques quitados con `remove_block`
+    removed_objects: HashSet<usize>,
+    /// Mallas OBJ cargadas en su espacio local, por ruta; `None` si falló la carga
+    prototypes: HashMap<String, Option<Arc<Mesh>>>,
+    /// Sistemas L leídos de `assets/lsystems`, por ruta; `None` si no se pudo leer
+    lsystems: HashMap<String, Option<Arc<LSystem>>>,
+    /// Celdas con cercas, para conectar cada una con sus vecinas
+    fences: HashSet<(i32, i32, i32)>,
+    /// Biomas del terreno generado; sin ellos todo es pasto y robles
+    biomes: Option<BiomeMap>,
+    /// Grupos abiertos con `begin_group`, del más externo al más interno
+    groups: Vec<OpenGroup>,
+    /// Bloques con un material distinto arriba, a los costados y abajo, por nombre
+    block_palette: HashMap<String, Arc<BlockFaces>>,
+    /// Semilla de lo que varía al azar, como la forma de los árboles
+    seed: u32,
+    /// Copias teñidas de los materiales con colormap, por material y clima
+    /// redondeado, para que los bloques de un mismo clima compartan una
+    tinted_materials: RefCell<HashMap<TintKey, Arc<Material>>>,
+}
+
+/// Grupo abierto con `begin_group`: su primer objeto y su primera luz, y la
+/// grilla de afuera, que se aparta para armar el grupo en una grilla vacía
+struct OpenGroup {
+    first_object: usize,
+    first_light: usize,
+    occupied_positions: HashMap<(i32, i32, i32), String>,
+    blocks: HashMap<(i32, i32, i32), (usize, bool)>,
+    fences: HashSet<(i32, i32, i32)>,
 }
 
 struct MaterialLibrary {
-    materials: std::collections::HashMap<String, Material>,
+    materials: std::collections::HashMap<String, Arc<Material>>,
 }
 
 impl MaterialLibrary {
@@ -45,12 +149,56 @@ impl MaterialLibrary {
         materials.insert("obsidian".to_string(), Self::obsidian());
         materials.insert("ice".to_string(), Self::ice());
         materials.insert("portal".to_string(), Self::portal());
-        
+        materials.insert("sand".to_string(), Self::matte(Vector3::new(0.86, 0.8, 0.55), Some("sand")));
+        materials.insert("snow".to_string(), Self::matte(Vector3::new(0.95, 0.97, 1.0), None));
+        materials.insert("mycelium".to_string(), Self::matte(Vector3::new(0.45, 0.38, 0.45), None));
+        materials.insert("birch_wood".to_string(), Self::matte(Vector3::new(0.86, 0.84, 0.78), None).with_uv_variation());
+        materials.insert("birch_leaves".to_string(), Self::matte(Vector3::new(0.42, 0.62, 0.25), None).with_uv_variation());
+        materials.insert("spruce_leaves".to_string(), Self::matte(Vector3::new(0.12, 0.3, 0.16), None).with_uv_variation());
+        materials.insert("cactus".to_string(), Self::matte(Vector3::new(0.25, 0.55, 0.2), None));
+        materials.insert("mushroom_stem".to_string(), Self::matte(Vector3::new(0.85, 0.8, 0.7), None));
+        materials.insert("mushroom_cap".to_string(), Self::matte(Vector3::new(0.75, 0.12, 0.1), None));
+        materials.insert("particle_seed".to_string(), Self::particle(Vector3::new(0.95, 0.95, 0.9), Vector3::new(0.2, 0.2, 0.18)));
+        materials.insert("particle_ash".to_string(), Self::particle(Vector3::new(0.3, 0.28, 0.27), Vector3::zero()));
+        materials.insert("particle_snow".to_string(), Self::particle(Vector3::new(1.0, 1.0, 1.0), Vector3::new(0.25, 0.25, 0.3)));
+        materials.insert("particle_spore".to_string(), Self::particle(Vector3::new(0.6, 0.4, 0.7), Vector3::new(0.3, 0.15, 0.35)));
+
+        material_editor::apply_overrides(&mut materials);
+        let materials = materials
+            .into_iter()
+            .map(|(name, material)| {
+                let mut material = material.with_texture_maps();
+                material.name = name.clone();
+                (name, Arc::new(material))
+            })
+            .collect();
         MaterialLibrary { materials }
     }
     
-    fn get(&self, name: &str) -> Material {
-        self.materials.get(name).cloned().unwrap_or_else(|| Self::stone())
+    /// Material compartido por todos los objetos que lo usan
+    fn get(&self, name: &str) -> Arc<Material> {
+        self.materials.get(name).cloned().unwrap_or_else(|| Arc::new(Self::stone()))
+    }
+
+    /// Agrega un material o reemplaza el que tenga ese nombre; los cambios
+    /// guardados con el editor de materiales también se le aplican
+    fn register(&mut self, name: &str, mut material: Material) {
+        material.name = name.to_string();
+        let mut registered = HashMap::from([(name.to_string(), material)]);
+        material_editor::apply_overrides(&mut registered);
+        self.materials.extend(registered.into_iter().map(|(name, material)| (name, Arc::new(material))));
+    }
+
+    /// Si el material no deja pasar luz ni tiene huecos en la textura (los
+    /// desconocidos se cargan como piedra)
+    fn is_opaque(&self, name: &str) -> bool {
+        self.materials.get(name).is_none_or(|material| material.transparency <= 0.05 && material.alpha_mask.is_none())
+    }
+    
+    fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.materials.keys().cloned().collect();
+        names.sort();
+        names
     }
     
     fn grass_top() -> Material {
@@ -63,7 +211,7 @@ impl MaterialLibrary {
             1.0,
             Vector3::zero(),
             Some("grass_top".to_string())
-        )
+        ).with_uv_variation().with_biome_tint(BiomeTint::Grass)
     }
     
     fn grass_side() -> Material {
@@ -89,7 +237,7 @@ impl MaterialLibrary {
             1.0,
             Vector3::zero(),
             Some("dirt".to_string())
-        )
+        ).with_uv_variation()
     }
     
     fn stone() -> Material {
@@ -102,7 +250,7 @@ impl MaterialLibrary {
             1.0,
             Vector3::zero(),
             Some("stone".to_string())
-        )
+        ).with_uv_variation()
     }
     
     fn wood() -> Material {
@@ -128,7 +276,7 @@ impl MaterialLibrary {
             1.0,
             Vector3::zero(),
             Some("leaves".to_string())
-        )
+        ).with_biome_tint(BiomeTint::Foliage)
     }
     
     fn water() -> Material {
@@ -179,7 +327,7 @@ impl MaterialLibrary {
             0.2,
             1.52,
             Vector3::zero(),
-            None
+            Some("glass".to_string())
         )
     }
     
@@ -232,7 +380,7 @@ impl MaterialLibrary {
             1.0,
             Vector3::zero(),
             Some("netherrack".to_string())
-        )
+        ).with_uv_variation()
     }
     
     fn nether_brick() -> Material {
@@ -323,20 +471,45 @@ impl MaterialLibrary {
             1.2,
             Vector3::new(0.8, 0.2, 1.5),
             Some("portal".to_string())
-        )
+        ).with_emissive_pulse()
+    }
+
+    /// Material mate de los biomas, con textura o solo con color
+    fn matte(diffuse: Vector3, texture: Option<&str>) -> Material {
+        Material::new(diffuse, 5.0, [0.85, 0.05], 0.0, 0.0, 1.0, Vector3::zero(), texture.map(str::to_string))
+    }
+
+    /// Partícula ambiental: mate y con un leve brillo propio para que se vea de lejos
+    fn particle(diffuse: Vector3, emissive: Vector3) -> Material {
+        Material::new(diffuse, 10.0, [0.9, 0.1], 0.0, 0.0, 1.0, emissive, None)
     }
 }
 
 
 impl SceneBuilder {
     pub fn new() -> Self {
+        let materials = MaterialLibrary::new();
+        // El pasto lleva el nombre de su material de arriba, así el terreno,
+        // los biomas y los esquemas que ya usan grass_top lo arman completo
+        let grass = BlockFaces::new(materials.get("grass_top"), materials.get("grass_side"), materials.get("dirt"));
+
         SceneBuilder {
             objects: Vec::new(),
             lights: Vec::new(),
-            materials: MaterialLibrary::new(),
+            materials,
             use_obj_cubes: false,
             grass_positions: Vec::new(),
-            occupied_positions: HashSet::new(),
+            occupied_positions: HashMap::new(),
+            blocks: HashMap::new(),
+            removed_objects: HashSet::new(),
+            prototypes: HashMap::new(),
+            lsystems: HashMap::new(),
+            fences: HashSet::new(),
+            biomes: None,
+            groups: Vec::new(),
+            block_palette: HashMap::from([("grass_top".to_string(), Arc::new(grass))]),
+            seed: 0,
+            tinted_materials: RefCell::new(HashMap::new()),
         }
     }
     
@@ -345,16 +518,144 @@ impl SceneBuilder {
         self
     }
 
+    /// Cambia la semilla con que varían la altura, la copa y las ramas de los
+    /// árboles que se planten después; la misma semilla da el mismo bosque
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Reparte el terreno y las islas que se generen después en biomas
+    /// (llanura, desierto, nevado, hongos y Nether) según la semilla: cada uno
+    /// cambia los materiales de la superficie y qué crece en ella
+    pub fn with_biomes(mut self, seed: u32) -> Self {
+        self.biomes = Some(BiomeMap::new(seed));
+        self
+    }
+
+    /// Materiales de la superficie y de las capas de debajo en la columna (x, z)
+    fn ground_materials(&self, x: i32, z: i32) -> (&'static str, &'static str) {
+        match &self.biomes {
+            Some(biomes) => {
+                let biome = biomes.biome_at(x, z);
+                (biome.surface(), biome.subsurface())
+            }
+            None => ("grass_top", "dirt"),
+        }
+    }
+
+    /// El material con el tinte de su colormap en el clima de la columna
+    /// (x, z); sin biomas queda el de la llanura, que ya trae de la biblioteca
+    fn tinted(&self, material: Arc<Material>, x: i32, z: i32) -> Arc<Material> {
+        let (Some(biome_tint), Some(biomes)) = (material.biome_tint, &self.biomes) else {
+            return material;
+        };
+        let climate = biomes.climate_at(x, z);
+        let steps = CLIMATE_STEPS as f32;
+        let key = ((climate.0 * steps).round() as u8, (climate.1 * steps).round() as u8);
+        let mut tinted_materials = self.tinted_materials.borrow_mut();
+        tinted_materials
+            .entry((Arc::as_ptr(&material) as usize, key))
+            .or_insert_with(|| {
+                let climate = (key.0 as f32 / steps, key.1 as f32 / steps);
+                let tint = TEXTURE_MANAGER.lock().unwrap().biome_tint(biome_tint, material.texture_path.as_deref(), climate);
+                if (tint - material.tint).length() < 1e-3 {
+                    return material.clone();
+                }
+                Arc::new(Material { tint, ..(*material).clone() })
+            })
+            .clone()
+    }
+
+    /// Material de la biblioteca con ese nombre, teñido para la columna (x, z)
+    fn column_material(&self, name: &str, x: i32, z: i32) -> Arc<Material> {
+        self.tinted(self.materials.get(name), x, z)
+    }
+
+    /// Nombres de todos los materiales registrados, en orden alfabético
+    pub fn material_names(&self) -> Vec<String> {
+        self.materials.names()
+    }
+
+    /// Modelo compartido para `path`, cargado la primera vez que se pide
+    fn prototype(&mut self, path: &str) -> Option<Arc<Mesh>> {
+        self.prototypes
+            .entry(path.to_string())
+            .or_insert_with(|| match Mesh::from_obj(path, &Material::shared_black(), Vector3::zero(), 1.0) {
+                Ok(mesh) => Some(Arc::new(mesh)),
+                Err(e) => {
+                    println!("  Error cargando {}: {}. Usando cubo procedural.", path, e);
+                    None
+                }
+            })
+            .clone()
+    }
+
+    /// Agrega una instancia del modelo en `path`, o un cubo si no se pudo cargar
+    fn add_instance(&mut self, path: &str, position: Vector3, scale: f32, material: Arc<Material>) {
+        match self.prototype(path) {
+            Some(mesh) => self.objects.push(Arc::new(Instance::new(mesh, position, scale, material))),
+            None => self.objects.push(Arc::new(Cube::new(position, scale, material))),
+        }
+    }
+
     pub fn is_position_occupied(&self, x: i32, y: i32, z: i32) -> bool {
-        self.occupied_positions.contains(&(x, y, z))
+        self.occupied_positions.contains_key(&(x, y, z))
     }
 
-    fn mark_position(&mut self, x: i32, y: i32, z: i32) {
-        self.occupied_positions.insert((x, y, z));
+    fn mark_position(&mut self, x: i32, y: i32, z: i32, material: &str) {
+        self.occupied_positions.insert((x, y, z), material.to_string());
+    }
+
+    /// Material del bloque colocado en la celda, si hay uno
+    pub fn material_at(&self, x: i32, y: i32, z: i32) -> Option<&str> {
+        self.occupied_positions.get(&(x, y, z)).map(String::as_str)
     }
 
     pub fn remove_block(&mut self, x: i32, y: i32, z: i32) {
         self.occupied_positions.remove(&(x, y, z));
+        if let Some((index, _)) = self.blocks.remove(&(x, y, z)) {
+            self.removed_objects.insert(index);
+        }
+        if self.fences.remove(&(x, y, z)) {
+            self.connect_fence_neighbors(x, y, z);
+        }
+    }
+
+    /// Coloca una pieza de bloque en la celda si está libre. No tapa las caras
+    /// de sus vecinos, así que no cuenta como bloque completo.
+    fn add_partial_block(&mut self, x: i32, y: i32, z: i32, shape: BlockShape, material: &str) -> bool {
+        if self.is_position_occupied(x, y, z) {
+            return false;
+        }
+        let mat = self.column_material(material, x, z);
+        self.blocks.insert((x, y, z), (self.objects.len(), false));
+        self.objects.push(Arc::new(PartialBlock::new(Vector3::new(x as f32, y as f32, z as f32), shape, mat)));
+        self.mark_position(x, y, z, material);
+        true
+    }
+
+    /// Vuelve a armar la cerca de la celda con travesaños hacia las cercas vecinas
+    fn connect_fence(&mut self, x: i32, y: i32, z: i32) {
+        let (Some(&(index, _)), Some(material)) = (self.blocks.get(&(x, y, z)), self.occupied_positions.get(&(x, y, z))) else {
+            return;
+        };
+        let connections = Facing::ALL.map(|facing| {
+            let (dx, dz) = facing.offset();
+            self.fences.contains(&(x + dx, y, z + dz))
+        });
+        let mat = self.materials.get(material);
+        let shape = BlockShape::Fence { connections };
+        self.objects[index] = Arc::new(PartialBlock::new(Vector3::new(x as f32, y as f32, z as f32), shape, mat));
+    }
+
+    fn connect_fence_neighbors(&mut self, x: i32, y: i32, z: i32) {
+        for facing in Facing::ALL {
+            let (dx, dz) = facing.offset();
+            if self.fences.contains(&(x + dx, y, z + dz)) {
+                self.connect_fence(x + dx, y, z + dz);
+            }
+        }
     }
     
     pub fn add_cube(mut self, x: f32, y: f32, z: f32, size: f32, material: &str) -> Self {
@@ -367,58 +668,243 @@ impl SceneBuilder {
             return self;
         }
         
-        let mat = self.materials.get(material);
+        let mat = self.column_material(material, xi, zi);
+        let full = size >= 1.0 && x == xi as f32 && y == yi as f32 && z == zi as f32;
+        self.blocks.insert((xi, yi, zi), (self.objects.len(), full));
         
         if self.use_obj_cubes {
-            match Mesh::from_obj("assets/cube.obj", &mat, Vector3::new(x, y, z), size) {
-                Ok(mesh) => {
-                    self.objects.extend(mesh.to_objects());
-                }
-                Err(e) => {
-                    println!("  Error cargando cube.obj: {}. Usando cubo procedural.", e);
-                    self.objects.push(Arc::new(Cube::new(Vector3::new(x, y, z), size, mat)));
-                }
-            }
+            self.add_instance("assets/cube.obj", Vector3::new(x, y, z), size, mat);
         } else {
             self.objects.push(Arc::new(Cube::new(Vector3::new(x, y, z), size, mat)));
         }
         
         // Marcar posición como ocupada
-        self.mark_position(xi, yi, zi);
+        self.mark_position(xi, yi, zi, material);
         
         self
     }
 
-    pub fn add_floor(mut self, radius: i32, material: &str) -> Self {
+    /// Agrega un bloque completo de la paleta (con sus caras) en la celda, o
+    /// un cubo de un solo material si `block` no está en la paleta
+    pub fn add_block(mut self, x: i32, y: i32, z: i32, block: &str) -> Self {
+        if !self.block_palette.contains_key(block) {
+            return self.add_cube(x as f32, y as f32, z as f32, 1.0, block);
+        }
+        if self.is_position_occupied(x, y, z) {
+            return self;
+        }
+
+        self.blocks.insert((x, y, z), (self.objects.len(), true));
+        let cube = self.block_cube(x, y, z, block);
+        self.objects.push(Arc::new(cube));
+        self.mark_position(x, y, z, block);
+        self
+    }
+
+    /// Cubo de un bloque completo en la celda: con sus caras si está en la
+    /// paleta, o del material con ese nombre
+    fn block_cube(&self, x: i32, y: i32, z: i32, block: &str) -> Cube {
+        let center = Vector3::new(x as f32, y as f32, z as f32);
+        match self.block_palette.get(block) {
+            Some(faces) if [&faces.top, &faces.side, &faces.bottom].iter().any(|face| face.biome_tint.is_some()) => {
+                let tinted = BlockFaces::new(
+                    self.tinted(faces.top.clone(), x, z),
+                    self.tinted(faces.side.clone(), x, z),
+                    self.tinted(faces.bottom.clone(), x, z),
+                );
+                Cube::with_faces(center, 1.0, Arc::new(tinted))
+            }
+            Some(faces) => Cube::with_faces(center, 1.0, faces.clone()),
+            None => Cube::new(center, 1.0, self.column_material(block, x, z)),
+        }
+    }
+
+    pub fn add_sphere(mut self, x: f32, y: f32, z: f32, radius: f32, material: &str) -> Self {
+        let mat = self.materials.get(material);
+        self.objects.push(Arc::new(Sphere::new(Vector3::new(x, y, z), radius, mat)));
+        self
+    }
+
+    /// Agrega una media losa en la mitad de abajo (o de arriba, con `top`) del bloque
+    pub fn add_slab(mut self, x: i32, y: i32, z: i32, top: bool, material: &str) -> Self {
+        self.add_partial_block(x, y, z, BlockShape::Slab { top }, material);
+        self
+    }
+
+    /// Agrega un escalón que sube hacia `facing`
+    pub fn add_stairs(mut self, x: i32, y: i32, z: i32, facing: Facing, material: &str) -> Self {
+        self.add_partial_block(x, y, z, BlockShape::Stairs(facing), material);
+        self
+    }
+
+    /// Agrega un poste de cerca; se une con travesaños a las cercas de al lado
+    pub fn add_fence(mut self, x: i32, y: i32, z: i32, material: &str) -> Self {
+        let shape = BlockShape::Fence { connections: [false; 4] };
+        if self.add_partial_block(x, y, z, shape, material) {
+            self.fences.insert((x, y, z));
+            self.connect_fence(x, y, z);
+            self.connect_fence_neighbors(x, y, z);
+        }
+        self
+    }
+
+    /// Agrega una línea de cercas de `length` bloques, como `add_wall`
+    pub fn add_fence_line(mut self, start_x: i32, y: i32, start_z: i32, length: i32, direction: WallDirection, material: &str) -> Self {
+        for i in 0..length {
+            let (x, z) = match direction {
+                WallDirection::North | WallDirection::South => (start_x + i, start_z),
+                WallDirection::East | WallDirection::West => (start_x, start_z + i),
+            };
+            self = self.add_fence(x, y, z, material);
+        }
+        self
+    }
+
+    /// Agrega un panel fino (vidrio, reja) a lo largo del eje X o del Z
+    pub fn add_pane(mut self, x: i32, y: i32, z: i32, along_x: bool, material: &str) -> Self {
+        self.add_partial_block(x, y, z, BlockShape::Pane { along_x }, material);
+        self
+    }
+
+    /// Agrega una caja de lados `size` centrada en `center` y girada `angle`
+    /// radianes alrededor de `axis` (techos inclinados, puertas entreabiertas)
+    pub fn add_oriented_box(mut self, center: Vector3, size: Vector3, axis: Vector3, angle: f32, material: &str) -> Self {
+        let mat = self.materials.get(material);
+        let transform = Transform::new(center).with_rotation(axis, angle).with_scale(size);
+        self.objects.push(Arc::new(Transformed::new(Cube::new(Vector3::zero(), 1.0, mat), transform)));
+        self
+    }
+
+    /// Agrega un cilindro vertical con tapas; (x, y, z) es el centro de su base
+    pub fn add_cylinder(mut self, x: f32, y: f32, z: f32, radius: f32, height: f32, material: &str) -> Self {
         let mat = self.materials.get(material);
+        self.objects.push(Arc::new(Cylinder::new(Vector3::new(x, y, z), radius, height, mat)));
+        self
+    }
+
+    /// Agrega un cono vertical con la punta hacia arriba; (x, y, z) es el centro de su base
+    pub fn add_cone(mut self, x: f32, y: f32, z: f32, radius: f32, height: f32, material: &str) -> Self {
+        let mat = self.materials.get(material);
+        self.objects.push(Arc::new(Cone::new(Vector3::new(x, y, z), radius, height, mat)));
+        self
+    }
+
+    /// Agrega una cascada (o caída de lava) como un solo volumen que va de
+    /// `top_y` a `bottom_y` en la columna (x, z). La lava es más densa y lenta.
+    pub fn add_fall(mut self, x: i32, z: i32, top_y: i32, bottom_y: i32, material: &str) -> Self {
+        let mat = self.materials.get(material);
+        let (density, flow_speed) = if material == "lava" { (3.0, 1.0) } else { (1.2, 4.0) };
+        self.objects.push(Arc::new(FallVolume::new(
+            x as f32,
+            z as f32,
+            bottom_y.min(top_y) as f32,
+            top_y.max(bottom_y) as f32,
+            mat,
+            density,
+            flow_speed,
+        )));
+        self
+    }
+
+    /// Agrega una cascada que nace en la columna (x, z) de `edge`: busca en
+    /// las cuatro direcciones el borde más cercano del suelo ya colocado (la
+    /// primera columna con `WATERFALL_MIN_DROP` bloques de caída), lleva el agua por
+    /// la superficie hasta él y la deja caer hasta el primer bloque que
+    /// encuentre, o `max_depth` bloques si no hay nada debajo. Donde cae sobre
+    /// el suelo se forma una poza.
+    pub fn add_waterfall(mut self, edge: (i32, i32), max_depth: i32) -> Self {
+        let (x, z) = edge;
+        let ground_at = |builder: &Self, x: i32, z: i32| {
+            builder
+                .occupied_positions
+                .iter()
+                .filter(|&(&(cx, _, cz), material)| cx == x && cz == z && CARVABLE_MATERIALS.contains(&material.as_str()))
+                .map(|(&(_, y, _), _)| y)
+                .max()
+        };
+        let Some(top) = ground_at(&self, x, z) else {
+            println!("  Cascada en ({}, {}): no hay suelo donde nacer", x, z);
+            return self;
+        };
+
+        let edge = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .into_iter()
+            .filter_map(|(dx, dz)| {
+                (1..=WATERFALL_EDGE_SEARCH)
+                    .find(|k| (top - WATERFALL_MIN_DROP + 1..=top).all(|y| !self.is_position_occupied(x + dx * k, y, z + dz * k)))
+                    .map(|k| (k, (dx, dz)))
+            })
+            .min();
+        let Some((distance, (dx, dz))) = edge else {
+            println!("  Cascada en ({}, {}): no hay un borde cerca", x, z);
+            return self;
+        };
+
+        // Cauce por la superficie hasta el borde
+        let mut spill = top;
+        for k in 0..distance {
+            let (cx, cz) = (x + dx * k, z + dz * k);
+            if let Some(y) = ground_at(&self, cx, cz) {
+                spill = y;
+                self.remove_block(cx, y, cz);
+                self = self.add_block(cx, y, cz, "water");
+            }
+        }
+
+        let (fx, fz) = (x + dx * distance, z + dz * distance);
+        let floor = (1..=max_depth).map(|depth| spill - depth).find(|&y| self.is_position_occupied(fx, y, fz));
+        let bottom = floor.map_or(spill - max_depth, |y| y + 1);
+        self = self.add_fall(fx, fz, spill, bottom, "water");
+
+        // Poza al pie, en la superficie que rodea donde cae y con fondo de
+        // piedra donde no lo haya; si ya cae sobre agua no hace falta
+        let mut pool = 0;
+        if let Some(floor) = floor.filter(|&y| self.material_at(fx, y, fz) != Some("water")) {
+            for px in fx - WATERFALL_POOL_RADIUS..=fx + WATERFALL_POOL_RADIUS {
+                for pz in fz - WATERFALL_POOL_RADIUS..=fz + WATERFALL_POOL_RADIUS {
+                    let (ox, oz) = (px - fx, pz - fz);
+                    let inside = ox * ox + oz * oz <= WATERFALL_POOL_RADIUS * WATERFALL_POOL_RADIUS;
+                    let surface = self.is_position_occupied(px, floor, pz) && !self.is_position_occupied(px, floor + 1, pz);
+                    if inside && surface {
+                        if !self.is_position_occupied(px, floor - 1, pz) {
+                            self = self.add_block(px, floor - 1, pz, "stone");
+                        }
+                        self.remove_block(px, floor, pz);
+                        self = self.add_block(px, floor, pz, "water");
+                        pool += 1;
+                    }
+                }
+            }
+        }
+        let occupied = &self.occupied_positions;
+        self.grass_positions.retain(|position| occupied.get(position).is_some_and(|material| material != "water"));
+
+        println!("  Cascada de {} bloques de caída y poza de {} bloques", spill - bottom + 1, pool);
+        self
+    }
+
+    pub fn add_floor(mut self, radius: i32, material: &str) -> Self {
         for x in -radius..=radius {
             for z in -radius..=radius {
-                self.objects.push(Arc::new(Cube::new(
-                    Vector3::new(x as f32, 0.0, z as f32), 
-                    1.0, 
-                    mat.clone()
-                )));
+                let cube = self.block_cube(x, 0, z, material);
+                self.objects.push(Arc::new(cube));
             }
         }
         self
     }
     
     pub fn add_checkered_floor(mut self, radius: i32, material1: &str, material2: &str) -> Self {
-        let mat1 = self.materials.get(material1);
-        let mat2 = self.materials.get(material2);
-        
         for x in -radius..=radius {
             for z in -radius..=radius {
-                let mat = if (x + z) % 2 == 0 { mat1.clone() } else { mat2.clone() };
-                self.objects.push(Arc::new(Cube::new(Vector3::new(x as f32, 0.0, z as f32), 1.0, mat)));
+                let material = if (x + z) % 2 == 0 { material1 } else { material2 };
+                let cube = self.block_cube(x, 0, z, material);
+                self.objects.push(Arc::new(cube));
             }
         }
         self
     }
     
     pub fn add_wall(mut self, start_x: i32, start_z: i32, length: i32, height: i32, direction: WallDirection, material: &str) -> Self {
-        let mat = self.materials.get(material);
-        
         for y in 0..height {
             for i in 0..length {
                 let (x, z) = match direction {
@@ -426,39 +912,149 @@ impl SceneBuilder {
                     WallDirection::East | WallDirection::West => (start_x, start_z + i),
                 };
                 
-                self.objects.push(Arc::new(Cube::new(Vector3::new(x as f32, y as f32, z as f32), 1.0, mat.clone())));
+                self.objects.push(Arc::new(self.block_cube(x, y, z, material)));
             }
         }
         self
     }
     
+    /// Llena de bloques macizos la caja entre dos esquinas (ambas incluidas);
+    /// las celdas ya ocupadas se conservan
+    pub fn fill_box(mut self, from: (i32, i32, i32), to: (i32, i32, i32), material: &str) -> Self {
+        for (bx, by, bz) in box_cells(from, to) {
+            self = self.add_block(bx, by, bz, material);
+        }
+        self
+    }
+
+    /// Llena de bloques macizos las celdas cuyo centro cae dentro de la esfera;
+    /// las celdas ya ocupadas se conservan
+    pub fn fill_sphere(mut self, cx: f32, cy: f32, cz: f32, radius: f32, material: &str) -> Self {
+        for (x, y, z) in sphere_cells(cx, cy, cz, radius) {
+            self = self.add_block(x, y, z, material);
+        }
+        self
+    }
+
+    /// Cúpula hueca de un bloque de espesor: la mitad de arriba de la esfera
+    /// de `radius` con centro en `center`, abierta por abajo. Solo se colocan
+    /// las celdas de la esfera con algún vecino afuera, así la cáscara no
+    /// tiene huecos entre bloques. Las celdas ya ocupadas se conservan.
+    pub fn add_dome(mut self, center: (i32, i32, i32), radius: f32, material: &str) -> Self {
+        let (cx, cy, cz) = center;
+        let inside = |x: i32, y: i32, z: i32| {
+            let (dx, dy, dz) = ((x - cx) as f32, (y - cy) as f32, (z - cz) as f32);
+            dx * dx + dy * dy + dz * dz <= radius * radius
+        };
+        let shell: Vec<(i32, i32, i32)> = sphere_cells(cx as f32, cy as f32, cz as f32, radius)
+            .filter(|&(x, y, z)| y >= cy && FACE_NEIGHBORS.iter().any(|(dx, dy, dz)| !inside(x + dx, y + dy, z + dz)))
+            .collect();
+        for (x, y, z) in shell {
+            self = self.add_block(x, y, z, material);
+        }
+        self
+    }
+
+    /// Arco de un bloque de grosor entre dos pies (ambos incluidos) que sube
+    /// `height` bloques en el medio siguiendo medio seno, sin huecos entre
+    /// bloques. Las celdas ya ocupadas se conservan.
+    pub fn add_arch(mut self, start: (i32, i32, i32), end: (i32, i32, i32), height: i32, material: &str) -> Self {
+        let from = Vector3::new(start.0 as f32, start.1 as f32, start.2 as f32);
+        let to = Vector3::new(end.0 as f32, end.1 as f32, end.2 as f32);
+        let length = (to - from).length() + 2.0 * height as f32;
+        let cells = curve_cells(length, |t| {
+            from + (to - from) * t + Vector3::new(0.0, height as f32 * (t * std::f32::consts::PI).sin(), 0.0)
+        });
+
+        for [x, y, z] in cells {
+            self = self.add_block(x, y, z, material);
+        }
+        self
+    }
+
+    /// Puente de bloques entre dos puntos cualesquiera, de arco con pilares
+    /// o colgante según `style`, con barandas de cercas y antorchas. Los
+    /// extremos pueden quedar dentro del suelo: las celdas ya ocupadas se
+    /// conservan y el puente sale de donde termina el terreno. Los pilares
+    /// bajan hasta el primer bloque que encuentran; si no hay nada debajo
+    /// (entre islas flotantes) no se colocan.
+    pub fn add_bridge(mut self, from: Vector3, to: Vector3, style: BridgeStyle) -> Self {
+        let plan = BridgePlan::new(from, to, &style);
+
+        for &(x, y, z) in &plan.deck {
+            self = self.add_block(x, y, z, &style.material);
+        }
+        for &(x, y, z) in &plan.towers {
+            self = self.add_block(x, y, z, &style.material);
+        }
+        for &(x, y, z) in &plan.supports {
+            let Some(ground) = (1..=MAX_PILLAR_DEPTH).find(|depth| self.is_position_occupied(x, y - depth, z)) else {
+                continue;
+            };
+            for depth in 0..ground {
+                self = self.add_block(x, y - depth, z, &style.material);
+            }
+        }
+        if let Some(railing) = &style.railing {
+            for &(x, y, z) in &plan.fences {
+                self = self.add_fence(x, y, z, railing);
+            }
+        }
+        for light in &plan.lights {
+            self = self.add_torch(light.x, light.y, light.z);
+        }
+        println!("  Puente de {} bloques de tablero", plan.deck.len());
+        self
+    }
+
+    /// Quita todos los bloques de la caja entre dos esquinas (ambas
+    /// incluidas), sea cual sea su material
+    pub fn carve_box(mut self, from: (i32, i32, i32), to: (i32, i32, i32)) -> Self {
+        self.carve_cells(box_cells(from, to));
+        self
+    }
+
+    /// Quita todos los bloques cuyo centro cae dentro de la esfera: cráteres,
+    /// el remate redondo de un arco o el interior de una cúpula
+    pub fn carve_sphere(mut self, cx: f32, cy: f32, cz: f32, radius: f32) -> Self {
+        self.carve_cells(sphere_cells(cx, cy, cz, radius));
+        self
+    }
+
+    fn carve_cells(&mut self, cells: impl Iterator<Item = (i32, i32, i32)>) {
+        for (x, y, z) in cells {
+            if self.is_position_occupied(x, y, z) {
+                self.remove_block(x, y, z);
+            }
+        }
+        let occupied = &self.occupied_positions;
+        self.grass_positions.retain(|position| occupied.contains_key(position));
+    }
+
     pub fn add_tower(mut self, x: i32, z: i32, height: i32, material: &str) -> Self {
-        let mat = self.materials.get(material);
         for y in 0..height {
-            self.objects.push(Arc::new(Cube::new(Vector3::new(x as f32, y as f32, z as f32), 1.0, mat.clone())));
+            self.objects.push(Arc::new(self.block_cube(x, y, z, material)));
         }
         self
     }
     
     pub fn add_box(mut self, x: i32, y: i32, z: i32, width: i32, height: i32, depth: i32, material: &str) -> Self {
-        let mat = self.materials.get(material);
-        
         for dy in 0..height {
             for dx in 0..width {
-                self.objects.push(Arc::new(Cube::new(Vector3::new((x + dx) as f32, (y + dy) as f32, z as f32), 1.0, mat.clone())));
-                self.objects.push(Arc::new(Cube::new(Vector3::new((x + dx) as f32, (y + dy) as f32, (z + depth - 1) as f32), 1.0, mat.clone())));
+                self.objects.push(Arc::new(self.block_cube(x + dx, y + dy, z, material)));
+                self.objects.push(Arc::new(self.block_cube(x + dx, y + dy, z + depth - 1, material)));
             }
             
             for dz in 1..depth-1 {
-                self.objects.push(Arc::new(Cube::new(Vector3::new(x as f32, (y + dy) as f32, (z + dz) as f32), 1.0, mat.clone())));
-                self.objects.push(Arc::new(Cube::new(Vector3::new((x + width - 1) as f32, (y + dy) as f32, (z + dz) as f32), 1.0, mat.clone())));
+                self.objects.push(Arc::new(self.block_cube(x, y + dy, z + dz, material)));
+                self.objects.push(Arc::new(self.block_cube(x + width - 1, y + dy, z + dz, material)));
             }
         }
         
         for dx in 0..width {
             for dz in 0..depth {
-                self.objects.push(Arc::new(Cube::new(Vector3::new((x + dx) as f32, y as f32, (z + dz) as f32), 1.0, mat.clone())));
-                self.objects.push(Arc::new(Cube::new(Vector3::new((x + dx) as f32, (y + height - 1) as f32, (z + dz) as f32), 1.0, mat.clone())));
+                self.objects.push(Arc::new(self.block_cube(x + dx, y, z + dz, material)));
+                self.objects.push(Arc::new(self.block_cube(x + dx, y + height - 1, z + dz, material)));
             }
         }
         
@@ -467,32 +1063,156 @@ impl SceneBuilder {
     
     pub fn add_model(mut self, path: &str, x: f32, y: f32, z: f32, scale: f32, material: &str) -> Self {
         let mat = self.materials.get(material);
-        
-        match Mesh::from_obj(path, &mat, Vector3::new(x, y, z), scale) {
-            Ok(mesh) => {
-                self.objects.extend(mesh.to_objects());
+        self.add_instance(path, Vector3::new(x, y, z), scale, mat);
+        self
+    }
+    
+    /// Agrega un modelo glTF 2.0 con sus propios materiales y luces, con su
+    /// origen en (x, y, z). Las partes sin material usan piedra.
+    pub fn add_gltf(mut self, path: &str, x: f32, y: f32, z: f32, scale: f32) -> Self {
+        let fallback = self.materials.get("stone");
+        match Mesh::from_gltf(path, &fallback, Vector3::new(x, y, z), scale) {
+            Ok((mesh, lights)) => {
+                self.objects.push(Arc::new(mesh));
+                self.lights.extend(lights);
             }
-            Err(_) => {
-                self.objects.push(Arc::new(Cube::new(Vector3::new(x, y, z), scale, mat)));
+            Err(e) => println!("  Error cargando {}: {}", path, e),
+        }
+        self
+    }
+
+    /// Importa una construcción de Minecraft (`.schem` de Sponge o `.nbt` de
+    /// estructura) con su esquina mínima en (x, y, z). Cada bloque se traduce
+    /// al material y la pieza más parecidos; si el archivo no se puede leer
+    /// se informa y la escena sigue sin él.
+    pub fn add_schematic(mut self, path: &str, x: i32, y: i32, z: i32) -> Self {
+        let schematic = match Schematic::load(path) {
+            Ok(schematic) => schematic,
+            Err(e) => {
+                println!("  Error cargando {}: {}", path, e);
+                return self;
             }
+        };
+
+        let placements: Vec<BlockPlacement> = schematic.palette.iter().map(|state| state.placement()).collect();
+        let mut unknown: Vec<&str> = Vec::new();
+        let mut placed = 0;
+        for &((bx, by, bz), slot) in &schematic.blocks {
+            let (bx, by, bz) = (x + bx, y + by, z + bz);
+            self = match placements[slot] {
+                BlockPlacement::Skip => continue,
+                BlockPlacement::Cube(material) => self.add_cube(bx as f32, by as f32, bz as f32, 1.0, material),
+                BlockPlacement::Unknown => {
+                    let name = schematic.palette[slot].name.as_str();
+                    if !unknown.contains(&name) {
+                        unknown.push(name);
+                    }
+                    self.add_cube(bx as f32, by as f32, bz as f32, 1.0, "stone")
+                }
+                BlockPlacement::Slab { material, top } => self.add_slab(bx, by, bz, top, material),
+                BlockPlacement::Stairs(material, facing) => self.add_stairs(bx, by, bz, facing, material),
+                BlockPlacement::Fence(material) => self.add_fence(bx, by, bz, material),
+                BlockPlacement::Pane { material, along_x } => self.add_pane(bx, by, bz, along_x, material),
+                BlockPlacement::Torch => self.add_torch(bx as f32, by as f32 - 0.2, bz as f32),
+            };
+            placed += 1;
+        }
+
+        let (width, height, length) = schematic.size;
+        println!("  Esquema {} ({}x{}x{}): {} bloques", path, width, height, length, placed);
+        if !unknown.is_empty() {
+            unknown.sort();
+            println!("  Bloques sin material equivalente (colocados como piedra): {}", unknown.join(", "));
         }
         self
     }
-    
+
     pub fn add_light(mut self, x: f32, y: f32, z: f32, color: Color, intensity: f32) -> Self {
         self.lights.push(Light::new(Vector3::new(x, y, z), color, intensity));
         self
     }
     
-    pub fn add_sun(self, x: f32, y: f32, z: f32, intensity: f32) -> Self {
-        self.add_light(x, y, z, Color::new(255, 250, 240, 255), intensity)
+    /// Agrega un foco en `position` que apunta hacia `target`.
+    /// Los ángulos son semiángulos del cono en grados.
+    pub fn add_spotlight(
+        mut self,
+        position: Vector3,
+        target: Vector3,
+        inner_degrees: f32,
+        outer_degrees: f32,
+        color: Color,
+        intensity: f32,
+    ) -> Self {
+        self.lights.push(Light::spot(
+            position,
+            target - position,
+            inner_degrees.to_radians(),
+            outer_degrees.to_radians(),
+            color,
+            intensity,
+        ));
+        self
+    }
+    
+    /// Agrega una luz de área rectangular centrada en `center`, con lados
+    /// `edge_u` y `edge_v` (en bloques): una ventana o la superficie de un portal
+    pub fn add_area_light(mut self, center: Vector3, edge_u: Vector3, edge_v: Vector3, color: Color, intensity: f32) -> Self {
+        self.lights.push(Light::area(center, edge_u, edge_v, color, intensity));
+        self
+    }
+    
+    /// Cambia la atenuación de la última luz agregada
+    pub fn with_light_falloff(mut self, falloff: Falloff) -> Self {
+        if let Some(light) = self.lights.last_mut() {
+            light.falloff = falloff;
+        }
+        self
+    }
+    
+    /// Hace parpadear la última luz agregada como fuego
+    pub fn with_light_flame(mut self, flame: Flame) -> Self {
+        if let Some(light) = self.lights.last_mut() {
+            light.flame = Some(flame);
+        }
+        self
+    }
+    
+    /// Cambia el lado de la grilla de rayos de sombra de la última luz de área
+    pub fn with_light_area_samples(mut self, samples: u32) -> Self {
+        if let Some(area) = self.lights.last_mut().and_then(|light| light.area.as_mut()) {
+            area.samples = samples.clamp(1, MAX_AREA_LIGHT_SAMPLES);
+        }
+        self
+    }
+    
+    /// Hace que la intensidad de la última luz agregada siga el pulso del portal
+    pub fn with_light_portal_pulse(mut self) -> Self {
+        if let Some(light) = self.lights.last_mut() {
+            light.portal_pulse = true;
+        }
+        self
+    }
+    
+    /// El sol no se atenúa con la distancia, para iluminar escenas grandes por completo.
+    /// `intensity` es la del sol a pleno; 1.0 es el del ciclo de día y noche.
+    pub fn add_sun(mut self, x: f32, y: f32, z: f32, intensity: f32) -> Self {
+        self = self.add_light(x, y, z, Color::new(255, 250, 240, 255), intensity)
+            .with_light_falloff(Falloff::None);
+        if let Some(light) = self.lights.last_mut() {
+            light.cloud_shadows = true;
+        }
+        self
     }
     
     pub fn add_torch(mut self, x: f32, y: f32, z: f32) -> Self {
-        let torch_mat = Material::new(Vector3::new(1.0, 0.6, 0.0), 40.0, [0.3, 0.2], 0.0, 0.0, 0.1, Vector3::new(1.2, 0.6, 0.1), Some("glowstone".to_string()));
+        let torch_mat = Arc::new(Material::new(Vector3::new(1.0, 0.6, 0.0), 40.0, [0.3, 0.2], 0.0, 0.0, 0.1, Vector3::new(1.2, 0.6, 0.1), Some("glowstone".to_string())));
         
         self.objects.push(Arc::new(Cube::new(Vector3::new(x, y, z), 0.3, torch_mat)));
-        self.lights.push(Light::new(Vector3::new(x, y + 0.5, z), Color::new(255, 180, 80, 255), 2.5));
+        self.lights.push(
+            Light::new(Vector3::new(x, y + 0.5, z), Color::new(255, 180, 80, 255), 2.5)
+                .with_falloff(Falloff::InverseSquare { radius: TORCH_LIGHT_RADIUS })
+                .with_flame(Flame::torch(x * 12.9898 + z * 78.233)),
+        );
         
         self
     }
@@ -504,34 +1224,121 @@ impl SceneBuilder {
         self
     }
     
-    pub fn add_tree(mut self, x: i32, y: i32, z: i32) -> Self {
-        // Tronco del árbol (5 bloques de altura desde Y)
-        for dy in 0..5 {
-            self = self.add_cube(x as f32, (y + dy) as f32, z as f32, 1.0, "wood");
+    /// Roble con el tronco empezando en (x, y, z)
+    pub fn add_tree(self, x: i32, y: i32, z: i32) -> Self {
+        self.add_tree_species(TreeSpecies::Oak, x, y, z)
+    }
+
+    /// Árbol de la especie con el tronco empezando en (x, y, z); su altura, su
+    /// copa y sus ramas dependen de la posición y de la semilla de la escena
+    pub fn add_tree_species(mut self, species: TreeSpecies, x: i32, y: i32, z: i32) -> Self {
+        let plan = TreePlan::new(species, (x, y, z), self.seed);
+        let (trunk, leaves) = species.materials();
+        for (tx, ty, tz) in plan.trunk {
+            self = self.add_block(tx, ty, tz, trunk);
         }
-        
-        // Hojas (relativas a Y)
-        let leaf_positions = [
-            (x, y + 4, z-1), (x, y + 4, z+1), (x-1, y + 4, z), (x+1, y + 4, z),
-            (x, y + 5, z-1), (x, y + 5, z+1), (x-1, y + 5, z), (x+1, y + 5, z),
-            (x, y + 5, z), (x, y + 6, z),
-        ];
-        
-        for (lx, ly, lz) in leaf_positions {
-            self = self.add_cube(lx as f32, ly as f32, lz as f32, 1.0, "leaves");
+        for (lx, ly, lz) in plan.leaves {
+            self = self.add_block(lx, ly, lz, leaves);
         }
-        
         self
     }
     
+    /// Hace crecer desde (x, y, z) el sistema L del archivo `path` (árboles
+    /// ramificados, enredaderas, hongos gigantes), con la semilla de la
+    /// escena. Cada archivo se lee una sola vez; si tiene errores se informa y
+    /// no se coloca nada.
+    pub fn add_lsystem(mut self, path: &str, x: i32, y: i32, z: i32) -> Self {
+        let system = self
+            .lsystems
+            .entry(path.to_string())
+            .or_insert_with(|| match LSystem::load(path) {
+                Ok(system) => Some(Arc::new(system)),
+                Err(e) => {
+                    println!("  {}", e);
+                    None
+                }
+            })
+            .clone();
+        let Some(system) = system else {
+            return self;
+        };
+
+        let plan = system.grow((x, y, z), self.seed);
+        for (wx, wy, wz) in plan.wood {
+            self = self.add_block(wx, wy, wz, system.wood_material());
+        }
+        for (lx, ly, lz) in plan.leaves {
+            self = self.add_block(lx, ly, lz, system.leaf_material());
+        }
+        self
+    }
+
+    /// Cactus de dos a cuatro bloques de alto
+    pub fn add_cactus(mut self, x: i32, y: i32, z: i32) -> Self {
+        let height = 2 + (hash01(x, y, z, 0) * 3.0) as i32;
+        for dy in 0..height {
+            self = self.add_cube(x as f32, (y + dy) as f32, z as f32, 0.8, "cactus");
+        }
+        self
+    }
+
+    /// Hongo gigante: tallo de cuatro bloques y sombrero ancho encima
+    pub fn add_giant_mushroom(mut self, x: i32, y: i32, z: i32) -> Self {
+        for dy in 0..4 {
+            self = self.add_cube(x as f32, (y + dy) as f32, z as f32, 1.0, "mushroom_stem");
+        }
+        for dx in -2..=2 {
+            for dz in -2..=2 {
+                // Sin las esquinas, para que el sombrero se vea redondeado
+                if dx * dx + dz * dz <= 5 {
+                    self = self.add_cube((x + dx) as f32, (y + 4) as f32, (z + dz) as f32, 1.0, "mushroom_cap");
+                }
+            }
+        }
+        self.add_cube(x as f32, (y + 5) as f32, z as f32, 1.0, "mushroom_cap")
+    }
+
+    /// Planta lo que crece en un bioma con su base en (x, y, z)
+    fn add_vegetation(self, vegetation: Vegetation, x: i32, y: i32, z: i32) -> Self {
+        match vegetation {
+            Vegetation::Tree(species) => self.add_tree_species(species, x, y, z),
+            Vegetation::Pine => self.add_pine(x, y, z),
+            Vegetation::Cactus => self.add_cactus(x, y, z),
+            Vegetation::GiantMushroom => self.add_giant_mushroom(x, y, z),
+            Vegetation::Glowstone => self
+                .add_cube(x as f32, y as f32, z as f32, 1.0, "glowstone")
+                .add_cube(x as f32, (y + 1) as f32, z as f32, 0.6, "glowstone"),
+        }
+    }
+
+    /// Pino con tronco cilíndrico y tres conos de hojas; (x, y, z) es el
+    /// bloque donde empieza el tronco, como en `add_tree`
+    pub fn add_pine(self, x: i32, y: i32, z: i32) -> Self {
+        let (x, base, z) = (x as f32, y as f32 - 0.5, z as f32);
+        self.add_cylinder(x, base, z, 0.25, 2.5, "wood")
+            .add_cone(x, base + 1.5, z, 1.6, 2.5, "leaves")
+            .add_cone(x, base + 2.7, z, 1.2, 2.2, "leaves")
+            .add_cone(x, base + 3.9, z, 0.8, 1.8, "leaves")
+    }
+    
     pub fn add_house(mut self, x: i32, z: i32) -> Self {
         self = self.add_box(x, 0, z, 5, 4, 5, "wood");
         
+        // Techo escalonado: el borde de cada nivel son escalones que suben hacia el centro
         for level in 0..3 {
             let offset = level;
             for dx in offset..(5-offset) {
                 for dz in offset..(5-offset) {
-                    self = self.add_cube((x + dx) as f32, (4 + level) as f32, (z + dz) as f32, 1.0, "stone");
+                    let facing = if level == 2 { None }
+                        else if dz == offset { Some(Facing::South) }
+                        else if dz == 4 - offset { Some(Facing::North) }
+                        else if dx == offset { Some(Facing::East) }
+                        else if dx == 4 - offset { Some(Facing::West) }
+                        else { None };
+                    self = match facing {
+                        Some(facing) => self.add_stairs(x + dx, 4 + level, z + dz, facing, "stone"),
+                        None => self.add_cube((x + dx) as f32, (4 + level) as f32, (z + dz) as f32, 1.0, "stone"),
+                    };
                 }
             }
         }
@@ -539,6 +1346,186 @@ impl SceneBuilder {
         self
     }
     
+    /// Camino de `width` bloques de ancho que sigue una curva suave
+    /// (Catmull-Rom) por las columnas `control_points` (x, z), sobre el suelo
+    /// natural ya colocado. Su altura sigue la del suelo suavizada: se corta
+    /// lo que sobresale y se rellena con tierra lo que queda por debajo. Las
+    /// columnas sin suelo o bajo el agua se saltean.
+    pub fn add_path(mut self, control_points: &[(i32, i32)], width: i32, material: &str) -> Self {
+        let columns;
+        (self, columns) = self.lay_path(control_points, width, material);
+        println!("  Camino de {} columnas por {} puntos", columns, control_points.len());
+        self
+    }
+
+    /// Coloca el camino de `add_path` y devuelve cuántas columnas cubrió
+    fn lay_path(mut self, control_points: &[(i32, i32)], width: i32, material: &str) -> (Self, usize) {
+        let radius = width.max(1) as f32 / 2.0;
+        let points: Vec<Vector2> = control_points.iter().map(|&(x, z)| Vector2::new(x as f32, z as f32)).collect();
+
+        let mut columns: HashSet<(i32, i32)> = HashSet::new();
+        for center in catmull_rom(&points) {
+            let reach = radius.ceil() as i32;
+            for dx in -reach..=reach {
+                for dz in -reach..=reach {
+                    let column = ((center.x + dx as f32).round() as i32, (center.y + dz as f32).round() as i32);
+                    let (ox, oz) = (column.0 as f32 - center.x, column.1 as f32 - center.y);
+                    if ox * ox + oz * oz <= radius * radius {
+                        columns.insert(column);
+                    }
+                }
+            }
+        }
+
+        // Suelo natural de cada columna, sin árboles encima ni agua
+        let mut ground: HashMap<(i32, i32), i32> = HashMap::new();
+        for (&(x, y, z), material) in &self.occupied_positions {
+            if columns.contains(&(x, z)) && CARVABLE_MATERIALS.contains(&material.as_str()) {
+                ground.entry((x, z)).and_modify(|top| *top = (*top).max(y)).or_insert(y);
+            }
+        }
+        ground.retain(|&(x, z), top| self.material_at(x, *top + 1, z) != Some("water"));
+
+        let mut path: Vec<((i32, i32), i32)> = ground.iter().map(|(&column, &top)| (column, top)).collect();
+        path.sort();
+        for &((x, z), top) in &path {
+            let nearby: Vec<i32> = (-2..=2)
+                .flat_map(|dx| (-2..=2).map(move |dz| (x + dx, z + dz)))
+                .filter_map(|column| ground.get(&column).copied())
+                .collect();
+            let level = (nearby.iter().sum::<i32>() as f32 / nearby.len() as f32).round() as i32;
+
+            for y in level + 1..=top {
+                self.remove_block(x, y, z);
+            }
+            for y in top + 1..level {
+                self = self.add_block(x, y, z, "dirt");
+            }
+            self.remove_block(x, level, z);
+            self = self.add_block(x, level, z, material);
+        }
+        self.grass_positions.retain(|(x, _, z)| !ground.contains_key(&(*x, *z)));
+        let columns = path.len();
+        (self, columns)
+    }
+
+    /// Genera una aldea de `size` bloques desde `center` hasta cada borde con
+    /// el estilo dado: calles de piedra, casas de madera con techo de piedra a
+    /// lo largo de ellas, una fuente en la plaza, árboles, faroles y atalayas.
+    /// Se asienta sobre lo ya colocado: las calles reemplazan la superficie de
+    /// cada columna y las casas van sobre cimientos a la altura de su lote;
+    /// donde no hay suelo se toma `center.1` como su altura. La misma semilla
+    /// da siempre la misma aldea.
+    pub fn add_village(mut self, center: (i32, i32, i32), size: i32, seed: u32, style: VillageStyle) -> Self {
+        let plan = VillagePlan::new(size, seed, &style);
+        let (cx, cy, cz) = center;
+
+        let mut surface: HashMap<(i32, i32), i32> = HashMap::new();
+        for &(x, y, z) in self.occupied_positions.keys() {
+            surface.entry((x, z)).and_modify(|top| *top = (*top).max(y)).or_insert(y);
+        }
+        let ground = |x: i32, z: i32| surface.get(&(cx + x, cz + z)).copied().unwrap_or(cy);
+
+        // Senderos de arena desde el lado de cada casa que da a la calle más
+        // cercana hasta ella, con una curva que cambia con la semilla
+        for &(hx, hz) in &plan.houses {
+            let middle_of_lot = (hx + HOUSE_SIZE / 2, hz + HOUSE_SIZE / 2);
+            let distance = |&&(x, z): &&(i32, i32)| ((x - middle_of_lot.0).pow(2) + (z - middle_of_lot.1).pow(2), x, z);
+            let Some(&road) = plan.roads.iter().min_by_key(distance) else {
+                continue;
+            };
+            let (dx, dz) = (road.0 - middle_of_lot.0, road.1 - middle_of_lot.1);
+            let door = if dx.abs() > dz.abs() {
+                (if dx > 0 { hx + HOUSE_SIZE } else { hx - 1 }, middle_of_lot.1)
+            } else {
+                (middle_of_lot.0, if dz > 0 { hz + HOUSE_SIZE } else { hz - 1 })
+            };
+            let bend = ((hash01(hx, seed as i32, hz, 15) - 0.5) * 4.0).round() as i32;
+            let middle = ((door.0 + road.0) / 2 + bend, (door.1 + road.1) / 2 - bend);
+            let points = [door, middle, road].map(|(x, z)| (cx + x, cz + z));
+            self = self.lay_path(&points, 1, "sand").0;
+        }
+
+        for &(x, z) in &plan.roads {
+            let y = ground(x, z);
+            self.remove_block(cx + x, y, cz + z);
+            self = self.add_block(cx + x, y, cz + z, "stone");
+        }
+
+        // Fuente en la plaza
+        for (x, z) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let y = ground(x, z) + 1;
+            self = self
+                .add_block(cx + x, y, cz + z, "stone")
+                .add_cube((cx + x) as f32, (y + 1) as f32, (cz + z) as f32, 0.8, "water");
+        }
+
+        for &((sx, sz), (ex, ez)) in &plan.arches {
+            let (start, end) = ((cx + sx, ground(sx, sz) + 1, cz + sz), (cx + ex, ground(ex, ez) + 1, cz + ez));
+            self = self.add_arch(start, end, 5, "stone");
+        }
+
+        for &(hx, hz) in &plan.houses {
+            let footprint: Vec<(i32, i32)> = (hx..hx + HOUSE_SIZE).flat_map(|x| (hz..hz + HOUSE_SIZE).map(move |z| (x, z))).collect();
+            let base = footprint.iter().map(|&(x, z)| ground(x, z)).max().unwrap_or(cy);
+            for &(x, z) in &footprint {
+                for y in ground(x, z) + 1..=base {
+                    self = self.add_block(cx + x, y, cz + z, "stone");
+                }
+            }
+            self = self.add_village_house(cx + hx, base + 1, cz + hz);
+        }
+
+        for &(x, z) in &plan.lamps {
+            let y = ground(x, z) + 1;
+            self = self
+                .add_fence(cx + x, y, cz + z, "wood")
+                .add_torch((cx + x) as f32, (y + 1) as f32 - 0.3, (cz + z) as f32);
+        }
+
+        for &(x, z) in &plan.trees {
+            // Solo sobre suelo, no sobre el agua ni en el aire
+            let y = ground(x, z);
+            if surface.contains_key(&(cx + x, cz + z)) && self.material_at(cx + x, y, cz + z) != Some("water") {
+                self = self.add_tree(cx + x, y + 1, cz + z);
+            }
+        }
+
+        // Una atalaya armada en el origen, con su balcón hacia +X, y girada en
+        // cada esquina para que el balcón mire a la aldea
+        for (turns, &(tx, tz)) in plan.towers.iter().enumerate() {
+            self = self.begin_group();
+            for y in 1..10 {
+                self = self.add_block(0, y, 0, "stone");
+            }
+            self = self
+                .add_slab(1, 7, 0, true, "wood")
+                .add_fence(1, 8, 0, "wood")
+                .add_torch(0.0, 10.0, 0.0)
+                .end_group(GroupTransform::translation((cx + tx) as f32, ground(tx, tz) as f32, (cz + tz) as f32).with_quarter_turns(turns as i32));
+        }
+
+        println!(
+            "  Aldea en ({}, {}, {}): {} casas, {} árboles, {} faroles",
+            cx, cy, cz, plan.houses.len(), plan.trees.len(), plan.lamps.len()
+        );
+        self
+    }
+
+    /// Casa de la aldea: caja de madera con techo escalonado de piedra y una
+    /// antorcha junto a la pared norte
+    fn add_village_house(mut self, x: i32, y: i32, z: i32) -> Self {
+        self = self.add_box(x, y, z, HOUSE_SIZE, 3, HOUSE_SIZE, "wood");
+        for level in 0..2 {
+            for dx in level..HOUSE_SIZE - level {
+                for dz in level..HOUSE_SIZE - level {
+                    self = self.add_block(x + dx, y + 3 + level, z + dz, "stone");
+                }
+            }
+        }
+        self.add_torch((x + 2) as f32, (y + 1) as f32, z as f32 - 0.5)
+    }
+
     pub fn add_floating_island(mut self, center_x: i32, center_y: i32, center_z: i32, radius: i32) -> Self {
         let cx = center_x as f32;
         let cy = center_y as f32;
@@ -563,20 +1550,21 @@ impl SceneBuilder {
                         let wy = cy + fy;
                         let wz = cz + fz;
                         
+                        let (surface, subsurface) = self.ground_materials(wx as i32, wz as i32);
                         let material = if fy > radius as f32 * 0.5 {
                             self.grass_positions.push((
                                 wx as i32,
                                 wy as i32,
                                 wz as i32
                             ));
-                            "grass_top"
+                            surface
                         } else if fy > radius as f32 * 0.0 {
-                            "dirt"
+                            subsurface
                         } else {
                             "stone"
                         };
                         
-                        self = self.add_cube(wx, wy, wz, 1.0, material);
+                        self = self.add_block(wx as i32, wy as i32, wz as i32, material);
                     }
                 }
             }
@@ -585,6 +1573,296 @@ impl SceneBuilder {
         self
     }
 
+    /// Genera un terreno de `width` x `depth` columnas centrado en el origen,
+    /// con la forma de `params`: pasto arriba (piedra en las pendientes
+    /// fuertes), tierra debajo y piedra más abajo, o los materiales del bioma
+    /// con `with_biomes`. Cada columna baja hasta la
+    /// más baja de sus vecinas para que los acantilados queden macizos.
+    pub fn add_terrain(mut self, width: i32, depth: i32, params: TerrainParams) -> Self {
+        let terrain = Terrain::new(params);
+        let (min_x, min_z) = (-width / 2, -depth / 2);
+        let heights: Vec<i32> = (0..width * depth)
+            .map(|i| terrain.height_at(min_x + i % width, min_z + i / width))
+            .collect();
+        let height = |x: i32, z: i32| {
+            let (col, row) = ((x - min_x).clamp(0, width - 1), (z - min_z).clamp(0, depth - 1));
+            heights[(row * width + col) as usize]
+        };
+
+        for z in min_z..min_z + depth {
+            for x in min_x..min_x + width {
+                let surface = height(x, z);
+                let lowest_neighbor = FACE_NEIGHBORS
+                    .iter()
+                    .filter(|(_, dy, _)| *dy == 0)
+                    .map(|(dx, _, dz)| height(x + dx, z + dz))
+                    .min()
+                    .unwrap_or(surface);
+                let steep = surface - lowest_neighbor >= 2;
+                let underwater = params.water_level.is_some_and(|level| surface < level);
+                let (top_material, under_material) = self.ground_materials(x, z);
+
+                for y in (lowest_neighbor.min(surface) - 1)..=surface {
+                    let material = if y == surface && !steep && !underwater {
+                        self.grass_positions.push((x, y, z));
+                        top_material
+                    } else if surface - y < 3 && !steep {
+                        under_material
+                    } else {
+                        "stone"
+                    };
+                    self = self.add_block(x, y, z, material);
+                }
+
+                if let Some(level) = params.water_level {
+                    for y in surface + 1..=level {
+                        self = self.add_cube(x as f32, y as f32, z as f32, 1.0, "water");
+                    }
+                }
+            }
+        }
+
+        self
+    }
+
+    /// Genera un terreno a partir de un mapa de alturas en escala de grises
+    /// (un píxel por columna, centrado en el origen): el blanco sube `scale`
+    /// bloques y el negro queda en 0. `materials` son franjas `(hasta, material)`
+    /// ordenadas por altura relativa (0-1) que eligen el bloque de la superficie;
+    /// debajo van tres capas del mismo material (tierra bajo el pasto) y piedra.
+    /// Si la imagen no se puede leer se informa y la escena sigue sin ella.
+    pub fn add_heightmap_terrain(mut self, path: &str, scale: f32, materials: &[(f32, &str)]) -> Self {
+        // 16 bits por canal, para no perder precisión con datos de elevación reales
+        let image = match image::open(path) {
+            Ok(image) => image.to_luma16(),
+            Err(e) => {
+                println!("  Error cargando {}: {}", path, e);
+                return self;
+            }
+        };
+
+        let (width, depth) = (image.width() as i32, image.height() as i32);
+        let (min_x, min_z) = (-width / 2, -depth / 2);
+        let level = |x: i32, z: i32| {
+            let (col, row) = ((x - min_x).clamp(0, width - 1), (z - min_z).clamp(0, depth - 1));
+            image.get_pixel(col as u32, row as u32)[0] as f32 / u16::MAX as f32
+        };
+        let height = |x: i32, z: i32| (level(x, z) * scale).round() as i32;
+
+        for z in min_z..min_z + depth {
+            for x in min_x..min_x + width {
+                let surface = height(x, z);
+                let lowest_neighbor = FACE_NEIGHBORS
+                    .iter()
+                    .filter(|(_, dy, _)| *dy == 0)
+                    .map(|(dx, _, dz)| height(x + dx, z + dz))
+                    .min()
+                    .unwrap_or(surface);
+                let top_material = materials
+                    .iter()
+                    .find(|(up_to, _)| level(x, z) <= *up_to)
+                    .or(materials.last())
+                    .map_or("grass_top", |(_, material)| *material);
+                let under_material = if top_material == "grass_top" { "dirt" } else { top_material };
+
+                for y in (lowest_neighbor.min(surface) - 1)..=surface {
+                    let material = if y == surface {
+                        if top_material == "grass_top" {
+                            self.grass_positions.push((x, y, z));
+                        }
+                        top_material
+                    } else if surface - y <= 3 {
+                        under_material
+                    } else {
+                        "stone"
+                    };
+                    self = self.add_block(x, y, z, material);
+                }
+            }
+        }
+
+        println!("  Mapa de alturas {} ({}x{} columnas, hasta {} bloques)", path, width, depth, scale);
+        self
+    }
+
+    /// Excava cavernas de ruido 3D y túneles serpenteantes en el suelo natural
+    /// ya colocado (islas y terreno), sin tocar la superficie de cada columna
+    /// ni las construcciones. Las caras que quedan al aire se vuelven a
+    /// dibujar solas, y con `glowstone` algunas paredes se vuelven vetas que
+    /// brillan.
+    pub fn add_caves(mut self, params: CaveParams) -> Self {
+        let caves = Caves::new(params);
+
+        let mut column_tops: HashMap<(i32, i32), i32> = HashMap::new();
+        for &(x, y, z) in self.occupied_positions.keys() {
+            column_tops.entry((x, z)).and_modify(|top| *top = (*top).max(y)).or_insert(y);
+        }
+        // Ordenado, para que la misma semilla excave siempre lo mismo
+        let mut ground: Vec<(i32, i32, i32)> = self
+            .occupied_positions
+            .iter()
+            .filter(|&(&(x, y, z), material)| {
+                CARVABLE_MATERIALS.contains(&material.as_str()) && column_tops[&(x, z)] - y > params.surface_margin
+            })
+            .map(|(&position, _)| position)
+            .collect();
+        ground.sort();
+        let diggable: HashSet<(i32, i32, i32)> = ground.iter().copied().collect();
+
+        let mut carved: HashSet<(i32, i32, i32)> =
+            ground.iter().copied().filter(|&(x, y, z)| caves.is_cavern(x, y, z)).collect();
+
+        if !ground.is_empty() {
+            let reach = params.tunnel_radius.ceil() as i32;
+            for tunnel in 0..params.tunnels {
+                let pick = hash01(tunnel as i32, params.seed as i32, 0, 7) * ground.len() as f32;
+                let start = ground[(pick as usize).min(ground.len() - 1)];
+                for (cx, cy, cz) in caves.tunnel_path(tunnel, start) {
+                    let (bx, by, bz) = (cx.round() as i32, cy.round() as i32, cz.round() as i32);
+                    for dx in -reach..=reach {
+                        for dy in -reach..=reach {
+                            for dz in -reach..=reach {
+                                let cell = (bx + dx, by + dy, bz + dz);
+                                let (fx, fy, fz) = (cell.0 as f32 - cx, cell.1 as f32 - cy, cell.2 as f32 - cz);
+                                if fx * fx + fy * fy + fz * fz <= params.tunnel_radius * params.tunnel_radius
+                                    && diggable.contains(&cell)
+                                {
+                                    carved.insert(cell);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for &(x, y, z) in &carved {
+            self.remove_block(x, y, z);
+        }
+        self.grass_positions.retain(|position| !carved.contains(position));
+
+        let mut veins = 0;
+        if let Some(chance) = params.glowstone {
+            for &(x, y, z) in &ground {
+                let exposed = !carved.contains(&(x, y, z))
+                    && FACE_NEIGHBORS.iter().any(|(dx, dy, dz)| carved.contains(&(x + dx, y + dy, z + dz)));
+                if exposed && hash01(x, y, z, params.seed) < chance {
+                    self.remove_block(x, y, z);
+                    self = self.add_cube(x as f32, y as f32, z as f32, 1.0, "glowstone");
+                    veins += 1;
+                }
+            }
+        }
+
+        println!("  Cuevas: {} bloques excavados, {} de glowstone", carved.len(), veins);
+        self
+    }
+
+    /// Traza un río de hasta `length` bloques que nace en la columna
+    /// (`start_x`, `start_z`) y baja por la superficie ya generada hacia la
+    /// columna vecina más baja. Excava un cauce de tres bloques de ancho con
+    /// el agua un bloque por debajo de las orillas, y termina al llegar a un
+    /// lago u otro río, al salir del suelo o en un pozo demasiado hondo.
+    pub fn add_river(mut self, start_x: i32, start_z: i32, length: i32) -> Self {
+        let mut surface: HashMap<(i32, i32), i32> = HashMap::new();
+        for &(x, y, z) in self.occupied_positions.keys() {
+            surface.entry((x, z)).and_modify(|top| *top = (*top).max(y)).or_insert(y);
+        }
+
+        // El relieve original guía el cauce; `surface` sigue lo ya excavado
+        let relief = surface.clone();
+        let slope_height = |x: i32, z: i32| {
+            let heights: Vec<i32> = (-RIVER_SLOPE_RADIUS..=RIVER_SLOPE_RADIUS)
+                .flat_map(|dx| (-RIVER_SLOPE_RADIUS..=RIVER_SLOPE_RADIUS).map(move |dz| (x + dx, z + dz)))
+                .filter_map(|column| relief.get(&column).copied())
+                .collect();
+            heights.iter().sum::<i32>() as f32 / heights.len().max(1) as f32
+        };
+
+        let Some(&start_y) = surface.get(&(start_x, start_z)) else {
+            println!("  El río en ({}, {}) no tiene suelo donde nacer", start_x, start_z);
+            return self;
+        };
+
+        let (mut x, mut z) = (start_x, start_z);
+        let mut level = start_y - 1;
+        let mut direction = (0, 0);
+        let mut visited: HashSet<(i32, i32)> = HashSet::new();
+        // Columnas del cauce, para no confundir el agua del propio río con un lago
+        let mut channel: HashSet<(i32, i32)> = HashSet::new();
+
+        for _ in 0..length {
+            visited.insert((x, z));
+            for dx in -1..=1 {
+                for dz in -1..=1 {
+                    let (cx, cz) = (x + dx, z + dz);
+                    let Some(&top) = surface.get(&(cx, cz)) else { continue };
+                    // Las orillas más bajas que el agua y los lagos quedan como están
+                    if top < level || channel.contains(&(cx, cz)) || self.material_at(cx, top, cz) == Some("water") {
+                        continue;
+                    }
+                    for y in level + 1..=top {
+                        self.remove_block(cx, y, cz);
+                    }
+                    self.remove_block(cx, level, cz);
+                    self = self.add_cube(cx as f32, level as f32, cz as f32, 1.0, "water");
+                    surface.insert((cx, cz), level);
+                    channel.insert((cx, cz));
+                }
+            }
+
+            // Siguiente paso: la vecina más baja, primero por la altura del
+            // bloque y luego por la pendiente general; ante un empate, derecho
+            let next = FACE_NEIGHBORS
+                .iter()
+                .filter(|(_, dy, _)| *dy == 0)
+                .map(|&(dx, _, dz)| (dx, dz))
+                .filter(|&(dx, dz)| !visited.contains(&(x + dx, z + dz)))
+                .filter_map(|(dx, dz)| Some(((dx, dz), *relief.get(&(x + dx, z + dz))?, slope_height(x + dx, z + dz))))
+                .min_by(|a, b| {
+                    (a.1.max(level + 1), a.2, a.0 != direction)
+                        .partial_cmp(&(b.1.max(level + 1), b.2, b.0 != direction))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+            let Some(((dx, dz), top, slope)) = next else { break };
+            // Un pozo: para seguir habría que cortar una loma que no baja a ningún lado
+            if top > level + 1 + RIVER_MAX_CUT || (top > level + 1 && slope >= slope_height(x, z)) {
+                break;
+            }
+
+            (x, z) = (x + dx, z + dz);
+            direction = (dx, dz);
+            if !channel.contains(&(x, z)) && self.material_at(x, surface[&(x, z)], z) == Some("water") {
+                // Desemboca en un lago u otro río
+                break;
+            }
+            level = level.min(top - 1);
+        }
+
+        // El terreno solo es macizo cerca de la superficie: se rellenan el
+        // fondo del cauce y las orillas para que no quede un hueco al vacío
+        for &(cx, cz) in &channel {
+            let water = surface[&(cx, cz)];
+            let banks = FACE_NEIGHBORS
+                .iter()
+                .filter(|(_, dy, _)| *dy == 0)
+                .map(|(dx, _, dz)| (cx + dx, cz + dz))
+                .filter(|column| !channel.contains(column))
+                .filter_map(|(bx, bz)| Some((bx, bz, *surface.get(&(bx, bz))?)));
+            for (bx, bz, top) in banks.chain(std::iter::once((cx, cz, water - 1))) {
+                for y in water - 1..=top {
+                    if !self.is_position_occupied(bx, y, bz) {
+                        self = self.add_cube(bx as f32, y as f32, bz as f32, 1.0, "dirt");
+                    }
+                }
+            }
+        }
+
+        self.grass_positions.retain(|&(x, y, z)| surface.get(&(x, z)).is_some_and(|&top| top >= y));
+        println!("  Río de ({}, {}) a ({}, {}): {} columnas de agua", start_x, start_z, x, z, channel.len());
+        self
+    }
+
     /// Genera un lago orgánico en la superficie de la isla
     pub fn add_organic_lake(
         mut self,
@@ -682,6 +1960,258 @@ impl SceneBuilder {
         self
     }
     
+    /// Agrega a la biblioteca de esta escena un material nuevo (o reemplaza
+    /// uno existente) para usarlo por nombre en los demás métodos. Los
+    /// objetos ya agregados conservan el material que tenían.
+    pub fn register_material(mut self, name: &str, material: Material) -> Self {
+        self.materials.register(name, material);
+        self
+    }
+
+    /// Agrega a la paleta un bloque con los materiales `top`, `side` y `bottom`
+    /// (nombres de la biblioteca) para colocarlo con `add_block`. Si se llama
+    /// igual que un material, `add_block` usa el bloque en su lugar.
+    pub fn register_block(mut self, name: &str, top: &str, side: &str, bottom: &str) -> Self {
+        let faces = BlockFaces::new(self.materials.get(top), self.materials.get(side), self.materials.get(bottom));
+        self.block_palette.insert(name.to_string(), Arc::new(faces));
+        self
+    }
+
+    /// Material de la biblioteca con ese nombre, si existe
+    pub fn get_material(&self, name: &str) -> Option<Arc<Material>> {
+        self.materials.materials.get(name).cloned()
+    }
+
+    /// Abre un grupo: todo lo que se agregue hasta el `end_group`
+    /// correspondiente se mueve junto. Los grupos se pueden anidar.
+    pub fn begin_group(mut self) -> Self {
+        self.groups.push(OpenGroup {
+            first_object: self.objects.len(),
+            first_light: self.lights.len(),
+            occupied_positions: std::mem::take(&mut self.occupied_positions),
+            blocks: std::mem::take(&mut self.blocks),
+            fences: std::mem::take(&mut self.fences),
+        });
+        self
+    }
+
+    /// Cierra el último grupo abierto y le aplica `transform` a todo lo que se
+    /// agregó en él (objetos y luces), alrededor del origen. Si el grupo se
+    /// armó en el origen, así se lleva a cualquier lugar de la escena. Con
+    /// un desplazamiento entero y sin escala los bloques siguen en la grilla
+    /// (se pueden quitar, esculpir y ocultar); si no, se mueven como objetos
+    /// sueltos.
+    pub fn end_group(mut self, transform: GroupTransform) -> Self {
+        let Some(group) = self.groups.pop() else {
+            println!("  end_group sin un begin_group abierto: se ignora");
+            return self;
+        };
+        let (first_object, first_light) = (group.first_object, group.first_light);
+        let on_grid = transform.keeps_grid();
+        let object_transform = transform.to_transform();
+
+        // Los bloques del grupo (toda la grilla mientras estuvo abierto)
+        // vuelven a la de afuera en su nueva celda si siguen en la grilla
+        let mut group_occupied = std::mem::replace(&mut self.occupied_positions, group.occupied_positions);
+        let mut group_fences = std::mem::replace(&mut self.fences, group.fences);
+        let group_blocks = std::mem::replace(&mut self.blocks, group.blocks);
+        let mut moved = Vec::with_capacity(group_blocks.len());
+        for (cell, (index, full)) in group_blocks {
+            let material = group_occupied.remove(&cell).unwrap_or_default();
+            let fence = group_fences.remove(&cell);
+            moved.push((cell, index, full, material, fence));
+        }
+        let moved_cells: HashSet<(i32, i32, i32)> = moved.iter().map(|(cell, ..)| *cell).collect();
+        let full_blocks: HashMap<usize, ((i32, i32, i32), &str)> = moved
+            .iter()
+            .filter(|(_, _, full, ..)| *full)
+            .map(|(cell, index, _, material, _)| (*index, (*cell, material.as_str())))
+            .collect();
+
+        for index in first_object..self.objects.len() {
+            if self.removed_objects.contains(&index) {
+                continue;
+            }
+            let object: Arc<dyn RayIntersect + Send + Sync> = match full_blocks.get(&index) {
+                // Un bloque completo que sigue en la grilla es un cubo nuevo, sin el costo de la transformación
+                Some(&(cell, material)) if on_grid => {
+                    let (x, y, z) = transform.apply_cell(cell);
+                    Arc::new(self.block_cube(x, y, z, material))
+                }
+                _ => Arc::new(Transformed::new(self.objects[index].clone(), object_transform)),
+            };
+            self.objects[index] = object;
+        }
+
+        if on_grid {
+            for (cell, index, full, material, fence) in moved {
+                let cell = transform.apply_cell(cell);
+                // Como al colocar un bloque, lo que ya había en la celda se conserva
+                if self.blocks.contains_key(&cell) {
+                    self.removed_objects.insert(index);
+                    continue;
+                }
+                self.blocks.insert(cell, (index, full));
+                self.occupied_positions.insert(cell, material);
+                if fence {
+                    self.fences.insert(cell);
+                }
+            }
+        }
+        self.grass_positions = std::mem::take(&mut self.grass_positions)
+            .into_iter()
+            .filter_map(|cell| match moved_cells.contains(&cell) {
+                false => Some(cell),
+                true if on_grid => Some(transform.apply_cell(cell)),
+                true => None,
+            })
+            .collect();
+
+        for light in &mut self.lights[first_light..] {
+            light.position = transform.apply_point(light.position);
+            if let Some(spot) = &mut light.spot {
+                spot.direction = transform.rotate(spot.direction);
+            }
+            if let Some(area) = &mut light.area {
+                area.edge_u = transform.rotate(area.edge_u * transform.scale);
+                area.edge_v = transform.rotate(area.edge_v * transform.scale);
+                area.normal = transform.rotate(area.normal);
+            }
+            // Copias del mismo grupo en distintos lugares no parpadean al unísono
+            if let Some(flame) = &mut light.flame {
+                flame.seed += transform.offset.x * 12.9898 + transform.offset.z * 78.233;
+            }
+        }
+        self
+    }
+
+    /// Suma a la escena todo lo armado en `other` (bloques, objetos sueltos y
+    /// luces), corrido `offset` celdas. Así una estructura armada por su
+    /// cuenta en el origen (un castillo, una isla, un esquema importado) se
+    /// lleva a cualquier lugar del mundo. Como al colocar un bloque, si una
+    /// celda ya está ocupada se conserva lo que había y el bloque de `other`
+    /// se descarta. Los materiales y bloques de la paleta de `other` que no
+    /// existan en esta escena se copian.
+    pub fn merge(mut self, other: SceneBuilder, offset: (i32, i32, i32)) -> Self {
+        if !other.groups.is_empty() {
+            println!("  merge: la escena a combinar tiene {} grupos sin cerrar; se combinan sin su transformación", other.groups.len());
+        }
+        for (name, material) in other.materials.materials {
+            self.materials.materials.entry(name).or_insert(material);
+        }
+        for (name, faces) in other.block_palette {
+            self.block_palette.entry(name).or_insert(faces);
+        }
+
+        let shift = |(x, y, z): (i32, i32, i32)| (x + offset.0, y + offset.1, z + offset.2);
+        let delta = Vector3::new(offset.0 as f32, offset.1 as f32, offset.2 as f32);
+        let translation = Transform::new(delta);
+        let cells: HashMap<usize, ((i32, i32, i32), bool)> = other.blocks.iter().map(|(&cell, &(index, full))| (index, (cell, full))).collect();
+        let mut merged: HashSet<(i32, i32, i32)> = HashSet::new();
+        let mut collisions = 0;
+
+        for (index, object) in other.objects.into_iter().enumerate() {
+            if other.removed_objects.contains(&index) {
+                continue;
+            }
+            let Some(&(cell, full)) = cells.get(&index) else {
+                self.objects.push(Arc::new(Transformed::new(object, translation)));
+                continue;
+            };
+            let (x, y, z) = shift(cell);
+            if self.is_position_occupied(x, y, z) {
+                collisions += 1;
+                continue;
+            }
+            let material = other.occupied_positions.get(&cell).cloned().unwrap_or_default();
+            // Un bloque completo vuelve a ser un cubo en su celda nueva, sin el costo de la transformación
+            let object: Arc<dyn RayIntersect + Send + Sync> = match full {
+                true => Arc::new(self.block_cube(x, y, z, &material)),
+                false => Arc::new(Transformed::new(object, translation)),
+            };
+            self.blocks.insert((x, y, z), (self.objects.len(), full));
+            self.objects.push(object);
+            self.mark_position(x, y, z, &material);
+            merged.insert(cell);
+        }
+
+        self.grass_positions.extend(other.grass_positions.into_iter().filter(|cell| merged.contains(cell)).map(shift));
+        // Las cercas se vuelven a unir, también con las vecinas que ya había
+        for cell in other.fences.into_iter().filter(|cell| merged.contains(cell)) {
+            let (x, y, z) = shift(cell);
+            self.fences.insert((x, y, z));
+            self.connect_fence(x, y, z);
+            self.connect_fence_neighbors(x, y, z);
+        }
+
+        for mut light in other.lights {
+            light.position += delta;
+            if let Some(flame) = &mut light.flame {
+                flame.seed += delta.x * 12.9898 + delta.z * 78.233;
+            }
+            self.lights.push(light);
+        }
+
+        if collisions > 0 {
+            println!("  merge: {} bloques descartados por caer en celdas ocupadas", collisions);
+        }
+        self
+    }
+
+    /// Duplica todo lo agregado hasta ahora reflejado en el plano perpendicular
+    /// a `axis` en la coordenada `plane` (entera o a media celda, para que los
+    /// bloques caigan en la grilla), y las luces también con `include_lights`.
+    /// Los bloques reflejados se vuelven a colocar como bloques; el resto de
+    /// los objetos se envuelve en una transformación de espejo.
+    pub fn mirror(mut self, axis: Axis, plane: f32, include_lights: bool) -> Self {
+        let full_blocks: HashMap<usize, (i32, i32, i32)> = self
+            .blocks
+            .iter()
+            .filter(|(_, (_, full))| *full)
+            .map(|(&position, &(index, _))| (index, position))
+            .collect();
+        let reflection = Transform::new(axis.reflect_point(Vector3::zero(), plane)).with_scale(axis.reflect_vector(Vector3::one()));
+
+        for index in 0..self.objects.len() {
+            if self.removed_objects.contains(&index) {
+                continue;
+            }
+            match full_blocks.get(&index) {
+                Some(&(x, y, z)) => {
+                    let cell = axis.reflect_point(Vector3::new(x as f32, y as f32, z as f32), plane);
+                    let material = self.occupied_positions[&(x, y, z)].clone();
+                    self = self.add_cube(cell.x.round(), cell.y.round(), cell.z.round(), 1.0, &material);
+                }
+                None => {
+                    let object = Transformed::new(self.objects[index].clone(), reflection);
+                    self.objects.push(Arc::new(object));
+                }
+            }
+        }
+
+        if include_lights {
+            let mirrored: Vec<Light> = self
+                .lights
+                .iter()
+                .map(|light| {
+                    let mut light = light.clone();
+                    light.position = axis.reflect_point(light.position, plane);
+                    if let Some(spot) = &mut light.spot {
+                        spot.direction = axis.reflect_vector(spot.direction);
+                    }
+                    if let Some(area) = &mut light.area {
+                        area.edge_u = axis.reflect_vector(area.edge_u);
+                        area.edge_v = axis.reflect_vector(area.edge_v);
+                        area.normal = axis.reflect_vector(area.normal);
+                    }
+                    light
+                })
+                .collect();
+            self.lights.extend(mirrored);
+        }
+        self
+    }
+
     pub fn add_nether_reflection(mut self, center_x: i32, center_y: i32, center_z: i32, radius: i32) -> Self {
         let cx = center_x as f32;
         let cy = center_y as f32;
@@ -752,8 +2282,16 @@ impl SceneBuilder {
             // Verificar que no haya agua en esta posición
             let has_water = self.is_position_occupied(x, grass_y + 1, z);
             
-            if random < density && dist_to_center > min_dist_sq && !has_water {
-                self = self.add_tree(x, grass_y + 1, z);
+            let (vegetation, biome_density) = match &self.biomes {
+                Some(biomes) => {
+                    let biome = biomes.biome_at(x, z);
+                    (biome.vegetation(hash01(x, self.seed as i32, z, 22)), biome.vegetation_density())
+                }
+                None => (Vegetation::Tree(TreeSpecies::Oak), 1.0),
+            };
+            
+            if random < density * biome_density && dist_to_center > min_dist_sq && !has_water {
+                self = self.add_vegetation(vegetation, x, grass_y + 1, z);
                 tree_count += 1;
             }
         }
@@ -787,19 +2325,115 @@ impl SceneBuilder {
     }
     
     pub fn add_dual_world_lighting(mut self, center_x: f32, center_z: f32) -> Self {
-        self = self.add_sun(center_x + 20.0, 30.0, center_z + 20.0, 4.0);
+        self = self.add_sun(center_x + 20.0, 30.0, center_z + 20.0, 1.0);
         self = self.add_light(center_x - 15.0, 25.0, center_z - 15.0, Color::new(180, 200, 255, 255), 2.0);
-        self = self.add_light(center_x, -10.0, center_z, Color::new(255, 80, 30, 255), 3.5);
-        self = self.add_light(center_x + 5.0, -15.0, center_z + 5.0, Color::new(255, 120, 40, 255), 2.5);
-        self = self.add_light(center_x - 5.0, -15.0, center_z - 5.0, Color::new(255, 100, 20, 255), 2.5);
+        // Resplandor de la lava del Nether, con pulso lento
+        self = self.add_light(center_x, -10.0, center_z, Color::new(255, 80, 30, 255), 3.5)
+            .with_light_flame(Flame::lava(0.0));
+        self = self.add_light(center_x + 5.0, -15.0, center_z + 5.0, Color::new(255, 120, 40, 255), 2.5)
+            .with_light_flame(Flame::lava(2.1));
+        self = self.add_light(center_x - 5.0, -15.0, center_z - 5.0, Color::new(255, 100, 20, 255), 2.5)
+            .with_light_flame(Flame::lava(4.3));
         self
     }
     
-    pub fn build(self) -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
-        (self.objects, self.lights)
+    /// Agrega partículas ambientales sobre los bloques de superficie: semillas
+    /// sobre el pasto, ceniza en el Nether, nieve sobre hielo y esporas sobre
+    /// hongos. Las posiciones son fijas para cada escena.
+    pub fn add_ambient_particles(mut self) -> Self {
+        let mut surfaces: Vec<((i32, i32, i32), ParticleKind)> = self
+            .occupied_positions
+            .iter()
+            .filter(|((x, y, z), _)| !self.occupied_positions.contains_key(&(*x, y + 1, *z)))
+            .filter_map(|(pos, material)| ParticleKind::for_surface(material, pos.1).map(|kind| (*pos, kind)))
+            .collect();
+        surfaces.sort_by_key(|(pos, _)| *pos);
+
+        for ((x, y, z), kind) in surfaces {
+            if hash01(x, y, z, 0) >= kind.density() {
+                continue;
+            }
+
+            let (min_height, max_height) = kind.height_range();
+            let height = min_height + hash01(x, y, z, 1) * (max_height - min_height);
+            let px = x as f32 + hash01(x, y, z, 2) - 0.5;
+            let pz = z as f32 + hash01(x, y, z, 3) - 0.5;
+            let radius = kind.radius();
+
+            for i in 0..kind.column_length() {
+                let py = y as f32 + 0.5 + height + i as f32 * radius * 4.0;
+                self = self.add_sphere(px, py, pz, radius, kind.material());
+            }
+        }
+        self
+    }
+
+    /// Bloque completo y opaco en `pos`, que tapa la cara del vecino que lo toca
+    fn is_occluder(&self, pos: &(i32, i32, i32)) -> bool {
+        self.blocks.get(pos).is_some_and(|(_, full)| *full)
+            && self.occupied_positions.get(pos).is_some_and(|material| self.materials.is_opaque(material))
+    }
+
+    /// Objetos de bloques rodeados por bloques opacos en sus seis caras:
+    /// ningún rayo puede llegar a ellos
+    fn hidden_blocks(&self) -> HashSet<usize> {
+        self.blocks
+            .iter()
+            .filter(|((x, y, z), _)| {
+                FACE_NEIGHBORS
+                    .iter()
+                    .all(|(dx, dy, dz)| self.is_occluder(&(x + dx, y + dy, z + dz)))
+            })
+            .map(|(_, (index, _))| *index)
+            .collect()
+    }
+
+    /// Devuelve la escena construida, sin los bloques interiores (las islas
+    /// se generan macizas) ni los quitados con `remove_block`; los interiores
+    /// quedan aparte para el editor de bloques
+    pub fn build(self) -> BuiltScene {
+        BuiltScene::new(self.build_data())
+    }
+
+    /// Lo mismo que `build` pero sin construir el BVH, para actualizar una
+    /// escena ya construida con `BuiltScene::update`
+    pub fn build_data(mut self) -> SceneData {
+        let hidden = self.hidden_blocks();
+        if !hidden.is_empty() {
+            println!("  Bloques interiores descartados: {} de {}", hidden.len(), self.blocks.len());
+        }
+
+        // Índice de cada objeto que queda en la lista final
+        let mut kept: HashMap<usize, usize> = HashMap::new();
+        let mut objects = Vec::with_capacity(self.objects.len() - hidden.len());
+        for (index, object) in self.objects.iter().enumerate() {
+            if !hidden.contains(&index) && !self.removed_objects.contains(&index) {
+                kept.insert(index, objects.len());
+                objects.push(object.clone());
+            }
+        }
+
+        let mut blocks = HashMap::with_capacity(kept.len());
+        let mut hidden_blocks = HiddenBlocks::new();
+        for (cell, (index, _)) in self.blocks {
+            let material = self.occupied_positions.remove(&cell).unwrap_or_default();
+            match kept.get(&index) {
+                Some(&kept_index) => {
+                    blocks.insert(cell, (kept_index, material));
+                }
+                None if hidden.contains(&index) => {
+                    hidden_blocks.insert(cell, (self.objects[index].clone(), material));
+                }
+                None => {}
+            }
+        }
+        let materials = SceneMaterials { materials: self.materials.materials, block_types: self.block_palette };
+        SceneData::new(objects, self.lights, blocks, hidden_blocks, materials)
     }
 }
 
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum WallDirection {
     North,
     South,
@@ -807,6 +2441,168 @@ pub enum WallDirection {
     West,
 }
 
+/// Cómo se mueve un grupo al cerrarlo: escala, luego giro en cuartos de
+/// vuelta alrededor del eje Y y luego desplazamiento, todo desde el origen
+#[derive(Clone, Copy)]
+pub struct GroupTransform {
+    offset: Vector3,
+    /// Cuartos de vuelta antihorarios vistos desde arriba
+    quarter_turns: i32,
+    scale: f32,
+}
+
+impl GroupTransform {
+    /// Solo desplaza el grupo
+    pub fn translation(x: f32, y: f32, z: f32) -> Self {
+        GroupTransform {
+            offset: Vector3::new(x, y, z),
+            quarter_turns: 0,
+            scale: 1.0,
+        }
+    }
+
+    pub fn with_quarter_turns(mut self, turns: i32) -> Self {
+        self.quarter_turns = turns.rem_euclid(4);
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Si los bloques del grupo caen en celdas enteras al moverlo
+    fn keeps_grid(&self) -> bool {
+        self.scale == 1.0 && [self.offset.x, self.offset.y, self.offset.z].iter().all(|c| c.fract() == 0.0)
+    }
+
+    fn rotate(&self, v: Vector3) -> Vector3 {
+        match self.quarter_turns {
+            1 => Vector3::new(v.z, v.y, -v.x),
+            2 => Vector3::new(-v.x, v.y, -v.z),
+            3 => Vector3::new(-v.z, v.y, v.x),
+            _ => v,
+        }
+    }
+
+    fn apply_point(&self, p: Vector3) -> Vector3 {
+        self.offset + self.rotate(p * self.scale)
+    }
+
+    fn apply_cell(&self, (x, y, z): (i32, i32, i32)) -> (i32, i32, i32) {
+        let p = self.apply_point(Vector3::new(x as f32, y as f32, z as f32));
+        (p.x.round() as i32, p.y.round() as i32, p.z.round() as i32)
+    }
+
+    fn to_transform(self) -> Transform {
+        Transform::new(self.offset)
+            .with_rotation(Vector3::new(0.0, 1.0, 0.0), self.quarter_turns as f32 * std::f32::consts::FRAC_PI_2)
+            .with_scale(Vector3::one() * self.scale)
+    }
+}
+
+/// Eje perpendicular al plano de una simetría
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Refleja una dirección: cambia de signo su componente sobre el eje
+    fn reflect_vector(self, v: Vector3) -> Vector3 {
+        match self {
+            Axis::X => Vector3::new(-v.x, v.y, v.z),
+            Axis::Y => Vector3::new(v.x, -v.y, v.z),
+            Axis::Z => Vector3::new(v.x, v.y, -v.z),
+        }
+    }
+
+    /// Refleja un punto respecto del plano perpendicular al eje en `plane`
+    fn reflect_point(self, p: Vector3, plane: f32) -> Vector3 {
+        match self {
+            Axis::X => Vector3::new(2.0 * plane - p.x, p.y, p.z),
+            Axis::Y => Vector3::new(p.x, 2.0 * plane - p.y, p.z),
+            Axis::Z => Vector3::new(p.x, p.y, 2.0 * plane - p.z),
+        }
+    }
+}
+
+/// Celdas de la caja entre dos esquinas, ambas incluidas y en cualquier orden
+fn box_cells(from: (i32, i32, i32), to: (i32, i32, i32)) -> impl Iterator<Item = (i32, i32, i32)> {
+    let (min, max) = ((from.0.min(to.0), from.1.min(to.1), from.2.min(to.2)), (from.0.max(to.0), from.1.max(to.1), from.2.max(to.2)));
+    (min.0..=max.0).flat_map(move |x| (min.1..=max.1).flat_map(move |y| (min.2..=max.2).map(move |z| (x, y, z))))
+}
+
+/// Celdas cuyo centro cae dentro de la esfera
+fn sphere_cells(cx: f32, cy: f32, cz: f32, radius: f32) -> impl Iterator<Item = (i32, i32, i32)> {
+    let min = |c: f32| (c - radius).ceil() as i32;
+    let max = |c: f32| (c + radius).floor() as i32;
+    box_cells((min(cx), min(cy), min(cz)), (max(cx), max(cy), max(cz))).filter(
+        move |&(x, y, z)| {
+            let (dx, dy, dz) = (x as f32 - cx, y as f32 - cy, z as f32 - cz);
+            dx * dx + dy * dy + dz * dz <= radius * radius
+        },
+    )
+}
+
+/// Puntos cada cuarto de bloque de la curva de Catmull-Rom que pasa por
+/// todos los `points`; los extremos se repiten para que empiece y termine en ellos
+fn catmull_rom(points: &[Vector2]) -> Vec<Vector2> {
+    if points.len() < 2 {
+        return points.to_vec();
+    }
+    let at = |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize];
+
+    let mut samples = Vec::new();
+    for i in 0..points.len() as isize - 1 {
+        let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+        let steps = ((p2 - p1).length() * 4.0).ceil().max(1.0) as i32;
+        for step in 0..steps {
+            let t = step as f32 / steps as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            samples.push(
+                (p1 * 2.0 + (p2 - p0) * t + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2 + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3) * 0.5,
+            );
+        }
+    }
+    samples.push(points[points.len() - 1]);
+    samples
+}
+
+/// Celdas por las que pasa la curva `point_at(t)` con t de 0 a 1, de largo
+/// aproximado `length`, tocándose siempre por una cara: donde la curva
+/// cambia de celda en más de un eje a la vez se agrega el escalón
+pub fn curve_cells(length: f32, point_at: impl Fn(f32) -> Vector3) -> Vec<[i32; 3]> {
+    // Pasos de un cuarto de bloque: la curva no salta más de una celda por paso
+    let steps = (length * 4.0).ceil().max(1.0) as i32;
+
+    let mut cells: Vec<[i32; 3]> = Vec::new();
+    for step in 0..=steps {
+        let point = point_at(step as f32 / steps as f32);
+        let cell = [point.x.round() as i32, point.y.round() as i32, point.z.round() as i32];
+        let Some(&last) = cells.last() else {
+            cells.push(cell);
+            continue;
+        };
+        if last == cell {
+            continue;
+        }
+        // Subiendo se apila sobre el bloque anterior; bajando se avanza primero
+        let order = if cell[1] > last[1] { [1, 0, 2] } else { [0, 2, 1] };
+        let mut walk = last;
+        for axis in order {
+            if walk[axis] != cell[axis] {
+                walk[axis] = cell[axis];
+                cells.push(walk);
+            }
+        }
+    }
+    cells
+}
+
 impl Default for SceneBuilder {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,688 @@
+use raylib::prelude::*;
+use serde::Deserialize;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::camera::{Camera, Projection};
+use crate::material::Material;
+use crate::partial_block::Facing;
+use crate::built_scene::{BuiltScene, SceneData, SceneDiff};
+use crate::scene_builder::{Axis, GroupTransform, SceneBuilder, WallDirection};
+use crate::scene_script::SceneScript;
+use crate::scenes::SceneInfo;
+use crate::terrain::TerrainParams;
+use crate::caves::CaveParams;
+use crate::village::VillageStyle;
+use crate::bridge::BridgeStyle;
+use crate::trees::TreeSpecies;
+use crate::light::{AREA_LIGHT_SAMPLES, MAX_AREA_LIGHT_SAMPLES};
+use crate::lighting::LightingPreset;
+use crate::world_scale::WorldScale;
+
+/// Número de escena con el que se identifica la escena cargada desde archivo
+pub const SCENE_FILE_NUM: i32 = 0;
+/// Cada cuánto se revisa si el archivo cambió
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Escenas que se pueden anidar con el prefab `scene`; evita ciclos entre archivos
+const MAX_INCLUDE_DEPTH: usize = 4;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    position: [f32; 3],
+    target: [f32; 3],
+    #[serde(default)]
+    projection: ProjectionKind,
+    /// Mitad de la altura de la imagen en bloques (ortográfica e isométrica);
+    /// por defecto la que deja `target` del mismo tamaño que en perspectiva
+    extent: Option<f32>,
+    /// Inclinación en grados (ángulo holandés)
+    #[serde(default)]
+    roll: f32,
+}
+
+/// Proyección de la cámara de la escena
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ProjectionKind {
+    #[default]
+    Perspective,
+    Orthographic,
+    /// Ortográfica desde la diagonal, a la misma distancia de `target`
+    Isometric,
+}
+
+impl CameraDescription {
+    fn camera(&self) -> Camera {
+        let (position, target) = (vector(self.position), vector(self.target));
+        let camera = Camera::new(position, target, Vector3::new(0.0, 1.0, 0.0));
+        let extent = self.extent.unwrap_or_else(|| camera.matching_extent()).max(0.1);
+        let camera = match self.projection {
+            ProjectionKind::Perspective => camera,
+            ProjectionKind::Orthographic => camera.with_projection(Projection::Orthographic { extent }),
+            ProjectionKind::Isometric => Camera::isometric(target, (target - position).length(), extent),
+        };
+        camera.with_roll(self.roll.to_radians())
+    }
+}
+
+/// Forma de un bloque suelto
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum BlockKind {
+    #[default]
+    Cube,
+    Slab,
+    TopSlab,
+    Stairs,
+    Fence,
+    PaneX,
+    PaneZ,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockEntry {
+    at: [i32; 3],
+    material: String,
+    #[serde(default)]
+    shape: BlockKind,
+    /// Hacia dónde suben las escaleras
+    facing: Option<Facing>,
+}
+
+/// Material nuevo, o que reemplaza uno de la biblioteca: parte de `base` (un
+/// material ya definido, por defecto `stone`) y cambia los campos que se den
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialEntry {
+    name: String,
+    base: Option<String>,
+    diffuse: Option<[f32; 3]>,
+    specular: Option<f32>,
+    transparency: Option<f32>,
+    reflectivity: Option<f32>,
+    refraction_index: Option<f32>,
+    emissive: Option<[f32; 3]>,
+    /// Textura procedural o archivo de `assets/textures`; `"none"` la quita
+    texture: Option<String>,
+    /// Mapa de normales (como `stone_normal`); `"none"` lo quita. Al cambiar
+    /// `texture`, los tres mapas pasan a ser los que haya junto a la textura nueva
+    normal_map: Option<String>,
+    /// Mapa de rugosidad en escala de grises; `"none"` lo quita
+    roughness_map: Option<String>,
+    /// Mapa de metal en escala de grises; `"none"` lo quita
+    metalness_map: Option<String>,
+    /// Proyecta la textura desde los tres ejes, una repetición cada tantas
+    /// unidades (para esferas, conos y modelos); 0 lo quita
+    triplanar: Option<f32>,
+}
+
+impl MaterialEntry {
+    fn material(&self, builder: &SceneBuilder) -> Material {
+        let base = self.base.as_deref().unwrap_or("stone");
+        let mut material = match builder.get_material(base) {
+            Some(material) => (*material).clone(),
+            None => {
+                println!("  Material {}: no existe la base {}, se usa stone", self.name, base);
+                (*builder.get_material("stone").unwrap()).clone()
+            }
+        };
+        if let Some(diffuse) = self.diffuse {
+            material.diffuse = vector(diffuse);
+        }
+        if let Some(specular) = self.specular {
+            material.specular = specular;
+        }
+        if let Some(transparency) = self.transparency {
+            material.transparency = transparency;
+        }
+        if let Some(reflectivity) = self.reflectivity {
+            material.reflectivity = reflectivity;
+        }
+        if let Some(refraction_index) = self.refraction_index {
+            material.refraction_index = refraction_index;
+        }
+        if let Some(emissive) = self.emissive {
+            material.emissive = vector(emissive);
+        }
+        if let Some(texture) = &self.texture {
+            material.texture_path = (texture != "none").then(|| texture.clone());
+            material = material.with_texture_maps();
+        }
+        if let Some(normal_map) = &self.normal_map {
+            material.normal_map = (normal_map != "none").then(|| normal_map.clone());
+        }
+        if let Some(roughness_map) = &self.roughness_map {
+            material.roughness_map = (roughness_map != "none").then(|| roughness_map.clone());
+        }
+        if let Some(metalness_map) = &self.metalness_map {
+            material.metalness_map = (metalness_map != "none").then(|| metalness_map.clone());
+        }
+        if let Some(scale) = self.triplanar {
+            material.triplanar = None;
+            if scale > 0.0 {
+                material = material.with_triplanar(scale);
+            }
+        }
+        material
+    }
+}
+
+/// Bloque de la paleta con un material arriba, otro a los costados y otro
+/// abajo; los costados usan el de arriba y lo de abajo el de los costados si
+/// no se dan
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockTypeEntry {
+    name: String,
+    top: String,
+    side: Option<String>,
+    bottom: Option<String>,
+}
+
+/// Región maciza de bloques, con ambos extremos incluidos
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FillEntry {
+    from: [i32; 3],
+    to: [i32; 3],
+    material: String,
+}
+
+/// Operación de esculpido sobre los bloques ya colocados, en el orden del archivo
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum SculptEntry {
+    FillBox { from: [i32; 3], to: [i32; 3], material: String },
+    FillSphere { center: [f32; 3], radius: f32, material: String },
+    CarveBox { from: [i32; 3], to: [i32; 3] },
+    CarveSphere { center: [f32; 3], radius: f32 },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum ShapeEntry {
+    Sphere { center: [f32; 3], radius: f32, material: String },
+    Cylinder { base: [f32; 3], radius: f32, height: f32, material: String },
+    Cone { base: [f32; 3], radius: f32, height: f32, material: String },
+    /// Caja girada `angle` grados alrededor de `axis`
+    OrientedBox { center: [f32; 3], size: [f32; 3], axis: [f32; 3], angle: f32, material: String },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum PrefabEntry {
+    /// Árbol, enredadera u hongo del sistema L de `path` que crece desde `at`
+    Lsystem { path: String, at: [i32; 3] },
+    /// Árbol de la especie dada (por defecto un roble)
+    Tree { at: [i32; 3], species: Option<TreeSpecies> },
+    Pine { at: [i32; 3] },
+    House { at: [i32; 2] },
+    Tower { at: [i32; 2], height: i32, material: String },
+    Torch { at: [f32; 3] },
+    /// Construcción de Minecraft (`.schem` o `.nbt`) con su esquina mínima en
+    /// `at`, girada `turns` cuartos de vuelta alrededor de esa esquina
+    Schematic {
+        path: String,
+        at: [i32; 3],
+        #[serde(default)]
+        turns: i32,
+        scale: Option<f32>,
+    },
+    /// Modelo glTF 2.0 (`.gltf` o `.glb`) con sus materiales y luces
+    Gltf { path: String, at: [f32; 3], scale: Option<f32> },
+    /// Otro archivo de escena armado por su cuenta y combinado con su origen
+    /// en `at`; se ignoran su cámara y su simetría, y sus bloques no pisan los
+    /// que ya había
+    Scene { path: String, at: [i32; 3] },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum GeneratorEntry {
+    Floor { radius: i32, material: String },
+    CheckeredFloor { radius: i32, materials: [String; 2] },
+    FloatingIsland { center: [i32; 3], radius: i32 },
+    Wall { start: [i32; 2], length: i32, height: i32, direction: WallDirection, material: String },
+    FenceLine { start: [i32; 3], length: i32, direction: WallDirection, material: String },
+    /// Media esfera hueca de bloques, abierta por abajo
+    Dome { center: [i32; 3], radius: f32, material: String },
+    /// Arco de bloques entre dos pies que sube `height` en el medio
+    Arch { start: [i32; 3], end: [i32; 3], height: i32, material: String },
+    AmbientParticles,
+    /// Colinas de ruido de Perlin centradas en el origen
+    Terrain {
+        width: i32,
+        depth: i32,
+        #[serde(default)]
+        params: TerrainParams,
+    },
+    /// Terreno de un mapa de alturas en escala de grises; `materials` son
+    /// franjas `[hasta, material]` por altura relativa
+    Heightmap {
+        path: String,
+        scale: f32,
+        #[serde(default)]
+        materials: Vec<(f32, String)>,
+    },
+    /// Río que baja por la superficie desde la columna `start` (x, z)
+    River { start: [i32; 2], length: i32 },
+    /// Cuevas y túneles en el suelo de los generadores anteriores
+    Caves {
+        #[serde(default)]
+        params: CaveParams,
+    },
+    /// Aldea sobre el suelo de los generadores anteriores, de `size` bloques
+    /// desde `center` hasta cada borde
+    Village {
+        center: [i32; 3],
+        size: i32,
+        #[serde(default)]
+        seed: u32,
+        #[serde(default)]
+        style: VillageStyle,
+    },
+    /// Camino que sigue una curva suave por las columnas (x, z) de `points`
+    /// sobre el suelo de los generadores anteriores
+    Path {
+        points: Vec<[i32; 2]>,
+        width: i32,
+        material: String,
+    },
+    /// Cascada que nace en la columna (x, z) de `edge` y corre hasta el
+    /// borde más cercano del suelo de los generadores anteriores
+    Waterfall {
+        edge: [i32; 2],
+        max_depth: i32,
+    },
+    /// Puente de arco o colgante entre dos puntos
+    Bridge {
+        from: [f32; 3],
+        to: [f32; 3],
+        #[serde(default)]
+        style: BridgeStyle,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+enum LightEntry {
+    Point { position: [f32; 3], color: [u8; 3], intensity: f32 },
+    Sun { position: [f32; 3], intensity: f32 },
+    /// Foco con conos en grados
+    Spot { position: [f32; 3], target: [f32; 3], inner_angle: f32, outer_angle: f32, color: [u8; 3], intensity: f32 },
+    /// Rectángulo emisor centrado en `position`; `samples` es el lado de la
+    /// grilla de rayos de sombra
+    Area {
+        position: [f32; 3],
+        edge_u: [f32; 3],
+        edge_v: [f32; 3],
+        color: [u8; 3],
+        intensity: f32,
+        samples: Option<u32>,
+    },
+}
+
+/// Simetría que duplica toda la escena reflejada en un plano
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MirrorDescription {
+    axis: Axis,
+    plane: f32,
+    /// También se reflejan las luces
+    #[serde(default)]
+    lights: bool,
+}
+
+/// Contenido de un archivo de escena (TOML)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    camera: CameraDescription,
+    /// Metros por bloque; por defecto, los de `WorldScale`
+    meters_per_block: Option<f32>,
+    /// Semilla de los biomas del terreno y las islas; sin ella todo es pasto
+    biomes: Option<u32>,
+    /// Semilla de la forma de los árboles
+    seed: Option<u32>,
+    /// Ambiente de iluminación al abrir la escena; sin él sigue el ciclo día/noche
+    lighting: Option<LightingPreset>,
+    #[serde(default)]
+    materials: Vec<MaterialEntry>,
+    #[serde(default)]
+    block_types: Vec<BlockTypeEntry>,
+    #[serde(default)]
+    generators: Vec<GeneratorEntry>,
+    #[serde(default)]
+    fill: Vec<FillEntry>,
+    #[serde(default)]
+    blocks: Vec<BlockEntry>,
+    #[serde(default)]
+    sculpt: Vec<SculptEntry>,
+    #[serde(default)]
+    shapes: Vec<ShapeEntry>,
+    #[serde(default)]
+    prefabs: Vec<PrefabEntry>,
+    #[serde(default)]
+    lights: Vec<LightEntry>,
+    mirror: Option<MirrorDescription>,
+}
+
+fn vector(v: [f32; 3]) -> Vector3 {
+    Vector3::new(v[0], v[1], v[2])
+}
+
+fn cell(c: [i32; 3]) -> (i32, i32, i32) {
+    (c[0], c[1], c[2])
+}
+
+fn color(c: [u8; 3]) -> Color {
+    Color::new(c[0], c[1], c[2], 255)
+}
+
+impl SceneDescription {
+    fn parse(text: &str, path: &str) -> Result<Self, String> {
+        let description: SceneDescription = toml::from_str(text).map_err(|e| format!("Error leyendo la escena {}: {}", path, e))?;
+        for block in &description.blocks {
+            if matches!(block.shape, BlockKind::Stairs) && block.facing.is_none() {
+                return Err(format!("Error leyendo la escena {}: la escalera en {:?} necesita `facing`", path, block.at));
+            }
+        }
+        for light in &description.lights {
+            if let LightEntry::Area { position, edge_u, edge_v, samples, .. } = light {
+                if vector(*edge_u).cross(vector(*edge_v)).length() <= f32::EPSILON {
+                    return Err(format!("Error leyendo la escena {}: la luz de área en {:?} tiene lados nulos o paralelos", path, position));
+                }
+                if samples.is_some_and(|samples| !(1..=MAX_AREA_LIGHT_SAMPLES).contains(&samples)) {
+                    return Err(format!("Error leyendo la escena {}: `samples` de la luz de área en {:?} debe ir de 1 a {}", path, position, MAX_AREA_LIGHT_SAMPLES));
+                }
+            }
+        }
+        Ok(description)
+    }
+
+    fn world_scale(&self) -> WorldScale {
+        self.meters_per_block.map(WorldScale::new).unwrap_or_default()
+    }
+
+    fn data(&self) -> SceneData {
+        let mut builder = self.builder(0);
+        if let Some(mirror) = &self.mirror {
+            builder = builder.mirror(mirror.axis, mirror.plane, mirror.lights);
+        }
+
+        let mut data = builder.build_data();
+        let world_scale = self.world_scale();
+        for light in &mut data.lights {
+            light.world_scale = world_scale;
+        }
+        data
+    }
+
+    /// Arma la escena en el orden del archivo por secciones: primero los
+    /// materiales y bloques propios, luego los generadores (suelos, islas), luego los bloques y el esculpido, después
+    /// las luces. `depth` cuenta las escenas combinadas con el prefab `scene`
+    /// que llevaron hasta esta
+    fn builder(&self, depth: usize) -> SceneBuilder {
+        let mut builder = SceneBuilder::new();
+        if let Some(seed) = self.biomes {
+            builder = builder.with_biomes(seed);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.with_seed(seed);
+        }
+        for entry in &self.materials {
+            let material = entry.material(&builder);
+            builder = builder.register_material(&entry.name, material);
+        }
+        for entry in &self.block_types {
+            let side = entry.side.as_deref().unwrap_or(&entry.top);
+            let bottom = entry.bottom.as_deref().unwrap_or(side);
+            builder = builder.register_block(&entry.name, &entry.top, side, bottom);
+        }
+
+        for generator in &self.generators {
+            builder = match generator {
+                GeneratorEntry::Floor { radius, material } => builder.add_floor(*radius, material),
+                GeneratorEntry::CheckeredFloor { radius, materials } => builder.add_checkered_floor(*radius, &materials[0], &materials[1]),
+                GeneratorEntry::FloatingIsland { center, radius } => builder.add_floating_island(center[0], center[1], center[2], *radius),
+                GeneratorEntry::Wall { start, length, height, direction, material } => {
+                    builder.add_wall(start[0], start[1], *length, *height, *direction, material)
+                }
+                GeneratorEntry::FenceLine { start, length, direction, material } => {
+                    builder.add_fence_line(start[0], start[1], start[2], *length, *direction, material)
+                }
+                GeneratorEntry::Dome { center, radius, material } => builder.add_dome(cell(*center), *radius, material),
+                GeneratorEntry::Arch { start, end, height, material } => builder.add_arch(cell(*start), cell(*end), *height, material),
+                GeneratorEntry::AmbientParticles => builder.add_ambient_particles(),
+                GeneratorEntry::Terrain { width, depth, params } => builder.add_terrain(*width, *depth, *params),
+                GeneratorEntry::Heightmap { path, scale, materials } => {
+                    let bands: Vec<(f32, &str)> = materials.iter().map(|(up_to, material)| (*up_to, material.as_str())).collect();
+                    builder.add_heightmap_terrain(path, *scale, &bands)
+                }
+                GeneratorEntry::River { start, length } => builder.add_river(start[0], start[1], *length),
+                GeneratorEntry::Caves { params } => builder.add_caves(*params),
+                GeneratorEntry::Village { center, size, seed, style } => builder.add_village(cell(*center), *size, *seed, *style),
+                GeneratorEntry::Path { points, width, material } => {
+                    let points: Vec<(i32, i32)> = points.iter().map(|point| (point[0], point[1])).collect();
+                    builder.add_path(&points, *width, material)
+                }
+                GeneratorEntry::Waterfall { edge, max_depth } => builder.add_waterfall((edge[0], edge[1]), *max_depth),
+                GeneratorEntry::Bridge { from, to, style } => builder.add_bridge(vector(*from), vector(*to), style.clone()),
+            };
+        }
+
+        for fill in &self.fill {
+            builder = builder.fill_box(cell(fill.from), cell(fill.to), &fill.material);
+        }
+
+        for block in &self.blocks {
+            let [x, y, z] = block.at;
+            let material = block.material.as_str();
+            builder = match block.shape {
+                BlockKind::Cube => builder.add_block(x, y, z, material),
+                BlockKind::Slab => builder.add_slab(x, y, z, false, material),
+                BlockKind::TopSlab => builder.add_slab(x, y, z, true, material),
+                BlockKind::Stairs => builder.add_stairs(x, y, z, block.facing.unwrap_or(Facing::North), material),
+                BlockKind::Fence => builder.add_fence(x, y, z, material),
+                BlockKind::PaneX => builder.add_pane(x, y, z, true, material),
+                BlockKind::PaneZ => builder.add_pane(x, y, z, false, material),
+            };
+        }
+
+        for operation in &self.sculpt {
+            builder = match operation {
+                SculptEntry::FillBox { from, to, material } => builder.fill_box(cell(*from), cell(*to), material),
+                SculptEntry::FillSphere { center, radius, material } => builder.fill_sphere(center[0], center[1], center[2], *radius, material),
+                SculptEntry::CarveBox { from, to } => builder.carve_box(cell(*from), cell(*to)),
+                SculptEntry::CarveSphere { center, radius } => builder.carve_sphere(center[0], center[1], center[2], *radius),
+            };
+        }
+
+        for shape in &self.shapes {
+            builder = match shape {
+                ShapeEntry::Sphere { center, radius, material } => builder.add_sphere(center[0], center[1], center[2], *radius, material),
+                ShapeEntry::Cylinder { base, radius, height, material } => {
+                    builder.add_cylinder(base[0], base[1], base[2], *radius, *height, material)
+                }
+                ShapeEntry::Cone { base, radius, height, material } => builder.add_cone(base[0], base[1], base[2], *radius, *height, material),
+                ShapeEntry::OrientedBox { center, size, axis, angle, material } => {
+                    builder.add_oriented_box(vector(*center), vector(*size), vector(*axis), angle.to_radians(), material)
+                }
+            };
+        }
+
+        for prefab in &self.prefabs {
+            builder = match prefab {
+                PrefabEntry::Lsystem { path, at } => builder.add_lsystem(path, at[0], at[1], at[2]),
+                PrefabEntry::Tree { at, species } => builder.add_tree_species(species.unwrap_or(TreeSpecies::Oak), at[0], at[1], at[2]),
+                PrefabEntry::Pine { at } => builder.add_pine(at[0], at[1], at[2]),
+                PrefabEntry::House { at } => builder.add_house(at[0], at[1]),
+                PrefabEntry::Tower { at, height, material } => builder.add_tower(at[0], at[1], *height, material),
+                PrefabEntry::Torch { at } => builder.add_torch(at[0], at[1], at[2]),
+                PrefabEntry::Schematic { path, at, turns, scale } => builder
+                    .begin_group()
+                    .add_schematic(path, 0, 0, 0)
+                    .end_group(
+                        GroupTransform::translation(at[0] as f32, at[1] as f32, at[2] as f32)
+                            .with_quarter_turns(*turns)
+                            .with_scale(scale.unwrap_or(1.0)),
+                    ),
+                PrefabEntry::Gltf { path, at, scale } => builder.add_gltf(path, at[0], at[1], at[2], scale.unwrap_or(1.0)),
+                PrefabEntry::Scene { path, at } => match included_scene(path, depth) {
+                    Ok(other) => builder.merge(other, cell(*at)),
+                    Err(e) => {
+                        println!("  {}", e);
+                        builder
+                    }
+                },
+            };
+        }
+
+        for light in &self.lights {
+            builder = match light {
+                LightEntry::Point { position, color: c, intensity } => builder.add_light(position[0], position[1], position[2], color(*c), *intensity),
+                LightEntry::Sun { position, intensity } => builder.add_sun(position[0], position[1], position[2], *intensity),
+                LightEntry::Spot { position, target, inner_angle, outer_angle, color: c, intensity } => {
+                    builder.add_spotlight(vector(*position), vector(*target), *inner_angle, *outer_angle, color(*c), *intensity)
+                }
+                LightEntry::Area { position, edge_u, edge_v, color: c, intensity, samples } => builder
+                    .add_area_light(vector(*position), vector(*edge_u), vector(*edge_v), color(*c), *intensity)
+                    .with_light_area_samples(samples.unwrap_or(AREA_LIGHT_SAMPLES)),
+            };
+        }
+
+        builder
+    }
+}
+
+/// Escena de otro archivo para el prefab `scene`, todavía sin armar del todo
+/// para combinarla con `SceneBuilder::merge`
+fn included_scene(path: &str, depth: usize) -> Result<SceneBuilder, String> {
+    if depth >= MAX_INCLUDE_DEPTH {
+        return Err(format!("Escena {} no combinada: más de {} escenas anidadas (¿se incluye a sí misma?)", path, MAX_INCLUDE_DEPTH));
+    }
+    if is_script(path) {
+        return SceneScript::load(path)?.builder();
+    }
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Error abriendo la escena {}: {}", path, e))?;
+    Ok(SceneDescription::parse(&text, path)?.builder(depth + 1))
+}
+
+fn is_script(path: &str) -> bool {
+    path.ends_with(".rhai")
+}
+
+/// Lo que se leyó del archivo: una descripción TOML o un script de Rhai
+enum SceneSource {
+    Description(Box<SceneDescription>),
+    Script(SceneScript),
+}
+
+impl SceneSource {
+    fn read(path: &str) -> Result<Self, String> {
+        if is_script(path) {
+            return SceneScript::load(path).map(SceneSource::Script);
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Error abriendo la escena {}: {}", path, e))?;
+        SceneDescription::parse(&text, path).map(|description| SceneSource::Description(Box::new(description)))
+    }
+}
+
+/// Escena descrita en un archivo TOML (bloques, piezas, generadores, luces y
+/// cámara) o escrita como script de Rhai (`.rhai`), que se vuelve a importar
+/// cuando el archivo cambia en disco
+pub struct SceneFile {
+    path: String,
+    source: SceneSource,
+    /// Fecha de modificación de la última lectura (buena o con errores)
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+impl SceneFile {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let modified = modified_time(path);
+        Ok(SceneFile {
+            path: path.to_string(),
+            source: SceneSource::read(path)?,
+            modified,
+            last_poll: Instant::now(),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Lleva `scene`, construida antes desde este archivo, a lo que describe
+    /// ahora, ajustando solo lo que cambió (ver `BuiltScene::update`)
+    pub fn update(&self, scene: &mut BuiltScene) -> SceneDiff {
+        scene.update(self.data())
+    }
+
+    /// Objetos y luces que describe el archivo, sin construir el BVH
+    pub fn data(&self) -> SceneData {
+        match &self.source {
+            SceneSource::Description(description) => description.data(),
+            SceneSource::Script(script) => {
+                let builder = script.builder().unwrap_or_else(|e| {
+                    println!("{}", e);
+                    SceneBuilder::new()
+                });
+                let mut data = builder.build_data();
+                for light in &mut data.lights {
+                    light.world_scale = script.world_scale();
+                }
+                data
+            }
+        }
+    }
+
+    pub fn info(&self) -> SceneInfo {
+        let (camera, world_scale, lighting) = match &self.source {
+            SceneSource::Description(description) => (
+                description.camera.camera(),
+                description.world_scale(),
+                description.lighting,
+            ),
+            SceneSource::Script(script) => {
+                let (position, target) = script.camera();
+                (Camera::new(position, target, Vector3::new(0.0, 1.0, 0.0)), script.world_scale(), script.lighting())
+            }
+        };
+        SceneInfo {
+            name: if is_script(&self.path) { "Escena desde script" } else { "Escena desde archivo" },
+            camera_pos: camera.eye,
+            camera_target: camera.center,
+            projection: camera.projection,
+            camera_roll: camera.roll,
+            world_scale,
+            environment_map: None,
+            lighting,
+        }
+    }
+
+    /// Vuelve a leer el archivo si se modificó desde la última lectura.
+    /// Devuelve `Ok(true)` si hay una escena nueva; si el archivo tiene errores
+    /// se informa una vez y se conserva la escena anterior.
+    pub fn reload_if_changed(&mut self) -> Result<bool, String> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Ok(false);
+        }
+        self.last_poll = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return Ok(false);
+        }
+        self.modified = modified;
+
+        self.source = SceneSource::read(&self.path)?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,299 @@
+use raylib::prelude::*;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope};
+use serde::de::DeserializeOwned;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::lighting::LightingPreset;
+use crate::scene_builder::SceneBuilder;
+use crate::world_scale::WorldScale;
+
+/// Operaciones que puede hacer un script antes de cortarlo, para que un
+/// bucle sin fin no deje colgada la ventana
+const MAX_OPERATIONS: u64 = 500_000_000;
+/// Cámara si el script no llama a `scene.camera`
+const DEFAULT_CAMERA: ([f32; 3], [f32; 3]) = ([20.0, 15.0, 20.0], [0.0, 0.0, 0.0]);
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Lo que va armando el script
+#[derive(Default)]
+struct ScriptState {
+    builder: Option<SceneBuilder>,
+    camera: Option<(Vector3, Vector3)>,
+    meters_per_block: Option<f32>,
+    lighting: Option<LightingPreset>,
+}
+
+/// La escena que ve el script como la variable `scene`; sus métodos llaman a
+/// los de `SceneBuilder` con los mismos nombres
+#[derive(Clone)]
+struct ScriptScene(Rc<RefCell<ScriptState>>);
+
+impl ScriptScene {
+    fn apply(&mut self, step: impl FnOnce(SceneBuilder) -> SceneBuilder) {
+        let mut state = self.0.borrow_mut();
+        if let Some(builder) = state.builder.take() {
+            state.builder = Some(step(builder));
+        }
+    }
+
+    fn read<T: Default>(&self, query: impl FnOnce(&SceneBuilder) -> T) -> T {
+        self.0.borrow().builder.as_ref().map(query).unwrap_or_default()
+    }
+}
+
+/// Valor de un enum de la escena por su nombre en snake_case, como en los
+/// archivos TOML (`"north"`, `"golden_hour"`, `"birch"`)
+fn named<T: DeserializeOwned>(kind: &str, name: &str) -> ScriptResult<T> {
+    toml::Value::String(name.to_string())
+        .try_into()
+        .map_err(|_| format!("valor desconocido para {}: {}", kind, name).into())
+}
+
+fn number(value: &Dynamic) -> ScriptResult<f32> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|n| n as f32))
+        .map_err(|_| format!("se esperaba un número y llegó {}", value.type_name()).into())
+}
+
+fn triple(values: &Array) -> ScriptResult<[f32; 3]> {
+    match values.as_slice() {
+        [x, y, z] => Ok([number(x)?, number(y)?, number(z)?]),
+        _ => Err(format!("se esperaban 3 números y llegaron {}", values.len()).into()),
+    }
+}
+
+fn vector(values: &Array) -> ScriptResult<Vector3> {
+    triple(values).map(|[x, y, z]| Vector3::new(x, y, z))
+}
+
+fn cell(values: &Array) -> ScriptResult<(i32, i32, i32)> {
+    triple(values).map(|[x, y, z]| (x.round() as i32, y.round() as i32, z.round() as i32))
+}
+
+fn color(values: &Array) -> ScriptResult<Color> {
+    triple(values).map(|[r, g, b]| Color::new(r.clamp(0.0, 255.0) as u8, g.clamp(0.0, 255.0) as u8, b.clamp(0.0, 255.0) as u8, 255))
+}
+
+/// Motor con el tipo `Scene` y sus métodos registrados
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.register_type_with_name::<ScriptScene>("Scene");
+
+    // Datos de la escena
+    engine.register_fn("camera", |scene: &mut ScriptScene, position: Array, target: Array| -> ScriptResult<()> {
+        scene.0.borrow_mut().camera = Some((vector(&position)?, vector(&target)?));
+        Ok(())
+    });
+    engine.register_fn("meters_per_block", |scene: &mut ScriptScene, meters: f32| {
+        scene.0.borrow_mut().meters_per_block = Some(meters);
+    });
+    engine.register_fn("lighting", |scene: &mut ScriptScene, preset: &str| -> ScriptResult<()> {
+        scene.0.borrow_mut().lighting = Some(named("ambiente", preset)?);
+        Ok(())
+    });
+    engine.register_fn("with_seed", |scene: &mut ScriptScene, seed: i32| scene.apply(|b| b.with_seed(seed as u32)));
+    engine.register_fn("with_biomes", |scene: &mut ScriptScene, seed: i32| scene.apply(|b| b.with_biomes(seed as u32)));
+    engine.register_fn("register_block", |scene: &mut ScriptScene, name: &str, top: &str, side: &str, bottom: &str| {
+        scene.apply(|b| b.register_block(name, top, side, bottom))
+    });
+
+    // Consultas
+    engine.register_fn("is_occupied", |scene: &mut ScriptScene, x: i32, y: i32, z: i32| {
+        scene.read(|b| b.is_position_occupied(x, y, z))
+    });
+    engine.register_fn("material_at", |scene: &mut ScriptScene, x: i32, y: i32, z: i32| {
+        scene.read(|b| b.material_at(x, y, z).map(|name| Dynamic::from(name.to_string())).unwrap_or(Dynamic::UNIT))
+    });
+
+    // Bloques y piezas
+    engine.register_fn("add_block", |scene: &mut ScriptScene, x: i32, y: i32, z: i32, block: &str| {
+        scene.apply(|b| b.add_block(x, y, z, block))
+    });
+    engine.register_fn("add_cube", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, size: f32, material: &str| {
+        scene.apply(|b| b.add_cube(x, y, z, size, material))
+    });
+    engine.register_fn("add_sphere", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, radius: f32, material: &str| {
+        scene.apply(|b| b.add_sphere(x, y, z, radius, material))
+    });
+    engine.register_fn("add_slab", |scene: &mut ScriptScene, x: i32, y: i32, z: i32, top: bool, material: &str| {
+        scene.apply(|b| b.add_slab(x, y, z, top, material))
+    });
+    engine.register_fn("add_stairs", |scene: &mut ScriptScene, x: i32, y: i32, z: i32, facing: &str, material: &str| -> ScriptResult<()> {
+        let facing = named("orientación", facing)?;
+        scene.apply(|b| b.add_stairs(x, y, z, facing, material));
+        Ok(())
+    });
+    engine.register_fn("add_fence", |scene: &mut ScriptScene, x: i32, y: i32, z: i32, material: &str| {
+        scene.apply(|b| b.add_fence(x, y, z, material))
+    });
+    engine.register_fn("add_pane", |scene: &mut ScriptScene, x: i32, y: i32, z: i32, along_x: bool, material: &str| {
+        scene.apply(|b| b.add_pane(x, y, z, along_x, material))
+    });
+    engine.register_fn("add_cylinder", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, radius: f32, height: f32, material: &str| {
+        scene.apply(|b| b.add_cylinder(x, y, z, radius, height, material))
+    });
+    engine.register_fn("add_cone", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, radius: f32, height: f32, material: &str| {
+        scene.apply(|b| b.add_cone(x, y, z, radius, height, material))
+    });
+
+    // Volúmenes y estructuras
+    engine.register_fn("add_floor", |scene: &mut ScriptScene, radius: i32, material: &str| scene.apply(|b| b.add_floor(radius, material)));
+    engine.register_fn("add_checkered_floor", |scene: &mut ScriptScene, radius: i32, first: &str, second: &str| {
+        scene.apply(|b| b.add_checkered_floor(radius, first, second))
+    });
+    engine.register_fn(
+        "add_wall",
+        |scene: &mut ScriptScene, x: i32, z: i32, length: i32, height: i32, direction: &str, material: &str| -> ScriptResult<()> {
+            let direction = named("dirección", direction)?;
+            scene.apply(|b| b.add_wall(x, z, length, height, direction, material));
+            Ok(())
+        },
+    );
+    engine.register_fn("fill_box", |scene: &mut ScriptScene, from: Array, to: Array, material: &str| -> ScriptResult<()> {
+        let (from, to) = (cell(&from)?, cell(&to)?);
+        scene.apply(|b| b.fill_box(from, to, material));
+        Ok(())
+    });
+    engine.register_fn("fill_sphere", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, radius: f32, material: &str| {
+        scene.apply(|b| b.fill_sphere(x, y, z, radius, material))
+    });
+    engine.register_fn("carve_box", |scene: &mut ScriptScene, from: Array, to: Array| -> ScriptResult<()> {
+        let (from, to) = (cell(&from)?, cell(&to)?);
+        scene.apply(|b| b.carve_box(from, to));
+        Ok(())
+    });
+    engine.register_fn("carve_sphere", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, radius: f32| {
+        scene.apply(|b| b.carve_sphere(x, y, z, radius))
+    });
+    engine.register_fn("add_dome", |scene: &mut ScriptScene, center: Array, radius: f32, material: &str| -> ScriptResult<()> {
+        let center = cell(&center)?;
+        scene.apply(|b| b.add_dome(center, radius, material));
+        Ok(())
+    });
+    engine.register_fn("add_arch", |scene: &mut ScriptScene, start: Array, end: Array, height: i32, material: &str| -> ScriptResult<()> {
+        let (start, end) = (cell(&start)?, cell(&end)?);
+        scene.apply(|b| b.add_arch(start, end, height, material));
+        Ok(())
+    });
+    engine.register_fn("add_tower", |scene: &mut ScriptScene, x: i32, z: i32, height: i32, material: &str| {
+        scene.apply(|b| b.add_tower(x, z, height, material))
+    });
+    engine.register_fn("add_floating_island", |scene: &mut ScriptScene, x: i32, y: i32, z: i32, radius: i32| {
+        scene.apply(|b| b.add_floating_island(x, y, z, radius))
+    });
+    engine.register_fn("add_house", |scene: &mut ScriptScene, x: i32, z: i32| scene.apply(|b| b.add_house(x, z)));
+
+    // Vegetación
+    engine.register_fn("add_tree", |scene: &mut ScriptScene, x: i32, y: i32, z: i32| scene.apply(|b| b.add_tree(x, y, z)));
+    engine.register_fn("add_tree_species", |scene: &mut ScriptScene, species: &str, x: i32, y: i32, z: i32| -> ScriptResult<()> {
+        let species = named("especie", species)?;
+        scene.apply(|b| b.add_tree_species(species, x, y, z));
+        Ok(())
+    });
+    engine.register_fn("add_cactus", |scene: &mut ScriptScene, x: i32, y: i32, z: i32| scene.apply(|b| b.add_cactus(x, y, z)));
+    engine.register_fn("add_giant_mushroom", |scene: &mut ScriptScene, x: i32, y: i32, z: i32| {
+        scene.apply(|b| b.add_giant_mushroom(x, y, z))
+    });
+    engine.register_fn("add_lsystem", |scene: &mut ScriptScene, path: &str, x: i32, y: i32, z: i32| {
+        scene.apply(|b| b.add_lsystem(path, x, y, z))
+    });
+
+    // Archivos
+    engine.register_fn("add_schematic", |scene: &mut ScriptScene, path: &str, x: i32, y: i32, z: i32| {
+        scene.apply(|b| b.add_schematic(path, x, y, z))
+    });
+    engine.register_fn("add_gltf", |scene: &mut ScriptScene, path: &str, x: f32, y: f32, z: f32, scale: f32| {
+        scene.apply(|b| b.add_gltf(path, x, y, z, scale))
+    });
+
+    // Luces
+    engine.register_fn("add_light", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, rgb: Array, intensity: f32| -> ScriptResult<()> {
+        let rgb = color(&rgb)?;
+        scene.apply(|b| b.add_light(x, y, z, rgb, intensity));
+        Ok(())
+    });
+    engine.register_fn("add_torch", |scene: &mut ScriptScene, x: f32, y: f32, z: f32| scene.apply(|b| b.add_torch(x, y, z)));
+    engine.register_fn("add_sun", |scene: &mut ScriptScene, x: f32, y: f32, z: f32, intensity: f32| {
+        scene.apply(|b| b.add_sun(x, y, z, intensity))
+    });
+
+    engine
+}
+
+/// Escena escrita como script de Rhai (`assets/scenes/*.rhai`): el script
+/// recibe la variable `scene` y la arma llamando a los métodos de
+/// `SceneBuilder`, con bucles, funciones y cuentas propias
+pub struct SceneScript {
+    camera: (Vector3, Vector3),
+    world_scale: WorldScale,
+    lighting: Option<LightingPreset>,
+    path: String,
+    /// Escena de la corrida con la que se cargó, para no repetirla en el
+    /// primer `builder`
+    pending: RefCell<Option<Box<SceneBuilder>>>,
+}
+
+impl SceneScript {
+    /// Corre el script; los errores de sintaxis o de ejecución vienen con su línea
+    pub fn load(path: &str) -> Result<Self, String> {
+        let state = run(path)?;
+        let (position, target) = DEFAULT_CAMERA;
+        Ok(SceneScript {
+            camera: state.camera.unwrap_or((
+                Vector3::new(position[0], position[1], position[2]),
+                Vector3::new(target[0], target[1], target[2]),
+            )),
+            world_scale: state.meters_per_block.map(WorldScale::new).unwrap_or_default(),
+            lighting: state.lighting,
+            path: path.to_string(),
+            pending: RefCell::new(state.builder.map(Box::new)),
+        })
+    }
+
+    /// Posición y objetivo de la cámara
+    pub fn camera(&self) -> (Vector3, Vector3) {
+        self.camera
+    }
+
+    pub fn world_scale(&self) -> WorldScale {
+        self.world_scale
+    }
+
+    pub fn lighting(&self) -> Option<LightingPreset> {
+        self.lighting
+    }
+
+    /// Escena armada por el script; después de la primera vez lo vuelve a correr
+    pub fn builder(&self) -> Result<SceneBuilder, String> {
+        match self.pending.borrow_mut().take() {
+            Some(builder) => Ok(*builder),
+            None => run(&self.path).map(|state| state.builder.unwrap_or_default()),
+        }
+    }
+}
+
+/// Corre el script y devuelve lo que armó
+fn run(path: &str) -> Result<ScriptState, String> {
+    let engine = engine();
+    let ast = engine
+        .compile_file(path.into())
+        .map_err(|e| format!("Error leyendo el script {}: {}", path, e))?;
+
+    let scene = ScriptScene(Rc::new(RefCell::new(ScriptState {
+        builder: Some(SceneBuilder::new()),
+        camera: None,
+        meters_per_block: None,
+        lighting: None,
+    })));
+    let mut scope = Scope::new();
+    scope.push("scene", scene.clone());
+    engine
+        .run_ast_with_scope(&mut scope, &ast)
+        .map_err(|e| format!("Error en el script {}: {}", path, e))?;
+    drop(scope);
+    Ok(scene.0.take())
+}
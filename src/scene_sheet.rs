@@ -0,0 +1,79 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::camera::Camera;
+use crate::light::Light;
+use crate::ray_intersect::{RayIntersect, BVH};
+use crate::renderer::{RenderSettings, Renderer, Scene};
+use crate::scenes::SceneInfo;
+use crate::skybox::Environment;
+
+/// Tamaño (en pixels) de cada vista
+const VIEW_WIDTH: u32 = 320;
+const VIEW_HEIGHT: u32 = 240;
+/// Vistas alrededor de la escena (la última fila agrega una vista cenital)
+const ORBIT_VIEWS: u32 = 8;
+/// Columnas de la hoja de contactos
+const SHEET_COLUMNS: u32 = 3;
+
+/// Cámaras repartidas alrededor del objetivo de la escena, a la misma distancia
+/// y altura que la cámara inicial, más una vista desde arriba
+fn preset_cameras(info: &SceneInfo) -> Vec<Camera> {
+    let offset = info.camera_pos - info.camera_target;
+    let radius = (offset.x * offset.x + offset.z * offset.z).sqrt().max(1.0);
+    let height = offset.y;
+    let start_angle = offset.z.atan2(offset.x);
+    let up = Vector3::new(0.0, 1.0, 0.0);
+
+    let mut cameras: Vec<Camera> = (0..ORBIT_VIEWS)
+        .map(|i| {
+            let angle = start_angle + i as f32 / ORBIT_VIEWS as f32 * 2.0 * PI;
+            let eye = info.camera_target + Vector3::new(angle.cos() * radius, height, angle.sin() * radius);
+            Camera::new(eye, info.camera_target, up).with_projection(info.projection)
+        })
+        .collect();
+
+    // Vista cenital: `up` apunta hacia -Z para que la cámara no quede degenerada
+    let top_eye = info.camera_target + Vector3::new(0.0, offset.length(), 0.0);
+    cameras.push(Camera::new(top_eye, info.camera_target, Vector3::new(0.0, 0.0, -1.0)).with_projection(info.projection));
+    cameras
+}
+
+/// Renderiza la escena cargada desde varios ángulos en una sola imagen,
+/// reutilizando el BVH ya construido. Devuelve la ruta de la hoja.
+pub fn render_scene_sheet(
+    scene_num: i32,
+    scene_info: &SceneInfo,
+    bvh: &BVH,
+    objects: &[Arc<dyn RayIntersect + Send + Sync>],
+    lights: &[Light],
+    time_of_day: f32,
+    absolute_time: f32,
+) -> Result<String, String> {
+    let dir_path = "renders/scenes";
+    std::fs::create_dir_all(dir_path)
+        .map_err(|e| format!("No se pudo crear directorio {}: {}", dir_path, e))?;
+
+    let cameras = preset_cameras(scene_info);
+    let rows = (cameras.len() as u32).div_ceil(SHEET_COLUMNS);
+    let mut sheet = image::RgbaImage::new(SHEET_COLUMNS * VIEW_WIDTH, rows * VIEW_HEIGHT);
+    let environment = Environment::new(time_of_day, absolute_time);
+    let renderer = Renderer::new(Scene { bvh, objects, grid: None, chunks: None, lights, environment: &environment });
+    let settings = RenderSettings::new(VIEW_WIDTH, VIEW_HEIGHT);
+
+    for (i, camera) in cameras.iter().enumerate() {
+        let view = renderer.render_frame(camera, &settings);
+
+        let col = i as u32 % SHEET_COLUMNS;
+        let row = i as u32 / SHEET_COLUMNS;
+        image::imageops::replace(&mut sheet, &view, (col * VIEW_WIDTH) as i64, (row * VIEW_HEIGHT) as i64);
+        println!("  Vista {}/{}", i + 1, cameras.len());
+    }
+
+    let sheet_path = format!("{}/scene_{:02}_sheet.png", dir_path, scene_num);
+    sheet
+        .save(&sheet_path)
+        .map_err(|e| format!("Error guardando {}: {}", sheet_path, e))?;
+    Ok(sheet_path)
+}
@@ -1,11 +1,19 @@
 use raylib::prelude::*;
-use std::sync::Arc;
-use crate::ray_intersect::RayIntersect;
-use crate::light::Light;
+use crate::built_scene::{BuiltScene, SceneData};
+use crate::world_scale::WorldScale;
 use crate::scene_builder::{SceneBuilder, WallDirection};
+use crate::terrain::{Terrain, TerrainParams};
+use crate::caves::CaveParams;
+use crate::village::VillageStyle;
+use crate::bridge::BridgeStyle;
+use crate::trees::TreeSpecies;
+use crate::lighting::LightingPreset;
+use crate::particles::hash01;
+use crate::material::Material;
+use crate::camera::{Camera, Projection};
 
 /// ESCENA 1: Isla Flotante Básica
-pub fn floating_island_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn floating_island_scene() -> SceneData {
     let center_x = 0;
     let center_y = 12;
     let center_z = 0;
@@ -16,15 +24,17 @@ pub fn floating_island_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec
         .add_floating_island(center_x, center_y, center_z, radius)
         .add_organic_lake(center_x - 2, center_z + 2, 2, 1)
         .add_organic_lake(center_x + 3, center_z - 2, 3, 2)
+        .add_caves(CaveParams { threshold: 0.3, tunnel_radius: 2.0, glowstone: Some(0.05), ..CaveParams::new(1) })
         .add_island_vegetation_auto(center_x, center_z, 0.08)
         .add_nether_reflection(center_x, -center_y, center_z, radius)
         .add_nether_features(center_x, -center_y, center_z, radius)
+        .add_ambient_particles()
         .add_dual_world_lighting(center_x as f32, center_z as f32)
-        .build()
+        .build_data()
 }
 
 /// ESCENA 2: Isla con Cascadas
-pub fn floating_island_waterfalls() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn floating_island_waterfalls() -> SceneData {
     let center_x = 0;
     let center_y = 14;
     let center_z = 0;
@@ -43,38 +53,29 @@ pub fn floating_island_waterfalls() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>
         (center_x, center_z + 5),
     ];
     
-    for (wx, wz) in waterfall_positions {
-        let top_y = center_y + radius;
-        
-        if builder.is_position_occupied(wx, top_y - 1, wz) {
-            // Cambiar a tamaño 1.0 para bloques sólidos conectados
-            for h in 0..12 {
-                let y = top_y - h;
-                builder = builder.add_cube(wx as f32, y as f32, wz as f32, 1.0, "water");
-            }
-        }
+    // Cada una corre hasta el borde más cercano de la isla y cae al vacío
+    for edge in waterfall_positions {
+        builder = builder.add_waterfall(edge, 11);
     }
     
     builder = builder
         .add_nether_reflection(center_x, -center_y, center_z, radius)
         .add_nether_features(center_x, -center_y, center_z, radius);
     
-    // Cascadas de lava en el Nether también con tamaño 1.0
+    // Cascadas de lava en el Nether
     for (wx, wz) in waterfall_positions {
         let bottom_y = -center_y - radius;
-        for h in 0..12 {
-            let y = bottom_y + h;
-            builder = builder.add_cube(wx as f32, y as f32, wz as f32, 1.0, "lava");
-        }
+        builder = builder.add_fall(wx, wz, bottom_y + 11, bottom_y, "lava");
     }
     
     builder
+        .add_ambient_particles()
         .add_dual_world_lighting(center_x as f32, center_z as f32)
-        .build()
+        .build_data()
 }
 
 /// ESCENA 3: Isla con Puente Portal
-pub fn floating_island_bridge() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn floating_island_bridge() -> SceneData {
     let center_x = 0;
     let center_y = 12;
     let center_z = 0;
@@ -89,48 +90,18 @@ pub fn floating_island_bridge() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Ve
     
     let top_connection = center_y - radius - 1;
     let bottom_connection = -center_y + radius + 1;
-    let bridge_length = top_connection - bottom_connection;
     
-    println!("Construyendo puente de {} bloques", bridge_length);
-    
-    // Puente con bloques de tamaño 1.0
-    for y in bottom_connection..=top_connection {
-        let progress = (y - bottom_connection) as f32 / bridge_length as f32;
-        
-        // Columnas principales del puente (glass)
-        builder = builder
-            .add_cube((center_x - 1) as f32, y as f32, center_z as f32, 1.0, "glass")
-            .add_cube((center_x + 1) as f32, y as f32, center_z as f32, 1.0, "glass");
-        
-        // Piso del puente (cada 2 bloques)
-        if y % 2 == 0 {
-            builder = builder
-                .add_cube(center_x as f32, y as f32, center_z as f32, 1.0, "stone");
-        }
-        
-        // Luces decorativas (cada 4 bloques)
-        if y % 4 == 0 {
-            let angle = progress * std::f32::consts::PI * 4.0;
-            let light_x = center_x as f32 + angle.cos() * 2.0;
-            let light_z = center_z as f32 + angle.sin() * 2.0;
-            builder = builder.add_cube(
-                light_x.round(), 
-                y as f32, 
-                light_z.round(), 
-                1.0, 
-                "glowstone"
-            );
-        }
-        
-        // Soportes diagonales (cada 5 bloques)
-        if y % 5 == 0 {
-            builder = builder
-                .add_cube((center_x - 1) as f32, y as f32, (center_z - 1) as f32, 1.0, "stone")
-                .add_cube((center_x + 1) as f32, y as f32, (center_z - 1) as f32, 1.0, "stone")
-                .add_cube((center_x - 1) as f32, y as f32, (center_z + 1) as f32, 1.0, "stone")
-                .add_cube((center_x + 1) as f32, y as f32, (center_z + 1) as f32, 1.0, "stone");
-        }
-    }
+    // Columna de piedra entre la isla y su reflejo, con barandas de vidrio
+    builder = builder.add_bridge(
+        Vector3::new(center_x as f32, bottom_connection as f32, center_z as f32),
+        Vector3::new(center_x as f32, top_connection as f32, center_z as f32),
+        BridgeStyle {
+            railing: Some("glass".to_string()),
+            width: 1,
+            light_spacing: 4,
+            ..BridgeStyle::arch("stone", 0.0)
+        },
+    );
     
     // Plataformas de conexión con bloques de tamaño 1.0
     for dx in -2..=2 {
@@ -142,12 +113,13 @@ pub fn floating_island_bridge() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Ve
     }
     
     builder
+        .add_ambient_particles()
         .add_dual_world_lighting(center_x as f32, center_z as f32)
-        .build()
+        .build_data()
 }
 
 /// ESCENA 4: Castillo Medieval
-pub fn castle_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn castle_scene() -> SceneData {
     SceneBuilder::new()
         .add_floor(20, "stone")
         .add_tower(-10, -10, 8, "stone")
@@ -158,17 +130,25 @@ pub fn castle_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
         .add_wall(-10, 10, 21, 5, WallDirection::South, "stone")
         .add_wall(-10, -10, 21, 5, WallDirection::East, "stone")
         .add_wall(10, -10, 21, 5, WallDirection::West, "stone")
+        // Invernadero de vidrio en el centro del patio
+        .add_dome((0, 1, 0), 4.5, "glass")
+        // Techos cónicos en las dos torres sin faro
+        .add_cone(-10.0, 7.5, 10.0, 1.3, 3.0, "wood")
+        .add_cone(10.0, 7.5, -10.0, 1.3, 3.0, "wood")
         .add_torches(&[
             (-5.0, 5.0, -10.0),
             (0.0, 5.0, -10.0),
             (5.0, 5.0, -10.0),
         ])
-        .add_sun(20.0, 25.0, 20.0, 4.0)
-        .build()
+        .add_sun(20.0, 25.0, 20.0, 1.0)
+        // Faros en lo alto de dos torres apuntando al patio
+        .add_spotlight(Vector3::new(-10.0, 9.5, -10.0), Vector3::new(0.0, 0.0, 0.0), 10.0, 18.0, Color::new(255, 240, 200, 255), 4.0)
+        .add_spotlight(Vector3::new(10.0, 9.5, 10.0), Vector3::new(0.0, 0.0, 0.0), 10.0, 18.0, Color::new(255, 240, 200, 255), 4.0)
+        .build_data()
 }
 
 /// ESCENA 5: Casa con Jardín
-pub fn house_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn house_scene() -> SceneData {
     SceneBuilder::new()
         .add_checkered_floor(10, "grass_top", "dirt")
         .add_house(0, 0)
@@ -176,135 +156,72 @@ pub fn house_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
         .add_tree(-5, 0, 5)
         .add_tree(8, 0, -5)
         .add_tree(8, 0, 5)
-        .add_sun(15.0, 20.0, 15.0, 3.5)
-        .build()
+        .add_pine(-5, 1, 0)
+        .add_pine(8, 1, 0)
+        // Cerca con una entrada y un camino de losas hasta la casa
+        .add_fence_line(-3, 1, 7, 5, WallDirection::North, "wood")
+        .add_fence_line(3, 1, 7, 5, WallDirection::North, "wood")
+        .add_slab(2, 1, 5, false, "stone")
+        .add_slab(2, 1, 6, false, "stone")
+        .add_slab(2, 1, 7, false, "stone")
+        // Faroles (modelo glTF con su propia luz) a los lados de la entrada
+        .add_gltf("assets/models/lantern.gltf", 1.0, 0.5, 8.0, 1.0)
+        .add_gltf("assets/models/lantern.gltf", 3.0, 0.5, 8.0, 1.0)
+        // Tablón apoyado contra la pared este de la casa
+        .add_oriented_box(Vector3::new(5.0, 1.9, 2.0), Vector3::new(0.15, 3.0, 0.8), Vector3::new(0.0, 0.0, 1.0), 0.3, "wood")
+        // Paneles de vidrio al oeste del jardín
+        .add_pane(-8, 1, -2, false, "glass")
+        .add_pane(-8, 1, -1, false, "glass")
+        .add_pane(-8, 1, 0, false, "glass")
+        .add_pane(-8, 1, 1, false, "glass")
+        .add_pane(-8, 1, 2, false, "glass")
+        .add_sun(15.0, 20.0, 15.0, 0.875)
+        .build_data()
 }
 
 /// ESCENA 6: Escena Simple
-pub fn simple_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn simple_scene() -> SceneData {
     SceneBuilder::new()
         .add_floor(10, "grass_top")
         .add_cube(0.0, 1.0, 0.0, 2.0, "stone")
-        .add_sun(10.0, 15.0, 10.0, 3.0)
-        .build()
+        .add_sun(10.0, 15.0, 10.0, 0.75)
+        .build_data()
 }
 
 /// ESCENA 7: Aldea Medieval
-pub fn village_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn village_scene() -> SceneData {
     let mut builder = SceneBuilder::new();
     
     for x in -25_i32..=25 {
         for z in -25_i32..=25 {
-            let is_road = (x.abs() < 3 && z.abs() < 20) || 
-                         (z.abs() < 3 && x.abs() < 20);
-            
-            let material = if is_road {
-                "stone"
-            } else {
-                if (x + z) % 3 == 0 { "grass_top" } else { "dirt" }
-            };
-            
-            builder = builder.add_cube(x as f32, 0.0, z as f32, 1.0, material);
-        }
-    }
-    
-    let fountain_positions = [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)];
-    for (x, z) in fountain_positions {
-        builder = builder
-            .add_cube(x as f32, 1.0, z as f32, 1.0, "stone")
-            .add_cube(x as f32, 2.0, z as f32, 0.8, "water");
-    }
-    
-    let house_positions = [
-        (-15, -15), (-15, 8), (8, -15), (8, 8),
-        (-15, -8), (8, -8), (-8, -15), (-8, 8),
-    ];
-    
-    for (hx, hz) in house_positions {
-        builder = builder.add_box(hx, 1, hz, 4, 3, 4, "wood");
-        
-        for level in 0..2 {
-            let offset = level;
-            for dx in offset..(4-offset) {
-                for dz in offset..(4-offset) {
-                    builder = builder.add_cube(
-                        (hx + dx) as f32, 
-                        (4 + level) as f32, 
-                        (hz + dz) as f32, 
-                        1.0, 
-                        "stone"
-                    );
-                }
-            }
-        }
-        
-        builder = builder
-            .add_cube((hx + 1) as f32, 4.0, (hz + 1) as f32, 0.5, "stone")
-            .add_cube((hx + 1) as f32, 5.0, (hz + 1) as f32, 0.4, "stone")
-            .add_torch((hx + 2) as f32, 2.0, hz as f32 - 0.5);
-    }
-    
-    for i in 0..30 {
-        let angle = (i as f32 / 30.0) * std::f32::consts::PI * 2.0;
-        let radius = 18.0 + ((i * 7) % 5) as f32;
-        let x = (angle.cos() * radius) as i32;
-        let z = (angle.sin() * radius) as i32;
-        
-        if x.abs() > 5 || z.abs() > 5 {
-            builder = builder.add_tree(x, 1, z);
+            let material = if (x + z) % 3 == 0 { "grass_top" } else { "dirt" };
+            builder = builder.add_block(x, 0, z, material);
         }
     }
     
-    let tower_positions = [(-20, -20), (-20, 20), (20, -20), (20, 20)];
-    for (tx, tz) in tower_positions {
-        for y in 0..10 {
-            builder = builder.add_cube(tx as f32, y as f32, tz as f32, 1.0, "stone");
-        }
-        builder = builder.add_torch(tx as f32, 10.0, tz as f32);
-    }
-    
     builder
-        .add_sun(30.0, 40.0, 30.0, 4.0)
+        .add_village((0, 0, 0), 25, 7, VillageStyle::new())
+        .add_sun(30.0, 40.0, 30.0, 1.0)
         .add_light(-20.0, 12.0, -20.0, Color::new(255, 200, 150, 255), 3.0)
         .add_light(20.0, 12.0, 20.0, Color::new(255, 200, 150, 255), 3.0)
-        .build()
+        .build_data()
 }
 
 /// ESCENA 8: Bosque Encantado
-pub fn enchanted_forest_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
-    let mut builder = SceneBuilder::new();
-    
-    for x in -30..=30 {
-        for z in -30..=30 {
-            let wave = ((x as f32 * 0.2).sin() + (z as f32 * 0.15).cos()) * 2.0;
-            let y = wave as i32;
-            
-            for dy in 0..=y.abs() {
-                let actual_y = if y < 0 { -dy } else { dy };
-                let material = if dy == y.abs() { "grass_top" } else { "dirt" };
-                builder = builder.add_cube(x as f32, actual_y as f32, z as f32, 1.0, material);
-            }
-        }
-    }
-    
-    for x in -28..=28 {
-        for z in -28..=28 {
-            let dist = ((x * x + z * z) as f32).sqrt();
-            let noise = ((x as f32 * 0.3).sin() * (z as f32 * 0.4).cos()) * 10.0;
-            let density = ((dist + noise) % 8.0) / 8.0;
-            
-            if density < 0.4 {
-                let wave = ((x as f32 * 0.2).sin() + (z as f32 * 0.15).cos()) * 2.0;
-                let y = wave as i32 + 1;
-                builder = builder.add_tree(x, y, z);
-            }
-        }
-    }
+pub fn enchanted_forest_scene() -> SceneData {
+    // Colinas bajas con algún escalón; los lagos y los árboles siguen su superficie
+    let params = TerrainParams {
+        amplitude: 6.0,
+        feature_size: 24.0,
+        plateau_height: Some(0.65),
+        ..TerrainParams::new(8)
+    };
+    let terrain = Terrain::new(params);
+    let mut builder = SceneBuilder::new().with_seed(8).add_terrain(61, 61, params);
     
     let lake_positions = [(0, 0, 4), (-15, -10, 3), (12, 15, 3)];
     for (lx, lz, radius) in lake_positions {
-        let wave = ((lx as f32 * 0.2).sin() + (lz as f32 * 0.15).cos()) * 2.0;
-        let lake_y = wave as i32;
+        let lake_y = terrain.height_at(lx, lz);
         
         for dx in -radius..=radius {
             for dz in -radius..=radius {
@@ -325,12 +242,58 @@ pub fn enchanted_forest_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Ve
         }
     }
     
+    // Ríos que bajan de las lomas hasta los lagos
+    builder = builder.add_river(-29, 1, 60).add_river(6, -14, 60);
+    
+    // El lago del centro desborda por el escalón que tiene al oeste
+    builder = builder.add_waterfall((-3, 1), 8);
+    
+    // Hongos gigantes en los claros, antes que los árboles para que no los tapen
+    for (mx, mz) in [(-6, 12), (15, -3), (-19, -3), (5, -19), (22, 20)] {
+        builder = builder.add_lsystem("assets/lsystems/mushroom.toml", mx, terrain.height_at(mx, mz) + 1, mz);
+    }
+    
+    let mut jungle_trees = Vec::new();
+    for x in -28..=28 {
+        for z in -28..=28 {
+            let dist = ((x * x + z * z) as f32).sqrt();
+            let noise = ((x as f32 * 0.3).sin() * (z as f32 * 0.4).cos()) * 10.0;
+            let density = ((dist + noise) % 8.0) / 8.0;
+            
+            // Sin árboles sobre el cauce de los ríos ni sobre el agua
+            if density < 0.4 && builder.material_at(x, terrain.height_at(x, z), z).is_some_and(|material| material != "water") {
+                // Robles y abedules, algunos ramificados y alguno de la selva que asoma sobre el resto
+                let ground = terrain.height_at(x, z) + 1;
+                builder = match hash01(x, 8, z, 23) {
+                    roll if roll < 0.06 => {
+                        jungle_trees.push((x, ground, z));
+                        builder.add_tree_species(TreeSpecies::Jungle, x, ground, z)
+                    }
+                    roll if roll < 0.16 => builder.add_lsystem("assets/lsystems/tree.toml", x, ground, z),
+                    roll if roll < 0.4 => builder.add_tree_species(TreeSpecies::Birch, x, ground, z),
+                    _ => builder.add_tree_species(TreeSpecies::Oak, x, ground, z),
+                };
+            }
+        }
+    }
+    
+    // Enredaderas que cuelgan de las copas de los árboles de la selva
+    for (x, ground, z) in jungle_trees {
+        for (dx, dz) in [(2, 0), (-2, 0), (0, 2), (0, -2)] {
+            let (vx, vz) = (x + dx, z + dz);
+            if let Some(y) = (ground + 3..ground + 20).find(|&y| builder.material_at(vx, y, vz) == Some("leaves")) {
+                builder = builder.add_lsystem("assets/lsystems/vine.toml", vx, y - 1, vz);
+            }
+        }
+    }
+    
     let rock_positions: [(i32, i32); 3] = [(-20, 15), (18, -12), (-10, -20)];
     for (rx, rz) in rock_positions {
         let height = 5 + (((rx * rz).abs()) % 4) as i32;
+        let ground = terrain.height_at(rx, rz) + 1;
         for y in 0..height {
             let size = (height - y) as f32 * 0.3 + 0.5;
-            builder = builder.add_cube(rx as f32, y as f32, rz as f32, size, "stone");
+            builder = builder.add_cube(rx as f32, (ground + y) as f32, rz as f32, size, "stone");
         }
     }
     
@@ -339,23 +302,23 @@ pub fn enchanted_forest_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Ve
         let radius = 10.0 + ((i * 3) % 8) as f32;
         let x = (angle.cos() * radius) as i32;
         let z = (angle.sin() * radius) as i32;
-        let wave = ((x as f32 * 0.2).sin() + (z as f32 * 0.15).cos()) * 2.0;
-        let y = wave as i32 + 1;
+        let y = terrain.height_at(x, z) + 1;
         
         builder = builder.add_cube(x as f32, y as f32, z as f32, 0.3, "glowstone");
     }
     
     builder
-        .add_sun(40.0, 50.0, 40.0, 3.0)
+        .add_sun(40.0, 50.0, 40.0, 0.75)
         .add_light(0.0, 10.0, 0.0, Color::new(150, 255, 150, 255), 4.0)
         .add_light(-15.0, 8.0, -10.0, Color::new(100, 200, 255, 255), 3.0)
         .add_light(12.0, 8.0, 15.0, Color::new(255, 150, 200, 255), 3.0)
-        .build()
+        .add_ambient_particles()
+        .build_data()
 }
 
 /// ESCENA 9: Archipiélago Masivo
-pub fn massive_archipelago_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
-    let mut builder = SceneBuilder::new();
+pub fn massive_archipelago_scene() -> SceneData {
+    let mut builder = SceneBuilder::new().with_biomes(9);
     
     builder = builder
         .add_floating_island(0, 15, 0, 8)
@@ -374,9 +337,13 @@ pub fn massive_archipelago_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>,
         let z = (angle.sin() * radius) as i32;
         let y = 12 + ((i * 3) % 6) as i32 - 3;
         
-        builder = builder
-            .add_floating_island(x, y, z, 5)
-            .add_island_vegetation_auto(x, z, 0.1);
+        builder = builder.add_floating_island(x, y, z, 5);
+        // Las islas sin columna tienen un mirador armado aparte, antes de la
+        // vegetación para que los árboles no crezcan adentro
+        if i % 2 == 1 {
+            builder = builder.merge(lookout_post(), (x, y + 5, z));
+        }
+        builder = builder.add_island_vegetation_auto(x, z, 0.1);
         
         if i % 3 == 0 {
             builder = builder.add_organic_lake(x, z, 2, 1);
@@ -402,33 +369,38 @@ pub fn massive_archipelago_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>,
             .add_island_vegetation_auto(x, z, 0.15);
     }
     
+    // Puentes colgantes de madera desde la isla central a cuatro de las del anillo
     for i in [0, 2, 4, 6] {
         let angle = (i as f32 / 8.0) * std::f32::consts::PI * 2.0;
         let end_x = (angle.cos() * 20.0) as i32;
         let end_z = (angle.sin() * 20.0) as i32;
         let end_y = 12 + ((i * 3) % 6) as i32 - 3;
         
-        let steps = 20;
-        for step in 0..=steps {
-            let t = step as f32 / steps as f32;
-            let x = t * end_x as f32;
-            let z = t * end_z as f32;
-            let y = 15.0 + t * (end_y as f32 - 15.0) - (t * (1.0 - t) * 8.0);
-            
-            builder = builder
-                .add_cube(x - 0.5, y, z, 0.3, "glass")
-                .add_cube(x + 0.5, y, z, 0.3, "glass");
-            
-            if step % 5 == 0 {
-                builder = builder.add_cube(x, y + 0.5, z, 0.2, "glowstone");
-            }
-        }
+        builder = builder.add_bridge(
+            Vector3::new(0.0, 15.0, 0.0),
+            Vector3::new(end_x as f32, end_y as f32, end_z as f32),
+            BridgeStyle {
+                railing: Some("glass".to_string()),
+                width: 1,
+                light_spacing: 10,
+                ..BridgeStyle::arch("wood", -2.0)
+            },
+        );
+    }
+    
+    // Cascadas desde la isla central y caídas de lava bajo su reflejo
+    for wx in [-5, 5] {
+        builder = builder.add_waterfall((wx, 0), 14);
     }
     
     builder = builder
         .add_nether_reflection(0, -15, 0, 8)
         .add_nether_features(0, -15, 0, 8);
     
+    for wx in [-5, 5] {
+        builder = builder.add_fall(wx, 0, -15 - 8 + 14, -15 - 8, "lava");
+    }
+    
     for i in 0..8 {
         let angle = (i as f32 / 8.0) * std::f32::consts::PI * 2.0;
         let radius = 20.0;
@@ -442,16 +414,34 @@ pub fn massive_archipelago_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>,
     }
     
     builder
-        .add_sun(0.0, 60.0, 40.0, 5.0)
+        .add_sun(0.0, 60.0, 40.0, 1.25)
         .add_light(0.0, 30.0, 0.0, Color::new(255, 255, 200, 255), 6.0)
         .add_light(-25.0, 20.0, -25.0, Color::new(150, 200, 255, 255), 3.0)
         .add_light(25.0, 20.0, 25.0, Color::new(255, 150, 150, 255), 3.0)
         .add_light(0.0, -30.0, 0.0, Color::new(255, 100, 50, 255), 5.0)
-        .build()
+        .add_ambient_particles()
+        .build_data()
+}
+
+/// Mirador de madera de 3x3 con la base en el origen: piso, cuatro postes de
+/// cerca, techo de losas y una antorcha colgando en el centro
+fn lookout_post() -> SceneBuilder {
+    let mut post = SceneBuilder::new();
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            post = post
+                .add_block(dx, 0, dz, "wood")
+                .add_slab(dx, 3, dz, false, "wood");
+            if dx != 0 && dz != 0 {
+                post = post.add_fence(dx, 1, dz, "wood").add_fence(dx, 2, dz, "wood");
+            }
+        }
+    }
+    post.add_torch(0.0, 2.5, 0.0)
 }
 
 /// ESCENA 10: Templo Antiguo
-pub fn temple_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn temple_scene() -> SceneData {
     let mut builder = SceneBuilder::new();
     
     for x in -20..=20 {
@@ -495,6 +485,14 @@ pub fn temple_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
         }
     }
     
+    // Puertas en arco donde llega cada escalinata
+    for (dx, dz) in [(1_i32, 0_i32), (0, 1), (-1, 0), (0, -1)] {
+        let (x, z) = (dx * 8, dz * 8);
+        builder = builder
+            .carve_box((x - dz.abs(), 8, z - dx.abs()), (x + dz.abs(), 10, z + dx.abs()))
+            .carve_sphere(x as f32, 10.0, z as f32, 1.2);
+    }
+    
     let pillar_positions = [
         (-6, -6), (-6, 6), (6, -6), (6, 6),
         (-6, 0), (6, 0), (0, -6), (0, 6),
@@ -533,13 +531,7 @@ pub fn temple_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
         
         for dx in -3..=3 {
             for dz in -3..=3 {
-                builder = builder.add_cube(
-                    (garden_x + dx) as f32, 
-                    1.0, 
-                    (garden_z + dz) as f32, 
-                    1.0, 
-                    "grass_top"
-                );
+                builder = builder.add_block(garden_x + dx, 1, garden_z + dz, "grass_top");
             }
         }
         
@@ -559,16 +551,22 @@ pub fn temple_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
     }
     
     builder
-        .add_sun(0.0, 80.0, 0.0, 6.0)
+        .add_sun(0.0, 80.0, 0.0, 1.5)
         .add_light(0.0, 22.0, 0.0, Color::new(255, 255, 150, 255), 8.0)
         .add_light(-12.0, 5.0, -12.0, Color::new(255, 200, 100, 255), 3.0)
         .add_light(12.0, 5.0, 12.0, Color::new(255, 200, 100, 255), 3.0)
-        .build()
+        .build_data()
 }
 
 /// ESCENA 11: Cañón con Río
-pub fn canyon_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
-    let mut builder = SceneBuilder::new();
+pub fn canyon_scene() -> SceneData {
+    // Arenisca para las paredes del cañón, que no está en la biblioteca
+    let sandstone = Material::new(Vector3::new(0.88, 0.7, 0.48), 8.0, [0.85, 0.05], 0.0, 0.0, 1.0, Vector3::zero(), Some("sand".to_string()))
+        .with_uv_variation();
+    // Las repisas y el fondo del cañón: arena arriba y arenisca a los costados
+    let mut builder = SceneBuilder::new()
+        .register_material("sandstone", sandstone)
+        .register_block("sandstone", "sand", "sandstone", "sandstone");
     
     for x in -30_i32..=30 {
         for z in -30_i32..=30 {
@@ -584,14 +582,14 @@ pub fn canyon_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
             
             for y in 0..=height {
                 let material = if y == height {
-                    if canyon_depth > 0 { "stone" } else { "grass_top" }
+                    if canyon_depth > 0 { "sandstone" } else { "grass_top" }
                 } else if y > height - 3 {
                     "dirt"
                 } else {
                     "stone"
                 };
                 
-                builder = builder.add_cube(x as f32, y as f32, z as f32, 1.0, material);
+                builder = builder.add_block(x, y, z, material);
             }
         }
     }
@@ -615,35 +613,37 @@ pub fn canyon_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
         for y in 0..height {
             let width = height - y;
             for dx in 0..width {
-                builder = builder.add_cube(
-                    (x + dx * side) as f32, 
-                    (5 + y) as f32, 
-                    z as f32, 
-                    1.0, 
-                    "stone"
-                );
+                builder = builder.add_block(x + dx * side, 5 + y, z, "sandstone");
             }
         }
     }
     
-    for x in [-25, -20, -15, 15, 20, 25] {
+    for x in [-25_i32, -20, -15, 15, 20, 25] {
         for z in (-25..=25).step_by(5) {
             let base_height = ((x as f32 * 0.1).sin() * (z as f32 * 0.08).cos() * 3.0) as i32 + 5;
-            builder = builder.add_tree(x, base_height + 1, z);
+            // Los más cercanos al borde, secos
+            let species = if x.abs() == 15 && (z / 5) % 2 == 0 { TreeSpecies::Dead } else { TreeSpecies::Oak };
+            builder = builder.add_tree_species(species, x, base_height + 1, z);
         }
     }
     
+    // Un sendero por cada borde del cañón que pasa junto a los puentes
+    for side in [-1, 1] {
+        let trail: Vec<(i32, i32)> = [(12, -28), (13, -15), (12, -5), (13, 7), (12, 15), (13, 28)]
+            .iter()
+            .map(|&(x, z)| (side * x, z))
+            .collect();
+        builder = builder.add_path(&trail, 2, "sand");
+    }
+    
+    // Puentes colgantes de borde a borde del cañón
+    let surface = |x: i32, z: i32| ((x as f32 * 0.1).sin() * (z as f32 * 0.08).cos() * 3.0) as i32 + 5;
     for bridge_z in [-15, 0, 15] {
-        for dx in -7..=7 {
-            let arch_height = ((dx as f32 / 7.0).powi(2) * 4.0) as i32;
-            builder = builder.add_cube(
-                dx as f32, 
-                (8 + arch_height) as f32, 
-                bridge_z as f32, 
-                1.0, 
-                "stone"
-            );
-        }
+        builder = builder.add_bridge(
+            Vector3::new(-10.0, (surface(-10, bridge_z) + 1) as f32, bridge_z as f32),
+            Vector3::new(10.0, (surface(10, bridge_z) + 1) as f32, bridge_z as f32),
+            BridgeStyle::suspension("wood", 6.0),
+        );
     }
     
     for side in [-1, 1] {
@@ -665,15 +665,15 @@ pub fn canyon_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
     }
     
     builder
-        .add_sun(0.0, 60.0, 50.0, 5.0)
+        .add_sun(0.0, 60.0, 50.0, 1.25)
         .add_light(0.0, 20.0, -20.0, Color::new(255, 220, 180, 255), 4.0)
         .add_light(0.0, 20.0, 20.0, Color::new(180, 220, 255, 255), 4.0)
         .add_light(0.0, -5.0, 0.0, Color::new(100, 150, 255, 255), 3.0)
-        .build()
+        .build_data()
 }
 
 /// ESCENA 12: Portal Dimensional
-pub fn portal_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
+pub fn portal_scene() -> SceneData {
     let mut builder = SceneBuilder::new();
     
     // Plataforma base de obsidiana
@@ -800,12 +800,15 @@ pub fn portal_scene() -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>)
     }
     
     builder
-        .add_sun(0.0, 30.0, 20.0, 5.0)
-        .add_light(0.0, 6.0, 0.0, Color::new(200, 100, 255, 255), 8.0) // Luz del portal
+        .add_sun(0.0, 30.0, 20.0, 1.25)
+        .add_area_light(Vector3::new(0.0, 6.0, 0.0), Vector3::new(3.0, 0.0, 0.0), Vector3::new(0.0, 9.0, 0.0), Color::new(200, 100, 255, 255), 8.0) // Superficie del portal
+        .with_light_portal_pulse()
         .add_light(-8.0, 4.0, -8.0, Color::new(100, 200, 255, 255), 3.0)
         .add_light(8.0, 4.0, 8.0, Color::new(100, 255, 200, 255), 3.0)
         .add_light(0.0, 10.0, 10.0, Color::new(150, 200, 255, 255), 2.5)
-        .build()
+        .add_spotlight(Vector3::new(0.0, 14.0, 8.0), Vector3::new(0.0, 1.0, 2.0), 15.0, 28.0, Color::new(200, 100, 255, 255), 6.0) // Foco sobre la entrada del portal
+        .with_light_portal_pulse()
+        .build_data()
 }
 
 /// Configuración de escenas
@@ -813,6 +816,16 @@ pub struct SceneInfo {
     pub name: &'static str,
     pub camera_pos: Vector3,
     pub camera_target: Vector3,
+    /// Metros por bloque de la escena
+    pub world_scale: WorldScale,
+    /// Mapa de entorno HDR (.hdr/.exr) para el cielo y la luz; `None` usa el cielo procedural
+    pub environment_map: Option<&'static str>,
+    /// Ambiente de iluminación con el que se abre la escena; `None` sigue el ciclo día/noche
+    pub lighting: Option<LightingPreset>,
+    /// Proyección de la cámara inicial
+    pub projection: Projection,
+    /// Inclinación de la cámara inicial (radianes)
+    pub camera_roll: f32,
 }
 
 impl SceneInfo {
@@ -822,74 +835,151 @@ impl SceneInfo {
                 name: "Isla Flotante Básica",
                 camera_pos: Vector3::new(25.0, 0.0, 25.0),
                 camera_target: Vector3::new(0.0, 0.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             2 => SceneInfo {
                 name: "Isla con Cascadas",
                 camera_pos: Vector3::new(25.0, 0.0, 25.0),
                 camera_target: Vector3::new(0.0, 0.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             3 => SceneInfo {
                 name: "Isla con Puente Portal",
                 camera_pos: Vector3::new(25.0, 0.0, 25.0),
                 camera_target: Vector3::new(0.0, 0.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             4 => SceneInfo {
                 name: "Castillo Medieval",
                 camera_pos: Vector3::new(25.0, 15.0, 25.0),
                 camera_target: Vector3::new(0.0, 5.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             5 => SceneInfo {
                 name: "Casa con Jardín",
                 camera_pos: Vector3::new(15.0, 8.0, 15.0),
                 camera_target: Vector3::new(0.0, 2.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             6 => SceneInfo {
                 name: "Escena Simple",
                 camera_pos: Vector3::new(15.0, 8.0, 15.0),
                 camera_target: Vector3::new(0.0, 2.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             7 => SceneInfo {
                 name: "Aldea Medieval",
                 camera_pos: Vector3::new(35.0, 20.0, 35.0),
                 camera_target: Vector3::new(0.0, 3.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             8 => SceneInfo {
                 name: "Bosque Encantado",
                 camera_pos: Vector3::new(40.0, 15.0, 40.0),
                 camera_target: Vector3::new(0.0, 5.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             9 => SceneInfo {
                 name: "Archipiélago Masivo",
                 camera_pos: Vector3::new(50.0, 20.0, 50.0),
                 camera_target: Vector3::new(0.0, 10.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             10 => SceneInfo {
                 name: "Templo Antiguo",
                 camera_pos: Vector3::new(30.0, 25.0, 30.0),
                 camera_target: Vector3::new(0.0, 10.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             11 => SceneInfo {
                 name: "Cañón con Río",
                 camera_pos: Vector3::new(0.0, 25.0, 40.0),
                 camera_target: Vector3::new(0.0, 5.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
             12 => SceneInfo {
                 name: "Portal Dimensional",
                 camera_pos: Vector3::new(0.0, 8.0, 25.0),
                 camera_target: Vector3::new(0.0, 5.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },            
             _ => SceneInfo {
                 name: "Escena Desconocida",
                 camera_pos: Vector3::new(15.0, 8.0, 15.0),
                 camera_target: Vector3::new(0.0, 2.0, 0.0),
+                world_scale: WorldScale::default(),
+                environment_map: None,
+                lighting: None,
+                projection: Projection::Perspective,
+                camera_roll: 0.0,
             },
         }
     }
+
+    /// Cámara con la que se abre la escena
+    pub fn camera(&self) -> Camera {
+        Camera::new(self.camera_pos, self.camera_target, Vector3::new(0.0, 1.0, 0.0))
+            .with_projection(self.projection)
+            .with_roll(self.camera_roll)
+    }
 }
 
 /// Carga una escena según su número
-pub fn load_scene(scene_num: i32) -> (Vec<Arc<dyn RayIntersect + Send + Sync>>, Vec<Light>) {
-    match scene_num {
+pub fn load_scene(scene_num: i32) -> BuiltScene {
+    BuiltScene::new(load_scene_data(scene_num))
+}
+
+/// Objetos y luces de una escena según su número, sin construir el BVH
+pub fn load_scene_data(scene_num: i32) -> SceneData {
+    let mut scene = match scene_num {
         1 => floating_island_scene(),
         2 => floating_island_waterfalls(),
         3 => floating_island_bridge(),
@@ -903,5 +993,11 @@ pub fn load_scene(scene_num: i32) -> (Vec<Arc<dyn RayIntersect + Send + Sync>>,
         11 => canyon_scene(),
         12 => portal_scene(),
         _ => simple_scene(),
+    };
+
+    let world_scale = SceneInfo::get(scene_num).world_scale;
+    for light in &mut scene.lights {
+        light.world_scale = world_scale;
     }
+    scene
 }
\ No newline at end of file
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use crate::nbt::{self, Tag};
+use crate::partial_block::Facing;
+
+/// Estado de bloque de Minecraft: identificador y propiedades, como
+/// `minecraft:oak_stairs[facing=north,half=bottom]`
+pub struct BlockState {
+    /// Identificador sin el espacio de nombres (`oak_stairs`)
+    pub name: String,
+    pub properties: HashMap<String, String>,
+}
+
+fn strip_namespace(id: &str) -> &str {
+    id.rsplit_once(':').map_or(id, |(_, name)| name)
+}
+
+/// Construcción de Minecraft leída de un archivo `.schem` (Sponge, versiones
+/// 1 a 3) o `.nbt` (estructura del juego). Las posiciones son relativas a la
+/// esquina mínima; Y hacia arriba, el este es +X y el sur +Z, como en la escena.
+pub struct Schematic {
+    pub size: (i32, i32, i32),
+    pub palette: Vec<BlockState>,
+    /// Posición y entrada de la paleta de cada bloque (el aire incluido)
+    pub blocks: Vec<((i32, i32, i32), usize)>,
+}
+
+fn field<'a>(tag: &'a Tag, name: &str, path: &str) -> Result<&'a Tag, String> {
+    tag.get(name).ok_or_else(|| format!("{}: falta el campo `{}`", path, name))
+}
+
+fn int_field(tag: &Tag, name: &str, path: &str) -> Result<i32, String> {
+    field(tag, name, path)?
+        .as_int()
+        .map(|v| v as i32)
+        .ok_or_else(|| format!("{}: el campo `{}` no es un entero", path, name))
+}
+
+impl Schematic {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let root = nbt::read_file(path)?;
+        // Sponge v3 envuelve todo en un compuesto `Schematic`
+        let root = root.get("Schematic").unwrap_or(&root);
+
+        if root.get("Palette").is_some() || root.get("Blocks").and_then(|blocks| blocks.get("Palette")).is_some() {
+            Self::from_sponge(root, path)
+        } else if root.get("palette").is_some() || root.get("palettes").is_some() {
+            Self::from_structure(root, path)
+        } else {
+            Err(format!("{}: no es un esquema de Sponge ni una estructura de Minecraft", path))
+        }
+    }
+
+    /// Sponge: paleta `estado -> índice` y los índices de cada bloque como
+    /// varints, en orden X, luego Z y luego Y
+    fn from_sponge(root: &Tag, path: &str) -> Result<Self, String> {
+        let size = (int_field(root, "Width", path)?, int_field(root, "Height", path)?, int_field(root, "Length", path)?);
+        let (palette_tag, data) = match root.get("Blocks") {
+            Some(blocks) => (field(blocks, "Palette", path)?, field(blocks, "Data", path)?),
+            None => (field(root, "Palette", path)?, field(root, "BlockData", path)?),
+        };
+
+        let entries = palette_tag.as_compound().ok_or_else(|| format!("{}: la paleta no es un compuesto", path))?;
+        let mut palette: Vec<(i64, BlockState)> = entries
+            .iter()
+            .filter_map(|(state, index)| Some((index.as_int()?, BlockState::parse(state))))
+            .collect();
+        palette.sort_by_key(|(index, _)| *index);
+        // Los índices pueden tener huecos: se traducen a posiciones en la paleta
+        let slots: HashMap<i64, usize> = palette.iter().enumerate().map(|(slot, (index, _))| (*index, slot)).collect();
+
+        let bytes = data.as_bytes().ok_or_else(|| format!("{}: los datos de bloques no son un arreglo de bytes", path))?;
+        let (width, length) = (size.0.max(1) as usize, size.2.max(1) as usize);
+        let mut blocks = Vec::new();
+        let (mut value, mut shift) = (0i64, 0);
+        for &byte in bytes {
+            let byte = byte as u8;
+            value |= ((byte & 0x7f) as i64) << shift;
+            if byte & 0x80 != 0 {
+                shift += 7;
+                if shift > 35 {
+                    return Err(format!("{}: varint demasiado largo en los datos de bloques", path));
+                }
+                continue;
+            }
+
+            let i = blocks.len();
+            let position = ((i % width) as i32, (i / (width * length)) as i32, ((i / width) % length) as i32);
+            let slot = *slots.get(&value).ok_or_else(|| format!("{}: el bloque {} usa el índice {} que no está en la paleta", path, i, value))?;
+            blocks.push((position, slot));
+            (value, shift) = (0, 0);
+        }
+
+        Ok(Schematic {
+            size,
+            palette: palette.into_iter().map(|(_, state)| state).collect(),
+            blocks,
+        })
+    }
+
+    /// Estructura del juego: lista de estados `{Name, Properties}` y lista de
+    /// bloques `{pos, state}`; con varias paletas se usa la primera
+    fn from_structure(root: &Tag, path: &str) -> Result<Self, String> {
+        let size = field(root, "size", path)?
+            .as_ints()
+            .filter(|size| size.len() == 3)
+            .ok_or_else(|| format!("{}: `size` no son tres enteros", path))?;
+
+        let palette_tag = match root.get("palette") {
+            Some(palette) => palette,
+            None => field(root, "palettes", path)?
+                .as_list()
+                .and_then(|palettes| palettes.first())
+                .ok_or_else(|| format!("{}: `palettes` está vacío", path))?,
+        };
+        let palette = palette_tag
+            .as_list()
+            .ok_or_else(|| format!("{}: la paleta no es una lista", path))?
+            .iter()
+            .map(|entry| {
+                let name = field(entry, "Name", path)?.as_str().unwrap_or("air");
+                let properties = entry
+                    .get("Properties")
+                    .and_then(Tag::as_compound)
+                    .map(|properties| {
+                        properties
+                            .iter()
+                            .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Ok(BlockState { name: strip_namespace(name).to_string(), properties })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let mut blocks = Vec::new();
+        for block in field(root, "blocks", path)?.as_list().unwrap_or_default() {
+            let pos = field(block, "pos", path)?
+                .as_ints()
+                .filter(|pos| pos.len() == 3)
+                .ok_or_else(|| format!("{}: `pos` no son tres enteros", path))?;
+            let state = int_field(block, "state", path)? as usize;
+            if state >= palette.len() {
+                return Err(format!("{}: el estado {} no está en la paleta", path, state));
+            }
+            blocks.push(((pos[0] as i32, pos[1] as i32, pos[2] as i32), state));
+        }
+
+        Ok(Schematic {
+            size: (size[0] as i32, size[1] as i32, size[2] as i32),
+            palette,
+            blocks,
+        })
+    }
+}
+
+/// Cómo se arma un bloque de Minecraft con las piezas de la escena
+pub enum BlockPlacement {
+    Cube(&'static str),
+    Slab { material: &'static str, top: bool },
+    Stairs(&'static str, Facing),
+    Fence(&'static str),
+    Pane { material: &'static str, along_x: bool },
+    Torch,
+    /// Aire y decoración sin volumen (flores, pasto, carteles)
+    Skip,
+    /// Sin equivalente en la biblioteca de materiales: se coloca como piedra
+    Unknown,
+}
+
+/// Bloques que no se dibujan: aire y detalles sin volumen
+const SKIPPED_BLOCKS: [&str; 22] = [
+    "air", "cave_air", "void_air", "structure_void", "barrier", "light", "short_grass", "grass", "tall_grass", "fern",
+    "large_fern", "flower", "tulip", "sapling", "carpet", "button", "pressure_plate", "sign", "banner", "rail", "vine",
+    "redstone_wire",
+];
+
+/// Maderas de Minecraft, para reconocer tablas, troncos y sus variantes
+const WOOD_TYPES: [&str; 11] = [
+    "oak", "spruce", "birch", "jungle", "acacia", "dark_oak", "mangrove", "cherry", "bamboo", "crimson", "warped",
+];
+
+/// Material de la biblioteca más parecido a un bloque entero de Minecraft
+fn block_material(name: &str) -> Option<&'static str> {
+    let material = match name {
+        "grass_block" | "moss_block" => "grass_top",
+        "dirt" | "coarse_dirt" | "rooted_dirt" | "podzol" | "farmland" | "dirt_path" | "mud" => "dirt",
+        "mycelium" => "mycelium",
+        "sand" | "red_sand" | "suspicious_sand" => "sand",
+        "snow_block" | "powder_snow" => "snow",
+        "cactus" => "cactus",
+        "red_mushroom_block" | "brown_mushroom_block" => "mushroom_cap",
+        "mushroom_stem" => "mushroom_stem",
+        "water" | "bubble_column" => "water",
+        "lava" | "magma_block" => "lava",
+        "glowstone" | "sea_lantern" | "shroomlight" | "redstone_lamp" | "ochre_froglight" | "verdant_froglight"
+        | "pearlescent_froglight" => "glowstone",
+        "gold_block" | "raw_gold_block" => "gold",
+        "iron_block" | "raw_iron_block" => "silver",
+        "diamond_block" | "diamond_ore" | "deepslate_diamond_ore" => "diamond",
+        "emerald_block" | "emerald_ore" | "deepslate_emerald_ore" => "emerald",
+        "obsidian" | "crying_obsidian" => "obsidian",
+        "ice" | "packed_ice" | "blue_ice" | "frosted_ice" => "ice",
+        "nether_portal" | "end_portal" => "portal",
+        "netherrack" | "nether_wart_block" | "warped_wart_block" => "netherrack",
+        "soul_sand" | "soul_soil" => "soul_sand",
+        "bookshelf" | "crafting_table" | "barrel" | "chest" | "trapped_chest" | "note_block" | "jukebox" => "wood",
+        _ if name.contains("nether_brick") => "nether_brick",
+        _ if name.contains("glass") => "glass",
+        _ if name.ends_with("leaves") => "leaves",
+        _ if name.ends_with("_log") || name.ends_with("_wood") || name.ends_with("_planks") || name.ends_with("_stem")
+            || name.ends_with("_hyphae") => "wood",
+        _ if WOOD_TYPES.iter().any(|wood| name.starts_with(wood)) => "wood",
+        _ if ["stone", "cobble", "brick", "deepslate", "andesite", "diorite", "granite", "tuff", "basalt", "blackstone",
+              "gravel", "ore", "prismarine", "quartz", "purpur", "terracotta", "concrete"]
+            .iter()
+            .any(|part| name.contains(part)) => "stone",
+        _ => return None,
+    };
+    Some(material)
+}
+
+fn facing(state: &BlockState) -> Facing {
+    match state.property("facing") {
+        Some("south") => Facing::South,
+        Some("east") => Facing::East,
+        Some("west") => Facing::West,
+        _ => Facing::North,
+    }
+}
+
+impl BlockState {
+    /// Lee la forma de texto de los esquemas de Sponge
+    fn parse(text: &str) -> Self {
+        let (id, properties) = match text.split_once('[') {
+            Some((id, rest)) => (id, rest.trim_end_matches(']')),
+            None => (text, ""),
+        };
+        BlockState {
+            name: strip_namespace(id).to_string(),
+            properties: properties
+                .split(',')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .collect(),
+        }
+    }
+
+    fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).map(String::as_str)
+    }
+
+    /// Pieza y material con que se representa el bloque
+    pub fn placement(&self) -> BlockPlacement {
+        let name = self.name.as_str();
+        if SKIPPED_BLOCKS.iter().any(|skipped| name == *skipped || name.ends_with(&format!("_{}", skipped))) {
+            return BlockPlacement::Skip;
+        }
+        if name.ends_with("torch") || matches!(name, "lantern" | "soul_lantern") {
+            return BlockPlacement::Torch;
+        }
+
+        // Las formas parciales toman el material del bloque del que salen
+        let shaped = |suffix: &str| name.strip_suffix(suffix).map(|base| block_material(base).unwrap_or("stone"));
+        if let Some(material) = shaped("_slab") {
+            return match self.property("type") {
+                Some("double") => BlockPlacement::Cube(material),
+                Some("top") => BlockPlacement::Slab { material, top: true },
+                _ => BlockPlacement::Slab { material, top: false },
+            };
+        }
+        if let Some(material) = shaped("_stairs") {
+            return BlockPlacement::Stairs(material, facing(self));
+        }
+        if let Some(material) = shaped("_fence").or_else(|| shaped("_wall")) {
+            return BlockPlacement::Fence(material);
+        }
+        // La capa de nieve se aproxima con una losa
+        if name == "snow" {
+            return BlockPlacement::Slab { material: "snow", top: false };
+        }
+        if name.ends_with("glass_pane") || name == "iron_bars" {
+            let material = if name == "iron_bars" { "silver" } else { "glass" };
+            // Un panel que se une al este o al oeste corre a lo largo de X
+            let along_x = self.property("east") == Some("true") || self.property("west") == Some("true");
+            return BlockPlacement::Pane { material, along_x };
+        }
+
+        match block_material(name) {
+            Some(material) => BlockPlacement::Cube(material),
+            None => BlockPlacement::Unknown,
+        }
+    }
+}
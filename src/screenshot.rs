@@ -0,0 +1,139 @@
+use raylib::prelude::Vector3;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::framebuffer::Framebuffer;
+
+/// Ancho máximo de las miniaturas generadas junto a cada captura
+pub const THUMBNAIL_WIDTH: u32 = 160;
+
+/// Datos necesarios para reproducir un render a partir de su archivo
+pub struct RenderMetadata {
+    pub scene_num: i32,
+    pub scene_name: String,
+    pub camera_eye: Vector3,
+    pub camera_center: Vector3,
+    pub time_of_day: f32,
+    pub absolute_time: f32,
+    pub samples: u32,
+    pub render_time: f32,
+}
+
+impl RenderMetadata {
+    /// Pares clave/valor que se incrustan como chunks de texto en el PNG
+    fn text_entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Title", self.scene_name.clone()),
+            ("Software", format!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))),
+            ("Scene", self.scene_num.to_string()),
+            ("CameraEye", format_vec3(&self.camera_eye)),
+            ("CameraCenter", format_vec3(&self.camera_center)),
+            ("TimeOfDay", format!("{:.4}", self.time_of_day)),
+            ("AbsoluteTime", format!("{:.4}", self.absolute_time)),
+            ("Samples", self.samples.to_string()),
+            ("RenderTime", format!("{:.3}", self.render_time)),
+        ]
+    }
+
+    /// Serializa los metadatos como JSON (archivo sidecar)
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\n  \"scene\": {},\n  \"scene_name\": \"{}\",\n  \"camera_eye\": {},\n  \"camera_center\": {},\n  \"time_of_day\": {},\n  \"absolute_time\": {},\n  \"samples\": {},\n  \"render_time\": {},\n  \"version\": \"{}\"\n}}\n",
+            self.scene_num,
+            escape_json(&self.scene_name),
+            json_vec3(&self.camera_eye),
+            json_vec3(&self.camera_center),
+            self.time_of_day,
+            self.absolute_time,
+            self.samples,
+            self.render_time,
+            env!("CARGO_PKG_VERSION"),
+        )
+    }
+}
+
+fn format_vec3(v: &Vector3) -> String {
+    format!("{:.4} {:.4} {:.4}", v.x, v.y, v.z)
+}
+
+fn json_vec3(v: &Vector3) -> String {
+    format!("[{}, {}, {}]", v.x, v.y, v.z)
+}
+
+pub fn escape_json(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Guarda el framebuffer como PNG con metadatos incrustados y una miniatura
+///
+/// # Argumentos
+/// * `path` - Ruta del PNG principal (la miniatura se guarda como `<nombre>_thumb.png`)
+/// * `write_sidecar` - Si es verdadero, escribe también `<nombre>.json`
+pub fn save_render(
+    framebuffer: &Framebuffer,
+    path: &str,
+    metadata: &RenderMetadata,
+    write_sidecar: bool,
+) -> Result<(), String> {
+    let file_path = Path::new(path);
+    if let Some(parent) = file_path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("No se pudo crear directorio {}: {}", parent.display(), e))?;
+    }
+
+    let rgba = framebuffer.to_rgba();
+    write_png(file_path, framebuffer.width, framebuffer.height, &rgba, metadata)?;
+
+    let image = image::RgbaImage::from_raw(framebuffer.width, framebuffer.height, rgba)
+        .ok_or_else(|| format!("Error creando imagen para {}", path))?;
+    let thumb_height = (framebuffer.height * THUMBNAIL_WIDTH / framebuffer.width.max(1)).max(1);
+    let thumbnail = image::imageops::thumbnail(&image, THUMBNAIL_WIDTH, thumb_height);
+    let thumb_path = file_path.with_file_name(format!("{}_thumb.png", file_stem(file_path)));
+    write_png(&thumb_path, thumbnail.width(), thumbnail.height(), thumbnail.as_raw(), metadata)?;
+
+    if write_sidecar {
+        let json_path = file_path.with_extension("json");
+        std::fs::write(&json_path, metadata.to_json())
+            .map_err(|e| format!("Error guardando {}: {}", json_path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "render".to_string())
+}
+
+fn write_png(
+    path: &Path,
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    metadata: &RenderMetadata,
+) -> Result<(), String> {
+    let file = File::create(path)
+        .map_err(|e| format!("Error creando {}: {}", path.display(), e))?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    // iTXt admite UTF-8 (los nombres de escena llevan acentos)
+    for (key, value) in metadata.text_entries() {
+        encoder
+            .add_itxt_chunk(key.to_string(), value)
+            .map_err(|e| format!("Error agregando metadato {}: {}", key, e))?;
+    }
+
+    let mut writer = encoder
+        .write_header()
+        .map_err(|e| format!("Error escribiendo {}: {}", path.display(), e))?;
+    writer
+        .write_image_data(rgba)
+        .map_err(|e| format!("Error escribiendo {}: {}", path.display(), e))?;
+
+    Ok(())
+}
@@ -1,4 +1,43 @@
 use raylib::prelude::Vector3;
+use crate::backdrop::BackdropView;
+use crate::material::{limit_radiance, srgb_to_linear};
+use crate::cubemap::Cubemap;
+use crate::texture::EnvironmentMap;
+use crate::lighting::LightingPreset;
+
+/// Radiancia del disco solar (puede superar 1.0; se limita según el rayo)
+const SUN_DISK_RADIANCE: f32 = 12.0;
+/// Radiancia del disco lunar
+const MOON_DISK_RADIANCE: f32 = 1.5;
+/// Coseno del radio angular del sol y la luna (~2°)
+const SUN_DISK_COS: f32 = 0.9994;
+const MOON_DISK_COS: f32 = 0.9996;
+
+/// Límite de brillo del sol/luna para rayos primarios (la pantalla satura en 1.0)
+pub const PRIMARY_DISK_LIMIT: f32 = 1.0;
+/// Límite de brillo del sol/luna visto en reflejos de superficies muy pulidas
+pub const SECONDARY_DISK_LIMIT: f32 = 8.0;
+
+/// Dirección hacia el sol; coincide con la luz del ciclo día/noche y con los
+/// colores del cielo: sale a las 0.25, está en lo alto a las 0.5 y se pone a las 0.75.
+/// La luna está siempre en la dirección opuesta.
+pub fn sun_direction(time_of_day: f32) -> Vector3 {
+    let angle = (time_of_day - 0.25) * std::f32::consts::PI * 2.0;
+    Vector3::new(angle.cos(), angle.sin(), 0.0)
+}
+
+/// Qué tan de noche es (0 = sol sobre el horizonte, 1 = sol bien abajo).
+/// Controla las estrellas y la luz de la luna.
+pub fn night_factor(time_of_day: f32) -> f32 {
+    let t = ((-sun_direction(time_of_day).y + 0.05) / 0.3).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Límite de brillo del sol/luna para un rayo reflejado por un material con
+/// exponente especular `specular`: los materiales ásperos no muestran un disco nítido
+pub fn reflection_disk_limit(specular: f32) -> f32 {
+    SECONDARY_DISK_LIMIT * specular / (specular + 50.0)
+}
 
 /// Configuración de colores para diferentes momentos del día
 pub struct SkyColors {
@@ -7,8 +46,18 @@ pub struct SkyColors {
     pub cloud_color: Vector3,
 }
 
-/// Genera colores del cielo según la hora del día
+/// Colores del cielo según la hora del día, en espacio lineal
 pub fn get_sky_colors(time_of_day: f32) -> SkyColors {
+    let palette = sky_palette(time_of_day);
+    SkyColors {
+        zenith: srgb_to_linear(palette.zenith),
+        horizon: srgb_to_linear(palette.horizon),
+        cloud_color: srgb_to_linear(palette.cloud_color),
+    }
+}
+
+/// Colores del cielo según la hora del día, elegidos a ojo (en sRGB)
+fn sky_palette(time_of_day: f32) -> SkyColors {
     if time_of_day < 0.2 {
         // Noche (0.0 - 0.2)
         let t = time_of_day / 0.2;
@@ -139,6 +188,57 @@ fn cloud_noise(x: f32, y: f32, z: f32, octaves: i32) -> f32 {
     (value / max_value) * 0.5 + 0.5
 }
 
+/// Escala del ruido de nubes sobre el plano proyectado
+const CLOUD_SCALE: f32 = 3.0;
+/// Densidad a partir de la cual se forman nubes
+const CLOUD_THRESHOLD: f32 = 0.5;
+/// Altura (en bloques) del plano de nubes que proyecta sombras sobre el terreno
+const CLOUD_HEIGHT: f32 = 60.0;
+/// Fracción de la luz del sol que bloquea una nube densa
+const CLOUD_SHADOW_STRENGTH: f32 = 0.6;
+/// Radio (en coordenadas de ruido) del promedio que suaviza el borde de las sombras
+const CLOUD_SHADOW_SOFTNESS: f32 = 0.08;
+
+/// Densidad de nubes en las coordenadas (x, z) del plano de nubes, y el detalle fino
+/// (usado para variar el color). Las nubes cambian con la hora del día.
+#[inline]
+fn cloud_density(x: f32, z: f32, time_of_day: f32) -> (f32, f32) {
+    // Múltiples capas de ruido para volumen
+    let large_clouds = cloud_noise(x, z, time_of_day * 0.1, 3);
+    let medium_detail = cloud_noise(x * 2.5, z * 2.5, time_of_day * 0.15, 2);
+    let fine_detail = cloud_noise(x * 7.0, z * 7.0, time_of_day * 0.2, 1);
+
+    // Combinar capas
+    (large_clouds * 0.6 + medium_detail * 0.25 + fine_detail * 0.15, fine_detail)
+}
+
+/// Fracción de la luz del sol (o la luna) que atraviesa las nubes hasta `point`.
+/// `light_dir` apunta hacia la luz. Usa el mismo ruido que el cielo, proyectado
+/// en un plano a `CLOUD_HEIGHT` bloques, así que las sombras se mueven con las nubes.
+pub fn cloud_shadow(point: Vector3, light_dir: Vector3, time_of_day: f32) -> f32 {
+    if light_dir.y <= 0.05 || point.y >= CLOUD_HEIGHT {
+        return 1.0;
+    }
+
+    let along = (CLOUD_HEIGHT - point.y) / light_dir.y;
+    let hit = point + light_dir * along;
+    // El cielo ve el punto (x, H, z) del plano en la dirección (x, H, z) desde el origen
+    let x = hit.x / CLOUD_HEIGHT * CLOUD_SCALE;
+    let z = hit.z / CLOUD_HEIGHT * CLOUD_SCALE;
+
+    let offsets = [(0.0, 0.0), (1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)];
+    let coverage: f32 = offsets
+        .iter()
+        .map(|(ox, oz)| {
+            let (density, _) = cloud_density(x + ox * CLOUD_SHADOW_SOFTNESS, z + oz * CLOUD_SHADOW_SOFTNESS, time_of_day);
+            ((density - CLOUD_THRESHOLD) / (1.0 - CLOUD_THRESHOLD)).clamp(0.0, 1.0).sqrt()
+        })
+        .sum::<f32>()
+        / offsets.len() as f32;
+
+    1.0 - coverage * CLOUD_SHADOW_STRENGTH
+}
+
 /// Genera nubes volumétricas realistas
 fn generate_clouds(dir: Vector3, time_of_day: f32, cloud_color: Vector3) -> (f32, Vector3) {
     if dir.y <= 0.1 {
@@ -154,22 +254,14 @@ fn generate_clouds(dir: Vector3, time_of_day: f32, cloud_color: Vector3) -> (f32
     }
 
     // Coordenadas normalizadas para sampling
-    let cloud_scale = 3.0;
-    let x = dir.x / (dir.y + 0.1) * cloud_scale;
-    let z = dir.z / (dir.y + 0.1) * cloud_scale;
-
-    // Múltiples capas de ruido para volumen
-    let large_clouds = cloud_noise(x, z, time_of_day * 0.1, 3);
-    let medium_detail = cloud_noise(x * 2.5, z * 2.5, time_of_day * 0.15, 2);
-    let fine_detail = cloud_noise(x * 7.0, z * 7.0, time_of_day * 0.2, 1);
+    let x = dir.x / (dir.y + 0.1) * CLOUD_SCALE;
+    let z = dir.z / (dir.y + 0.1) * CLOUD_SCALE;
 
-    // Combinar capas
-    let cloud_density = large_clouds * 0.6 + medium_detail * 0.25 + fine_detail * 0.15;
+    let (cloud_density, fine_detail) = cloud_density(x, z, time_of_day);
 
     // Threshold para formar nubes definidas
-    let threshold = 0.5;
-    let cloud_alpha = if cloud_density > threshold {
-        ((cloud_density - threshold) / (1.0 - threshold)).powf(1.5) * altitude_factor
+    let cloud_alpha = if cloud_density > CLOUD_THRESHOLD {
+        ((cloud_density - CLOUD_THRESHOLD) / (1.0 - CLOUD_THRESHOLD)).powf(1.5) * altitude_factor
     } else {
         0.0
     };
@@ -181,15 +273,18 @@ fn generate_clouds(dir: Vector3, time_of_day: f32, cloud_color: Vector3) -> (f32
     (cloud_alpha.clamp(0.0, 0.7), varied_cloud_color)
 }
 
-/// Genera estrellas en el cielo nocturno
+/// Genera estrellas en el cielo nocturno. El campo de estrellas gira con el
+/// sol y la luna, y se desvanece al clarear.
 fn generate_stars(dir: Vector3, time_of_day: f32) -> Vector3 {
-    if time_of_day > 0.2 && time_of_day < 0.85 {
+    let night_intensity = night_factor(time_of_day);
+    if night_intensity <= 0.0 || dir.y <= 0.0 {
         return Vector3::zero();
     }
 
-    if dir.y <= 0.0 {
-        return Vector3::zero();
-    }
+    // Misma rotación que el sol (alrededor del eje Z)
+    let angle = -(time_of_day - 0.25) * std::f32::consts::PI * 2.0;
+    let (sin, cos) = angle.sin_cos();
+    let dir = Vector3::new(dir.x * cos - dir.y * sin, dir.x * sin + dir.y * cos, dir.z);
 
     // Patrón de estrellas usando múltiples frecuencias
     let star_pattern = ((dir.x * 50.0).sin() * (dir.y * 50.0).cos() * (dir.z * 50.0).sin()).abs();
@@ -202,13 +297,6 @@ fn generate_stars(dir: Vector3, time_of_day: f32) -> Vector3 {
     if combined_pattern > star_threshold {
         let star_brightness = ((combined_pattern - star_threshold) / (1.0 - star_threshold)).powf(2.0);
         
-        // Intensidad según hora de la noche
-        let night_intensity = if time_of_day < 0.2 {
-            (0.2 - time_of_day) / 0.2
-        } else {
-            (time_of_day - 0.85) / 0.15
-        };
-        
         // Colores variados para las estrellas
         let star_color = if star_pattern > star_pattern2 {
             Vector3::new(1.0, 1.0, 0.9) // Blanco amarillento
@@ -222,8 +310,40 @@ fn generate_stars(dir: Vector3, time_of_day: f32) -> Vector3 {
     Vector3::zero()
 }
 
-/// Skybox del Overworld con nubes y estrellas
-pub fn overworld_sky(dir: Vector3, time_of_day: f32) -> Vector3 {
+/// Discos del sol y la luna, con un halo suave alrededor del sol
+fn celestial_disks(dir: Vector3, time_of_day: f32, disk_limit: f32) -> Vector3 {
+    let sun_dir = sun_direction(time_of_day);
+    let mut color = Vector3::zero();
+
+    // Sol: se tiñe de naranja cerca del horizonte
+    let sun_visibility = (sun_dir.y * 20.0).clamp(0.0, 1.0);
+    if sun_visibility > 0.0 {
+        let cos_angle = dir.dot(sun_dir);
+        let elevation = (sun_dir.y / 0.3).clamp(0.0, 1.0);
+        let sun_color = Vector3::new(1.0, 0.6, 0.3) * (1.0 - elevation)
+            + Vector3::new(1.0, 0.97, 0.9) * elevation;
+
+        if cos_angle > SUN_DISK_COS {
+            color += limit_radiance(sun_color * SUN_DISK_RADIANCE, disk_limit) * sun_visibility;
+        }
+        color += sun_color * cos_angle.max(0.0).powf(400.0) * 0.4 * sun_visibility;
+    }
+
+    // Luna
+    let moon_dir = -sun_dir;
+    let moon_visibility = (moon_dir.y * 20.0).clamp(0.0, 1.0);
+    if moon_visibility > 0.0 && dir.dot(moon_dir) > MOON_DISK_COS {
+        let moon_color = Vector3::new(0.85, 0.9, 1.0) * MOON_DISK_RADIANCE;
+        color += limit_radiance(moon_color, disk_limit) * moon_visibility;
+    }
+
+    color
+}
+
+/// Skybox del Overworld con sol, luna, nubes y estrellas
+///
+/// `disk_limit` es el brillo máximo de los discos del sol y la luna para este rayo
+pub fn overworld_sky(dir: Vector3, time_of_day: f32, disk_limit: f32) -> Vector3 {
     let d = dir.normalized();
     let colors = get_sky_colors(time_of_day);
 
@@ -237,13 +357,14 @@ pub fn overworld_sky(dir: Vector3, time_of_day: f32) -> Vector3 {
         colors.horizon * (1.0 - t) + below_horizon * t
     };
 
+    // Sol, luna y estrellas detrás de las nubes
+    let sky_with_disks = vertical_gradient
+        + celestial_disks(d, time_of_day, disk_limit)
+        + generate_stars(d, time_of_day);
+
     // Agregar nubes volumétricas
     let (cloud_alpha, cloud_color) = generate_clouds(d, time_of_day, colors.cloud_color);
-    let sky_with_clouds = vertical_gradient * (1.0 - cloud_alpha) + cloud_color * cloud_alpha;
-
-    // Agregar estrellas en la noche
-    let stars = generate_stars(d, time_of_day);
-    sky_with_clouds + stars
+    sky_with_disks * (1.0 - cloud_alpha) + cloud_color * cloud_alpha
 }
 
 /// Genera ruido para efectos del Nether
@@ -277,8 +398,13 @@ fn ash_particles(dir: Vector3, time: f32) -> f32 {
     0.0
 }
 
-/// Skybox del Nether con efectos especiales
-pub fn nether_sky(dir: Vector3, time: f32) -> Vector3{
+/// Skybox del Nether con efectos especiales, en espacio lineal
+pub fn nether_sky(dir: Vector3, time: f32) -> Vector3 {
+    srgb_to_linear(nether_palette(dir, time))
+}
+
+/// Colores del Nether elegidos a ojo (en sRGB)
+fn nether_palette(dir: Vector3, time: f32) -> Vector3 {
     let d = dir.normalized();
     
     // Techo rocoso con grietas de lava
@@ -354,10 +480,180 @@ pub fn nether_sky(dir: Vector3, time: f32) -> Vector3{
 }
 
 /// Función principal que selecciona el skybox apropiado
-pub fn sky_color(dir: Vector3, is_nether: bool, time_of_day: f32, time: f32) -> Vector3 {
+pub fn sky_color(dir: Vector3, is_nether: bool, time_of_day: f32, time: f32, disk_limit: f32) -> Vector3 {
     if is_nether {
         nether_sky(dir, time)
     } else {
-        overworld_sky(dir, time_of_day)
+        overworld_sky(dir, time_of_day, disk_limit)
+    }
+}
+/// Fracción de la luz del cielo que llega como luz ambiental
+const AMBIENT_STRENGTH: f32 = 0.25;
+/// Luz ambiental mínima (noches sin luna, interiores)
+const AMBIENT_FLOOR: f32 = 0.02;
+/// Direcciones del cielo muestreadas por cada eje
+const AMBIENT_SAMPLES: usize = 48;
+
+/// Luz ambiental tomada del cielo, precalculada una vez por frame como un
+/// "ambient cube": la irradiancia del hemisferio alrededor de cada eje (±X, ±Y, ±Z)
+pub struct SkyAmbient {
+    overworld: [Vector3; 6],
+    nether: [Vector3; 6],
+}
+
+impl SkyAmbient {
+    pub fn new(time_of_day: f32, time: f32) -> Self {
+        SkyAmbient {
+            overworld: Self::ambient_cube(|dir| overworld_sky(dir, time_of_day, 0.0)),
+            nether: Self::ambient_cube(|dir| nether_sky(dir, time)),
+        }
     }
-}
\ No newline at end of file
+
+    fn ambient_cube(sky: impl Fn(Vector3) -> Vector3) -> [Vector3; 6] {
+        let axes = [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, -1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            Vector3::new(0.0, 0.0, -1.0),
+        ];
+        axes.map(|axis| hemisphere_irradiance(axis, &sky))
+    }
+
+    /// Luz ambiental para una superficie con normal `normal`
+    pub fn irradiance(&self, normal: &Vector3, is_nether: bool) -> Vector3 {
+        let cube = if is_nether { &self.nether } else { &self.overworld };
+        let n2 = Vector3::new(normal.x * normal.x, normal.y * normal.y, normal.z * normal.z);
+
+        let x = if normal.x >= 0.0 { cube[0] } else { cube[1] };
+        let y = if normal.y >= 0.0 { cube[2] } else { cube[3] };
+        let z = if normal.z >= 0.0 { cube[4] } else { cube[5] };
+
+        let ambient = (x * n2.x + y * n2.y + z * n2.z) * AMBIENT_STRENGTH;
+        Vector3::new(
+            ambient.x.max(AMBIENT_FLOOR),
+            ambient.y.max(AMBIENT_FLOOR),
+            ambient.z.max(AMBIENT_FLOOR),
+        )
+    }
+}
+
+/// Promedio del cielo sobre el hemisferio de `axis` con peso coseno
+/// (direcciones en espiral de Fibonacci, siempre las mismas para evitar parpadeo)
+fn hemisphere_irradiance(axis: Vector3, sky: &impl Fn(Vector3) -> Vector3) -> Vector3 {
+    let helper = if axis.y.abs() < 0.9 { Vector3::new(0.0, 1.0, 0.0) } else { Vector3::new(1.0, 0.0, 0.0) };
+    let tangent = helper.cross(axis).normalized();
+    let bitangent = axis.cross(tangent);
+    let golden_angle = std::f32::consts::PI * (3.0 - 5.0_f32.sqrt());
+
+    let mut sum = Vector3::zero();
+    for i in 0..AMBIENT_SAMPLES {
+        // Con r = sqrt(u) sobre el disco, la proyección al hemisferio queda con peso coseno
+        let u = (i as f32 + 0.5) / AMBIENT_SAMPLES as f32;
+        let r = u.sqrt();
+        let phi = i as f32 * golden_angle;
+        let height = (1.0 - u).sqrt();
+        let dir = tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + axis * height;
+        sum += sky(dir);
+    }
+    sum / AMBIENT_SAMPLES as f32
+}
+
+/// Todo lo que ven los rayos que no golpean geometría durante un frame:
+/// cielo (o imagen de fondo) y luz ambiental
+pub struct Environment<'a> {
+    pub time_of_day: f32,
+    pub absolute_time: f32,
+    pub ambient: SkyAmbient,
+    pub backdrop: Option<BackdropView<'a>>,
+    /// Altura del plano invisible que recibe sombras sobre el fondo (`None` = desactivado)
+    pub shadow_catcher: Option<f32>,
+    /// Brillo actual del portal (materiales con `emissive_pulse`)
+    pub portal_pulse: f32,
+    /// Las nubes del cielo procedural proyectan sombras sobre el terreno
+    pub cloud_shadows: bool,
+    /// Mapa HDR que reemplaza al cielo del mundo normal (el Nether conserva el suyo)
+    pub environment_map: Option<&'a EnvironmentMap>,
+    /// Cielo de seis caras elegido por el usuario; tiene prioridad sobre el mapa HDR
+    pub cubemap: Option<&'a Cubemap>,
+    /// Tinte del cielo procedural del mundo normal (ambientes de iluminación)
+    pub sky_tint: Vector3,
+}
+
+impl<'a> Environment<'a> {
+    pub fn new(time_of_day: f32, absolute_time: f32) -> Self {
+        Environment {
+            time_of_day,
+            absolute_time,
+            ambient: SkyAmbient::new(time_of_day, absolute_time),
+            backdrop: None,
+            shadow_catcher: None,
+            portal_pulse: 1.0,
+            cloud_shadows: true,
+            environment_map: None,
+            cubemap: None,
+            sky_tint: Vector3::one(),
+        }
+    }
+
+    /// Aplica un ambiente de iluminación: tiñe el cielo procedural y la luz
+    /// ambiental del mundo normal (la hora ya viene fijada por el ambiente)
+    pub fn with_lighting(mut self, preset: LightingPreset) -> Self {
+        let tint = preset.ambient_tint();
+        for side in &mut self.ambient.overworld {
+            *side *= tint;
+        }
+        self.sky_tint = preset.sky_tint();
+        self.cloud_shadows &= preset.cloud_shadows();
+        self
+    }
+
+    /// Usa `map` como cielo y como fuente de la luz ambiental del mundo normal
+    pub fn with_environment_map(mut self, map: &'a EnvironmentMap) -> Self {
+        self.ambient.overworld = SkyAmbient::ambient_cube(|dir| map.sample(dir));
+        self.environment_map = Some(map);
+        self.cloud_shadows = false;
+        self
+    }
+
+    /// Usa el cubemap como cielo del mundo normal (también para la luz ambiental)
+    pub fn with_cubemap(mut self, cubemap: &'a Cubemap) -> Self {
+        self.ambient.overworld = SkyAmbient::ambient_cube(|dir| cubemap.sample(dir));
+        self.cubemap = Some(cubemap);
+        self.cloud_shadows = false;
+        self
+    }
+
+    pub fn with_backdrop(mut self, backdrop: BackdropView<'a>, shadow_catcher: Option<f32>) -> Self {
+        self.backdrop = Some(backdrop);
+        self.shadow_catcher = shadow_catcher;
+        self
+    }
+
+    /// Atenuación por nubes de la luz `light_dir` en `point` (1.0 = sin nubes).
+    /// Solo afecta al mundo normal y a luces marcadas como luz del cielo.
+    pub fn cloud_shadow(&self, point: Vector3, light_dir: Vector3) -> f32 {
+        if !self.cloud_shadows || point.y < 0.0 {
+            return 1.0;
+        }
+        cloud_shadow(point, light_dir, self.time_of_day)
+    }
+
+    /// Color para un rayo que no golpeó nada; todos los rayos (primarios y
+    /// secundarios) pasan por aquí
+    pub fn background(&self, dir: Vector3, is_nether: bool, disk_limit: f32) -> Vector3 {
+        if let Some(color) = self.backdrop.as_ref().and_then(|b| b.sample(dir)) {
+            return color;
+        }
+        if let (Some(cubemap), false) = (self.cubemap, is_nether) {
+            return cubemap.sample(dir);
+        }
+        if let (Some(map), false) = (self.environment_map, is_nether) {
+            // El mapa ya trae sol y nubes; solo se limita el brillo igual que los discos
+            return limit_radiance(map.sample(dir), disk_limit.max(PRIMARY_DISK_LIMIT));
+        }
+        let color = sky_color(dir, is_nether, self.time_of_day, self.absolute_time, disk_limit);
+        if is_nether { color } else { color * self.sky_tint }
+    }
+}
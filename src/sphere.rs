@@ -0,0 +1,65 @@
+use raylib::prelude::Vector3;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+use crate::material::Material;
+
+/// Esfera con mapeo UV esférico
+pub struct Sphere {
+    pub center: Vector3,
+    pub radius: f32,
+    pub material: Arc<Material>,
+}
+
+impl Sphere {
+    pub fn new(center: Vector3, radius: f32, material: Arc<Material>) -> Self {
+        Sphere {
+            center,
+            radius,
+            material,
+        }
+    }
+
+    #[inline]
+    fn get_uv(&self, normal: &Vector3) -> (f32, f32) {
+        let u = 0.5 + normal.z.atan2(normal.x) / (2.0 * PI);
+        let v = 0.5 - normal.y.clamp(-1.0, 1.0).asin() / PI;
+        (u, v)
+    }
+}
+
+impl RayIntersect for Sphere {
+    #[inline]
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let oc = *ray_origin - self.center;
+        let a = ray_direction.dot(*ray_direction);
+        let half_b = oc.dot(*ray_direction);
+        let c = oc.dot(oc) - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+
+        if discriminant < 0.0 {
+            return Intersect::empty();
+        }
+
+        let sqrt_d = discriminant.sqrt();
+        let t_near = (-half_b - sqrt_d) / a;
+        let t_far = (-half_b + sqrt_d) / a;
+        let t = if t_near > 1e-4 { t_near } else { t_far };
+
+        if t <= 1e-4 {
+            return Intersect::empty();
+        }
+
+        let point = *ray_origin + *ray_direction * t;
+        let normal = (point - self.center) / self.radius;
+        let (u, v) = self.get_uv(&normal);
+
+        Intersect::new(point, normal, t, Arc::clone(&self.material), u, v)
+    }
+
+    fn get_bounds(&self) -> AABB {
+        let r = Vector3::new(self.radius, self.radius, self.radius);
+        AABB::new(self.center - r, self.center + r)
+    }
+}
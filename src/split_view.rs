@@ -0,0 +1,43 @@
+use raylib::prelude::Vector3;
+
+use crate::camera::Camera;
+use crate::renderer::{RenderSettings, Renderer};
+
+/// Ancho (en pixels) de la línea oscura entre las dos vistas
+const DIVIDER_WIDTH: usize = 2;
+const DIVIDER_COLOR: Vector3 = Vector3 { x: 0.02, y: 0.02, z: 0.02 };
+
+/// Cámara del Nether: la misma vista reflejada en el plano y = 0, así la isla
+/// de arriba y su reflejo de abajo se ven desde el mismo ángulo
+pub fn nether_camera(camera: &Camera) -> Camera {
+    let mirror = |v: Vector3| Vector3::new(v.x, -v.y, v.z);
+    Camera::new(mirror(camera.eye), mirror(camera.center), camera.world_up)
+        .with_projection(camera.projection)
+        .with_roll(camera.roll)
+}
+
+/// Traza `cameras[0]` en la mitad izquierda y `cameras[1]` en la derecha de
+/// una imagen de `width`×`height`, con una línea oscura entre ambas. Cada
+/// mitad usa su propia relación de aspecto, así no se deforma.
+pub fn render_split(renderer: &Renderer, cameras: [&Camera; 2], width: u32, height: u32) -> Vec<Vector3> {
+    let (width, height) = (width as usize, height as usize);
+    let left_width = width / 2;
+    let widths = [left_width, width - left_width];
+    let halves = [0, 1].map(|i| {
+        let settings = RenderSettings::new(widths[i] as u32, height as u32);
+        renderer.render_radiance(cameras[i], &settings)
+    });
+
+    let mut image = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for (half, &half_width) in halves.iter().zip(&widths) {
+            image.extend_from_slice(&half[y * half_width..(y + 1) * half_width]);
+        }
+    }
+
+    let divider = left_width.saturating_sub(DIVIDER_WIDTH / 2)..(left_width + DIVIDER_WIDTH / 2).min(width);
+    for y in 0..height {
+        image[y * width + divider.start..y * width + divider.end].fill(DIVIDER_COLOR);
+    }
+    image
+}
@@ -0,0 +1,100 @@
+use crate::ray_intersect::BvhMetrics;
+use crate::screenshot::escape_json;
+
+/// Estadísticas de una escena mientras estuvo cargada
+pub struct SceneStats {
+    pub scene_num: i32,
+    pub scene_name: String,
+    pub object_count: usize,
+    pub light_count: usize,
+    pub bvh: BvhMetrics,
+    /// Segundos para generar la escena
+    pub load_time: f32,
+    /// Segundos para construir el BVH
+    pub bvh_build_time: f32,
+    /// Segundos de cada frame renderizado
+    pub frame_times: Vec<f32>,
+}
+
+impl SceneStats {
+    fn to_json(&self) -> String {
+        let frames: Vec<String> = self.frame_times.iter().map(|t| format!("{:.6}", t)).collect();
+        let mean = if self.frame_times.is_empty() {
+            0.0
+        } else {
+            self.frame_times.iter().sum::<f32>() / self.frame_times.len() as f32
+        };
+
+        format!(
+            "    {{\n      \"scene\": {},\n      \"scene_name\": \"{}\",\n      \"objects\": {},\n      \"lights\": {},\n      \"bvh\": {{ \"nodes\": {}, \"leaves\": {}, \"max_depth\": {}, \"mean_leaf_depth\": {:.3} }},\n      \"load_time\": {:.6},\n      \"bvh_build_time\": {:.6},\n      \"mean_frame_time\": {:.6},\n      \"frame_times\": [{}]\n    }}",
+            self.scene_num,
+            escape_json(&self.scene_name),
+            self.object_count,
+            self.light_count,
+            self.bvh.node_count,
+            self.bvh.leaf_count,
+            self.bvh.max_depth,
+            self.bvh.mean_leaf_depth,
+            self.load_time,
+            self.bvh_build_time,
+            mean,
+            frames.join(", "),
+        )
+    }
+}
+
+/// Junta las estadísticas de cada escena cargada y las escribe como JSON
+/// (opción `--stats-out <archivo>`) para seguir el rendimiento entre versiones
+pub struct StatsRecorder {
+    path: String,
+    scenes: Vec<SceneStats>,
+}
+
+impl StatsRecorder {
+    pub fn new(path: &str) -> Self {
+        StatsRecorder {
+            path: path.to_string(),
+            scenes: Vec::new(),
+        }
+    }
+
+    /// Empieza a registrar una escena recién cargada
+    pub fn begin_scene(&mut self, stats: SceneStats) {
+        self.scenes.push(stats);
+    }
+
+    pub fn record_frame(&mut self, seconds: f32) {
+        if let Some(scene) = self.scenes.last_mut() {
+            scene.frame_times.push(seconds);
+        }
+    }
+
+    /// Escribe todo lo registrado hasta ahora (se reescribe el archivo completo)
+    pub fn write(&self) -> Result<(), String> {
+        let scenes: Vec<String> = self.scenes.iter().map(|s| s.to_json()).collect();
+        let text = format!(
+            "{{\n  \"version\": \"{}\",\n  \"scenes\": [\n{}\n  ]\n}}\n",
+            env!("CARGO_PKG_VERSION"),
+            scenes.join(",\n"),
+        );
+        std::fs::write(&self.path, text).map_err(|e| format!("Error guardando {}: {}", self.path, e))
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// Lee `--stats-out <archivo>` (o `--stats-out=<archivo>`) de los argumentos
+pub fn stats_out_arg(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--stats-out" {
+            return iter.next().cloned();
+        }
+        if let Some(path) = arg.strip_prefix("--stats-out=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
@@ -0,0 +1,102 @@
+use serde::Deserialize;
+
+use crate::noise::Perlin;
+
+/// Cuánto sigue subiendo el terreno por encima de la altura de las mesetas
+const PLATEAU_FLATNESS: f32 = 0.15;
+/// El ruido por octavas casi nunca pasa de ±0.6: se estira para que las
+/// colinas usen toda la amplitud
+const FBM_CONTRAST: f32 = 1.8;
+/// Tamaño relativo de las zonas con acantilados respecto de las colinas
+const CLIFF_REGION_SCALE: f32 = 0.5;
+
+/// Forma del terreno que genera `SceneBuilder::add_terrain`
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TerrainParams {
+    pub seed: u32,
+    /// Altura de las partes más bajas del terreno
+    pub base_height: i32,
+    /// Diferencia entre las partes más bajas y las más altas, en bloques
+    pub amplitude: f32,
+    /// Tamaño aproximado de las colinas más grandes, en bloques
+    pub feature_size: f32,
+    /// Capas de ruido: más octavas agregan detalle a las colinas
+    pub octaves: u32,
+    /// Altura de los escalones de los acantilados; 0 los desactiva
+    pub cliff_step: f32,
+    /// Fracción de `amplitude` (0-1) a partir de la que las cimas se aplanan en mesetas
+    pub plateau_height: Option<f32>,
+    /// Los valles por debajo de esta altura se llenan de agua
+    pub water_level: Option<i32>,
+}
+
+impl TerrainParams {
+    /// Colinas suaves con algunos acantilados y mesetas
+    pub fn new(seed: u32) -> Self {
+        TerrainParams {
+            seed,
+            base_height: 0,
+            amplitude: 10.0,
+            feature_size: 32.0,
+            octaves: 4,
+            cliff_step: 3.0,
+            plateau_height: Some(0.7),
+            water_level: None,
+        }
+    }
+}
+
+impl Default for TerrainParams {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Mapa de alturas de ruido de Perlin para unos `TerrainParams`
+pub struct Terrain {
+    params: TerrainParams,
+    perlin: Perlin,
+}
+
+impl Terrain {
+    pub fn new(params: TerrainParams) -> Self {
+        Terrain {
+            params,
+            perlin: Perlin::new(params.seed),
+        }
+    }
+
+    /// Altura del bloque de la superficie en la columna (x, z)
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        let params = &self.params;
+        let (fx, fz) = (x as f32 / params.feature_size, z as f32 / params.feature_size);
+
+        // Colinas: el ruido por octavas llevado a [0, amplitude]
+        let hills = (self.perlin.fbm2(fx, fz, params.octaves) * FBM_CONTRAST).clamp(-1.0, 1.0);
+        let mut height = (hills * 0.5 + 0.5) * params.amplitude;
+
+        if let Some(plateau) = params.plateau_height.map(|fraction| fraction * params.amplitude)
+            && height > plateau
+        {
+            height = plateau + (height - plateau) * PLATEAU_FLATNESS;
+        }
+
+        // Acantilados: en algunas regiones el terreno sube en escalones con
+        // paredes casi verticales en vez de pendientes
+        if params.cliff_step > 0.0 {
+            let region = self.perlin.noise2(fx * CLIFF_REGION_SCALE + 31.7, fz * CLIFF_REGION_SCALE - 17.3);
+            let cliffs = smoothstep(0.0, 0.3, region);
+            let steps = height / params.cliff_step;
+            let terraced = (steps.floor() + smoothstep(0.4, 0.6, steps.fract())) * params.cliff_step;
+            height += (terraced - height) * cliffs;
+        }
+
+        params.base_height + height.round() as i32
+    }
+}
@@ -1,24 +1,585 @@
 use raylib::prelude::*;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::cache::ByteReader;
+use crate::compressed_texture::{self, COMPRESSED_EXTENSIONS};
+use crate::colormap::{BiomeTint, Colormap, PLAINS_CLIMATE};
+use crate::material::{srgb_byte_to_linear, vector3_to_color};
+use crate::texture_animation::AnimationTimeline;
+use crate::procedural_textures::{self, CircuitBoard, DEFAULT_TEXTURE_SEED, DEFAULT_TEXTURE_SIZE, ProceduralTexture, ProceduralTextures};
+
+/// Sufijo de los mapas de normales: el de `stone` es `stone_normal`
+pub const NORMAL_MAP_SUFFIX: &str = "_normal";
+/// Sufijo de los mapas de rugosidad (`obsidian_roughness.png`)
+pub const ROUGHNESS_MAP_SUFFIX: &str = "_roughness";
+/// Sufijo de los mapas de metal (`diamond_metal.png`)
+pub const METALNESS_MAP_SUFFIX: &str = "_metal";
+/// Extensiones de las imágenes en rango dinámico alto, que se leen en flotantes
+const HDR_EXTENSIONS: [&str; 2] = ["hdr", "exr"];
+/// Extensiones de las imágenes que se cargan como texturas
+const IMAGE_EXTENSIONS: [&str; 9] = ["png", "jpg", "jpeg", "bmp", "tga", "hdr", "exr", "dds", "ktx2"];
+
+/// Mapas que acompañan a una textura y no se ofrecen como texturas propias
+const TEXTURE_MAP_SUFFIXES: [&str; 3] = [NORMAL_MAP_SUFFIX, ROUGHNESS_MAP_SUFFIX, METALNESS_MAP_SUFFIX];
+
+/// Encabezado del atlas guardado en la caché; cambia si cambia el formato
+const ATLAS_MAGIC: &[u8; 4] = b"ATL1";
+
+/// Si la textura es un mapa (normales, rugosidad, metal): guarda datos
+/// lineales, no colores en sRGB como el resto
+fn is_texture_map(name: &str) -> bool {
+    TEXTURE_MAP_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| extensions.contains(&ext.as_str()))
+}
+
+/// Texturas con relieve e inclinación máxima de su mapa de normales, que se
+/// deriva del brillo de la textura (lo oscuro queda hundido) si no hay uno en disco
+const RELIEF_TEXTURES: [(&str, f32); 3] = [("stone", 1.2), ("netherrack", 1.6), ("nether_brick", 2.5)];
+
+/// Alfa por debajo del cual un texel es un hueco por el que el rayo sigue de largo
+const ALPHA_CUTOFF: u8 = 128;
+
+/// Diferencia máxima entre canales de un texel que todavía cuenta como gris
+const GRAYSCALE_TOLERANCE: u8 = 8;
+
+/// Texels opacos de una textura con huecos (hojas, vidrios). Se guarda en el
+/// material para descartar los impactos en un hueco al intersecar, sin pasar
+/// por el administrador de texturas
+#[derive(Debug, PartialEq)]
+pub struct AlphaMask {
+    width: u32,
+    height: u32,
+    opaque: Vec<bool>,
+}
+
+impl AlphaMask {
+    /// Si el texel en (u, v) es opaco (con el mismo redondeo que `sample`)
+    #[inline]
+    pub fn is_opaque(&self, u: f32, v: f32) -> bool {
+        let x = (u * self.width as f32) as u32 % self.width;
+        let y = (v * self.height as f32) as u32 % self.height;
+        self.opaque[(y * self.width + x) as usize]
+    }
+}
+
+/// Lugar de una imagen dentro del atlas, en texels
+#[derive(Clone, Copy, Debug)]
+pub struct AtlasRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Imágenes de una textura dentro del atlas: una si es estática, los cuadros
+/// en orden si es animada (con cómo se recorren)
+#[derive(Clone)]
+struct AtlasEntry {
+    frames: Vec<AtlasRect>,
+    timeline: Option<AnimationTimeline>,
+    /// Guarda colores en sRGB; los mapas (normales, rugosidad, metal) son datos lineales
+    srgb: bool,
+    /// Texels en flotantes de una textura estática .hdr/.exr, que se leen en
+    /// lugar de los del atlas (ahí quedan recortados a 1 para la GPU)
+    hdr: Option<Arc<Vec<Vector3>>>,
+    /// Niveles de mipmap de una textura estática después del primero, del
+    /// más grande al más chico (solo los que trae el archivo)
+    mips: Vec<AtlasRect>,
+}
+
+/// Imagen que se acomoda en el atlas: la de una textura, uno de sus cuadros
+/// o uno de sus niveles de mipmap (`level` desde 1)
+struct PackedImage<'a> {
+    slot: usize,
+    timeline: Option<&'a AnimationTimeline>,
+    level: usize,
+    width: u32,
+    height: u32,
+    data: &'a [u8],
+    hdr: Option<&'a Arc<Vec<Vector3>>>,
+}
+
+impl<'a> PackedImage<'a> {
+    fn new(slot: usize, timeline: Option<&'a AnimationTimeline>, texture: &'a TextureData) -> Self {
+        PackedImage {
+            slot,
+            timeline,
+            level: 0,
+            width: texture.width,
+            height: texture.height,
+            data: &texture.data,
+            hdr: texture.hdr.as_ref(),
+        }
+    }
+}
+
+/// Si una imagen tiene los texels que dice su tamaño y entra en el atlas
+fn fits_atlas(width: u32, height: u32, data: &[u8]) -> bool {
+    width > 0 && data.len() >= (width * height * 4) as usize
+}
+
+/// Todas las texturas (y cada cuadro de las animadas) en una sola imagen RGBA.
+/// Los materiales guardan el índice de su textura en el atlas y el sombreado
+/// la lee sin buscar el nombre; el mismo arreglo se sube tal cual a la GPU.
+#[derive(Clone)]
+pub struct TextureAtlas {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    /// Índice de cada textura; solo crece, así los índices que ya guardaron
+    /// los materiales siguen valiendo cuando el atlas se vuelve a armar
+    slots: HashMap<String, usize>,
+    entries: Vec<AtlasEntry>,
+}
+
+/// Índices en el atlas de la textura de un material y de sus mapas
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextureSlots {
+    pub texture: Option<usize>,
+    pub normal: Option<usize>,
+    pub roughness: Option<usize>,
+    pub metalness: Option<usize>,
+}
+
+/// Cuadro de una textura que toca mostrar: su lugar en el atlas, el del
+/// siguiente con su peso si la animación se interpola, y si es animada
+struct AtlasFrame {
+    rect: AtlasRect,
+    next: Option<(AtlasRect, f32)>,
+    animated: bool,
+}
+
+impl TextureAtlas {
+    fn empty() -> Self {
+        TextureAtlas { width: 0, height: 0, data: Vec::new(), slots: HashMap::new(), entries: Vec::new() }
+    }
+
+    /// Cantidad de índices repartidos (incluye los de texturas que ya no están)
+    #[cfg(feature = "gpu")]
+    pub fn slot_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Índice de una textura, repartiendo uno nuevo (sin imágenes hasta el
+    /// próximo armado) si todavía no tiene
+    fn slot_of(&mut self, name: &str) -> usize {
+        if let Some(&slot) = self.slots.get(name) {
+            return slot;
+        }
+        self.slots.insert(name.to_string(), self.entries.len());
+        self.entries.push(AtlasEntry { frames: Vec::new(), timeline: None, srgb: !is_texture_map(name), hdr: None, mips: Vec::new() });
+        self.entries.len() - 1
+    }
+
+    /// Vuelve a acomodar las imágenes en estantes (filas del alto de la más
+    /// alta, de mayor a menor) sobre un ancho potencia de dos
+    fn pack(&mut self, textures: &HashMap<String, TextureData>, animated: &HashMap<String, AnimatedTexture>) {
+        let mut images: Vec<PackedImage> = Vec::new();
+        for (name, texture) in textures {
+            if !animated.contains_key(name) {
+                let slot = self.slot_of(name);
+                images.push(PackedImage::new(slot, None, texture));
+                images.extend(texture.mips.iter().enumerate().map(|(level, mip)| PackedImage {
+                    level: level + 1,
+                    width: mip.width,
+                    height: mip.height,
+                    data: &mip.data,
+                    ..PackedImage::new(slot, None, texture)
+                }));
+            }
+        }
+        for (name, texture) in animated {
+            let slot = self.slot_of(name);
+            images.extend(texture.frames.iter().map(|frame| PackedImage::new(slot, Some(&texture.timeline), frame)));
+        }
+        images.retain(|image| fits_atlas(image.width, image.height, image.data));
+        // Orden fijo (alto, índice, nivel) para que los cuadros de una
+        // animación y los niveles de mipmap queden en orden
+        images.sort_by_key(|image| (std::cmp::Reverse(image.height), image.slot, image.level));
+
+        let area: u32 = images.iter().map(|image| image.width * image.height).sum();
+        let widest = images.iter().map(|image| image.width).max().unwrap_or(0);
+        let width = ((area as f32).sqrt().ceil() as u32).max(widest).max(1).next_power_of_two();
+
+        let mut rects = Vec::with_capacity(images.len());
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for image in &images {
+            if x + image.width > width {
+                (x, y, shelf_height) = (0, y + shelf_height, 0);
+            }
+            rects.push(AtlasRect { x, y, width: image.width, height: image.height });
+            x += image.width;
+            shelf_height = shelf_height.max(image.height);
+        }
+        let height = y + shelf_height;
+
+        self.width = width;
+        self.height = height;
+        self.data = vec![0; (width * height * 4) as usize];
+        for entry in &mut self.entries {
+            entry.frames.clear();
+            entry.timeline = None;
+            entry.hdr = None;
+            entry.mips.clear();
+        }
+        for (image, rect) in images.into_iter().zip(rects) {
+            let row_len = (image.width * 4) as usize;
+            for row in 0..image.height {
+                let src = (row * image.width * 4) as usize;
+                let dst = (((rect.y + row) * width + rect.x) * 4) as usize;
+                self.data[dst..dst + row_len].copy_from_slice(&image.data[src..src + row_len]);
+            }
+            let entry = &mut self.entries[image.slot];
+            if image.level > 0 {
+                entry.mips.push(rect);
+                continue;
+            }
+            entry.frames.push(rect);
+            entry.timeline = image.timeline.cloned();
+            entry.hdr = image.hdr.cloned();
+        }
+    }
+
+    /// El atlas en bytes para la caché de la escena: tamaño, cada índice con
+    /// su nombre y sus lugares, y la imagen. Los tiempos de las animaciones y
+    /// los texels HDR no se guardan; salen de las texturas al leerlo.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut names = vec![""; self.entries.len()];
+        for (name, &slot) in &self.slots {
+            names[slot] = name.as_str();
+        }
+
+        let mut bytes = Vec::with_capacity(self.data.len() + 64 * self.entries.len() + 16);
+        bytes.extend_from_slice(ATLAS_MAGIC);
+        for value in [self.width, self.height, self.entries.len() as u32] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for (name, entry) in names.iter().zip(&self.entries) {
+            bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.push(entry.srgb as u8);
+            for rects in [&entry.frames, &entry.mips] {
+                bytes.extend_from_slice(&(rects.len() as u32).to_le_bytes());
+                for rect in rects {
+                    for value in [rect.x, rect.y, rect.width, rect.height] {
+                        bytes.extend_from_slice(&value.to_le_bytes());
+                    }
+                }
+            }
+        }
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Lee lo que escribe `to_bytes`; `None` si el archivo no es válido o
+    /// algún lugar cae fuera de la imagen
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(4)? != ATLAS_MAGIC {
+            return None;
+        }
+        let (width, height, count) = (reader.u32()?, reader.u32()?, reader.u32()? as usize);
+        let mut atlas = TextureAtlas::empty();
+        for slot in 0..count {
+            let len = reader.u32()? as usize;
+            let name = std::str::from_utf8(reader.bytes(len)?).ok()?.to_string();
+            let srgb = reader.u8()? != 0;
+            let mut read_rects = || -> Option<Vec<AtlasRect>> {
+                let count = reader.u32()?;
+                (0..count)
+                    .map(|_| {
+                        let rect = AtlasRect { x: reader.u32()?, y: reader.u32()?, width: reader.u32()?, height: reader.u32()? };
+                        let inside = rect.width > 0
+                            && rect.height > 0
+                            && rect.x.checked_add(rect.width)? <= width
+                            && rect.y.checked_add(rect.height)? <= height;
+                        inside.then_some(rect)
+                    })
+                    .collect()
+            };
+            let frames = read_rects()?;
+            let mips = read_rects()?;
+            if atlas.slots.insert(name, slot).is_some() {
+                return None;
+            }
+            atlas.entries.push(AtlasEntry { frames, timeline: None, srgb, hdr: None, mips });
+        }
+        let data = reader.bytes((width as usize).checked_mul(height as usize)?.checked_mul(4)?)?;
+        if !reader.is_empty() {
+            return None;
+        }
+        atlas.width = width;
+        atlas.height = height;
+        atlas.data = data.to_vec();
+        Some(atlas)
+    }
+
+    /// Cuadro de `slot` que toca en `time`
+    fn frame(&self, slot: usize, time: f32) -> Option<AtlasFrame> {
+        let entry = self.entries.get(slot)?;
+        let Some(timeline) = &entry.timeline else {
+            return Some(AtlasFrame { rect: *entry.frames.first()?, next: None, animated: false });
+        };
+        let frame = timeline.frame_at(time);
+        let next = frame.next.and_then(|(index, blend)| Some((*entry.frames.get(index)?, blend)));
+        Some(AtlasFrame { rect: *entry.frames.get(frame.frame)?, next, animated: true })
+    }
+
+    /// Nivel de mipmap de `slot` para un pixel que cubre `footprint` de la
+    /// textura (en unidades de UV): el más grande cuyos texels no son más
+    /// chicos que el pixel. `rect` es el nivel más grande; las texturas sin
+    /// mipmaps (y las animadas o HDR) siempre lo usan.
+    fn mip_rect(&self, slot: usize, rect: AtlasRect, footprint: f32) -> AtlasRect {
+        let entry = &self.entries[slot];
+        if entry.mips.is_empty() || entry.hdr.is_some() || footprint <= 0.0 {
+            return rect;
+        }
+        let level = (footprint * rect.width.max(rect.height) as f32).log2().floor();
+        if level < 1.0 {
+            return rect;
+        }
+        *entry.mips.get(level as usize - 1).or(entry.mips.last()).unwrap_or(&rect)
+    }
+
+    /// Valor del texel de `rect` que cae en (u, v), repitiendo la textura: de
+    /// 0 a 1 con el color pasado a lineal, o sin tope si la textura es HDR
+    fn texel(&self, slot: usize, rect: AtlasRect, u: f32, v: f32) -> Vector3 {
+        let x = (u * rect.width as f32) as u32 % rect.width;
+        let y = (v * rect.height as f32) as u32 % rect.height;
+        let entry = &self.entries[slot];
+        if let Some(hdr) = &entry.hdr {
+            return hdr[(y * rect.width + x) as usize];
+        }
+        let idx = (((rect.y + y) * self.width + rect.x + x) * 4) as usize;
+        let rgb = &self.data[idx..idx + 3];
+        if entry.srgb {
+            Vector3::new(srgb_byte_to_linear(rgb[0]), srgb_byte_to_linear(rgb[1]), srgb_byte_to_linear(rgb[2]))
+        } else {
+            Vector3::new(rgb[0] as f32 / 255.0, rgb[1] as f32 / 255.0, rgb[2] as f32 / 255.0)
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct TextureData {
     pub width: u32,
     pub height: u32,
     pub data: Vec<u8>,
+    /// Radiancia lineal sin tope de las imágenes .hdr/.exr, un RGB por texel;
+    /// `data` guarda la misma imagen recortada a 1 y en sRGB para exportarla
+    /// y subirla a la GPU
+    pub hdr: Option<Arc<Vec<Vector3>>>,
+    /// Niveles de mipmap que trae el archivo (.dds/.ktx2) después de la
+    /// imagen, cada uno de la mitad de lado que el anterior; vacío si no trae
+    pub mips: Vec<MipLevel>,
+}
+
+/// Un nivel de mipmap de una textura, en RGBA
+#[derive(Clone)]
+pub struct MipLevel {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Mapa de entorno equirectangular en rango dinámico alto (.hdr/.exr).
+/// Se usa como cielo de fondo y como fuente de luz para reflejos y luz ambiental.
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Vector3>,
+}
+
+impl EnvironmentMap {
+    fn load(path: &str) -> Result<Self, String> {
+        let image = TextureManager::load_image_data(path)
+            .map_err(|e| format!("No se pudo abrir mapa de entorno {}: {}", path, e))?;
+
+        if image.width == 0 || image.height == 0 {
+            return Err(format!("El mapa de entorno {} está vacío", path));
+        }
+
+        // Un .hdr/.exr ya trae radiancia; una imagen común se pasa de sRGB a lineal
+        let pixels = match image.hdr {
+            Some(hdr) => Arc::unwrap_or_clone(hdr),
+            None => image.data
+                .chunks_exact(4)
+                .map(|p| Vector3::new(srgb_byte_to_linear(p[0]), srgb_byte_to_linear(p[1]), srgb_byte_to_linear(p[2])))
+                .collect(),
+        };
+        Ok(EnvironmentMap { width: image.width, height: image.height, pixels })
+    }
+
+    #[inline]
+    fn texel(&self, x: u32, y: u32) -> Vector3 {
+        self.pixels[(y.min(self.height - 1) * self.width + x % self.width) as usize]
+    }
+
+    /// Radiancia en la dirección `dir` (interpolación bilineal; la costura en u se repite)
+    pub fn sample(&self, dir: Vector3) -> Vector3 {
+        let d = dir.normalized();
+        let u = 0.5 + d.z.atan2(d.x) / (2.0 * std::f32::consts::PI);
+        let v = 0.5 - d.y.clamp(-1.0, 1.0).asin() / std::f32::consts::PI;
+
+        let x = (u * self.width as f32 - 0.5).rem_euclid(self.width as f32);
+        let y = (v * self.height as f32 - 0.5).max(0.0);
+        let (x0, y0) = (x as u32, y as u32);
+        let (fx, fy) = (x.fract(), y.fract());
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
 }
 
+/// Genera el cuadro `frame` de `frames` de una textura animada procedural
+type FrameGenerator = fn(&ProceduralTextures, u32, u32) -> Vec<u8>;
+
 pub struct AnimatedTexture {
     frames: Vec<TextureData>,
-    frame_duration: f32,
+    timeline: AnimationTimeline,
+}
+
+/// Mapa de normales (RGBA, x/y/z de -1 a 1 llevados a 0-255) que toma el
+/// brillo de la textura como altura: se normaliza al rango de la textura y
+/// `strength` es la pendiente de la normal en el salto más brusco
+fn normal_map_from_brightness(texture: &TextureData, strength: f32) -> Vec<u8> {
+    let (width, height) = (texture.width as i32, texture.height as i32);
+    let brightness: Vec<f32> = texture.data
+        .chunks_exact(4)
+        .map(|rgba| 0.299 * rgba[0] as f32 + 0.587 * rgba[1] as f32 + 0.114 * rgba[2] as f32)
+        .collect();
+    let low = brightness.iter().copied().fold(f32::MAX, f32::min);
+    let high = brightness.iter().copied().fold(f32::MIN, f32::max);
+    let range = (high - low).max(1.0);
+    let at = |x: i32, y: i32| {
+        let index = (y.rem_euclid(height) * width + x.rem_euclid(width)) as usize;
+        brightness.get(index).map_or(0.0, |b| (b - low) / range)
+    };
+
+    let mut data = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let slope_u = (at(x + 1, y) - at(x - 1, y)) * 0.5;
+            let slope_v = (at(x, y + 1) - at(x, y - 1)) * 0.5;
+            let normal = Vector3::new(-slope_u * strength, -slope_v * strength, 1.0).normalized();
+            for component in [normal.x, normal.y, normal.z] {
+                data.push(((component * 0.5 + 0.5) * 255.0).round() as u8);
+            }
+            data.push(255);
+        }
+    }
+    data
+}
+
+/// Segundos que dura cada cuadro de una textura animada
+fn default_frame_duration(name: &str) -> f32 {
+    match name {
+        "water" => 0.3,
+        "lava" => 0.2,
+        "portal" => 0.15,
+        _ => 0.25,
+    }
+}
+
+/// Texturas tal como estaban al empezar un render: el atlas armado y la
+/// hora del reloj de las animaciones. Los rayos leen de acá, sin el lock
+/// de `TEXTURE_MANAGER`.
+#[derive(Clone)]
+pub struct TextureView {
+    atlas: Arc<TextureAtlas>,
+    time: f32,
+    animated_sampled: Arc<AtomicBool>,
+}
+
+impl TextureView {
+    fn frame(&self, slot: usize) -> Option<AtlasFrame> {
+        let frame = self.atlas.frame(slot, self.time)?;
+        if frame.animated {
+            self.animated_sampled.store(true, Ordering::Relaxed);
+        }
+        Some(frame)
+    }
+
+    /// Lugar en el atlas del cuadro que toca mostrar de una textura (sin
+    /// fundirlo con el siguiente aunque la animación se interpole)
+    #[cfg(feature = "gpu")]
+    pub fn frame_rect(&self, slot: usize) -> Option<AtlasRect> {
+        Some(self.frame(slot)?.rect)
+    }
+
+    /// Texel de `slot` en (u, v) para un pixel que cubre `footprint` de la
+    /// textura (en unidades de UV; 0 lee siempre el nivel más grande)
+    fn texel(&self, slot: usize, u: f32, v: f32, footprint: f32) -> Option<Vector3> {
+        let frame = self.frame(slot)?;
+        let rect = if frame.animated { frame.rect } else { self.atlas.mip_rect(slot, frame.rect, footprint) };
+        let color = self.atlas.texel(slot, rect, u, v);
+        Some(match frame.next {
+            Some((next, blend)) => color * (1.0 - blend) + self.atlas.texel(slot, next, u, v) * blend,
+            None => color,
+        })
+    }
+
+    /// Color de la textura en (u, v); `footprint` (lo que cubre el pixel, en
+    /// unidades de UV) elige el nivel de mipmap si la textura trae
+    pub fn sample(&self, slot: usize, u: f32, v: f32, footprint: f32) -> Vector3 {
+        if let Some(color) = self.texel(slot, u, v, footprint) {
+            return color;
+        }
+        
+        let checker = ((u * 8.0) as i32 + (v * 8.0) as i32) % 2;
+        if checker == 0 {
+            Vector3::new(1.0, 0.0, 1.0)
+        } else {
+            Vector3::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    /// Normal en el espacio de la cara (x hacia donde crece u, y hacia donde
+    /// crece v, z hacia afuera) leída del mapa de normales, o `None` si no existe
+    pub fn sample_normal(&self, normal_map: usize, u: f32, v: f32, footprint: f32) -> Option<Vector3> {
+        let normal = self.texel(normal_map, u, v, footprint)? * 2.0 - Vector3::one();
+        (normal.z > 0.0).then(|| normal.normalized())
+    }
+
+    /// Valor (0-1) de un mapa en escala de grises, como los de rugosidad y
+    /// metal, o `None` si no existe
+    pub fn sample_value(&self, map: usize, u: f32, v: f32, footprint: f32) -> Option<f32> {
+        Some(self.texel(map, u, v, footprint)?.x)
+    }
 }
 
 pub struct TextureManager {
     textures: HashMap<String, TextureData>,
     animated_textures: HashMap<String, AnimatedTexture>,
+    environment_maps: HashMap<String, Arc<EnvironmentMap>>,
+    /// Patrones propios registrados con `register_procedural_texture`, para
+    /// volver a pintarlos si cambia el lado de las texturas procedurales
+    custom_textures: Vec<(String, Box<dyn ProceduralTexture>)>,
+    /// Colormaps del pasto y el follaje que tiñen sus texturas según el bioma
+    colormaps: HashMap<BiomeTint, Colormap>,
+    /// Lado (en texels) y semilla de las texturas procedurales
     default_size: u32,
+    texture_seed: u32,
     time: f32,
+    /// Si desde la última consulta se leyó algún cuadro de una textura
+    /// animada; lo comparten las vistas que sacan los renders
+    animated_sampled: Arc<AtomicBool>,
+    /// Paquete de recursos aplicado sobre las texturas, si hay uno
+    resource_pack: Option<String>,
+    /// Lo comparten las vistas de los renders en curso; al volver a armarlo
+    /// mientras hay una se copia (ver `Arc::make_mut`)
+    atlas: Arc<TextureAtlas>,
+    /// Alguna textura cambió desde que se armó el atlas
+    atlas_dirty: bool,
 }
 
 impl TextureManager {
@@ -26,25 +587,50 @@ impl TextureManager {
         let mut manager = TextureManager {
             textures: HashMap::new(),
             animated_textures: HashMap::new(),
-            default_size: 16,
+            environment_maps: HashMap::new(),
+            custom_textures: Vec::new(),
+            colormaps: HashMap::new(),
+            default_size: DEFAULT_TEXTURE_SIZE,
+            texture_seed: DEFAULT_TEXTURE_SEED,
             time: 0.0,
+            animated_sampled: Arc::new(AtomicBool::new(false)),
+            resource_pack: None,
+            atlas: Arc::new(TextureAtlas::empty()),
+            atlas_dirty: true,
         };
         
         // 1. Generar texturas procedurales en memoria
         manager.load_placeholder_textures();
         manager.load_animated_textures();
+        manager.register_procedural_texture("circuit", CircuitBoard { cells: 4, seed: DEFAULT_TEXTURE_SEED });
         
         // 2. Intentar cargar desde disco (reemplaza las procedurales si existen)
         manager.load_textures_from_directory("assets/textures");
         
-        // 3. Exportar las que faltan (NUEVO)
+        // 3. Mapas de normales de las texturas con relieve que no vinieron del disco
+        manager.load_normal_maps();
+        manager.load_colormaps("assets/textures/colormap");
+
+        // 4. Exportar las que faltan (NUEVO)
         manager.export_missing_textures("assets/textures");
         
         manager
     }
 
-    pub fn update(&mut self, delta_time: f32) {
+    /// Avanza el reloj de las animaciones; devuelve `true` si alguna textura
+    /// animada pasó a otro cuadro
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        let previous = self.time;
         self.time += delta_time;
+        self.animated_textures.values().any(|animated| {
+            animated.timeline.frame_at(previous) != animated.timeline.frame_at(self.time)
+        })
+    }
+
+    /// Si la imagen trazada desde la última llamada usó alguna textura animada
+    /// (y por lo tanto hay que volver a trazarla cuando cambie de cuadro)
+    pub fn take_animated_sampled(&self) -> bool {
+        self.animated_sampled.swap(false, Ordering::Relaxed)
     }
     
     pub fn export_missing_textures(&self, dir_path: &str) {
@@ -56,9 +642,12 @@ impl TextureManager {
 
         let mut exported_count = 0;
         let mut skipped_count = 0;
+        // Los patrones propios se vuelven a pintar al arrancar; desde el disco
+        // perderían el tiempo de sus cuadros
+        let is_custom = |name: &str| self.custom_textures.iter().any(|(custom, _)| custom == name);
 
         // Exportar texturas estáticas
-        for (name, texture_data) in &self.textures {
+        for (name, texture_data) in self.textures.iter().filter(|(name, _)| !is_custom(name)) {
             let file_path = format!("{}/{}.png", dir_path, name);
             
             if Path::new(&file_path).exists() {
@@ -73,7 +662,7 @@ impl TextureManager {
         }
 
         // Exportar frames de texturas animadas
-        for (name, animated) in &self.animated_textures {
+        for (name, animated) in self.animated_textures.iter().filter(|(name, _)| !is_custom(name)) {
             for (frame_idx, frame_data) in animated.frames.iter().enumerate() {
                 let file_path = format!("{}/{}_{}.png", dir_path, name, frame_idx);
                 
@@ -189,26 +778,22 @@ impl TextureManager {
             for entry in entries.flatten() {
                 let file_path = entry.path();
                 
-                if let Some(extension) = file_path.extension() {
-                    let ext = extension.to_string_lossy().to_lowercase();
-                    
-                    if ext == "png" || ext == "jpg" || ext == "jpeg" || ext == "bmp" || ext == "tga" {
-                        if let Some(file_name) = file_path.file_stem() {
-                            let texture_name = file_name.to_string_lossy().to_string();
-                            
-                            // Verificar si es un frame de animación
-                            if let Some(base_name) = texture_name.strip_suffix("_0")
-                                .or_else(|| texture_name.strip_suffix("_1"))
-                                .or_else(|| texture_name.strip_suffix("_2"))
-                                .or_else(|| texture_name.strip_suffix("_3"))
-                                .or_else(|| texture_name.strip_suffix("_4"))
-                                .or_else(|| texture_name.strip_suffix("_5"))
-                            {
-                                // Es un frame de animación, cargar todos los frames
-                                self.load_animated_texture_from_files(base_name, dir_path);
-                            } else if self.load_texture_from_file(&texture_name, &file_path.to_string_lossy()) {
-                                loaded_count += 1;
-                            }
+                if has_extension(&file_path, &IMAGE_EXTENSIONS) {
+                    if let Some(file_name) = file_path.file_stem() {
+                        let texture_name = file_name.to_string_lossy().to_string();
+                        
+                        // Verificar si es un frame de animación
+                        if let Some(base_name) = texture_name.strip_suffix("_0")
+                            .or_else(|| texture_name.strip_suffix("_1"))
+                            .or_else(|| texture_name.strip_suffix("_2"))
+                            .or_else(|| texture_name.strip_suffix("_3"))
+                            .or_else(|| texture_name.strip_suffix("_4"))
+                            .or_else(|| texture_name.strip_suffix("_5"))
+                        {
+                            // Es un frame de animación, cargar todos los frames
+                            self.load_animated_texture_from_files(base_name, dir_path);
+                        } else if self.load_texture_from_file(&texture_name, &file_path.to_string_lossy()) {
+                            loaded_count += 1;
                         }
                     }
                 }
@@ -232,7 +817,7 @@ impl TextureManager {
                 break;
             }
 
-            match self.load_image_data(&file_path) {
+            match Self::load_image_data(&file_path) {
                 Ok(texture_data) => {
                     frames.push(texture_data);
                     frame_idx += 1;
@@ -242,32 +827,43 @@ impl TextureManager {
         }
 
         if !frames.is_empty() {
-            let frame_duration = match base_name {
-                "water" => 0.3,
-                "lava" => 0.2,
-                "portal" => 0.15,
-                _ => 0.25,
+            // `<nombre>.mcmeta` al lado de los cuadros elige su orden y duración
+            let meta_path = format!("{}/{}.mcmeta", dir_path, base_name);
+            let timeline = match std::fs::read_to_string(&meta_path) {
+                Ok(json) => AnimationTimeline::from_mcmeta(&json, frames.len())
+                    .map_err(|e| println!("{}: {}", meta_path, e))
+                    .ok(),
+                Err(_) => None,
             };
+            let source = if timeline.is_some() { format!(", {}", meta_path) } else { String::new() };
+            let timeline = timeline.unwrap_or_else(|| AnimationTimeline::uniform(frames.len(), default_frame_duration(base_name)));
+            self.animated_textures.insert(base_name.to_string(), AnimatedTexture { frames, timeline });
+            self.atlas_dirty = true;
 
-            self.animated_textures.insert(
-                base_name.to_string(),
-                AnimatedTexture {
-                    frames,
-                    frame_duration,
-                },
-            );
-
-            println!("Textura animada cargada: {} ({} frames)", base_name, frame_idx);
+            println!("Textura animada cargada: {} ({} frames{})", base_name, frame_idx, source);
             true
         } else {
             false
         }
     }
 
+    /// Carga (una sola vez por ruta) un mapa de entorno HDR equirectangular
+    pub fn load_environment_map(&mut self, file_path: &str) -> Result<Arc<EnvironmentMap>, String> {
+        if let Some(map) = self.environment_maps.get(file_path) {
+            return Ok(map.clone());
+        }
+
+        let map = Arc::new(EnvironmentMap::load(file_path)?);
+        println!("Mapa de entorno cargado: {} ({}x{})", file_path, map.width, map.height);
+        self.environment_maps.insert(file_path.to_string(), map.clone());
+        Ok(map)
+    }
+
     pub fn load_texture_from_file(&mut self, name: &str, file_path: &str) -> bool {
-        match self.load_image_data(file_path) {
+        match Self::load_image_data(file_path) {
             Ok(texture_data) => {
                 self.textures.insert(name.to_string(), texture_data);
+                self.atlas_dirty = true;
                 true
             }
             Err(e) => {
@@ -277,430 +873,400 @@ impl TextureManager {
         }
     }
 
-    fn load_image_data(&self, file_path: &str) -> Result<TextureData, String> {
-        use image::GenericImageView;
-        
+    /// Lee una imagen: las comunes en 8 bits, las .hdr/.exr además en
+    /// flotantes y las .dds/.ktx2 con su propio lector
+    fn load_image_data(file_path: &str) -> Result<TextureData, String> {
+        if has_extension(Path::new(file_path), &COMPRESSED_EXTENSIONS) {
+            return compressed_texture::load(file_path);
+        }
         let img = image::open(file_path)
             .map_err(|e| format!("No se pudo abrir imagen: {}", e))?;
-        
-        let (width, height) = img.dimensions();
-        let rgba = img.to_rgba8();
-        
-        Ok(TextureData {
-            width,
-            height,
-            data: rgba.into_raw(),
-        })
-    }
+        let (width, height) = (img.width(), img.height());
 
-    fn load_animated_textures(&mut self) {
-        let water_frames = vec![
-            self.generate_water_frame(0),
-            self.generate_water_frame(1),
-            self.generate_water_frame(2),
-            self.generate_water_frame(3),
-        ];
-        
-        self.animated_textures.insert(
-            "water".to_string(),
-            AnimatedTexture {
-                frames: water_frames.into_iter().map(|data| TextureData {
-                    width: self.default_size,
-                    height: self.default_size,
-                    data,
-                }).collect(),
-                frame_duration: 0.3,
-            },
-        );
+        if !has_extension(Path::new(file_path), &HDR_EXTENSIONS) {
+            return Ok(TextureData { width, height, data: img.to_rgba8().into_raw(), hdr: None, mips: Vec::new() });
+        }
 
-        let lava_frames = vec![
-            self.generate_lava_frame(0),
-            self.generate_lava_frame(1),
-            self.generate_lava_frame(2),
-            self.generate_lava_frame(3),
-        ];
-        
-        self.animated_textures.insert(
-            "lava".to_string(),
-            AnimatedTexture {
-                frames: lava_frames.into_iter().map(|data| TextureData {
-                    width: self.default_size,
-                    height: self.default_size,
-                    data,
-                }).collect(),
-                frame_duration: 0.2,
-            },
-        );
+        let pixels = img.to_rgba32f();
+        let hdr: Vec<Vector3> = pixels.pixels().map(|p| Vector3::new(p[0], p[1], p[2])).collect();
+        let data = pixels
+            .pixels()
+            .zip(&hdr)
+            .flat_map(|(p, &radiance)| {
+                let color = vector3_to_color(radiance);
+                [color.r, color.g, color.b, (p[3].clamp(0.0, 1.0) * 255.0).round() as u8]
+            })
+            .collect();
+        Ok(TextureData { width, height, data, hdr: Some(Arc::new(hdr)), mips: Vec::new() })
+    }
 
-        let portal_frames = vec![
-            self.generate_portal_frame(0),
-            self.generate_portal_frame(1),
-            self.generate_portal_frame(2),
-            self.generate_portal_frame(3),
-            self.generate_portal_frame(4),
-            self.generate_portal_frame(5),
+    fn load_animated_textures(&mut self) {
+        let procedural = ProceduralTextures::new(self.default_size, self.texture_seed);
+        let animations: [(&str, u32, f32, FrameGenerator); 3] = [
+            ("water", 4, 0.3, ProceduralTextures::water_frame),
+            ("lava", 4, 0.2, ProceduralTextures::lava_frame),
+            ("portal", 6, 0.15, ProceduralTextures::portal_frame),
         ];
-        
-        self.animated_textures.insert(
-            "portal".to_string(),
-            AnimatedTexture {
-                frames: portal_frames.into_iter().map(|data| TextureData {
+        for (name, frame_count, frame_duration, generate) in animations {
+            let frames: Vec<TextureData> = (0..frame_count)
+                .map(|frame| TextureData {
                     width: self.default_size,
                     height: self.default_size,
-                    data,
-                }).collect(),
-                frame_duration: 0.15,
-            },
-        );
+                    data: generate(&procedural, frame, frame_count),
+                    hdr: None,
+                    mips: Vec::new(),
+                })
+                .collect();
+            let timeline = AnimationTimeline::uniform(frames.len(), frame_duration);
+            self.animated_textures.insert(name.to_string(), AnimatedTexture { frames, timeline });
+        }
+        self.atlas_dirty = true;
     }
 
     fn load_placeholder_textures(&mut self) {
         let size = self.default_size;
-        
-        self.register_procedural("grass_top", size, size, self.generate_grass_top());
-        self.register_procedural("grass_side", size, size, self.generate_grass_side());
-        self.register_procedural("dirt", size, size, self.generate_dirt());
-        self.register_procedural("stone", size, size, self.generate_stone());
-        self.register_procedural("wood", size, size, self.generate_wood());
-        self.register_procedural("leaves", size, size, self.generate_leaves());
-        self.register_procedural("netherrack", size, size, self.generate_netherrack());
-        self.register_procedural("nether_brick", size, size, self.generate_nether_brick());
-        self.register_procedural("soul_sand", size, size, self.generate_soul_sand());
-        self.register_procedural("glowstone", size, size, self.generate_glowstone());
-        self.register_procedural("diamond", size, size, self.generate_diamond());
-        self.register_procedural("emerald", size, size, self.generate_emerald());
-        self.register_procedural("obsidian", size, size, self.generate_obsidian());
-        self.register_procedural("ice", size, size, self.generate_ice());
-        
-        println!("{} texturas procedurales cargadas", self.textures.len());
+        let procedural = ProceduralTextures::new(size, self.texture_seed);
+
+        self.register_procedural("grass_top", size, size, procedural.grass_top());
+        self.register_procedural("grass_side", size, size, procedural.grass_side());
+        self.register_procedural("dirt", size, size, procedural.dirt());
+        self.register_procedural("stone", size, size, procedural.stone());
+        self.register_procedural("wood", size, size, procedural.wood());
+        self.register_procedural("leaves", size, size, procedural.leaves());
+        self.register_procedural("netherrack", size, size, procedural.netherrack());
+        self.register_procedural("nether_brick", size, size, procedural.nether_brick());
+        self.register_procedural("soul_sand", size, size, procedural.soul_sand());
+        self.register_procedural("glowstone", size, size, procedural.glowstone());
+        self.register_procedural("diamond", size, size, procedural.diamond());
+        self.register_procedural("emerald", size, size, procedural.emerald());
+        self.register_procedural("obsidian", size, size, procedural.obsidian());
+        self.register_procedural("ice", size, size, procedural.ice());
+        self.register_procedural("glass", size, size, procedural.glass());
+
+        println!("{} texturas procedurales cargadas ({}x{}, semilla {})", self.textures.len(), size, size, self.texture_seed);
     }
 
-    pub fn sample(&self, texture_name: &str, u: f32, v: f32) -> Vector3 {
-        if let Some(animated) = self.animated_textures.get(texture_name) {
-            let total_frames = animated.frames.len() as f32;
-            let frame_index = ((self.time / animated.frame_duration) % total_frames) as usize;
-            let texture_data = &animated.frames[frame_index];
-            
-            let width = texture_data.width as f32;
-            let height = texture_data.height as f32;
-            
-            let x = ((u * width) as u32 % texture_data.width) as usize;
-            let y = ((v * height) as u32 % texture_data.height) as usize;
-            
-            let idx = (y * texture_data.width as usize + x) * 4;
-            
-            if idx + 2 < texture_data.data.len() {
-                return Vector3::new(
-                    texture_data.data[idx] as f32 / 255.0,
-                    texture_data.data[idx + 1] as f32 / 255.0,
-                    texture_data.data[idx + 2] as f32 / 255.0,
-                );
-            }
-        }
+    /// Vuelve a generar las texturas procedurales con otro tamaño (en texels
+    /// por lado) y semilla, y carga encima las del disco y los mapas de
+    /// normales, como al iniciar: las de `assets/textures` siguen mandando
+    pub fn regenerate_procedural(&mut self, size: u32, seed: u32) {
+        self.default_size = size.max(1);
+        self.texture_seed = seed;
+        self.textures.clear();
+        self.animated_textures.clear();
+        self.load_placeholder_textures();
+        self.load_animated_textures();
+        self.bake_custom_textures();
+        self.load_textures_from_directory("assets/textures");
+        self.load_normal_maps();
+    }
 
-        if let Some(texture_data) = self.textures.get(texture_name) {
-            let width = texture_data.width as f32;
-            let height = texture_data.height as f32;
-            
-            let x = ((u * width) as u32 % texture_data.width) as usize;
-            let y = ((v * height) as u32 % texture_data.height) as usize;
-            
-            let idx = (y * texture_data.width as usize + x) * 4;
-            
-            if idx + 2 < texture_data.data.len() {
-                return Vector3::new(
-                    texture_data.data[idx] as f32 / 255.0,
-                    texture_data.data[idx + 1] as f32 / 255.0,
-                    texture_data.data[idx + 2] as f32 / 255.0,
-                );
-            }
+    /// Registra un patrón propio con ese nombre (o reemplaza la textura que
+    /// lo tenga): se pinta al lado de las texturas procedurales, como textura
+    /// estática si tiene un cuadro o animada en bucle si tiene más
+    pub fn register_procedural_texture(&mut self, name: &str, texture: impl ProceduralTexture + 'static) {
+        self.custom_textures.retain(|(custom, _)| custom != name);
+        self.custom_textures.push((name.to_string(), Box::new(texture)));
+        self.bake_custom_texture(self.custom_textures.len() - 1);
+    }
+
+    fn bake_custom_textures(&mut self) {
+        for index in 0..self.custom_textures.len() {
+            self.bake_custom_texture(index);
         }
-        
-        let checker = ((u * 8.0) as i32 + (v * 8.0) as i32) % 2;
-        if checker == 0 {
-            Vector3::new(1.0, 0.0, 1.0)
+    }
+
+    fn bake_custom_texture(&mut self, index: usize) {
+        let size = self.default_size;
+        let (name, texture) = &self.custom_textures[index];
+        let frame_duration = texture.frames().1;
+        let mut frames: Vec<TextureData> = procedural_textures::bake(texture.as_ref(), size)
+            .into_iter()
+            .map(|data| TextureData { width: size, height: size, data, hdr: None, mips: Vec::new() })
+            .collect();
+        self.textures.remove(name);
+        self.animated_textures.remove(name);
+        if frames.len() > 1 {
+            let timeline = AnimationTimeline::uniform(frames.len(), frame_duration);
+            self.animated_textures.insert(name.clone(), AnimatedTexture { frames, timeline });
         } else {
-            Vector3::new(0.0, 0.0, 0.0)
+            self.textures.insert(name.clone(), frames.swap_remove(0));
         }
+        self.atlas_dirty = true;
     }
 
-    pub fn register_procedural(&mut self, name: &str, width: u32, height: u32, data: Vec<u8>) {
-        self.textures.insert(
-            name.to_string(),
-            TextureData {
-                width,
-                height,
-                data,
-            },
-        );
+    /// Índice de una textura (estática o animada) en el atlas. Si alguna
+    /// textura cambió, primero vuelve a armar el atlas; un nombre que no
+    /// existe también recibe índice, y se dibuja con el damero de faltante.
+    pub fn slot(&mut self, name: &str) -> usize {
+        if self.atlas_dirty {
+            Arc::make_mut(&mut self.atlas).pack(&self.textures, &self.animated_textures);
+            self.atlas_dirty = false;
+        }
+        match self.atlas.slots.get(name) {
+            Some(&slot) => slot,
+            None => Arc::make_mut(&mut self.atlas).slot_of(name),
+        }
     }
 
-    fn generate_grass_top(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 7 + y * 13) % 5) as u8;
-                data.push(50 + noise);
-                data.push(180 + noise);
-                data.push(50 + noise);
-                data.push(255);
-            }
+    /// El atlas y el reloj de las animaciones de ahora, para leer texturas
+    /// durante un render sin volver a tomar el lock del administrador
+    pub fn view(&self) -> TextureView {
+        TextureView { atlas: Arc::clone(&self.atlas), time: self.time, animated_sampled: Arc::clone(&self.animated_sampled) }
+    }
+
+    #[cfg(feature = "gpu")]
+    pub fn atlas(&self) -> &TextureAtlas {
+        &self.atlas
+    }
+
+    /// El atlas armado, en bytes para la caché de la escena
+    pub fn atlas_bytes(&mut self) -> Vec<u8> {
+        if self.atlas_dirty {
+            Arc::make_mut(&mut self.atlas).pack(&self.textures, &self.animated_textures);
+            self.atlas_dirty = false;
+        }
+        self.atlas.to_bytes()
+    }
+
+    /// Usa el atlas guardado con `atlas_bytes` en lugar de volver a armarlo.
+    /// Solo si sirve para las texturas cargadas: los índices ya repartidos
+    /// (que guardan los materiales) son los mismos y cada textura tiene sus
+    /// cuadros y niveles del mismo tamaño. Devuelve si lo usó.
+    pub fn restore_atlas(&mut self, bytes: &[u8]) -> bool {
+        let Some(mut atlas) = TextureAtlas::from_bytes(bytes) else {
+            return false;
+        };
+        if self.atlas.slots.iter().any(|(name, slot)| atlas.slots.get(name) != Some(slot)) {
+            return false;
         }
-        data
-    }
-
-    fn generate_grass_side(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                if y < self.default_size / 4 {
-                    let noise = ((x * 7 + y * 13) % 5) as u8;
-                    data.push(50 + noise);
-                    data.push(180 + noise);
-                    data.push(50 + noise);
-                    data.push(255);
-                } else {
-                    let noise = ((x * 11 + y * 17) % 8) as u8;
-                    data.push(130 + noise);
-                    data.push(80 + noise);
-                    data.push(40 + noise);
-                    data.push(255);
+
+        // Tamaño de cada imagen que `pack` pone en el atlas
+        let size = |width: u32, height: u32, data: &[u8]| fits_atlas(width, height, data).then_some((width, height));
+        let expected = |name: &str| -> (Vec<(u32, u32)>, Vec<(u32, u32)>) {
+            match (self.textures.get(name), self.animated_textures.get(name)) {
+                (_, Some(animated)) => {
+                    (animated.frames.iter().filter_map(|frame| size(frame.width, frame.height, &frame.data)).collect(), Vec::new())
                 }
+                (Some(texture), None) => (
+                    size(texture.width, texture.height, &texture.data).into_iter().collect(),
+                    texture.mips.iter().filter_map(|mip| size(mip.width, mip.height, &mip.data)).collect(),
+                ),
+                (None, None) => (Vec::new(), Vec::new()),
             }
-        }
-        data
-    }
+        };
+        let sizes = |rects: &[AtlasRect]| -> Vec<(u32, u32)> { rects.iter().map(|rect| (rect.width, rect.height)).collect() };
 
-    fn generate_dirt(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 11 + y * 17) % 12) as u8;
-                data.push(130 + noise);
-                data.push(80 + noise);
-                data.push(40 + noise);
-                data.push(255);
+        // Cada textura cargada tiene sus imágenes, y ningún otro índice tiene
+        let names = self.textures.keys().chain(self.animated_textures.keys().filter(|name| !self.textures.contains_key(*name)));
+        let mut packed = 0;
+        for name in names {
+            let (frames, mips) = expected(name);
+            if frames.is_empty() {
+                continue;
             }
+            let Some(entry) = atlas.slots.get(name).map(|&slot| &mut atlas.entries[slot]) else {
+                return false;
+            };
+            if sizes(&entry.frames) != frames || sizes(&entry.mips) != mips {
+                return false;
+            }
+            // Como en `pack`: la animación y los texels HDR de la última imagen
+            (entry.timeline, entry.hdr) = match self.animated_textures.get(name) {
+                Some(animated) => {
+                    let last = animated.frames.iter().rfind(|frame| fits_atlas(frame.width, frame.height, &frame.data));
+                    (Some(animated.timeline.clone()), last.and_then(|frame| frame.hdr.clone()))
+                }
+                None => (None, self.textures.get(name).and_then(|texture| texture.hdr.clone())),
+            };
+            packed += 1;
         }
-        data
+        if atlas.entries.iter().filter(|entry| !entry.frames.is_empty()).count() != packed {
+            return false;
+        }
+
+        self.atlas = Arc::new(atlas);
+        self.atlas_dirty = false;
+        true
     }
 
-    fn generate_stone(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 13 + y * 19) % 15) as u8;
-                data.push(100 + noise);
-                data.push(100 + noise);
-                data.push(100 + noise);
-                data.push(255);
-            }
-        }
-        data
+    /// Lugar en el atlas del cuadro que toca mostrar de una textura (sin
+    /// fundirlo con el siguiente aunque la animación se interpole)
+    #[cfg(feature = "gpu")]
+    pub fn frame_rect(&self, slot: usize) -> Option<AtlasRect> {
+        self.view().frame_rect(slot)
     }
 
-    fn generate_wood(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let ring = (x as i32 - 8).abs() + (y as i32 - 8).abs();
-                let noise = (ring % 4) as u8 * 10;
-                data.push(80 + noise);
-                data.push(50 + noise / 2);
-                data.push(20 + noise / 3);
-                data.push(255);
-            }
+    /// Máscara de huecos de una textura estática, o `None` si no tiene
+    /// ningún texel con alfa bajo el corte
+    pub fn alpha_mask(&self, texture_name: &str) -> Option<AlphaMask> {
+        let texture = self.textures.get(texture_name)?;
+        if texture.width == 0 || texture.height == 0 {
+            return None;
         }
-        data
+        let opaque: Vec<bool> = texture.data.chunks_exact(4).map(|rgba| rgba[3] >= ALPHA_CUTOFF).collect();
+        if opaque.len() != (texture.width * texture.height) as usize || opaque.iter().all(|&opaque| opaque) {
+            return None;
+        }
+        Some(AlphaMask { width: texture.width, height: texture.height, opaque })
     }
 
-    fn generate_leaves(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 23 + y * 29) % 20) as u8;
-                data.push(20 + noise / 2);
-                data.push(120 + noise);
-                data.push(20 + noise / 2);
-                data.push(255);
-            }
-        }
-        data
+    /// Si hay una textura (estática o animada) con ese nombre
+    pub fn has_texture(&self, name: &str) -> bool {
+        self.textures.contains_key(name) || self.animated_textures.contains_key(name)
     }
 
-    fn generate_water_frame(&self, frame: i32) -> Vec<u8> {
-        let mut data = Vec::new();
-        let offset = frame as u32 * 2;
-        
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let wave = (((x + offset) + y) % 4) as u8 * 8;
-                data.push(20 + wave);
-                data.push(60 + wave);
-                data.push(180 + wave);
-                data.push(200);
-            }
-        }
-        data
+    /// Posición (0-1) dentro del ciclo de una textura animada, o `None` si no es animada
+    pub fn animation_phase(&self, texture_name: &str) -> Option<f32> {
+        let animated = self.animated_textures.get(texture_name)?;
+        let cycle = animated.timeline.cycle();
+        Some((self.time / cycle).fract())
     }
 
-    fn generate_lava_frame(&self, frame: i32) -> Vec<u8> {
-        let mut data = Vec::new();
-        let offset = (frame * 17) as u32;
-        
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let flow = ((x * 3 + y * 7 + offset) % 12) as u8 * 10;
-                let bubble = if (x + y + offset / 2) % 7 == 0 { 40 } else { 0 };
-                data.push(255);
-                data.push(100 + flow + bubble);
-                data.push((flow / 2) + bubble / 2);
-                data.push(255);
-            }
-        }
-        data
+    /// Nombres de todas las texturas disponibles (estáticas y animadas, sin los
+    /// mapas de normales, rugosidad y metal), ordenados
+    pub fn texture_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.textures.keys()
+            .chain(self.animated_textures.keys())
+            .filter(|name| !is_texture_map(name))
+            .cloned()
+            .collect();
+        names.sort();
+        names.dedup();
+        names
     }
 
-    fn generate_portal_frame(&self, frame: i32) -> Vec<u8> {
-        let mut data = Vec::new();
-        let phase = frame as f32 * 0.5;
-        
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let wave = (((x as f32 * 0.8 + phase).sin() + (y as f32 * 0.6 + phase).cos()) * 50.0) as i32;
-                let swirl = (((x as f32 - 8.0).atan2(y as f32 - 8.0) * 3.0 + phase).sin() * 30.0) as i32;
-                let purple = (128 + wave + swirl).clamp(80, 220) as u8;
-                let magenta = (0 + wave / 3 + swirl / 2).clamp(0, 120) as u8;
-                let violet = (200 + wave + swirl).clamp(150, 255) as u8;
-                
-                data.push(purple);
-                data.push(magenta);
-                data.push(violet);
-                data.push(180);
+    /// Deriva el mapa de normales de cada textura con relieve, salvo que ya
+    /// se haya cargado uno desde el disco
+    fn load_normal_maps(&mut self) {
+        let mut generated = 0;
+        for (name, strength) in RELIEF_TEXTURES {
+            let normal_map = format!("{}{}", name, NORMAL_MAP_SUFFIX);
+            if self.textures.contains_key(&normal_map) {
+                continue;
+            }
+            if let Some(texture) = self.textures.get(name) {
+                let data = normal_map_from_brightness(texture, strength);
+                self.register_procedural(&normal_map, texture.width, texture.height, data);
+                generated += 1;
             }
         }
-        data
+        if generated > 0 {
+            println!("{} mapas de normales generados", generated);
+        }
     }
 
-    fn generate_netherrack(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 31 + y * 37) % 25) as u8;
-                data.push(150 + noise);
-                data.push(40 + noise / 2);
-                data.push(40 + noise / 2);
-                data.push(255);
-            }
-        }
-        data
-    }
-
-    fn generate_nether_brick(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let is_mortar = (x % 8 == 0) || (y % 8 == 0);
-                if is_mortar {
-                    data.push(20);
-                    data.push(10);
-                    data.push(10);
-                } else {
-                    data.push(50);
-                    data.push(15);
-                    data.push(15);
+    /// Carga `grass.png` y `foliage.png` de la carpeta, o los genera si no están
+    fn load_colormaps(&mut self, dir_path: &str) {
+        for tint in BiomeTint::ALL {
+            let path = format!("{}/{}.png", dir_path, tint.file_name());
+            let colormap = if Path::new(&path).exists() {
+                match Colormap::load(&path) {
+                    Ok(colormap) => {
+                        println!("  ✓ Colormap cargado: {}", path);
+                        colormap
+                    }
+                    Err(e) => {
+                        println!("  ✗ {}", e);
+                        Colormap::procedural(tint)
+                    }
                 }
-                data.push(255);
-            }
+            } else {
+                Colormap::procedural(tint)
+            };
+            self.colormaps.insert(tint, colormap);
         }
-        data
     }
 
-    fn generate_soul_sand(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 41 + y * 43) % 18) as u8;
-                data.push(70 + noise);
-                data.push(50 + noise);
-                data.push(35 + noise);
-                data.push(255);
-            }
-        }
-        data
+    /// Reemplaza un colormap (el de un paquete de recursos)
+    pub fn set_colormap(&mut self, tint: BiomeTint, colormap: Colormap) {
+        self.colormaps.insert(tint, colormap);
     }
 
-    fn generate_glowstone(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let glow = ((x + y) % 3) as u8 * 15;
-                data.push(255);
-                data.push(220 + glow);
-                data.push(100 + glow);
-                data.push(255);
-            }
-        }
-        data
+    /// Color (lineal) del colormap para una temperatura y humedad de 0 a 1
+    pub fn biome_color(&self, tint: BiomeTint, (temperature, humidity): (f32, f32)) -> Vector3 {
+        self.colormaps
+            .get(&tint)
+            .map_or(Vector3::one(), |colormap| colormap.sample(temperature, humidity))
     }
 
-    fn generate_diamond(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let crystal = ((x + y) % 3) as u8;
-                let sparkle = if (x * 7 + y * 11) % 13 == 0 { 60 } else { 0 };
-                data.push(180 + crystal * 20 + sparkle);
-                data.push(230 + crystal * 8 + sparkle);
-                data.push(255);
-                data.push(255);
-            }
+    /// Tinte lineal de una textura en un clima: el color del colormap, o su
+    /// cociente con el de la llanura si la textura no es gris y ya trae su color
+    pub fn biome_tint(&self, tint: BiomeTint, texture: Option<&str>, climate: (f32, f32)) -> Vector3 {
+        let color = self.biome_color(tint, climate);
+        if texture.is_some_and(|texture| self.is_grayscale(texture)) {
+            return color;
         }
-        data
+        let reference = self.biome_color(tint, PLAINS_CLIMATE);
+        Vector3::new(
+            color.x / reference.x.max(1e-4),
+            color.y / reference.y.max(1e-4),
+            color.z / reference.z.max(1e-4),
+        )
     }
 
-    fn generate_emerald(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let crystal = ((x + y) % 4) as u8;
-                let sparkle = if (x * 5 + y * 13) % 11 == 0 { 40 } else { 0 };
-                data.push(50 + crystal * 10 + sparkle);
-                data.push(230 + crystal * 6 + sparkle);
-                data.push(80 + crystal * 15 + sparkle);
-                data.push(255);
-            }
-        }
-        data
+    /// Si la textura (o el primer cuadro, si es animada) es gris: las que
+    /// vienen grises toman todo su color del colormap
+    pub fn is_grayscale(&self, name: &str) -> bool {
+        let texture = match self.textures.get(name) {
+            Some(texture) => texture,
+            None => match self.animated_textures.get(name).and_then(|animated| animated.frames.first()) {
+                Some(frame) => frame,
+                None => return false,
+            },
+        };
+        texture.data.chunks_exact(4).filter(|rgba| rgba[3] >= ALPHA_CUTOFF).all(|rgba| {
+            let (min, max) = (rgba[0].min(rgba[1]).min(rgba[2]), rgba[0].max(rgba[1]).max(rgba[2]));
+            max - min <= GRAYSCALE_TOLERANCE
+        })
     }
 
-    fn generate_obsidian(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let noise = ((x * 17 + y * 23) % 20) as u8;
-                let purple_tint = if noise > 15 { 20 } else { 0 };
-                data.push(10 + noise / 2);
-                data.push(5 + noise / 4);
-                data.push(25 + purple_tint);
-                data.push(255);
+    /// Reemplaza texturas por las de un paquete de recursos. Cada una trae sus
+    /// cuadros (y cómo recorrerlos, si el paquete lo dice): las animadas los
+    /// usan todos y las estáticas solo el primero.
+    /// Los mapas de rugosidad y metal de las reemplazadas se descartan, porque
+    /// eran para la imagen anterior, y los de normales se vuelven a derivar.
+    pub fn apply_resource_pack(&mut self, pack: &str, textures: Vec<(&str, Vec<TextureData>, Option<AnimationTimeline>)>) {
+        for (name, mut frames, timeline) in textures {
+            if frames.is_empty() {
+                continue;
+            }
+            for suffix in TEXTURE_MAP_SUFFIXES {
+                self.textures.remove(&format!("{}{}", name, suffix));
+            }
+            if self.animated_textures.contains_key(name) {
+                let timeline = timeline.unwrap_or_else(|| AnimationTimeline::uniform(frames.len(), default_frame_duration(name)));
+                self.animated_textures.insert(name.to_string(), AnimatedTexture { frames, timeline });
+            } else {
+                self.textures.insert(name.to_string(), frames.swap_remove(0));
             }
         }
-        data
+        self.load_normal_maps();
+        self.resource_pack = Some(pack.to_string());
+        self.atlas_dirty = true;
     }
 
-    fn generate_ice(&self) -> Vec<u8> {
-        let mut data = Vec::new();
-        for y in 0..self.default_size {
-            for x in 0..self.default_size {
-                let crack = if (x + y) % 7 == 0 { 30 } else { 0 };
-                let shine = ((x * y) % 5) as u8 * 3;
-                data.push(200 + shine + crack);
-                data.push(230 + shine + crack);
-                data.push(255);
-                data.push(200);
-            }
-        }
-        data
+    /// Lado y semilla con que se generaron las texturas procedurales
+    pub fn procedural_settings(&self) -> (u32, u32) {
+        (self.default_size, self.texture_seed)
+    }
+
+    /// Paquete de recursos aplicado con `apply_resource_pack`, si hay uno
+    pub fn resource_pack(&self) -> Option<&str> {
+        self.resource_pack.as_deref()
+    }
+
+    pub fn register_procedural(&mut self, name: &str, width: u32, height: u32, data: Vec<u8>) {
+        self.atlas_dirty = true;
+        self.textures.insert(
+            name.to_string(),
+            TextureData {
+                width,
+                height,
+                data,
+                hdr: None,
+                mips: Vec::new(),
+            },
+        );
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,125 @@
+use serde::Deserialize;
+
+/// Segundos de un tick de Minecraft, la unidad de tiempo de los `.mcmeta`
+const TICK_SECONDS: f32 = 1.0 / 20.0;
+/// Pasos en que se funde un cuadro con el siguiente al interpolar: la imagen
+/// solo cambia (y se vuelve a trazar) al pasar de uno a otro
+const BLEND_STEPS: f32 = 8.0;
+
+/// Un paso de la animación: qué cuadro se muestra y cuántos segundos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationStep {
+    pub frame: usize,
+    pub duration: f32,
+}
+
+/// Cómo se recorre una textura animada: el orden de los cuadros (pueden
+/// repetirse), lo que dura cada uno y si se funde con el siguiente
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationTimeline {
+    pub steps: Vec<AnimationStep>,
+    pub interpolate: bool,
+}
+
+/// Cuadro que toca mostrar y, al interpolar, el siguiente con su peso (0-1)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimationFrame {
+    pub frame: usize,
+    pub next: Option<(usize, f32)>,
+}
+
+#[derive(Deserialize)]
+struct Mcmeta {
+    animation: McmetaAnimation,
+}
+
+#[derive(Deserialize)]
+struct McmetaAnimation {
+    #[serde(default = "default_frametime")]
+    frametime: u32,
+    #[serde(default)]
+    interpolate: bool,
+    frames: Option<Vec<McmetaFrame>>,
+}
+
+/// Un cuadro de `frames`: solo el índice, o el índice con su propio tiempo
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum McmetaFrame {
+    Index(usize),
+    Timed { index: usize, time: Option<u32> },
+}
+
+fn default_frametime() -> u32 {
+    1
+}
+
+impl AnimationTimeline {
+    /// Los `frame_count` cuadros en orden, cada uno `frame_duration` segundos
+    pub fn uniform(frame_count: usize, frame_duration: f32) -> Self {
+        let steps = (0..frame_count.max(1)).map(|frame| AnimationStep { frame, duration: frame_duration }).collect();
+        AnimationTimeline { steps, interpolate: false }
+    }
+
+    /// Lee un `.mcmeta` de Minecraft para una animación de `frame_count` cuadros:
+    /// `{"animation": {"frametime": 2, "interpolate": true, "frames": [0, 1, {"index": 2, "time": 6}]}}`.
+    /// Los tiempos van en ticks (1/20 s); sin `frames` se muestran todos en orden.
+    pub fn from_mcmeta(json: &str, frame_count: usize) -> Result<Self, String> {
+        let meta: Mcmeta = serde_json::from_str(json).map_err(|e| format!("mcmeta inválido: {}", e))?;
+        let animation = meta.animation;
+        if animation.frametime == 0 {
+            return Err("mcmeta inválido: frametime debe ser mayor que 0".to_string());
+        }
+        let frametime = animation.frametime;
+        // (cuadro, ticks) de cada paso
+        let frames: Vec<(usize, u32)> = match animation.frames {
+            None => (0..frame_count).map(|frame| (frame, frametime)).collect(),
+            Some(frames) => frames
+                .into_iter()
+                .map(|frame| match frame {
+                    McmetaFrame::Index(index) => (index, frametime),
+                    McmetaFrame::Timed { index, time } => (index, time.unwrap_or(frametime)),
+                })
+                .collect(),
+        };
+        let steps = frames
+            .into_iter()
+            .map(|(frame, ticks)| {
+                if frame >= frame_count {
+                    return Err(format!("mcmeta inválido: el cuadro {} no existe (hay {})", frame, frame_count));
+                }
+                if ticks == 0 {
+                    return Err(format!("mcmeta inválido: el cuadro {} dura 0 ticks", frame));
+                }
+                Ok(AnimationStep { frame, duration: ticks as f32 * TICK_SECONDS })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if steps.is_empty() {
+            return Err("mcmeta inválido: la animación no tiene cuadros".to_string());
+        }
+        Ok(AnimationTimeline { steps, interpolate: animation.interpolate })
+    }
+
+    /// Segundos de una vuelta completa
+    pub fn cycle(&self) -> f32 {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+
+    /// Cuadro que toca en `time` (en bucle). Al interpolar, el peso del
+    /// siguiente avanza en `BLEND_STEPS` saltos a lo largo del paso.
+    pub fn frame_at(&self, time: f32) -> AnimationFrame {
+        let cycle = self.cycle();
+        let mut elapsed = if cycle > 0.0 { time.rem_euclid(cycle) } else { 0.0 };
+        for (index, step) in self.steps.iter().enumerate() {
+            if elapsed < step.duration || index + 1 == self.steps.len() {
+                let next = self.interpolate.then(|| {
+                    let blend = ((elapsed / step.duration).clamp(0.0, 1.0) * BLEND_STEPS).floor() / BLEND_STEPS;
+                    (self.steps[(index + 1) % self.steps.len()].frame, blend)
+                });
+                return AnimationFrame { frame: step.frame, next: next.filter(|&(_, blend)| blend > 0.0) };
+            }
+            elapsed -= step.duration;
+        }
+        AnimationFrame { frame: 0, next: None }
+    }
+}
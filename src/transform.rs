@@ -0,0 +1,120 @@
+use raylib::prelude::Vector3;
+
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+
+/// Escala (por eje), rotación y traslación de un objeto, aplicadas en ese
+/// orden para llevar un punto de su espacio local al mundo
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    /// Hacia dónde apuntan en el mundo los ejes X, Y y Z locales (ortonormales)
+    axes: [Vector3; 3],
+    scale: Vector3,
+    translation: Vector3,
+}
+
+/// Gira `v` un ángulo `angle` (radianes) alrededor del eje unitario `axis` (Rodrigues)
+fn rotate(v: Vector3, axis: Vector3, angle: f32) -> Vector3 {
+    let (sin, cos) = angle.sin_cos();
+    v * cos + axis.cross(v) * sin + axis * (axis.dot(v) * (1.0 - cos))
+}
+
+impl Transform {
+    /// Transformación que solo mueve el objeto a `translation`
+    pub fn new(translation: Vector3) -> Self {
+        Transform {
+            axes: [Vector3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0)],
+            scale: Vector3::one(),
+            translation,
+        }
+    }
+
+    /// Agrega un giro de `angle` radianes alrededor de `axis` (en el mundo),
+    /// después de los giros anteriores
+    pub fn with_rotation(mut self, axis: Vector3, angle: f32) -> Self {
+        let axis = axis.normalized();
+        self.axes = self.axes.map(|local_axis| rotate(local_axis, axis, angle));
+        self
+    }
+
+    pub fn with_scale(mut self, scale: Vector3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Punto del mundo en el espacio local
+    #[inline]
+    fn point_to_local(&self, point: &Vector3) -> Vector3 {
+        self.vector_to_local(&(*point - self.translation))
+    }
+
+    /// Dirección del mundo en el espacio local. No se normaliza: así la
+    /// distancia a lo largo del rayo local es la misma que en el mundo.
+    #[inline]
+    fn vector_to_local(&self, v: &Vector3) -> Vector3 {
+        Vector3::new(
+            v.dot(self.axes[0]) / self.scale.x,
+            v.dot(self.axes[1]) / self.scale.y,
+            v.dot(self.axes[2]) / self.scale.z,
+        )
+    }
+
+    #[inline]
+    fn point_to_world(&self, p: &Vector3) -> Vector3 {
+        self.translation + self.axes[0] * (p.x * self.scale.x) + self.axes[1] * (p.y * self.scale.y) + self.axes[2] * (p.z * self.scale.z)
+    }
+
+    /// Normal local en el mundo (inversa traspuesta de la escala y la rotación)
+    #[inline]
+    fn normal_to_world(&self, n: &Vector3) -> Vector3 {
+        (self.axes[0] * (n.x / self.scale.x) + self.axes[1] * (n.y / self.scale.y) + self.axes[2] * (n.z / self.scale.z)).normalized()
+    }
+
+    /// Caja alineada a los ejes del mundo que contiene a `bounds` transformada
+    fn bounds_to_world(&self, bounds: &AABB) -> AABB {
+        let corners: Vec<Vector3> = (0..8)
+            .map(|corner| {
+                let x = if corner & 1 == 0 { bounds.min.x } else { bounds.max.x };
+                let y = if corner & 2 == 0 { bounds.min.y } else { bounds.max.y };
+                let z = if corner & 4 == 0 { bounds.min.z } else { bounds.max.z };
+                self.point_to_world(&Vector3::new(x, y, z))
+            })
+            .collect();
+        AABB::from_points(&corners)
+    }
+}
+
+/// Objeto colocado con una `Transform`: los rayos se llevan a su espacio
+/// local, se intersectan allí y el impacto vuelve al mundo. Sirve para rotar
+/// cualquier primitiva (bloques, cilindros, modelos) en ángulos arbitrarios.
+pub struct Transformed<T> {
+    object: T,
+    transform: Transform,
+    bounds: AABB,
+}
+
+impl<T: RayIntersect> Transformed<T> {
+    pub fn new(object: T, transform: Transform) -> Self {
+        let bounds = transform.bounds_to_world(&object.get_bounds());
+        Transformed { object, transform, bounds }
+    }
+}
+
+impl<T: RayIntersect> RayIntersect for Transformed<T> {
+    #[inline]
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let local_origin = self.transform.point_to_local(ray_origin);
+        let local_direction = self.transform.vector_to_local(ray_direction);
+        let mut intersect = self.object.ray_intersect(&local_origin, &local_direction);
+        if !intersect.is_intersecting {
+            return intersect;
+        }
+
+        intersect.point = *ray_origin + *ray_direction * intersect.distance;
+        intersect.normal = self.transform.normal_to_world(&intersect.normal);
+        intersect
+    }
+
+    fn get_bounds(&self) -> AABB {
+        self.bounds
+    }
+}
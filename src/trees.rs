@@ -0,0 +1,132 @@
+use serde::Deserialize;
+
+use crate::particles::hash01;
+
+/// Especie de árbol que arma `SceneBuilder::add_tree_species`
+#[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeSpecies {
+    /// Tronco corto y copa redonda
+    Oak,
+    /// Tronco blanco, alto y delgado, con una copa angosta
+    Birch,
+    /// Abeto: copa en cono de capas que se angostan hacia la punta
+    Spruce,
+    /// Tronco muy alto con ramas y una copa ancha y chata
+    Jungle,
+    /// Tronco seco con ramas peladas, sin hojas
+    Dead,
+}
+
+impl TreeSpecies {
+    /// Materiales del tronco y de las hojas
+    pub fn materials(&self) -> (&'static str, &'static str) {
+        match self {
+            TreeSpecies::Birch => ("birch_wood", "birch_leaves"),
+            TreeSpecies::Spruce => ("wood", "spruce_leaves"),
+            _ => ("wood", "leaves"),
+        }
+    }
+}
+
+/// Bloques de un árbol, en celdas del mundo
+pub struct TreePlan {
+    pub trunk: Vec<(i32, i32, i32)>,
+    pub leaves: Vec<(i32, i32, i32)>,
+}
+
+impl TreePlan {
+    /// Árbol de la especie con el tronco empezando en `base`; la altura, la
+    /// copa y las ramas varían con la posición y la semilla de la escena
+    pub fn new(species: TreeSpecies, base: (i32, i32, i32), seed: u32) -> Self {
+        let (x, y, z) = base;
+        let salt = seed.wrapping_mul(64);
+        let roll = |channel: u32| hash01(x, y, z, salt.wrapping_add(channel));
+        let mut plan = TreePlan { trunk: Vec::new(), leaves: Vec::new() };
+
+        match species {
+            TreeSpecies::Oak => {
+                let height = 4 + (roll(1) * 3.0) as i32;
+                plan.column(base, height);
+                plan.canopy((x as f32, (y + height) as f32 - 0.5, z as f32), 2.0 + roll(2) * 0.8, 1.8, salt);
+            }
+            TreeSpecies::Birch => {
+                let height = 5 + (roll(1) * 3.0) as i32;
+                plan.column(base, height);
+                plan.canopy((x as f32, (y + height) as f32 - 1.0, z as f32), 1.5 + roll(2) * 0.4, 2.3, salt);
+            }
+            TreeSpecies::Spruce => {
+                let height = 6 + (roll(1) * 4.0) as i32;
+                let widest = 2.6 + roll(2) * 0.8;
+                plan.column(base, height);
+                // Capas desde el tercer bloque del tronco, alternando anchas y angostas
+                for dy in 2..=height {
+                    let taper = 1.0 - (dy - 2) as f32 / (height - 1) as f32;
+                    let radius = 0.8 + (widest - 0.8) * taper - if (height - dy) % 2 == 0 { 0.6 } else { 0.0 };
+                    plan.disc((x, y + dy, z), radius.max(1.0));
+                }
+                plan.leaves.push((x, y + height, z));
+            }
+            TreeSpecies::Jungle => {
+                let height = 9 + (roll(1) * 5.0) as i32;
+                plan.column(base, height);
+                // Dos ramas a media altura hacia lados opuestos, cada una con su copa
+                let (dx, dz) = if roll(3) < 0.5 { (1, 0) } else { (0, 1) };
+                for (side, level) in [(1, height / 2 + 1), (-1, height * 2 / 3)] {
+                    let tip = (x + 2 * side * dx, y + level + 1, z + 2 * side * dz);
+                    plan.trunk.extend([(x + side * dx, y + level, z + side * dz), tip]);
+                    plan.canopy((tip.0 as f32, tip.1 as f32 + 1.0, tip.2 as f32), 1.6, 1.0, salt);
+                }
+                plan.canopy((x as f32, (y + height) as f32, z as f32), 3.0 + roll(2), 1.4, salt);
+            }
+            TreeSpecies::Dead => {
+                let height = 3 + (roll(1) * 3.0) as i32;
+                plan.column(base, height);
+                // Una a tres ramas que suben en diagonal desde cerca de la punta
+                let branches = 1 + (roll(2) * 3.0) as usize;
+                let first = (roll(3) * 4.0) as usize;
+                let directions = [(1, 0), (0, 1), (-1, 0), (0, -1)].into_iter().cycle().skip(first).take(branches);
+                for (i, (dx, dz)) in directions.enumerate() {
+                    let start = y + height - 1 - (roll(4 + i as u32) * 2.0) as i32;
+                    plan.trunk.extend((1..=2).map(|step| (x + dx * step, start + step, z + dz * step)));
+                }
+            }
+        }
+        plan
+    }
+
+    fn column(&mut self, (x, y, z): (i32, i32, i32), height: i32) {
+        self.trunk.extend((0..height).map(|dy| (x, y + dy, z)));
+    }
+
+    /// Copa en elipsoide alrededor de `center`, con el borde mordido al azar
+    fn canopy(&mut self, center: (f32, f32, f32), radius: f32, vertical: f32, salt: u32) {
+        let (cx, cy, cz) = center;
+        let reach = radius.ceil() as i32;
+        let rise = vertical.ceil() as i32;
+        for ly in cy.round() as i32 - rise..=cy.round() as i32 + rise {
+            for lx in cx.round() as i32 - reach..=cx.round() as i32 + reach {
+                for lz in cz.round() as i32 - reach..=cz.round() as i32 + reach {
+                    let (dx, dy, dz) = ((lx as f32 - cx) / radius, (ly as f32 - cy) / vertical, (lz as f32 - cz) / radius);
+                    let distance = dx * dx + dy * dy + dz * dz;
+                    let bitten = distance > 0.6 && hash01(lx, ly, lz, salt.wrapping_add(8)) < 0.35;
+                    if distance <= 1.0 && !bitten && !self.trunk.contains(&(lx, ly, lz)) {
+                        self.leaves.push((lx, ly, lz));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Capa plana y redonda de hojas alrededor del tronco
+    fn disc(&mut self, (x, y, z): (i32, i32, i32), radius: f32) {
+        let reach = radius.ceil() as i32;
+        for dx in -reach..=reach {
+            for dz in -reach..=reach {
+                if ((dx * dx + dz * dz) as f32) <= radius * radius && (dx, dz) != (0, 0) {
+                    self.leaves.push((x + dx, y, z + dz));
+                }
+            }
+        }
+    }
+}
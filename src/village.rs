@@ -0,0 +1,182 @@
+use serde::Deserialize;
+use std::collections::HashSet;
+
+use crate::particles::hash01;
+
+/// Lado de las casas, en bloques
+pub const HOUSE_SIZE: i32 = 4;
+/// Distancia entre las calles paralelas del trazado en cuadrícula
+const GRID_SPACING: i32 = 12;
+/// Árboles por columna libre con `decoration` = 1
+const TREE_DENSITY: f32 = 0.05;
+/// Cada cuántas columnas del borde de una calle puede ir un farol
+const LAMP_SPACING: i32 = 9;
+/// Intentos por casa al buscarles lugar
+const LOT_ATTEMPTS: u32 = 40;
+
+/// Trazado de las calles de la aldea
+#[derive(Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RoadLayout {
+    /// Dos calles que se cruzan en la plaza, con un arco en cada entrada
+    Cross,
+    /// Una calle en anillo alrededor de la plaza, unida a ella por cuatro calles cortas
+    Ring,
+    /// Calles paralelas en las dos direcciones, cada `GRID_SPACING` bloques
+    Grid,
+}
+
+/// Cómo es la aldea que genera `SceneBuilder::add_village`
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VillageStyle {
+    /// Casas que se intentan colocar; las que no entran se omiten
+    pub houses: u32,
+    pub roads: RoadLayout,
+    /// Ancho de las calles, en bloques
+    pub road_width: i32,
+    /// Densidad (0-1) de árboles y faroles
+    pub decoration: f32,
+    /// Atalayas en las cuatro esquinas
+    pub watchtowers: bool,
+}
+
+impl VillageStyle {
+    /// Ocho casas a lo largo de dos calles que se cruzan, con atalayas
+    pub fn new() -> Self {
+        VillageStyle {
+            houses: 8,
+            roads: RoadLayout::Cross,
+            road_width: 5,
+            decoration: 0.5,
+            watchtowers: true,
+        }
+    }
+}
+
+impl Default for VillageStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dónde va cada cosa de una aldea, en columnas (x, z) relativas a su
+/// centro; las alturas las pone el terreno sobre el que se construye
+pub struct VillagePlan {
+    pub roads: HashSet<(i32, i32)>,
+    /// Esquina mínima de cada casa
+    pub houses: Vec<(i32, i32)>,
+    pub trees: Vec<(i32, i32)>,
+    pub lamps: Vec<(i32, i32)>,
+    /// Pies de los arcos sobre las calles
+    pub arches: Vec<((i32, i32), (i32, i32))>,
+    pub towers: Vec<(i32, i32)>,
+}
+
+impl VillagePlan {
+    /// Reparte una aldea de `size` bloques desde el centro hasta cada borde
+    pub fn new(size: i32, seed: u32, style: &VillageStyle) -> Self {
+        let half = style.road_width.max(1) / 2;
+        let reach = (size - 5).max(half + 1);
+        let plaza = reach / 2 + 2;
+        let seed = seed as i32;
+
+        let roads: HashSet<(i32, i32)> = (-size..=size)
+            .flat_map(|x| (-size..=size).map(move |z| (x, z)))
+            .filter(|&(x, z)| is_road(style.roads, x, z, half, reach))
+            .collect();
+
+        let arches = if style.roads == RoadLayout::Cross {
+            let (side, entry) = (half + 1, plaza);
+            vec![
+                ((-side, -entry), (side, -entry)),
+                ((-side, entry), (side, entry)),
+                ((-entry, -side), (-entry, side)),
+                ((entry, -side), (entry, side)),
+            ]
+        } else {
+            Vec::new()
+        };
+        let arch_feet: HashSet<(i32, i32)> = arches.iter().flat_map(|&(start, end)| [start, end]).collect();
+
+        let towers = if style.watchtowers {
+            vec![(-reach, -reach), (-reach, reach), (reach, reach), (reach, -reach)]
+        } else {
+            Vec::new()
+        };
+
+        // Casas sobre las calles: lotes libres con una calle a dos bloques o menos
+        let fountain = |x: i32, z: i32| x.abs() <= 2 && z.abs() <= 2;
+        let near = |cells: &HashSet<(i32, i32)>, (x, z): (i32, i32), margin: i32| {
+            (x - margin..x + HOUSE_SIZE + margin).any(|lx| (z - margin..z + HOUSE_SIZE + margin).any(|lz| cells.contains(&(lx, lz))))
+        };
+        let mut houses: Vec<(i32, i32)> = Vec::new();
+        let mut taken: HashSet<(i32, i32)> = arch_feet.clone();
+        let span = 2 * (reach - 2) - HOUSE_SIZE + 1;
+        for attempt in 0..style.houses * LOT_ATTEMPTS {
+            if houses.len() as u32 >= style.houses || span <= 0 {
+                break;
+            }
+            let lot = (
+                -(reach - 2) + (hash01(attempt as i32, seed, 0, 11) * span as f32) as i32,
+                -(reach - 2) + (hash01(attempt as i32, seed, 0, 12) * span as f32) as i32,
+            );
+            let footprint = (lot.0 - 1..lot.0 + HOUSE_SIZE + 1).flat_map(|x| (lot.1 - 1..lot.1 + HOUSE_SIZE + 1).map(move |z| (x, z)));
+            let free = footprint.clone().all(|(x, z)| !roads.contains(&(x, z)) && !fountain(x, z) && !taken.contains(&(x, z)));
+            if free && near(&roads, lot, 2) {
+                taken.extend(footprint);
+                houses.push(lot);
+            }
+        }
+
+        // Faroles en el borde de las calles, a intervalos y según la decoración
+        let mut lamps = Vec::new();
+        for x in -reach..=reach {
+            for z in -reach..=reach {
+                let beside_road = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dz)| roads.contains(&(x + dx, z + dz)));
+                if roads.contains(&(x, z)) || taken.contains(&(x, z)) || !beside_road {
+                    continue;
+                }
+                if (x * 3 + z * 5).rem_euclid(LAMP_SPACING) == 0 && hash01(x, seed, z, 13) < style.decoration {
+                    lamps.push((x, z));
+                    taken.insert((x, z));
+                }
+            }
+        }
+
+        // Árboles separados entre sí, lejos de las calles, las casas y las atalayas
+        let mut trees: Vec<(i32, i32)> = Vec::new();
+        for x in -size..=size {
+            for z in -size..=size {
+                let crowded = (x - 2..=x + 2).any(|nx| (z - 2..=z + 2).any(|nz| roads.contains(&(nx, nz)) || taken.contains(&(nx, nz))))
+                    || towers.iter().any(|&(tx, tz)| (tx - x).abs() <= 2 && (tz - z).abs() <= 2)
+                    || trees.iter().any(|&(tx, tz)| (tx - x).abs() <= 2 && (tz - z).abs() <= 2);
+                if !crowded && hash01(x, seed, z, 14) < style.decoration * TREE_DENSITY {
+                    trees.push((x, z));
+                }
+            }
+        }
+
+        VillagePlan { roads, houses, trees, lamps, arches, towers }
+    }
+}
+
+/// Si la columna (x, z) es parte de una calle del trazado
+fn is_road(layout: RoadLayout, x: i32, z: i32, half: i32, reach: i32) -> bool {
+    let cross = (x.abs() <= half && z.abs() < reach) || (z.abs() <= half && x.abs() < reach);
+    match layout {
+        RoadLayout::Cross => cross,
+        RoadLayout::Ring => {
+            let ring = reach / 2 + 2;
+            let distance = x.abs().max(z.abs());
+            (distance - ring).abs() <= half || (cross && distance <= ring)
+        }
+        RoadLayout::Grid => {
+            let on_line = |c: i32| {
+                let offset = c.rem_euclid(GRID_SPACING);
+                offset <= half || offset >= GRID_SPACING - half
+            };
+            x.abs() < reach && z.abs() < reach && (on_line(x) || on_line(z))
+        }
+    }
+}
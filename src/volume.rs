@@ -0,0 +1,130 @@
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect, AABB};
+
+/// Pasos de ray marching a lo largo de la cuerda del rayo dentro del volumen
+const MARCH_STEPS: u32 = 16;
+/// Transparencia mínima: incluso el centro de la caída deja pasar algo de luz
+const MIN_TRANSPARENCY: f32 = 0.05;
+
+/// Tiempo (en segundos) que anima el flujo de todas las caídas; se fija una
+/// vez por frame porque los objetos de la escena no reciben el tiempo
+static FLOW_TIME: AtomicU32 = AtomicU32::new(0);
+
+pub fn set_flow_time(time: f32) {
+    FLOW_TIME.store(time.to_bits(), Ordering::Relaxed);
+}
+
+fn flow_time() -> f32 {
+    f32::from_bits(FLOW_TIME.load(Ordering::Relaxed))
+}
+
+/// Ruido suave en [0, 1] para las vetas del flujo
+#[inline]
+fn flow_noise(x: f32, y: f32, z: f32) -> f32 {
+    let n = (x * 1.7 + y * 0.9).sin() * (z * 2.3 - y * 0.4).cos()
+        + (y * 2.1 + x * 0.6).sin() * 0.5
+        + (z * 1.3 + y * 1.7).cos() * 0.25;
+    (n / 1.75) * 0.5 + 0.5
+}
+
+/// Columna de agua o lava que cae, representada como un medio que dispersa
+/// la luz en lugar de una pila de cubos. La densidad baja hacia los bordes y
+/// tiene vetas animadas que bajan con el tiempo.
+///
+/// Cada impacto devuelve una copia del material cuya transparencia es la
+/// transmitancia del rayo a través del volumen, así que el sombreado normal
+/// (refracción con índice 1.0) compone lo que hay detrás.
+pub struct FallVolume {
+    center_x: f32,
+    center_z: f32,
+    half_width: f32,
+    min: Vector3,
+    max: Vector3,
+    material: Arc<Material>,
+    /// Densidad en el eje de la columna (coeficiente de extinción por bloque)
+    density: f32,
+    /// Velocidad de las vetas en bloques por segundo (positiva = hacia abajo)
+    flow_speed: f32,
+}
+
+impl FallVolume {
+    /// Columna centrada en (x, z) que ocupa los bloques de `bottom_y` a `top_y`
+    pub fn new(x: f32, z: f32, bottom_y: f32, top_y: f32, material: Arc<Material>, density: f32, flow_speed: f32) -> Self {
+        let half_width = 0.5;
+        FallVolume {
+            center_x: x,
+            center_z: z,
+            half_width,
+            min: Vector3::new(x - half_width, bottom_y - 0.5, z - half_width),
+            max: Vector3::new(x + half_width, top_y + 0.5, z + half_width),
+            material,
+            density,
+            flow_speed,
+        }
+    }
+
+    #[inline]
+    fn density_at(&self, p: Vector3, time: f32) -> f32 {
+        let radial = ((p.x - self.center_x).abs().max((p.z - self.center_z).abs()) / self.half_width).min(1.0);
+        let edge = 1.0 - radial * radial;
+        let flow = flow_noise(p.x * 3.0, (p.y + time * self.flow_speed) * 1.5, p.z * 3.0);
+        self.density * edge * (0.4 + 0.6 * flow)
+    }
+}
+
+impl RayIntersect for FallVolume {
+    fn ray_intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let inv_dir = Vector3::new(1.0 / ray_direction.x, 1.0 / ray_direction.y, 1.0 / ray_direction.z);
+
+        let tx = ((self.min.x - ray_origin.x) * inv_dir.x, (self.max.x - ray_origin.x) * inv_dir.x);
+        let ty = ((self.min.y - ray_origin.y) * inv_dir.y, (self.max.y - ray_origin.y) * inv_dir.y);
+        let tz = ((self.min.z - ray_origin.z) * inv_dir.z, (self.max.z - ray_origin.z) * inv_dir.z);
+        let (near_x, near_y, near_z) = (tx.0.min(tx.1), ty.0.min(ty.1), tz.0.min(tz.1));
+
+        let tmin = near_x.max(near_y).max(near_z);
+        let tmax = tx.0.max(tx.1).min(ty.0.max(ty.1)).min(tz.0.max(tz.1));
+
+        // Los rayos que empiezan dentro ya cruzaron el volumen al entrar
+        if tmin <= 1e-4 || tmin > tmax {
+            return Intersect::empty();
+        }
+
+        let time = flow_time();
+        let step = (tmax - tmin) / MARCH_STEPS as f32;
+        let optical_depth: f32 = (0..MARCH_STEPS)
+            .map(|i| {
+                let t = tmin + (i as f32 + 0.5) * step;
+                self.density_at(*ray_origin + *ray_direction * t, time) * step
+            })
+            .sum();
+        let transparency = (-optical_depth).exp().max(MIN_TRANSPARENCY);
+
+        let point = *ray_origin + *ray_direction * tmin;
+        let normal = if tmin == near_x {
+            Vector3::new(-ray_direction.x.signum(), 0.0, 0.0)
+        } else if tmin == near_y {
+            Vector3::new(0.0, -ray_direction.y.signum(), 0.0)
+        } else {
+            Vector3::new(0.0, 0.0, -ray_direction.z.signum())
+        };
+
+        // La textura se desliza con el flujo
+        let u = if normal.x.abs() > 0.5 { point.z } else { point.x };
+        let v = point.y * 0.5 + time * self.flow_speed * 0.5;
+
+        let mut material = (*self.material).clone();
+        material.reflectivity *= 1.0 - transparency;
+        material.transparency = transparency;
+        material.refraction_index = 1.0;
+
+        Intersect::new(point, normal, tmin, Arc::new(material), u.rem_euclid(1.0), v.rem_euclid(1.0))
+    }
+
+    fn get_bounds(&self) -> AABB {
+        AABB::new(self.min, self.max)
+    }
+}
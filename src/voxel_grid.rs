@@ -0,0 +1,221 @@
+use raylib::prelude::Vector3;
+use std::sync::Arc;
+
+use crate::ray_intersect::{Intersect, RayIntersect, BVH};
+
+/// Celdas máximas de la grilla (4 bytes cada una); escenas más grandes usan solo el BVH
+const MAX_CELLS: usize = 16 * 1024 * 1024;
+/// Tolerancia para aceptar que un objeto cabe en una celda
+const CELL_TOLERANCE: f32 = 1e-3;
+const EMPTY_CELL: u32 = u32::MAX;
+
+/// Grilla uniforme de celdas de un bloque, centradas en coordenadas enteras,
+/// recorrida con DDA (Amanatides & Woo). Cada objeto que cabe en una sola celda
+/// (los bloques de la escena) se guarda en ella; el resto (caídas, mallas,
+/// objetos grandes o que comparten celda) va a un BVH aparte.
+pub struct VoxelGrid {
+    /// Coordenada entera de la celda (0, 0, 0)
+    origin: [i32; 3],
+    dims: [usize; 3],
+    cells: Vec<u32>,
+    voxels: Vec<Arc<dyn RayIntersect + Send + Sync>>,
+    others: Vec<Arc<dyn RayIntersect + Send + Sync>>,
+    others_bvh: BVH,
+}
+
+impl VoxelGrid {
+    /// Devuelve `None` si no hay bloques o la grilla ocuparía demasiada memoria
+    pub fn build(objects: &[Arc<dyn RayIntersect + Send + Sync>]) -> Option<Self> {
+        let cell_of = |object: &Arc<dyn RayIntersect + Send + Sync>| {
+            let bounds = object.get_bounds();
+            let center = bounds.center();
+            let cell = [center.x.round(), center.y.round(), center.z.round()];
+            let fits = (0..3).all(|axis| {
+                let (min, max) = match axis {
+                    0 => (bounds.min.x, bounds.max.x),
+                    1 => (bounds.min.y, bounds.max.y),
+                    _ => (bounds.min.z, bounds.max.z),
+                };
+                min >= cell[axis] - 0.5 - CELL_TOLERANCE && max <= cell[axis] + 0.5 + CELL_TOLERANCE
+            });
+            fits.then(|| cell.map(|c| c as i32))
+        };
+
+        let cells_of: Vec<Option<[i32; 3]>> = objects.iter().map(cell_of).collect();
+        let mut min = [i32::MAX; 3];
+        let mut max = [i32::MIN; 3];
+        for cell in cells_of.iter().flatten() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(cell[axis]);
+                max[axis] = max[axis].max(cell[axis]);
+            }
+        }
+        if min[0] > max[0] {
+            return None;
+        }
+
+        let dims = [0, 1, 2].map(|axis| (max[axis] - min[axis] + 1) as usize);
+        if dims[0] * dims[1] * dims[2] > MAX_CELLS {
+            return None;
+        }
+
+        let cell_index = |cell: [i32; 3]| {
+            let local = [0, 1, 2].map(|axis| (cell[axis] - min[axis]) as usize);
+            (local[2] * dims[1] + local[1]) * dims[0] + local[0]
+        };
+        let mut cells = vec![EMPTY_CELL; dims[0] * dims[1] * dims[2]];
+        let mut voxels = Vec::new();
+        let mut others = Vec::new();
+        for (object, cell) in objects.iter().zip(cells_of) {
+            match cell.map(cell_index) {
+                Some(index) if cells[index] == EMPTY_CELL => {
+                    cells[index] = voxels.len() as u32;
+                    voxels.push(object.clone());
+                }
+                _ => others.push(object.clone()),
+            }
+        }
+
+        let others_bvh = BVH::build(&others);
+        Some(VoxelGrid {
+            origin: min,
+            dims,
+            cells,
+            voxels,
+            others,
+            others_bvh,
+        })
+    }
+
+    /// Bloques guardados en celdas y objetos que quedaron en el BVH auxiliar
+    pub fn counts(&self) -> (usize, usize) {
+        (self.voxels.len(), self.others.len())
+    }
+
+    #[inline]
+    fn cell_index(&self, cell: [usize; 3]) -> usize {
+        (cell[2] * self.dims[1] + cell[1]) * self.dims[0] + cell[0]
+    }
+
+    /// Recorre las celdas que cruza el rayo, en orden, hasta `max_distance`.
+    /// `visit` recibe el objeto de cada celda ocupada y devuelve la distancia
+    /// del impacto, si lo hubo; el recorrido termina con el primer impacto.
+    fn traverse(
+        &self,
+        ray_origin: &Vector3,
+        ray_direction: &Vector3,
+        max_distance: f32,
+        mut visit: impl FnMut(&Arc<dyn RayIntersect + Send + Sync>) -> Option<f32>,
+    ) {
+        let origin = [ray_origin.x, ray_origin.y, ray_origin.z];
+        let direction = [ray_direction.x, ray_direction.y, ray_direction.z];
+
+        // Recorte contra la caja de la grilla
+        let mut t_enter = 0.0f32;
+        let mut t_exit = max_distance;
+        for axis in 0..3 {
+            let low = self.origin[axis] as f32 - 0.5;
+            let high = low + self.dims[axis] as f32;
+            if direction[axis].abs() < 1e-12 {
+                if origin[axis] < low || origin[axis] > high {
+                    return;
+                }
+                continue;
+            }
+            let inv = 1.0 / direction[axis];
+            let (t0, t1) = ((low - origin[axis]) * inv, (high - origin[axis]) * inv);
+            t_enter = t_enter.max(t0.min(t1));
+            t_exit = t_exit.min(t0.max(t1));
+        }
+        if t_enter > t_exit {
+            return;
+        }
+
+        let mut cell = [0i64; 3];
+        let mut step = [0i64; 3];
+        let mut t_max = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+        for axis in 0..3 {
+            let position = origin[axis] + direction[axis] * t_enter;
+            let local = (position - self.origin[axis] as f32 + 0.5).floor() as i64;
+            cell[axis] = local.clamp(0, self.dims[axis] as i64 - 1);
+
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                let boundary = (self.origin[axis] as i64 + cell[axis]) as f32 + 0.5;
+                t_max[axis] = (boundary - origin[axis]) / direction[axis];
+                t_delta[axis] = 1.0 / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                let boundary = (self.origin[axis] as i64 + cell[axis]) as f32 - 0.5;
+                t_max[axis] = (boundary - origin[axis]) / direction[axis];
+                t_delta[axis] = -1.0 / direction[axis];
+            }
+        }
+
+        let mut limit = t_exit;
+        let mut t_cell = t_enter;
+        while t_cell <= limit {
+            let index = self.cell_index([cell[0] as usize, cell[1] as usize, cell[2] as usize]);
+            let slot = self.cells[index];
+            if slot != EMPTY_CELL
+                && let Some(distance) = visit(&self.voxels[slot as usize])
+            {
+                // El objeto está dentro de la celda: lo que esté en celdas siguientes queda detrás
+                limit = limit.min(distance);
+                if distance <= t_max[0].min(t_max[1]).min(t_max[2]) {
+                    return;
+                }
+            }
+
+            let axis = if t_max[0] < t_max[1] {
+                if t_max[0] < t_max[2] { 0 } else { 2 }
+            } else if t_max[1] < t_max[2] {
+                1
+            } else {
+                2
+            };
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= self.dims[axis] as i64 {
+                return;
+            }
+            t_cell = t_max[axis];
+            t_max[axis] += t_delta[axis];
+        }
+    }
+
+    /// Objeto más cercano que toca el rayo (bloques de la grilla y el resto)
+    pub fn intersect(&self, ray_origin: &Vector3, ray_direction: &Vector3) -> Intersect {
+        let mut best = self.others_bvh.intersect(ray_origin, ray_direction, &self.others);
+        let max_distance = if best.is_intersecting { best.distance } else { f32::INFINITY };
+
+        self.traverse(ray_origin, ray_direction, max_distance, |object| {
+            let hit = object.ray_intersect(ray_origin, ray_direction);
+            if hit.is_intersecting && (!best.is_intersecting || hit.distance < best.distance) {
+                let distance = hit.distance;
+                best = hit;
+                return Some(distance);
+            }
+            None
+        });
+        best
+    }
+
+    /// Consulta de oclusión para rayos de sombra (ver `BVH::occluded`)
+    pub fn occluded(&self, ray_origin: &Vector3, ray_direction: &Vector3, max_distance: f32) -> bool {
+        if self.others_bvh.occluded(ray_origin, ray_direction, max_distance, &self.others) {
+            return true;
+        }
+
+        let mut blocked = false;
+        self.traverse(ray_origin, ray_direction, max_distance, |object| {
+            let hit = object.ray_intersect(ray_origin, ray_direction);
+            if hit.is_intersecting && hit.distance < max_distance {
+                blocked = true;
+                return Some(0.0);
+            }
+            None
+        });
+        blocked
+    }
+}
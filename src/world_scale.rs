@@ -0,0 +1,36 @@
+/// Escala física del mundo: cuántos metros mide un bloque.
+///
+/// Los parámetros "físicos" (caída de la luz, velocidad de la cámara) se
+/// definen en metros y se convierten a bloques con esta escala, para que se
+/// comporten igual si un bloque mide 1 m o 0.25 m.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldScale {
+    pub meters_per_block: f32,
+}
+
+impl WorldScale {
+    pub fn new(meters_per_block: f32) -> Self {
+        WorldScale {
+            meters_per_block: meters_per_block.max(1e-3),
+        }
+    }
+
+    /// Convierte una distancia en bloques (unidades de la escena) a metros
+    #[inline]
+    pub fn to_meters(self, blocks: f32) -> f32 {
+        blocks * self.meters_per_block
+    }
+
+    /// Convierte una distancia en metros a bloques
+    #[inline]
+    pub fn to_blocks(self, meters: f32) -> f32 {
+        meters / self.meters_per_block
+    }
+}
+
+impl Default for WorldScale {
+    /// Un bloque = 1 metro (como en Minecraft)
+    fn default() -> Self {
+        WorldScale::new(1.0)
+    }
+}